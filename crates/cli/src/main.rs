@@ -1,13 +1,19 @@
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use plumise_agent_core as core;
 
+mod service;
+mod top;
+
 #[derive(Parser)]
 #[command(name = "plumise-agent", about = "Plumise distributed inference agent (CLI)")]
 enum Cli {
     /// Start the agent
+    ///
+    /// Exits with 10 (config), 11 (network), 12 (oracle), 13 (chain), 14 (process)
+    /// or 15 (model) when startup fails, 1 otherwise.
     Start {
         /// Path to config file (default: ~/.plumise-agent/agent-config.json)
         #[arg(short, long)]
@@ -28,20 +34,273 @@ enum Cli {
         /// Path to llama-server binary (auto-downloaded if omitted)
         #[arg(long)]
         llama_path: Option<PathBuf>,
+
+        /// Run in the background, logging to agent.log in the config directory
+        #[arg(short, long)]
+        detach: bool,
+
+        /// Start llama-server and benchmark it, then print the registration payload and
+        /// relay auth message instead of contacting the Oracle or chain
+        #[arg(long, conflicts_with = "detach")]
+        dry_run: bool,
     },
 
     /// Stop the running agent (via PID file)
     Stop,
 
+    /// Stop the running agent and start it again with the current config
+    Restart {
+        /// Path to config file (default: ~/.plumise-agent/agent-config.json)
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+
+        /// Override HTTP port
+        #[arg(long)]
+        port: Option<u16>,
+
+        /// Override model HuggingFace repo
+        #[arg(long)]
+        model: Option<String>,
+
+        /// Override GPU layers (-1 or 99 for all, 0 for CPU-only)
+        #[arg(long)]
+        gpu_layers: Option<i32>,
+
+        /// Path to llama-server binary (auto-downloaded if omitted)
+        #[arg(long)]
+        llama_path: Option<PathBuf>,
+
+        /// Run in the background, logging to agent.log in the config directory
+        #[arg(short, long)]
+        detach: bool,
+    },
+
     /// Show agent status
+    ///
+    /// Exit code: 0 running, 2 degraded, 3 stopped.
     Status {
-        /// Agent HTTP port to query (default: 18920)
-        #[arg(long, default_value = "18920")]
-        port: u16,
+        /// Agent HTTP port to query (default: the running agent's port, or 18920)
+        #[arg(long)]
+        port: Option<u16>,
+
+        /// Print machine-readable JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Live dashboard: status, tok/s, slots, relay, CPU/GPU/RAM and recent log lines
+    Top {
+        /// llama-server HTTP port (default: from the running agent)
+        #[arg(short, long)]
+        port: Option<u16>,
+    },
+
+    /// Switch the running agent's node mode without stopping it
+    Mode {
+        /// "auto" follows the config (or the Oracle) again
+        #[arg(value_parser = ["standalone", "rpc-server", "coordinator", "auto"])]
+        mode: String,
+
+        /// Coordinator only: rpc-server peers as host[:port], comma-separated
+        #[arg(long, value_delimiter = ',')]
+        peers: Vec<String>,
     },
 
     /// Interactive setup wizard
     Init,
+
+    /// Show served requests and earnings from the local ledger
+    Earnings {
+        /// Only include requests from the last N hours
+        #[arg(long)]
+        hours: Option<u64>,
+    },
+
+    /// Claim rewards and show the claim history
+    ///
+    /// Exits with 13 (chain) when the claim transaction fails or reverts, 10 (config)
+    /// without a usable key or payout address, 1 otherwise.
+    Rewards {
+        #[command(subcommand)]
+        action: RewardsCommand,
+    },
+
+    /// Install or manage the agent as a system service (systemd / launchd / Windows service)
+    Service {
+        #[command(subcommand)]
+        action: ServiceCommand,
+    },
+
+    /// Download and switch to another llama.cpp release, rolling back if it fails to start
+    UpdateLlama {
+        /// Release tag (e.g. b4722) or "latest" (default: `llamaVersion` from the config)
+        #[arg(long)]
+        version: Option<String>,
+
+        /// Release asset to install instead of auto-detecting, e.g. ubuntu-vulkan-x64
+        /// or a full file name like llama-b4722-bin-win-avx2-x64.zip
+        #[arg(long)]
+        asset: Option<String>,
+
+        /// Switch back to the previously used version
+        #[arg(long, conflicts_with_all = ["version", "asset"])]
+        rollback: bool,
+    },
+
+    /// Delete agent data (the ledger and lifetime totals are kept)
+    Clean {
+        /// Downloaded models
+        #[arg(long)]
+        models: bool,
+
+        /// Downloaded llama.cpp releases
+        #[arg(long)]
+        binaries: bool,
+
+        /// agent.log, the audit log and crash records
+        #[arg(long)]
+        logs: bool,
+
+        /// Config file and runtime state (a private key in the file is kept unless --keys)
+        #[arg(long)]
+        config: bool,
+
+        /// Private key and HF token (keyring and config file); needs --confirm-keys
+        #[arg(long)]
+        keys: bool,
+
+        /// Everything above except keys
+        #[arg(long)]
+        all: bool,
+
+        /// Confirm that the private key is backed up and may be deleted
+        #[arg(long, requires = "keys")]
+        confirm_keys: bool,
+    },
+
+    /// Check the key, Oracle, chain RPC, port, GPU, disk, model, llama-server and reachability
+    ///
+    /// Exit code: 0 all checks passed, 1 otherwise.
+    Doctor {
+        /// Print the results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Manage the node's private key
+    Key {
+        #[command(subcommand)]
+        action: KeyCommand,
+    },
+
+    /// Read or change settings in the config file
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+
+    /// Check or manage downloaded models
+    Models {
+        #[command(subcommand)]
+        action: ModelsCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum ModelsCommand {
+    /// Re-hash the configured model (and mmproj) against the recorded checksums
+    Verify {
+        /// Verify this file in the models directory instead
+        #[arg(long)]
+        file: Option<String>,
+
+        /// Delete a corrupted file and download it again
+        #[arg(long)]
+        redownload: bool,
+    },
+    /// Copy (or move) a GGUF you already have into the models directory
+    Import {
+        /// The .gguf file, e.g. on an external drive or in another tool's model folder
+        path: PathBuf,
+
+        /// File name in the models directory (default: the source's name)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// copy, move, or reference the file in place: link (hardlink, else symlink),
+        /// symlink or hardlink. Links fall back to a copy where the filesystem refuses them.
+        #[arg(long, default_value = "copy", value_parser = ["copy", "move", "link", "symlink", "hardlink"])]
+        mode: String,
+
+        /// Make it the configured model file
+        #[arg(long = "use")]
+        use_model: bool,
+    },
+    /// Models in the models directory and where they came from
+    List,
+}
+
+#[derive(Subcommand)]
+enum KeyCommand {
+    /// Switch to a new key (generated, or read from stdin with --import). The old key
+    /// is backed up to retired-keys/, and a running agent restarts under the new address.
+    Rotate {
+        /// Read the new private key from stdin instead of generating one
+        #[arg(long)]
+        import: bool,
+
+        /// Sign a migration with both keys so the Oracle links the old address to the new one
+        #[arg(long)]
+        link: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum RewardsCommand {
+    /// Send claimReward() to the RewardPool and record the result
+    Claim,
+    /// Every claim attempt and on-chain claim, newest first
+    History {
+        /// Show at most this many entries
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+
+        /// Print the records as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Print one setting (camelCase or snake_case; `cluster.role` for nested ones)
+    Get { key: String },
+    /// Change a setting; lists take JSON or comma-separated values
+    Set { key: String, value: String },
+    /// Reset a setting to its default
+    Unset { key: String },
+    /// Print every setting (secrets masked)
+    List,
+}
+
+#[derive(Subcommand)]
+enum ServiceCommand {
+    /// Register and start the service (restarts on failure and at boot)
+    Install {
+        /// Config file for the service (default: ~/.plumise-agent/agent-config.json)
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+    },
+    /// Stop and remove the service
+    Uninstall,
+    /// Show whether the service is installed and running
+    Status,
+    /// Entry point used by the Windows Service Control Manager
+    #[command(hide = true)]
+    Run {
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+    },
 }
 
 #[tokio::main]
@@ -57,10 +316,35 @@ async fn main() {
             model,
             gpu_layers,
             llama_path,
+            detach,
+            dry_run,
         } => {
-            if let Err(e) = cmd_start(config, port, model, gpu_layers, llama_path).await {
+            if dry_run {
+                if let Err(e) = cmd_dry_run(config, port, model, gpu_layers, llama_path).await {
+                    log::error!("{}", e);
+                    std::process::exit(1);
+                }
+                return;
+            }
+            if detach {
+                if let Err(e) = cmd_start_detached(config, port, model, gpu_layers, llama_path) {
+                    log::error!("{}", e);
+                    std::process::exit(1);
+                }
+                return;
+            }
+            let shutdown = match install_ctrlc_handler() {
+                Ok(s) => s,
+                Err(e) => {
+                    log::error!("{}", e);
+                    std::process::exit(1);
+                }
+            };
+            if let Err(e) = cmd_start(config, port, model, gpu_layers, llama_path, shutdown).await {
                 log::error!("{}", e);
-                std::process::exit(1);
+                core::events::publish(core::events::AgentEvent::Crashed { reason: e.to_string() });
+                core::events::flush(EVENT_FLUSH_TIMEOUT).await;
+                std::process::exit(e.code());
             }
         }
         Cli::Stop => {
@@ -69,8 +353,54 @@ async fn main() {
                 std::process::exit(1);
             }
         }
-        Cli::Status { port } => {
-            if let Err(e) = cmd_status(port).await {
+        Cli::Restart {
+            config,
+            port,
+            model,
+            gpu_layers,
+            llama_path,
+            detach,
+        } => {
+            if let Err(e) = cmd_restart(config.as_ref(), port, model.clone(), gpu_layers) {
+                log::error!("{}", e);
+                std::process::exit(1);
+            }
+            if detach {
+                if let Err(e) = cmd_start_detached(config, port, model, gpu_layers, llama_path) {
+                    log::error!("{}", e);
+                    std::process::exit(1);
+                }
+                return;
+            }
+            let shutdown = match install_ctrlc_handler() {
+                Ok(s) => s,
+                Err(e) => {
+                    log::error!("{}", e);
+                    std::process::exit(1);
+                }
+            };
+            if let Err(e) = cmd_start(config, port, model, gpu_layers, llama_path, shutdown).await {
+                log::error!("{}", e);
+                core::events::publish(core::events::AgentEvent::Crashed { reason: e.to_string() });
+                core::events::flush(EVENT_FLUSH_TIMEOUT).await;
+                std::process::exit(e.code());
+            }
+        }
+        Cli::Status { port, json } => match cmd_status(port, json).await {
+            Ok(code) => std::process::exit(code),
+            Err(e) => {
+                log::error!("{}", e);
+                std::process::exit(1);
+            }
+        },
+        Cli::Top { port } => {
+            if let Err(e) = top::run(port).await {
+                log::error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        Cli::Mode { mode, peers } => {
+            if let Err(e) = cmd_mode(mode, peers) {
                 log::error!("{}", e);
                 std::process::exit(1);
             }
@@ -81,25 +411,225 @@ async fn main() {
                 std::process::exit(1);
             }
         }
+        Cli::Earnings { hours } => {
+            if let Err(e) = cmd_earnings(hours).await {
+                log::error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        Cli::Rewards { action } => {
+            if let Err(e) = cmd_rewards(action).await {
+                log::error!("{}", e);
+                std::process::exit(e.code());
+            }
+        }
+        Cli::Service { action } => {
+            if let Err(e) = cmd_service(action) {
+                log::error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        Cli::UpdateLlama {
+            version,
+            asset,
+            rollback,
+        } => {
+            if let Err(e) = cmd_update_llama(version, asset, rollback).await {
+                log::error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        Cli::Clean {
+            models,
+            binaries,
+            logs,
+            config,
+            keys,
+            all,
+            confirm_keys,
+        } => {
+            let targets = core::cleanup::CleanupTargets {
+                models: models || all,
+                binaries: binaries || all,
+                logs: logs || all,
+                config: config || all,
+                keys,
+            };
+            if let Err(e) = cmd_clean(targets, confirm_keys) {
+                log::error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        Cli::Doctor { json } => match cmd_doctor(json).await {
+            Ok(true) => {}
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                log::error!("{}", e);
+                std::process::exit(1);
+            }
+        },
+        Cli::Key { action } => {
+            if let Err(e) = cmd_key(action).await {
+                log::error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        Cli::Config { action } => {
+            if let Err(e) = cmd_config(action) {
+                log::error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        Cli::Models { action } => {
+            if let Err(e) = cmd_models(action).await {
+                log::error!("{}", e);
+                std::process::exit(1);
+            }
+        }
     }
 }
 
-async fn cmd_start(
+/// Setup Ctrl+C handler using std::sync for signal handler compatibility
+fn install_ctrlc_handler() -> Result<Arc<std::sync::atomic::AtomicBool>, String> {
+    let shutdown = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let shutdown_flag = Arc::clone(&shutdown);
+    ctrlc::set_handler(move || {
+        shutdown_flag.store(true, std::sync::atomic::Ordering::SeqCst);
+    })
+    .map_err(|e| format!("Failed to set Ctrl+C handler: {}", e))?;
+    Ok(shutdown)
+}
+
+fn cmd_service(action: ServiceCommand) -> Result<(), String> {
+    match action {
+        ServiceCommand::Install { config } => service::install(config),
+        ServiceCommand::Uninstall => service::uninstall(),
+        ServiceCommand::Status => service::status(),
+        #[cfg(windows)]
+        ServiceCommand::Run { config } => service::run_windows_service(config),
+        #[cfg(not(windows))]
+        ServiceCommand::Run { .. } => {
+            Err("`service run` is only used by the Windows service manager; use `plumise-agent start`".into())
+        }
+    }
+}
+
+fn config_dir_for(config_path: Option<&PathBuf>) -> PathBuf {
+    config_path
+        .and_then(|p| p.parent().map(|d| d.to_path_buf()))
+        .unwrap_or_else(core::config::default_config_dir)
+}
+
+/// PID recorded in `<config_dir>/<name>`, if the file exists and parses.
+fn read_pid_file(path: &std::path::Path) -> Option<u32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Re-run `start` without `--detach` as a background process and return once it is up.
+fn cmd_start_detached(
     config_path: Option<PathBuf>,
     port_override: Option<u16>,
     model_override: Option<String>,
     gpu_layers_override: Option<i32>,
     llama_path_override: Option<PathBuf>,
 ) -> Result<(), String> {
-    let config_dir = config_path
-        .clone()
-        .and_then(|p| p.parent().map(|d| d.to_path_buf()))
-        .unwrap_or_else(|| core::config::default_config_dir());
+    let config_dir = config_dir_for(config_path.as_ref());
+    std::fs::create_dir_all(&config_dir)
+        .map_err(|e| format!("Failed to create config dir: {}", e))?;
 
-    // Load config
-    let mut config = core::config::load_config(&config_dir)?;
+    let pid_file = config_dir.join("agent.pid");
+    if let Some(pid) = read_pid_file(&pid_file) {
+        if core::system::is_pid_alive(pid) {
+            return Err(format!("Agent is already running (PID {})", pid));
+        }
+    }
 
-    // Apply overrides
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("Failed to locate plumise-agent binary: {}", e))?;
+    let mut cmd = std::process::Command::new(exe);
+    cmd.arg("start");
+    if let Some(ref path) = config_path {
+        cmd.arg("--config").arg(path);
+    }
+    if let Some(port) = port_override {
+        cmd.arg("--port").arg(port.to_string());
+    }
+    if let Some(ref model) = model_override {
+        cmd.arg("--model").arg(model);
+    }
+    if let Some(ngl) = gpu_layers_override {
+        cmd.arg("--gpu-layers").arg(ngl.to_string());
+    }
+    if let Some(ref path) = llama_path_override {
+        cmd.arg("--llama-path").arg(path);
+    }
+
+    let log_path = config_dir.join("agent.log");
+    let log_file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .map_err(|e| format!("Failed to open log file {}: {}", log_path.display(), e))?;
+    let log_file_err = log_file
+        .try_clone()
+        .map_err(|e| format!("Failed to open log file: {}", e))?;
+    cmd.stdin(std::process::Stdio::null())
+        .stdout(log_file)
+        .stderr(log_file_err);
+
+    // Detach from the terminal so closing it doesn't stop the agent
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // SAFETY: setsid is async-signal-safe and called between fork and exec.
+        unsafe {
+            cmd.pre_exec(|| {
+                libc::setsid();
+                Ok(())
+            });
+        }
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        // DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP | CREATE_NO_WINDOW
+        cmd.creation_flags(0x00000008 | 0x00000200 | 0x08000000);
+    }
+
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to start agent in background: {}", e))?;
+    let pid = child.id();
+    let _ = std::fs::write(&pid_file, pid.to_string());
+
+    // Catch immediate failures (bad config, missing key) before reporting success
+    std::thread::sleep(std::time::Duration::from_secs(2));
+    if let Ok(Some(status)) = child.try_wait() {
+        let _ = std::fs::remove_file(&pid_file);
+        return Err(format!(
+            "Agent exited immediately ({}). See {}",
+            status,
+            log_path.display()
+        ));
+    }
+
+    println!("Agent started in background (PID {}).", pid);
+    println!("Logs: {}", log_path.display());
+    println!("Stop with: plumise-agent stop");
+    Ok(())
+}
+
+/// `start --dry-run`: everything up to registration, printing what would be sent.
+/// Model and llama.cpp downloads still happen; no PID or state files are written.
+async fn cmd_dry_run(
+    config_path: Option<PathBuf>,
+    port_override: Option<u16>,
+    model_override: Option<String>,
+    gpu_layers_override: Option<i32>,
+    llama_path_override: Option<PathBuf>,
+) -> Result<(), String> {
+    let config_dir = config_dir_for(config_path.as_ref());
+    let mut config = core::config::load_config(&config_dir)?;
     if let Some(port) = port_override {
         config.http_port = port;
     }
@@ -109,64 +639,570 @@ async fn cmd_start(
     if let Some(ngl) = gpu_layers_override {
         config.gpu_layers = ngl;
     }
-
-    // Validate private key
+    config.validate()?;
+    let llama_path_override = llama_path_override.or_else(|| config.llama_path());
     if config.private_key.is_empty() {
-        return Err(
-            "Private key not configured. Run `plumise-agent init` to set up.".into(),
-        );
+        return Err("Private key not configured. Run `plumise-agent init` to set up.".into());
     }
-    if !config.private_key.starts_with("0x") || config.private_key.len() != 66 {
-        return Err("Invalid private key format. Must be 0x-prefixed hex (66 chars).".into());
+    if is_rpc_server_only(&config)? {
+        return Err("--dry-run covers llama-server nodes; this config runs rpc-server only".into());
     }
-
-    // Derive agent address
     let signing_key = core::chain::crypto::parse_private_key(&config.private_key)?;
-    let agent_address = core::chain::crypto::address_from_key(&signing_key);
-    log::info!("Agent address: {}", agent_address);
-
-    // Ensure model
-    let models_dir = config_dir.join("models");
-    log::info!("Checking model file...");
-    let model_path = core::model::download::ensure_model(
-        &models_dir,
-        &config.model,
-        &config.model_file,
-        |progress| {
-            if progress.total_bytes > 0 {
-                print!(
-                    "\rDownloading model: {:.0}% ({:.1} / {:.1} GB)",
-                    progress.percent,
-                    progress.downloaded_bytes as f64 / 1e9,
-                    progress.total_bytes as f64 / 1e9,
-                );
-                use std::io::Write;
-                let _ = std::io::stdout().flush();
-                if progress.percent >= 100.0 {
-                    println!();
-                }
-            }
-        },
-    )
-    .await?;
+    println!("Agent address: {}", core::chain::crypto::address_from_key(&signing_key));
 
-    // Ensure llama-server binary
-    let bin_dir = config_dir.join("bin");
-    let llama_server_path = if let Some(ref path) = llama_path_override {
-        if !path.exists() {
-            return Err(format!("Specified llama-server not found: {}", path.display()));
-        }
-        path.clone()
-    } else {
-        core::llama_bin::ensure_llama_server(&bin_dir).await?
+    let (model_path, mmproj_path) = ensure_model_files(&config, &config_dir.join("models")).await?;
+    let llama_server_path = match llama_path_override {
+        Some(path) if path.exists() => path,
+        Some(path) => return Err(format!("Specified llama-server not found: {}", path.display())),
+        None => core::llama_bin::ensure_llama_server(&config_dir.join("bin"), &config.llama_version, |progress| {
+            print_download_progress("llama-server", progress)
+        })
+        .await?,
     };
+    let bin_dir = llama_server_path
+        .parent()
+        .map(|p| p.to_path_buf())
+        .unwrap_or_else(|| config_dir.join("bin"));
 
-    // Kill leftover process on our port
     if let Some(killed) = core::system::kill_process_on_port(config.http_port) {
         log::warn!("Killed leftover process on port {}: {}", config.http_port, killed);
         tokio::time::sleep(std::time::Duration::from_millis(500)).await;
     }
-
+    let api_key = core::process::generate_api_key()?;
+    core::process::set_api_key(Some(api_key.clone()));
+    let mut env_vars = std::collections::HashMap::new();
+    env_vars.insert("GGML_BACKEND_DIR".to_string(), bin_dir.to_string_lossy().to_string());
+    let llama_config = core::process::LlamaServerConfig {
+        binary_path: llama_server_path.clone(),
+        model_path,
+        mmproj_path,
+        host: config.llama_bind_host(),
+        port: config.http_port,
+        gpu_layers: config.gpu_layers,
+        ctx_size: config.ctx_size,
+        parallel_slots: core::process::adjust_parallel_slots(config.ctx_size, config.parallel_slots),
+        env_vars,
+        rpc_peers: None,
+        serve_mode: config.serve_mode(),
+        sandbox: config.sandbox,
+        api_key: Some(api_key),
+        context_shift: config.context_shift,
+        cache_reuse: config.cache_reuse,
+        log_verbosity: config.llama_log_verbosity,
+        log_disable: config.llama_log_disable,
+    };
+    let mut llama = core::process::LlamaProcess::spawn(&llama_config)?;
+    // Drain output so a full pipe can't stall llama-server
+    for reader in [
+        llama.take_stdout().map(|r| Box::new(r) as Box<dyn tokio::io::AsyncRead + Send + Unpin>),
+        llama.take_stderr().map(|r| Box::new(r) as Box<dyn tokio::io::AsyncRead + Send + Unpin>),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        tokio::spawn(async move {
+            use tokio::io::{AsyncBufReadExt, BufReader};
+            let mut lines = BufReader::new(reader).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                log::debug!("[llama] {}", core::system::mask_sensitive_data(&line));
+            }
+        });
+    }
+    log::info!("Waiting for llama-server to become ready...");
+    if let Err(e) = llama.wait_ready(300, config.http_port).await {
+        llama.kill();
+        return Err(e.into());
+    }
+
+    let client = core::http::local();
+    if let Err(e) = core::inference::metrics::probe_metrics(&client, config.http_port).await {
+        log::warn!("{}", e);
+    }
+    let serve_mode = config.serve_mode();
+    let benchmark = if serve_mode == core::config::ServeMode::Chat {
+        match core::inference::benchmark::run_benchmark(&client, config.http_port).await {
+            Ok(result) => Some(result),
+            Err(e) => {
+                log::warn!("Benchmark failed: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    llama.kill();
+
+    let bind_host = config.llama_bind_host();
+    let local_ip = core::system::advertised_ip(&config.oracle_url, &bind_host)
+        .await
+        .unwrap_or_else(|| "127.0.0.1".to_string());
+    let loopback = bind_host
+        .parse::<std::net::IpAddr>()
+        .map(|ip| ip.is_loopback())
+        .unwrap_or(bind_host.eq_ignore_ascii_case("localhost"));
+    let can_distribute = config.static_cluster()?.is_none()
+        && config.distributed_mode != "disabled"
+        && serve_mode == core::config::ServeMode::Chat;
+    // The network benchmark and reachability check need the Oracle; the payload
+    // shows them as they would be without it
+    let payload = core::oracle::registry::registration_payload(
+        &signing_key,
+        serve_mode.capability(),
+        &config.oracle_model(),
+        config.http_port,
+        &local_ip,
+        benchmark.as_ref(),
+        None,
+        can_distribute,
+        &local_ip,
+        loopback,
+        &core::oracle::registry::NodeInfo::from_config(&config),
+    )?;
+    let auth = core::relay::client::auth_message(&signing_key, &[config.oracle_model()])?;
+
+    println!(
+        "\nRegistration (POST {}/api/nodes/register):\n{}",
+        config.oracle_url.trim_end_matches('/'),
+        serde_json::to_string_pretty(&payload).map_err(|e| e.to_string())?
+    );
+    println!(
+        "\nRelay auth message:\n{}",
+        serde_json::to_string_pretty(&auth).map_err(|e| e.to_string())?
+    );
+    println!("\nDry run complete: nothing was sent to the Oracle or chain");
+    Ok(())
+}
+
+/// How long an exiting agent waits for webhooks and alerts to deliver its last event.
+const EVENT_FLUSH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+async fn cmd_start(
+    config_path: Option<PathBuf>,
+    port_override: Option<u16>,
+    model_override: Option<String>,
+    gpu_layers_override: Option<i32>,
+    llama_path_override: Option<PathBuf>,
+    shutdown: Arc<std::sync::atomic::AtomicBool>,
+) -> core::error::CoreResult<()> {
+    let config_dir = config_dir_for(config_path.as_ref());
+
+    // Refuse to start twice; a PID file whose process is gone is stale
+    let pid_file = config_dir.join("agent.pid");
+    if let Some(pid) = read_pid_file(&pid_file) {
+        if pid != std::process::id() && core::system::is_pid_alive(pid) {
+            return Err(format!("Agent is already running (PID {})", pid).into());
+        }
+    }
+
+    // Load config
+    let mut config = core::config::load_config(&config_dir)?;
+
+    // Apply overrides
+    if let Some(port) = port_override {
+        config.http_port = port;
+    }
+    let llama_path_override = llama_path_override.or_else(|| config.llama_path());
+    if let Some(model) = model_override {
+        config.model = model;
+    }
+    if let Some(ngl) = gpu_layers_override {
+        config.gpu_layers = ngl;
+    }
+
+    if let Err(e) = core::telemetry::init_tracing(config.otlp_endpoint.as_deref()) {
+        log::warn!("Tracing export disabled: {}", e);
+    }
+
+    // Validate private key
+    if config.private_key.is_empty() {
+        return Err(core::error::CoreError::Config(
+            "Private key not configured. Run `plumise-agent init` to set up.".into(),
+        ));
+    }
+    if !config.private_key.starts_with("0x") || config.private_key.len() != 66 {
+        return Err(core::error::CoreError::Config(
+            "Invalid private key format. Must be 0x-prefixed hex (66 chars).".into(),
+        ));
+    }
+
+    // A static rpc-server only lends its GPU to the coordinator and needs no model
+    let mut rpc_server_only = is_rpc_server_only(&config)?;
+    core::tls_pin::install(&config)?;
+    core::http::configure(&config);
+    core::chain::profile::install(&core::http::client(), &config).await?;
+    // Restored when `plumise-agent mode auto` ends a manual override
+    let configured_cluster = (config.cluster.clone(), config.distributed_mode.clone());
+
+    // Derive agent address
+    let signing_key = core::chain::crypto::parse_private_key(&config.private_key)?;
+
+    // Remember what we started with so `restart` can report changes
+    let _ = std::fs::create_dir_all(&config_dir);
+    if let Err(e) = core::config::save_last_start(&config, &config_dir) {
+        log::warn!("{}", e);
+    }
+    let agent_address = core::chain::crypto::address_from_key(&signing_key);
+    log::info!("Agent address: {}", agent_address);
+    core::webhook::validate(&config.webhooks)?;
+    config.alerts.validate()?;
+    // Not aborted on return, so `Stopped` and `Crashed` still go out
+    let _webhooks = core::webhook::start_webhooks(config.webhooks.clone(), agent_address.clone());
+    let _alerts = core::alerts::start_alerts(config.alerts.clone(), agent_address.clone());
+
+    // Ensure model
+    let models_dir = config_dir.join("models");
+    let mut model_files = if rpc_server_only {
+        None
+    } else {
+        Some(ensure_model_files(&config, &models_dir).await?)
+    };
+
+    // Ensure llama-server binary
+    let bin_dir = config_dir.join("bin");
+    let llama_server_path = if let Some(ref path) = llama_path_override {
+        if !path.exists() {
+            return Err(core::error::CoreError::Config(format!(
+                "Specified llama-server not found: {}",
+                path.display()
+            )));
+        }
+        path.clone()
+    } else {
+        core::llama_bin::ensure_llama_server(&bin_dir, &config.llama_version, |progress| {
+            print_download_progress("llama-server", progress)
+        })
+        .await?
+    };
+    // Backend libraries live next to the server binary
+    let bin_dir = llama_server_path
+        .parent()
+        .map(|p| p.to_path_buf())
+        .unwrap_or(bin_dir);
+
+    let schedule = config.schedule()?;
+
+    // Write PID file (this process; llama-server's PID goes to llama.pid)
+    let _ = std::fs::create_dir_all(&config_dir);
+    let _ = std::fs::write(&pid_file, std::process::id().to_string());
+
+    loop {
+        if !schedule.is_always() && !schedule.is_active_now() {
+            let mut state = runtime_state_for(&config);
+            state.phase = core::runtime_state::AgentPhase::Scheduled;
+            let _ = state.save(&config_dir);
+            if let Some(next) = schedule.status().next_change {
+                log::info!(
+                    "Outside serving hours; next activation at {}",
+                    core::schedule::format_local(next)
+                );
+            }
+            if !wait_for_serving_window(&schedule, &shutdown).await {
+                break;
+            }
+        }
+
+        // CPU-only while on battery, if configured
+        let low_power = config.battery_policy() == core::power::BatteryPolicy::Cpu
+            && core::power::power_status().on_battery();
+        if low_power {
+            log::info!("Running on battery: starting llama-server CPU-only");
+        }
+
+        let end = match (rpc_server_only, &model_files) {
+            (false, Some((model_path, mmproj_path))) => {
+                run_session(
+                    &config,
+                    &config_dir,
+                    &signing_key,
+                    model_path,
+                    mmproj_path.as_deref(),
+                    &llama_server_path,
+                    &bin_dir,
+                    &schedule,
+                    &shutdown,
+                    low_power,
+                )
+                .await?
+            }
+            _ => {
+                run_rpc_server_session(&config, &config_dir, &signing_key, &bin_dir, &schedule, &shutdown)
+                    .await?
+            }
+        };
+        match end {
+            SessionEnd::Shutdown => break,
+            SessionEnd::ModeChanged(request) => {
+                let mut next = config.clone();
+                match request.mode.as_str() {
+                    "auto" => {
+                        next.cluster = configured_cluster.0.clone();
+                        next.distributed_mode = configured_cluster.1.clone();
+                    }
+                    "standalone" => {
+                        next.cluster = Default::default();
+                        next.distributed_mode = "standalone".into();
+                    }
+                    role => {
+                        next.cluster = core::config::ClusterConfig {
+                            role: role.to_string(),
+                            peers: request.peers,
+                        };
+                    }
+                }
+                match is_rpc_server_only(&next) {
+                    Ok(only) => {
+                        log::info!("Switching node mode to {}", request.mode);
+                        config = next;
+                        rpc_server_only = only;
+                    }
+                    Err(e) => log::error!("Ignoring node mode request: {}", e),
+                }
+                if !rpc_server_only && model_files.is_none() {
+                    model_files = Some(ensure_model_files(&config, &models_dir).await?);
+                }
+            }
+            SessionEnd::MemoryPressure => {
+                config.ctx_size = (config.ctx_size / 2).max(core::system::MIN_REDUCED_CTX);
+                log::warn!(
+                    "Critical memory pressure: restarting llama-server with ctx_size {}",
+                    config.ctx_size
+                );
+            }
+            SessionEnd::ScheduleClosed | SessionEnd::PowerChanged => {}
+        }
+    }
+
+    let _ = std::fs::remove_file(&pid_file);
+    core::runtime_state::RuntimeState::clear(&config_dir);
+    core::telemetry::shutdown_tracing();
+    log::info!("Agent stopped.");
+    core::events::publish(core::events::AgentEvent::Stopped {
+        reason: "Stopped by the operator".to_string(),
+    });
+    core::events::flush(EVENT_FLUSH_TIMEOUT).await;
+    Ok(())
+}
+
+/// True when `config` forces a static rpc-server, which needs no model.
+fn is_rpc_server_only(config: &core::config::AgentConfig) -> Result<bool, String> {
+    Ok(config
+        .static_cluster()?
+        .is_some_and(|a| a.mode == "rpc-server"))
+}
+
+/// Download (or verify) the model and its vision projector, if configured.
+async fn ensure_model_files(
+    config: &core::config::AgentConfig,
+    models_dir: &std::path::Path,
+) -> core::error::CoreResult<(PathBuf, Option<PathBuf>)> {
+    log::info!("Checking model file...");
+    let model_path =
+        ensure_verified_file(config, models_dir, &config.model_file, Some(config.model_url.as_str()), "model").await?;
+
+    // Vision projector lives in the same repo; a direct model_url can't point at both files
+    let mmproj_path = if config.mmproj_file.is_empty() {
+        None
+    } else {
+        log::info!("Checking mmproj file...");
+        let mirror = Some(config.model_url.as_str()).filter(|u| !u.to_lowercase().ends_with(".gguf"));
+        Some(ensure_verified_file(config, models_dir, &config.mmproj_file, mirror, "mmproj").await?)
+    };
+    Ok((model_path, mmproj_path))
+}
+
+/// Download `filename` if missing and re-verify its checksum (at most every
+/// `modelVerifyIntervalHours`). A corrupted file is downloaded again once the user
+/// confirms; without a terminal to ask on, the start fails instead.
+async fn ensure_verified_file(
+    config: &core::config::AgentConfig,
+    models_dir: &std::path::Path,
+    filename: &str,
+    mirror: Option<&str>,
+    label: &'static str,
+) -> core::error::CoreResult<PathBuf> {
+    let download = || {
+        core::model::download::ensure_model(
+            models_dir,
+            &config.model,
+            filename,
+            Some(config.hf_token.as_str()),
+            mirror,
+            move |progress| print_download_progress(label, progress),
+        )
+    };
+    let path = download().await?;
+    let Some(interval) = config.model_verify_interval() else {
+        return Ok(path);
+    };
+
+    let (dir, file) = (models_dir.to_path_buf(), filename.to_string());
+    let status = tokio::task::spawn_blocking(move || core::model::integrity::verify_model(&dir, &file, Some(interval)))
+        .await
+        .map_err(|e| core::error::CoreError::Model(format!("Verification task failed: {}", e)))?
+        .map_err(core::error::CoreError::Model)?;
+    if !status.is_mismatch() {
+        return Ok(path);
+    }
+
+    let message = status.message(filename);
+    log::error!("{}", message);
+    if !confirm("Download it again?") {
+        return Err(core::error::CoreError::Model(format!(
+            "{}. Run `plumise-agent models verify --redownload` to replace it.",
+            message
+        )));
+    }
+    core::model::integrity::discard(models_dir, filename).map_err(core::error::CoreError::Model)?;
+    download().await
+}
+
+/// Ask a yes/no question on the terminal; false when stdin is not a terminal.
+fn confirm(question: &str) -> bool {
+    use std::io::IsTerminal;
+    if !std::io::stdin().is_terminal() {
+        return false;
+    }
+    eprint!("{} [y/N] ", question);
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line).is_ok() && line.trim().eq_ignore_ascii_case("y")
+}
+
+/// Initial `status` snapshot for this process before registration.
+fn runtime_state_for(config: &core::config::AgentConfig) -> core::runtime_state::RuntimeState {
+    core::runtime_state::RuntimeState {
+        pid: std::process::id(),
+        model: config.model.clone(),
+        http_port: config.http_port,
+        node_mode: "standalone".into(),
+        availability: core::availability::AvailabilitySnapshot {
+            accepting: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Reporter callback for `minSupportedVersion` changes after the first registration.
+fn log_update_required(update: Option<&core::oracle::registry::UpdateRequired>) {
+    match update {
+        Some(u) => log::error!(
+            "Agent update required: running {}, the Oracle requires {} or newer. Install the latest release.",
+            u.current_version,
+            u.min_supported_version
+        ),
+        None => log::info!("The Oracle no longer requires an agent update"),
+    }
+}
+
+#[derive(PartialEq)]
+enum SessionEnd {
+    Shutdown,
+    ScheduleClosed,
+    /// Switched between AC and battery in `cpu` battery mode; restart with the other GPU setting.
+    PowerChanged,
+    /// `plumise-agent mode` asked for another node mode; restart the servers in it.
+    ModeChanged(core::runtime_state::NodeModeRequest),
+    /// Critical memory pressure in `reduce-ctx` mode; restart with half the context.
+    MemoryPressure,
+}
+
+/// Sleep until the schedule opens. Returns false if Ctrl+C was pressed meanwhile.
+async fn wait_for_serving_window(
+    schedule: &core::schedule::Schedule,
+    shutdown: &std::sync::atomic::AtomicBool,
+) -> bool {
+    while !schedule.is_active_now() {
+        if shutdown.load(std::sync::atomic::Ordering::SeqCst) {
+            return false;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    }
+    !shutdown.load(std::sync::atomic::Ordering::SeqCst)
+}
+
+fn print_download_progress(what: &str, progress: core::model::download::DownloadProgress) {
+    if progress.total_bytes > 0 {
+        print!(
+            "\rDownloading {}: {:.0}% ({:.1} / {:.1} GB)",
+            what,
+            progress.percent,
+            progress.downloaded_bytes as f64 / 1e9,
+            progress.total_bytes as f64 / 1e9,
+        );
+        use std::io::Write;
+        let _ = std::io::stdout().flush();
+        if progress.percent >= 100.0 {
+            println!();
+        }
+    }
+}
+
+/// Run llama-server, register and serve until Ctrl+C or the serving window closes.
+#[allow(clippy::too_many_arguments)]
+async fn run_session(
+    config: &core::config::AgentConfig,
+    config_dir: &std::path::Path,
+    signing_key: &core::chain::crypto::SigningKey,
+    model_path: &std::path::Path,
+    mmproj_path: Option<&std::path::Path>,
+    llama_server_path: &std::path::Path,
+    bin_dir: &std::path::Path,
+    schedule: &core::schedule::Schedule,
+    shutdown: &std::sync::atomic::AtomicBool,
+    low_power: bool,
+) -> core::error::CoreResult<SessionEnd> {
+    // Kill leftover process on our port
+    if let Some(killed) = core::system::kill_process_on_port(config.http_port) {
+        log::warn!("Killed leftover process on port {}: {}", config.http_port, killed);
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    }
+    // Anything else holding it stays; move to the next free port. Registration and
+    // the runtime state below read the port from `config`.
+    let mut config = config.clone();
+    let port = core::system::pick_free_port(
+        &config.llama_bind_host(),
+        config.http_port,
+        config.http_port_scan,
+    )?;
+    if port != config.http_port {
+        log::warn!("Port {} is in use by another program, using {}", config.http_port, port);
+        config.http_port = port;
+    }
+    let config = &config;
+
+    // Static coordinator: split the model across the configured peers that are close enough
+    let static_cluster = config.static_cluster()?;
+    let rpc_peers = match static_cluster.as_ref().and_then(|a| a.rpc_peers.as_ref()) {
+        Some(peers) => {
+            let measurements =
+                core::oracle::peers::measure_peers(peers, config.max_peer_latency_ms).await;
+            if let Err(e) = core::oracle::peers::report_peer_measurements(
+                &core::http::client(),
+                &config.oracle_url,
+                signing_key,
+                Some("static"),
+                &measurements,
+            )
+            .await
+            {
+                log::warn!("{}", e);
+            }
+            let usable: Vec<String> = measurements
+                .into_iter()
+                .filter(|m| m.accepted)
+                .map(|m| m.peer)
+                .collect();
+            if usable.is_empty() {
+                log::warn!("No usable RPC peers, running standalone");
+                None
+            } else {
+                log::info!("Static cluster: coordinating {} RPC peer(s): {}", usable.len(), usable.join(", "));
+                Some(usable)
+            }
+        }
+        None => None,
+    };
+
     // Auto-adjust parallel slots
     let effective_slots = core::process::adjust_parallel_slots(config.ctx_size, config.parallel_slots);
 
@@ -178,39 +1214,56 @@ async fn cmd_start(
         bin_dir.to_string_lossy().to_string(),
     );
 
+    // Per-start key so nobody else on the LAN can use this llama-server
+    let api_key = core::process::generate_api_key()?;
+    core::process::set_api_key(Some(api_key.clone()));
+
     // Spawn llama-server
     let llama_config = core::process::LlamaServerConfig {
-        binary_path: llama_server_path,
-        model_path,
-        host: "0.0.0.0".into(),
+        binary_path: llama_server_path.to_path_buf(),
+        model_path: model_path.to_path_buf(),
+        mmproj_path: mmproj_path.map(|p| p.to_path_buf()),
+        host: config.llama_bind_host(),
         port: config.http_port,
-        gpu_layers: config.gpu_layers,
+        gpu_layers: if low_power { 0 } else { config.gpu_layers },
         ctx_size: config.ctx_size,
         parallel_slots: effective_slots,
         env_vars,
-        rpc_peers: None,
+        rpc_peers,
+        serve_mode: config.serve_mode(),
+        sandbox: config.sandbox,
+        api_key: Some(api_key.clone()),
+        context_shift: config.context_shift,
+        cache_reuse: config.cache_reuse,
+        log_verbosity: config.llama_log_verbosity,
+        log_disable: config.llama_log_disable,
     };
 
     let mut llama = core::process::LlamaProcess::spawn(&llama_config)?;
     let pid = llama.pid;
 
-    // Write PID file
-    let pid_file = config_dir.join("agent.pid");
-    let _ = std::fs::write(&pid_file, pid.to_string());
+    // Write llama-server PID file (lets `stop` clean up if the agent dies first)
+    let llama_pid_file = config_dir.join("llama.pid");
+    let _ = std::fs::write(&llama_pid_file, pid.to_string());
+
+    let mut state = runtime_state_for(config);
+    state.llama_api_key = Some(api_key);
+    let _ = state.save(config_dir);
 
     // Stream stdout/stderr to logger
+    let llama_log = std::sync::Arc::new(core::process::llama_log::LlamaLog::from_config(config, config_dir));
     if let Some(stdout) = llama.take_stdout() {
+        let llama_log = std::sync::Arc::clone(&llama_log);
         tokio::spawn(async move {
             use tokio::io::{AsyncBufReadExt, BufReader};
             let mut lines = BufReader::new(stdout).lines();
             while let Ok(Some(line)) = lines.next_line().await {
-                let level = core::system::parse_log_level(&line);
-                let masked = core::system::mask_sensitive_data(&line);
-                match level {
-                    "ERROR" => log::error!("[llama] {}", masked),
-                    "WARNING" => log::warn!("[llama] {}", masked),
-                    "DEBUG" => log::debug!("[llama] {}", masked),
-                    _ => log::info!("[llama] {}", masked),
+                match llama_log.handle(&line) {
+                    Some(("ERROR", masked)) => log::error!("[llama] {}", masked),
+                    Some(("WARNING", masked)) => log::warn!("[llama] {}", masked),
+                    Some(("DEBUG", masked)) => log::debug!("[llama] {}", masked),
+                    Some((_, masked)) => log::info!("[llama] {}", masked),
+                    None => {}
                 }
             }
         });
@@ -220,8 +1273,9 @@ async fn cmd_start(
             use tokio::io::{AsyncBufReadExt, BufReader};
             let mut lines = BufReader::new(stderr).lines();
             while let Ok(Some(line)) = lines.next_line().await {
-                let masked = core::system::mask_sensitive_data(&line);
-                log::warn!("[llama:err] {}", masked);
+                if let Some((_, masked)) = llama_log.handle(&line) {
+                    log::warn!("[llama:err] {}", masked);
+                }
             }
         });
     }
@@ -230,75 +1284,330 @@ async fn cmd_start(
     llama.wait_ready(300, config.http_port).await?;
 
     // Agent is ready — register with Oracle
-    let client = reqwest::Client::new();
+    let client = core::http::client();
 
-    let local_ip = core::system::get_local_ip().unwrap_or_else(|| "127.0.0.1".to_string());
-    log::info!("Local endpoint: http://{}:{}", local_ip, config.http_port);
+    let local_ip = core::system::advertised_ip(&config.oracle_url, &config.llama_bind_host())
+        .await
+        .unwrap_or_else(|| "127.0.0.1".to_string());
+    log::info!("Local endpoint: {}", core::system::http_endpoint(&local_ip, config.http_port));
 
-    let oracle_model = core::config::oracle_model_name(&config.model);
+    let serve_mode = config.serve_mode();
+    let oracle_model = config.oracle_model();
 
-    // RAM
-    let ram_mb = if config.ram_limit_gb > 0 {
-        (config.ram_limit_gb as u64) * 1024
+    // Benchmark (completion throughput; not applicable to embedding/rerank servers)
+    let benchmark = if serve_mode != core::config::ServeMode::Chat {
+        None
     } else {
-        core::system::total_ram_mb()
-    };
-
-    // Benchmark
-    let benchmark_tps = match core::inference::benchmark::run_benchmark(&client, config.http_port).await {
-        Ok(tps) => {
-            log::info!("Benchmark: {:.2} tok/s", tps);
-            tps
+        match core::inference::benchmark::run_benchmark(&client, config.http_port).await {
+            Ok(result) => Some(result),
+            Err(e) => {
+                log::warn!("Benchmark skipped: {}", e);
+                None
+            }
         }
+    };
+    let network = match core::oracle::network::run_network_benchmark(&client, &config.oracle_url).await {
+        Ok(result) => Some(result),
         Err(e) => {
-            log::warn!("Benchmark skipped: {}", e);
-            0.0
+            log::warn!("Network benchmark skipped: {}", e);
+            None
         }
     };
 
-    let can_distribute = config.distributed_mode != "disabled";
+    // Embedding/reranker models are small enough to never be split across machines,
+    // and a static cluster is never offered to the Oracle for assignment
+    let can_distribute = static_cluster.is_none()
+        && config.distributed_mode != "disabled"
+        && serve_mode == core::config::ServeMode::Chat;
+
+    // Make sure the endpoint we advertise can actually be reached before registering it
+    let reachability = core::oracle::reachability::check_endpoint(
+        &client,
+        &config.oracle_url,
+        signing_key,
+        &config.llama_bind_host(),
+        &local_ip,
+        config.http_port,
+    )
+    .await;
+    match reachability.reachable {
+        Some(true) => log::info!("{}", reachability.message),
+        _ => log::warn!("{}", reachability.message),
+    }
+    let relay_only = reachability.relay_only();
 
     // Register with Oracle
-    match core::oracle::registry::register(
+    let geo = core::oracle::geo::GeoLocator::from_config(config, config_dir);
+    let mut node_info = core::oracle::registry::NodeInfo::from_config(config);
+    node_info.llama_version = core::inference::metrics::fetch_build_info(&client, config.http_port)
+        .await
+        .ok();
+    if let Some(geo) = &geo {
+        node_info.location = geo.locate(&client).await;
+    }
+    let oracle_assignment = match core::oracle::registry::register(
         &client,
         &config.oracle_url,
-        &signing_key,
-        oracle_model,
+        signing_key,
+        serve_mode.capability(),
+        &oracle_model,
         config.http_port,
-        ram_mb,
-        0,
-        &config.device,
         &local_ip,
-        benchmark_tps,
+        benchmark.as_ref(),
+        network.as_ref(),
         can_distribute,
         &local_ip,
+        relay_only,
+        &node_info,
     )
     .await
     {
-        Ok(_assignment) => {
+        Ok(registration) => {
             log::info!("Registered with Oracle");
+            state.registered = true;
+            state.last_registered_at = Some(core::ledger::unix_millis() / 1000);
+            state.update_required = registration.update_required;
+            if let Some(assignment) = &registration.assignment {
+                state.node_mode = assignment.mode.clone();
+                state.cluster_id = assignment.cluster_id.clone();
+            }
+            registration.assignment
         }
         Err(e) => {
             log::warn!("Oracle registration failed (non-fatal): {}", e);
+            state.registration_error = Some(e.to_string());
+            None
         }
+    };
+    core::events::publish(core::events::AgentEvent::Started {
+        model: oracle_model.clone(),
+        endpoint: core::system::http_endpoint(&local_ip, config.http_port),
+    });
+    if let Some(assignment) = static_cluster {
+        state.node_mode = assignment.mode;
+        state.cluster_id = assignment.cluster_id;
+    }
+    state.phase = core::runtime_state::AgentPhase::Serving;
+    let _ = state.save(config_dir);
+
+    // Availability gate shared by the reporter, relay and idle watcher
+    let availability = Arc::new(core::availability::Availability::new());
+    let idle_policy = config.idle_policy();
+    let idle_handle = if idle_policy.enabled() {
+        log::info!(
+            "Idle policy: {} (idle after {}s, GPU busy above {}%)",
+            idle_policy.mode,
+            idle_policy.idle_threshold_secs,
+            idle_policy.gpu_busy_percent
+        );
+        Some(core::idle::start_idle_watcher(idle_policy, availability.clone()))
+    } else {
+        None
+    };
+
+    let power_changed = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let battery_policy = config.battery_policy();
+    let battery_handle = if battery_policy != core::power::BatteryPolicy::Off {
+        let flag = power_changed.clone();
+        Some(core::power::start_battery_watcher(
+            battery_policy,
+            availability.clone(),
+            15,
+            move |status| {
+                if battery_policy == core::power::BatteryPolicy::Cpu {
+                    log::info!(
+                        "Power source changed ({:?}), restarting llama-server",
+                        status.source
+                    );
+                    flag.store(true, std::sync::atomic::Ordering::SeqCst);
+                }
+            },
+        ))
+    } else {
+        None
+    };
+
+    let memory_critical = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let memory_policy = config.memory_pressure_policy();
+    let memory_handle = if memory_policy != core::system::MemoryPressurePolicy::Off {
+        let flag = memory_critical.clone();
+        let can_reduce = memory_policy == core::system::MemoryPressurePolicy::ReduceCtx
+            && config.ctx_size > core::system::MIN_REDUCED_CTX;
+        Some(core::system::start_memory_pressure_watcher(
+            memory_policy,
+            availability.clone(),
+            10,
+            move |level| {
+                if can_reduce && level == core::system::MemoryPressure::Critical {
+                    flag.store(true, std::sync::atomic::Ordering::SeqCst);
+                }
+            },
+        ))
+    } else {
+        None
+    };
+
+    // Mirror pause/deregistration, relay connection, update requests and penalties
+    // into the state file for `status` and `top`
+    let relay_connected = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let initial_update_required = state.update_required.clone();
+    let (update_tx, mut update_rx) = tokio::sync::watch::channel(initial_update_required.clone());
+    let (penalty_tx, mut penalty_rx) = tokio::sync::watch::channel(None);
+    let state_handle = {
+        let availability = availability.clone();
+        let relay_connected = relay_connected.clone();
+        let config_dir = config_dir.to_path_buf();
+        let mut rx = availability.subscribe();
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(std::time::Duration::from_secs(2));
+            loop {
+                tokio::select! {
+                    changed = rx.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                        state.availability = availability.snapshot();
+                    }
+                    changed = update_rx.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                        state.update_required = update_rx.borrow().clone();
+                    }
+                    changed = penalty_rx.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                        state.penalty = penalty_rx.borrow().clone();
+                    }
+                    _ = tick.tick() => {
+                        let connected = relay_connected.load(std::sync::atomic::Ordering::Relaxed);
+                        if connected == state.relay_connected {
+                            continue;
+                        }
+                        state.relay_connected = connected;
+                    }
+                }
+                let _ = state.save(&config_dir);
+            }
+        })
+    };
+
+    // Log thermal throttling transitions
+    let gpu_handle = core::system::start_gpu_monitor(30, |_| {});
+
+    // Failed jobs and downtime the Oracle holds against this node
+    let penalty_handle = core::oracle::penalty::start_penalty_monitor(
+        client.clone(),
+        config.oracle_url.clone(),
+        core::chain::crypto::address_from_key(signing_key),
+        core::oracle::penalty::POLL_INTERVAL_SECS,
+        move |status| {
+            let _ = penalty_tx.send(Some(status));
+        },
+    );
+
+    // All-time totals in <config_dir>/lifetime.json
+    let latency = core::inference::stats::SharedLatencyStats::default();
+    let lifetime = Arc::new(std::sync::Mutex::new(core::lifetime::LifetimeTracker::open(config_dir)));
+    if let Ok(mut t) = lifetime.lock() {
+        t.record_launch();
     }
+    let llama_port = config.http_port;
+    let lifetime_handle = core::lifetime::start_lifetime_recorder(
+        lifetime.clone(),
+        move || Some(llama_port),
+        Some(latency.clone()),
+        60,
+    );
+
+    let rewards_lifetime = lifetime.clone();
+    let claim_history = std::sync::Arc::new(core::claims::ClaimHistory::open(config_dir)?);
+    let payout_address = config.payout_address().unwrap_or_else(|e| {
+        log::warn!("Claimed rewards will not be forwarded: {}", e);
+        None
+    });
+    let forward_rpc = config.chain_rpc.clone();
+    let forward_key = signing_key.clone();
+    let rewards_handle = core::chain::events::start_reward_watcher(
+        config.chain_rpc.clone(),
+        Some(config.chain_ws_url()),
+        core::chain::crypto::address_from_key(signing_key),
+        move |event| match event.kind {
+            core::chain::events::RewardEventKind::Accrued => {
+                log::info!("Reward accrued: {:.6} PLM", event.amount_plm)
+            }
+            core::chain::events::RewardEventKind::Claimed => {
+                log::info!("Rewards claimed: {:.6} PLM (tx {})", event.amount_plm, event.tx_hash);
+                if let Ok(mut t) = rewards_lifetime.lock() {
+                    t.record_claim(event.amount_plm);
+                    let _ = t.save();
+                }
+                match claim_history.record_event(&event) {
+                    Ok(true) => {
+                        if let Some(to) = payout_address {
+                            let (rpc, key, history) =
+                                (forward_rpc.clone(), forward_key.clone(), claim_history.clone());
+                            tokio::spawn(async move {
+                                if let Err(e) =
+                                    core::claims::forward_claimed(&rpc, &key, to, &event, &history).await
+                                {
+                                    log::warn!("Failed to forward claimed rewards: {}", e);
+                                }
+                            });
+                        }
+                    }
+                    Ok(false) => {}
+                    Err(e) => log::warn!("Failed to record claim: {}", e),
+                }
+            }
+        },
+    );
+
+    // Low-balance alerts are logged by the watcher itself
+    let balance_handle = (!config.balance_alert_plm.is_empty()).then(|| {
+        core::wallet::start_balance_watcher(
+            config.chain_rpc.clone(),
+            config.private_key.clone(),
+            config.balance_alert_plm.clone(),
+            Default::default(),
+            config.balance_check_secs.max(30),
+            |_| {},
+        )
+    });
 
     // Start background reporter
     let reporter_handle = core::oracle::reporter::start_reporter(
         client.clone(),
-        config.oracle_url.clone(),
-        signing_key.clone(),
-        config.http_port,
-        core::oracle::reporter::RegistrationParams {
-            model: oracle_model.to_string(),
-            http_port: config.http_port,
-            ram_mb,
-            vram_mb: 0,
-            device: config.device.clone(),
-            external_ip: local_ip.clone(),
-            benchmark_tok_per_sec: benchmark_tps,
-            can_distribute,
-            lan_ip: local_ip.clone(),
+        core::oracle::reporter::ReporterConfig {
+            oracle_url: config.oracle_url.clone(),
+            signing_key: signing_key.clone(),
+            llama_port: config.http_port,
+            registration: core::oracle::reporter::RegistrationParams {
+                serve_mode,
+                model: oracle_model.clone(),
+                http_port: config.http_port,
+                external_ip: local_ip.clone(),
+                benchmark,
+                network,
+                rebenchmark_hours: config.rebenchmark_hours,
+                can_distribute,
+                lan_ip: local_ip.clone(),
+                relay_only,
+                assignment: oracle_assignment,
+                update_required: initial_update_required,
+                routes: None,
+                node_info,
+                geo,
+                attester: Some(core::oracle::attestation::UptimeAttester::open(config_dir, signing_key)),
+            },
+            availability: Some(availability.clone()),
+            on_assignment: None,
+            on_oracle_status: None,
+            on_update_required: Some(Box::new(move |update: Option<core::oracle::registry::UpdateRequired>| {
+                log_update_required(update.as_ref());
+                let _ = update_tx.send(update);
+            })),
         },
     );
 
@@ -315,41 +1624,344 @@ async fn cmd_start(
     let ws_url = format!("{}/ws/agent-relay", ws_base);
     log::info!("Connecting to inference relay: {}", ws_url);
 
+    let ledger = match core::ledger::Ledger::open(config_dir) {
+        Ok(l) => Some(Arc::new(l)),
+        Err(e) => {
+            log::warn!("Request ledger disabled: {}", e);
+            None
+        }
+    };
+
+    let (proofs, proof_handle) =
+        match core::inference::proof_batch::start_from_config(config, config_dir, signing_key, client.clone()) {
+            Some((batcher, handle)) => (Some(batcher), Some(handle)),
+            None => (None, None),
+        };
+    let (usage, usage_handle) =
+        match core::oracle::usage::start_from_config(config, signing_key, client.clone()) {
+            Some((reporter, handle)) => (Some(reporter), Some(handle)),
+            None => (None, None),
+        };
+
+    let slots = core::inference::metrics::SharedSlots::default();
+    let slots_port = config.http_port;
+    let slots_handle = core::inference::metrics::start_slot_poller(
+        client.clone(),
+        slots.clone(),
+        std::time::Duration::from_secs(2),
+        move || Some(slots_port),
+    );
+    let keepalive_handle = (config.llama_keepalive_secs > 0).then(|| {
+        core::http::start_keepalive(
+            std::time::Duration::from_secs(config.llama_keepalive_secs),
+            move || Some(slots_port),
+        )
+    });
+
     let relay_handle = core::relay::client::start_relay(
         ws_url,
         signing_key.clone(),
-        oracle_model.to_string(),
-        config.http_port,
+        core::relay::client::ModelRoutes::single(oracle_model, config.http_port),
+        core::relay::client::RelayContext {
+            ledger,
+            availability: Some(availability.clone()),
+            serve_mode,
+            proofs: proofs.clone(),
+            audit: core::audit::AuditLog::from_config(config, config_dir).map(Arc::new),
+            stats: Some(latency),
+            connected: Some(relay_connected),
+            usage,
+            slots: Some(slots),
+        },
     );
 
-    log::info!("Agent is running. Press Ctrl+C to stop.");
-
-    // Setup Ctrl+C handler using std::sync for signal handler compatibility
-    let shutdown = Arc::new(std::sync::atomic::AtomicBool::new(false));
-    let shutdown_flag = Arc::clone(&shutdown);
-    ctrlc::set_handler(move || {
-        shutdown_flag.store(true, std::sync::atomic::Ordering::SeqCst);
-    })
-    .map_err(|e| format!("Failed to set Ctrl+C handler: {}", e))?;
+    if schedule.is_always() {
+        log::info!("Agent is running. Press Ctrl+C to stop.");
+    } else if let Some(next) = schedule.status().next_change {
+        log::info!(
+            "Agent is running until {}. Press Ctrl+C to stop.",
+            core::schedule::format_local(next)
+        );
+    }
 
-    // Poll for shutdown signal
-    loop {
+    // Poll for shutdown signal or the end of the serving window
+    let end = loop {
         tokio::time::sleep(std::time::Duration::from_millis(500)).await;
         if shutdown.load(std::sync::atomic::Ordering::SeqCst) {
-            break;
+            break SessionEnd::Shutdown;
         }
-    }
+        if power_changed.load(std::sync::atomic::Ordering::SeqCst) {
+            break SessionEnd::PowerChanged;
+        }
+        if memory_critical.load(std::sync::atomic::Ordering::SeqCst) {
+            break SessionEnd::MemoryPressure;
+        }
+        if let Some(request) = core::runtime_state::NodeModeRequest::take(config_dir) {
+            break SessionEnd::ModeChanged(request);
+        }
+        if !schedule.is_active_now() {
+            log::info!("Serving window closed, deregistering");
+            availability.pause(
+                core::availability::PauseSource::Schedule,
+                "Outside serving hours",
+                true,
+            );
+            // Give the relay a moment to close the connection cleanly
+            tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+            break SessionEnd::ScheduleClosed;
+        }
+    };
 
     // Graceful shutdown
     log::info!("Shutting down...");
+    if let Some(handle) = idle_handle {
+        handle.abort();
+    }
+    if let Some(handle) = battery_handle {
+        handle.abort();
+    }
+    if let Some(handle) = memory_handle {
+        handle.abort();
+    }
+    gpu_handle.abort();
+    penalty_handle.abort();
+    rewards_handle.abort();
+    lifetime_handle.abort();
+    if let Some(handle) = balance_handle {
+        handle.abort();
+    }
+    state_handle.abort();
     reporter_handle.abort();
     relay_handle.abort();
+    slots_handle.abort();
+    for handle in [proof_handle, usage_handle, keepalive_handle].into_iter().flatten() {
+        handle.abort();
+    }
+    // Persist the partial batch; it is submitted on the next start
+    if let Some(batch) = proofs.and_then(|p| p.take_batch(true)) {
+        log::info!("Saved {} pending proof(s) for submission on next start", batch.leaf_count);
+    }
     llama.kill();
 
     // Remove PID file
+    let _ = std::fs::remove_file(&llama_pid_file);
+
+    Ok(end)
+}
+
+/// Run rpc-server for a static cluster's coordinator until Ctrl+C or the serving window closes.
+async fn run_rpc_server_session(
+    config: &core::config::AgentConfig,
+    config_dir: &std::path::Path,
+    signing_key: &core::chain::crypto::SigningKey,
+    bin_dir: &std::path::Path,
+    schedule: &core::schedule::Schedule,
+    shutdown: &std::sync::atomic::AtomicBool,
+) -> Result<SessionEnd, String> {
+    if let Some(killed) = core::system::kill_process_on_port(config.rpc_port) {
+        log::warn!("Killed leftover process on port {}: {}", config.rpc_port, killed);
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    }
+
+    let rpc_server_path = bin_dir.join(core::llama_bin::rpc_server_exe_name());
+    if !rpc_server_path.exists() {
+        return Err(format!("rpc-server not found in {}", bin_dir.display()));
+    }
+    log::info!("Starting rpc-server on port {}", config.rpc_port);
+    let args = vec![
+        "--host".to_string(),
+        "0.0.0.0".to_string(),
+        "--port".to_string(),
+        config.rpc_port.to_string(),
+    ];
+    let (mut cmd, sandboxed) = if config.sandbox {
+        let policy = core::process::sandbox::SandboxPolicy::for_rpc_server(&rpc_server_path, config.rpc_port);
+        let (cmd, guard) = core::process::sandbox::command(&rpc_server_path, &args, &policy)?;
+        (cmd, Some(guard))
+    } else {
+        let mut cmd = tokio::process::Command::new(&rpc_server_path);
+        cmd.args(&args);
+        (cmd, None)
+    };
+    let mut child = cmd
+        .env("GGML_BACKEND_DIR", bin_dir)
+        .current_dir(bin_dir)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to start rpc-server: {}", e))?;
+    if let Some(guard) = sandboxed {
+        guard
+            .attach(child.id().unwrap_or(0))
+            .map_err(|e| format!("Failed to sandbox rpc-server: {}", e))?;
+    }
+
+    // Shares llama.pid so `stop` cleans it up if the agent dies first
+    let pid_file = config_dir.join("llama.pid");
+    let _ = std::fs::write(&pid_file, child.id().unwrap_or(0).to_string());
+
+    if let Some(stderr) = child.stderr.take() {
+        tokio::spawn(async move {
+            use tokio::io::{AsyncBufReadExt, BufReader};
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                log::debug!("[rpc-server] {}", line);
+            }
+        });
+    }
+
+    let probe = core::inference::rpc_health::wait_until_ready(
+        config.rpc_port,
+        std::time::Duration::from_secs(30),
+    )
+    .await?;
+    log::info!(
+        "rpc-server ready on port {} (protocol {})",
+        config.rpc_port,
+        probe.protocol_version.as_deref().unwrap_or("unknown")
+    );
+
+    // Register so the Oracle keeps tracking this node; it serves no relay traffic itself
+    let client = core::http::client();
+    let local_ip = core::system::advertised_ip(&config.oracle_url, &config.llama_bind_host())
+        .await
+        .unwrap_or_else(|| "127.0.0.1".to_string());
+    let mut registration = core::oracle::reporter::RegistrationParams {
+        serve_mode: config.serve_mode(),
+        model: config.oracle_model(),
+        http_port: config.http_port,
+        external_ip: local_ip.clone(),
+        benchmark: None,
+        network: None,
+        rebenchmark_hours: 0,
+        can_distribute: false,
+        lan_ip: local_ip,
+        relay_only: false,
+        assignment: None,
+        update_required: None,
+        routes: None,
+        node_info: core::oracle::registry::NodeInfo::from_config(config),
+        geo: core::oracle::geo::GeoLocator::from_config(config, config_dir),
+        attester: Some(core::oracle::attestation::UptimeAttester::open(config_dir, signing_key)),
+    };
+    if let Some(geo) = &registration.geo {
+        registration.node_info.location = geo.locate(&client).await;
+    }
+
+    let mut state = runtime_state_for(config);
+    state.node_mode = "rpc-server".into();
+    state.cluster_id = Some("static".into());
+    match core::oracle::registry::register(
+        &client,
+        &config.oracle_url,
+        signing_key,
+        registration.serve_mode.capability(),
+        &registration.model,
+        registration.http_port,
+        &registration.external_ip,
+        None,
+        None,
+        false,
+        &registration.lan_ip,
+        registration.relay_only,
+        &registration.node_info,
+    )
+    .await
+    {
+        Ok(result) => {
+            state.registered = true;
+            state.last_registered_at = Some(core::ledger::unix_millis() / 1000);
+            state.update_required = result.update_required.clone();
+            registration.update_required = result.update_required;
+        }
+        Err(e) => {
+            log::warn!("Oracle registration failed (non-fatal): {}", e);
+            state.registration_error = Some(e.to_string());
+        }
+    }
+    state.phase = core::runtime_state::AgentPhase::Serving;
+    let _ = state.save(config_dir);
+
+    // Drop out of the Oracle while the RPC port stops answering
+    let availability = Arc::new(core::availability::Availability::new());
+    let health_handle = {
+        let availability = availability.clone();
+        core::inference::rpc_health::start_rpc_health_monitor(config.rpc_port, 15, move |health| {
+            if health.healthy {
+                availability.resume(core::availability::PauseSource::RpcServer);
+            } else {
+                availability.pause(core::availability::PauseSource::RpcServer, health.message, true);
+            }
+        })
+    };
+    let reporter_handle = core::oracle::reporter::start_reporter(
+        client,
+        core::oracle::reporter::ReporterConfig {
+            oracle_url: config.oracle_url.clone(),
+            signing_key: signing_key.clone(),
+            llama_port: config.http_port,
+            registration,
+            availability: Some(availability.clone()),
+            on_assignment: None,
+            on_oracle_status: None,
+            on_update_required: Some(Box::new(|update: Option<core::oracle::registry::UpdateRequired>| {
+                log_update_required(update.as_ref())
+            })),
+        },
+    );
+
+    log::info!("Serving as rpc-server for the static cluster. Press Ctrl+C to stop.");
+    let end = loop {
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        if shutdown.load(std::sync::atomic::Ordering::SeqCst) {
+            break Ok(SessionEnd::Shutdown);
+        }
+        if let Some(request) = core::runtime_state::NodeModeRequest::take(config_dir) {
+            break Ok(SessionEnd::ModeChanged(request));
+        }
+        if !schedule.is_active_now() {
+            log::info!("Serving window closed, stopping rpc-server");
+            break Ok(SessionEnd::ScheduleClosed);
+        }
+        if let Ok(Some(status)) = child.try_wait() {
+            break Err(format!("rpc-server exited unexpectedly ({})", status));
+        }
+    };
+
+    log::info!("Shutting down...");
+    health_handle.abort();
+    reporter_handle.abort();
+    let _ = child.kill().await;
     let _ = std::fs::remove_file(&pid_file);
+    end
+}
 
-    log::info!("Agent stopped.");
+/// Ask the running agent to switch node mode; it restarts llama-server / rpc-server
+/// within a second, keeping the process (and its PID file) alive.
+fn cmd_mode(mode: String, peers: Vec<String>) -> Result<(), String> {
+    let config_dir = core::config::default_config_dir();
+    match read_pid_file(&config_dir.join("agent.pid")) {
+        Some(pid) if core::system::is_pid_alive(pid) => {}
+        _ => return Err("Agent is not running".into()),
+    }
+
+    // Same checks the agent applies, so a bad request fails here rather than in its log
+    if mode == "coordinator" || mode == "rpc-server" {
+        let mut config = core::config::load_config(&config_dir)?;
+        config.cluster = core::config::ClusterConfig {
+            role: mode.clone(),
+            peers: peers.clone(),
+        };
+        config.static_cluster()?;
+    }
+
+    core::runtime_state::NodeModeRequest {
+        mode: mode.clone(),
+        peers,
+    }
+    .save(&config_dir)?;
+    log::info!("Requested node mode '{}'; the agent switches within a few seconds", mode);
     Ok(())
 }
 
@@ -361,6 +1973,12 @@ fn cmd_stop() -> Result<(), String> {
         return Err("No PID file found. Agent may not be running.".into());
     }
 
+    stop_agent(&config_dir)
+}
+
+/// Gracefully stop the agent recorded in `<config_dir>/agent.pid`.
+fn stop_agent(config_dir: &std::path::Path) -> Result<(), String> {
+    let pid_file = config_dir.join("agent.pid");
     let pid_str = std::fs::read_to_string(&pid_file)
         .map_err(|e| format!("Failed to read PID file: {}", e))?;
     let pid: u32 = pid_str
@@ -369,55 +1987,333 @@ fn cmd_stop() -> Result<(), String> {
         .map_err(|e| format!("Invalid PID: {}", e))?;
 
     log::info!("Stopping agent PID: {}", pid);
-    core::system::kill_pid(pid);
+    core::system::terminate_pid(pid);
+
+    // Give the agent time to shut down llama-server itself, then force it
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(30);
+    while core::system::is_pid_alive(pid) && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(250));
+    }
+    if core::system::is_pid_alive(pid) {
+        log::warn!("Agent did not exit within 30s, killing it");
+        core::system::kill_pid(pid);
+    }
+
+    // Clean up an orphaned llama-server
+    let llama_pid_file = config_dir.join("llama.pid");
+    if let Some(llama_pid) = read_pid_file(&llama_pid_file) {
+        if core::system::is_pid_alive(llama_pid) {
+            core::system::kill_pid(llama_pid);
+        }
+    }
+    let _ = std::fs::remove_file(&llama_pid_file);
 
     let _ = std::fs::remove_file(&pid_file);
+    core::runtime_state::RuntimeState::clear(config_dir);
     log::info!("Agent stopped.");
     Ok(())
 }
 
-async fn cmd_status(port: u16) -> Result<(), String> {
+/// Stop the running agent (if any), wait for its port to free up and report
+/// which config fields changed since it was started. The caller starts it again.
+fn cmd_restart(
+    config_path: Option<&PathBuf>,
+    port_override: Option<u16>,
+    model_override: Option<String>,
+    gpu_layers_override: Option<i32>,
+) -> Result<(), String> {
+    let config_dir = config_dir_for(config_path);
+
+    let mut config = core::config::load_config(&config_dir)?;
+    if let Some(port) = port_override {
+        config.http_port = port;
+    }
+    if let Some(model) = model_override {
+        config.model = model;
+    }
+    if let Some(ngl) = gpu_layers_override {
+        config.gpu_layers = ngl;
+    }
+
+    let previous = core::config::load_last_start(&config_dir);
+    match &previous {
+        Some(prev) => {
+            let changes = core::config::diff_config(prev, &config);
+            if changes.is_empty() {
+                println!("No config changes since last start.");
+            } else {
+                println!("Config changes since last start:");
+                for change in &changes {
+                    println!("  {}", change);
+                }
+            }
+        }
+        None => println!("No record of the previous start; cannot compare config."),
+    }
+
+    let running = read_pid_file(&config_dir.join("agent.pid"))
+        .map(core::system::is_pid_alive)
+        .unwrap_or(false);
+    if !running {
+        println!("Agent is not running, starting it.");
+        return Ok(());
+    }
+    stop_agent(&config_dir)?;
+
+    // The old llama-server may hold its port for a moment after exiting
+    let old_port = previous.map(|p| p.http_port).unwrap_or(config.http_port);
+    wait_for_port_free(old_port, std::time::Duration::from_secs(30))
+}
+
+fn wait_for_port_free(port: u16, timeout: std::time::Duration) -> Result<(), String> {
+    let deadline = std::time::Instant::now() + timeout;
+    while std::net::TcpListener::bind(("127.0.0.1", port)).is_err() {
+        if std::time::Instant::now() >= deadline {
+            return Err(format!(
+                "Port {} is still in use after {}s",
+                port,
+                timeout.as_secs()
+            ));
+        }
+        std::thread::sleep(std::time::Duration::from_millis(250));
+    }
+    Ok(())
+}
+
+const EXIT_RUNNING: i32 = 0;
+const EXIT_DEGRADED: i32 = 2;
+const EXIT_STOPPED: i32 = 3;
+
+/// Print agent status and return the process exit code for healthchecks.
+async fn cmd_status(port: Option<u16>, json: bool) -> Result<i32, String> {
     let client = reqwest::Client::builder()
         .timeout(std::time::Duration::from_secs(3))
         .build()
         .map_err(|e| format!("HTTP client error: {}", e))?;
 
+    let config_dir = core::config::default_config_dir();
+    let pid_file = config_dir.join("agent.pid");
+    let pid = read_pid_file(&pid_file);
+    let alive = pid.map(core::system::is_pid_alive).unwrap_or(false);
+    let state = if alive {
+        core::runtime_state::RuntimeState::load(&config_dir)
+    } else {
+        None
+    };
+    let port = port
+        .or_else(|| state.as_ref().map(|s| s.http_port))
+        .unwrap_or(18920);
+    core::process::set_api_key(state.as_ref().and_then(|s| s.llama_api_key.clone()));
+
     let health_url = format!("http://127.0.0.1:{}/health", port);
-    match client.get(&health_url).send().await {
-        Ok(resp) => {
-            if let Ok(body) = resp.json::<serde_json::Value>().await {
-                let status = body["status"].as_str().unwrap_or("unknown");
-                println!("Status: {}", status);
-
-                if status == "ok" {
-                    // Fetch metrics
-                    let metrics =
-                        core::inference::metrics::fetch_metrics(&client, port)
-                            .await
-                            .unwrap_or_default();
-                    println!("Tokens processed: {}", metrics.total_tokens);
-                    println!("Requests served: {}", metrics.total_requests);
-                    println!("Throughput: {:.2} tok/s", metrics.tps);
-                }
+    let health = match client.get(&health_url).send().await {
+        Ok(resp) => Some(
+            resp.json::<serde_json::Value>()
+                .await
+                .ok()
+                .and_then(|body| body["status"].as_str().map(String::from))
+                .unwrap_or_else(|| "unknown".to_string()),
+        ),
+        Err(_) => None,
+    };
+    let healthy = health.as_deref() == Some("ok");
+    let metrics = if healthy {
+        Some(
+            core::inference::metrics::fetch_metrics(&client, port)
+                .await
+                .unwrap_or_default(),
+        )
+    } else {
+        None
+    };
+
+    let lifetime = core::lifetime::LifetimeStats::load(&config_dir);
+    let uptime_streak = core::oracle::attestation::UptimeStreak::load(&config_dir);
+    let streak_secs = uptime_streak.streak_secs(core::ledger::unix_millis() / 1000);
+    let projection = fetch_reward_projection(&client).await;
+    let phase = state.as_ref().map(|s| s.phase);
+    let registration_failed = state
+        .as_ref()
+        .map(|s| s.phase == core::runtime_state::AgentPhase::Serving && !s.registered)
+        .unwrap_or(false);
+    let (label, code) = if healthy && !registration_failed {
+        ("running", EXIT_RUNNING)
+    } else if healthy || health.is_some() {
+        ("degraded", EXIT_DEGRADED)
+    } else if phase == Some(core::runtime_state::AgentPhase::Scheduled) {
+        ("scheduled", EXIT_RUNNING)
+    } else if alive {
+        ("degraded", EXIT_DEGRADED)
+    } else {
+        ("stopped", EXIT_STOPPED)
+    };
+
+    if json {
+        let report = serde_json::json!({
+            "status": label,
+            "pid": if alive { pid } else { None },
+            "port": port,
+            "health": health,
+            "metrics": metrics,
+            "phase": phase,
+            "model": state.as_ref().map(|s| s.model.clone()),
+            "nodeMode": state.as_ref().map(|s| s.node_mode.clone()),
+            "clusterId": state.as_ref().and_then(|s| s.cluster_id.clone()),
+            "oracle": state.as_ref().map(|s| serde_json::json!({
+                "registered": s.registered && !s.availability.deregistered,
+                "deregistered": s.availability.deregistered,
+                "lastRegisteredAt": s.last_registered_at,
+                "error": s.registration_error,
+                "updateRequired": s.update_required,
+                "penalty": s.penalty,
+                "warnings": s.penalty.as_ref().map(|p| p.warnings()).unwrap_or_default(),
+            })),
+            "availability": state.as_ref().map(|s| s.availability.clone()),
+            "relayConnected": state.as_ref().map(|s| s.relay_connected),
+            "lifetime": lifetime,
+            "uptimeStreak": {
+                "attestations": uptime_streak.counter,
+                "streakSecs": streak_secs,
+                "streakStartedAt": (streak_secs > 0).then_some(uptime_streak.streak_started_at),
+            },
+            "rewardProjection": projection.as_ref().and_then(|p| p.as_ref().ok()),
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report)
+                .map_err(|e| format!("Failed to serialize status: {}", e))?
+        );
+        return Ok(code);
+    }
+
+    match health.as_deref() {
+        Some(status) => println!("Status: {}", status),
+        None => println!("Status: {} (port {} unreachable)", label, port),
+    }
+    if let Some(ref state) = state {
+        println!("Model: {}", state.model);
+        println!("Node mode: {}", state.node_mode);
+        match (&state.registration_error, state.availability.deregistered) {
+            (_, true) => println!("Oracle: deregistered"),
+            (Some(e), false) if !state.registered => println!("Oracle: not registered ({})", e),
+            _ if state.registered => println!("Oracle: registered"),
+            _ => println!("Oracle: not registered yet"),
+        }
+        if let Some(penalty) = &state.penalty {
+            for warning in penalty.warnings() {
+                println!("Warning: {}", warning);
+            }
+        }
+        if let Some(update) = &state.update_required {
+            println!(
+                "Update required: running {}, the Oracle requires {} or newer",
+                update.current_version, update.min_supported_version
+            );
+        }
+        println!(
+            "Relay: {}",
+            if state.relay_connected { "connected" } else { "disconnected" }
+        );
+        if !state.availability.accepting {
+            for reason in &state.availability.reasons {
+                println!("Paused: {} ({})", reason.reason, reason.source);
+            }
+        }
+    }
+    if let Some(metrics) = metrics {
+        println!("Tokens processed: {}", metrics.total_tokens);
+        println!("Requests served: {}", metrics.total_requests);
+        println!("Throughput: {:.2} tok/s", metrics.tps);
+
+        print_earnings_estimate(&client, metrics.tps).await;
+    }
+    match &projection {
+        Some(Ok(p)) => println!(
+            "Projected rewards: {:.4} PLM/day ({:.4} PLM/week) from the last {:.1} h",
+            p.plm_per_day,
+            p.plm_per_week,
+            p.window_secs as f64 / 3600.0
+        ),
+        Some(Err(e)) => println!("Projected rewards: unavailable ({})", e),
+        None => {}
+    }
+    if lifetime.first_started_at.is_some() {
+        println!(
+            "All time: {} tokens, {} requests, {:.1} h up, {} restarts, {:.4} PLM claimed",
+            lifetime.tokens,
+            lifetime.requests,
+            lifetime.uptime_secs as f64 / 3600.0,
+            lifetime.restarts,
+            lifetime.rewards_claimed_plm
+        );
+    }
+    if streak_secs > 0 {
+        println!(
+            "Verified uptime streak: {:.1} h ({} attestations in total)",
+            streak_secs as f64 / 3600.0,
+            uptime_streak.counter
+        );
+    }
+    if health.is_none() {
+        if let Some(pid) = pid {
+            if alive {
+                println!(
+                    "Agent process is running (PID {}) but llama-server is not up yet (starting, or outside serving hours).",
+                    pid
+                );
             } else {
-                println!("Status: running (could not parse health response)");
+                println!("Stale PID file found: {}. Run `plumise-agent stop` to clean up.", pid);
             }
         }
-        Err(_) => {
-            println!("Status: not running (port {} unreachable)", port);
+    }
+
+    Ok(code)
+}
 
-            // Check PID file
-            let config_dir = core::config::default_config_dir();
-            let pid_file = config_dir.join("agent.pid");
-            if pid_file.exists() {
-                if let Ok(pid) = std::fs::read_to_string(&pid_file) {
-                    println!("Stale PID file found: {}. Run `plumise-agent stop` to clean up.", pid.trim());
+/// Projection from the rewards accrued since the last claim; None without a configured key.
+async fn fetch_reward_projection(
+    client: &reqwest::Client,
+) -> Option<Result<core::economics::projection::RewardProjection, String>> {
+    let config_dir = core::config::default_config_dir();
+    let config = core::config::load_config(&config_dir).ok()?;
+    let signing_key = core::chain::crypto::parse_private_key(&config.private_key).ok()?;
+    let address = core::chain::crypto::address_from_key(&signing_key);
+    Some(core::economics::projection::projection(client, &config.chain_rpc, &address, &config_dir).await)
+}
+
+async fn print_earnings_estimate(client: &reqwest::Client, tok_per_sec: f64) {
+    let config_dir = core::config::default_config_dir();
+    let config = match core::config::load_config(&config_dir) {
+        Ok(c) => c,
+        Err(_) => return,
+    };
+    let ledger = core::ledger::Ledger::open(&config_dir).ok();
+    let address = core::chain::crypto::parse_private_key(&config.private_key)
+        .ok()
+        .map(|key| core::chain::crypto::address_from_key(&key));
+
+    match core::economics::estimate::estimate_from_chain(
+        client,
+        &config.chain_rpc,
+        address.as_deref(),
+        &config_dir,
+        tok_per_sec,
+        ledger.as_ref(),
+        24.0,
+    )
+    .await
+    {
+        Ok(est) => {
+            println!("Utilization (7d): {:.1}%", est.utilization * 100.0);
+            match (est.plm_per_day, est.plm_per_week) {
+                (Some(day), Some(week)) => {
+                    println!("Est. rewards: {:.4} PLM/day ({:.4} PLM/week)", day, week)
                 }
+                _ => println!("Est. rewards: unknown (no served tokens rewarded since the last claim yet)"),
             }
         }
+        Err(e) => println!("Est. rewards: unavailable ({})", e),
     }
-
-    Ok(())
 }
 
 fn cmd_init() -> Result<(), String> {
@@ -425,7 +2321,16 @@ fn cmd_init() -> Result<(), String> {
     std::fs::create_dir_all(&config_dir)
         .map_err(|e| format!("Failed to create config directory: {}", e))?;
 
-    let config = core::config::load_config(&config_dir)?;
+    let mut config = core::config::load_config(&config_dir)?;
+    let config_file = config_dir.join("agent-config.json");
+
+    // Default a fresh config to the model that fits this machine
+    let recommendation = core::model::recommend::recommend_for_host();
+    if !config_file.exists() {
+        config.model = recommendation.model.clone();
+        config.model_file = recommendation.model_file.clone();
+        config.gpu_layers = recommendation.gpu_layers;
+    }
 
     println!("Plumise Agent Setup");
     println!("===================");
@@ -454,8 +2359,19 @@ fn cmd_init() -> Result<(), String> {
     println!("Context Size: {}", config.ctx_size);
     println!();
 
+    println!(
+        "Recommended for this machine: {} ({})",
+        recommendation.model_file, recommendation.reason
+    );
+    if config.model_file != recommendation.model_file {
+        println!(
+            "  To use it, set \"modelFile\": \"{}\" and \"gpuLayers\": {} in the config.",
+            recommendation.model_file, recommendation.gpu_layers
+        );
+    }
+    println!();
+
     // Save default config if none exists
-    let config_file = config_dir.join("agent-config.json");
     if !config_file.exists() {
         core::config::save_config(&config, &config_dir)?;
         println!("Default config saved to {}", config_file.display());
@@ -465,11 +2381,11 @@ fn cmd_init() -> Result<(), String> {
     }
 
     // GPU detection
-    if let Some((name, vram)) = core::system::detect_gpu() {
-        if vram > 0 {
-            println!("GPU: {} ({} MB)", name, vram);
+    if let Some(gpu) = &core::hardware::profile().gpu {
+        if gpu.vram_mb > 0 {
+            println!("GPU: {} ({} MB)", gpu.name, gpu.vram_mb);
         } else {
-            println!("GPU: {}", name);
+            println!("GPU: {} (VRAM unknown; {} driver may still be installing)", gpu.name, gpu.vendor);
         }
     } else {
         println!("GPU: not detected (CPU-only mode)");
@@ -477,3 +2393,571 @@ fn cmd_init() -> Result<(), String> {
 
     Ok(())
 }
+
+/// Delete the selected agent data; the agent must not be running.
+fn cmd_clean(targets: core::cleanup::CleanupTargets, confirm_keys: bool) -> Result<(), String> {
+    if !targets.any() {
+        return Err("Nothing selected. Pass --models, --binaries, --logs, --config, --keys or --all".into());
+    }
+    let config_dir = core::config::default_config_dir();
+    if let Some(pid) = read_pid_file(&config_dir.join("agent.pid")) {
+        if core::system::is_pid_alive(pid) {
+            return Err(format!("Agent is running (PID {}). Run `plumise-agent stop` first", pid));
+        }
+    }
+    if targets.keys && !confirm_keys {
+        return Err("--keys deletes your private key. Back it up, then re-run with --confirm-keys".into());
+    }
+
+    let bin_dir = config_dir.join("bin");
+    let report = core::cleanup::clean(&config_dir, Some(&bin_dir), targets, confirm_keys)?;
+    for path in &report.removed {
+        println!("Removed {}", path);
+    }
+    if report.removed.is_empty() {
+        println!("Nothing to remove");
+    } else {
+        println!("Freed {:.2} GB", report.freed_bytes as f64 / 1e9);
+    }
+    if !report.errors.is_empty() {
+        return Err(format!("Some data could not be removed: {}", report.errors.join("; ")));
+    }
+    Ok(())
+}
+
+/// Run the preflight checks against the saved config. Returns whether all passed.
+async fn cmd_doctor(json: bool) -> Result<bool, String> {
+    let config_dir = core::config::default_config_dir();
+    let config = core::config::load_config(&config_dir)?;
+    let agent_running = read_pid_file(&config_dir.join("agent.pid"))
+        .is_some_and(core::system::is_pid_alive);
+    let models_dir = config_dir.join("models");
+    let env = core::preflight::PreflightEnv {
+        models_dir: &models_dir,
+        llama_server: core::llama_bin::current_server_path(&config_dir.join("bin"))
+            .filter(|p| p.exists())
+            .ok_or_else(|| "llama-server not installed".to_string()),
+        llama_auto_install: true,
+        free_port: false,
+        agent_running,
+        check_reachability: true,
+    };
+    let result = core::preflight::run_checks(&config, env).await;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).map_err(|e| e.to_string())?
+        );
+        return Ok(result.passed);
+    }
+    for check in &result.checks {
+        let mark = if check.passed { "ok  " } else { "FAIL" };
+        println!("[{}] {:<13} {}", mark, check.name, check.message);
+        if let Some(hint) = &check.hint {
+            println!("       {:<13} -> {}", "", hint);
+        }
+    }
+    let failed = result.checks.iter().filter(|c| !c.passed).count();
+    if failed == 0 {
+        println!("\nAll checks passed");
+    } else {
+        println!("\n{} check(s) failed", failed);
+    }
+    Ok(result.passed)
+}
+
+async fn cmd_key(action: KeyCommand) -> Result<(), String> {
+    let KeyCommand::Rotate { import, link } = action;
+    let config_dir = core::config::default_config_dir();
+
+    let new_key = if import {
+        eprintln!("Paste the new private key and press Enter:");
+        let mut line = String::new();
+        std::io::stdin()
+            .read_line(&mut line)
+            .map_err(|e| format!("Failed to read key: {}", e))?;
+        Some(line.trim().to_string())
+    } else {
+        None
+    };
+
+    // The running agent is registered under the old address; restart it afterwards
+    let was_running = read_pid_file(&config_dir.join("agent.pid")).is_some_and(core::system::is_pid_alive);
+    if was_running {
+        stop_agent(&config_dir)?;
+    }
+
+    let client = core::http::client();
+    let rotated = core::key_rotation::rotate_key(&client, &config_dir, new_key, link).await;
+    let report = match rotated {
+        Ok(report) => report,
+        Err(e) => {
+            if was_running {
+                let _ = cmd_start_detached(None, None, None, None, None);
+            }
+            return Err(e);
+        }
+    };
+
+    if let Some(old) = &report.old_address {
+        println!("Old address: {}", old);
+    }
+    println!("New address: {}", report.new_address);
+    if let Some(path) = &report.backup_path {
+        println!("Old key backed up to {} — keep it until its rewards are claimed", path);
+    }
+    if report.migration_linked {
+        println!("The Oracle linked the old address to the new one");
+    }
+    match &report.registration_error {
+        None => println!("New address registered with the Oracle"),
+        Some(e) => eprintln!("Oracle registration of the new address failed: {}", e),
+    }
+    if was_running {
+        cmd_start_detached(None, None, None, None, None)?;
+        println!("Agent restarted under the new address");
+    } else if !report.registered {
+        println!("The new address registers with the Oracle when the agent starts");
+    }
+    Ok(())
+}
+
+fn cmd_config(action: ConfigCommand) -> Result<(), String> {
+    let config_dir = core::config::default_config_dir();
+    let config = core::config::load_config(&config_dir)?;
+    let print = |value: &serde_json::Value| match value {
+        serde_json::Value::String(s) => println!("{}", s),
+        other => println!("{}", serde_json::to_string_pretty(other).unwrap_or_default()),
+    };
+
+    let updated = match action {
+        ConfigCommand::Get { key } => {
+            print(&core::config::config_get(&config, &key)?);
+            return Ok(());
+        }
+        ConfigCommand::List => {
+            print(&core::config::masked_config(&config)?);
+            return Ok(());
+        }
+        ConfigCommand::Set { key, value } => {
+            let updated = core::config::config_set(&config, &key, &value)?;
+            println!("{} = {}", key, core::config::config_get(&updated, &key)?);
+            updated
+        }
+        ConfigCommand::Unset { key } => {
+            let updated = core::config::config_unset(&config, &key)?;
+            println!("{} reset to {}", key, core::config::config_get(&updated, &key)?);
+            updated
+        }
+    };
+    core::config::save_config(&updated, &config_dir)?;
+
+    if let Some(pid) = read_pid_file(&config_dir.join("agent.pid")) {
+        if core::system::is_pid_alive(pid) {
+            println!("The agent is running; `plumise-agent restart` applies the change");
+        }
+    }
+    Ok(())
+}
+
+async fn cmd_update_llama(
+    version: Option<String>,
+    asset: Option<String>,
+    rollback: bool,
+) -> Result<(), String> {
+    let config_dir = core::config::default_config_dir();
+    let bin_dir = config_dir.join("bin");
+
+    if let Some(pid) = read_pid_file(&config_dir.join("agent.pid")) {
+        if core::system::is_pid_alive(pid) {
+            return Err(format!(
+                "Agent is running (PID {}). Stop it before updating llama-server.",
+                pid
+            ));
+        }
+    }
+
+    let old = core::llama_bin::current_version(&bin_dir);
+
+    if rollback {
+        let previous = core::llama_bin::previous_version(&bin_dir)
+            .ok_or("No previous llama.cpp version recorded")?;
+        core::llama_bin::set_current(&bin_dir, &previous)?;
+        println!(
+            "Rolled back llama.cpp to {} (was {}).",
+            previous,
+            old.as_deref().unwrap_or("none")
+        );
+        return Ok(());
+    }
+
+    let config = core::config::load_config(&config_dir)?;
+    let requested = version.unwrap_or_else(|| config.llama_version.clone());
+    let version = core::llama_bin::resolve_version(&bin_dir, &requested).await?;
+    if old.as_deref() == Some(version.as_str()) && asset.is_none() {
+        println!("llama.cpp {} is already current.", version);
+        return Ok(());
+    }
+
+    std::fs::create_dir_all(&bin_dir)
+        .map_err(|e| format!("Failed to create bin dir: {}", e))?;
+    let server_path =
+        core::llama_bin::install_version(&bin_dir, &version, asset.as_deref(), |progress| {
+            print_download_progress("llama-server", progress)
+        })
+        .await?;
+    core::llama_bin::set_current(&bin_dir, &version)?;
+
+    // Make sure the new build can actually load the configured model
+    let model_path = config_dir.join("models").join(&config.model_file);
+    if model_path.exists() {
+        println!("Verifying llama-server {} with {}...", version, config.model_file);
+        if let Err(e) = verify_llama_server(&server_path, &model_path, &config).await {
+            if let Some(old) = old.as_ref().filter(|o| **o != version) {
+                core::llama_bin::set_current(&bin_dir, old)?;
+                let _ = core::llama_bin::remove_version(&bin_dir, &version);
+                return Err(format!(
+                    "llama.cpp {} failed to start ({}); rolled back to {}",
+                    version, e, old
+                ));
+            }
+            return Err(format!("llama.cpp {} failed to start: {}", version, e));
+        }
+    } else {
+        println!("Model not downloaded yet; skipping startup check.");
+    }
+
+    println!(
+        "llama.cpp {} is now current (was {}).",
+        version,
+        old.as_deref().unwrap_or("none")
+    );
+    let pinned = config.llama_version.trim();
+    if !pinned.is_empty() && !pinned.eq_ignore_ascii_case("latest") && pinned != version {
+        println!(
+            "Note: config pins llamaVersion = {}; `start` will switch back to it.",
+            pinned
+        );
+    }
+    Ok(())
+}
+
+/// Start `server_path` on a spare local port and wait until it serves the model.
+async fn verify_llama_server(
+    server_path: &std::path::Path,
+    model_path: &std::path::Path,
+    config: &core::config::AgentConfig,
+) -> Result<(), String> {
+    let port = std::net::TcpListener::bind(("127.0.0.1", 0))
+        .and_then(|l| l.local_addr())
+        .map(|a| a.port())
+        .map_err(|e| format!("No free port for verification: {}", e))?;
+
+    let mut env_vars = std::collections::HashMap::new();
+    if let Some(dir) = server_path.parent() {
+        env_vars.insert(
+            "GGML_BACKEND_DIR".to_string(),
+            dir.to_string_lossy().to_string(),
+        );
+    }
+    let llama_config = core::process::LlamaServerConfig {
+        binary_path: server_path.to_path_buf(),
+        model_path: model_path.to_path_buf(),
+        mmproj_path: None,
+        host: "127.0.0.1".into(),
+        port,
+        gpu_layers: config.gpu_layers,
+        ctx_size: config.ctx_size.min(4096),
+        parallel_slots: 1,
+        env_vars,
+        rpc_peers: None,
+        serve_mode: core::config::ServeMode::Chat,
+        sandbox: config.sandbox,
+        api_key: None,
+        context_shift: config.context_shift,
+        cache_reuse: config.cache_reuse,
+        log_verbosity: config.llama_log_verbosity,
+        log_disable: config.llama_log_disable,
+    };
+
+    let mut llama = core::process::LlamaProcess::spawn(&llama_config)?;
+    // Drain output so a full pipe can't stall the server
+    for reader in [
+        llama.take_stdout().map(|s| Box::new(s) as Box<dyn tokio::io::AsyncRead + Send + Unpin>),
+        llama.take_stderr().map(|s| Box::new(s) as Box<dyn tokio::io::AsyncRead + Send + Unpin>),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        tokio::spawn(async move {
+            use tokio::io::{AsyncBufReadExt, BufReader};
+            let mut lines = BufReader::new(reader).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                log::debug!("[llama] {}", core::system::mask_sensitive_data(&line));
+            }
+        });
+    }
+    let result = llama.wait_ready(300, port).await;
+    llama.kill();
+    Ok(result?)
+}
+
+async fn cmd_earnings(hours: Option<u64>) -> Result<(), String> {
+    let config_dir = core::config::default_config_dir();
+    let config = core::config::load_config(&config_dir)?;
+    let ledger = core::ledger::Ledger::open(&config_dir)?;
+
+    let since = hours.map(|h| core::ledger::unix_millis().saturating_sub(h.saturating_mul(3600 * 1000)));
+    let summary = ledger.summary(since)?;
+
+    match hours {
+        Some(h) => println!("Served requests (last {}h)", h),
+        None => println!("Served requests (all time)"),
+    }
+    println!("==========================");
+    println!("Requests:          {}", summary.total_requests);
+    println!("  completed:       {}", summary.completed);
+    println!("  failed:          {}", summary.failed);
+    println!("  cancelled:       {}", summary.cancelled);
+    println!("Prompt tokens:     {}", summary.prompt_tokens);
+    println!("Completion tokens: {}", summary.completion_tokens);
+    println!("Avg duration:      {:.0} ms", summary.avg_duration_ms);
+
+    if !config.private_key.is_empty() {
+        let signing_key = core::chain::crypto::parse_private_key(&config.private_key)?;
+        let address = core::chain::crypto::address_from_key(&signing_key);
+        let client = core::http::client();
+        match core::chain::rewards::get_pending_reward(&client, &config.chain_rpc, &address).await {
+            Ok(wei) => println!(
+                "Pending reward:    {} PLM",
+                core::system::wei_to_display(&wei.to_string())
+            ),
+            Err(e) => println!("Pending reward:    unavailable ({})", e),
+        }
+    }
+
+    Ok(())
+}
+
+/// "5m ago", "3h ago", "12d ago" for a unix-millisecond timestamp.
+fn format_age(at_millis: u64) -> String {
+    let secs = core::ledger::unix_millis().saturating_sub(at_millis) / 1000;
+    match secs {
+        0..=59 => format!("{}s ago", secs),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+async fn cmd_models(action: ModelsCommand) -> Result<(), String> {
+    let config_dir = core::config::default_config_dir();
+    let mut config = core::config::load_config(&config_dir)?;
+    let models_dir = config_dir.join("models");
+
+    let (file, redownload) = match action {
+        ModelsCommand::Verify { file, redownload } => (file, redownload),
+        ModelsCommand::Import {
+            path,
+            name,
+            mode,
+            use_model,
+        } => {
+            let mode = core::model::import::ImportMode::parse(&mode)?;
+            let dir = models_dir.clone();
+            let entry = tokio::task::spawn_blocking(move || {
+                core::model::import::import_model(&dir, &path, name.as_deref(), mode)
+            })
+            .await
+            .map_err(|e| format!("Import task failed: {}", e))??;
+            println!(
+                "Imported {} ({:.1} GB) into {}{}",
+                entry.file,
+                entry.size_bytes as f64 / 1e9,
+                models_dir.display(),
+                entry.link.as_deref().map(|l| format!(" as a {}", l)).unwrap_or_default()
+            );
+            if use_model {
+                config.model_file = entry.file;
+                core::config::save_config(&config, &config_dir)?;
+                println!("modelFile set to {}", config.model_file);
+            } else {
+                println!("Set modelFile to {} to serve it (or pass --use)", entry.file);
+            }
+            return Ok(());
+        }
+        ModelsCommand::List => {
+            let entries = core::model::registry::list(&models_dir);
+            if entries.is_empty() {
+                println!("No models registered in {}", models_dir.display());
+            }
+            for entry in entries {
+                let origin = match (entry.imported_from, entry.link) {
+                    (Some(path), Some(link)) => format!("{} to {}", link, path),
+                    (Some(path), None) => format!("imported from {}", path),
+                    (None, _) => format!("downloaded from {}", entry.repo),
+                };
+                let active = if entry.file == config.model_file { " (active)" } else { "" };
+                println!(
+                    "{}{}  {:.1} GB  {}",
+                    entry.file,
+                    active,
+                    entry.size_bytes as f64 / 1e9,
+                    origin
+                );
+            }
+            return Ok(());
+        }
+    };
+
+    let files = match file {
+        Some(file) => vec![file],
+        None => [&config.model_file, &config.mmproj_file]
+            .into_iter()
+            .filter(|f| !f.is_empty())
+            .cloned()
+            .collect(),
+    };
+
+    let mut corrupted = Vec::new();
+    for file in files {
+        if !models_dir.join(&file).exists() {
+            println!("{}: not downloaded", file);
+            continue;
+        }
+        let (dir, name) = (models_dir.clone(), file.clone());
+        let status = tokio::task::spawn_blocking(move || core::model::integrity::verify_model(&dir, &name, None))
+            .await
+            .map_err(|e| format!("Verification task failed: {}", e))??;
+        println!("{}", status.message(&file));
+        if status.is_mismatch() {
+            corrupted.push(file);
+        }
+    }
+    if corrupted.is_empty() {
+        return Ok(());
+    }
+    if !redownload {
+        return Err(format!(
+            "{} corrupted file(s); run again with --redownload to replace them",
+            corrupted.len()
+        ));
+    }
+
+    for file in corrupted {
+        core::model::integrity::discard(&models_dir, &file)?;
+        // A direct model_url only points at the model file itself
+        let mirror = Some(config.model_url.as_str())
+            .filter(|u| file == config.model_file || !u.to_lowercase().ends_with(".gguf"));
+        core::model::download::ensure_model(
+            &models_dir,
+            &config.model,
+            &file,
+            Some(config.hf_token.as_str()),
+            mirror,
+            |progress| print_download_progress("model", progress),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        println!("{}: downloaded again", file);
+    }
+    Ok(())
+}
+
+async fn cmd_rewards(action: RewardsCommand) -> core::error::CoreResult<()> {
+    let config_dir = core::config::default_config_dir();
+    let history = core::claims::ClaimHistory::open(&config_dir)?;
+
+    match action {
+        RewardsCommand::Claim => {
+            let config = core::config::load_config(&config_dir)?;
+            if config.private_key.is_empty() {
+                return Err(core::error::CoreError::Config("No private key configured".into()));
+            }
+            let payout_address = config.payout_address().map_err(core::error::CoreError::Config)?;
+            let signing_key = core::chain::crypto::parse_private_key(&config.private_key)
+                .map_err(core::error::CoreError::Config)?;
+            let client = core::http::client();
+            let chain_id = core::chain::tx::fetch_chain_id(&client, &config.chain_rpc).await?;
+            let sender = core::chain::tx::TxSender::new(config.chain_rpc.clone(), chain_id, signing_key);
+
+            println!("Claiming rewards for {}...", sender.address);
+            let record = core::claims::claim_and_forward(&sender, payout_address, &history).await?;
+            if let Some(payout) = &record.payout {
+                match (&payout.tx_hash, &payout.error) {
+                    (Some(tx), _) => println!("Forwarded to {} (tx {})", payout.address, tx),
+                    (None, Some(e)) => eprintln!("Claimed PLM stays on the agent key: {}", e),
+                    (None, None) => {}
+                }
+            }
+            let tx_hash = record.tx_hash.as_deref().unwrap_or_default();
+            match (record.status, record.amount_plm) {
+                (core::claims::ClaimStatus::Confirmed, Some(amount)) => {
+                    println!("Claimed {:.6} PLM (tx {})", amount, tx_hash)
+                }
+                (core::claims::ClaimStatus::Confirmed, None) => {
+                    println!("Claim confirmed (tx {}); amount unknown", tx_hash)
+                }
+                _ => {
+                    return Err(core::error::CoreError::Chain(format!(
+                        "Claim transaction reverted (tx {})",
+                        tx_hash
+                    )))
+                }
+            }
+            Ok(())
+        }
+        RewardsCommand::History { limit, json } => {
+            let mut records = history.records()?;
+            let summary = core::claims::summarize(&records);
+            records.reverse();
+            records.truncate(limit);
+
+            if json {
+                let report = serde_json::json!({ "summary": summary, "records": records });
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&report)
+                        .map_err(|e| format!("Failed to serialize claim history: {}", e))?
+                );
+                return Ok(());
+            }
+
+            if records.is_empty() {
+                println!("No claims recorded yet");
+                return Ok(());
+            }
+            println!(
+                "{:<10} {:<6} {:<10} {:>14} {:>9}  TX",
+                "WHEN", "SOURCE", "STATUS", "PLM", "GAS"
+            );
+            for r in &records {
+                let time = format_age(r.at);
+                let source = match r.source {
+                    core::claims::ClaimSource::Agent => "agent",
+                    core::claims::ClaimSource::Chain => "chain",
+                };
+                let status = match r.status {
+                    core::claims::ClaimStatus::Confirmed => "confirmed",
+                    core::claims::ClaimStatus::Reverted => "reverted",
+                    core::claims::ClaimStatus::Failed => "failed",
+                };
+                println!(
+                    "{:<10} {:<6} {:<10} {:>14} {:>9}  {}",
+                    time,
+                    source,
+                    status,
+                    r.amount_plm.map(|a| format!("{:.6}", a)).unwrap_or_else(|| "-".into()),
+                    r.gas_used.map(|g| g.to_string()).unwrap_or_else(|| "-".into()),
+                    r.tx_hash.as_deref().or(r.error.as_deref()).unwrap_or("-")
+                );
+            }
+            println!(
+                "\n{} confirmed, {} failed, {:.6} PLM claimed, {} gas used",
+                summary.confirmed, summary.failed, summary.claimed_plm, summary.gas_used
+            );
+            Ok(())
+        }
+    }
+}
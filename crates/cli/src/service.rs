@@ -0,0 +1,501 @@
+//! `plumise-agent service install|uninstall|status`: run the agent as an OS service
+//! (systemd unit, launchd agent or Windows service) that restarts on failure and at boot.
+
+#[cfg(unix)]
+use std::path::Path;
+use std::path::PathBuf;
+
+use plumise_agent_core as core;
+
+#[cfg(target_os = "linux")]
+const SERVICE_NAME: &str = "plumise-agent";
+#[cfg(target_os = "macos")]
+const LAUNCHD_LABEL: &str = "com.plumise.agent";
+#[cfg(windows)]
+const WINDOWS_SERVICE_NAME: &str = "PlumiseAgent";
+
+/// Resolve the config file the service should use. The service may run as another
+/// user (root, LocalSystem), so the path is always passed explicitly.
+#[cfg(any(target_os = "linux", target_os = "macos", windows))]
+fn resolve_config_path(config: Option<PathBuf>) -> Result<PathBuf, String> {
+    let path = config.unwrap_or_else(|| core::config::default_config_dir().join("agent-config.json"));
+    if !path.exists() {
+        return Err(format!(
+            "Config not found at {}. Run `plumise-agent init` first.",
+            path.display()
+        ));
+    }
+    path.canonicalize()
+        .map_err(|e| format!("Failed to resolve config path: {}", e))
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos", windows))]
+fn current_exe() -> Result<PathBuf, String> {
+    std::env::current_exe().map_err(|e| format!("Failed to locate plumise-agent binary: {}", e))
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn run(cmd: &str, args: &[&str]) -> Result<String, String> {
+    let output = std::process::Command::new(cmd)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", cmd, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} {} failed: {}",
+            cmd,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// ---- systemd ----
+
+#[cfg(target_os = "linux")]
+fn is_root() -> bool {
+    // SAFETY: geteuid has no preconditions.
+    unsafe { libc::geteuid() == 0 }
+}
+
+/// System unit when root (starts at boot), user unit otherwise.
+#[cfg(target_os = "linux")]
+fn systemd_unit_path() -> Result<PathBuf, String> {
+    if is_root() {
+        Ok(PathBuf::from(format!("/etc/systemd/system/{}.service", SERVICE_NAME)))
+    } else {
+        let home = dirs_next::home_dir().ok_or("Cannot determine home directory")?;
+        Ok(home
+            .join(".config/systemd/user")
+            .join(format!("{}.service", SERVICE_NAME)))
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn systemctl(args: &[&str]) -> Result<String, String> {
+    let mut full = Vec::new();
+    if !is_root() {
+        full.push("--user");
+    }
+    full.extend_from_slice(args);
+    run("systemctl", &full)
+}
+
+/// `path` as a double-quoted ExecStart argument: backslashes and quotes escaped, and
+/// `%` and `$` doubled so systemd doesn't expand them as specifiers or variables.
+#[cfg(target_os = "linux")]
+fn systemd_quote(path: &Path) -> String {
+    let mut quoted = String::from("\"");
+    for c in path.display().to_string().chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '%' => quoted.push_str("%%"),
+            '$' => quoted.push_str("$$"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(target_os = "linux")]
+pub fn render_systemd_unit(exe: &Path, config: &Path) -> String {
+    format!(
+        "[Unit]\n\
+         Description=Plumise inference agent\n\
+         Wants=network-online.target\n\
+         After=network-online.target\n\
+         \n\
+         [Service]\n\
+         Type=simple\n\
+         ExecStart={} start --config {}\n\
+         Restart=on-failure\n\
+         RestartSec=10\n\
+         KillSignal=SIGINT\n\
+         TimeoutStopSec=30\n\
+         \n\
+         [Install]\n\
+         WantedBy={}\n",
+        systemd_quote(exe),
+        systemd_quote(config),
+        if is_root() { "multi-user.target" } else { "default.target" },
+    )
+}
+
+#[cfg(target_os = "linux")]
+pub fn install(config: Option<PathBuf>) -> Result<(), String> {
+    let config = resolve_config_path(config)?;
+    let unit_path = systemd_unit_path()?;
+    if let Some(dir) = unit_path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    std::fs::write(&unit_path, render_systemd_unit(&current_exe()?, &config))
+        .map_err(|e| format!("Failed to write {}: {}", unit_path.display(), e))?;
+    println!("Wrote {}", unit_path.display());
+
+    systemctl(&["daemon-reload"])?;
+    systemctl(&["enable", "--now", SERVICE_NAME])?;
+    println!("Service {} enabled and started.", SERVICE_NAME);
+    if !is_root() {
+        println!(
+            "Note: user services stop at logout. Run `sudo loginctl enable-linger {}` to keep the agent running after reboot without logging in.",
+            std::env::var("USER").unwrap_or_else(|_| "$USER".into())
+        );
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+pub fn uninstall() -> Result<(), String> {
+    let unit_path = systemd_unit_path()?;
+    if !unit_path.exists() {
+        return Err(format!("Service not installed ({} not found)", unit_path.display()));
+    }
+    let _ = systemctl(&["disable", "--now", SERVICE_NAME]);
+    std::fs::remove_file(&unit_path)
+        .map_err(|e| format!("Failed to remove {}: {}", unit_path.display(), e))?;
+    let _ = systemctl(&["daemon-reload"]);
+    println!("Service {} removed.", SERVICE_NAME);
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+pub fn status() -> Result<(), String> {
+    let unit_path = systemd_unit_path()?;
+    if !unit_path.exists() {
+        println!("Service: not installed");
+        return Ok(());
+    }
+    let active = systemctl(&["is-active", SERVICE_NAME])
+        .unwrap_or_else(|_| "inactive".into());
+    let enabled = systemctl(&["is-enabled", SERVICE_NAME])
+        .unwrap_or_else(|_| "disabled".into());
+    println!("Service: {} ({})", SERVICE_NAME, unit_path.display());
+    println!("State: {}", active.trim());
+    println!("Start at boot: {}", enabled.trim());
+    Ok(())
+}
+
+// ---- launchd ----
+
+#[cfg(target_os = "macos")]
+fn plist_path() -> Result<PathBuf, String> {
+    let home = dirs_next::home_dir().ok_or("Cannot determine home directory")?;
+    Ok(home
+        .join("Library/LaunchAgents")
+        .join(format!("{}.plist", LAUNCHD_LABEL)))
+}
+
+/// `path` escaped for a plist `<string>`.
+#[cfg(target_os = "macos")]
+fn xml_escape(path: &Path) -> String {
+    path.display()
+        .to_string()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(target_os = "macos")]
+pub fn render_launchd_plist(exe: &Path, config: &Path, log_dir: &Path) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{exe}</string>
+        <string>start</string>
+        <string>--config</string>
+        <string>{config}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>ThrottleInterval</key>
+    <integer>10</integer>
+    <key>StandardOutPath</key>
+    <string>{log}/agent.out.log</string>
+    <key>StandardErrorPath</key>
+    <string>{log}/agent.err.log</string>
+</dict>
+</plist>
+"#,
+        label = LAUNCHD_LABEL,
+        exe = xml_escape(exe),
+        config = xml_escape(config),
+        log = xml_escape(log_dir),
+    )
+}
+
+#[cfg(target_os = "macos")]
+pub fn install(config: Option<PathBuf>) -> Result<(), String> {
+    let config = resolve_config_path(config)?;
+    let plist = plist_path()?;
+    if let Some(dir) = plist.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let log_dir = config
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(core::config::default_config_dir);
+    std::fs::write(&plist, render_launchd_plist(&current_exe()?, &config, &log_dir))
+        .map_err(|e| format!("Failed to write {}: {}", plist.display(), e))?;
+    println!("Wrote {}", plist.display());
+
+    let plist_str = plist.to_string_lossy().to_string();
+    // Reload if a previous version is loaded
+    let _ = run("launchctl", &["unload", &plist_str]);
+    run("launchctl", &["load", "-w", &plist_str])?;
+    println!("Launch agent {} loaded (starts at login, restarts on failure).", LAUNCHD_LABEL);
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+pub fn uninstall() -> Result<(), String> {
+    let plist = plist_path()?;
+    if !plist.exists() {
+        return Err(format!("Service not installed ({} not found)", plist.display()));
+    }
+    let _ = run("launchctl", &["unload", "-w", &plist.to_string_lossy()]);
+    std::fs::remove_file(&plist).map_err(|e| format!("Failed to remove {}: {}", plist.display(), e))?;
+    println!("Launch agent {} removed.", LAUNCHD_LABEL);
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+pub fn status() -> Result<(), String> {
+    let plist = plist_path()?;
+    if !plist.exists() {
+        println!("Service: not installed");
+        return Ok(());
+    }
+    println!("Service: {} ({})", LAUNCHD_LABEL, plist.display());
+    match run("launchctl", &["list", LAUNCHD_LABEL]) {
+        Ok(out) => {
+            let pid = out
+                .lines()
+                .find(|l| l.contains("\"PID\""))
+                .and_then(|l| l.split('=').nth(1))
+                .map(|s| s.trim().trim_end_matches(';').to_string());
+            match pid {
+                Some(pid) => println!("State: running (PID {})", pid),
+                None => println!("State: loaded, not running"),
+            }
+        }
+        Err(_) => println!("State: not loaded"),
+    }
+    Ok(())
+}
+
+// ---- Windows service ----
+
+#[cfg(windows)]
+pub fn install(config: Option<PathBuf>) -> Result<(), String> {
+    use std::ffi::OsString;
+    use std::time::Duration;
+    use windows_service::service::{
+        ServiceAccess, ServiceAction, ServiceActionType, ServiceErrorControl, ServiceFailureActions,
+        ServiceFailureResetPeriod, ServiceInfo, ServiceStartType, ServiceType,
+    };
+    use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+
+    let config = resolve_config_path(config)?;
+    let manager = ServiceManager::local_computer(
+        None::<&str>,
+        ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+    )
+    .map_err(|e| format!("Failed to open service manager (run as Administrator): {}", e))?;
+
+    let info = ServiceInfo {
+        name: OsString::from(WINDOWS_SERVICE_NAME),
+        display_name: OsString::from("Plumise Agent"),
+        service_type: ServiceType::OWN_PROCESS,
+        start_type: ServiceStartType::AutoStart,
+        error_control: ServiceErrorControl::Normal,
+        executable_path: current_exe()?,
+        launch_arguments: vec![
+            OsString::from("service"),
+            OsString::from("run"),
+            OsString::from("--config"),
+            config.into_os_string(),
+        ],
+        dependencies: vec![],
+        account_name: None, // LocalSystem
+        account_password: None,
+    };
+    let service = manager
+        .create_service(&info, ServiceAccess::CHANGE_CONFIG | ServiceAccess::START)
+        .map_err(|e| format!("Failed to create service: {}", e))?;
+    let _ = service.set_description("Plumise distributed inference agent");
+
+    let restart = ServiceAction {
+        action_type: ServiceActionType::Restart,
+        delay: Duration::from_secs(10),
+    };
+    service
+        .update_failure_actions(ServiceFailureActions {
+            reset_period: ServiceFailureResetPeriod::After(Duration::from_secs(24 * 3600)),
+            reboot_msg: None,
+            command: None,
+            actions: Some(vec![restart.clone(), restart.clone(), restart]),
+        })
+        .map_err(|e| format!("Failed to set restart policy: {}", e))?;
+    let _ = service.set_failure_actions_on_non_crash_failures(true);
+
+    service
+        .start::<&str>(&[])
+        .map_err(|e| format!("Service created but failed to start: {}", e))?;
+    println!("Windows service {} installed and started.", WINDOWS_SERVICE_NAME);
+    Ok(())
+}
+
+#[cfg(windows)]
+pub fn uninstall() -> Result<(), String> {
+    use windows_service::service::{ServiceAccess, ServiceState};
+    use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+        .map_err(|e| format!("Failed to open service manager (run as Administrator): {}", e))?;
+    let service = manager
+        .open_service(
+            WINDOWS_SERVICE_NAME,
+            ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE,
+        )
+        .map_err(|e| format!("Service not installed: {}", e))?;
+
+    if let Ok(status) = service.query_status() {
+        if status.current_state != ServiceState::Stopped {
+            let _ = service.stop();
+        }
+    }
+    service
+        .delete()
+        .map_err(|e| format!("Failed to delete service: {}", e))?;
+    println!("Windows service {} removed.", WINDOWS_SERVICE_NAME);
+    Ok(())
+}
+
+#[cfg(windows)]
+pub fn status() -> Result<(), String> {
+    use windows_service::service::ServiceAccess;
+    use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+        .map_err(|e| format!("Failed to open service manager: {}", e))?;
+    match manager.open_service(WINDOWS_SERVICE_NAME, ServiceAccess::QUERY_STATUS) {
+        Ok(service) => {
+            let status = service
+                .query_status()
+                .map_err(|e| format!("Failed to query service: {}", e))?;
+            println!("Service: {}", WINDOWS_SERVICE_NAME);
+            println!("State: {:?}", status.current_state);
+        }
+        Err(_) => println!("Service: not installed"),
+    }
+    Ok(())
+}
+
+/// Entry point when started by the Service Control Manager (`service run`).
+#[cfg(windows)]
+pub fn run_windows_service(config: Option<PathBuf>) -> Result<(), String> {
+    *SERVICE_CONFIG.lock().unwrap_or_else(|e| e.into_inner()) = config;
+    windows_service::service_dispatcher::start(WINDOWS_SERVICE_NAME, ffi_service_main)
+        .map_err(|e| format!("Service dispatcher failed: {}", e))
+}
+
+#[cfg(windows)]
+static SERVICE_CONFIG: std::sync::Mutex<Option<PathBuf>> = std::sync::Mutex::new(None);
+
+#[cfg(windows)]
+windows_service::define_windows_service!(ffi_service_main, windows_service_main);
+
+#[cfg(windows)]
+fn windows_service_main(_args: Vec<std::ffi::OsString>) {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use windows_service::service::{
+        ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceState, ServiceStatus,
+        ServiceType,
+    };
+    use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let shutdown_flag = shutdown.clone();
+    let handler = move |control| match control {
+        ServiceControl::Stop | ServiceControl::Shutdown => {
+            shutdown_flag.store(true, Ordering::SeqCst);
+            ServiceControlHandlerResult::NoError
+        }
+        ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+        _ => ServiceControlHandlerResult::NotImplemented,
+    };
+    let status_handle = match service_control_handler::register(WINDOWS_SERVICE_NAME, handler) {
+        Ok(h) => h,
+        Err(e) => {
+            log::error!("Failed to register service control handler: {}", e);
+            return;
+        }
+    };
+
+    let set_state = |state: ServiceState, exit_code: u32| {
+        let _ = status_handle.set_service_status(ServiceStatus {
+            service_type: ServiceType::OWN_PROCESS,
+            current_state: state,
+            controls_accepted: if state == ServiceState::Running {
+                ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN
+            } else {
+                ServiceControlAccept::empty()
+            },
+            exit_code: ServiceExitCode::Win32(exit_code),
+            checkpoint: 0,
+            wait_hint: Duration::from_secs(30),
+            process_id: None,
+        });
+    };
+    set_state(ServiceState::Running, 0);
+
+    let config = SERVICE_CONFIG.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let result = tokio::runtime::Runtime::new()
+        .map_err(|e| format!("Failed to start runtime: {}", e))
+        .and_then(|rt| {
+            rt.block_on(crate::cmd_start(config, None, None, None, None, shutdown))
+                .map_err(String::from)
+        });
+
+    match result {
+        Ok(()) => set_state(ServiceState::Stopped, 0),
+        Err(e) => {
+            log::error!("{}", e);
+            // Non-zero exit makes the SCM apply the restart-on-failure actions
+            set_state(ServiceState::Stopped, 1);
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn install(_config: Option<PathBuf>) -> Result<(), String> {
+    Err("Service installation is not supported on this platform".into())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn uninstall() -> Result<(), String> {
+    Err("Service installation is not supported on this platform".into())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn status() -> Result<(), String> {
+    Err("Service installation is not supported on this platform".into())
+}
@@ -0,0 +1,350 @@
+//! `plumise-agent top`: live terminal dashboard for headless nodes, reading the same
+//! sources as `status` (state file, llama-server metrics, agent.log) once a second.
+
+use std::collections::VecDeque;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use plumise_agent_core as core;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Gauge, Paragraph, Sparkline};
+use ratatui::Frame;
+
+/// tok/s samples kept for the sparkline (one per refresh).
+const HISTORY_LEN: usize = 240;
+const LOG_LINES: usize = 200;
+/// nvidia-smi and friends are slow; sample GPUs less often than the rest.
+const GPU_SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+struct Snapshot {
+    state: Option<core::runtime_state::RuntimeState>,
+    alive: bool,
+    healthy: bool,
+    metrics: Option<core::inference::metrics::InferenceMetrics>,
+    parallel_slots: u32,
+    tps_history: VecDeque<u64>,
+    cpu_percent: f32,
+    ram_used: u64,
+    ram_total: u64,
+    gpu_percent: Option<f32>,
+    gpu_temp: Option<f32>,
+    log: Vec<String>,
+}
+
+pub async fn run(port: Option<u16>) -> Result<(), String> {
+    let config_dir = core::config::default_config_dir();
+    let parallel_slots = core::config::load_config(&config_dir)
+        .map(|c| c.parallel_slots)
+        .unwrap_or(1);
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(2))
+        .build()
+        .map_err(|e| format!("HTTP client error: {}", e))?;
+
+    let mut snapshot = Snapshot {
+        parallel_slots,
+        ..Default::default()
+    };
+    let mut sys = sysinfo::System::new();
+    let mut last_gpu_sample: Option<Instant> = None;
+
+    let mut terminal =
+        ratatui::try_init().map_err(|e| format!("Failed to open terminal: {}", e))?;
+    let result = loop {
+        refresh(&mut snapshot, &config_dir, port, &client, &mut sys).await;
+        if last_gpu_sample.is_none_or(|t| t.elapsed() >= GPU_SAMPLE_INTERVAL) {
+            let gpus = tokio::task::spawn_blocking(core::system::gpu_telemetry)
+                .await
+                .unwrap_or_default();
+            (snapshot.gpu_percent, snapshot.gpu_temp) =
+                core::system::summarize_gpu_telemetry(&gpus);
+            last_gpu_sample = Some(Instant::now());
+        }
+
+        if let Err(e) = terminal.draw(|frame| draw(frame, &snapshot)) {
+            break Err(format!("Failed to draw: {}", e));
+        }
+        match wait_for_quit(Duration::from_secs(1)) {
+            Ok(true) => break Ok(()),
+            Ok(false) => {}
+            Err(e) => break Err(e),
+        }
+    };
+    ratatui::restore();
+    result
+}
+
+/// Wait up to `timeout` for input; true when the user asked to quit.
+fn wait_for_quit(timeout: Duration) -> Result<bool, String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if !event::poll(remaining).map_err(|e| format!("Terminal input error: {}", e))? {
+            return Ok(false);
+        }
+        if let Event::Key(key) =
+            event::read().map_err(|e| format!("Terminal input error: {}", e))?
+        {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let ctrl_c =
+                key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+            if ctrl_c || matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                return Ok(true);
+            }
+        }
+    }
+}
+
+async fn refresh(
+    snapshot: &mut Snapshot,
+    config_dir: &Path,
+    port: Option<u16>,
+    client: &reqwest::Client,
+    sys: &mut sysinfo::System,
+) {
+    let pid = std::fs::read_to_string(config_dir.join("agent.pid"))
+        .ok()
+        .and_then(|s| s.trim().parse::<u32>().ok());
+    snapshot.alive = pid.is_some_and(core::system::is_pid_alive);
+    snapshot.state = if snapshot.alive {
+        core::runtime_state::RuntimeState::load(config_dir)
+    } else {
+        None
+    };
+    let port = port
+        .or_else(|| snapshot.state.as_ref().map(|s| s.http_port))
+        .unwrap_or(18920);
+    core::process::set_api_key(
+        snapshot
+            .state
+            .as_ref()
+            .and_then(|s| s.llama_api_key.clone()),
+    );
+
+    snapshot.healthy = client
+        .get(format!("http://127.0.0.1:{}/health", port))
+        .send()
+        .await
+        .map(|r| r.status().is_success())
+        .unwrap_or(false);
+    snapshot.metrics = if snapshot.healthy {
+        core::inference::metrics::fetch_metrics(client, port)
+            .await
+            .ok()
+    } else {
+        None
+    };
+    let tps = snapshot.metrics.as_ref().map(|m| m.tps).unwrap_or(0.0);
+    if snapshot.tps_history.len() == HISTORY_LEN {
+        snapshot.tps_history.pop_front();
+    }
+    snapshot.tps_history.push_back(tps.round() as u64);
+
+    sys.refresh_cpu_usage();
+    sys.refresh_memory();
+    snapshot.cpu_percent = sys.global_cpu_usage();
+    snapshot.ram_used = sys.used_memory();
+    snapshot.ram_total = sys.total_memory();
+
+    snapshot.log = tail_lines(&config_dir.join("agent.log"), LOG_LINES);
+}
+
+/// Last `n` lines of a file, reading only its end.
+fn tail_lines(path: &Path, n: usize) -> Vec<String> {
+    let Ok(mut file) = std::fs::File::open(path) else {
+        return Vec::new();
+    };
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let start = len.saturating_sub(64 * 1024);
+    if file.seek(SeekFrom::Start(start)).is_err() {
+        return Vec::new();
+    }
+    let mut buf = Vec::new();
+    let _ = file.read_to_end(&mut buf);
+    let text = String::from_utf8_lossy(&buf);
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    if start > 0 && !lines.is_empty() {
+        lines.remove(0); // partial line
+    }
+    let skip = lines.len().saturating_sub(n);
+    lines.split_off(skip)
+}
+
+fn draw(frame: &mut Frame, s: &Snapshot) {
+    let [header, spark, gauges, logs, footer] = Layout::vertical([
+        Constraint::Length(4),
+        Constraint::Length(6),
+        Constraint::Length(3),
+        Constraint::Min(4),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    frame.render_widget(status_panel(s), header);
+
+    let current = s.tps_history.back().copied().unwrap_or(0);
+    let data: Vec<u64> = s.tps_history.iter().copied().collect();
+    let width = spark.width.saturating_sub(2) as usize;
+    let visible = &data[data.len().saturating_sub(width)..];
+    frame.render_widget(
+        Sparkline::default()
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!(" Throughput: {} tok/s ", current)),
+            )
+            .data(visible)
+            .style(Style::default().fg(Color::Green)),
+        spark,
+    );
+
+    let [slots, cpu, ram, gpu] = Layout::horizontal([Constraint::Ratio(1, 4); 4]).areas(gauges);
+    let active = s.metrics.as_ref().map(|m| m.slots_processing).unwrap_or(0);
+    let total = s.parallel_slots.max(1) as u64;
+    frame.render_widget(
+        gauge(
+            "Slots",
+            ratio(active as f64, total as f64),
+            format!("{}/{}", active, total),
+        ),
+        slots,
+    );
+    frame.render_widget(
+        gauge(
+            "CPU",
+            s.cpu_percent as f64 / 100.0,
+            format!("{:.0}%", s.cpu_percent),
+        ),
+        cpu,
+    );
+    frame.render_widget(
+        gauge(
+            "RAM",
+            ratio(s.ram_used as f64, s.ram_total as f64),
+            format!(
+                "{:.1}/{:.1} GB",
+                s.ram_used as f64 / 1e9,
+                s.ram_total as f64 / 1e9
+            ),
+        ),
+        ram,
+    );
+    let gpu_label = match (s.gpu_percent, s.gpu_temp) {
+        (Some(util), Some(temp)) => format!("{:.0}% {:.0}°C", util, temp),
+        (Some(util), None) => format!("{:.0}%", util),
+        _ => "n/a".to_string(),
+    };
+    frame.render_widget(
+        gauge(
+            "GPU",
+            s.gpu_percent.unwrap_or(0.0) as f64 / 100.0,
+            gpu_label,
+        ),
+        gpu,
+    );
+
+    let height = logs.height.saturating_sub(2) as usize;
+    let lines: Vec<Line> = s.log[s.log.len().saturating_sub(height)..]
+        .iter()
+        .map(|line| {
+            let color = match core::system::parse_log_level(line) {
+                "ERROR" => Color::Red,
+                "WARNING" => Color::Yellow,
+                _ => Color::Reset,
+            };
+            Line::styled(line.clone(), Style::default().fg(color))
+        })
+        .collect();
+    let log_title = if s.log.is_empty() {
+        " Log (agent.log is written by `start --detach` and the service) "
+    } else {
+        " Log "
+    };
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(log_title)),
+        logs,
+    );
+
+    frame.render_widget(Line::from(" q: quit").dim(), footer);
+}
+
+fn status_panel(s: &Snapshot) -> Paragraph<'static> {
+    let (label, color) = match (s.alive, s.healthy, &s.state) {
+        (_, true, Some(state)) if state.registered => ("running", Color::Green),
+        (_, true, _) => ("degraded", Color::Yellow),
+        (true, false, _) => ("starting", Color::Yellow),
+        (false, false, _) => ("stopped", Color::Red),
+    };
+    let mut first = vec![
+        Span::raw("Status: "),
+        Span::styled(label, Style::default().fg(color).bold()),
+    ];
+    let mut second = Vec::new();
+    if let Some(state) = &s.state {
+        first.push(Span::raw(format!(
+            "   Model: {}   Mode: {}",
+            state.model, state.node_mode
+        )));
+        let oracle = if state.availability.deregistered {
+            "deregistered"
+        } else if state.registered {
+            "registered"
+        } else {
+            "not registered"
+        };
+        let (relay, relay_color) = if state.relay_connected {
+            ("connected", Color::Green)
+        } else {
+            ("disconnected", Color::Red)
+        };
+        second.push(Span::raw(format!("Oracle: {}   Relay: ", oracle)));
+        second.push(Span::styled(relay, Style::default().fg(relay_color)));
+        if !state.availability.accepting {
+            let reasons: Vec<String> = state
+                .availability
+                .reasons
+                .iter()
+                .map(|r| format!("{} ({})", r.reason, r.source))
+                .collect();
+            second.push(Span::styled(
+                format!("   Paused: {}", reasons.join(", ")),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+    }
+    if let Some(m) = &s.metrics {
+        second.push(Span::raw(format!("   Tokens: {}", m.total_tokens)));
+    }
+    Paragraph::new(vec![Line::from(first), Line::from(second)]).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" plumise-agent "),
+    )
+}
+
+fn gauge(title: &str, ratio: f64, label: String) -> Gauge<'static> {
+    Gauge::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" {} ", title)),
+        )
+        .gauge_style(Style::default().fg(Color::Cyan))
+        .ratio(ratio.clamp(0.0, 1.0))
+        .label(label)
+}
+
+fn ratio(used: f64, total: f64) -> f64 {
+    if total > 0.0 {
+        used / total
+    } else {
+        0.0
+    }
+}
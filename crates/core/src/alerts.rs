@@ -0,0 +1,192 @@
+//! Telegram and Discord messages for the `events` an operator wants on their
+//! phone, e.g. the node going down. Each event kind is enabled separately and
+//! rate limited, so a flapping node doesn't flood the chat.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::config::AlertsConfig;
+use crate::events::AgentEvent;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+impl AlertsConfig {
+    fn telegram(&self) -> Option<(&str, &str)> {
+        let token = self.telegram_bot_token.trim();
+        (!token.is_empty()).then(|| (token, self.telegram_chat_id.trim()))
+    }
+
+    fn discord(&self) -> Option<&str> {
+        Some(self.discord_webhook_url.trim()).filter(|url| !url.is_empty())
+    }
+
+    /// Whether a backend is configured.
+    pub fn enabled(&self) -> bool {
+        self.telegram().is_some() || self.discord().is_some()
+    }
+
+    /// The per-kind flag for `event`.
+    pub fn wants(&self, event: &AgentEvent) -> bool {
+        match event {
+            AgentEvent::Started { .. } => self.agent_started,
+            AgentEvent::Stopped { .. } => self.agent_stopped,
+            AgentEvent::Crashed { .. } => self.agent_crashed,
+            AgentEvent::RegistrationFailed { .. } => self.registration_failed,
+            AgentEvent::RewardClaimed { .. } => self.reward_claimed,
+            AgentEvent::DownloadCompleted { .. } => self.download_completed,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some((_, chat_id)) = self.telegram() {
+            if chat_id.is_empty() {
+                return Err("alerts.telegramChatId is required with a Telegram bot token".to_string());
+            }
+        }
+        if let Some(url) = self.discord() {
+            if !url.starts_with("https://") {
+                return Err(format!("Invalid Discord webhook URL '{}': expected https://", url));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Per event kind: at most one message per `interval`, counting what was held back.
+pub struct RateLimiter {
+    interval: Duration,
+    /// Last send and the number suppressed since, by event kind.
+    state: HashMap<&'static str, (Instant, u32)>,
+}
+
+impl RateLimiter {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            state: HashMap::new(),
+        }
+    }
+
+    /// Some(number suppressed since the last send) if `kind` may be sent now.
+    pub fn allow(&mut self, kind: &'static str) -> Option<u32> {
+        let now = Instant::now();
+        match self.state.get_mut(kind) {
+            Some((last, suppressed)) if now.duration_since(*last) < self.interval => {
+                *suppressed += 1;
+                None
+            }
+            Some((last, suppressed)) => {
+                let held_back = *suppressed;
+                *last = now;
+                *suppressed = 0;
+                Some(held_back)
+            }
+            None => {
+                self.state.insert(kind, (now, 0));
+                Some(0)
+            }
+        }
+    }
+}
+
+/// Message text: who, what, and how many similar ones were held back.
+fn format_message(node: &str, event: &AgentEvent, suppressed: u32) -> String {
+    let short = if node.len() > 12 {
+        format!("{}…{}", &node[..6], &node[node.len() - 4..])
+    } else {
+        node.to_string()
+    };
+    let mut text = format!("[{}] {}: {}", short, event.title(), event.message());
+    if suppressed > 0 {
+        text.push_str(&format!(" (+{} similar suppressed)", suppressed));
+    }
+    text
+}
+
+async fn post(client: &reqwest::Client, url: &str, body: serde_json::Value) -> Result<(), String> {
+    let resp = client
+        .post(url)
+        .json(&body)
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| e.without_url().to_string())?;
+    if resp.status().is_success() {
+        Ok(())
+    } else {
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        Err(format!("HTTP {}: {}", status, text.chars().take(200).collect::<String>()))
+    }
+}
+
+/// Send `text` to every configured backend.
+pub async fn send(client: &reqwest::Client, config: &AlertsConfig, text: &str) -> Result<(), String> {
+    let mut errors = Vec::new();
+    if let Some((token, chat_id)) = config.telegram() {
+        let url = format!("https://api.telegram.org/bot{}/sendMessage", token);
+        let body = serde_json::json!({ "chat_id": chat_id, "text": text });
+        // The URL carries the token, so errors never include it
+        if let Err(e) = post(client, &url, body).await {
+            errors.push(format!("Telegram: {}", e));
+        }
+    }
+    if let Some(url) = config.discord() {
+        let body = serde_json::json!({ "content": text });
+        if let Err(e) = post(client, url, body).await {
+            errors.push(format!("Discord: {}", e));
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+/// Send the enabled events to Telegram and/or Discord. None when no backend is
+/// configured.
+pub fn start_alerts(config: AlertsConfig, node: String) -> Option<tokio::task::JoinHandle<()>> {
+    if !config.enabled() {
+        return None;
+    }
+    log::info!(
+        "Alerts enabled ({})",
+        [config.telegram().map(|_| "Telegram"), config.discord().map(|_| "Discord")]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let mut events = crate::events::subscribe();
+    Some(tokio::spawn(async move {
+        let client = crate::http::client();
+        let mut limiter = RateLimiter::new(Duration::from_secs(config.min_interval_secs));
+        let config = std::sync::Arc::new(config);
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event.event,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                    log::warn!("Alerts fell behind; {} event(s) not sent", n);
+                    continue;
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            };
+            if !config.wants(&event) {
+                continue;
+            }
+            let Some(suppressed) = limiter.allow(event.kind()) else {
+                log::debug!("Alert for {} rate limited", event.kind());
+                continue;
+            };
+            let text = format_message(&node, &event, suppressed);
+            let client = client.clone();
+            let config = config.clone();
+            crate::events::spawn_delivery(async move {
+                if let Err(e) = send(&client, &config, &text).await {
+                    log::warn!("Alert delivery failed: {}", e);
+                }
+            });
+        }
+    }))
+}
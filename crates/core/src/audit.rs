@@ -0,0 +1,214 @@
+//! Optional audit log of relay requests: `<config_dir>/audit/audit.jsonl`, one JSON
+//! object per line. The active file is rotated when it outgrows `audit_log_max_mb`
+//! or is a day old; rotated files older than `audit_log_max_days` are deleted.
+//! Unlike the ledger, this may contain prompt/response text (`audit_log_content`).
+
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::ledger::{unix_millis, RequestOutcome};
+
+const AUDIT_DIR_NAME: &str = "audit";
+const AUDIT_FILE_NAME: &str = "audit.jsonl";
+const MAX_SEGMENT_AGE_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// What happens to prompt and response text (`audit_log_content` in `AgentConfig`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AuditContent {
+    /// Metadata only.
+    #[default]
+    Omit,
+    /// SHA-256 of the text, to match a request later without keeping it.
+    Hash,
+    /// The text itself.
+    Store,
+}
+
+impl AuditContent {
+    pub fn from_config(mode: &str) -> Self {
+        match mode {
+            "hash" => AuditContent::Hash,
+            "store" => AuditContent::Store,
+            _ => AuditContent::Omit,
+        }
+    }
+
+    fn apply(self, text: &str) -> Option<String> {
+        match self {
+            AuditContent::Omit => None,
+            AuditContent::Hash => Some(format!("sha256:{}", hex::encode(Sha256::digest(text.as_bytes())))),
+            AuditContent::Store => Some(text.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub id: String,
+    /// "chat" | "embedding" | "rerank".
+    pub kind: String,
+    /// Unix epoch milliseconds.
+    pub started_at: u64,
+    pub duration_ms: u64,
+    pub stream: bool,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub outcome: RequestOutcome,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Request text, hash or absent depending on `AuditContent`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
+}
+
+/// A finished request as the relay sees it, before the content policy is applied.
+pub struct AuditRecord<'a> {
+    pub id: &'a str,
+    pub kind: &'a str,
+    pub started_at: u64,
+    pub stream: bool,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub outcome: RequestOutcome,
+    pub error: Option<&'a str>,
+    pub prompt: &'a str,
+    pub response: &'a str,
+}
+
+struct Segment {
+    /// When the active file got its first entry (unix ms).
+    started_at: u64,
+}
+
+pub struct AuditLog {
+    dir: PathBuf,
+    content: AuditContent,
+    max_bytes: u64,
+    max_age_days: u64,
+    segment: Mutex<Segment>,
+}
+
+impl AuditLog {
+    /// Open the audit log under `config_dir`, or None when `audit_log` is off.
+    pub fn from_config(config: &crate::config::AgentConfig, config_dir: &Path) -> Option<Self> {
+        if !config.audit_log {
+            return None;
+        }
+        match Self::open(
+            config_dir,
+            config.audit_content(),
+            config.audit_log_max_mb.max(1) * 1024 * 1024,
+            config.audit_log_max_days,
+        ) {
+            Ok(log) => Some(log),
+            Err(e) => {
+                log::warn!("Audit log disabled: {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn open(
+        config_dir: &Path,
+        content: AuditContent,
+        max_bytes: u64,
+        max_age_days: u64,
+    ) -> Result<Self, String> {
+        let dir = config_dir.join(AUDIT_DIR_NAME);
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create audit directory: {}", e))?;
+        let started_at = first_entry_time(&dir.join(AUDIT_FILE_NAME)).unwrap_or_else(unix_millis);
+        let log = Self {
+            dir,
+            content,
+            max_bytes,
+            max_age_days,
+            segment: Mutex::new(Segment { started_at }),
+        };
+        log.prune();
+        Ok(log)
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(AUDIT_FILE_NAME)
+    }
+
+    /// Append one request, rotating the file first if it is due.
+    pub fn record(&self, record: AuditRecord<'_>) -> Result<(), String> {
+        let now = unix_millis();
+        let entry = AuditEntry {
+            id: record.id.to_string(),
+            kind: record.kind.to_string(),
+            started_at: record.started_at,
+            duration_ms: now.saturating_sub(record.started_at),
+            stream: record.stream,
+            prompt_tokens: record.prompt_tokens,
+            completion_tokens: record.completion_tokens,
+            outcome: record.outcome,
+            error: record.error.map(str::to_string),
+            prompt: self.content.apply(record.prompt),
+            response: self.content.apply(record.response),
+        };
+        let mut line = serde_json::to_string(&entry)
+            .map_err(|e| format!("Failed to serialize audit entry: {}", e))?;
+        line.push('\n');
+
+        let mut segment = self.segment.lock().map_err(|_| "Audit log lock poisoned".to_string())?;
+        let path = self.path();
+        let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        if size > 0 && (size >= self.max_bytes || now.saturating_sub(segment.started_at) >= MAX_SEGMENT_AGE_MS) {
+            let rotated = self.dir.join(format!("audit-{}.jsonl", now));
+            std::fs::rename(&path, &rotated)
+                .map_err(|e| format!("Failed to rotate audit log: {}", e))?;
+            self.prune();
+        }
+        if size == 0 || !path.exists() {
+            segment.started_at = now;
+        }
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open audit log: {}", e))?;
+        file.write_all(line.as_bytes())
+            .map_err(|e| format!("Failed to write audit log: {}", e))
+    }
+
+    /// Delete rotated files older than `max_age_days` (0 keeps everything).
+    fn prune(&self) {
+        if self.max_age_days == 0 {
+            return;
+        }
+        let cutoff = unix_millis().saturating_sub(self.max_age_days * 24 * 60 * 60 * 1000);
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let rotated_at = name
+                .to_str()
+                .and_then(|n| n.strip_prefix("audit-"))
+                .and_then(|n| n.strip_suffix(".jsonl"))
+                .and_then(|ts| ts.parse::<u64>().ok());
+            if rotated_at.is_some_and(|ts| ts < cutoff) {
+                if let Err(e) = std::fs::remove_file(entry.path()) {
+                    log::warn!("Failed to delete old audit log {:?}: {}", name, e);
+                }
+            }
+        }
+    }
+}
+
+fn first_entry_time(path: &Path) -> Option<u64> {
+    let file = std::fs::File::open(path).ok()?;
+    let line = std::io::BufReader::new(file).lines().next()?.ok()?;
+    serde_json::from_str::<AuditEntry>(&line).ok().map(|e| e.started_at)
+}
@@ -0,0 +1,201 @@
+//! Whether the agent is currently accepting relay traffic.
+//!
+//! Several independent policies can pause the agent at once; it serves again
+//! only when every source has resumed. A pause can also ask for deregistration,
+//! in which case the relay disconnects and the reporter stops keeping the node
+//! alive in the Oracle.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PauseSource {
+    /// The machine is in use (input activity or foreign GPU load).
+    Idle,
+    /// Outside the configured serving hours.
+    Schedule,
+    /// Running on battery power.
+    Battery,
+    /// The rpc-server this node runs for a cluster is not accepting connections.
+    RpcServer,
+    /// The OS reports critical memory pressure.
+    MemoryPressure,
+    /// Draining in-flight requests before an app update is installed.
+    Update,
+    /// Maintenance pause requested by the operator; llama-server stays loaded.
+    Manual,
+}
+
+impl std::fmt::Display for PauseSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PauseSource::Idle => write!(f, "idle"),
+            PauseSource::Schedule => write!(f, "schedule"),
+            PauseSource::Battery => write!(f, "battery"),
+            PauseSource::RpcServer => write!(f, "rpc-server"),
+            PauseSource::MemoryPressure => write!(f, "memory-pressure"),
+            PauseSource::Update => write!(f, "update"),
+            PauseSource::Manual => write!(f, "manual"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PauseReason {
+    pub source: PauseSource,
+    pub reason: String,
+    pub deregister: bool,
+    /// Unix epoch seconds.
+    pub since: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvailabilitySnapshot {
+    pub accepting: bool,
+    pub deregistered: bool,
+    pub reasons: Vec<PauseReason>,
+}
+
+pub struct Availability {
+    pauses: Mutex<BTreeMap<PauseSource, PauseReason>>,
+    version: watch::Sender<u64>,
+}
+
+impl Default for Availability {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Availability {
+    pub fn new() -> Self {
+        let (version, _) = watch::channel(0);
+        Self {
+            pauses: Mutex::new(BTreeMap::new()),
+            version,
+        }
+    }
+
+    /// Pause on behalf of `source`. Returns true if this changed the state.
+    pub fn pause(&self, source: PauseSource, reason: impl Into<String>, deregister: bool) -> bool {
+        let reason = reason.into();
+        let changed = {
+            let mut pauses = self.pauses.lock().unwrap_or_else(|e| e.into_inner());
+            match pauses.get(&source) {
+                Some(p) if p.reason == reason && p.deregister == deregister => false,
+                _ => {
+                    let since = pauses
+                        .get(&source)
+                        .map(|p| p.since)
+                        .unwrap_or_else(unix_secs);
+                    pauses.insert(
+                        source,
+                        PauseReason {
+                            source,
+                            reason,
+                            deregister,
+                            since,
+                        },
+                    );
+                    true
+                }
+            }
+        };
+        if changed {
+            self.notify();
+        }
+        changed
+    }
+
+    /// Lift the pause held by `source`. Returns true if it was paused.
+    pub fn resume(&self, source: PauseSource) -> bool {
+        let changed = self
+            .pauses
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&source)
+            .is_some();
+        if changed {
+            self.notify();
+        }
+        changed
+    }
+
+    pub fn is_accepting(&self) -> bool {
+        self.pauses.lock().unwrap_or_else(|e| e.into_inner()).is_empty()
+    }
+
+    pub fn is_deregistered(&self) -> bool {
+        self.pauses
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .any(|p| p.deregister)
+    }
+
+    /// Human-readable reason for the current pause, if any.
+    pub fn pause_message(&self) -> Option<String> {
+        let pauses = self.pauses.lock().unwrap_or_else(|e| e.into_inner());
+        if pauses.is_empty() {
+            return None;
+        }
+        Some(
+            pauses
+                .values()
+                .map(|p| format!("{} ({})", p.reason, p.source))
+                .collect::<Vec<_>>()
+                .join("; "),
+        )
+    }
+
+    pub fn snapshot(&self) -> AvailabilitySnapshot {
+        let pauses = self.pauses.lock().unwrap_or_else(|e| e.into_inner());
+        AvailabilitySnapshot {
+            accepting: pauses.is_empty(),
+            deregistered: pauses.values().any(|p| p.deregister),
+            reasons: pauses.values().cloned().collect(),
+        }
+    }
+
+    /// Receiver that changes whenever any pause is added or lifted.
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.version.subscribe()
+    }
+
+    /// Resolves once some source requests deregistration.
+    pub async fn wait_for_deregister(&self) {
+        let mut rx = self.subscribe();
+        while !self.is_deregistered() {
+            if rx.changed().await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+
+    /// Resolves once no source requests deregistration.
+    pub async fn wait_until_registered(&self) {
+        let mut rx = self.subscribe();
+        while self.is_deregistered() {
+            if rx.changed().await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+
+    fn notify(&self) {
+        self.version.send_modify(|v| *v += 1);
+    }
+}
+
+fn unix_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
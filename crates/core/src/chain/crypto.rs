@@ -1,4 +1,5 @@
-use k256::ecdsa::SigningKey;
+pub use k256::ecdsa::SigningKey;
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
 use sha3::{Digest, Keccak256};
 
 /// Compute keccak256 hash
@@ -31,6 +32,31 @@ pub fn personal_sign(message: &str, signing_key: &SigningKey) -> Result<String,
     Ok(format!("0x{}", hex::encode(result)))
 }
 
+/// Recover the signer address (lowercase, 0x-prefixed) of a `personal_sign` signature
+pub fn recover_address(message: &str, signature: &str) -> Result<String, String> {
+    let hex_str = signature.strip_prefix("0x").unwrap_or(signature);
+    let bytes = hex::decode(hex_str).map_err(|e| format!("Invalid signature hex: {}", e))?;
+    if bytes.len() != 65 {
+        return Err(format!("Invalid signature length: {}", bytes.len()));
+    }
+    let sig = Signature::from_slice(&bytes[..64])
+        .map_err(|e| format!("Invalid signature: {}", e))?;
+    let rec_id = RecoveryId::from_byte(bytes[64].wrapping_sub(27))
+        .ok_or_else(|| format!("Invalid recovery id: {}", bytes[64]))?;
+
+    let prefix = format!("\x19Ethereum Signed Message:\n{}", message.len());
+    let mut data = Vec::new();
+    data.extend_from_slice(prefix.as_bytes());
+    data.extend_from_slice(message.as_bytes());
+    let hash = keccak256(&data);
+
+    let key = VerifyingKey::recover_from_prehash(&hash, &sig, rec_id)
+        .map_err(|e| format!("Signature recovery failed: {}", e))?;
+    let point = key.to_encoded_point(false);
+    let hash = keccak256(&point.as_bytes()[1..]);
+    Ok(format!("0x{}", hex::encode(&hash[12..])))
+}
+
 /// Derive Ethereum address from signing key (lowercase, 0x-prefixed)
 pub fn address_from_key(signing_key: &SigningKey) -> String {
     let public_key = signing_key.verifying_key();
@@ -0,0 +1,274 @@
+//! RewardPool event stream for the agent address.
+//!
+//! Subscribes to `RewardAccrued` / `RewardClaimed` logs with `eth_subscribe` over
+//! the WebSocket RPC, and falls back to `eth_getLogs` polling over HTTP when the
+//! WebSocket endpoint is missing or drops, so the UI can update without polling
+//! the pending reward itself.
+
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use super::crypto::keccak256;
+
+const REWARD_ACCRUED_SIG: &[u8] = b"RewardAccrued(address,uint256)";
+const REWARD_CLAIMED_SIG: &[u8] = b"RewardClaimed(address,uint256)";
+/// eth_getLogs interval while polling.
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+/// How long to poll before trying the WebSocket again.
+const WS_RETRY_AFTER: std::time::Duration = std::time::Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RewardEventKind {
+    Accrued,
+    Claimed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RewardEvent {
+    pub kind: RewardEventKind,
+    /// Decimal string; may exceed f64 precision.
+    pub amount_wei: String,
+    pub amount_plm: f64,
+    pub tx_hash: String,
+    pub block_number: u64,
+}
+
+/// WebSocket RPC URL for an HTTP one (https -> wss, http -> ws).
+pub fn ws_url_for(rpc_url: &str) -> String {
+    rpc_url
+        .replacen("https://", "wss://", 1)
+        .replacen("http://", "ws://", 1)
+}
+
+/// Watch RewardPool events for `agent_address`, calling `on_event` for each.
+/// Claims are also published on the `events` bus. `ws_url` None polls over HTTP only.
+pub fn start_reward_watcher<F>(
+    rpc_url: String,
+    ws_url: Option<String>,
+    agent_address: String,
+    on_event: F,
+) -> tokio::task::JoinHandle<()>
+where
+    F: Fn(RewardEvent) + Send + Sync + 'static,
+{
+    let on_event = move |event: RewardEvent| {
+        if event.kind == RewardEventKind::Claimed {
+            crate::events::publish(crate::events::AgentEvent::RewardClaimed {
+                amount_plm: event.amount_plm,
+                tx_hash: event.tx_hash.clone(),
+            });
+        }
+        on_event(event);
+    };
+    tokio::spawn(async move {
+        let client = crate::http::client();
+        let filter = log_filter(&agent_address);
+
+        loop {
+            if let Some(ws_url) = &ws_url {
+                match subscribe(ws_url, &filter, &on_event).await {
+                    Ok(()) => log::info!("Reward event subscription closed"),
+                    Err(e) => log::debug!("Reward event subscription unavailable: {}", e),
+                }
+            }
+
+            // Poll until it's time to retry the WebSocket (forever without one)
+            let started = std::time::Instant::now();
+            let mut from_block = match block_number(&client, &rpc_url).await {
+                Ok(n) => n + 1,
+                Err(e) => {
+                    log::warn!("Reward event polling failed: {}", e);
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                    continue;
+                }
+            };
+            while ws_url.is_none() || started.elapsed() < WS_RETRY_AFTER {
+                tokio::time::sleep(POLL_INTERVAL).await;
+                match poll_logs(&client, &rpc_url, &filter, from_block).await {
+                    Ok((events, next)) => {
+                        events.into_iter().for_each(&on_event);
+                        from_block = next;
+                    }
+                    Err(e) => log::debug!("Reward event poll failed: {}", e),
+                }
+            }
+        }
+    })
+}
+
+fn topic(signature: &[u8]) -> String {
+    format!("0x{}", hex::encode(keccak256(signature)))
+}
+
+/// Logs from the RewardPool with either event topic and the agent as first indexed arg.
+fn log_filter(agent_address: &str) -> serde_json::Value {
+    let addr = agent_address.strip_prefix("0x").unwrap_or(agent_address).to_lowercase();
+    serde_json::json!({
+        "address": format!("0x{}", hex::encode(super::profile::active().reward_pool_bytes())),
+        "topics": [
+            [topic(REWARD_ACCRUED_SIG), topic(REWARD_CLAIMED_SIG)],
+            format!("0x{:0>64}", addr),
+        ],
+    })
+}
+
+/// RewardPool event in an `eth_getLogs` entry or receipt log; None for anything else.
+pub(crate) fn parse_log(log: &serde_json::Value) -> Option<RewardEvent> {
+    if log["removed"].as_bool() == Some(true) {
+        return None;
+    }
+    let topic0 = log["topics"][0].as_str()?;
+    let kind = if topic0 == topic(REWARD_ACCRUED_SIG) {
+        RewardEventKind::Accrued
+    } else if topic0 == topic(REWARD_CLAIMED_SIG) {
+        RewardEventKind::Claimed
+    } else {
+        return None;
+    };
+
+    // Data is the single uint256 amount word
+    let data = log["data"].as_str().unwrap_or_default();
+    let word = data.strip_prefix("0x").unwrap_or(data);
+    if word.len() != 64 || !word.bytes().all(|b| b.is_ascii_hexdigit()) {
+        log::warn!("Skipping reward event with malformed data: {:?}", data);
+        return None;
+    }
+    let amount = match super::rewards::decode_u128(word) {
+        Ok(amount) => amount,
+        Err(e) => {
+            log::warn!("Skipping reward event: {}", e);
+            return None;
+        }
+    };
+
+    let block_hex = log["blockNumber"].as_str().unwrap_or("0x0");
+    Some(RewardEvent {
+        kind,
+        amount_wei: amount.to_string(),
+        amount_plm: amount as f64 / 1e18,
+        tx_hash: log["transactionHash"].as_str().unwrap_or_default().to_string(),
+        block_number: u64::from_str_radix(block_hex.trim_start_matches("0x"), 16).unwrap_or(0),
+    })
+}
+
+async fn subscribe<F>(ws_url: &str, filter: &serde_json::Value, on_event: &F) -> Result<(), String>
+where
+    F: Fn(RewardEvent),
+{
+    let (ws_stream, _) = connect_async(ws_url)
+        .await
+        .map_err(|e| format!("WebSocket connect failed: {}", e))?;
+    let (mut write, mut read) = ws_stream.split();
+
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eth_subscribe",
+        "params": ["logs", filter],
+        "id": 1
+    });
+    write
+        .send(Message::Text(request.to_string()))
+        .await
+        .map_err(|e| format!("Subscribe send failed: {}", e))?;
+
+    let mut subscription: Option<String> = None;
+    while let Some(msg) = read.next().await {
+        let msg = msg.map_err(|e| format!("WebSocket read error: {}", e))?;
+        let text = match msg {
+            Message::Text(text) => text,
+            Message::Ping(data) => {
+                let _ = write.send(Message::Pong(data)).await;
+                continue;
+            }
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let json: serde_json::Value = match serde_json::from_str(&text) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+
+        if json["id"] == 1 {
+            if let Some(error) = json.get("error") {
+                return Err(format!("eth_subscribe rejected: {}", error));
+            }
+            subscription = json["result"].as_str().map(str::to_string);
+            log::info!("Subscribed to reward events over {}", ws_url);
+            continue;
+        }
+
+        let params = &json["params"];
+        if json["method"] == "eth_subscription"
+            && params["subscription"].as_str() == subscription.as_deref()
+        {
+            if let Some(event) = parse_log(&params["result"]) {
+                on_event(event);
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn rpc(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    method: &str,
+    params: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let resp = client
+        .post(rpc_url)
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": 1
+        }))
+        .timeout(std::time::Duration::from_secs(15))
+        .send()
+        .await
+        .map_err(|e| format!("RPC request failed: {}", e))?;
+
+    let mut json: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("RPC response parse error: {}", e))?;
+
+    if let Some(error) = json.get("error") {
+        return Err(format!("RPC error: {}", error));
+    }
+    Ok(json["result"].take())
+}
+
+async fn block_number(client: &reqwest::Client, rpc_url: &str) -> Result<u64, String> {
+    let result = rpc(client, rpc_url, "eth_blockNumber", serde_json::json!([])).await?;
+    let hex_str = result.as_str().ok_or("Expected hex block number")?;
+    u64::from_str_radix(hex_str.trim_start_matches("0x"), 16)
+        .map_err(|e| format!("Invalid block number: {}", e))
+}
+
+/// Events in `from_block..=latest`, plus the next block to poll from.
+async fn poll_logs(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    filter: &serde_json::Value,
+    from_block: u64,
+) -> Result<(Vec<RewardEvent>, u64), String> {
+    let latest = block_number(client, rpc_url).await?;
+    if latest < from_block {
+        return Ok((Vec::new(), from_block));
+    }
+
+    let mut range = filter.clone();
+    range["fromBlock"] = format!("0x{:x}", from_block).into();
+    range["toBlock"] = format!("0x{:x}", latest).into();
+    let logs = rpc(client, rpc_url, "eth_getLogs", serde_json::json!([range])).await?;
+
+    let events = logs
+        .as_array()
+        .map(|logs| logs.iter().filter_map(parse_log).collect())
+        .unwrap_or_default();
+    Ok((events, latest + 1))
+}
@@ -1,4 +1,7 @@
 pub mod crypto;
+pub mod events;
+pub mod nonce;
 pub mod precompiles;
+pub mod profile;
 pub mod rewards;
 pub mod tx;
@@ -0,0 +1,88 @@
+//! Local nonce tracking, so concurrent transactions from one key (claims,
+//! heartbeats, proof roots) don't reuse a nonce fetched from the node.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, OnceLock};
+
+use tokio::sync::Mutex;
+
+/// Nonces handed out for one address.
+#[derive(Default)]
+pub struct NonceManager {
+    state: Mutex<NonceState>,
+}
+
+#[derive(Default)]
+struct NonceState {
+    /// Next nonce to hand out. `None` until synced with the node.
+    next: Option<u64>,
+    /// Reserved but neither broadcast (`sent`) nor given back (`release`) yet.
+    outstanding: HashSet<u64>,
+}
+
+impl NonceManager {
+    /// The process-wide manager for `address`. Every `TxSender` for the same key
+    /// shares it, wherever it was created.
+    pub fn for_address(address: &str) -> Arc<NonceManager> {
+        static MANAGERS: OnceLock<std::sync::Mutex<HashMap<String, Arc<NonceManager>>>> =
+            OnceLock::new();
+        let mut managers = MANAGERS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        managers
+            .entry(address.to_lowercase())
+            .or_default()
+            .clone()
+    }
+
+    /// Reserve the next nonce. `chain_pending` is the node's pending transaction
+    /// count; the local counter wins when it is ahead while other reservations are
+    /// still outstanding (our own txs not yet seen). With none outstanding the node's
+    /// count is used, so a nonce that was never broadcast doesn't leave a gap.
+    pub async fn reserve<F, Fut>(&self, chain_pending: F) -> Result<u64, String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<u64, String>>,
+    {
+        let mut state = self.state.lock().await;
+        let on_chain = chain_pending().await?;
+        let nonce = match state.next {
+            Some(local) if local > on_chain && state.outstanding.is_empty() => {
+                log::debug!(
+                    "Local nonce {} is ahead of the node's {} with nothing in flight, using the node's",
+                    local,
+                    on_chain
+                );
+                on_chain
+            }
+            Some(local) => local.max(on_chain),
+            None => on_chain,
+        };
+        state.next = Some(nonce + 1);
+        state.outstanding.insert(nonce);
+        Ok(nonce)
+    }
+
+    /// A transaction with `nonce` reached the node; the node's pending count covers it now.
+    pub async fn sent(&self, nonce: u64) {
+        self.state.lock().await.outstanding.remove(&nonce);
+    }
+
+    /// Give back a nonce whose transaction never reached the node. It is handed out
+    /// again if it is still the latest one (otherwise the gap is filled by the next
+    /// `reserve` once nothing else is outstanding).
+    pub async fn release(&self, nonce: u64) {
+        let mut state = self.state.lock().await;
+        state.outstanding.remove(&nonce);
+        if state.next == Some(nonce + 1) {
+            state.next = Some(nonce);
+        }
+    }
+
+    /// Forget the local counter, e.g. after "nonce too low"; the next `reserve`
+    /// starts from the node's count again.
+    pub async fn resync(&self) {
+        self.state.lock().await.next = None;
+    }
+}
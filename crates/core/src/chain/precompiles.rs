@@ -1,13 +1,6 @@
+use super::profile;
 use super::tx::TxSender;
-
-const fn precompile_addr(id: u8) -> [u8; 20] {
-    let mut addr = [0u8; 20];
-    addr[19] = id;
-    addr
-}
-
-const PRECOMPILE_REGISTER: [u8; 20] = precompile_addr(0x21);
-const PRECOMPILE_HEARTBEAT: [u8; 20] = precompile_addr(0x22);
+use crate::error::{CoreError, CoreResult};
 
 /// Register agent on-chain via precompile 0x21
 /// Input: name(32B) + modelHash(32B) + capCount(32B)
@@ -15,7 +8,7 @@ pub async fn register_agent(
     tx_sender: &TxSender,
     name: &str,
     model_hash: [u8; 32],
-) -> Result<String, String> {
+) -> CoreResult<String> {
     let mut name_bytes = [0u8; 32];
     let name_raw = name.as_bytes();
     let copy_len = name_raw.len().min(32);
@@ -28,24 +21,58 @@ pub async fn register_agent(
     data.extend_from_slice(&model_hash);
     data.extend_from_slice(&cap_count);
 
-    let tx_hash = tx_sender.send_tx(PRECOMPILE_REGISTER, data, 300_000).await?;
-    let success = tx_sender.wait_receipt(&tx_hash, 30).await?;
+    let precompile = profile::active().register_precompile_bytes();
+    let gas = tx_sender.gas_limit(precompile, &data, 300_000).await;
+    let (tx_hash, success) = tx_sender
+        .send_and_confirm(precompile, data, gas, 30)
+        .await?;
     if success {
         log::info!("Agent registration tx succeeded: {}", tx_hash);
         Ok(tx_hash)
     } else {
-        Err("Registration transaction failed (status=0)".to_string())
+        Err(CoreError::Chain("Registration transaction failed (status=0)".to_string()))
     }
 }
 
 /// Send heartbeat via precompile 0x22 (empty data, uses msg.sender)
-pub async fn heartbeat(tx_sender: &TxSender) -> Result<String, String> {
-    let tx_hash = tx_sender.send_tx(PRECOMPILE_HEARTBEAT, vec![], 100_000).await?;
-    let success = tx_sender.wait_receipt(&tx_hash, 30).await?;
+pub async fn heartbeat(tx_sender: &TxSender) -> CoreResult<String> {
+    let precompile = profile::active().heartbeat_precompile_bytes();
+    let gas = tx_sender.gas_limit(precompile, &[], 100_000).await;
+    let (tx_hash, success) = tx_sender
+        .send_and_confirm(precompile, vec![], gas, 30)
+        .await?;
     if success {
         log::debug!("Heartbeat tx succeeded: {}", tx_hash);
         Ok(tx_hash)
     } else {
-        Err("Heartbeat transaction failed (status=0)".to_string())
+        Err(CoreError::Chain("Heartbeat transaction failed (status=0)".to_string()))
+    }
+}
+
+/// Submit a batched inference proof root via precompile 0x23
+/// Input: merkleRoot(32B) + leafCount(32B) + tokenCount(32B)
+pub async fn submit_proof_root(
+    tx_sender: &TxSender,
+    root: [u8; 32],
+    leaf_count: u64,
+    token_count: u64,
+) -> CoreResult<String> {
+    let mut data = Vec::with_capacity(96);
+    data.extend_from_slice(&root);
+    data.extend_from_slice(&[0u8; 24]);
+    data.extend_from_slice(&leaf_count.to_be_bytes());
+    data.extend_from_slice(&[0u8; 24]);
+    data.extend_from_slice(&token_count.to_be_bytes());
+
+    let precompile = profile::active().proof_root_precompile_bytes();
+    let gas = tx_sender.gas_limit(precompile, &data, 150_000).await;
+    let (tx_hash, success) = tx_sender
+        .send_and_confirm(precompile, data, gas, 30)
+        .await?;
+    if success {
+        log::debug!("Proof root tx succeeded: {}", tx_hash);
+        Ok(tx_hash)
+    } else {
+        Err(CoreError::Chain("Proof root transaction failed (status=0)".to_string()))
     }
 }
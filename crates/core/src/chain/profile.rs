@@ -0,0 +1,177 @@
+//! Network parameters (`chain` in `AgentConfig`): chain id and the system contract
+//! addresses. The only preset is mainnet, whose chain id is learned from the RPC's
+//! `eth_chainId`, as the agent always did. Any other network must set every field,
+//! chain id included, and an RPC serving a different chain stops the agent.
+
+use std::sync::{OnceLock, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+/// Mainnet system contracts (the genesis addresses the agent has always used).
+const REWARD_POOL: &str = "0x0000000000000000000000000000000000001000";
+const PRECOMPILE_REGISTER: &str = "0x0000000000000000000000000000000000000021";
+const PRECOMPILE_HEARTBEAT: &str = "0x0000000000000000000000000000000000000022";
+const PRECOMPILE_PROOF_ROOT: &str = "0x0000000000000000000000000000000000000023";
+
+static ACTIVE: OnceLock<RwLock<ChainProfile>> = OnceLock::new();
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainProfile {
+    /// "mainnet" (default), or any name when every field below is set.
+    #[serde(default = "default_network")]
+    pub network: String,
+    /// Chain id the RPC is expected to serve; 0 = whatever it reports (mainnet only).
+    #[serde(default)]
+    pub chain_id: u64,
+    #[serde(default)]
+    pub reward_pool: String,
+    #[serde(default)]
+    pub register_precompile: String,
+    #[serde(default)]
+    pub heartbeat_precompile: String,
+    #[serde(default)]
+    pub proof_root_precompile: String,
+}
+
+fn default_network() -> String {
+    "mainnet".to_string()
+}
+
+impl Default for ChainProfile {
+    fn default() -> Self {
+        Self::mainnet()
+    }
+}
+
+impl ChainProfile {
+    pub fn mainnet() -> Self {
+        Self {
+            network: "mainnet".to_string(),
+            chain_id: 0,
+            reward_pool: REWARD_POOL.to_string(),
+            register_precompile: PRECOMPILE_REGISTER.to_string(),
+            heartbeat_precompile: PRECOMPILE_HEARTBEAT.to_string(),
+            proof_root_precompile: PRECOMPILE_PROOF_ROOT.to_string(),
+        }
+    }
+
+    pub fn preset(network: &str) -> Option<Self> {
+        match network {
+            "" | "mainnet" => Some(Self::mainnet()),
+            _ => None,
+        }
+    }
+
+    /// Fill empty fields from the preset and check every address parses.
+    pub fn resolve(&self) -> Result<Self, String> {
+        let preset = Self::preset(&self.network);
+        if preset.is_none() && self.chain_id == 0 {
+            return Err(format!("chain.chainId is required for network '{}'", self.network));
+        }
+        let pick = |value: &str, field: &str, from: fn(&Self) -> &str| -> Result<String, String> {
+            if !value.is_empty() {
+                parse_address(value).map_err(|e| format!("chain.{}: {}", field, e))?;
+                return Ok(value.to_string());
+            }
+            preset.as_ref().map(|p| from(p).to_string()).ok_or_else(|| {
+                format!("chain.{} is required for network '{}'", field, self.network)
+            })
+        };
+
+        Ok(Self {
+            network: self.network.clone(),
+            chain_id: self.chain_id,
+            reward_pool: pick(&self.reward_pool, "rewardPool", |p| &p.reward_pool)?,
+            register_precompile: pick(&self.register_precompile, "registerPrecompile", |p| {
+                &p.register_precompile
+            })?,
+            heartbeat_precompile: pick(&self.heartbeat_precompile, "heartbeatPrecompile", |p| {
+                &p.heartbeat_precompile
+            })?,
+            proof_root_precompile: pick(&self.proof_root_precompile, "proofRootPrecompile", |p| {
+                &p.proof_root_precompile
+            })?,
+        })
+    }
+
+    pub fn reward_pool_bytes(&self) -> [u8; 20] {
+        parse_address(&self.reward_pool).unwrap_or_default()
+    }
+
+    pub fn register_precompile_bytes(&self) -> [u8; 20] {
+        parse_address(&self.register_precompile).unwrap_or_default()
+    }
+
+    pub fn heartbeat_precompile_bytes(&self) -> [u8; 20] {
+        parse_address(&self.heartbeat_precompile).unwrap_or_default()
+    }
+
+    pub fn proof_root_precompile_bytes(&self) -> [u8; 20] {
+        parse_address(&self.proof_root_precompile).unwrap_or_default()
+    }
+}
+
+fn parse_address(value: &str) -> Result<[u8; 20], String> {
+    let hex_str = value.strip_prefix("0x").unwrap_or(value);
+    let bytes = hex::decode(hex_str).map_err(|e| format!("invalid address '{}': {}", value, e))?;
+    bytes
+        .try_into()
+        .map_err(|_| format!("invalid address '{}': expected 20 bytes", value))
+}
+
+fn active_slot() -> &'static RwLock<ChainProfile> {
+    ACTIVE.get_or_init(|| RwLock::new(ChainProfile::mainnet()))
+}
+
+/// Resolve `profile` and make it the one every chain call in this process uses.
+pub fn set_active(profile: &ChainProfile) -> Result<ChainProfile, String> {
+    let resolved = profile.resolve()?;
+    if let Ok(mut slot) = active_slot().write() {
+        *slot = resolved.clone();
+    }
+    Ok(resolved)
+}
+
+/// Profile set by `set_active`; mainnet until then.
+pub fn active() -> ChainProfile {
+    active_slot()
+        .read()
+        .map(|p| p.clone())
+        .unwrap_or_else(|_| ChainProfile::mainnet())
+}
+
+/// Activate `config.chain` for this process, taking the chain id from the RPC when
+/// the profile doesn't set one. An RPC serving a different chain id is an error; an
+/// unreachable one only warns.
+pub async fn install(
+    client: &reqwest::Client,
+    config: &crate::config::AgentConfig,
+) -> Result<ChainProfile, String> {
+    let mut profile = config.chain.resolve()?;
+    match super::tx::fetch_chain_id(client, &config.chain_rpc).await {
+        Ok(id) if profile.chain_id == 0 || id == profile.chain_id => {
+            profile.chain_id = id;
+            log::info!("Chain: {} (id {})", profile.network, id)
+        }
+        Ok(id) => return Err(mismatch(&config.chain_rpc, id, &profile)),
+        Err(e) => log::warn!(
+            "Could not verify the chain id ({}); assuming {}",
+            e,
+            profile.network
+        ),
+    }
+    set_active(&profile)
+}
+
+/// Why `chain_id` (served by `rpc_url`) doesn't match `profile`, or None if it does.
+pub fn check_chain_id(rpc_url: &str, chain_id: u64, profile: &ChainProfile) -> Option<String> {
+    (profile.chain_id != 0 && chain_id != profile.chain_id).then(|| mismatch(rpc_url, chain_id, profile))
+}
+
+fn mismatch(rpc_url: &str, chain_id: u64, profile: &ChainProfile) -> String {
+    format!(
+        "Chain RPC {} serves chain {} but the '{}' profile expects {}; check chainRpc or chain.chainId",
+        rpc_url, chain_id, profile.network, profile.chain_id
+    )
+}
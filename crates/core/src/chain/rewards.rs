@@ -1,27 +1,98 @@
 use super::crypto::keccak256;
+use super::events::{self, RewardEventKind};
 use super::tx::TxSender;
-
-const REWARD_POOL_ADDRESS: &str = "0000000000000000000000000000000000001000";
+use crate::error::{CoreError, CoreResult};
 
 fn pool_address() -> [u8; 20] {
-    let mut addr = [0u8; 20];
-    addr[18] = 0x10;
-    addr[19] = 0x00;
-    addr
+    super::profile::active().reward_pool_bytes()
+}
+
+/// A mined claimReward() transaction, with what its receipt says.
+#[derive(Debug, Clone)]
+pub struct ClaimOutcome {
+    pub tx_hash: String,
+    pub success: bool,
+    pub gas_used: Option<u64>,
+    pub block_number: Option<u64>,
+    /// From the receipt's RewardClaimed log; None if the receipt couldn't be read.
+    pub amount_wei: Option<u128>,
 }
 
-/// Submit claimReward() transaction to RewardPool
-pub async fn claim_reward(tx_sender: &TxSender) -> Result<String, String> {
+/// Submit claimReward() transaction to RewardPool and wait for it to be mined.
+/// A reverted claim is still `Ok`, with `success: false`.
+pub async fn claim_reward(tx_sender: &TxSender) -> CoreResult<ClaimOutcome> {
     let selector = &keccak256(b"claimReward()")[..4];
     let data = selector.to_vec();
 
-    let tx_hash = tx_sender.send_tx(pool_address(), data, 200_000).await?;
-    let success = tx_sender.wait_receipt(&tx_hash, 30).await?;
+    let gas = tx_sender.gas_limit(pool_address(), &data, 200_000).await;
+    let (tx_hash, success) = tx_sender
+        .send_and_confirm(pool_address(), data, gas, 30)
+        .await?;
     if success {
         log::info!("Claim reward tx succeeded: {}", tx_hash);
+    } else {
+        log::warn!("Claim reward tx reverted: {}", tx_hash);
+    }
+
+    let receipt = match tx_sender.receipt(&tx_hash).await {
+        Ok(receipt) => receipt,
+        Err(e) => {
+            log::warn!("Failed to read claim receipt: {}", e);
+            None
+        }
+    };
+    let hex_u64 = |v: &serde_json::Value| {
+        v.as_str()
+            .and_then(|s| u64::from_str_radix(s.trim_start_matches("0x"), 16).ok())
+    };
+    // No RewardClaimed log in a readable receipt means nothing was paid out
+    let amount_wei = receipt.as_ref().map(|r| {
+        r["logs"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(events::parse_log)
+            .find(|e| e.kind == RewardEventKind::Claimed)
+            .and_then(|e| e.amount_wei.parse().ok())
+            .unwrap_or(0)
+    });
+    Ok(ClaimOutcome {
+        gas_used: receipt.as_ref().and_then(|r| hex_u64(&r["gasUsed"])),
+        block_number: receipt.as_ref().and_then(|r| hex_u64(&r["blockNumber"])),
+        amount_wei,
+        tx_hash,
+        success,
+    })
+}
+
+/// Send a claimed amount, less the transfer's gas, on to the payout wallet. Returns
+/// the transfer's tx hash; nothing is sent when the gas would eat the whole amount.
+pub async fn forward_reward(
+    tx_sender: &TxSender,
+    payout_address: [u8; 20],
+    amount_wei: u128,
+) -> CoreResult<String> {
+    let to = format!("0x{}", hex::encode(payout_address));
+    let fee_wei = tx_sender.max_transfer_fee().await?;
+    let value = amount_wei.saturating_sub(fee_wei);
+    if value == 0 {
+        return Err(CoreError::Chain(format!(
+            "{:.6} PLM does not cover the {:.6} PLM transfer gas; not forwarding",
+            amount_wei as f64 / 1e18,
+            fee_wei as f64 / 1e18
+        )));
+    }
+    let (tx_hash, success) = tx_sender.transfer(payout_address, value, 30).await?;
+    if success {
+        log::info!(
+            "Forwarded {:.6} PLM to {}: {}",
+            value as f64 / 1e18,
+            to,
+            tx_hash
+        );
         Ok(tx_hash)
     } else {
-        Err("Claim transaction failed (status=0)".to_string())
+        Err(CoreError::Chain(format!("Transfer to {} reverted (tx {})", to, tx_hash)))
     }
 }
 
@@ -30,21 +101,32 @@ pub async fn get_pending_reward(
     client: &reqwest::Client,
     rpc_url: &str,
     agent_address: &str,
-) -> Result<u128, String> {
+) -> CoreResult<u128> {
     let selector = &keccak256(b"getPendingReward(address)")[..4];
     let addr_hex = agent_address
         .strip_prefix("0x")
         .unwrap_or(agent_address);
     let addr_bytes =
-        hex::decode(addr_hex).map_err(|e| format!("Invalid address hex: {}", e))?;
+        hex::decode(addr_hex).map_err(|e| CoreError::Chain(format!("Invalid address hex: {}", e)))?;
 
     let mut data = Vec::with_capacity(36);
     data.extend_from_slice(selector);
     data.extend_from_slice(&[0u8; 12]); // left-pad to 32 bytes
     data.extend_from_slice(&addr_bytes);
 
-    let call_data = format!("0x{}", hex::encode(&data));
-    let pool_addr = format!("0x{}", REWARD_POOL_ADDRESS);
+    call_pool_u128(client, rpc_url, &data).await
+}
+
+/// eth_call against the RewardPool, decoding the result as a single uint. Transport
+/// failures, JSON-RPC errors and reverts are all errors; callers only use getters the
+/// pool is known to have.
+async fn call_pool_u128(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    data: &[u8],
+) -> CoreResult<u128> {
+    let call_data = format!("0x{}", hex::encode(data));
+    let pool_addr = format!("0x{}", hex::encode(pool_address()));
 
     let resp = client
         .post(rpc_url)
@@ -56,22 +138,31 @@ pub async fn get_pending_reward(
         }))
         .send()
         .await
-        .map_err(|e| format!("RPC request failed: {}", e))?;
+        .map_err(|e| CoreError::Chain(format!("RPC request failed: {}", e)))?;
 
     let json: serde_json::Value = resp
         .json()
         .await
-        .map_err(|e| format!("RPC response parse error: {}", e))?;
+        .map_err(|e| CoreError::Chain(format!("RPC response parse error: {}", e)))?;
 
+    if let Some(error) = json.get("error") {
+        return Err(CoreError::Chain(format!("eth_call to the reward pool failed: {}", error)));
+    }
     let result_hex = json["result"]
         .as_str()
-        .ok_or("No result in eth_call response")?;
-    let result_hex = result_hex.strip_prefix("0x").unwrap_or(result_hex);
+        .ok_or_else(|| CoreError::Chain("No result in eth_call response".to_string()))?;
+    decode_u128(result_hex.strip_prefix("0x").unwrap_or(result_hex))
+}
 
-    if result_hex.is_empty() || result_hex.chars().all(|c| c == '0') {
+/// A uint256 word (eth_call result or log data) as u128; an error if it doesn't fit rather than a truncated value.
+pub(crate) fn decode_u128(result_hex: &str) -> CoreResult<u128> {
+    let digits = result_hex.trim_start_matches('0');
+    if digits.is_empty() {
         return Ok(0);
     }
-
-    u128::from_str_radix(result_hex, 16)
-        .map_err(|e| format!("Failed to parse reward: {}", e))
+    if digits.len() > 32 {
+        return Err(CoreError::Chain(format!("uint256 value 0x{} does not fit in 128 bits", digits)));
+    }
+    u128::from_str_radix(digits, 16)
+        .map_err(|e| CoreError::Chain(format!("Failed to parse uint256 value: {}", e)))
 }
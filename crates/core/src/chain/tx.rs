@@ -1,6 +1,19 @@
+use std::sync::Arc;
+
 use k256::ecdsa::SigningKey;
 use reqwest::Client;
 use super::crypto::{address_from_key, keccak256};
+use super::nonce::NonceManager;
+use crate::error::{CoreError, CoreResult};
+
+/// Resubmissions with a higher gas price before giving up on a transaction.
+const MAX_GAS_BUMPS: u32 = 3;
+/// Headroom on top of `eth_estimateGas`, in percent.
+const GAS_SAFETY_PERCENT: u64 = 130;
+/// Never send with a higher gas limit than this, whatever the estimate says.
+const MAX_GAS_LIMIT: u64 = 2_000_000;
+/// Gas for a plain value transfer to an externally owned account.
+const TRANSFER_GAS: u64 = 21_000;
 
 pub struct TxSender {
     pub rpc_url: String,
@@ -8,6 +21,7 @@ pub struct TxSender {
     pub signing_key: SigningKey,
     pub address: String,
     pub client: Client,
+    nonces: Arc<NonceManager>,
 }
 
 impl TxSender {
@@ -17,24 +31,223 @@ impl TxSender {
             rpc_url,
             chain_id,
             signing_key,
+            nonces: NonceManager::for_address(&address),
             address,
-            client: Client::new(),
+            client: crate::http::client(),
         }
     }
 
-    /// Send a transaction and return the tx hash
-    pub async fn send_tx(&self, to: [u8; 20], data: Vec<u8>, gas: u64) -> Result<String, String> {
-        let nonce = self.get_nonce().await?;
-        let gas_price = self.get_gas_price().await?;
+    /// Send a transaction and return the tx hash.
+    ///
+    /// The nonce comes from the shared `NonceManager`. "Underpriced" rejections
+    /// are retried with a bumped gas price and "nonce too low" with a fresh nonce.
+    pub async fn send_tx(&self, to: [u8; 20], data: Vec<u8>, gas: u64) -> CoreResult<String> {
+        let mut gas_price = self.get_gas_price().await?;
+        let mut nonce = self.reserve_nonce().await?;
+
+        for _ in 0..=MAX_GAS_BUMPS {
+            match self.sign_and_send(nonce, gas_price, to, 0, &data, gas).await {
+                Ok(hash) => {
+                    self.nonces.sent(nonce).await;
+                    return Ok(hash);
+                }
+                Err(TxError::Underpriced(e)) => {
+                    log::warn!("Transaction underpriced at {} wei ({}), bumping gas", gas_price, e);
+                    gas_price = bump_gas_price(gas_price);
+                }
+                Err(TxError::NonceTooLow(e)) => {
+                    log::warn!("Nonce {} already used ({}), resyncing", nonce, e);
+                    self.nonces.release(nonce).await;
+                    self.nonces.resync().await;
+                    nonce = self.reserve_nonce().await?;
+                }
+                Err(TxError::Other(e)) => {
+                    self.nonces.release(nonce).await;
+                    return Err(CoreError::Chain(e));
+                }
+            }
+        }
+        self.nonces.release(nonce).await;
+        Err(CoreError::Chain(format!("Transaction not accepted after {} gas bumps", MAX_GAS_BUMPS)))
+    }
+
+    /// Send a transaction and wait for it to be mined; return (tx hash, status == 1).
+    ///
+    /// If no receipt shows up within `timeout_secs`, the transaction is assumed
+    /// stuck and replaced (same nonce, bumped gas), up to `MAX_GAS_BUMPS` times.
+    pub async fn send_and_confirm(
+        &self,
+        to: [u8; 20],
+        data: Vec<u8>,
+        gas: u64,
+        timeout_secs: u64,
+    ) -> CoreResult<(String, bool)> {
+        self.confirm_with_value(to, 0, data, gas, timeout_secs).await
+    }
+
+    /// Send `value` wei to `to` and wait for it to be mined, like `send_and_confirm`.
+    pub async fn transfer(
+        &self,
+        to: [u8; 20],
+        value: u128,
+        timeout_secs: u64,
+    ) -> CoreResult<(String, bool)> {
+        self.confirm_with_value(to, value, Vec::new(), TRANSFER_GAS, timeout_secs)
+            .await
+    }
+
+    /// Most a `transfer` can pay in fees: its gas at the current price with every
+    /// resubmission bump applied.
+    pub async fn max_transfer_fee(&self) -> CoreResult<u128> {
+        let mut gas_price = self.get_gas_price().await?;
+        for _ in 0..MAX_GAS_BUMPS {
+            gas_price = bump_gas_price(gas_price);
+        }
+        Ok(TRANSFER_GAS as u128 * gas_price as u128)
+    }
+
+    async fn confirm_with_value(
+        &self,
+        to: [u8; 20],
+        value: u128,
+        data: Vec<u8>,
+        gas: u64,
+        timeout_secs: u64,
+    ) -> CoreResult<(String, bool)> {
+        let mut gas_price = self.get_gas_price().await?;
+        let mut nonce = self.reserve_nonce().await?;
+        // Every hash broadcast for this nonce; whichever gets mined wins
+        let mut sent: Vec<String> = Vec::new();
+
+        for attempt in 0..=MAX_GAS_BUMPS {
+            if attempt > 0 {
+                gas_price = bump_gas_price(gas_price);
+                log::warn!("Resubmitting nonce {} at {} wei", nonce, gas_price);
+            }
+            match self.sign_and_send(nonce, gas_price, to, value, &data, gas).await {
+                Ok(hash) => {
+                    if sent.is_empty() {
+                        self.nonces.sent(nonce).await;
+                    }
+                    sent.push(hash);
+                }
+                Err(TxError::Underpriced(_)) => continue,
+                Err(TxError::NonceTooLow(e)) => {
+                    // An earlier broadcast may have been mined meanwhile
+                    if let Some(result) = self.find_receipt(&sent).await? {
+                        return Ok(result);
+                    }
+                    if !sent.is_empty() {
+                        return Err(CoreError::Chain(format!("Nonce {} was used by another transaction: {}", nonce, e)));
+                    }
+                    self.nonces.release(nonce).await;
+                    self.nonces.resync().await;
+                    nonce = self.reserve_nonce().await?;
+                    continue;
+                }
+                Err(TxError::Other(e)) => {
+                    if sent.is_empty() {
+                        self.nonces.release(nonce).await;
+                    }
+                    return Err(CoreError::Chain(e));
+                }
+            }
 
-        // RLP encode for signing: [nonce, gasPrice, gasLimit, to, value=0, data, chainId, 0, 0]
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(timeout_secs);
+            while std::time::Instant::now() < deadline {
+                if let Some(result) = self.find_receipt(&sent).await? {
+                    return Ok(result);
+                }
+                tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+            }
+        }
+        if sent.is_empty() {
+            self.nonces.release(nonce).await;
+            return Err(CoreError::Chain(format!("Transaction not accepted after {} gas bumps", MAX_GAS_BUMPS)));
+        }
+        Err(CoreError::Chain(format!(
+            "Transaction with nonce {} not mined after {} gas bumps",
+            nonce, MAX_GAS_BUMPS
+        )))
+    }
+
+    /// `eth_estimateGas` for a zero-value call from this address.
+    pub async fn estimate_gas(&self, to: [u8; 20], data: &[u8]) -> CoreResult<u64> {
+        let resp = self
+            .client
+            .post(&self.rpc_url)
+            .json(&serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "eth_estimateGas",
+                "params": [{
+                    "from": &self.address,
+                    "to": format!("0x{}", hex::encode(to)),
+                    "data": format!("0x{}", hex::encode(data)),
+                }],
+                "id": 1
+            }))
+            .send()
+            .await
+            .map_err(|e| CoreError::Chain(format!("RPC request failed: {}", e)))?;
+
+        let json: serde_json::Value = resp
+            .json()
+            .await
+            .map_err(|e| CoreError::Chain(format!("RPC response parse error: {}", e)))?;
+
+        if let Some(error) = json.get("error") {
+            return Err(CoreError::Chain(format!("Gas estimation failed: {}", error)));
+        }
+        parse_hex_u64(&json["result"])
+    }
+
+    /// Gas limit for a call: the estimate plus `GAS_SAFETY_PERCENT` headroom, capped at
+    /// `MAX_GAS_LIMIT`. Falls back to `fallback` when the node can't estimate.
+    pub async fn gas_limit(&self, to: [u8; 20], data: &[u8], fallback: u64) -> u64 {
+        match self.estimate_gas(to, data).await {
+            Ok(estimate) => (estimate.saturating_mul(GAS_SAFETY_PERCENT) / 100).min(MAX_GAS_LIMIT),
+            Err(e) => {
+                log::warn!("{}; using default gas limit {}", e, fallback);
+                fallback
+            }
+        }
+    }
+
+    /// First mined receipt among `hashes`, as (hash, status == 1).
+    async fn find_receipt(&self, hashes: &[String]) -> CoreResult<Option<(String, bool)>> {
+        for hash in hashes {
+            if let Some(success) = self.get_receipt(hash).await? {
+                return Ok(Some((hash.clone(), success)));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn reserve_nonce(&self) -> CoreResult<u64> {
+        self.nonces
+            .reserve(|| async { self.get_nonce().await.map_err(String::from) })
+            .await
+            .map_err(CoreError::Chain)
+    }
+
+    async fn sign_and_send(
+        &self,
+        nonce: u64,
+        gas_price: u64,
+        to: [u8; 20],
+        value: u128,
+        data: &[u8],
+        gas: u64,
+    ) -> Result<String, TxError> {
+        let value = strip_leading_zeros(&value.to_be_bytes());
+        // RLP encode for signing: [nonce, gasPrice, gasLimit, to, value, data, chainId, 0, 0]
         let sign_payload = rlp_encode_list(&[
             rlp_encode_u64(nonce),
             rlp_encode_u64(gas_price),
             rlp_encode_u64(gas),
             rlp_encode_bytes(&to),
-            rlp_encode_u64(0), // value = 0
-            rlp_encode_bytes(&data),
+            rlp_encode_bytes(&value),
+            rlp_encode_bytes(data),
             rlp_encode_u64(self.chain_id),
             rlp_encode_u64(0),
             rlp_encode_u64(0),
@@ -45,27 +258,28 @@ impl TxSender {
         let (sig, rec_id) = self
             .signing_key
             .sign_prehash_recoverable(&tx_hash)
-            .map_err(|e| format!("Signing failed: {}", e))?;
+            .map_err(|e| TxError::Other(format!("Signing failed: {}", e)))?;
 
         let sig_bytes = sig.to_bytes();
         let r = strip_leading_zeros(&sig_bytes[..32]);
         let s = strip_leading_zeros(&sig_bytes[32..64]);
         let v = self.chain_id * 2 + 35 + rec_id.to_byte() as u64;
 
-        // RLP encode signed tx: [nonce, gasPrice, gasLimit, to, value=0, data, v, r, s]
+        // RLP encode signed tx: [nonce, gasPrice, gasLimit, to, value, data, v, r, s]
         let signed_tx = rlp_encode_list(&[
             rlp_encode_u64(nonce),
             rlp_encode_u64(gas_price),
             rlp_encode_u64(gas),
             rlp_encode_bytes(&to),
-            rlp_encode_u64(0),
-            rlp_encode_bytes(&data),
+            rlp_encode_bytes(&value),
+            rlp_encode_bytes(data),
             rlp_encode_u64(v),
             rlp_encode_bytes(&r),
             rlp_encode_bytes(&s),
         ]);
 
         let raw_tx = format!("0x{}", hex::encode(&signed_tx));
+        let local_hash = format!("0x{}", hex::encode(keccak256(&signed_tx)));
 
         let resp = self
             .client
@@ -78,83 +292,100 @@ impl TxSender {
             }))
             .send()
             .await
-            .map_err(|e| format!("RPC request failed: {}", e))?;
+            .map_err(|e| TxError::Other(format!("RPC request failed: {}", e)))?;
 
         let json: serde_json::Value = resp
             .json()
             .await
-            .map_err(|e| format!("RPC response parse error: {}", e))?;
+            .map_err(|e| TxError::Other(format!("RPC response parse error: {}", e)))?;
 
         if let Some(error) = json.get("error") {
-            return Err(format!("RPC error: {}", error));
+            let message = error["message"].as_str().unwrap_or_default().to_lowercase();
+            // Identical tx re-broadcast: it is already in the pool under our hash
+            if message.contains("already known") {
+                return Ok(local_hash);
+            }
+            return Err(TxError::classify(&message, format!("RPC error: {}", error)));
         }
 
         json["result"]
             .as_str()
             .map(|s| s.to_string())
-            .ok_or_else(|| "No result in sendRawTransaction response".to_string())
+            .ok_or_else(|| TxError::Other("No result in sendRawTransaction response".to_string()))
+    }
+
+    /// Receipt status for `tx_hash`: None while pending, Some(status == 1) once mined.
+    async fn get_receipt(&self, tx_hash: &str) -> CoreResult<Option<bool>> {
+        Ok(self
+            .receipt(tx_hash)
+            .await?
+            .map(|receipt| receipt["status"].as_str().unwrap_or("0x0") == "0x1"))
+    }
+
+    /// Full `eth_getTransactionReceipt` result (status, gasUsed, logs, ...); None while pending.
+    pub async fn receipt(&self, tx_hash: &str) -> CoreResult<Option<serde_json::Value>> {
+        let resp = self
+            .client
+            .post(&self.rpc_url)
+            .json(&serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "eth_getTransactionReceipt",
+                "params": [tx_hash],
+                "id": 1
+            }))
+            .send()
+            .await
+            .map_err(|e| CoreError::Chain(format!("RPC request failed: {}", e)))?;
+
+        let json: serde_json::Value = resp
+            .json()
+            .await
+            .map_err(|e| CoreError::Chain(format!("RPC response parse error: {}", e)))?;
+
+        match json.get("result") {
+            Some(result) if !result.is_null() => Ok(Some(result.clone())),
+            _ => Ok(None),
+        }
     }
 
     /// Wait for a transaction receipt, return true if status=1
-    pub async fn wait_receipt(&self, tx_hash: &str, timeout_secs: u64) -> Result<bool, String> {
+    pub async fn wait_receipt(&self, tx_hash: &str, timeout_secs: u64) -> CoreResult<bool> {
         let start = std::time::Instant::now();
         loop {
             if start.elapsed().as_secs() > timeout_secs {
-                return Err("Transaction receipt timeout".to_string());
+                return Err(CoreError::Chain("Transaction receipt timeout".to_string()));
             }
-
-            let resp = self
-                .client
-                .post(&self.rpc_url)
-                .json(&serde_json::json!({
-                    "jsonrpc": "2.0",
-                    "method": "eth_getTransactionReceipt",
-                    "params": [tx_hash],
-                    "id": 1
-                }))
-                .send()
-                .await
-                .map_err(|e| format!("RPC request failed: {}", e))?;
-
-            let json: serde_json::Value = resp
-                .json()
-                .await
-                .map_err(|e| format!("RPC response parse error: {}", e))?;
-
-            if let Some(result) = json.get("result") {
-                if !result.is_null() {
-                    let status = result["status"].as_str().unwrap_or("0x0");
-                    return Ok(status == "0x1");
-                }
+            if let Some(success) = self.get_receipt(tx_hash).await? {
+                return Ok(success);
             }
-
             tokio::time::sleep(std::time::Duration::from_secs(2)).await;
         }
     }
 
-    async fn get_nonce(&self) -> Result<u64, String> {
+    async fn get_nonce(&self) -> CoreResult<u64> {
         let resp = self
             .client
             .post(&self.rpc_url)
             .json(&serde_json::json!({
                 "jsonrpc": "2.0",
                 "method": "eth_getTransactionCount",
-                "params": [&self.address, "latest"],
+                // Include our own txs still in the pool
+                "params": [&self.address, "pending"],
                 "id": 1
             }))
             .send()
             .await
-            .map_err(|e| format!("RPC request failed: {}", e))?;
+            .map_err(|e| CoreError::Chain(format!("RPC request failed: {}", e)))?;
 
         let json: serde_json::Value = resp
             .json()
             .await
-            .map_err(|e| format!("RPC response parse error: {}", e))?;
+            .map_err(|e| CoreError::Chain(format!("RPC response parse error: {}", e)))?;
 
         parse_hex_u64(&json["result"])
     }
 
-    async fn get_gas_price(&self) -> Result<u64, String> {
+    async fn get_gas_price(&self) -> CoreResult<u64> {
         let resp = self
             .client
             .post(&self.rpc_url)
@@ -166,24 +397,74 @@ impl TxSender {
             }))
             .send()
             .await
-            .map_err(|e| format!("RPC request failed: {}", e))?;
+            .map_err(|e| CoreError::Chain(format!("RPC request failed: {}", e)))?;
 
         let json: serde_json::Value = resp
             .json()
             .await
-            .map_err(|e| format!("RPC response parse error: {}", e))?;
+            .map_err(|e| CoreError::Chain(format!("RPC response parse error: {}", e)))?;
 
         parse_hex_u64(&json["result"])
     }
 }
 
-fn parse_hex_u64(value: &serde_json::Value) -> Result<u64, String> {
-    let hex_str = value.as_str().ok_or("Expected hex string in RPC response")?;
+/// Why `eth_sendRawTransaction` rejected a transaction.
+enum TxError {
+    /// Gas price too low, or too low to replace the pending tx with the same nonce.
+    Underpriced(String),
+    /// The nonce was already mined.
+    NonceTooLow(String),
+    Other(String),
+}
+
+impl TxError {
+    fn classify(message: &str, error: String) -> Self {
+        if message.contains("underpriced") || message.contains("fee too low") {
+            TxError::Underpriced(error)
+        } else if message.contains("nonce too low") {
+            TxError::NonceTooLow(error)
+        } else {
+            TxError::Other(error)
+        }
+    }
+}
+
+/// Nodes require at least +10% to replace a pending tx; bump by 12.5% to be safe.
+fn bump_gas_price(gas_price: u64) -> u64 {
+    gas_price.saturating_add(gas_price / 8).max(gas_price + 1)
+}
+
+/// Query the chain ID (`eth_chainId`) for signing.
+pub async fn fetch_chain_id(client: &Client, rpc_url: &str) -> CoreResult<u64> {
+    let resp = client
+        .post(rpc_url)
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "eth_chainId",
+            "params": [],
+            "id": 1
+        }))
+        .send()
+        .await
+        .map_err(|e| CoreError::Chain(format!("RPC request failed: {}", e)))?;
+
+    let json: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(|e| CoreError::Chain(format!("RPC response parse error: {}", e)))?;
+
+    parse_hex_u64(&json["result"])
+}
+
+fn parse_hex_u64(value: &serde_json::Value) -> CoreResult<u64> {
+    let hex_str = value
+        .as_str()
+        .ok_or_else(|| CoreError::Chain("Expected hex string in RPC response".to_string()))?;
     let hex_str = hex_str.strip_prefix("0x").unwrap_or(hex_str);
     if hex_str.is_empty() {
         return Ok(0);
     }
-    u64::from_str_radix(hex_str, 16).map_err(|e| format!("Invalid hex u64: {}", e))
+    u64::from_str_radix(hex_str, 16).map_err(|e| CoreError::Chain(format!("Invalid hex u64: {}", e)))
 }
 
 // ---- Manual RLP encoding (no external crate needed) ----
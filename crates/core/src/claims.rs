@@ -0,0 +1,335 @@
+//! Reward claim history: `<config_dir>/claims.jsonl`, one JSON object per line.
+//!
+//! Every claim this agent sends is recorded with its outcome, and claims made
+//! elsewhere (seen as RewardClaimed events) are added once per transaction, so the
+//! history can be reconciled against what the chain actually paid out.
+//!
+//! Both paths forward the claimed amount to `payout_address` when one is set:
+//! `claim_and_forward` for the agent's own claims, `forward_claimed` for events.
+
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use k256::ecdsa::SigningKey;
+
+use crate::chain::events::RewardEvent;
+use crate::chain::rewards::{self, ClaimOutcome};
+use crate::chain::tx::TxSender;
+use crate::error::CoreResult;
+
+const CLAIMS_FILE_NAME: &str = "claims.jsonl";
+
+/// How long a RewardClaimed event waits before it is forwarded, so that a claim sent
+/// through `claim_and_forward` (in this process or another) is recorded, and
+/// forwarded, by that path instead.
+const CLAIM_SETTLE_SECS: u64 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClaimStatus {
+    /// Mined with status 1.
+    Confirmed,
+    /// Mined but reverted.
+    Reverted,
+    /// Never mined: rejected by the RPC or dropped.
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClaimSource {
+    /// Sent by this agent.
+    Agent,
+    /// Seen on chain; claimed from another wallet or tool.
+    Chain,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimRecord {
+    /// Unix epoch milliseconds.
+    pub at: u64,
+    pub source: ClaimSource,
+    pub status: ClaimStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<String>,
+    /// Decimal string from the RewardClaimed log; may exceed f64 precision.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount_wei: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount_plm: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas_used: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Where the claimed amount was forwarded (`payout_address`), if anywhere.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payout: Option<Payout>,
+}
+
+/// Transfer of a claimed amount to the payout wallet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Payout {
+    pub address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Payout {
+    pub fn new(address: &str, result: &CoreResult<String>) -> Self {
+        Self {
+            address: address.to_string(),
+            tx_hash: result.as_ref().ok().cloned(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimSummary {
+    pub attempts: u64,
+    pub confirmed: u64,
+    pub failed: u64,
+    /// Sum of confirmed amounts.
+    pub claimed_plm: f64,
+    pub gas_used: u64,
+}
+
+pub struct ClaimHistory {
+    path: PathBuf,
+    write_lock: Mutex<()>,
+}
+
+impl ClaimHistory {
+    /// Open (or create) the claim history in `dir`.
+    pub fn open(dir: &Path) -> Result<Self, String> {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create claim history directory: {}", e))?;
+        Ok(Self {
+            path: dir.join(CLAIMS_FILE_NAME),
+            write_lock: Mutex::new(()),
+        })
+    }
+
+    /// Record the result of `claim_reward`, and of forwarding it when a payout
+    /// address is configured.
+    pub fn record_attempt(
+        &self,
+        result: &CoreResult<ClaimOutcome>,
+        payout: Option<Payout>,
+    ) -> Result<ClaimRecord, String> {
+        let record = match result {
+            Ok(outcome) => ClaimRecord {
+                at: crate::ledger::unix_millis(),
+                source: ClaimSource::Agent,
+                status: if outcome.success {
+                    ClaimStatus::Confirmed
+                } else {
+                    ClaimStatus::Reverted
+                },
+                tx_hash: Some(outcome.tx_hash.clone()),
+                amount_wei: outcome.amount_wei.map(|a| a.to_string()),
+                amount_plm: outcome.amount_wei.map(|a| a as f64 / 1e18),
+                gas_used: outcome.gas_used,
+                block_number: outcome.block_number,
+                error: None,
+                payout,
+            },
+            Err(e) => ClaimRecord {
+                at: crate::ledger::unix_millis(),
+                source: ClaimSource::Agent,
+                status: ClaimStatus::Failed,
+                tx_hash: None,
+                amount_wei: None,
+                amount_plm: None,
+                gas_used: None,
+                block_number: None,
+                error: Some(e.to_string()),
+                payout,
+            },
+        };
+        self.append(&record)?;
+        Ok(record)
+    }
+
+    /// Record a RewardClaimed event unless its transaction is already in the history.
+    /// Returns whether it was added.
+    pub fn record_event(&self, event: &RewardEvent) -> Result<bool, String> {
+        if self.find(&event.tx_hash, None)?.is_some() {
+            return Ok(false);
+        }
+        self.append(&event_record(event, None))?;
+        Ok(true)
+    }
+
+    /// The record for `tx_hash`, optionally only from `source`.
+    fn find(&self, tx_hash: &str, source: Option<ClaimSource>) -> Result<Option<ClaimRecord>, String> {
+        Ok(self.records()?.into_iter().find(|r| {
+            source.is_none_or(|s| r.source == s)
+                && r.tx_hash
+                    .as_deref()
+                    .is_some_and(|h| h.eq_ignore_ascii_case(tx_hash))
+        }))
+    }
+
+    /// All records, oldest first. Malformed lines are skipped, and so is the event for
+    /// a claim this agent sent when the watcher saw it before the receipt came back.
+    /// An event recorded again with its payout replaces the earlier line.
+    pub fn records(&self) -> Result<Vec<ClaimRecord>, String> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let file = std::fs::File::open(&self.path)
+            .map_err(|e| format!("Failed to open claim history: {}", e))?;
+        let records: Vec<ClaimRecord> = std::io::BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str::<ClaimRecord>(&line).ok())
+            .collect();
+        let sent: Vec<String> = records
+            .iter()
+            .filter(|r| r.source == ClaimSource::Agent)
+            .filter_map(|r| r.tx_hash.as_deref().map(str::to_lowercase))
+            .collect();
+        let mut seen = Vec::new();
+        let mut records: Vec<ClaimRecord> = records
+            .into_iter()
+            .rev()
+            .filter(|r| {
+                if r.source == ClaimSource::Agent {
+                    return true;
+                }
+                match r.tx_hash.as_deref().map(str::to_lowercase) {
+                    Some(h) if sent.contains(&h) || seen.contains(&h) => false,
+                    Some(h) => {
+                        seen.push(h);
+                        true
+                    }
+                    None => true,
+                }
+            })
+            .collect();
+        records.reverse();
+        Ok(records)
+    }
+
+    fn append(&self, record: &ClaimRecord) -> Result<(), String> {
+        let mut line = serde_json::to_string(record)
+            .map_err(|e| format!("Failed to serialize claim record: {}", e))?;
+        line.push('\n');
+
+        let _guard = self
+            .write_lock
+            .lock()
+            .map_err(|_| "Claim history lock poisoned".to_string())?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open claim history: {}", e))?;
+        file.write_all(line.as_bytes())
+            .map_err(|e| format!("Failed to write claim history: {}", e))
+    }
+}
+
+fn event_record(event: &RewardEvent, payout: Option<Payout>) -> ClaimRecord {
+    ClaimRecord {
+        at: crate::ledger::unix_millis(),
+        source: ClaimSource::Chain,
+        status: ClaimStatus::Confirmed,
+        tx_hash: Some(event.tx_hash.clone()),
+        amount_wei: Some(event.amount_wei.clone()),
+        amount_plm: Some(event.amount_plm),
+        gas_used: None,
+        block_number: Some(event.block_number),
+        error: None,
+        payout,
+    }
+}
+
+/// Claim the pending reward, forward it to `payout_address` when one is set, and
+/// record both. A failed claim is recorded and returned as the error; a failed
+/// transfer only shows up in the record's `payout`.
+pub async fn claim_and_forward(
+    tx_sender: &TxSender,
+    payout_address: Option<[u8; 20]>,
+    history: &ClaimHistory,
+) -> CoreResult<ClaimRecord> {
+    let result = rewards::claim_reward(tx_sender).await;
+    let payout = match (&result, payout_address) {
+        (Ok(outcome), Some(to)) if outcome.success && outcome.amount_wei.unwrap_or(0) > 0 => {
+            let amount = outcome.amount_wei.unwrap_or(0);
+            let forwarded = rewards::forward_reward(tx_sender, to, amount).await;
+            if let Err(e) = &forwarded {
+                log::warn!("Claimed PLM stays on the agent key: {}", e);
+            }
+            Some(Payout::new(&format!("0x{}", hex::encode(to)), &forwarded))
+        }
+        _ => None,
+    };
+    let record = history.record_attempt(&result, payout)?;
+    result.map(|_| record)
+}
+
+/// Forward the amount of a RewardClaimed event, already added by `record_event`, to
+/// `payout_address`, and record the transfer on the event. Skipped when the claim was
+/// sent through `claim_and_forward`, which forwards it itself; returns the updated
+/// record otherwise.
+pub async fn forward_claimed(
+    rpc_url: &str,
+    signing_key: &SigningKey,
+    payout_address: [u8; 20],
+    event: &RewardEvent,
+    history: &ClaimHistory,
+) -> CoreResult<Option<ClaimRecord>> {
+    tokio::time::sleep(std::time::Duration::from_secs(CLAIM_SETTLE_SECS)).await;
+    if history.find(&event.tx_hash, Some(ClaimSource::Agent))?.is_some() {
+        return Ok(None);
+    }
+    let amount: u128 = event.amount_wei.parse().unwrap_or(0);
+    if amount == 0 {
+        return Ok(None);
+    }
+
+    let client = crate::http::client();
+    let chain_id = crate::chain::tx::fetch_chain_id(&client, rpc_url).await?;
+    let sender = TxSender::new(rpc_url.to_string(), chain_id, signing_key.clone());
+    let forwarded = rewards::forward_reward(&sender, payout_address, amount).await;
+    if let Err(e) = &forwarded {
+        log::warn!("Claimed PLM stays on the agent key: {}", e);
+    }
+    let record = event_record(
+        event,
+        Some(Payout::new(&format!("0x{}", hex::encode(payout_address)), &forwarded)),
+    );
+    history.append(&record)?;
+    forwarded.map(|_| Some(record))
+}
+
+/// Totals over a set of records.
+pub fn summarize(records: &[ClaimRecord]) -> ClaimSummary {
+    let mut summary = ClaimSummary::default();
+    for r in records {
+        if r.source == ClaimSource::Agent {
+            summary.attempts += 1;
+        }
+        match r.status {
+            ClaimStatus::Confirmed => {
+                summary.confirmed += 1;
+                summary.claimed_plm += r.amount_plm.unwrap_or(0.0);
+            }
+            ClaimStatus::Reverted | ClaimStatus::Failed => summary.failed += 1,
+        }
+        summary.gas_used += r.gas_used.unwrap_or(0);
+    }
+    summary
+}
@@ -0,0 +1,128 @@
+//! Deleting agent data for `plumise-agent clean` and the app's reset. Each kind of
+//! data is opted into separately; the ledger and lifetime totals are never touched.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// What to delete.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupTargets {
+    /// Downloaded GGUF models.
+    #[serde(default)]
+    pub models: bool,
+    /// Downloaded llama.cpp releases (CLI only; the app bundles its own).
+    #[serde(default)]
+    pub binaries: bool,
+    /// agent.log, the audit log and crash records.
+    #[serde(default)]
+    pub logs: bool,
+    /// The config file and runtime state. A private key or HF token in the file is
+    /// kept (the rest of the file is reset) unless `keys` is set too.
+    #[serde(default)]
+    pub config: bool,
+    /// The private key and HF token, from the OS keyring and the config file.
+    #[serde(default)]
+    pub keys: bool,
+}
+
+impl CleanupTargets {
+    pub fn any(&self) -> bool {
+        self.models || self.binaries || self.logs || self.config || self.keys
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupReport {
+    pub removed: Vec<String>,
+    pub freed_bytes: u64,
+    /// Paths that existed but could not be deleted.
+    pub errors: Vec<String>,
+}
+
+impl CleanupReport {
+    fn remove(&mut self, path: &Path) {
+        let Ok(meta) = std::fs::symlink_metadata(path) else {
+            return;
+        };
+        let size = if meta.is_dir() { dir_size(path) } else { meta.len() };
+        let result = if meta.is_dir() {
+            std::fs::remove_dir_all(path)
+        } else {
+            std::fs::remove_file(path)
+        };
+        match result {
+            Ok(()) => {
+                self.removed.push(path.display().to_string());
+                self.freed_bytes += size;
+            }
+            Err(e) => self.errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| match e.metadata() {
+                    Ok(m) if m.is_dir() => dir_size(&e.path()),
+                    Ok(m) => m.len(),
+                    Err(_) => 0,
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Delete the selected data under `config_dir` (and `bin_dir` for binaries).
+/// Keys are only deleted when `confirm_keys` is set: without a backup they can't be
+/// recovered, and the rewards they hold go with them. The agent must be stopped.
+pub fn clean(
+    config_dir: &Path,
+    bin_dir: Option<&Path>,
+    targets: CleanupTargets,
+    confirm_keys: bool,
+) -> Result<CleanupReport, String> {
+    if targets.keys && !confirm_keys {
+        return Err(
+            "Refusing to delete keys without confirmation; back up your private key first".to_string(),
+        );
+    }
+
+    let mut report = CleanupReport::default();
+    if targets.models {
+        report.remove(&config_dir.join("models"));
+    }
+    if targets.binaries {
+        if let Some(bin_dir) = bin_dir {
+            report.remove(bin_dir);
+        }
+    }
+    if targets.logs {
+        let logs: [PathBuf; 3] = [
+            config_dir.join("agent.log"),
+            config_dir.join("audit"),
+            config_dir.join("crashes"),
+        ];
+        for path in &logs {
+            report.remove(path);
+        }
+    }
+    if targets.keys {
+        crate::config::forget_secrets(config_dir)?;
+        report.removed.push("private key and HF token".to_string());
+    }
+    if targets.config {
+        crate::runtime_state::RuntimeState::clear(config_dir);
+        if crate::config::reset_keeping_secrets(config_dir)? {
+            report.removed.push("config settings (private key and HF token kept)".to_string());
+        } else {
+            report.remove(&crate::config::config_file_path(config_dir));
+        }
+    }
+    Ok(report)
+}
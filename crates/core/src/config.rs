@@ -1,6 +1,8 @@
 use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
 
+use crate::error::{CoreError, CoreResult};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AgentConfig {
@@ -8,22 +10,318 @@ pub struct AgentConfig {
     pub model: String,
     #[serde(default = "default_model_file")]
     pub model_file: String,
+    /// On start, re-hash downloaded models against their recorded checksums at most
+    /// this often (0 = only on an explicit verify).
+    #[serde(default = "default_model_verify_interval_hours")]
+    pub model_verify_interval_hours: u32,
     pub device: String,
     pub oracle_url: String,
     pub chain_rpc: String,
     pub http_port: u16,
+    /// When `http_port` is taken by something other than llama-server, try up to this
+    /// many ports above it (0 = fail instead).
+    #[serde(default = "default_http_port_scan")]
+    pub http_port_scan: u16,
     #[serde(default = "default_gpu_layers")]
     pub gpu_layers: i32,
     #[serde(default = "default_ctx_size")]
     pub ctx_size: u32,
     #[serde(default = "default_parallel_slots")]
     pub parallel_slots: u32,
+    /// Let llama-server drop the oldest tokens when a conversation outgrows its slot's
+    /// context, instead of failing the request.
+    #[serde(default = "default_context_shift")]
+    pub context_shift: bool,
+    /// Smallest prompt chunk llama-server reuses from a slot's cache via KV shifting
+    /// (`--cache-reuse`; 0 = off).
+    #[serde(default = "default_cache_reuse")]
+    pub cache_reuse: u32,
     #[serde(default = "default_ram_limit_gb")]
     pub ram_limit_gb: u32,
     #[serde(default = "default_distributed_mode")]
     pub distributed_mode: String,
     #[serde(default = "default_rpc_port")]
     pub rpc_port: u16,
+    /// Address llama-server listens on. Empty = auto: 127.0.0.1 (all traffic arrives
+    /// through the relay, and a coordinator only connects out to its rpc-servers), or
+    /// 0.0.0.0 when `cluster.role` puts the node in a static LAN cluster. rpc-server
+    /// always listens on the LAN. Use `::` on IPv6-only networks; 0.0.0.0 listens on
+    /// IPv4 only.
+    #[serde(default)]
+    pub bind_host: String,
+    /// Seconds an idle pooled HTTP connection is kept before it is closed.
+    #[serde(default = "default_http_pool_idle_secs")]
+    pub http_pool_idle_secs: u64,
+    /// Ping llama-server this often so the pooled connection stays warm across idle
+    /// periods (0 = off). Keep it below `http_pool_idle_secs`.
+    #[serde(default = "default_llama_keepalive_secs")]
+    pub llama_keepalive_secs: u64,
+    /// OTLP/HTTP trace endpoint (e.g. `http://localhost:4318/v1/traces`). Tracing export is off when unset.
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>,
+    /// Electricity price per kWh, used for the energy cost estimate (0 = not set).
+    #[serde(default)]
+    pub electricity_price_kwh: f64,
+    /// PLM price in the same currency as `electricity_price_kwh`, for net earnings.
+    #[serde(default)]
+    pub plm_price: f64,
+    /// "off" | "pause" (reject relay requests) | "deregister" (disconnect from Oracle) while the machine is busy.
+    #[serde(default = "default_idle_policy")]
+    pub idle_policy: String,
+    #[serde(default = "default_idle_threshold_secs")]
+    pub idle_threshold_secs: u64,
+    #[serde(default = "default_idle_gpu_busy_percent")]
+    pub idle_gpu_busy_percent: u32,
+    /// Local-time serving windows, e.g. `["mon-fri 22:00-08:00"]`. Empty = always serve.
+    #[serde(default)]
+    pub serving_windows: Vec<String>,
+    /// React to running on battery power (see `battery_mode`).
+    #[serde(default)]
+    pub pause_on_battery: bool,
+    /// "pause" | "deregister" | "cpu" (restart llama-server CPU-only while on battery).
+    #[serde(default = "default_battery_mode")]
+    pub battery_mode: String,
+    /// Reaction to critical memory pressure (macOS unified memory): "off" | "warn" |
+    /// "pause" (reject new requests until it eases) | "reduce-ctx" (restart llama-server
+    /// with half the context, down to 4096).
+    #[serde(default = "default_memory_pressure_action")]
+    pub memory_pressure_action: String,
+    /// Run llama-server and rpc-server in an OS sandbox (seccomp + Landlock on Linux,
+    /// sandbox-exec on macOS, a Job Object on Windows). Off by default until GPU
+    /// drivers have been verified under it on each platform.
+    #[serde(default)]
+    pub sandbox: bool,
+    /// Desktop notifications for downloads, crashes, claims and Oracle outages (GUI only).
+    #[serde(default = "default_notifications")]
+    pub notifications: bool,
+    /// App update channel: "stable" | "beta" (GUI only).
+    #[serde(default = "default_update_channel")]
+    pub update_channel: String,
+    /// Re-run the benchmark this often while idle and report the new score (0 = off).
+    #[serde(default)]
+    pub rebenchmark_hours: u64,
+    /// llama.cpp release for the CLI: exact tag (e.g. "b4722"), "latest", or empty for the
+    /// version this build was tested with.
+    #[serde(default)]
+    pub llama_version: String,
+    /// Locally built llama-server for the CLI instead of a downloaded release, e.g. a
+    /// CUDA build on NVIDIA Jetson (no prebuilt release exists). `--llama-path` wins.
+    #[serde(default)]
+    pub llama_path: String,
+    /// HuggingFace access token for gated/private model repos. Kept in the OS keyring
+    /// when available, in which case it is left out of the JSON file.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub hf_token: String,
+    /// Where to fetch the model before the built-in mirrors: a direct `.gguf` URL, or a
+    /// mirror base serving `<base>/<repo>/resolve/main/<file>`. Empty uses the mirrors only.
+    #[serde(default)]
+    pub model_url: String,
+    /// Multimodal projector GGUF in the same repo (e.g. "mmproj-F16.gguf") for vision
+    /// models. Empty serves text only.
+    #[serde(default)]
+    pub mmproj_file: String,
+    /// "chat" (default), "embedding" or "rerank": serve `model` as an embedding or
+    /// reranker model only, for machines too small for the chat model.
+    #[serde(default = "default_serve_mode")]
+    pub serve_mode: String,
+    /// Where batched inference proof roots go: "off" (default), "oracle" or "chain".
+    #[serde(default = "default_proof_submission")]
+    pub proof_submission: String,
+    /// Close a proof batch after this many requests...
+    #[serde(default = "default_proof_batch_size")]
+    pub proof_batch_size: usize,
+    /// ...or this many minutes, whichever comes first.
+    #[serde(default = "default_proof_batch_minutes")]
+    pub proof_batch_minutes: u64,
+    /// WebSocket RPC for reward event subscriptions. Empty derives it from `chain_rpc`.
+    #[serde(default)]
+    pub chain_ws_url: String,
+    /// Network to run against; defaults to mainnet.
+    #[serde(default)]
+    pub chain: crate::chain::profile::ChainProfile,
+    /// llama-server `--log-verbosity`; None keeps its default.
+    #[serde(default)]
+    pub llama_log_verbosity: Option<u32>,
+    /// Start llama-server with `--log-disable` (no output at all, errors included).
+    #[serde(default)]
+    pub llama_log_disable: bool,
+    /// Also write every llama-server line here; relative paths are under the config dir.
+    #[serde(default)]
+    pub llama_log_file: String,
+    /// Lowest llama-server level passed to the agent log and GUI: "debug", "info",
+    /// "warning" or "error".
+    #[serde(default = "default_llama_log_level")]
+    pub llama_log_level: String,
+    /// Cold wallet each claim forwards the claimed PLM (less the transfer gas) to. Empty
+    /// keeps it on the agent key.
+    #[serde(default)]
+    pub payout_address: String,
+    /// Alert when the wallet balance crosses any of these (PLM). Empty (default) disables
+    /// the alerts; gas is sponsored, so a low balance only matters for self-paid txs.
+    #[serde(default)]
+    pub balance_alert_plm: Vec<f64>,
+    /// Wallet balance check interval in seconds.
+    #[serde(default = "default_balance_check_secs")]
+    pub balance_check_secs: u64,
+    /// Coordinators drop RPC peers slower than this round trip (0 = no limit).
+    #[serde(default = "default_max_peer_latency_ms")]
+    pub max_peer_latency_ms: f64,
+    /// Static LAN cluster; when `role` is set it replaces the Oracle's assignment.
+    #[serde(default)]
+    pub cluster: ClusterConfig,
+    /// Endpoints POSTed a JSON payload on agent events (see `events::AgentEvent`).
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+    /// Telegram / Discord messages on agent events.
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+    /// SPKI pins ("sha256/<base64>") for the Oracle/relay host. Empty trusts the system CAs alone.
+    #[serde(default)]
+    pub oracle_spki_pins: Vec<String>,
+    /// Record served relay requests in `<config_dir>/audit/audit.jsonl`.
+    #[serde(default)]
+    pub audit_log: bool,
+    /// Prompt/response text in the audit log: "omit" (default) | "hash" | "store".
+    #[serde(default = "default_audit_log_content")]
+    pub audit_log_content: String,
+    /// Rotate the audit log at this size.
+    #[serde(default = "default_audit_log_max_mb")]
+    pub audit_log_max_mb: u64,
+    /// Delete rotated audit logs after this many days (0 = keep).
+    #[serde(default = "default_audit_log_max_days")]
+    pub audit_log_max_days: u64,
+    /// Report every completed relay request (tokens, duration) to the Oracle in signed batches.
+    #[serde(default)]
+    pub usage_reporting: bool,
+    /// Region/zone label sent with the registration, e.g. "eu-west" or "us-east-1a".
+    #[serde(default)]
+    pub region: String,
+    /// Send a coarse IP-based location with the registration (off by default).
+    #[serde(default)]
+    pub geolocation: bool,
+    /// ipapi.co-compatible lookup service; empty uses ipapi.co.
+    #[serde(default)]
+    pub geolocation_url: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterConfig {
+    /// "" (the Oracle decides), "coordinator" or "rpc-server".
+    #[serde(default)]
+    pub role: String,
+    /// Coordinator only: rpc-server peers as "host:port" (port defaults to `rpc_port`).
+    #[serde(default)]
+    pub peers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Event kinds to send, e.g. "agent-crashed"; empty sends all.
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AlertsConfig {
+    /// Bot token from @BotFather; Telegram alerts are off when empty. Like `hf_token`,
+    /// kept in the OS keyring when available and left out of the JSON file.
+    pub telegram_bot_token: String,
+    /// Chat (or user) the bot writes to.
+    pub telegram_chat_id: String,
+    /// Discord channel webhook URL; Discord alerts are off when empty. Kept in the
+    /// OS keyring like the bot token.
+    pub discord_webhook_url: String,
+    pub agent_started: bool,
+    pub agent_stopped: bool,
+    pub agent_crashed: bool,
+    pub registration_failed: bool,
+    pub reward_claimed: bool,
+    pub download_completed: bool,
+    /// At most one message per event kind in this many seconds; the rest are
+    /// counted and mentioned in the next one.
+    pub min_interval_secs: u64,
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            telegram_bot_token: String::new(),
+            telegram_chat_id: String::new(),
+            discord_webhook_url: String::new(),
+            agent_started: false,
+            agent_stopped: true,
+            agent_crashed: true,
+            registration_failed: true,
+            reward_claimed: false,
+            download_completed: false,
+            min_interval_secs: 300,
+        }
+    }
+}
+
+/// Role forced by `cluster.role`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClusterRole {
+    /// Follow the Oracle's assignment.
+    #[default]
+    Auto,
+    Coordinator,
+    RpcServer,
+}
+
+impl ClusterRole {
+    pub fn from_config(role: &str) -> Result<Self, String> {
+        match role {
+            "" | "auto" => Ok(ClusterRole::Auto),
+            "coordinator" => Ok(ClusterRole::Coordinator),
+            "rpc-server" => Ok(ClusterRole::RpcServer),
+            other => Err(format!(
+                "Invalid cluster role '{}': expected \"coordinator\" or \"rpc-server\"",
+                other
+            )),
+        }
+    }
+}
+
+/// What this node serves (see `AgentConfig::serve_mode`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ServeMode {
+    #[default]
+    Chat,
+    Embedding,
+    Rerank,
+}
+
+impl ServeMode {
+    pub fn from_config(mode: &str) -> Self {
+        match mode {
+            "embedding" => ServeMode::Embedding,
+            "rerank" => ServeMode::Rerank,
+            _ => ServeMode::Chat,
+        }
+    }
+
+    /// Capability advertised to the Oracle on registration.
+    pub fn capability(self) -> &'static str {
+        match self {
+            ServeMode::Chat => "inference",
+            ServeMode::Embedding => "embedding",
+            ServeMode::Rerank => "rerank",
+        }
+    }
+
+    /// llama-server flag that restricts it to this mode's endpoint.
+    pub fn llama_flag(self) -> Option<&'static str> {
+        match self {
+            ServeMode::Chat => None,
+            ServeMode::Embedding => Some("--embedding"),
+            ServeMode::Rerank => Some("--reranking"),
+        }
+    }
 }
 
 fn default_model_file() -> String {
@@ -47,6 +345,75 @@ fn default_distributed_mode() -> String {
 fn default_rpc_port() -> u16 {
     50052
 }
+fn default_context_shift() -> bool {
+    true
+}
+fn default_cache_reuse() -> u32 {
+    256
+}
+fn default_model_verify_interval_hours() -> u32 {
+    168
+}
+fn default_http_pool_idle_secs() -> u64 {
+    90
+}
+fn default_llama_keepalive_secs() -> u64 {
+    30
+}
+fn default_llama_log_level() -> String {
+    "info".to_string()
+}
+fn default_http_port_scan() -> u16 {
+    20
+}
+fn default_battery_mode() -> String {
+    "pause".to_string()
+}
+fn default_memory_pressure_action() -> String {
+    "warn".to_string()
+}
+fn default_notifications() -> bool {
+    true
+}
+fn default_update_channel() -> String {
+    "stable".to_string()
+}
+fn default_serve_mode() -> String {
+    "chat".to_string()
+}
+fn default_audit_log_content() -> String {
+    "omit".to_string()
+}
+fn default_audit_log_max_mb() -> u64 {
+    50
+}
+fn default_audit_log_max_days() -> u64 {
+    30
+}
+fn default_proof_submission() -> String {
+    "off".to_string()
+}
+fn default_proof_batch_size() -> usize {
+    100
+}
+fn default_proof_batch_minutes() -> u64 {
+    10
+}
+fn default_balance_check_secs() -> u64 {
+    300
+}
+fn default_max_peer_latency_ms() -> f64 {
+    20.0
+}
+fn default_idle_policy() -> String {
+    "off".to_string()
+}
+fn default_idle_threshold_secs() -> u64 {
+    300
+}
+fn default_idle_gpu_busy_percent() -> u32 {
+    20
+}
 
 impl Default for AgentConfig {
     fn default() -> Self {
@@ -54,16 +421,68 @@ impl Default for AgentConfig {
             private_key: String::new(),
             model: "Qwen/Qwen3-32B-GGUF".to_string(),
             model_file: default_model_file(),
+            model_verify_interval_hours: default_model_verify_interval_hours(),
             device: "auto".to_string(),
             oracle_url: "https://plug.plumise.com/oracle".to_string(),
             chain_rpc: "https://plug.plumise.com/rpc/plug_live_w9mS7DOAqMGlhyYwhLa8MOE-7UZfbKwCT34ib8JLZL0".to_string(),
             http_port: 18920,
+            http_port_scan: default_http_port_scan(),
             gpu_layers: default_gpu_layers(),
             ctx_size: default_ctx_size(),
             parallel_slots: default_parallel_slots(),
+            context_shift: default_context_shift(),
+            cache_reuse: default_cache_reuse(),
             ram_limit_gb: default_ram_limit_gb(),
             distributed_mode: default_distributed_mode(),
+            bind_host: String::new(),
             rpc_port: default_rpc_port(),
+            http_pool_idle_secs: default_http_pool_idle_secs(),
+            llama_keepalive_secs: default_llama_keepalive_secs(),
+            otlp_endpoint: None,
+            electricity_price_kwh: 0.0,
+            plm_price: 0.0,
+            idle_policy: default_idle_policy(),
+            idle_threshold_secs: default_idle_threshold_secs(),
+            idle_gpu_busy_percent: default_idle_gpu_busy_percent(),
+            serving_windows: Vec::new(),
+            pause_on_battery: false,
+            battery_mode: default_battery_mode(),
+            memory_pressure_action: default_memory_pressure_action(),
+            sandbox: false,
+            notifications: default_notifications(),
+            update_channel: default_update_channel(),
+            rebenchmark_hours: 0,
+            llama_version: String::new(),
+            llama_path: String::new(),
+            hf_token: String::new(),
+            model_url: String::new(),
+            mmproj_file: String::new(),
+            serve_mode: default_serve_mode(),
+            proof_submission: default_proof_submission(),
+            proof_batch_size: default_proof_batch_size(),
+            proof_batch_minutes: default_proof_batch_minutes(),
+            chain_ws_url: String::new(),
+            chain: crate::chain::profile::ChainProfile::default(),
+            llama_log_verbosity: None,
+            llama_log_disable: false,
+            llama_log_file: String::new(),
+            llama_log_level: default_llama_log_level(),
+            payout_address: String::new(),
+            balance_alert_plm: Vec::new(),
+            balance_check_secs: default_balance_check_secs(),
+            max_peer_latency_ms: default_max_peer_latency_ms(),
+            cluster: ClusterConfig::default(),
+            webhooks: Vec::new(),
+            alerts: AlertsConfig::default(),
+            oracle_spki_pins: Vec::new(),
+            audit_log: false,
+            audit_log_content: default_audit_log_content(),
+            audit_log_max_mb: default_audit_log_max_mb(),
+            audit_log_max_days: default_audit_log_max_days(),
+            usage_reporting: false,
+            region: String::new(),
+            geolocation: false,
+            geolocation_url: String::new(),
         }
     }
 }
@@ -71,11 +490,14 @@ impl Default for AgentConfig {
 const CONFIG_FILE_NAME: &str = "agent-config.json";
 const KEYRING_SERVICE: &str = "com.plumise.agent";
 const KEYRING_USER: &str = "plumise-agent-private-key";
+const KEYRING_HF_TOKEN_USER: &str = "plumise-agent-hf-token";
+const KEYRING_TELEGRAM_TOKEN_USER: &str = "plumise-agent-telegram-bot-token";
+const KEYRING_DISCORD_WEBHOOK_USER: &str = "plumise-agent-discord-webhook-url";
 
 /// Save config to a directory (JSON file + optional keyring).
-pub fn save_config(config: &AgentConfig, dir: &Path) -> Result<(), String> {
+pub fn save_config(config: &AgentConfig, dir: &Path) -> CoreResult<()> {
     std::fs::create_dir_all(dir)
-        .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        .map_err(|e| CoreError::Config(format!("Failed to create config directory: {}", e)))?;
 
     // Try to save private_key to OS keyring (best-effort)
     if !config.private_key.is_empty() {
@@ -93,19 +515,127 @@ pub fn save_config(config: &AgentConfig, dir: &Path) -> Result<(), String> {
         }
     }
 
+    // HF token and alert credentials: keyring only when possible, JSON as fallback
+    let mut to_write = config.clone();
+    move_to_keyring(KEYRING_HF_TOKEN_USER, &mut to_write.hf_token, "HF token");
+    move_to_keyring(
+        KEYRING_TELEGRAM_TOKEN_USER,
+        &mut to_write.alerts.telegram_bot_token,
+        "Telegram bot token",
+    );
+    move_to_keyring(
+        KEYRING_DISCORD_WEBHOOK_USER,
+        &mut to_write.alerts.discord_webhook_url,
+        "Discord webhook URL",
+    );
+
     let path = dir.join(CONFIG_FILE_NAME);
-    let json = serde_json::to_string_pretty(config)
-        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    let json = serde_json::to_string_pretty(&to_write)
+        .map_err(|e| CoreError::Config(format!("Failed to serialize config: {}", e)))?;
 
-    std::fs::write(&path, json)
-        .map_err(|e| format!("Failed to write config file: {}", e))?;
+    write_config_file(&path, &json)
+        .map_err(|e| CoreError::Config(format!("Failed to write config file: {}", e)))?;
 
     log::info!("Config saved to {:?}", path);
     Ok(())
 }
 
+/// Store `secret` in the keyring under `user` and blank it in the copy written to JSON;
+/// it stays in the JSON when the keyring can't take it. An empty secret deletes the entry.
+fn move_to_keyring(user: &str, secret: &mut String, what: &str) {
+    if secret.is_empty() {
+        if let Ok(entry) = keyring::Entry::new(KEYRING_SERVICE, user) {
+            let _ = entry.delete_credential();
+        }
+        return;
+    }
+    let stored = keyring::Entry::new(KEYRING_SERVICE, user).and_then(|entry| entry.set_password(secret));
+    match stored {
+        Ok(()) => secret.clear(),
+        Err(e) => log::warn!("Keyring save of {} failed, keeping it in config JSON: {}", what, e),
+    }
+}
+
+/// Fill an empty `secret` from the keyring entry `user`, if there is one.
+fn load_from_keyring(user: &str, secret: &mut String) {
+    if secret.is_empty() {
+        if let Ok(value) = keyring::Entry::new(KEYRING_SERVICE, user).and_then(|entry| entry.get_password()) {
+            *secret = value;
+        }
+    }
+}
+
+/// Write then rename so a crash never leaves a half-written config.
+fn write_config_file(path: &Path, json: &str) -> std::io::Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json)?;
+    std::fs::rename(&tmp, path)
+}
+
+/// Path of the config file in `dir`.
+pub fn config_file_path(dir: &Path) -> PathBuf {
+    dir.join(CONFIG_FILE_NAME)
+}
+
+/// Delete the private key, HF token and alert credentials from the OS keyring and
+/// blank them in the config file, leaving the rest of the config as it is.
+pub fn forget_secrets(dir: &Path) -> Result<(), String> {
+    for user in [
+        KEYRING_USER,
+        KEYRING_HF_TOKEN_USER,
+        KEYRING_TELEGRAM_TOKEN_USER,
+        KEYRING_DISCORD_WEBHOOK_USER,
+    ] {
+        if let Ok(entry) = keyring::Entry::new(KEYRING_SERVICE, user) {
+            let _ = entry.delete_credential();
+        }
+    }
+
+    let path = dir.join(CONFIG_FILE_NAME);
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return Ok(());
+    };
+    let mut config: AgentConfig = serde_json::from_str(&contents)
+        .map_err(|e| format!("Failed to parse config file: {}", e))?;
+    config.private_key.clear();
+    config.hf_token.clear();
+    config.alerts.telegram_bot_token.clear();
+    config.alerts.discord_webhook_url.clear();
+    let json = serde_json::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    write_config_file(&path, &json).map_err(|e| format!("Failed to write config file: {}", e))
+}
+
+/// Reset the config file to defaults but keep the private key and HF token in it, since
+/// without a keyring the file may hold the only copy of the key. Returns false, leaving
+/// the file alone, when it holds no secrets (or doesn't exist) and can simply be deleted.
+pub fn reset_keeping_secrets(dir: &Path) -> Result<bool, String> {
+    let path = dir.join(CONFIG_FILE_NAME);
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return Ok(false);
+    };
+    let value: serde_json::Value = serde_json::from_str(&contents).map_err(|e| {
+        format!("Config file can't be parsed and may hold your private key, not deleting it: {}", e)
+    })?;
+    let secret = |key: &str| value.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+    let (private_key, hf_token) = (secret("privateKey"), secret("hfToken"));
+    if private_key.is_empty() && hf_token.is_empty() {
+        return Ok(false);
+    }
+
+    let config = AgentConfig {
+        private_key,
+        hf_token,
+        ..AgentConfig::default()
+    };
+    let json = serde_json::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    write_config_file(&path, &json).map_err(|e| format!("Failed to write config file: {}", e))?;
+    Ok(true)
+}
+
 /// Load config from a directory.
-pub fn load_config(dir: &Path) -> Result<AgentConfig, String> {
+pub fn load_config(dir: &Path) -> CoreResult<AgentConfig> {
     let path = dir.join(CONFIG_FILE_NAME);
 
     if !path.exists() {
@@ -113,10 +643,10 @@ pub fn load_config(dir: &Path) -> Result<AgentConfig, String> {
     }
 
     let contents = std::fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read config file: {}", e))?;
+        .map_err(|e| CoreError::Config(format!("Failed to read config file: {}", e)))?;
 
     let mut config: AgentConfig = serde_json::from_str(&contents)
-        .map_err(|e| format!("Failed to parse config file: {}", e))?;
+        .map_err(|e| CoreError::Config(format!("Failed to parse config file: {}", e)))?;
 
     // Migrations
     if config.http_port == 8080 {
@@ -169,10 +699,384 @@ pub fn load_config(dir: &Path) -> Result<AgentConfig, String> {
         }
     }
 
+    load_from_keyring(KEYRING_HF_TOKEN_USER, &mut config.hf_token);
+    load_from_keyring(KEYRING_TELEGRAM_TOKEN_USER, &mut config.alerts.telegram_bot_token);
+    load_from_keyring(KEYRING_DISCORD_WEBHOOK_USER, &mut config.alerts.discord_webhook_url);
+
+    // Commands that never start the agent still read the chain with the right addresses
+    if let Err(e) = crate::chain::profile::set_active(&config.chain) {
+        log::warn!("Invalid chain profile: {}", e);
+    }
+
     log::info!("Config loaded from {:?}", path);
     Ok(config)
 }
 
+impl AgentConfig {
+    pub fn llama_path(&self) -> Option<PathBuf> {
+        Some(self.llama_path.trim())
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+    }
+
+    /// How long a model verification holds on start; None = don't verify on start.
+    pub fn model_verify_interval(&self) -> Option<std::time::Duration> {
+        (self.model_verify_interval_hours > 0)
+            .then(|| std::time::Duration::from_secs(self.model_verify_interval_hours as u64 * 3600))
+    }
+
+    pub fn idle_policy(&self) -> crate::idle::IdlePolicy {
+        crate::idle::IdlePolicy {
+            mode: self.idle_policy.clone(),
+            idle_threshold_secs: self.idle_threshold_secs,
+            gpu_busy_percent: self.idle_gpu_busy_percent,
+        }
+    }
+
+    pub fn battery_policy(&self) -> crate::power::BatteryPolicy {
+        crate::power::BatteryPolicy::from_config(self.pause_on_battery, &self.battery_mode)
+    }
+
+    pub fn memory_pressure_policy(&self) -> crate::system::MemoryPressurePolicy {
+        crate::system::MemoryPressurePolicy::from_config(&self.memory_pressure_action)
+    }
+
+    pub fn schedule(&self) -> Result<crate::schedule::Schedule, String> {
+        crate::schedule::Schedule::parse(&self.serving_windows)
+    }
+
+    pub fn serve_mode(&self) -> ServeMode {
+        ServeMode::from_config(&self.serve_mode)
+    }
+
+    /// `bind_host`, with "auto" (empty) resolved.
+    pub fn llama_bind_host(&self) -> String {
+        let host = self.bind_host.trim();
+        if !host.is_empty() && !host.eq_ignore_ascii_case("auto") {
+            return host.to_string();
+        }
+        // distributed_mode "auto" alone never needs llama-server on the LAN
+        if !self.cluster.role.is_empty() {
+            "0.0.0.0".to_string()
+        } else {
+            "127.0.0.1".to_string()
+        }
+    }
+
+    pub fn chain_ws_url(&self) -> String {
+        if self.chain_ws_url.is_empty() {
+            crate::chain::events::ws_url_for(&self.chain_rpc)
+        } else {
+            self.chain_ws_url.clone()
+        }
+    }
+
+    /// `payout_address` as bytes; None when unset.
+    pub fn payout_address(&self) -> Result<Option<[u8; 20]>, String> {
+        let address = self.payout_address.trim();
+        if address.is_empty() {
+            return Ok(None);
+        }
+        let bytes = address
+            .strip_prefix("0x")
+            .filter(|h| h.len() == 40)
+            .and_then(|h| hex::decode(h).ok())
+            .ok_or_else(|| {
+                format!("Invalid payout address '{}': expected 0x + 40 hex digits", address)
+            })?;
+        let mut out = [0u8; 20];
+        out.copy_from_slice(&bytes);
+        Ok(Some(out))
+    }
+
+    pub fn audit_content(&self) -> crate::audit::AuditContent {
+        crate::audit::AuditContent::from_config(&self.audit_log_content)
+    }
+
+    pub fn proof_target(&self) -> crate::inference::proof_batch::ProofTarget {
+        crate::inference::proof_batch::ProofTarget::from_config(&self.proof_submission)
+    }
+
+    pub fn proof_batch_policy(&self) -> crate::inference::proof_batch::ProofBatchPolicy {
+        crate::inference::proof_batch::ProofBatchPolicy {
+            max_leaves: self.proof_batch_size.max(1),
+            max_age: std::time::Duration::from_secs(self.proof_batch_minutes.max(1) * 60),
+        }
+    }
+
+    /// The assignment `cluster` forces, or None to follow the Oracle.
+    pub fn static_cluster(&self) -> Result<Option<crate::oracle::registry::ClusterAssignment>, String> {
+        let mode = match ClusterRole::from_config(&self.cluster.role)? {
+            ClusterRole::Auto => return Ok(None),
+            ClusterRole::Coordinator => "coordinator",
+            ClusterRole::RpcServer => "rpc-server",
+        };
+        if self.serve_mode() != ServeMode::Chat {
+            return Err(format!(
+                "cluster.role '{}' requires serve_mode \"chat\"",
+                self.cluster.role
+            ));
+        }
+
+        let rpc_peers = if mode == "coordinator" {
+            let peers: Vec<String> = self
+                .cluster
+                .peers
+                .iter()
+                .map(|p| p.trim())
+                .filter(|p| !p.is_empty())
+                .map(|p| {
+                    if p.rsplit_once(':').is_some_and(|(_, port)| port.parse::<u16>().is_ok()) {
+                        p.to_string()
+                    } else {
+                        format!("{}:{}", p, self.rpc_port)
+                    }
+                })
+                .collect();
+            if peers.is_empty() {
+                return Err("cluster.role \"coordinator\" needs at least one entry in cluster.peers".into());
+            }
+            Some(peers)
+        } else {
+            None
+        };
+
+        Ok(Some(crate::oracle::registry::ClusterAssignment {
+            mode: mode.to_string(),
+            cluster_id: Some("static".to_string()),
+            rpc_port: self.rpc_port,
+            rpc_peers,
+        }))
+    }
+
+    /// Reject values that can't work: malformed private key, unusable ports, unknown
+    /// cluster role, region label or serving window syntax.
+    pub fn validate(&self) -> CoreResult<()> {
+        if !self.private_key.is_empty() {
+            let hex_part = self.private_key.strip_prefix("0x").unwrap_or("");
+            if hex_part.len() != 64 || !hex_part.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(CoreError::Config(
+                    "privateKey must be 0x followed by 64 hex characters".into(),
+                ));
+            }
+        }
+        for (name, port) in [("httpPort", self.http_port), ("rpcPort", self.rpc_port)] {
+            if port < 1024 {
+                return Err(CoreError::Config(format!(
+                    "{} must be between 1024 and 65535 (got {})",
+                    name, port
+                )));
+            }
+        }
+        if self.http_port == self.rpc_port {
+            return Err(CoreError::Config(format!(
+                "httpPort and rpcPort are both {}",
+                self.http_port
+            )));
+        }
+        if self.ctx_size == 0 || self.parallel_slots == 0 {
+            return Err(CoreError::Config(
+                "ctxSize and parallelSlots must be at least 1".into(),
+            ));
+        }
+        if self.region.len() > 64
+            || !self
+                .region
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'))
+        {
+            return Err(CoreError::Config(
+                "region must be up to 64 letters, digits, '-', '_', '.' or '/'".into(),
+            ));
+        }
+        ClusterRole::from_config(&self.cluster.role).map_err(CoreError::Config)?;
+        self.schedule().map_err(CoreError::Config)?;
+        Ok(())
+    }
+
+    /// Model ID registered with the Oracle and the relay.
+    pub fn oracle_model(&self) -> String {
+        match self.serve_mode() {
+            ServeMode::Chat => oracle_model_name(&self.model).to_string(),
+            ServeMode::Embedding | ServeMode::Rerank => oracle_model_id_from_repo(&self.model),
+        }
+    }
+}
+
+const LAST_START_FILE_NAME: &str = "last-start.json";
+
+/// Record the effective config a running agent was started with, so `restart`
+/// can report what changed. Secrets are stored only as hashes.
+pub fn save_last_start(config: &AgentConfig, dir: &Path) -> Result<(), String> {
+    let snapshot = redact_secrets(config);
+    let json = serde_json::to_string_pretty(&snapshot)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    std::fs::write(dir.join(LAST_START_FILE_NAME), json)
+        .map_err(|e| format!("Failed to write last-start snapshot: {}", e))
+}
+
+fn key_fingerprint(secret: &str) -> String {
+    hex::encode(crate::chain::crypto::keccak256(secret.as_bytes()))
+}
+
+fn redact_secrets(config: &AgentConfig) -> AgentConfig {
+    let mut redacted = config.clone();
+    redacted.private_key = key_fingerprint(&config.private_key);
+    for secret in [
+        &mut redacted.hf_token,
+        &mut redacted.alerts.telegram_bot_token,
+        &mut redacted.alerts.discord_webhook_url,
+    ] {
+        if !secret.is_empty() {
+            *secret = key_fingerprint(secret);
+        }
+    }
+    redacted
+}
+
+/// Config recorded by the last `save_last_start`, if any.
+pub fn load_last_start(dir: &Path) -> Option<AgentConfig> {
+    let contents = std::fs::read_to_string(dir.join(LAST_START_FILE_NAME)).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Human-readable list of fields that differ between a `save_last_start`
+/// snapshot and `current`, e.g. `gpuLayers: 99 -> 0`.
+pub fn diff_config(previous: &AgentConfig, current: &AgentConfig) -> Vec<String> {
+    let current = redact_secrets(current);
+
+    let (old, new) = match (serde_json::to_value(previous), serde_json::to_value(&current)) {
+        (Ok(serde_json::Value::Object(o)), Ok(serde_json::Value::Object(n))) => (o, n),
+        _ => return Vec::new(),
+    };
+
+    let mut changes = Vec::new();
+    for (key, new_value) in &new {
+        let old_value = old.get(key).unwrap_or(&serde_json::Value::Null);
+        if old_value == new_value {
+            continue;
+        }
+        if key == "privateKey" || key == "hfToken" {
+            changes.push(format!("{}: changed", key));
+        } else {
+            changes.push(format!("{}: {} -> {}", key, old_value, new_value));
+        }
+    }
+    changes
+}
+
+/// `gpu_layers` / `gpuLayers` / `cluster.role` -> the JSON path segments used in the file.
+fn config_key_path(key: &str) -> Vec<String> {
+    key.split('.')
+        .map(|part| {
+            let mut out = String::new();
+            let mut upper = false;
+            for c in part.chars() {
+                if c == '_' || c == '-' {
+                    upper = true;
+                } else if upper {
+                    out.extend(c.to_uppercase());
+                    upper = false;
+                } else {
+                    out.push(c);
+                }
+            }
+            out
+        })
+        .collect()
+}
+
+fn config_value_mut<'a>(
+    root: &'a mut serde_json::Value,
+    key: &str,
+) -> Result<&'a mut serde_json::Value, String> {
+    let mut value = root;
+    for part in config_key_path(key) {
+        value = value
+            .get_mut(&part)
+            .ok_or_else(|| format!("Unknown config key '{}'", key))?;
+    }
+    Ok(value)
+}
+
+/// Config as JSON with the private key, HF token and alert credentials masked, for display.
+pub fn masked_config(config: &AgentConfig) -> Result<serde_json::Value, String> {
+    let mut value =
+        serde_json::to_value(config).map_err(|e| format!("Failed to serialize config: {}", e))?;
+    for (pointer, secret) in [
+        ("/privateKey", &config.private_key),
+        ("/hfToken", &config.hf_token),
+        ("/alerts/telegramBotToken", &config.alerts.telegram_bot_token),
+        ("/alerts/discordWebhookUrl", &config.alerts.discord_webhook_url),
+    ] {
+        if let Some(slot) = value.pointer_mut(pointer) {
+            *slot = serde_json::Value::String(mask_secret(secret));
+        }
+    }
+    Ok(value)
+}
+
+fn mask_secret(secret: &str) -> String {
+    if secret.is_empty() {
+        String::new()
+    } else if secret.len() > 10 {
+        format!("{}...{}", &secret[..6], &secret[secret.len() - 4..])
+    } else {
+        "****".to_string()
+    }
+}
+
+/// Value of `key` (camelCase or snake_case; `cluster.role` for nested fields), secrets masked.
+pub fn config_get(config: &AgentConfig, key: &str) -> Result<serde_json::Value, String> {
+    let mut root = masked_config(config)?;
+    config_value_mut(&mut root, key).map(|v| v.take())
+}
+
+/// Copy of `config` with `key` set from its command-line form. The text is read as
+/// the field's current type: numbers and booleans are parsed, lists take JSON or a
+/// comma-separated string, and "null" clears an optional field.
+pub fn config_set(config: &AgentConfig, key: &str, raw: &str) -> Result<AgentConfig, String> {
+    let mut root =
+        serde_json::to_value(config).map_err(|e| format!("Failed to serialize config: {}", e))?;
+    let slot = config_value_mut(&mut root, key)?;
+    let parsed = match slot {
+        serde_json::Value::String(_) => serde_json::Value::String(raw.to_string()),
+        serde_json::Value::Array(_) if !raw.trim_start().starts_with('[') => {
+            let items: Vec<serde_json::Value> = raw
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(|s| serde_json::from_str(s).unwrap_or_else(|_| serde_json::Value::String(s.into())))
+                .collect();
+            serde_json::Value::Array(items)
+        }
+        serde_json::Value::Null => match serde_json::from_str(raw) {
+            Ok(v @ serde_json::Value::Null) => v,
+            _ => serde_json::Value::String(raw.to_string()),
+        },
+        serde_json::Value::Object(_) => {
+            return Err(format!("'{}' is a section; set one of its fields, e.g. {}.<field>", key, key))
+        }
+        _ => serde_json::from_str(raw).map_err(|_| format!("Invalid value for {}: {}", key, raw))?,
+    };
+    *slot = parsed;
+    let updated: AgentConfig =
+        serde_json::from_value(root).map_err(|e| format!("Invalid value for {}: {}", key, e))?;
+    updated.validate()?;
+    Ok(updated)
+}
+
+/// Copy of `config` with `key` reset to its default.
+pub fn config_unset(config: &AgentConfig, key: &str) -> Result<AgentConfig, String> {
+    let mut defaults = serde_json::to_value(AgentConfig::default())
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    let default_value = config_value_mut(&mut defaults, key)?.take();
+    let mut root =
+        serde_json::to_value(config).map_err(|e| format!("Failed to serialize config: {}", e))?;
+    *config_value_mut(&mut root, key)? = default_value;
+    serde_json::from_value(root).map_err(|e| format!("Invalid config: {}", e))
+}
+
 /// Map HuggingFace GGUF repo to oracle model ID.
 pub fn oracle_model_name(gguf_repo: &str) -> &'static str {
     match gguf_repo {
@@ -182,6 +1086,16 @@ pub fn oracle_model_name(gguf_repo: &str) -> &'static str {
     }
 }
 
+/// Map an embedding/reranker GGUF repo to its oracle model ID, e.g.
+/// "nomic-ai/nomic-embed-text-v1.5-GGUF" -> "nomic-ai/nomic-embed-text-v1.5".
+pub fn oracle_model_id_from_repo(gguf_repo: &str) -> String {
+    let lower = gguf_repo.to_lowercase();
+    lower
+        .strip_suffix("-gguf")
+        .unwrap_or(&lower)
+        .to_string()
+}
+
 /// Get the default config directory for CLI usage.
 pub fn default_config_dir() -> PathBuf {
     dirs_next().unwrap_or_else(|| PathBuf::from(".plumise-agent"))
@@ -0,0 +1,97 @@
+//! llama-server crash records: `<config_dir>/crashes/<unix_millis>.json`, one file per
+//! unexpected exit, with the output that led up to it. Only the newest
+//! `MAX_CRASH_RECORDS` are kept.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::AgentConfig;
+use crate::ledger::unix_millis;
+
+const CRASH_DIR_NAME: &str = "crashes";
+const MAX_CRASH_RECORDS: usize = 50;
+/// Log lines kept with each record.
+pub const CRASH_LOG_LINES: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashRecord {
+    /// Unix epoch milliseconds.
+    pub timestamp: u64,
+    pub exit_code: Option<i32>,
+    /// `system::describe_exit_code` for the exit code.
+    pub description: String,
+    /// Oldest first, at most `CRASH_LOG_LINES`.
+    pub log_tail: Vec<String>,
+    /// The config llama-server was launched with, secrets blanked.
+    pub config: serde_json::Value,
+}
+
+impl CrashRecord {
+    pub fn new(exit_code: Option<i32>, log_tail: Vec<String>, config: &AgentConfig) -> Self {
+        let mut config = config.clone();
+        config.private_key.clear();
+        config.hf_token.clear();
+        config.alerts.telegram_bot_token.clear();
+        config.alerts.discord_webhook_url.clear();
+        let skip = log_tail.len().saturating_sub(CRASH_LOG_LINES);
+        CrashRecord {
+            timestamp: unix_millis(),
+            exit_code,
+            description: crate::system::describe_exit_code(exit_code),
+            log_tail: log_tail.into_iter().skip(skip).collect(),
+            config: serde_json::to_value(&config).unwrap_or_default(),
+        }
+    }
+}
+
+fn crash_dir(config_dir: &Path) -> PathBuf {
+    config_dir.join(CRASH_DIR_NAME)
+}
+
+/// Crash files, oldest first (names are timestamps).
+fn crash_files(config_dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(crash_dir(config_dir))
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// Write `record` and drop the oldest records beyond `MAX_CRASH_RECORDS`.
+pub fn record_crash(config_dir: &Path, record: &CrashRecord) -> Result<PathBuf, String> {
+    let dir = crash_dir(config_dir);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir.join(format!("{:013}.json", record.timestamp));
+    let json = serde_json::to_string_pretty(record)
+        .map_err(|e| format!("Failed to serialize crash record: {}", e))?;
+    std::fs::write(&path, json)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    let files = crash_files(config_dir);
+    for old in files.iter().take(files.len().saturating_sub(MAX_CRASH_RECORDS)) {
+        let _ = std::fs::remove_file(old);
+    }
+    Ok(path)
+}
+
+/// Up to `limit` crash records, newest first. Unreadable files are skipped.
+pub fn crash_history(config_dir: &Path, limit: usize) -> Vec<CrashRecord> {
+    crash_files(config_dir)
+        .iter()
+        .rev()
+        .filter_map(|path| {
+            let data = std::fs::read_to_string(path).ok()?;
+            serde_json::from_str(&data).ok()
+        })
+        .take(limit)
+        .collect()
+}
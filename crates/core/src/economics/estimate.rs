@@ -0,0 +1,95 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use crate::ledger::{Ledger, LedgerEntry};
+
+/// Window of ledger history used for utilization (7 days).
+const UTILIZATION_WINDOW_MS: u64 = 7 * 24 * 3600 * 1000;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EarningsEstimate {
+    pub benchmark_tok_per_sec: f64,
+    /// Fraction of online time spent serving requests (0.0–1.0).
+    pub utilization: f64,
+    pub uptime_hours_per_day: f64,
+    pub tokens_per_day: f64,
+    /// Reward in wei per served token, as observed since the last claim (decimal string,
+    /// may exceed f64 precision; see `projection`). None until there is a window with
+    /// served tokens to measure it on; the PLM figures are then unknown too.
+    pub reward_rate_wei: Option<String>,
+    pub plm_per_day: Option<f64>,
+    pub plm_per_week: Option<f64>,
+    pub plm_per_month: Option<f64>,
+}
+
+/// Expected rewards given throughput, utilization and the observed per-token rate.
+pub fn estimate_rewards(
+    benchmark_tok_per_sec: f64,
+    utilization: f64,
+    uptime_hours_per_day: f64,
+    reward_rate_wei: Option<u128>,
+) -> EarningsEstimate {
+    let utilization = utilization.clamp(0.0, 1.0);
+    let uptime_hours_per_day = uptime_hours_per_day.clamp(0.0, 24.0);
+    let tokens_per_day =
+        benchmark_tok_per_sec.max(0.0) * uptime_hours_per_day * 3600.0 * utilization;
+    let plm_per_day = reward_rate_wei.map(|rate| tokens_per_day * (rate as f64 / 1e18));
+
+    EarningsEstimate {
+        benchmark_tok_per_sec,
+        utilization,
+        uptime_hours_per_day,
+        tokens_per_day,
+        reward_rate_wei: reward_rate_wei.map(|rate| rate.to_string()),
+        plm_per_day,
+        plm_per_week: plm_per_day.map(|plm| plm * 7.0),
+        plm_per_month: plm_per_day.map(|plm| plm * 30.0),
+    }
+}
+
+/// Busy time divided by observed time over the ledger window.
+/// Returns 0 when there is no history yet.
+pub fn historical_utilization(entries: &[LedgerEntry], now_ms: u64) -> f64 {
+    let window_start = now_ms.saturating_sub(UTILIZATION_WINDOW_MS);
+    let recent: Vec<&LedgerEntry> = entries.iter().filter(|e| e.started_at >= window_start).collect();
+    let first = match recent.iter().map(|e| e.started_at).min() {
+        Some(t) => t,
+        None => return 0.0,
+    };
+    let observed_ms = now_ms.saturating_sub(first).max(1);
+    let busy_ms: u64 = recent.iter().map(|e| e.duration_ms).sum();
+    (busy_ms as f64 / observed_ms as f64).min(1.0)
+}
+
+/// Measure the per-token rate from the pending reward (needs `agent_address`) and
+/// combine it with throughput and ledger history.
+pub async fn estimate_from_chain(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    agent_address: Option<&str>,
+    config_dir: &Path,
+    benchmark_tok_per_sec: f64,
+    ledger: Option<&Ledger>,
+    uptime_hours_per_day: f64,
+) -> Result<EarningsEstimate, String> {
+    let reward_rate = match agent_address {
+        Some(address) => {
+            super::projection::observed_projection(client, rpc_url, address, config_dir)
+                .await?
+                .and_then(|p| p.wei_per_token)
+                .and_then(|rate| rate.parse().ok())
+        }
+        None => None,
+    };
+    let utilization = match ledger {
+        Some(l) => historical_utilization(&l.entries(None)?, crate::ledger::unix_millis()),
+        None => 0.0,
+    };
+    Ok(estimate_rewards(
+        benchmark_tok_per_sec,
+        utilization,
+        uptime_hours_per_day,
+        reward_rate,
+    ))
+}
@@ -0,0 +1,106 @@
+//! Reward projection from what the pool has actually paid this node.
+//!
+//! Unlike `estimate`, which starts from benchmark throughput, this reads the pending
+//! reward (everything accrued since the last claim), divides it by the time since that
+//! claim, and assumes the next days look the same. Only `getPendingReward(address)` is
+//! read from the pool; the window comes from the claim history and the token count
+//! from the ledger.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::chain::rewards::get_pending_reward;
+use crate::claims::{ClaimHistory, ClaimStatus};
+use crate::ledger::Ledger;
+use crate::lifetime::LifetimeStats;
+
+/// Shorter windows extrapolate a few requests into a daily figure; wait for more.
+const MIN_WINDOW_SECS: u64 = 3600;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RewardProjection {
+    /// Start of the window (unix epoch milliseconds): the last confirmed claim, or the
+    /// first launch when nothing has been claimed yet.
+    pub since: u64,
+    pub window_secs: u64,
+    /// Pending reward, i.e. what accrued in the window (decimal string, may exceed f64 precision).
+    pub accrued_wei: String,
+    /// Prompt and completion tokens served in the window, from the ledger.
+    pub tokens: u64,
+    /// Accrued wei per served token (decimal string); None without served tokens.
+    pub wei_per_token: Option<String>,
+    pub plm_per_day: f64,
+    pub plm_per_week: f64,
+}
+
+/// Project rewards from `accrued_wei` earned between `since_ms` and `now_ms`.
+pub fn project(accrued_wei: u128, since_ms: u64, now_ms: u64, tokens: u64) -> RewardProjection {
+    let window_secs = now_ms.saturating_sub(since_ms) / 1000;
+    let plm_per_day = if window_secs == 0 {
+        0.0
+    } else {
+        accrued_wei as f64 / 1e18 * 86_400.0 / window_secs as f64
+    };
+
+    RewardProjection {
+        since: since_ms,
+        window_secs,
+        accrued_wei: accrued_wei.to_string(),
+        tokens,
+        wei_per_token: (tokens > 0).then(|| (accrued_wei / tokens as u128).to_string()),
+        plm_per_day,
+        plm_per_week: plm_per_day * 7.0,
+    }
+}
+
+/// Start of the current accrual window: the last confirmed claim in the history,
+/// otherwise the first launch. None when neither is known.
+pub fn accrual_start(config_dir: &Path) -> Result<Option<u64>, String> {
+    let last_claim = ClaimHistory::open(config_dir)?
+        .records()?
+        .iter()
+        .filter(|r| r.status == ClaimStatus::Confirmed)
+        .map(|r| r.at)
+        .max();
+    Ok(last_claim.or_else(|| {
+        LifetimeStats::load(config_dir)
+            .first_started_at
+            .map(|secs| secs.saturating_mul(1000))
+    }))
+}
+
+/// Read the pending reward over RPC and project it over the accrual window. Ok(None)
+/// while there is no window yet or it is shorter than `MIN_WINDOW_SECS`; RPC failures
+/// are errors.
+pub async fn observed_projection(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    agent_address: &str,
+    config_dir: &Path,
+) -> Result<Option<RewardProjection>, String> {
+    let now = crate::ledger::unix_millis();
+    let Some(since) = accrual_start(config_dir)? else {
+        return Ok(None);
+    };
+    if now.saturating_sub(since) / 1000 < MIN_WINDOW_SECS {
+        return Ok(None);
+    }
+    let accrued = get_pending_reward(client, rpc_url, agent_address).await?;
+    let summary = Ledger::open(config_dir)?.summary(Some(since))?;
+    let tokens = summary.prompt_tokens + summary.completion_tokens;
+    Ok(Some(project(accrued, since, now, tokens)))
+}
+
+/// Like `observed_projection`, but a missing or too short window is an error too.
+pub async fn projection(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    agent_address: &str,
+    config_dir: &Path,
+) -> Result<RewardProjection, String> {
+    observed_projection(client, rpc_url, agent_address, config_dir)
+        .await?
+        .ok_or_else(|| "less than an hour of rewards observed since the last claim".to_string())
+}
@@ -0,0 +1,103 @@
+//! Typed errors for the core entry points (config, Oracle registration, llama-server,
+//! model download, chain transactions and RewardPool reads). Lower-level helpers still return `Result<_, String>`; `?` turns
+//! those into `CoreError::Other`, and `CoreError` converts back into `String` for
+//! callers that haven't moved over yet.
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CoreError {
+    /// Missing or invalid settings, unreadable config file.
+    #[error("{0}")]
+    Config(String),
+    /// Could not reach a remote host at all.
+    #[error("{0}")]
+    Network(String),
+    /// The Oracle answered with an error or rejected the request.
+    #[error("{0}")]
+    Oracle(String),
+    /// RPC or transaction failure on the Plumise chain.
+    #[error("{0}")]
+    Chain(String),
+    /// llama-server / rpc-server failed to start or crashed.
+    #[error("{0}")]
+    Process(String),
+    /// Model download, verification or disk space.
+    #[error("{0}")]
+    Model(String),
+    /// Not categorized yet (errors from `Result<_, String>` helpers).
+    #[error("{0}")]
+    Other(String),
+}
+
+pub type CoreResult<T> = Result<T, CoreError>;
+
+impl CoreError {
+    /// Stable lowercase name of the category, e.g. "oracle".
+    pub fn category(&self) -> &'static str {
+        match self {
+            CoreError::Config(_) => "config",
+            CoreError::Network(_) => "network",
+            CoreError::Oracle(_) => "oracle",
+            CoreError::Chain(_) => "chain",
+            CoreError::Process(_) => "process",
+            CoreError::Model(_) => "model",
+            CoreError::Other(_) => "other",
+        }
+    }
+
+    /// Numeric code per category; the CLI exits with it.
+    pub fn code(&self) -> i32 {
+        match self {
+            CoreError::Other(_) => 1,
+            CoreError::Config(_) => 10,
+            CoreError::Network(_) => 11,
+            CoreError::Oracle(_) => 12,
+            CoreError::Chain(_) => 13,
+            CoreError::Process(_) => 14,
+            CoreError::Model(_) => 15,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            CoreError::Config(m)
+            | CoreError::Network(m)
+            | CoreError::Oracle(m)
+            | CoreError::Chain(m)
+            | CoreError::Process(m)
+            | CoreError::Model(m)
+            | CoreError::Other(m) => m,
+        }
+    }
+}
+
+/// Sent to the frontend as `{ category, code, message }`.
+impl Serialize for CoreError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("CoreError", 3)?;
+        s.serialize_field("category", self.category())?;
+        s.serialize_field("code", &self.code())?;
+        s.serialize_field("message", self.message())?;
+        s.end()
+    }
+}
+
+impl From<String> for CoreError {
+    fn from(message: String) -> Self {
+        CoreError::Other(message)
+    }
+}
+
+impl From<&str> for CoreError {
+    fn from(message: &str) -> Self {
+        CoreError::Other(message.to_string())
+    }
+}
+
+impl From<CoreError> for String {
+    fn from(e: CoreError) -> Self {
+        e.to_string()
+    }
+}
@@ -0,0 +1,135 @@
+//! Process-wide bus for the agent lifecycle events operators alert on. Producers
+//! `publish` and move on; `webhook` and `alerts` `subscribe`. Nothing is
+//! buffered for subscribers that don't exist yet.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use serde::Serialize;
+
+static BUS: OnceLock<tokio::sync::broadcast::Sender<Event>> = OnceLock::new();
+/// Deliveries started with `spawn_delivery` and not finished yet, for `flush`.
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+fn bus() -> &'static tokio::sync::broadcast::Sender<Event> {
+    BUS.get_or_init(|| tokio::sync::broadcast::channel(64).0)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "event")]
+pub enum AgentEvent {
+    /// llama-server is up and the node registered (or tried to).
+    #[serde(rename = "agent-started", rename_all = "camelCase")]
+    Started { model: String, endpoint: String },
+    #[serde(rename = "agent-stopped", rename_all = "camelCase")]
+    Stopped { reason: String },
+    #[serde(rename = "agent-crashed", rename_all = "camelCase")]
+    Crashed { reason: String },
+    #[serde(rename = "registration-failed", rename_all = "camelCase")]
+    RegistrationFailed { error: String },
+    #[serde(rename = "reward-claimed", rename_all = "camelCase")]
+    RewardClaimed { amount_plm: f64, tx_hash: String },
+    #[serde(rename = "download-completed", rename_all = "camelCase")]
+    DownloadCompleted { file: String, repo: String, size_bytes: u64 },
+}
+
+impl AgentEvent {
+    /// Every `kind`, for validating event filters.
+    pub const KINDS: &'static [&'static str] = &[
+        "agent-started",
+        "agent-stopped",
+        "agent-crashed",
+        "registration-failed",
+        "reward-claimed",
+        "download-completed",
+    ];
+
+    /// Stable name used in payloads and event filters.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AgentEvent::Started { .. } => "agent-started",
+            AgentEvent::Stopped { .. } => "agent-stopped",
+            AgentEvent::Crashed { .. } => "agent-crashed",
+            AgentEvent::RegistrationFailed { .. } => "registration-failed",
+            AgentEvent::RewardClaimed { .. } => "reward-claimed",
+            AgentEvent::DownloadCompleted { .. } => "download-completed",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            AgentEvent::Started { .. } => "Agent started",
+            AgentEvent::Stopped { .. } => "Agent stopped",
+            AgentEvent::Crashed { .. } => "Agent stopped unexpectedly",
+            AgentEvent::RegistrationFailed { .. } => "Oracle registration failed",
+            AgentEvent::RewardClaimed { .. } => "Rewards claimed",
+            AgentEvent::DownloadCompleted { .. } => "Model downloaded",
+        }
+    }
+
+    /// One line for chat messages and logs.
+    pub fn message(&self) -> String {
+        match self {
+            AgentEvent::Started { model, endpoint } => format!("Serving {} at {}", model, endpoint),
+            AgentEvent::Stopped { reason } => reason.clone(),
+            AgentEvent::Crashed { reason } => reason.clone(),
+            AgentEvent::RegistrationFailed { error } => error.clone(),
+            AgentEvent::RewardClaimed { amount_plm, tx_hash } => {
+                format!("{:.4} PLM claimed (tx {})", amount_plm, tx_hash)
+            }
+            AgentEvent::DownloadCompleted { file, size_bytes, .. } => {
+                format!("{} ({:.1} GB) is ready to serve", file, *size_bytes as f64 / 1e9)
+            }
+        }
+    }
+}
+
+/// An `AgentEvent` with when it happened.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Event {
+    /// Unix seconds.
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub event: AgentEvent,
+}
+
+/// Send `event` to every current subscriber.
+pub fn publish(event: AgentEvent) {
+    log::debug!("Event {}: {}", event.kind(), event.message());
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    // Err only means nobody is listening
+    let _ = bus().send(Event { timestamp, event });
+}
+
+pub fn subscribe() -> tokio::sync::broadcast::Receiver<Event> {
+    bus().subscribe()
+}
+
+/// Run the delivery of an event to an outside service in the background, counted
+/// by `flush`.
+pub fn spawn_delivery<F>(delivery: F)
+where
+    F: std::future::Future<Output = ()> + Send + 'static,
+{
+    IN_FLIGHT.fetch_add(1, Ordering::SeqCst);
+    tokio::spawn(async move {
+        delivery.await;
+        IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+    });
+}
+
+/// Wait up to `timeout` for deliveries to finish, e.g. before the process exits
+/// right after publishing `Stopped`.
+pub async fn flush(timeout: Duration) {
+    let deadline = tokio::time::Instant::now() + timeout;
+    // Let subscribers pick up events published just before
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    while IN_FLIGHT.load(Ordering::SeqCst) > 0 && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
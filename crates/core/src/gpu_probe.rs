@@ -0,0 +1,234 @@
+//! Check that GPU offload actually works, not just that a driver is installed: start
+//! llama-server on a tiny model with `-ngl 1`, read which backend it loaded and how
+//! many layers it offloaded, and time a short completion.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Tiny test model (~1 MB) from llama.cpp's own test suite.
+const PROBE_MODEL_URL: &str =
+    "https://huggingface.co/ggml-org/models/resolve/main/tinyllamas/stories260K.gguf";
+const PROBE_MODEL_FILE: &str = "probe-stories260K.gguf";
+const READY_TIMEOUT: Duration = Duration::from_secs(60);
+/// llama-server output kept for the report.
+const LOG_TAIL_LINES: usize = 40;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuProbeResult {
+    /// A layer was offloaded and a completion ran.
+    pub ok: bool,
+    /// GPU backend llama.cpp loaded ("CUDA", "Metal", "Vulkan", ...), if any.
+    pub backend: Option<String>,
+    pub offloaded_layers: Option<u32>,
+    pub tok_per_sec: Option<f64>,
+    pub duration_ms: u64,
+    pub message: String,
+    pub log_tail: Vec<String>,
+}
+
+/// Download the probe model into `models_dir` once.
+async fn ensure_probe_model(models_dir: &Path) -> Result<PathBuf, String> {
+    let path = models_dir.join(PROBE_MODEL_FILE);
+    if path.exists() {
+        return Ok(path);
+    }
+    std::fs::create_dir_all(models_dir)
+        .map_err(|e| format!("Failed to create models dir: {}", e))?;
+    let bytes = crate::http::client()
+        .get(PROBE_MODEL_URL)
+        .timeout(Duration::from_secs(60))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Probe model download failed: {}", e))?
+        .bytes()
+        .await
+        .map_err(|e| format!("Probe model download failed: {}", e))?;
+    let partial = path.with_extension("gguf.partial");
+    std::fs::write(&partial, &bytes).map_err(|e| format!("Failed to write probe model: {}", e))?;
+    std::fs::rename(&partial, &path).map_err(|e| format!("Failed to write probe model: {}", e))?;
+    Ok(path)
+}
+
+/// GPU backend named in a llama.cpp log line.
+fn parse_backend(line: &str) -> Option<String> {
+    // "load_backend: loaded CUDA backend from ..."
+    if let Some(rest) = line.split("load_backend: loaded ").nth(1) {
+        let name = rest.split_whitespace().next()?;
+        if name != "CPU" && name != "RPC" {
+            return Some(name.to_string());
+        }
+        return None;
+    }
+    [
+        ("ggml_cuda_init", "CUDA"),
+        ("ggml_metal_init", "Metal"),
+        ("ggml_vulkan", "Vulkan"),
+        ("ggml_sycl", "SYCL"),
+        ("ggml_hip", "ROCm"),
+    ]
+    .iter()
+    .find(|(marker, _)| line.contains(marker))
+    .map(|(_, name)| name.to_string())
+}
+
+/// "offloaded 1/6 layers to GPU" -> 1
+fn parse_offloaded(line: &str) -> Option<u32> {
+    let rest = line.split("offloaded ").nth(1)?;
+    if !rest.contains("layers to GPU") {
+        return None;
+    }
+    rest.split('/').next()?.trim().parse().ok()
+}
+
+/// Drain buffered llama-server output into `log`, picking out backend and offload lines.
+fn collect(
+    rx: &mut tokio::sync::mpsc::UnboundedReceiver<String>,
+    result: &mut GpuProbeResult,
+    log: &mut Vec<String>,
+) {
+    while let Ok(line) = rx.try_recv() {
+        if result.backend.is_none() {
+            result.backend = parse_backend(&line);
+        }
+        if let Some(n) = parse_offloaded(&line) {
+            result.offloaded_layers = Some(n);
+        }
+        log.push(line);
+    }
+}
+
+/// Run the probe with `llama_server` (its directory holds the backend libraries).
+pub async fn probe_gpu(llama_server: &Path, models_dir: &Path) -> GpuProbeResult {
+    let started = Instant::now();
+    let mut result = run_probe(llama_server, models_dir)
+        .await
+        .unwrap_or_else(|message| GpuProbeResult {
+            message,
+            ..Default::default()
+        });
+    result.duration_ms = started.elapsed().as_millis() as u64;
+    result
+}
+
+async fn run_probe(llama_server: &Path, models_dir: &Path) -> Result<GpuProbeResult, String> {
+    use tokio::io::AsyncBufReadExt;
+
+    let model = ensure_probe_model(models_dir).await?;
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|l| l.local_addr())
+        .map(|a| a.port())
+        .map_err(|e| format!("No free port for the probe: {}", e))?;
+
+    let mut cmd = tokio::process::Command::new(llama_server);
+    cmd.args([
+        "-m",
+        &model.to_string_lossy(),
+        "--host",
+        "127.0.0.1",
+        "--port",
+    ])
+    .arg(port.to_string())
+    .args(["-ngl", "1", "-c", "256", "-np", "1"])
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::piped())
+    .kill_on_drop(true);
+    if let Some(dir) = llama_server.parent() {
+        cmd.current_dir(dir).env("GGML_BACKEND_DIR", dir);
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to start llama-server: {}", e))?;
+
+    // llama.cpp logs to stderr; stdout is drained so it can't block
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+    if let Some(stdout) = child.stdout.take() {
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let mut lines = tokio::io::BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let _ = tx.send(line);
+            }
+        });
+    }
+    if let Some(stderr) = child.stderr.take() {
+        tokio::spawn(async move {
+            let mut lines = tokio::io::BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let _ = tx.send(line);
+            }
+        });
+    }
+
+    let client = crate::http::local();
+    let mut result = GpuProbeResult::default();
+    let mut log = Vec::new();
+
+    let deadline = Instant::now() + READY_TIMEOUT;
+    let ready = loop {
+        collect(&mut rx, &mut result, &mut log);
+        if let Ok(Some(status)) = child.try_wait() {
+            break Err(format!("llama-server exited during the probe ({})", status));
+        }
+        let healthy = client
+            .get(format!("http://127.0.0.1:{}/health", port))
+            .timeout(Duration::from_secs(2))
+            .send()
+            .await
+            .map(|r| r.status().is_success())
+            .unwrap_or(false);
+        if healthy {
+            break Ok(());
+        }
+        if Instant::now() >= deadline {
+            break Err("llama-server did not become ready".to_string());
+        }
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    };
+
+    if ready.is_ok() {
+        let completion = client
+            .post(format!("http://127.0.0.1:{}/completion", port))
+            .json(&serde_json::json!({ "prompt": "Once upon a time", "n_predict": 32 }))
+            .timeout(Duration::from_secs(30))
+            .send()
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|r| r.error_for_status().map_err(|e| e.to_string()));
+        match completion {
+            Ok(resp) => {
+                let body: serde_json::Value = resp.json().await.unwrap_or_default();
+                result.tok_per_sec = body["timings"]["predicted_per_second"].as_f64();
+            }
+            Err(e) => log.push(format!("probe completion failed: {}", e)),
+        }
+    }
+    let _ = child.kill().await;
+    collect(&mut rx, &mut result, &mut log);
+
+    let offloaded = result.offloaded_layers.unwrap_or(0) > 0;
+    result.ok = ready.is_ok() && offloaded && result.tok_per_sec.is_some();
+    result.message = match (&ready, offloaded, &result.backend) {
+        (Err(e), _, _) => e.clone(),
+        (Ok(()), false, _) => "llama-server ran but offloaded no layers to the GPU".to_string(),
+        (Ok(()), true, _) if result.tok_per_sec.is_none() => {
+            "GPU offload loaded but the test completion failed".to_string()
+        }
+        (Ok(()), true, backend) => format!(
+            "{} offload works ({:.0} tok/s)",
+            backend.as_deref().unwrap_or("GPU"),
+            result.tok_per_sec.unwrap_or(0.0)
+        ),
+    };
+    let skip = log.len().saturating_sub(LOG_TAIL_LINES);
+    result.log_tail = log.into_iter().skip(skip).collect();
+    Ok(result)
+}
@@ -0,0 +1,62 @@
+//! Hardware detection, done once per process. GPU detection spawns subprocesses
+//! and sysinfo scans are slow, while the answers don't change while the agent
+//! runs; `refresh` re-detects after a driver install.
+
+use std::sync::{Arc, OnceLock, RwLock};
+
+use serde::Serialize;
+
+use crate::system::{CpuFeatures, GpuInfo};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HardwareProfile {
+    pub gpu: Option<GpuInfo>,
+    pub total_ram_mb: u64,
+    pub cpu: CpuFeatures,
+    pub cpu_cores: usize,
+    /// Unix seconds of the detection.
+    pub detected_at: u64,
+}
+
+impl HardwareProfile {
+    /// Detect everything now, bypassing the cache.
+    pub fn detect() -> Self {
+        Self {
+            gpu: crate::system::detect_gpu_info(),
+            total_ram_mb: crate::system::total_ram_mb(),
+            cpu: crate::system::cpu_features(),
+            cpu_cores: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            detected_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        }
+    }
+
+    pub fn vram_mb(&self) -> u64 {
+        self.gpu.as_ref().map(|gpu| gpu.vram_mb).unwrap_or(0)
+    }
+}
+
+fn cache() -> &'static RwLock<Option<Arc<HardwareProfile>>> {
+    static CACHE: OnceLock<RwLock<Option<Arc<HardwareProfile>>>> = OnceLock::new();
+    CACHE.get_or_init(|| RwLock::new(None))
+}
+
+/// The cached profile, detected on first use. Blocking on first call.
+pub fn profile() -> Arc<HardwareProfile> {
+    if let Some(profile) = cache().read().ok().and_then(|p| p.clone()) {
+        return profile;
+    }
+    refresh()
+}
+
+/// Detect again and replace the cached profile. Blocking.
+pub fn refresh() -> Arc<HardwareProfile> {
+    let profile = Arc::new(HardwareProfile::detect());
+    if let Ok(mut cached) = cache().write() {
+        *cached = Some(profile.clone());
+    }
+    profile
+}
@@ -0,0 +1,142 @@
+//! Process-wide HTTP clients. A `reqwest::Client` owns its connection pool, so
+//! building one per call site reconnects every time; these are built once and
+//! cloned (cheap, the pool is shared).
+//!
+//! Neither client has an overall timeout: streamed completions and downloads run
+//! for minutes. Bounded calls set one per request.
+
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+
+/// Up to one idle connection per relay request in flight.
+const LOCAL_POOL_MAX_IDLE: usize = 64;
+const REMOTE_POOL_MAX_IDLE: usize = 8;
+const LOCAL_CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const REMOTE_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const TCP_KEEPALIVE: Duration = Duration::from_secs(30);
+const KEEPALIVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+struct Clients {
+    /// Idle connections are closed after this long (`http_pool_idle_secs`).
+    pool_idle_timeout: Option<Duration>,
+    local: Option<reqwest::Client>,
+    remote: Option<reqwest::Client>,
+}
+
+fn clients() -> &'static RwLock<Clients> {
+    static CLIENTS: OnceLock<RwLock<Clients>> = OnceLock::new();
+    CLIENTS.get_or_init(|| {
+        RwLock::new(Clients {
+            pool_idle_timeout: Some(Duration::from_secs(90)),
+            ..Default::default()
+        })
+    })
+}
+
+/// Apply the configured pool idle timeout. Clients handed out earlier keep their
+/// pool; later calls get rebuilt ones.
+pub fn configure(config: &crate::config::AgentConfig) {
+    if config.llama_keepalive_secs > 0 && config.llama_keepalive_secs >= config.http_pool_idle_secs {
+        log::warn!(
+            "llamaKeepaliveSecs ({}) is not below httpPoolIdleSecs ({}); idle connections will still be closed between pings",
+            config.llama_keepalive_secs,
+            config.http_pool_idle_secs
+        );
+    }
+    if let Ok(mut clients) = clients().write() {
+        *clients = Clients {
+            pool_idle_timeout: (config.http_pool_idle_secs > 0)
+                .then(|| Duration::from_secs(config.http_pool_idle_secs)),
+            ..Default::default()
+        };
+    }
+}
+
+/// The cached client in `slot`, built with `build` on first use.
+fn cached(
+    slot: fn(&mut Clients) -> &mut Option<reqwest::Client>,
+    build: fn(Option<Duration>) -> reqwest::Client,
+) -> reqwest::Client {
+    if let Ok(mut clients) = clients().write() {
+        let idle = clients.pool_idle_timeout;
+        return slot(&mut clients).get_or_insert_with(|| build(idle)).clone();
+    }
+    build(None)
+}
+
+/// Client for the local llama-server: never proxied, Nagle off for per-token streaming.
+pub fn local() -> reqwest::Client {
+    cached(
+        |c| &mut c.local,
+        |idle| {
+            reqwest::Client::builder()
+                .no_proxy()
+                .tcp_nodelay(true)
+                .pool_idle_timeout(idle)
+                .pool_max_idle_per_host(LOCAL_POOL_MAX_IDLE)
+                .connect_timeout(LOCAL_CONNECT_TIMEOUT)
+                .build()
+                .unwrap_or_default()
+        },
+    )
+}
+
+/// Client for the Oracle, chain RPC and downloads. TLS goes through
+/// `tls_pin::client_config`, so pinned hosts are checked during the handshake.
+pub fn client() -> reqwest::Client {
+    cached(
+        |c| &mut c.remote,
+        |idle| {
+            let builder = match crate::tls_pin::client_config(&[b"h2", b"http/1.1"]) {
+                Ok(tls) => reqwest::Client::builder().use_preconfigured_tls(tls),
+                // Nothing else can enforce the pins; with any set, trust no certificate
+                Err(e) => {
+                    log::error!("{}", e);
+                    reqwest::Client::builder().tls_built_in_root_certs(!crate::tls_pin::enabled())
+                }
+            };
+            builder
+                .tcp_keepalive(TCP_KEEPALIVE)
+                .pool_idle_timeout(idle)
+                .pool_max_idle_per_host(REMOTE_POOL_MAX_IDLE)
+                .connect_timeout(REMOTE_CONNECT_TIMEOUT)
+                .user_agent(concat!("plumise-agent/", env!("CARGO_PKG_VERSION")))
+                .build()
+                .unwrap_or_default()
+        },
+    )
+}
+
+/// Ping llama-server's `/health` through the `local` client every `interval`, so
+/// the first relay request after an idle period reuses a live connection instead
+/// of reconnecting (or hitting one the server already closed). `port` is read on
+/// every tick, None while no llama-server runs.
+pub fn start_keepalive<P>(interval: Duration, port: P) -> tokio::task::JoinHandle<()>
+where
+    P: Fn() -> Option<u16> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let client = local();
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            let Some(port) = port() else {
+                continue;
+            };
+            let result = client
+                .get(format!("http://127.0.0.1:{}/health", port))
+                .timeout(KEEPALIVE_TIMEOUT)
+                .send()
+                .await;
+            match result {
+                // Drain the body so the connection goes back to the pool
+                Ok(resp) => {
+                    let _ = resp.bytes().await;
+                }
+                Err(e) => log::debug!("llama-server keepalive failed: {}", e),
+            }
+        }
+    })
+}
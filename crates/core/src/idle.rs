@@ -0,0 +1,168 @@
+//! Idle detection: only serve while nobody is using the machine.
+
+use std::sync::Arc;
+
+use crate::availability::{Availability, PauseSource};
+
+/// Idle policy settings (mirrors the `idle_*` fields of `AgentConfig`).
+#[derive(Debug, Clone)]
+pub struct IdlePolicy {
+    /// "off" | "pause" | "deregister"
+    pub mode: String,
+    /// Seconds without keyboard/mouse input before the machine counts as idle.
+    pub idle_threshold_secs: u64,
+    /// GPU utilization by other processes (%) above which the machine counts as busy.
+    pub gpu_busy_percent: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IdleSample {
+    pub input_idle_secs: Option<u64>,
+    pub foreign_gpu_percent: Option<u32>,
+}
+
+impl IdlePolicy {
+    pub fn enabled(&self) -> bool {
+        self.mode == "pause" || self.mode == "deregister"
+    }
+
+    /// Reason the machine is busy, or None when idle. Unknown readings never count as busy.
+    pub fn busy_reason(&self, sample: &IdleSample) -> Option<&'static str> {
+        if let Some(idle) = sample.input_idle_secs {
+            if idle < self.idle_threshold_secs {
+                return Some("user active");
+            }
+        }
+        if let Some(gpu) = sample.foreign_gpu_percent {
+            if gpu > self.gpu_busy_percent {
+                return Some("GPU in use by another application");
+            }
+        }
+        None
+    }
+}
+
+/// Take one idle sample (spawns platform tools; call from a blocking context).
+pub fn sample() -> IdleSample {
+    IdleSample {
+        input_idle_secs: input_idle_secs(),
+        foreign_gpu_percent: foreign_gpu_utilization(),
+    }
+}
+
+/// Seconds since the last keyboard/mouse input, if the platform exposes it.
+#[cfg(target_os = "linux")]
+pub fn input_idle_secs() -> Option<u64> {
+    // X11 only; Wayland sessions and headless servers report None (treated as idle)
+    let output = std::process::Command::new("xprintidle")
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let ms: u64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+    Some(ms / 1000)
+}
+
+#[cfg(target_os = "macos")]
+pub fn input_idle_secs() -> Option<u64> {
+    let output = std::process::Command::new("ioreg")
+        .args(["-c", "IOHIDSystem", "-d", "4"])
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let ns: u64 = stdout
+        .lines()
+        .find(|l| l.contains("\"HIDIdleTime\""))
+        .and_then(|l| l.split('=').nth(1))
+        .and_then(|v| v.trim().parse().ok())?;
+    Some(ns / 1_000_000_000)
+}
+
+#[cfg(windows)]
+pub fn input_idle_secs() -> Option<u64> {
+    use windows_sys::Win32::System::SystemInformation::GetTickCount;
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    // SAFETY: `info` is a valid, correctly sized LASTINPUTINFO.
+    let ok = unsafe { GetLastInputInfo(&mut info) };
+    if ok == 0 {
+        return None;
+    }
+    // SAFETY: no preconditions.
+    let now = unsafe { GetTickCount() };
+    Some(now.wrapping_sub(info.dwTime) as u64 / 1000)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn input_idle_secs() -> Option<u64> {
+    None
+}
+
+/// Total SM utilization (%) of GPU processes that are not llama-server/rpc-server.
+pub fn foreign_gpu_utilization() -> Option<u32> {
+    let output = std::process::Command::new("nvidia-smi")
+        .args(["pmon", "-c", "1", "-s", "u"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    // "# gpu  pid  type  sm  mem  enc  dec ... command"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let total: u32 = stdout
+        .lines()
+        .filter(|l| !l.trim_start().starts_with('#'))
+        .filter_map(|l| {
+            let parts: Vec<&str> = l.split_whitespace().collect();
+            if parts.len() < 5 {
+                return None;
+            }
+            let command = parts[parts.len() - 1];
+            if command.contains("llama") || command.contains("rpc-server") {
+                return None;
+            }
+            parts[3].parse::<u32>().ok()
+        })
+        .sum();
+    Some(total.min(100))
+}
+
+/// Periodically sample idleness and pause/resume `availability` accordingly.
+pub fn start_idle_watcher(
+    policy: IdlePolicy,
+    availability: Arc<Availability>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let deregister = policy.mode == "deregister";
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(15));
+        loop {
+            interval.tick().await;
+            let sample = match tokio::task::spawn_blocking(sample).await {
+                Ok(s) => s,
+                Err(_) => continue,
+            };
+            match policy.busy_reason(&sample) {
+                Some(reason) => {
+                    if availability.pause(PauseSource::Idle, format!("Machine busy: {}", reason), deregister) {
+                        log::info!("Pausing agent: machine busy ({})", reason);
+                    }
+                }
+                None => {
+                    if availability.resume(PauseSource::Idle) {
+                        log::info!("Machine idle again, resuming agent");
+                    }
+                }
+            }
+        }
+    })
+}
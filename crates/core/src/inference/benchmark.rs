@@ -1,32 +1,109 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+/// Timed iterations after the warmup run.
+const ITERATIONS: usize = 3;
+/// Tokens generated per iteration (EOS is ignored so every run is the same length).
+const GEN_TOKENS: u32 = 128;
+/// Roughly 512 prompt tokens for most tokenizers.
+const PROMPT_REPEAT: usize = 48;
+
+/// Result of the local benchmark suite, reported to the Oracle on registration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkResult {
+    /// Median prompt processing speed.
+    pub prompt_tok_per_sec: f64,
+    /// Median generation speed. This is the node's headline tok/s.
+    pub gen_tok_per_sec: f64,
+    pub prompt_tokens: u64,
+    pub gen_tokens: u64,
+    pub iterations: u32,
+}
 
 #[derive(Deserialize)]
-struct ChatCompletionResponse {
-    usage: Option<Usage>,
+struct CompletionResponse {
+    timings: Option<Timings>,
 }
 
 #[derive(Deserialize)]
-struct Usage {
-    completion_tokens: Option<u64>,
+struct Timings {
+    prompt_n: Option<u64>,
+    prompt_per_second: Option<f64>,
+    predicted_n: Option<u64>,
+    predicted_per_second: Option<f64>,
+}
+
+/// Benchmark the local llama-server.
+///
+/// Runs one warmup completion, then `ITERATIONS` timed runs with prompt caching
+/// disabled. Prompt processing and generation speeds come from llama-server's
+/// own `timings`, so HTTP overhead doesn't skew fast GPUs, and the median of
+/// each is reported.
+#[tracing::instrument(name = "inference.benchmark", skip(client))]
+pub async fn run_benchmark(client: &reqwest::Client, port: u16) -> Result<BenchmarkResult, String> {
+    let prompt = "The quick brown fox jumps over the lazy dog while counting stars. "
+        .repeat(PROMPT_REPEAT);
+
+    // Warmup: loads weights into VRAM and fills caches; timings discarded
+    run_completion(client, port, "Hello", 8).await?;
+
+    let mut prompt_speeds = Vec::with_capacity(ITERATIONS);
+    let mut gen_speeds = Vec::with_capacity(ITERATIONS);
+    let mut prompt_tokens = 0;
+    let mut gen_tokens = 0;
+    for _ in 0..ITERATIONS {
+        let timings = run_completion(client, port, &prompt, GEN_TOKENS).await?;
+        if let Some(pps) = timings.prompt_per_second.filter(|v| v.is_finite() && *v > 0.0) {
+            prompt_speeds.push(pps);
+        }
+        if let Some(tps) = timings.predicted_per_second.filter(|v| v.is_finite() && *v > 0.0) {
+            gen_speeds.push(tps);
+        }
+        prompt_tokens = timings.prompt_n.unwrap_or(prompt_tokens);
+        gen_tokens = timings.predicted_n.unwrap_or(gen_tokens);
+    }
+
+    if gen_speeds.is_empty() {
+        return Err("Benchmark produced no generation timings".to_string());
+    }
+
+    let result = BenchmarkResult {
+        prompt_tok_per_sec: median(&mut prompt_speeds),
+        gen_tok_per_sec: median(&mut gen_speeds),
+        prompt_tokens,
+        gen_tokens,
+        iterations: gen_speeds.len() as u32,
+    };
+    log::info!(
+        "Benchmark: pp {:.1} tok/s ({} tokens), tg {:.2} tok/s ({} tokens), median of {}",
+        result.prompt_tok_per_sec,
+        result.prompt_tokens,
+        result.gen_tok_per_sec,
+        result.gen_tokens,
+        result.iterations
+    );
+
+    Ok(result)
 }
 
-/// Run a quick benchmark against local llama-server.
-/// Sends a short prompt, measures tokens/second from the response.
-pub async fn run_benchmark(client: &reqwest::Client, port: u16) -> Result<f64, String> {
-    let url = format!("http://127.0.0.1:{}/v1/chat/completions", port);
+async fn run_completion(
+    client: &reqwest::Client,
+    port: u16,
+    prompt: &str,
+    n_predict: u32,
+) -> Result<Timings, String> {
+    let url = format!("http://127.0.0.1:{}/completion", port);
 
     let body = serde_json::json!({
-        "model": "default",
-        "messages": [{"role": "user", "content": "Count from 1 to 50, one number per line."}],
-        "max_tokens": 256,
+        "prompt": prompt,
+        "n_predict": n_predict,
         "temperature": 0.0,
+        "ignore_eos": true,
+        "cache_prompt": false,
         "stream": false,
     });
 
-    let start = std::time::Instant::now();
-
-    let resp = client
-        .post(&url)
+    let resp = crate::process::with_api_key(client.post(&url))
         .json(&body)
         .timeout(std::time::Duration::from_secs(120))
         .send()
@@ -37,20 +114,24 @@ pub async fn run_benchmark(client: &reqwest::Client, port: u16) -> Result<f64, S
         return Err(format!("Benchmark HTTP {}", resp.status()));
     }
 
-    let elapsed = start.elapsed().as_secs_f64();
-    let data: ChatCompletionResponse = resp.json().await
+    let data: CompletionResponse = resp
+        .json()
+        .await
         .map_err(|e| format!("Benchmark parse error: {}", e))?;
 
-    let tokens = data.usage
-        .and_then(|u| u.completion_tokens)
-        .unwrap_or(0);
+    data.timings
+        .ok_or_else(|| "llama-server returned no timings".to_string())
+}
 
-    if tokens == 0 || elapsed < 0.1 {
-        return Err("Benchmark produced no tokens or too fast".to_string());
+fn median(values: &mut [f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
     }
-
-    let tok_per_sec = tokens as f64 / elapsed;
-    log::info!("Benchmark: {} tokens in {:.2}s = {:.2} tok/s", tokens, elapsed, tok_per_sec);
-
-    Ok(tok_per_sec)
 }
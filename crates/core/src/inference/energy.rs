@@ -0,0 +1,85 @@
+use std::sync::{Arc, Mutex};
+use serde::{Deserialize, Serialize};
+
+/// Integrates sampled GPU power draw (W) over time into consumed energy (Wh).
+#[derive(Debug, Clone, Default)]
+pub struct EnergyMeter {
+    total_wh: f64,
+    last_sample: Option<(std::time::Instant, f64)>,
+    samples: u64,
+    watts_sum: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnergyReport {
+    pub energy_kwh: f64,
+    pub current_power_watts: f64,
+    pub avg_power_watts: f64,
+    pub energy_cost: f64,
+}
+
+pub type SharedEnergyMeter = Arc<Mutex<EnergyMeter>>;
+
+impl EnergyMeter {
+    /// Add a power sample. Energy between samples uses the trapezoid rule.
+    pub fn record(&mut self, watts: f64) {
+        let now = std::time::Instant::now();
+        if let Some((prev_time, prev_watts)) = self.last_sample {
+            let hours = now.duration_since(prev_time).as_secs_f64() / 3600.0;
+            self.total_wh += (prev_watts + watts) / 2.0 * hours;
+        }
+        self.last_sample = Some((now, watts));
+        self.samples += 1;
+        self.watts_sum += watts;
+    }
+
+    pub fn energy_kwh(&self) -> f64 {
+        self.total_wh / 1000.0
+    }
+
+    pub fn report(&self, price_per_kwh: f64) -> EnergyReport {
+        EnergyReport {
+            energy_kwh: self.energy_kwh(),
+            current_power_watts: self.last_sample.map(|(_, w)| w).unwrap_or(0.0),
+            avg_power_watts: if self.samples > 0 {
+                self.watts_sum / self.samples as f64
+            } else {
+                0.0
+            },
+            energy_cost: self.energy_kwh() * price_per_kwh.max(0.0),
+        }
+    }
+}
+
+/// Net earnings in fiat: reward value minus electricity cost.
+pub fn net_earnings(reward_plm: f64, plm_price: f64, energy_cost: f64) -> f64 {
+    reward_plm * plm_price - energy_cost
+}
+
+/// Sample GPU power draw every `interval_secs` into `meter` until aborted.
+pub fn start_energy_sampler(
+    meter: SharedEnergyMeter,
+    interval_secs: u64,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+        loop {
+            interval.tick().await;
+            let watts = tokio::task::spawn_blocking(crate::system::gpu_power_draw_watts)
+                .await
+                .ok()
+                .flatten();
+            match watts {
+                Some(w) => {
+                    if let Ok(mut m) = meter.lock() {
+                        m.record(w);
+                    }
+                }
+                None => {
+                    log::debug!("No GPU power reading available, energy sampler idle");
+                }
+            }
+        }
+    })
+}
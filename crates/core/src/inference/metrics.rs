@@ -1,3 +1,6 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -10,18 +13,24 @@ pub struct InferenceMetrics {
     pub tps: f64,
     pub uptime: u64,
     pub slots_processing: u64,
+    /// Requests waiting for a free slot.
+    pub requests_deferred: u64,
+    /// KV cache fill across all slots (0.0–1.0); near 1.0 long conversations start
+    /// shifting context or failing.
+    pub kv_cache_usage_ratio: f64,
+    pub kv_cache_tokens: u64,
 }
 
 /// Fetch and parse llama-server /metrics (Prometheus text format)
+#[tracing::instrument(name = "inference.fetch_metrics", level = "debug", skip(client))]
 pub async fn fetch_metrics(
     client: &reqwest::Client,
     port: u16,
 ) -> Result<InferenceMetrics, String> {
     let url = format!("http://127.0.0.1:{}/metrics", port);
 
-    let resp = client
-        .get(&url)
-        .timeout(std::time::Duration::from_secs(3))
+    let resp = crate::process::with_api_key(client.get(&url))
+        .timeout(Duration::from_secs(3))
         .send()
         .await
         .map_err(|e| format!("Metrics fetch failed: {}", e))?;
@@ -34,6 +43,188 @@ pub async fn fetch_metrics(
     parse_prometheus(&text)
 }
 
+/// Check once after readiness that `/metrics` serves llama.cpp counters; without
+/// them every reported total would silently stay at zero.
+pub async fn probe_metrics(client: &reqwest::Client, port: u16) -> Result<(), String> {
+    let url = format!("http://127.0.0.1:{}/metrics", port);
+
+    let resp = crate::process::with_api_key(client.get(&url))
+        .timeout(Duration::from_secs(3))
+        .send()
+        .await
+        .map_err(|e| format!("llama-server /metrics unreachable: {}", e))?;
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(format!(
+            "llama-server /metrics unavailable (HTTP {}); token and request counters will read zero",
+            status.as_u16()
+        ));
+    }
+    let text = resp
+        .text()
+        .await
+        .map_err(|e| format!("Metrics read failed: {}", e))?;
+    if !text.contains("llamacpp:") && !text.contains("llamacpp_") {
+        return Err("llama-server /metrics has no llamacpp counters; token and request counters will read zero".into());
+    }
+    Ok(())
+}
+
+/// llama-server's build from `/props` `build_info`, e.g. "b4722-2f5e26e6" -> "b4722".
+pub async fn fetch_build_info(client: &reqwest::Client, port: u16) -> Result<String, String> {
+    let url = format!("http://127.0.0.1:{}/props", port);
+
+    let props: serde_json::Value = crate::process::with_api_key(client.get(&url))
+        .timeout(Duration::from_secs(3))
+        .send()
+        .await
+        .map_err(|e| format!("Props fetch failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Props parse failed: {}", e))?;
+
+    props["build_info"]
+        .as_str()
+        .and_then(|b| b.split('-').next())
+        .filter(|b| !b.is_empty())
+        .map(str::to_string)
+        .ok_or_else(|| "llama-server reports no build_info".to_string())
+}
+
+/// One llama-server slot, from `/slots`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlotStatus {
+    pub id: u32,
+    pub processing: bool,
+    pub n_ctx: u64,
+    /// Tokens generated so far for the current (or last) task.
+    pub tokens_decoded: u64,
+    /// llama-server's task id while processing.
+    pub task_id: Option<i64>,
+}
+
+/// Fetch and parse llama-server /slots. Newer builds report `is_processing` and
+/// `next_token.n_decoded`; older ones `state` (0 = idle) and a top-level `n_decoded`.
+#[tracing::instrument(name = "inference.fetch_slots", level = "debug", skip(client))]
+pub async fn fetch_slots(client: &reqwest::Client, port: u16) -> Result<Vec<SlotStatus>, String> {
+    let url = format!("http://127.0.0.1:{}/slots", port);
+
+    let body: serde_json::Value = crate::process::with_api_key(client.get(&url))
+        .timeout(Duration::from_secs(3))
+        .send()
+        .await
+        .map_err(|e| format!("Slots fetch failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Slots parse failed: {}", e))?;
+    let slots = body
+        .as_array()
+        .ok_or_else(|| format!("Unexpected /slots response: {}", body))?;
+
+    Ok(slots
+        .iter()
+        .map(|slot| {
+            let processing = slot["is_processing"]
+                .as_bool()
+                .unwrap_or_else(|| slot["state"].as_u64().unwrap_or(0) != 0);
+            SlotStatus {
+                id: slot["id"].as_u64().unwrap_or(0) as u32,
+                processing,
+                n_ctx: slot["n_ctx"].as_u64().unwrap_or(0),
+                tokens_decoded: slot["next_token"]["n_decoded"]
+                    .as_u64()
+                    .or_else(|| slot["n_decoded"].as_u64())
+                    .unwrap_or(0),
+                task_id: slot["id_task"].as_i64().filter(|id| processing && *id >= 0),
+            }
+        })
+        .collect())
+}
+
+/// Latest `/slots` snapshot, kept fresh by `start_slot_poller`.
+#[derive(Debug, Clone, Default)]
+pub struct SharedSlots(std::sync::Arc<std::sync::RwLock<Vec<SlotStatus>>>);
+
+impl SharedSlots {
+    pub fn snapshot(&self) -> Vec<SlotStatus> {
+        self.0.read().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    /// (busy, total) slots; (0, 0) before the first poll.
+    pub fn usage(&self) -> (usize, usize) {
+        self.0
+            .read()
+            .map(|s| (s.iter().filter(|slot| slot.processing).count(), s.len()))
+            .unwrap_or((0, 0))
+    }
+
+    fn set(&self, slots: Vec<SlotStatus>) {
+        if let Ok(mut current) = self.0.write() {
+            *current = slots;
+        }
+    }
+}
+
+/// `/metrics` sampled at most once per `max_age` for each port and shared by every
+/// caller, so concurrent requests reuse one scrape of llama-server.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsSampler(
+    std::sync::Arc<tokio::sync::Mutex<std::collections::HashMap<u16, (Instant, InferenceMetrics)>>>,
+);
+
+impl MetricsSampler {
+    /// The latest sample for `port`, fetched again once older than `max_age`. None
+    /// when the fetch fails.
+    pub async fn sample(
+        &self,
+        client: &reqwest::Client,
+        port: u16,
+        max_age: Duration,
+    ) -> Option<InferenceMetrics> {
+        // Held across the fetch so callers arriving meanwhile wait for its result
+        let mut samples = self.0.lock().await;
+        if let Some((at, metrics)) = samples.get(&port) {
+            if at.elapsed() < max_age {
+                return Some(metrics.clone());
+            }
+        }
+        match fetch_metrics(client, port).await {
+            Ok(metrics) => {
+                samples.insert(port, (Instant::now(), metrics.clone()));
+                Some(metrics)
+            }
+            Err(e) => {
+                log::debug!("{}", e);
+                None
+            }
+        }
+    }
+}
+
+/// Poll `/slots` on the port `port` currently returns (None skips the round).
+pub fn start_slot_poller<P>(
+    client: reqwest::Client,
+    slots: SharedSlots,
+    interval: Duration,
+    port: P,
+) -> tokio::task::JoinHandle<()>
+where
+    P: Fn() -> Option<u16> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let Some(port) = port() else { continue };
+            match fetch_slots(&client, port).await {
+                Ok(current) => slots.set(current),
+                Err(e) => log::debug!("{}", e),
+            }
+        }
+    })
+}
+
 /// Parse Prometheus text format from llama-server
 fn parse_prometheus(text: &str) -> Result<InferenceMetrics, String> {
     let mut metrics = InferenceMetrics::default();
@@ -72,6 +263,15 @@ fn parse_prometheus(text: &str) -> Result<InferenceMetrics, String> {
             "llamacpp:requests_processing" | "llamacpp_requests_processing" => {
                 metrics.slots_processing = value_f64 as u64;
             }
+            "llamacpp:requests_deferred" | "llamacpp_requests_deferred" => {
+                metrics.requests_deferred = value_f64 as u64;
+            }
+            "llamacpp:kv_cache_usage_ratio" | "llamacpp_kv_cache_usage_ratio" => {
+                metrics.kv_cache_usage_ratio = value_f64;
+            }
+            "llamacpp:kv_cache_tokens" | "llamacpp_kv_cache_tokens" => {
+                metrics.kv_cache_tokens = value_f64 as u64;
+            }
             "llamacpp:prompt_seconds_total" | "llamacpp_prompt_seconds_total" => {
                 prompt_seconds = value_f64;
             }
@@ -96,3 +296,90 @@ fn parse_prometheus(text: &str) -> Result<InferenceMetrics, String> {
 
     Ok(metrics)
 }
+
+/// How far back rolling rates look.
+const ROLLING_HISTORY: Duration = Duration::from_secs(5 * 60 + 10);
+
+/// Lifetime and rolling throughput derived from successive counter samples.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Throughput {
+    /// Tokens over generation time since llama-server started (`InferenceMetrics::tps`).
+    pub lifetime_tps: f64,
+    pub tps_1m: f64,
+    pub tps_5m: f64,
+    pub requests_per_min_1m: f64,
+    pub requests_per_min_5m: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CounterSample {
+    at: Instant,
+    tokens: u64,
+    requests: u64,
+}
+
+/// Recent counter samples; rates come from the delta against the sample closest
+/// to the start of each window (or the oldest one, until the window has filled).
+#[derive(Debug, Default)]
+pub struct MetricsHistory {
+    samples: VecDeque<CounterSample>,
+}
+
+impl MetricsHistory {
+    /// Record the current counters (`requests` comes from the caller, since
+    /// llama-server exposes no request counter) and return the rates.
+    pub fn record(&mut self, metrics: &InferenceMetrics, requests: u64) -> Throughput {
+        let now = Instant::now();
+        // A restarted llama-server resets its counters
+        if self
+            .samples
+            .back()
+            .is_some_and(|last| metrics.total_tokens < last.tokens || requests < last.requests)
+        {
+            self.samples.clear();
+        }
+        while self
+            .samples
+            .front()
+            .is_some_and(|s| now.duration_since(s.at) > ROLLING_HISTORY)
+        {
+            self.samples.pop_front();
+        }
+        let current = CounterSample {
+            at: now,
+            tokens: metrics.total_tokens,
+            requests,
+        };
+        self.samples.push_back(current);
+
+        let (tps_1m, requests_per_min_1m) = self.rates(current, Duration::from_secs(60));
+        let (tps_5m, requests_per_min_5m) = self.rates(current, Duration::from_secs(300));
+        Throughput {
+            lifetime_tps: metrics.tps,
+            tps_1m,
+            tps_5m,
+            requests_per_min_1m,
+            requests_per_min_5m,
+        }
+    }
+
+    /// (tokens/s, requests/min) between `current` and the start of `window`.
+    fn rates(&self, current: CounterSample, window: Duration) -> (f64, f64) {
+        let Some(start) = self
+            .samples
+            .iter()
+            .find(|s| current.at.duration_since(s.at) <= window)
+        else {
+            return (0.0, 0.0);
+        };
+        let secs = current.at.duration_since(start.at).as_secs_f64();
+        if secs < 1.0 {
+            return (0.0, 0.0);
+        }
+        (
+            current.tokens.saturating_sub(start.tokens) as f64 / secs,
+            current.requests.saturating_sub(start.requests) as f64 * 60.0 / secs,
+        )
+    }
+}
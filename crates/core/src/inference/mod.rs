@@ -1,3 +1,8 @@
 pub mod benchmark;
+pub mod energy;
 pub mod metrics;
+pub mod playground;
 pub mod proof;
+pub mod proof_batch;
+pub mod rpc_health;
+pub mod stats;
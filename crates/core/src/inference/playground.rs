@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+
+use crate::process::with_api_key;
+
+/// Outcome of a single playground prompt against the local llama-server.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestInferenceResult {
+    pub output: String,
+    /// Time to the first streamed token.
+    pub ttft_ms: f64,
+    pub total_ms: f64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Generation speed from llama-server's timings, or tokens over wall time without them.
+    pub tokens_per_sec: f64,
+}
+
+/// Send `prompt` to the local llama-server as a streaming chat completion,
+/// calling `on_token` for each content delta as it arrives.
+pub async fn test_inference(
+    client: &reqwest::Client,
+    port: u16,
+    prompt: &str,
+    max_tokens: u32,
+    mut on_token: impl FnMut(&str),
+) -> Result<TestInferenceResult, String> {
+    let url = format!("http://127.0.0.1:{}/v1/chat/completions", port);
+    let body = serde_json::json!({
+        "messages": [{ "role": "user", "content": prompt }],
+        "max_tokens": max_tokens,
+        "stream": true,
+    });
+
+    let started = std::time::Instant::now();
+    let resp = with_api_key(client.post(&url))
+        .json(&body)
+        .timeout(std::time::Duration::from_secs(120))
+        .send()
+        .await
+        .map_err(|e| format!("llama-server request failed: {}", e))?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        return Err(format!(
+            "llama-server error {}: {}",
+            status,
+            text.chars().take(200).collect::<String>()
+        ));
+    }
+
+    use futures_util::TryStreamExt;
+    use tokio::io::AsyncBufReadExt;
+    let reader = tokio_util::io::StreamReader::new(
+        resp.bytes_stream()
+            .map_err(std::io::Error::other),
+    );
+    let mut lines = tokio::io::BufReader::new(reader).lines();
+    let mut result = TestInferenceResult::default();
+    let mut chunks = 0u64;
+    let mut predicted_per_second = None;
+
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .map_err(|e| format!("llama-server stream error: {}", e))?
+    {
+        let Some(data) = line.strip_prefix("data: ") else {
+            continue;
+        };
+        if data == "[DONE]" {
+            break;
+        }
+        let Ok(parsed) = serde_json::from_str::<serde_json::Value>(data) else {
+            continue;
+        };
+        // llama-server attaches timings to the final chunk
+        if parsed["timings"].is_object() {
+            result.prompt_tokens = parsed["timings"]["prompt_n"].as_u64().unwrap_or(0);
+            result.completion_tokens = parsed["timings"]["predicted_n"].as_u64().unwrap_or(0);
+            predicted_per_second = parsed["timings"]["predicted_per_second"].as_f64();
+        }
+        if let Some(content) = parsed["choices"][0]["delta"]["content"].as_str() {
+            if !content.is_empty() {
+                if chunks == 0 {
+                    result.ttft_ms = started.elapsed().as_secs_f64() * 1000.0;
+                }
+                chunks += 1;
+                result.output.push_str(content);
+                on_token(content);
+            }
+        }
+    }
+
+    result.total_ms = started.elapsed().as_secs_f64() * 1000.0;
+    // Older llama-server builds omit timings; each chunk is roughly one token
+    if result.completion_tokens == 0 {
+        result.completion_tokens = chunks;
+    }
+    result.tokens_per_sec = predicted_per_second
+        .filter(|v| v.is_finite() && *v > 0.0)
+        .unwrap_or_else(|| {
+            let gen_secs = (result.total_ms - result.ttft_ms) / 1000.0;
+            if gen_secs > 0.0 {
+                result.completion_tokens as f64 / gen_secs
+            } else {
+                0.0
+            }
+        });
+    Ok(result)
+}
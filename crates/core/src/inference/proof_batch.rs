@@ -0,0 +1,432 @@
+//! Batches inference proofs into Merkle roots.
+//!
+//! Submitting one proof per request is too expensive, so completed requests are
+//! collected as `ProofData` leaves and only the root of each batch is submitted,
+//! every `max_leaves` requests or `max_age`, whichever comes first. Every batch,
+//! leaves included, is written to `<config_dir>/proofs/` before submission, so a
+//! disputed request can later be proven with `ProofBatch::merkle_proof`.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use k256::ecdsa::SigningKey;
+use serde::{Deserialize, Serialize};
+
+use crate::chain::crypto::{address_from_key, keccak256, personal_sign};
+use crate::inference::proof::{InferenceProofGenerator, ProofData};
+
+const PROOFS_DIR_NAME: &str = "proofs";
+/// How often the background task checks whether a batch is due.
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// When to close a batch.
+#[derive(Debug, Clone, Copy)]
+pub struct ProofBatchPolicy {
+    pub max_leaves: usize,
+    pub max_age: Duration,
+}
+
+/// Where batch roots are submitted (`proof_submission` in `AgentConfig`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofTarget {
+    Off,
+    Oracle,
+    Chain,
+}
+
+impl ProofTarget {
+    pub fn from_config(mode: &str) -> Self {
+        match mode {
+            "oracle" => ProofTarget::Oracle,
+            "chain" => ProofTarget::Chain,
+            _ => ProofTarget::Off,
+        }
+    }
+}
+
+/// A closed batch: the submitted root plus the leaves needed to prove any request in it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofBatch {
+    pub root: String,
+    pub leaf_count: usize,
+    pub token_count: u64,
+    /// Unix epoch seconds.
+    pub created_at: u64,
+    /// Transaction hash (chain) or "oracle" once accepted; None while pending.
+    pub submission: Option<String>,
+    pub leaves: Vec<ProofData>,
+}
+
+impl ProofBatch {
+    fn new(leaves: Vec<ProofData>) -> Self {
+        let hashes: Vec<[u8; 32]> = leaves.iter().map(leaf_hash).collect();
+        Self {
+            root: format!("0x{}", hex::encode(merkle_root(&hashes))),
+            leaf_count: leaves.len(),
+            token_count: leaves.iter().map(|l| l.token_count).sum(),
+            created_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            submission: None,
+            leaves,
+        }
+    }
+
+    fn file_name(&self) -> String {
+        let root = self.root.strip_prefix("0x").unwrap_or(&self.root);
+        format!("{}-{}.json", self.created_at, root.chars().take(16).collect::<String>())
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize proof batch: {}", e))?;
+        std::fs::write(dir.join(self.file_name()), json)
+            .map_err(|e| format!("Failed to write proof batch: {}", e))
+    }
+
+    /// Sibling hashes proving the leaf with `proof_hash` is part of `root`.
+    pub fn merkle_proof(&self, proof_hash: &str) -> Option<Vec<String>> {
+        let index = self.leaves.iter().position(|l| l.proof_hash == proof_hash)?;
+        let hashes: Vec<[u8; 32]> = self.leaves.iter().map(leaf_hash).collect();
+        Some(
+            merkle_proof(&hashes, index)
+                .iter()
+                .map(|h| format!("0x{}", hex::encode(h)))
+                .collect(),
+        )
+    }
+}
+
+/// Load every stored batch in `dir`, oldest first. Unreadable files are skipped.
+pub fn load_batches(dir: &Path) -> Vec<ProofBatch> {
+    let mut batches: Vec<ProofBatch> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+                .filter_map(|e| std::fs::read_to_string(e.path()).ok())
+                .filter_map(|s| serde_json::from_str(&s).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    batches.sort_by_key(|b| b.created_at);
+    batches
+}
+
+/// leaf = keccak256(proofHash || uint256(tokenCount)), so token counts can't be
+/// inflated after the root is submitted.
+fn leaf_hash(proof: &ProofData) -> [u8; 32] {
+    let proof_hex = proof.proof_hash.strip_prefix("0x").unwrap_or(&proof.proof_hash);
+    let mut data = [0u8; 64];
+    if let Ok(bytes) = hex::decode(proof_hex) {
+        if bytes.len() == 32 {
+            data[..32].copy_from_slice(&bytes);
+        }
+    }
+    data[56..].copy_from_slice(&proof.token_count.to_be_bytes());
+    keccak256(&data)
+}
+
+/// Hash a pair in sorted order (OpenZeppelin `MerkleProof` compatible).
+fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+    let mut data = [0u8; 64];
+    data[..32].copy_from_slice(lo);
+    data[32..].copy_from_slice(hi);
+    keccak256(&data)
+}
+
+/// Merkle root over `leaves`. An odd node at any level is carried up unchanged.
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    if leaves.is_empty() {
+        return [0u8; 32];
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => hash_pair(a, b),
+                [a] => *a,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    level[0]
+}
+
+/// Sibling path from `leaves[index]` up to the root.
+pub fn merkle_proof(leaves: &[[u8; 32]], mut index: usize) -> Vec<[u8; 32]> {
+    let mut proof = Vec::new();
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        let sibling = index ^ 1;
+        if sibling < level.len() {
+            proof.push(level[sibling]);
+        }
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => hash_pair(a, b),
+                [a] => *a,
+                _ => unreachable!(),
+            })
+            .collect();
+        index /= 2;
+    }
+    proof
+}
+
+struct Pending {
+    leaves: Vec<ProofData>,
+    since: Instant,
+}
+
+/// Collects proofs for completed requests. Shared by the relay (which records)
+/// and the submission task started by `start_proof_batcher`.
+pub struct ProofBatcher {
+    generator: InferenceProofGenerator,
+    dir: PathBuf,
+    policy: ProofBatchPolicy,
+    pending: Mutex<Pending>,
+}
+
+impl ProofBatcher {
+    /// Batches are stored in `<config_dir>/proofs/`.
+    pub fn new(
+        generator: InferenceProofGenerator,
+        config_dir: &Path,
+        policy: ProofBatchPolicy,
+    ) -> Result<Self, String> {
+        let dir = config_dir.join(PROOFS_DIR_NAME);
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create proofs directory: {}", e))?;
+        Ok(Self {
+            generator,
+            dir,
+            policy,
+            pending: Mutex::new(Pending {
+                leaves: Vec::new(),
+                since: Instant::now(),
+            }),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Generate and queue the proof for one served request.
+    pub fn record(&self, input: &str, output: &str, token_count: u64) {
+        let proof = self.generator.generate_proof(input, output, token_count);
+        if let Ok(mut pending) = self.pending.lock() {
+            if pending.leaves.is_empty() {
+                pending.since = Instant::now();
+            }
+            pending.leaves.push(proof);
+        }
+    }
+
+    /// Close the pending batch if it is due (or `force` and non-empty) and
+    /// persist it. The returned batch still needs to be submitted.
+    pub fn take_batch(&self, force: bool) -> Option<ProofBatch> {
+        let leaves = {
+            let mut pending = self.pending.lock().ok()?;
+            let due = pending.leaves.len() >= self.policy.max_leaves
+                || pending.since.elapsed() >= self.policy.max_age;
+            if pending.leaves.is_empty() || !(due || force) {
+                return None;
+            }
+            pending.since = Instant::now();
+            std::mem::take(&mut pending.leaves)
+        };
+        let batch = ProofBatch::new(leaves);
+        if let Err(e) = batch.save(&self.dir) {
+            log::warn!("{}", e);
+        }
+        Some(batch)
+    }
+}
+
+/// Submits batch roots to the configured target.
+pub struct ProofSubmitter {
+    pub target: ProofTarget,
+    pub client: reqwest::Client,
+    pub oracle_url: String,
+    pub chain_rpc: String,
+    pub signing_key: SigningKey,
+}
+
+impl ProofSubmitter {
+    /// Returns the tx hash (chain) or "oracle".
+    pub async fn submit(&self, batch: &ProofBatch) -> Result<String, String> {
+        let root_hex = batch.root.strip_prefix("0x").unwrap_or(&batch.root);
+        let root: [u8; 32] = hex::decode(root_hex)
+            .ok()
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| format!("Invalid batch root: {}", batch.root))?;
+
+        match self.target {
+            ProofTarget::Off => Err("Proof submission is disabled".to_string()),
+            ProofTarget::Chain => {
+                let chain_id = crate::chain::tx::fetch_chain_id(&self.client, &self.chain_rpc).await?;
+                let sender = crate::chain::tx::TxSender::new(
+                    self.chain_rpc.clone(),
+                    chain_id,
+                    self.signing_key.clone(),
+                );
+                crate::chain::precompiles::submit_proof_root(
+                    &sender,
+                    root,
+                    batch.leaf_count as u64,
+                    batch.token_count,
+                )
+                .await
+                .map_err(String::from)
+            }
+            ProofTarget::Oracle => self.submit_to_oracle(batch).await.map(|_| "oracle".to_string()),
+        }
+    }
+
+    async fn submit_to_oracle(&self, batch: &ProofBatch) -> Result<(), String> {
+        let address = address_from_key(&self.signing_key);
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| format!("Time error: {}", e))?
+            .as_secs();
+
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct BatchPayload<'a> {
+            address: String,
+            root: &'a str,
+            leaf_count: usize,
+            token_count: u64,
+            timestamp: u64,
+            signature: String,
+        }
+
+        let sign_msg = format!(
+            "{}:{}:{}:{}:{}",
+            address, batch.root, batch.leaf_count, batch.token_count, timestamp
+        );
+        let signature = personal_sign(&sign_msg, &self.signing_key)?;
+
+        let payload = BatchPayload {
+            address,
+            root: &batch.root,
+            leaf_count: batch.leaf_count,
+            token_count: batch.token_count,
+            timestamp,
+            signature,
+        };
+
+        let url = format!("{}/api/v1/proofs/batch", self.oracle_url.trim_end_matches('/'));
+        let resp = self
+            .client
+            .post(&url)
+            .json(&payload)
+            .timeout(Duration::from_secs(30))
+            .send()
+            .await
+            .map_err(|e| format!("Proof batch request failed: {}", e))?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            let status = resp.status();
+            let text = resp.text().await.unwrap_or_default();
+            Err(format!("Proof batch rejected ({}): {}", status, text.chars().take(300).collect::<String>()))
+        }
+    }
+}
+
+/// Close and submit batches in the background. Batches left unsubmitted by an
+/// earlier run (or a failed attempt) are retried on every check.
+pub fn start_proof_batcher(
+    batcher: Arc<ProofBatcher>,
+    submitter: ProofSubmitter,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut unsubmitted: Vec<ProofBatch> = load_batches(batcher.dir())
+            .into_iter()
+            .filter(|b| b.submission.is_none())
+            .collect();
+        if !unsubmitted.is_empty() {
+            log::info!("Retrying {} unsubmitted proof batch(es)", unsubmitted.len());
+        }
+
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            if let Some(batch) = batcher.take_batch(false) {
+                unsubmitted.push(batch);
+            }
+
+            let mut still_pending = Vec::new();
+            for mut batch in unsubmitted.drain(..) {
+                match submitter.submit(&batch).await {
+                    Ok(submission) => {
+                        log::info!(
+                            "Submitted proof root {} ({} requests, {} tokens)",
+                            batch.root,
+                            batch.leaf_count,
+                            batch.token_count
+                        );
+                        batch.submission = Some(submission);
+                        if let Err(e) = batch.save(batcher.dir()) {
+                            log::warn!("{}", e);
+                        }
+                    }
+                    Err(e) => {
+                        log::warn!("Proof batch {} submission failed: {}", batch.root, e);
+                        still_pending.push(batch);
+                    }
+                }
+            }
+            unsubmitted = still_pending;
+        }
+    })
+}
+
+/// Start batching as configured by `proof_submission`; None when it is "off".
+/// The returned batcher is handed to the relay via `RelayContext::proofs`.
+pub fn start_from_config(
+    config: &crate::config::AgentConfig,
+    config_dir: &Path,
+    signing_key: &SigningKey,
+    client: reqwest::Client,
+) -> Option<(Arc<ProofBatcher>, tokio::task::JoinHandle<()>)> {
+    let target = config.proof_target();
+    if target == ProofTarget::Off {
+        return None;
+    }
+    let generator =
+        InferenceProofGenerator::new(&config.oracle_model(), &address_from_key(signing_key));
+    let batcher = match ProofBatcher::new(generator, config_dir, config.proof_batch_policy()) {
+        Ok(b) => Arc::new(b),
+        Err(e) => {
+            log::warn!("Proof batching disabled: {}", e);
+            return None;
+        }
+    };
+    let submitter = ProofSubmitter {
+        target,
+        client,
+        oracle_url: config.oracle_url.clone(),
+        chain_rpc: config.chain_rpc.clone(),
+        signing_key: signing_key.clone(),
+    };
+    log::info!(
+        "Proof batching to {:?}: every {} requests or {} min",
+        target,
+        config.proof_batch_size,
+        config.proof_batch_minutes
+    );
+    let handle = start_proof_batcher(batcher.clone(), submitter);
+    Some((batcher, handle))
+}
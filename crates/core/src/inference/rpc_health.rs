@@ -0,0 +1,180 @@
+//! Readiness and liveness probing for the ggml rpc-server.
+//!
+//! The probe connects to the RPC port and sends the protocol's HELLO command.
+//! rpc-server serves one client at a time, so while a coordinator is attached
+//! the HELLO goes unanswered; an accepted TCP connection still counts as alive.
+
+use serde::Serialize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// `RPC_CMD_GRAPH_COMPUTE` / `RPC_CMD_HELLO` in ggml-rpc.
+const RPC_CMD_GRAPH_COMPUTE: u8 = 10;
+const RPC_CMD_HELLO: u8 = 14;
+const CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+const HELLO_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+/// Consecutive failed probes before the server is reported down.
+const FAILURES_BEFORE_DOWN: u32 = 3;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcProbe {
+    pub connect_ms: f64,
+    /// Protocol version from the HELLO reply, e.g. "2.0.0". None when the server
+    /// is busy with another client or predates HELLO.
+    pub protocol_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcHealth {
+    pub port: u16,
+    pub healthy: bool,
+    pub message: String,
+}
+
+/// Probe the local rpc-server on `port`. Errors only if the port refuses connections.
+pub async fn probe(port: u16) -> Result<RpcProbe, String> {
+    probe_addr(&format!("127.0.0.1:{}", port)).await
+}
+
+/// Probe an rpc-server at `addr` ("host:port").
+pub async fn probe_addr(addr: &str) -> Result<RpcProbe, String> {
+    let start = std::time::Instant::now();
+    let mut stream = connect(addr).await?;
+    let connect_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    let protocol_version = tokio::time::timeout(HELLO_TIMEOUT, hello(&mut stream))
+        .await
+        .ok()
+        .and_then(|r| r.ok());
+    Ok(RpcProbe {
+        connect_ms,
+        protocol_version,
+    })
+}
+
+async fn connect(addr: &str) -> Result<TcpStream, String> {
+    tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(addr))
+        .await
+        .map_err(|_| format!("Connection to {} timed out", addr))?
+        .map_err(|e| format!("{} not accepting connections: {}", addr, e))
+}
+
+/// Upload throughput to the rpc-server at `addr`, in Mbps.
+///
+/// Sends a GRAPH_COMPUTE message with an impossible node count: the server reads
+/// the whole payload, rejects it without touching its buffers and closes the
+/// connection, so the time until EOF covers the transfer. Only attempted after
+/// HELLO confirms the command numbering.
+pub async fn measure_upload(addr: &str, bytes: usize) -> Result<f64, String> {
+    let mut stream = connect(addr).await?;
+    tokio::time::timeout(HELLO_TIMEOUT, hello(&mut stream))
+        .await
+        .map_err(|_| "rpc-server busy (no HELLO reply)".to_string())?
+        .map_err(|e| format!("HELLO failed: {}", e))?;
+
+    let mut message = Vec::with_capacity(9 + bytes);
+    message.push(RPC_CMD_GRAPH_COMPUTE);
+    message.extend_from_slice(&(bytes as u64).to_le_bytes());
+    message.resize(9 + bytes, 0xff);
+
+    let start = std::time::Instant::now();
+    let transfer = async {
+        stream.write_all(&message).await?;
+        let mut rest = Vec::new();
+        stream.read_to_end(&mut rest).await
+    };
+    match tokio::time::timeout(std::time::Duration::from_secs(30), transfer).await {
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionReset => {}
+        Ok(Err(e)) => return Err(format!("transfer failed: {}", e)),
+        Err(_) => return Err("transfer timed out".to_string()),
+    }
+    let secs = start.elapsed().as_secs_f64();
+    if secs <= 0.0 {
+        return Err("no time elapsed".to_string());
+    }
+    Ok(bytes as f64 * 8.0 / secs / 1_000_000.0)
+}
+
+/// Request: cmd (u8) + payload size (u64 LE). Reply: size (u64 LE) + major, minor, patch.
+async fn hello(stream: &mut TcpStream) -> std::io::Result<String> {
+    let mut request = [0u8; 9];
+    request[0] = RPC_CMD_HELLO;
+    stream.write_all(&request).await?;
+
+    let mut size = [0u8; 8];
+    stream.read_exact(&mut size).await?;
+    if u64::from_le_bytes(size) != 3 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "unexpected HELLO reply size",
+        ));
+    }
+    let mut version = [0u8; 3];
+    stream.read_exact(&mut version).await?;
+    Ok(format!("{}.{}.{}", version[0], version[1], version[2]))
+}
+
+/// Poll until the rpc-server accepts connections, or fail after `timeout`.
+pub async fn wait_until_ready(port: u16, timeout: std::time::Duration) -> Result<RpcProbe, String> {
+    let deadline = std::time::Instant::now() + timeout;
+    loop {
+        match probe(port).await {
+            Ok(p) => return Ok(p),
+            Err(e) if std::time::Instant::now() >= deadline => {
+                return Err(format!("rpc-server not ready after {}s: {}", timeout.as_secs(), e));
+            }
+            Err(_) => tokio::time::sleep(std::time::Duration::from_millis(500)).await,
+        }
+    }
+}
+
+/// Re-probe every `interval_secs`, calling `on_change` when the server goes down
+/// (after a few consecutive failures) or comes back.
+pub fn start_rpc_health_monitor<F>(
+    port: u16,
+    interval_secs: u64,
+    on_change: F,
+) -> tokio::task::JoinHandle<()>
+where
+    F: Fn(RpcHealth) + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+        interval.tick().await;
+        let mut failures = 0u32;
+        let mut healthy = true;
+        loop {
+            interval.tick().await;
+            match probe(port).await {
+                Ok(_) => {
+                    failures = 0;
+                    if !healthy {
+                        healthy = true;
+                        log::info!("rpc-server on port {} is accepting connections again", port);
+                        on_change(RpcHealth {
+                            port,
+                            healthy,
+                            message: "rpc-server recovered".to_string(),
+                        });
+                    }
+                }
+                Err(e) => {
+                    failures += 1;
+                    log::debug!("rpc-server probe failed ({}): {}", failures, e);
+                    if healthy && failures >= FAILURES_BEFORE_DOWN {
+                        healthy = false;
+                        log::warn!("rpc-server health check failed: {}", e);
+                        on_change(RpcHealth {
+                            port,
+                            healthy,
+                            message: e,
+                        });
+                    }
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,147 @@
+//! Latency histograms for relay requests: time to first token (streams) and total
+//! duration, in fixed buckets so tail latency isn't hidden behind an average.
+
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+/// Upper bucket bounds in milliseconds; one overflow bucket follows.
+const BUCKETS_MS: [f64; 14] = [
+    25.0, 50.0, 100.0, 250.0, 500.0, 1_000.0, 2_000.0, 5_000.0, 10_000.0, 20_000.0, 30_000.0,
+    60_000.0, 120_000.0, 300_000.0,
+];
+
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    counts: [u64; BUCKETS_MS.len() + 1],
+    count: u64,
+    sum_ms: f64,
+    max_ms: f64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            counts: [0; BUCKETS_MS.len() + 1],
+            count: 0,
+            sum_ms: 0.0,
+            max_ms: 0.0,
+        }
+    }
+}
+
+impl Histogram {
+    pub fn record(&mut self, ms: f64) {
+        let ms = ms.max(0.0);
+        let bucket = BUCKETS_MS.iter().position(|&b| ms <= b).unwrap_or(BUCKETS_MS.len());
+        self.counts[bucket] += 1;
+        self.count += 1;
+        self.sum_ms += ms;
+        self.max_ms = self.max_ms.max(ms);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Estimated `q` quantile (0..=1), interpolated linearly inside its bucket and
+    /// capped at the largest value seen. None before the first sample.
+    pub fn percentile(&self, q: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = (q.clamp(0.0, 1.0) * self.count as f64).ceil().max(1.0) as u64;
+        let mut seen = 0u64;
+        for (i, &n) in self.counts.iter().enumerate() {
+            if n == 0 {
+                continue;
+            }
+            if seen + n >= rank {
+                let lower = if i == 0 { 0.0 } else { BUCKETS_MS[i - 1] };
+                let upper = BUCKETS_MS.get(i).copied().unwrap_or(self.max_ms).min(self.max_ms);
+                let fraction = (rank - seen) as f64 / n as f64;
+                return Some(lower + (upper - lower).max(0.0) * fraction);
+            }
+            seen += n;
+        }
+        Some(self.max_ms)
+    }
+
+    pub fn summary(&self) -> LatencySummary {
+        LatencySummary {
+            count: self.count,
+            avg_ms: if self.count > 0 { self.sum_ms / self.count as f64 } else { 0.0 },
+            p50_ms: self.percentile(0.50).unwrap_or(0.0),
+            p95_ms: self.percentile(0.95).unwrap_or(0.0),
+            p99_ms: self.percentile(0.99).unwrap_or(0.0),
+            max_ms: self.max_ms,
+        }
+    }
+
+    /// Prometheus histogram lines (`_bucket`, `_sum`, `_count`) in seconds.
+    fn write_prometheus(&self, out: &mut String, name: &str, help: &str) {
+        out.push_str(&format!("# HELP {} {}\n# TYPE {} histogram\n", name, help, name));
+        let mut cumulative = 0u64;
+        for (i, bound) in BUCKETS_MS.iter().enumerate() {
+            cumulative += self.counts[i];
+            out.push_str(&format!("{}_bucket{{le=\"{}\"}} {}\n", name, bound / 1000.0, cumulative));
+        }
+        out.push_str(&format!("{}_bucket{{le=\"+Inf\"}} {}\n", name, self.count));
+        out.push_str(&format!("{}_sum {}\n", name, self.sum_ms / 1000.0));
+        out.push_str(&format!("{}_count {}\n", name, self.count));
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencySummary {
+    pub count: u64,
+    pub avg_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyReport {
+    /// Streaming requests only: request received to first content chunk sent.
+    pub ttft: LatencySummary,
+    /// Request received to final message sent, completed requests only.
+    pub total: LatencySummary,
+}
+
+/// Histograms for the requests served since the agent started.
+#[derive(Debug, Clone, Default)]
+pub struct LatencyStats {
+    pub ttft: Histogram,
+    pub total: Histogram,
+}
+
+pub type SharedLatencyStats = Arc<Mutex<LatencyStats>>;
+
+impl LatencyStats {
+    pub fn report(&self) -> LatencyReport {
+        LatencyReport {
+            ttft: self.ttft.summary(),
+            total: self.total.summary(),
+        }
+    }
+
+    /// Both histograms in Prometheus text format, for a metrics exporter.
+    pub fn prometheus(&self) -> String {
+        let mut out = String::new();
+        self.ttft.write_prometheus(
+            &mut out,
+            "plumise_request_ttft_seconds",
+            "Time to first streamed token of relay requests.",
+        );
+        self.total.write_prometheus(
+            &mut out,
+            "plumise_request_duration_seconds",
+            "Total duration of completed relay requests.",
+        );
+        out
+    }
+}
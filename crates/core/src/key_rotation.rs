@@ -0,0 +1,261 @@
+//! Replace the node's private key. The retired key is backed up, optionally linked to
+//! the new address at the Oracle (both keys sign the link, so rewards history carries
+//! over), the new key is written to the keyring and config together, and the new
+//! address is registered with the Oracle.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::chain::crypto::{address_from_key, parse_private_key, personal_sign, SigningKey};
+use crate::config::{self, AgentConfig};
+
+/// Retired keys are kept here (one file per address) until the owner deletes them.
+const RETIRED_KEYS_DIR: &str = "retired-keys";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyRotationReport {
+    pub old_address: Option<String>,
+    pub new_address: String,
+    /// The Oracle accepted the old -> new link.
+    pub migration_linked: bool,
+    /// The new address is registered with the Oracle.
+    pub registered: bool,
+    /// Why the registration failed, when it did; the next start registers again.
+    pub registration_error: Option<String>,
+    /// Backup of the retired key.
+    pub backup_path: Option<String>,
+}
+
+/// Signed statement that `old_address` hands over to `new_address`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationProof {
+    pub old_address: String,
+    pub new_address: String,
+    pub timestamp: u64,
+    pub old_signature: String,
+    pub new_signature: String,
+}
+
+impl MigrationProof {
+    pub fn sign(old_key: &SigningKey, new_key: &SigningKey) -> Result<Self, String> {
+        let old_address = address_from_key(old_key);
+        let new_address = address_from_key(new_key);
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| format!("Time error: {}", e))?
+            .as_secs();
+        let message = migration_message(&old_address, &new_address, timestamp);
+        Ok(Self {
+            old_signature: personal_sign(&message, old_key)?,
+            new_signature: personal_sign(&message, new_key)?,
+            old_address,
+            new_address,
+            timestamp,
+        })
+    }
+}
+
+fn migration_message(old_address: &str, new_address: &str, timestamp: u64) -> String {
+    format!(
+        "plumise-key-migration:{}:{}:{}",
+        old_address, new_address, timestamp
+    )
+}
+
+/// Fresh secp256k1 private key from the OS RNG, "0x"-prefixed.
+pub fn generate_private_key() -> Result<String, String> {
+    loop {
+        let mut bytes = [0u8; 32];
+        getrandom::getrandom(&mut bytes).map_err(|e| format!("OS RNG unavailable: {}", e))?;
+        // Out-of-range scalars (zero or >= n) are astronomically rare; draw again
+        if SigningKey::from_bytes((&bytes[..]).into()).is_ok() {
+            return Ok(format!("0x{}", hex::encode(bytes)));
+        }
+    }
+}
+
+/// Ask the Oracle to link rewards and history of the old address to the new one.
+pub async fn announce_migration(
+    client: &reqwest::Client,
+    oracle_url: &str,
+    proof: &MigrationProof,
+) -> Result<(), String> {
+    let url = format!("{}/api/v1/nodes/migrate", oracle_url.trim_end_matches('/'));
+    let resp = client
+        .post(&url)
+        .json(proof)
+        .timeout(std::time::Duration::from_secs(30))
+        .send()
+        .await
+        .map_err(|e| format!("Oracle migration request failed: {}", e))?;
+    if resp.status().is_success() {
+        Ok(())
+    } else {
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        Err(format!(
+            "Oracle rejected key migration ({}): {}",
+            status,
+            text.chars().take(300).collect::<String>()
+        ))
+    }
+}
+
+fn backup_retired_key(dir: &Path, address: &str, private_key: &str) -> Result<PathBuf, String> {
+    let backup_dir = dir.join(RETIRED_KEYS_DIR);
+    std::fs::create_dir_all(&backup_dir)
+        .map_err(|e| format!("Failed to create {}: {}", backup_dir.display(), e))?;
+    let mut path = backup_dir.join(format!("{}.key", address));
+    let mut file = match create_private_file(&path) {
+        // A key retired before (rotated back and forth); never overwrite a backup
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            path = backup_dir.join(format!("{}-{}.key", address, timestamp));
+            create_private_file(&path)
+        }
+        other => other,
+    }
+    .map_err(|e| format!("Failed to back up the old key: {}", e))?;
+    file.write_all(format!("{}\n", private_key).as_bytes())
+        .and_then(|()| file.sync_all())
+        .map_err(|e| format!("Failed to back up the old key: {}", e))?;
+    Ok(path)
+}
+
+/// New file readable by the owner only from the moment it exists.
+fn create_private_file(path: &Path) -> std::io::Result<std::fs::File> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+/// Register `signing_key`'s address with the Oracle under `config`. The agent is
+/// stopped, so there is no benchmark yet; its next start registers again in full.
+async fn register_address(
+    client: &reqwest::Client,
+    config: &AgentConfig,
+    signing_key: &SigningKey,
+) -> Result<(), String> {
+    let serve_mode = config.serve_mode();
+    let bind_host = config.llama_bind_host();
+    let ip = crate::system::advertised_ip(&config.oracle_url, &bind_host)
+        .await
+        .unwrap_or_else(|| "127.0.0.1".to_string());
+    let loopback = bind_host
+        .parse::<std::net::IpAddr>()
+        .map(|ip| ip.is_loopback())
+        .unwrap_or(bind_host.eq_ignore_ascii_case("localhost"));
+    crate::oracle::registry::register(
+        client,
+        &config.oracle_url,
+        signing_key,
+        serve_mode.capability(),
+        &config.oracle_model(),
+        config.http_port,
+        &ip,
+        None,
+        None,
+        false,
+        &ip,
+        loopback,
+        &crate::oracle::registry::NodeInfo::from_config(config),
+    )
+    .await
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+/// Switch the config in `dir` to `new_key` (generated when None). With `link` the
+/// Oracle must accept the migration first; nothing is changed if it doesn't. Once
+/// the new key is saved its address is registered with the Oracle; a failed
+/// registration is returned in `registration_error`, not as an error, since the key
+/// has already changed.
+pub async fn rotate_key(
+    client: &reqwest::Client,
+    dir: &Path,
+    new_key: Option<String>,
+    link: bool,
+) -> Result<KeyRotationReport, String> {
+    let current: AgentConfig = config::load_config(dir)?;
+    let new_key = match new_key {
+        Some(key) => {
+            let key = key.trim().to_string();
+            if key.starts_with("0x") {
+                key
+            } else {
+                format!("0x{}", key)
+            }
+        }
+        None => generate_private_key()?,
+    };
+    let new_signing = parse_private_key(&new_key)?;
+    let new_address = address_from_key(&new_signing);
+
+    let old_signing = if current.private_key.is_empty() {
+        None
+    } else {
+        Some(parse_private_key(&current.private_key)?)
+    };
+    let old_address = old_signing.as_ref().map(address_from_key);
+    if old_address.as_deref() == Some(new_address.as_str()) {
+        return Err("The new key is the key already in use".into());
+    }
+
+    let mut migration_linked = false;
+    if link {
+        let old = old_signing
+            .as_ref()
+            .ok_or("No current key to link the new address to")?;
+        let proof = MigrationProof::sign(old, &new_signing)?;
+        announce_migration(client, &current.oracle_url, &proof).await?;
+        migration_linked = true;
+    }
+
+    let backup_path = match &old_address {
+        Some(address) => Some(backup_retired_key(dir, address, &current.private_key)?),
+        None => None,
+    };
+
+    let mut updated = current.clone();
+    updated.private_key = new_key;
+    updated.validate()?;
+    if let Err(e) = config::save_config(&updated, dir) {
+        // Put the old key back so keyring and file agree
+        let _ = config::save_config(&current, dir);
+        return Err(e.into());
+    }
+    log::info!(
+        "Rotated node key: {} -> {}",
+        old_address.as_deref().unwrap_or("(none)"),
+        new_address
+    );
+
+    let registration_error = match register_address(client, &updated, &new_signing).await {
+        Ok(()) => None,
+        Err(e) => {
+            log::warn!("Could not register {} with the Oracle yet: {}", new_address, e);
+            Some(e)
+        }
+    };
+
+    Ok(KeyRotationReport {
+        old_address,
+        new_address,
+        migration_linked,
+        registered: registration_error.is_none(),
+        registration_error,
+        backup_path: backup_path.map(|p| p.display().to_string()),
+    })
+}
@@ -0,0 +1,256 @@
+//! Append-only accounting ledger of served relay requests.
+//! One JSON object per line in `<config_dir>/ledger.jsonl`.
+//!
+//! Once the file passes `MAX_LEDGER_BYTES`, its older half is folded into hourly totals
+//! in `ledger-rollup.jsonl`, so reads stay bounded while `summary` still covers the
+//! whole history.
+
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+const LEDGER_FILE_NAME: &str = "ledger.jsonl";
+const ROLLUP_FILE_NAME: &str = "ledger-rollup.jsonl";
+/// Roughly 20k entries.
+const MAX_LEDGER_BYTES: u64 = 4 * 1024 * 1024;
+const ROLLUP_BUCKET_MS: u64 = 3_600_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RequestOutcome {
+    Completed,
+    Failed,
+    /// The relay connection dropped before the response could be delivered.
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerEntry {
+    pub id: String,
+    /// Unix epoch milliseconds.
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub duration_ms: u64,
+    pub stream: bool,
+    pub outcome: RequestOutcome,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerSummary {
+    pub total_requests: u64,
+    pub completed: u64,
+    pub failed: u64,
+    pub cancelled: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_duration_ms: u64,
+    pub avg_duration_ms: f64,
+    pub first_at: Option<u64>,
+    pub last_at: Option<u64>,
+}
+
+/// Totals of the rotated-out entries started within one hour.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RollupBucket {
+    /// Start of the hour (unix epoch milliseconds).
+    hour: u64,
+    summary: LedgerSummary,
+}
+
+pub struct Ledger {
+    path: PathBuf,
+    rollup_path: PathBuf,
+    write_lock: Mutex<()>,
+}
+
+impl Ledger {
+    /// Open (or create) the ledger in `dir`.
+    pub fn open(dir: &Path) -> Result<Self, String> {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create ledger directory: {}", e))?;
+        Ok(Self {
+            path: dir.join(LEDGER_FILE_NAME),
+            rollup_path: dir.join(ROLLUP_FILE_NAME),
+            write_lock: Mutex::new(()),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append one entry. Entries are never rewritten, only rotated into the rollup.
+    pub fn append(&self, entry: &LedgerEntry) -> Result<(), String> {
+        let mut line = serde_json::to_string(entry)
+            .map_err(|e| format!("Failed to serialize ledger entry: {}", e))?;
+        line.push('\n');
+
+        let _guard = self.write_lock.lock().map_err(|_| "Ledger lock poisoned".to_string())?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open ledger: {}", e))?;
+        file.write_all(line.as_bytes())
+            .map_err(|e| format!("Failed to write ledger: {}", e))?;
+
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        drop(file);
+        if size > MAX_LEDGER_BYTES {
+            if let Err(e) = self.rotate() {
+                log::warn!("Failed to rotate ledger: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Fold the older half of the entries into hourly rollup buckets and rewrite the
+    /// ledger with the rest. Called with the write lock held.
+    fn rotate(&self) -> Result<(), String> {
+        let mut entries = read_entries(&self.path, None)?;
+        let keep = entries.split_off(entries.len() / 2);
+
+        let mut buckets: Vec<RollupBucket> = Vec::new();
+        for chunk in entries.chunk_by(|a, b| {
+            a.started_at / ROLLUP_BUCKET_MS == b.started_at / ROLLUP_BUCKET_MS
+        }) {
+            buckets.push(RollupBucket {
+                hour: chunk[0].started_at / ROLLUP_BUCKET_MS * ROLLUP_BUCKET_MS,
+                summary: summarize(chunk),
+            });
+        }
+        let mut rollup = String::new();
+        for bucket in &buckets {
+            rollup += &serde_json::to_string(bucket)
+                .map_err(|e| format!("Failed to serialize ledger rollup: {}", e))?;
+            rollup.push('\n');
+        }
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.rollup_path)
+            .and_then(|mut f| f.write_all(rollup.as_bytes()))
+            .map_err(|e| format!("Failed to write ledger rollup: {}", e))?;
+
+        let mut kept = String::new();
+        for entry in &keep {
+            kept += &serde_json::to_string(entry)
+                .map_err(|e| format!("Failed to serialize ledger entry: {}", e))?;
+            kept.push('\n');
+        }
+        let tmp = self.path.with_extension("jsonl.tmp");
+        std::fs::write(&tmp, kept)
+            .and_then(|_| std::fs::rename(&tmp, &self.path))
+            .map_err(|e| format!("Failed to rewrite ledger: {}", e))?;
+        log::info!(
+            "Rotated {} ledger entries into {} hourly totals",
+            entries.len(),
+            buckets.len()
+        );
+        Ok(())
+    }
+
+    /// Read the entries still in the ledger that started at or after `since` (unix ms).
+    /// Rotated entries are only available as totals, through `summary`. Malformed lines
+    /// are skipped.
+    pub fn entries(&self, since: Option<u64>) -> Result<Vec<LedgerEntry>, String> {
+        read_entries(&self.path, since)
+    }
+
+    /// Totals since `since`, including rotated entries. Those count from the start of
+    /// their hour, so a `since` inside a rotated hour leaves that hour out.
+    pub fn summary(&self, since: Option<u64>) -> Result<LedgerSummary, String> {
+        let mut summary = summarize(&self.entries(since)?);
+        for bucket in read_rollup(&self.rollup_path)? {
+            if bucket.hour >= since.unwrap_or(0) {
+                merge(&mut summary, &bucket.summary);
+            }
+        }
+        Ok(summary)
+    }
+}
+
+fn read_rollup(path: &Path) -> Result<Vec<RollupBucket>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let file =
+        std::fs::File::open(path).map_err(|e| format!("Failed to open ledger rollup: {}", e))?;
+    Ok(std::io::BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<RollupBucket>(&line).ok())
+        .collect())
+}
+
+/// Add `other` into `summary`.
+fn merge(summary: &mut LedgerSummary, other: &LedgerSummary) {
+    summary.total_requests += other.total_requests;
+    summary.completed += other.completed;
+    summary.failed += other.failed;
+    summary.cancelled += other.cancelled;
+    summary.prompt_tokens += other.prompt_tokens;
+    summary.completion_tokens += other.completion_tokens;
+    summary.total_duration_ms += other.total_duration_ms;
+    summary.first_at = match (summary.first_at, other.first_at) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    };
+    summary.last_at = summary.last_at.max(other.last_at);
+    if summary.total_requests > 0 {
+        summary.avg_duration_ms = summary.total_duration_ms as f64 / summary.total_requests as f64;
+    }
+}
+
+fn read_entries(path: &Path, since: Option<u64>) -> Result<Vec<LedgerEntry>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let file = std::fs::File::open(path).map_err(|e| format!("Failed to open ledger: {}", e))?;
+    let since = since.unwrap_or(0);
+    Ok(std::io::BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<LedgerEntry>(&line).ok())
+        .filter(|e| e.started_at >= since)
+        .collect())
+}
+
+/// Aggregate a set of ledger entries.
+pub fn summarize(entries: &[LedgerEntry]) -> LedgerSummary {
+    let mut summary = LedgerSummary::default();
+    for e in entries {
+        summary.total_requests += 1;
+        match e.outcome {
+            RequestOutcome::Completed => summary.completed += 1,
+            RequestOutcome::Failed => summary.failed += 1,
+            RequestOutcome::Cancelled => summary.cancelled += 1,
+        }
+        summary.prompt_tokens += e.prompt_tokens;
+        summary.completion_tokens += e.completion_tokens;
+        summary.total_duration_ms += e.duration_ms;
+        summary.first_at = Some(summary.first_at.map_or(e.started_at, |t| t.min(e.started_at)));
+        summary.last_at = Some(summary.last_at.map_or(e.finished_at, |t| t.max(e.finished_at)));
+    }
+    if summary.total_requests > 0 {
+        summary.avg_duration_ms = summary.total_duration_ms as f64 / summary.total_requests as f64;
+    }
+    summary
+}
+
+/// Current unix time in milliseconds.
+pub fn unix_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
@@ -1,9 +1,35 @@
+pub mod alerts;
+pub mod audit;
+pub mod availability;
 pub mod chain;
+pub mod claims;
+pub mod cleanup;
 pub mod config;
+pub mod crash;
+pub mod economics;
+pub mod error;
+pub mod events;
+pub mod gpu_probe;
+pub mod hardware;
+pub mod http;
+pub mod idle;
 pub mod inference;
+pub mod key_rotation;
+pub mod ledger;
+pub mod lifetime;
 pub mod model;
 pub mod oracle;
+pub mod power;
+pub mod preflight;
 pub mod llama_bin;
 pub mod process;
 pub mod relay;
+pub mod runtime_state;
+pub mod schedule;
 pub mod system;
+pub mod telemetry;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod tls_pin;
+pub mod wallet;
+pub mod webhook;
@@ -0,0 +1,150 @@
+//! All-time totals that survive restarts: `<config_dir>/lifetime.json`.
+//! llama-server's counters start from zero with every launch, so the tracker adds up
+//! the increase between samples instead of storing the raw values.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::inference::stats::SharedLatencyStats;
+
+const LIFETIME_FILE_NAME: &str = "lifetime.json";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LifetimeStats {
+    #[serde(default)]
+    pub tokens: u64,
+    /// Completed relay requests.
+    #[serde(default)]
+    pub requests: u64,
+    /// Time llama-server was up and serving.
+    #[serde(default)]
+    pub uptime_secs: u64,
+    /// Launches after the first one.
+    #[serde(default)]
+    pub restarts: u64,
+    #[serde(default)]
+    pub rewards_claimed_plm: f64,
+    /// Unix epoch seconds of the first launch.
+    #[serde(default)]
+    pub first_started_at: Option<u64>,
+}
+
+impl LifetimeStats {
+    /// Saved totals, or zeros when there are none yet.
+    pub fn load(dir: &Path) -> Self {
+        std::fs::read_to_string(dir.join(LIFETIME_FILE_NAME))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+}
+
+pub struct LifetimeTracker {
+    dir: PathBuf,
+    stats: LifetimeStats,
+    last_tokens: u64,
+    last_requests: u64,
+    last_sample: Option<Instant>,
+}
+
+pub type SharedLifetime = Arc<Mutex<LifetimeTracker>>;
+
+impl LifetimeTracker {
+    pub fn open(dir: &Path) -> Self {
+        LifetimeTracker {
+            dir: dir.to_path_buf(),
+            stats: LifetimeStats::load(dir),
+            last_tokens: 0,
+            last_requests: 0,
+            last_sample: None,
+        }
+    }
+
+    /// A new llama-server launch: count the restart and start counting from zero.
+    pub fn record_launch(&mut self) {
+        if self.stats.first_started_at.is_some() {
+            self.stats.restarts += 1;
+        } else {
+            self.stats.first_started_at = Some(crate::ledger::unix_millis() / 1000);
+        }
+        self.last_tokens = 0;
+        self.last_requests = 0;
+        self.last_sample = Some(Instant::now());
+    }
+
+    /// Fold in the current session counters. A counter lower than last time means
+    /// llama-server restarted in between, so the whole value is new.
+    pub fn observe(&mut self, tokens: u64, requests: u64) {
+        self.stats.tokens += tokens.checked_sub(self.last_tokens).unwrap_or(tokens);
+        self.stats.requests += requests.checked_sub(self.last_requests).unwrap_or(requests);
+        self.last_tokens = tokens;
+        self.last_requests = requests;
+        let now = Instant::now();
+        if let Some(last) = self.last_sample {
+            self.stats.uptime_secs += now.duration_since(last).as_secs();
+            // Keep the sub-second remainder for the next sample
+            self.last_sample =
+                Some(last + std::time::Duration::from_secs(now.duration_since(last).as_secs()));
+        }
+    }
+
+    pub fn record_claim(&mut self, amount_plm: f64) {
+        self.stats.rewards_claimed_plm += amount_plm;
+    }
+
+    pub fn stats(&self) -> LifetimeStats {
+        self.stats.clone()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(&self.stats)
+            .map_err(|e| format!("Failed to serialize lifetime stats: {}", e))?;
+        let path = self.dir.join(LIFETIME_FILE_NAME);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)
+            .map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+        std::fs::rename(&tmp, &path)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+/// Sample llama-server's token counter (and completed relay requests from `latency`)
+/// every `interval_secs` and persist the totals. `llama_port` is read on every tick,
+/// so a model switch moves the sampling along; None skips the round.
+pub fn start_lifetime_recorder<P>(
+    tracker: SharedLifetime,
+    llama_port: P,
+    latency: Option<SharedLatencyStats>,
+    interval_secs: u64,
+) -> tokio::task::JoinHandle<()>
+where
+    P: Fn() -> Option<u16> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let client = crate::http::local();
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+        loop {
+            interval.tick().await;
+            let Some(port) = llama_port() else { continue };
+            let Ok(metrics) = crate::inference::metrics::fetch_metrics(&client, port).await
+            else {
+                continue;
+            };
+            let requests = latency
+                .as_ref()
+                .and_then(|l| l.lock().ok().map(|l| l.total.count()))
+                .unwrap_or(0);
+            let Ok(mut tracker) = tracker.lock() else {
+                return;
+            };
+            tracker.observe(metrics.total_tokens, requests);
+            if let Err(e) = tracker.save() {
+                log::warn!("{}", e);
+            }
+        }
+    })
+}
@@ -3,100 +3,731 @@
 
 use std::path::{Path, PathBuf};
 
+use serde::{Deserialize, Serialize};
+
+use crate::model::download::DownloadProgress;
+
 /// Pinned llama.cpp release version.
 pub const LLAMA_CPP_VERSION: &str = "b4722";
 
-/// Detect the appropriate llama.cpp release asset name for this platform.
-pub fn detect_asset_name() -> Result<String, String> {
-    let gpu = crate::system::detect_gpu();
-    let has_nvidia = gpu
-        .as_ref()
-        .map(|(name, _)| name.to_lowercase().contains("nvidia") || name.to_lowercase().contains("geforce") || name.to_lowercase().contains("rtx"))
-        .unwrap_or(false);
-    let has_metal = gpu
+/// Hardware/driver features that decide which llama.cpp build can run here.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostCapabilities {
+    pub arm64: bool,
+    pub avx: bool,
+    pub avx2: bool,
+    pub avx512: bool,
+    pub neon: bool,
+    pub nvidia: bool,
+    pub rocm: bool,
+    pub vulkan: bool,
+    /// NVIDIA Jetson (Tegra): ARM64 with an integrated CUDA GPU and no prebuilt CUDA release.
+    pub jetson: bool,
+}
+
+/// Logged when a Jetson has to fall back to a generic ARM64 build.
+pub const JETSON_HINT: &str = "NVIDIA Jetson detected: llama.cpp publishes no CUDA build for Jetson, \
+     so a generic ARM64 build is used (Vulkan if available, else CPU only). For CUDA offload, \
+     build llama.cpp on the device (cmake -B build -DGGML_CUDA=ON && cmake --build build -j) \
+     and set llamaPath in the config (or pass --llama-path) to build/bin/llama-server.";
+
+pub fn host_capabilities() -> HostCapabilities {
+    let hardware = crate::hardware::profile();
+    let gpu_vendor = hardware
+        .gpu
         .as_ref()
-        .map(|(name, _)| name.to_lowercase().contains("metal") || name.to_lowercase().contains("apple"))
-        .unwrap_or(false);
+        .map(|gpu| gpu.vendor.clone())
+        .unwrap_or_default();
+
+    let cpu = &hardware.cpu;
+
+    HostCapabilities {
+        arm64: cfg!(target_arch = "aarch64"),
+        avx: cpu.avx,
+        avx2: cpu.avx2,
+        avx512: cpu.avx512,
+        neon: cpu.neon,
+        nvidia: gpu_vendor == "nvidia",
+        rocm: has_rocm(),
+        vulkan: has_vulkan(),
+        jetson: crate::system::jetson_model().is_some(),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn has_rocm() -> bool {
+    Path::new("/dev/kfd").exists() && Path::new("/opt/rocm").exists()
+}
+
+#[cfg(target_os = "linux")]
+fn has_vulkan() -> bool {
+    ["/usr/share/vulkan/icd.d", "/etc/vulkan/icd.d"].iter().any(|dir| {
+        std::fs::read_dir(dir)
+            .map(|mut entries| entries.next().is_some())
+            .unwrap_or(false)
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn system32_dir() -> PathBuf {
+    std::env::var("SystemRoot")
+        .map(|r| PathBuf::from(r).join("System32"))
+        .unwrap_or_else(|_| PathBuf::from(r"C:\Windows\System32"))
+}
+
+/// The HIP SDK installs `amdhip64*.dll` into System32.
+#[cfg(target_os = "windows")]
+fn has_rocm() -> bool {
+    std::fs::read_dir(system32_dir())
+        .map(|entries| {
+            entries.flatten().any(|e| {
+                let name = e.file_name().to_string_lossy().to_lowercase();
+                name.starts_with("amdhip64") && name.ends_with(".dll")
+            })
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(target_os = "windows")]
+fn has_vulkan() -> bool {
+    system32_dir().join("vulkan-1.dll").exists()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn has_rocm() -> bool {
+    false
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn has_vulkan() -> bool {
+    false
+}
+
+/// Release asset names for this platform, best first. The installer tries them
+/// in order until one downloads and runs.
+pub fn asset_candidates(version: &str, caps: &HostCapabilities) -> Vec<String> {
+    #[allow(unused_mut)]
+    let mut suffixes: Vec<&str> = Vec::new();
 
     #[cfg(target_os = "linux")]
     {
-        if has_nvidia {
-            return Ok(format!("llama-{}-bin-ubuntu-x64-cuda-cu12.4.zip", LLAMA_CPP_VERSION));
+        if caps.arm64 {
+            if caps.vulkan {
+                suffixes.push("ubuntu-vulkan-arm64");
+            }
+            suffixes.push("ubuntu-arm64");
+        } else {
+            // GPU builds are compiled for AVX2 and die with an illegal instruction without it
+            let gpu_ok = caps.avx2;
+            if caps.nvidia && gpu_ok {
+                suffixes.push("ubuntu-x64-cuda-cu12.4");
+            }
+            if caps.rocm && gpu_ok {
+                suffixes.push("ubuntu-x64-hip");
+            }
+            if (caps.vulkan || caps.nvidia || caps.rocm) && gpu_ok {
+                suffixes.push("ubuntu-vulkan-x64");
+            }
+            suffixes.push("ubuntu-x64");
         }
-        return Ok(format!("llama-{}-bin-ubuntu-x64.zip", LLAMA_CPP_VERSION));
     }
 
     #[cfg(target_os = "macos")]
     {
-        let _ = has_nvidia;
-        let _ = has_metal;
-        return Ok(format!("llama-{}-bin-macos-arm64.zip", LLAMA_CPP_VERSION));
+        suffixes.push(if caps.arm64 { "macos-arm64" } else { "macos-x64" });
     }
 
     #[cfg(target_os = "windows")]
     {
-        if has_nvidia {
-            return Ok(format!("llama-{}-bin-win-cuda-cu12.4-x64.zip", LLAMA_CPP_VERSION));
+        if caps.arm64 {
+            suffixes.push("win-llvm-arm64");
+        } else {
+            // GPU builds are compiled for AVX2; older CPUs fall through to the avx/noavx builds
+            let gpu_ok = caps.avx2;
+            if caps.nvidia && gpu_ok {
+                suffixes.push("win-cuda-cu12.4-x64");
+                suffixes.push("win-cuda-cu11.7-x64");
+            }
+            if caps.rocm && gpu_ok {
+                suffixes.push("win-hip-x64-gfx1100");
+            }
+            if (caps.vulkan || caps.nvidia || caps.rocm) && gpu_ok {
+                suffixes.push("win-vulkan-x64");
+            }
+            if caps.avx512 {
+                suffixes.push("win-avx512-x64");
+            }
+            if caps.avx2 {
+                suffixes.push("win-avx2-x64");
+            }
+            if caps.avx {
+                suffixes.push("win-avx-x64");
+            }
+            suffixes.push("win-noavx-x64");
         }
-        return Ok(format!("llama-{}-bin-win-avx2-x64.zip", LLAMA_CPP_VERSION));
     }
 
-    #[allow(unreachable_code)]
-    Err("Unsupported platform".into())
+    let _ = caps;
+    suffixes
+        .into_iter()
+        .map(|suffix| asset_file_name(version, suffix))
+        .collect()
 }
 
-/// Ensure llama-server binary exists in the given directory.
-/// Downloads from GitHub releases if missing.
-/// Returns path to the llama-server executable.
-pub async fn ensure_llama_server(bin_dir: &Path) -> Result<PathBuf, String> {
-    std::fs::create_dir_all(bin_dir)
-        .map_err(|e| format!("Failed to create bin dir: {}", e))?;
+/// Expand an `--asset` value: a full file name is used as is, otherwise it is
+/// treated as the platform part, e.g. `ubuntu-vulkan-x64`.
+pub fn asset_file_name(version: &str, asset: &str) -> String {
+    if asset.ends_with(".zip") {
+        asset.to_string()
+    } else {
+        format!("llama-{}-bin-{}.zip", version, asset)
+    }
+}
+
+/// Best release asset for this platform.
+pub fn detect_asset_name(version: &str) -> Result<String, String> {
+    asset_candidates(version, &host_capabilities())
+        .into_iter()
+        .next()
+        .ok_or_else(|| "Unsupported platform".to_string())
+}
+
+/// Versioned install layout under `bin_dir`:
+///
+/// ```text
+/// bin/versions/<tag>/llama-server   one directory per installed release
+/// bin/current -> versions/<tag>     symlink (a `current` pointer file on Windows)
+/// bin/previous                      tag that was current before the last switch
+/// ```
+const VERSIONS_DIR: &str = "versions";
+const CURRENT_LINK: &str = "current";
+const PREVIOUS_FILE: &str = "previous";
+/// Written into each version directory once it is fully installed.
+const INSTALL_RECORD: &str = "install.json";
+/// Cached answer for "latest", so restarts don't hit the GitHub API every time.
+const LATEST_CACHE: &str = "latest.json";
+const LATEST_CACHE_SECS: u64 = 24 * 3600;
+/// Release archives being downloaded, kept across runs for resuming.
+const DOWNLOADS_DIR: &str = "downloads";
+/// Resumed attempts per archive before trying the next candidate asset.
+const DOWNLOAD_ATTEMPTS: u32 = 3;
 
-    let exe_name = if cfg!(windows) {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallRecord {
+    pub version: String,
+    pub asset: String,
+    /// Unix epoch seconds.
+    pub installed_at: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LatestCache {
+    tag: String,
+    checked_at: u64,
+}
+
+pub fn server_exe_name() -> &'static str {
+    if cfg!(windows) {
         "llama-server.exe"
     } else {
         "llama-server"
+    }
+}
+
+pub fn rpc_server_exe_name() -> &'static str {
+    if cfg!(windows) {
+        "rpc-server.exe"
+    } else {
+        "rpc-server"
+    }
+}
+
+/// Directory holding the files for `version`.
+pub fn version_dir(bin_dir: &Path, version: &str) -> PathBuf {
+    bin_dir.join(VERSIONS_DIR).join(version)
+}
+
+/// Tags installed under `bin_dir`, sorted.
+pub fn installed_versions(bin_dir: &Path) -> Vec<String> {
+    let mut versions: Vec<String> = std::fs::read_dir(bin_dir.join(VERSIONS_DIR))
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().join(server_exe_name()).exists())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    versions.sort();
+    versions
+}
+
+/// Tag the `current` pointer refers to, if any.
+pub fn current_version(bin_dir: &Path) -> Option<String> {
+    let current = bin_dir.join(CURRENT_LINK);
+    #[cfg(unix)]
+    let version = std::fs::read_link(&current)
+        .ok()?
+        .file_name()?
+        .to_string_lossy()
+        .to_string();
+    #[cfg(not(unix))]
+    let version = std::fs::read_to_string(&current).ok()?.trim().to_string();
+    if version.is_empty() {
+        None
+    } else {
+        Some(version)
+    }
+}
+
+/// Tag that was current before the last `set_current`, for rollback.
+pub fn previous_version(bin_dir: &Path) -> Option<String> {
+    std::fs::read_to_string(bin_dir.join(PREVIOUS_FILE))
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Path to the llama-server executable of the current version.
+pub fn current_server_path(bin_dir: &Path) -> Option<PathBuf> {
+    let version = current_version(bin_dir)?;
+    let path = version_dir(bin_dir, &version).join(server_exe_name());
+    if path.exists() {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// Atomically point `current` at an installed version.
+pub fn set_current(bin_dir: &Path, version: &str) -> Result<(), String> {
+    if !version_dir(bin_dir, version).join(server_exe_name()).exists() {
+        return Err(format!("llama.cpp {} is not installed", version));
+    }
+    if let Some(old) = current_version(bin_dir) {
+        if old != version {
+            let _ = std::fs::write(bin_dir.join(PREVIOUS_FILE), &old);
+        }
+    }
+
+    // Build the new pointer next to the old one, then rename over it
+    let tmp = bin_dir.join(format!("{}.tmp", CURRENT_LINK));
+    let _ = std::fs::remove_file(&tmp);
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(Path::new(VERSIONS_DIR).join(version), &tmp)
+        .map_err(|e| format!("Failed to create symlink: {}", e))?;
+    #[cfg(not(unix))]
+    std::fs::write(&tmp, version).map_err(|e| format!("Failed to write version pointer: {}", e))?;
+    std::fs::rename(&tmp, bin_dir.join(CURRENT_LINK))
+        .map_err(|e| format!("Failed to switch llama.cpp version: {}", e))?;
+
+    log::info!("llama.cpp {} is now current", version);
+    Ok(())
+}
+
+/// Install record of an installed version, if it has one.
+pub fn install_record(bin_dir: &Path, version: &str) -> Option<InstallRecord> {
+    let contents = std::fs::read_to_string(version_dir(bin_dir, version).join(INSTALL_RECORD)).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Turn a configured `llama_version` into a concrete release tag.
+/// Empty means `LLAMA_CPP_VERSION`; "latest" asks GitHub (cached for a day, and
+/// falls back to the last known answer or the current install when offline).
+pub async fn resolve_version(bin_dir: &Path, requested: &str) -> Result<String, String> {
+    let requested = requested.trim();
+    if requested.is_empty() {
+        return Ok(LLAMA_CPP_VERSION.to_string());
+    }
+    if !requested.eq_ignore_ascii_case("latest") {
+        return Ok(requested.to_string());
+    }
+
+    let cache_path = bin_dir.join(LATEST_CACHE);
+    let cached: Option<LatestCache> = std::fs::read_to_string(&cache_path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok());
+    let now = unix_secs();
+    if let Some(ref c) = cached {
+        if now.saturating_sub(c.checked_at) < LATEST_CACHE_SECS {
+            return Ok(c.tag.clone());
+        }
+    }
+
+    match fetch_latest_tag().await {
+        Ok(tag) => {
+            let _ = std::fs::create_dir_all(bin_dir);
+            let cache = LatestCache {
+                tag: tag.clone(),
+                checked_at: now,
+            };
+            if let Ok(json) = serde_json::to_string(&cache) {
+                let _ = std::fs::write(&cache_path, json);
+            }
+            Ok(tag)
+        }
+        Err(e) => {
+            let fallback = cached.map(|c| c.tag).or_else(|| current_version(bin_dir));
+            match fallback {
+                Some(tag) => {
+                    log::warn!("Could not check latest llama.cpp release ({}), using {}", e, tag);
+                    Ok(tag)
+                }
+                None => Err(format!("Could not resolve latest llama.cpp release: {}", e)),
+            }
+        }
+    }
+}
+
+async fn fetch_latest_tag() -> Result<String, String> {
+    #[derive(Deserialize)]
+    struct Release {
+        tag_name: String,
+    }
+
+    let resp = crate::http::client()
+        .get("https://api.github.com/repos/ggml-org/llama.cpp/releases/latest")
+        .header("User-Agent", "plumise-agent")
+        .header("Accept", "application/vnd.github+json")
+        .timeout(std::time::Duration::from_secs(15))
+        .send()
+        .await
+        .map_err(|e| format!("GitHub API request failed: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(format!("GitHub API HTTP {}", resp.status()));
+    }
+    let release: Release = resp
+        .json()
+        .await
+        .map_err(|e| format!("GitHub API parse error: {}", e))?;
+    Ok(release.tag_name)
+}
+
+/// Ensure the llama-server for `requested` (see `resolve_version`) is installed
+/// and current. With no version configured, whatever is current is kept (e.g.
+/// after `update-llama`), installing `LLAMA_CPP_VERSION` on first run.
+/// Returns path to the llama-server executable.
+///
+/// `on_progress` is called while a release archive downloads.
+pub async fn ensure_llama_server<F>(bin_dir: &Path, requested: &str, on_progress: F) -> Result<PathBuf, String>
+where
+    F: Fn(DownloadProgress) + Send + Sync,
+{
+    std::fs::create_dir_all(bin_dir)
+        .map_err(|e| format!("Failed to create bin dir: {}", e))?;
+
+    let current = current_version(bin_dir);
+    if requested.trim().is_empty() {
+        if let Some(path) = current_server_path(bin_dir) {
+            log::info!("llama-server {} already installed", current.unwrap_or_default());
+            return Ok(path);
+        }
+    }
+
+    let version = resolve_version(bin_dir, requested).await?;
+    if current.as_deref() == Some(version.as_str()) {
+        if let Some(path) = current_server_path(bin_dir) {
+            log::info!("llama-server {} already installed", version);
+            return Ok(path);
+        }
+    }
+
+    install_version(bin_dir, &version, None, on_progress).await?;
+    set_current(bin_dir, &version)?;
+    current_server_path(bin_dir).ok_or_else(|| "llama-server not found after install".to_string())
+}
+
+/// Download release `version` into its versioned directory (no-op if present).
+/// Tries each candidate asset until one downloads and passes a `--version`
+/// smoke test; `asset` forces a specific one (and reinstalls if a different
+/// asset was installed before). Does not change `current`.
+pub async fn install_version<F>(
+    bin_dir: &Path,
+    version: &str,
+    asset: Option<&str>,
+    on_progress: F,
+) -> Result<PathBuf, String>
+where
+    F: Fn(DownloadProgress) + Send + Sync,
+{
+    let dest = version_dir(bin_dir, version);
+    let server_path = dest.join(server_exe_name());
+    let forced = asset.map(|a| asset_file_name(version, a));
+    if server_path.exists() {
+        let installed_asset = install_record(bin_dir, version).map(|r| r.asset);
+        if forced.is_none() || forced == installed_asset {
+            log::info!("llama-server {} already downloaded", version);
+            return Ok(server_path);
+        }
+    }
+
+    let candidates = match forced {
+        Some(name) => vec![name],
+        None => {
+            let caps = host_capabilities();
+            log::info!("Host capabilities: {:?}", caps);
+            if let Some(warning) = crate::hardware::profile().cpu.warning() {
+                log::warn!("{}", warning);
+            }
+            if caps.jetson {
+                log::warn!("{}", JETSON_HINT);
+            }
+            asset_candidates(version, &caps)
+        }
     };
+    if candidates.is_empty() {
+        return Err("Unsupported platform".into());
+    }
 
-    let server_path = bin_dir.join(exe_name);
+    // Extract into a staging dir so a failed download never looks installed
+    let staging = bin_dir.join(VERSIONS_DIR).join(format!(".{}.partial", version));
+    let mut errors = Vec::new();
+    let mut installed_asset = None;
+    for asset_name in &candidates {
+        let _ = std::fs::remove_dir_all(&staging);
+        std::fs::create_dir_all(&staging)
+            .map_err(|e| format!("Failed to create bin dir: {}", e))?;
 
-    // Check version marker
-    let version_file = bin_dir.join(".llama-version");
-    let current_version = std::fs::read_to_string(&version_file).unwrap_or_default();
+        let attempt = match download_release(bin_dir, version, asset_name, &staging, &on_progress).await {
+            Ok(()) => smoke_test(&staging.join(server_exe_name())),
+            Err(e) => Err(e),
+        };
+        match attempt {
+            Ok(()) => {
+                installed_asset = Some(asset_name.clone());
+                break;
+            }
+            Err(e) => {
+                log::warn!("llama.cpp asset {} unusable: {}", asset_name, e);
+                errors.push(format!("{}: {}", asset_name, e));
+            }
+        }
+    }
+    let asset = match installed_asset {
+        Some(a) => a,
+        None => {
+            let _ = std::fs::remove_dir_all(&staging);
+            return Err(format!(
+                "No usable llama.cpp {} build found ({})",
+                version,
+                errors.join("; ")
+            ));
+        }
+    };
 
-    if server_path.exists() && current_version.trim() == LLAMA_CPP_VERSION {
-        log::info!("llama-server {} already installed", LLAMA_CPP_VERSION);
-        return Ok(server_path);
+    let record = InstallRecord {
+        version: version.to_string(),
+        asset: asset.clone(),
+        installed_at: unix_secs(),
+    };
+    if let Ok(json) = serde_json::to_string_pretty(&record) {
+        let _ = std::fs::write(staging.join(INSTALL_RECORD), json);
     }
+    let _ = std::fs::remove_dir_all(&dest);
+    std::fs::rename(&staging, &dest)
+        .map_err(|e| format!("Failed to install llama.cpp {}: {}", version, e))?;
+
+    log::info!("llama-server {} ({}) installed to {}", version, asset, dest.display());
+    println!("llama-server {} installed ({}).", version, asset);
 
-    let asset_name = detect_asset_name()?;
+    Ok(server_path)
+}
+
+/// Run `llama-server --version` to catch builds this machine can't execute
+/// (missing CUDA/HIP runtime, unsupported instruction set).
+fn smoke_test(server_path: &Path) -> Result<(), String> {
+    if !server_path.exists() {
+        return Err("llama-server not found in downloaded archive".into());
+    }
+    let mut cmd = std::process::Command::new(server_path);
+    cmd.arg("--version")
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped());
+    if let Some(parent) = server_path.parent() {
+        cmd.current_dir(parent);
+        cmd.env("GGML_BACKEND_DIR", parent);
+        #[cfg(target_os = "linux")]
+        cmd.env("LD_LIBRARY_PATH", parent);
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+    let output = cmd
+        .output()
+        .map_err(|e| format!("failed to run: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(format!(
+            "exited with {}: {}",
+            output.status,
+            stderr.lines().last().unwrap_or("").trim()
+        ))
+    }
+}
+
+/// Remove an installed version's directory. Refuses to remove the current one.
+pub fn remove_version(bin_dir: &Path, version: &str) -> Result<(), String> {
+    if current_version(bin_dir).as_deref() == Some(version) {
+        return Err(format!("llama.cpp {} is current; switch away first", version));
+    }
+    std::fs::remove_dir_all(version_dir(bin_dir, version))
+        .map_err(|e| format!("Failed to remove llama.cpp {}: {}", version, e))
+}
+
+/// Download release asset `asset_name` of `version` and extract it flat into `dest`.
+/// The archive is streamed to `downloads/<asset>.partial` under `bin_dir`, resuming
+/// from where an earlier attempt (or run) stopped.
+async fn download_release<F>(
+    bin_dir: &Path,
+    version: &str,
+    asset_name: &str,
+    dest: &Path,
+    on_progress: &F,
+) -> Result<(), String>
+where
+    F: Fn(DownloadProgress) + Send + Sync,
+{
     let url = format!(
         "https://github.com/ggml-org/llama.cpp/releases/download/{}/{}",
-        LLAMA_CPP_VERSION, asset_name
+        version, asset_name
     );
 
     log::info!("Downloading llama-server from {}", url);
-    println!("Downloading llama-server {}...", LLAMA_CPP_VERSION);
+    println!("Downloading llama-server {}...", version);
 
-    let client = reqwest::Client::new();
-    let resp = client
-        .get(&url)
-        .send()
-        .await
-        .map_err(|e| format!("Download failed: {}", e))?;
+    let downloads = bin_dir.join(DOWNLOADS_DIR);
+    std::fs::create_dir_all(&downloads)
+        .map_err(|e| format!("Failed to create {}: {}", downloads.display(), e))?;
+    let partial = downloads.join(format!("{}.partial", asset_name));
 
-    if !resp.status().is_success() {
-        return Err(format!("Download failed with HTTP {}", resp.status()));
+    let client = crate::http::client();
+    let mut last_error = String::new();
+    let mut finished = false;
+    for attempt in 1..=DOWNLOAD_ATTEMPTS {
+        match fetch_archive(&client, &url, &partial, on_progress).await {
+            Ok(()) => {
+                finished = true;
+                break;
+            }
+            Err(e) => {
+                log::warn!("llama.cpp download attempt {}/{} failed: {}", attempt, DOWNLOAD_ATTEMPTS, e);
+                last_error = e;
+                if attempt < DOWNLOAD_ATTEMPTS {
+                    tokio::time::sleep(std::time::Duration::from_secs(2 * attempt as u64)).await;
+                }
+            }
+        }
+    }
+    if !finished {
+        return Err(last_error);
     }
 
-    let bytes = resp
-        .bytes()
+    // Extracted size is roughly 3x the zip
+    let archive_len = std::fs::metadata(&partial).map(|m| m.len()).unwrap_or(0);
+    crate::system::ensure_disk_space(dest, archive_len.saturating_mul(3), "llama-server")?;
+    let result = extract_archive(&partial, dest);
+    // A corrupt archive must not be resumed from; a good one is no longer needed
+    let _ = std::fs::remove_file(&partial);
+    result
+}
+
+/// Download (or resume) `url` into `partial` until it is complete.
+async fn fetch_archive<F>(
+    client: &reqwest::Client,
+    url: &str,
+    partial: &Path,
+    on_progress: &F,
+) -> Result<(), String>
+where
+    F: Fn(DownloadProgress) + Send + Sync,
+{
+    let mut existing_size = std::fs::metadata(partial).map(|m| m.len()).unwrap_or(0);
+    let mut req = client.get(url);
+    if existing_size > 0 {
+        log::info!("Resuming llama.cpp download from byte {}", existing_size);
+        req = req.header("Range", format!("bytes={}-", existing_size));
+    }
+    let resp = req.send().await.map_err(|e| format!("Download failed: {}", e))?;
+
+    let total_size = match resp.status().as_u16() {
+        206 => resp
+            .headers()
+            .get("content-range")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|s| s.rsplit('/').next())
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(0),
+        // Asked past the end: the previous attempt already got everything
+        416 if existing_size > 0 => return Ok(()),
+        _ if resp.status().is_success() => {
+            if existing_size > 0 {
+                log::info!("Server does not support resume, restarting llama.cpp download");
+                existing_size = 0;
+            }
+            resp.content_length().unwrap_or(0)
+        }
+        _ => return Err(format!("Download failed with HTTP {}", resp.status())),
+    };
+    if total_size > 0 {
+        crate::system::ensure_disk_space(partial, total_size.saturating_sub(existing_size), "llama-server")?;
+    }
+
+    use std::io::Write;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(existing_size > 0)
+        .write(true)
+        .truncate(existing_size == 0)
+        .open(partial)
+        .map_err(|e| format!("Failed to open {}: {}", partial.display(), e))?;
+
+    let mut downloaded = existing_size;
+    let mut last_pct: i32 = -1;
+    let mut response = resp;
+    while let Some(chunk) = response
+        .chunk()
         .await
-        .map_err(|e| format!("Failed to read download: {}", e))?;
+        .map_err(|e| format!("Download stream error: {}", e))?
+    {
+        file.write_all(&chunk)
+            .map_err(|e| format!("Failed to write {}: {}", partial.display(), e))?;
+        downloaded += chunk.len() as u64;
+        if total_size > 0 {
+            let pct = ((downloaded as f64 / total_size as f64) * 100.0) as i32;
+            if pct != last_pct {
+                last_pct = pct;
+                on_progress(DownloadProgress {
+                    percent: pct as f32,
+                    phase: "downloading".to_string(),
+                    downloaded_bytes: downloaded,
+                    total_bytes: total_size,
+                    free_bytes: None,
+                });
+            }
+        }
+    }
+    file.flush()
+        .map_err(|e| format!("Failed to write {}: {}", partial.display(), e))?;
 
-    // Extract zip
-    let cursor = std::io::Cursor::new(&bytes);
-    let mut archive = zip::ZipArchive::new(cursor)
+    if total_size > 0 && downloaded < total_size {
+        return Err(format!("Download ended early at {} of {} bytes", downloaded, total_size));
+    }
+    Ok(())
+}
+
+/// Extract the executables and shared libraries of the zip at `archive_path` flat into `dest`.
+fn extract_archive(archive_path: &Path, dest: &Path) -> Result<(), String> {
+    let archive_file = std::fs::File::open(archive_path)
+        .map_err(|e| format!("Failed to open {}: {}", archive_path.display(), e))?;
+    let mut archive = zip::ZipArchive::new(std::io::BufReader::new(archive_file))
         .map_err(|e| format!("Failed to open zip: {}", e))?;
 
     for i in 0..archive.len() {
@@ -105,7 +736,7 @@ pub async fn ensure_llama_server(bin_dir: &Path) -> Result<PathBuf, String> {
             .map_err(|e| format!("Zip entry error: {}", e))?;
 
         let name = file.name().to_string();
-        // Extract files to bin_dir (flatten directory structure)
+        // Extract files to dest (flatten directory structure)
         let file_name = Path::new(&name)
             .file_name()
             .map(|n| n.to_string_lossy().to_string())
@@ -130,7 +761,7 @@ pub async fn ensure_llama_server(bin_dir: &Path) -> Result<PathBuf, String> {
             continue;
         }
 
-        let out_path = bin_dir.join(&file_name);
+        let out_path = dest.join(&file_name);
         let mut out_file = std::fs::File::create(&out_path)
             .map_err(|e| format!("Failed to create {}: {}", file_name, e))?;
 
@@ -150,15 +781,12 @@ pub async fn ensure_llama_server(bin_dir: &Path) -> Result<PathBuf, String> {
         }
     }
 
-    // Write version marker
-    let _ = std::fs::write(&version_file, LLAMA_CPP_VERSION);
-
-    if !server_path.exists() {
-        return Err("llama-server not found in downloaded archive".into());
-    }
-
-    log::info!("llama-server {} installed to {}", LLAMA_CPP_VERSION, server_path.display());
-    println!("llama-server {} installed.", LLAMA_CPP_VERSION);
+    Ok(())
+}
 
-    Ok(server_path)
+fn unix_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
 }
@@ -1,5 +1,7 @@
 use std::path::{Path, PathBuf};
 
+use crate::error::{CoreError, CoreResult};
+
 #[derive(Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DownloadProgress {
@@ -7,31 +9,170 @@ pub struct DownloadProgress {
     pub phase: String,
     pub downloaded_bytes: u64,
     pub total_bytes: u64,
+    /// Free space on the target disk, when known.
+    pub free_bytes: Option<u64>,
+}
+
+/// Smaller model files are taken for an incomplete download.
+pub const MIN_MODEL_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Attach `hf_token` (if any) as a bearer token for gated/private repos.
+fn with_hf_auth(req: reqwest::RequestBuilder, hf_token: Option<&str>) -> reqwest::RequestBuilder {
+    match hf_token.filter(|t| !t.is_empty()) {
+        Some(token) => req.bearer_auth(token),
+        None => req,
+    }
+}
+
+/// Error for a failed HuggingFace request, explaining gated-repo denials.
+fn hf_error(what: &str, repo: &str, status: reqwest::StatusCode, hf_token: Option<&str>) -> String {
+    let has_token = hf_token.map(|t| !t.is_empty()).unwrap_or(false);
+    match status.as_u16() {
+        401 | 403 if !has_token => format!(
+            "{} failed with HTTP {}: {} is gated or private. Accept its license at \
+             https://huggingface.co/{} and set an HF token (hfToken) in the config.",
+            what, status, repo, repo
+        ),
+        401 => format!("{} failed with HTTP {}: the HF token was rejected", what, status),
+        403 => format!(
+            "{} failed with HTTP {}: access to {} denied. Accept the license at \
+             https://huggingface.co/{} with the account that owns the HF token.",
+            what, status, repo, repo
+        ),
+        _ => format!("{} failed with HTTP {}", what, status),
+    }
+}
+
+/// A single-file GGUF in a HuggingFace repo.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GgufFile {
+    pub file: String,
+    /// Quantization parsed from the file name, e.g. "Q4_K_M".
+    pub quant: Option<String>,
+    pub size_bytes: u64,
+}
+
+/// List the GGUF files at the root of `repo` via the HuggingFace API, smallest first.
+/// Split (`-00001-of-00002`) files are skipped since `ensure_model` fetches one file.
+pub async fn list_gguf_files(
+    client: &reqwest::Client,
+    repo: &str,
+    hf_token: Option<&str>,
+) -> Result<Vec<GgufFile>, String> {
+    #[derive(serde::Deserialize)]
+    struct TreeEntry {
+        #[serde(rename = "type")]
+        kind: String,
+        path: String,
+        #[serde(default)]
+        size: u64,
+        lfs: Option<LfsInfo>,
+    }
+    #[derive(serde::Deserialize)]
+    struct LfsInfo {
+        size: u64,
+    }
+
+    let url = format!("https://huggingface.co/api/models/{}/tree/main", repo);
+    let resp = with_hf_auth(client.get(&url), hf_token)
+        .timeout(std::time::Duration::from_secs(30))
+        .send()
+        .await
+        .map_err(|e| format!("Model list request failed: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(hf_error("Model list request", repo, resp.status(), hf_token));
+    }
+    let entries: Vec<TreeEntry> = resp
+        .json()
+        .await
+        .map_err(|e| format!("Model list parse error: {}", e))?;
+
+    let mut files: Vec<GgufFile> = entries
+        .into_iter()
+        .filter(|e| e.kind == "file" && e.path.to_lowercase().ends_with(".gguf"))
+        .filter(|e| !e.path.contains("-of-"))
+        .map(|e| GgufFile {
+            quant: quant_label(&e.path),
+            size_bytes: e.lfs.map(|l| l.size).unwrap_or(e.size),
+            file: e.path,
+        })
+        .collect();
+    files.sort_by_key(|f| f.size_bytes);
+    Ok(files)
+}
+
+/// Quantization suffix of a GGUF file name: `Qwen3-32B-Q4_K_M.gguf` -> `Q4_K_M`.
+pub fn quant_label(filename: &str) -> Option<String> {
+    let stem = filename.strip_suffix(".gguf").unwrap_or(filename);
+    let label = stem.rsplit(['-', '.']).next()?.to_uppercase();
+    let known = ["Q", "IQ", "F16", "BF16", "F32"];
+    if known.iter().any(|p| label.starts_with(p)) && label.len() > 1 {
+        Some(label)
+    } else {
+        None
+    }
+}
+
+/// Size of a model file on HuggingFace without downloading it.
+pub async fn remote_model_size(
+    client: &reqwest::Client,
+    repo: &str,
+    filename: &str,
+    hf_token: Option<&str>,
+) -> Result<u64, String> {
+    let url = format!("https://huggingface.co/{}/resolve/main/{}", repo, filename);
+    let resp = with_hf_auth(client.head(&url), hf_token)
+        .send()
+        .await
+        .map_err(|e| format!("Model size request failed: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(hf_error("Model size request", repo, resp.status(), hf_token));
+    }
+    // LFS files report the real size in x-linked-size; content-length may be the redirect body
+    resp.headers()
+        .get("x-linked-size")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.parse::<u64>().ok())
+        .or_else(|| resp.content_length())
+        .filter(|&n| n > 0)
+        .ok_or_else(|| "Model size unknown".to_string())
+}
+
+/// Bytes still to download for `filename` in `models_dir` (accounts for a partial file).
+pub fn remaining_download_bytes(models_dir: &Path, filename: &str, total_bytes: u64) -> u64 {
+    let partial = models_dir.join(format!("{}.partial", filename));
+    let existing = std::fs::metadata(partial).map(|m| m.len()).unwrap_or(0);
+    total_bytes.saturating_sub(existing)
 }
 
 /// Ensure the GGUF model file exists. Downloads from HuggingFace if missing.
 /// Returns the full path to the model file.
 ///
-/// `on_progress` is called with download progress updates.
+/// `on_progress` is called with download progress updates. `hf_token` is sent
+/// for gated/private repos; `model_url` is tried before the built-in mirrors
+/// (see `model_download_urls`).
 pub async fn ensure_model<F>(
     models_dir: &Path,
     repo: &str,
     filename: &str,
+    hf_token: Option<&str>,
+    model_url: Option<&str>,
     on_progress: F,
-) -> Result<PathBuf, String>
+) -> CoreResult<PathBuf>
 where
     F: Fn(DownloadProgress) + Send + 'static,
 {
     std::fs::create_dir_all(models_dir)
-        .map_err(|e| format!("Failed to create models dir: {}", e))?;
+        .map_err(|e| CoreError::Model(format!("Failed to create models dir: {}", e)))?;
 
     let model_path = models_dir.join(filename);
 
     if model_path.exists() {
         let metadata = std::fs::metadata(&model_path)
-            .map_err(|e| format!("Failed to read model file metadata: {}", e))?;
+            .map_err(|e| CoreError::Model(format!("Failed to read model file metadata: {}", e)))?;
         // Sanity check: GGUF should be at least 100MB
-        if metadata.len() > 100 * 1024 * 1024 {
+        if metadata.len() > MIN_MODEL_BYTES {
             log::info!("Model already downloaded: {:?} ({:.1} GB)", model_path, metadata.len() as f64 / 1e9);
             return Ok(model_path);
         }
@@ -43,14 +184,73 @@ where
     // Check for partial download
     let partial_path = models_dir.join(format!("{}.partial", filename));
 
-    download_model(repo, filename, &model_path, &partial_path, on_progress).await?;
+    download_model(repo, filename, hf_token, model_url, &model_path, &partial_path, on_progress)
+        .await
+        .map_err(CoreError::Model)?;
+    // A checksum from an earlier copy of this file no longer applies
+    if let Err(e) = super::integrity::forget(models_dir, filename) {
+        log::warn!("{}", e);
+    }
+    let size_bytes = std::fs::metadata(&model_path).map(|m| m.len()).unwrap_or(0);
+    if let Err(e) = super::registry::register(
+        models_dir,
+        super::registry::ModelEntry::downloaded(repo, filename, size_bytes),
+    ) {
+        log::warn!("{}", e);
+    }
+    crate::events::publish(crate::events::AgentEvent::DownloadCompleted {
+        file: filename.to_string(),
+        repo: repo.to_string(),
+        size_bytes,
+    });
 
     Ok(model_path)
 }
 
+/// Built-in sources tried after `model_url`, in order. Each serves HF's `resolve` layout.
+pub const MODEL_MIRRORS: &[&str] = &["https://huggingface.co", "https://hf-mirror.com"];
+
+/// Candidate download URLs for `filename`, most preferred first.
+///
+/// `model_url` may be a direct link to the file (ends in `.gguf`) or a mirror
+/// base that serves `<base>/<repo>/resolve/main/<file>`; either way it is
+/// tried before the built-in `MODEL_MIRRORS`.
+pub fn model_download_urls(repo: &str, filename: &str, model_url: Option<&str>) -> Vec<String> {
+    let mut urls = Vec::new();
+    if let Some(custom) = model_url.map(str::trim).filter(|u| !u.is_empty()) {
+        if custom.to_lowercase().ends_with(".gguf") {
+            urls.push(custom.to_string());
+        } else {
+            urls.push(format!(
+                "{}/{}/resolve/main/{}",
+                custom.trim_end_matches('/'),
+                repo,
+                filename
+            ));
+        }
+    }
+    for base in MODEL_MIRRORS {
+        let url = format!("{}/{}/resolve/main/{}", base, repo, filename);
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+    urls
+}
+
+/// Why a download attempt from one source stopped.
+enum FetchError {
+    /// The source failed (network, HTTP status, dropped stream); try the next one.
+    Source(String),
+    /// A local problem (disk full, write error) that another source won't fix.
+    Local(String),
+}
+
 async fn download_model<F>(
     repo: &str,
     filename: &str,
+    hf_token: Option<&str>,
+    model_url: Option<&str>,
     target: &Path,
     partial: &Path,
     on_progress: F,
@@ -58,23 +258,72 @@ async fn download_model<F>(
 where
     F: Fn(DownloadProgress) + Send + 'static,
 {
-    let url = format!(
-        "https://huggingface.co/{}/resolve/main/{}",
-        repo, filename
-    );
+    let client = crate::http::client();
+    let urls = model_download_urls(repo, filename, model_url);
 
-    log::info!("Downloading model from {}", url);
+    // The partial file is shared, so a later mirror resumes where an earlier one stopped
+    let mut errors = Vec::new();
+    for url in &urls {
+        // Only HuggingFace itself gets the token
+        let token = if url.starts_with("https://huggingface.co/") {
+            hf_token
+        } else {
+            None
+        };
+        match download_from(&client, url, repo, filename, token, partial, &on_progress).await {
+            Ok(downloaded) => {
+                // Rename partial → final
+                std::fs::rename(partial, target)
+                    .map_err(|e| format!("Failed to finalize model file: {}", e))?;
+                if let Some(dir) = target.parent() {
+                    crate::system::sync_dir(dir);
+                }
+
+                log::info!(
+                    "Model download complete: {:.1} GB",
+                    downloaded as f64 / 1e9
+                );
+                return Ok(());
+            }
+            Err(FetchError::Local(e)) => return Err(e),
+            Err(FetchError::Source(e)) => {
+                log::warn!("Download from {} failed: {}", url, e);
+                errors.push(e);
+            }
+        }
+    }
 
-    let client = reqwest::Client::new();
+    // The first source's error is usually the most telling (e.g. a gated repo)
+    Err(match errors.len() {
+        0 => "No download sources".to_string(),
+        1 => errors.remove(0),
+        n => format!("{} (and {} mirror(s) also failed)", errors.remove(0), n - 1),
+    })
+}
+
+/// Download (or resume) `partial` from `url`. Returns the final size.
+async fn download_from<F>(
+    client: &reqwest::Client,
+    url: &str,
+    repo: &str,
+    filename: &str,
+    hf_token: Option<&str>,
+    partial: &Path,
+    on_progress: &F,
+) -> Result<u64, FetchError>
+where
+    F: Fn(DownloadProgress),
+{
+    log::info!("Downloading model from {}", url);
 
     // Check if partial file exists for resume
-    let existing_size = if partial.exists() {
+    let mut existing_size = if partial.exists() {
         std::fs::metadata(partial).map(|m| m.len()).unwrap_or(0)
     } else {
         0
     };
 
-    let mut req = client.get(&url);
+    let mut req = with_hf_auth(client.get(url), hf_token);
     if existing_size > 0 {
         log::info!("Resuming download from byte {}", existing_size);
         req = req.header("Range", format!("bytes={}-", existing_size));
@@ -83,10 +332,10 @@ where
     let resp = req
         .send()
         .await
-        .map_err(|e| format!("Download request failed: {}", e))?;
+        .map_err(|e| FetchError::Source(format!("Download request failed: {}", e)))?;
 
     if !resp.status().is_success() && resp.status().as_u16() != 206 {
-        return Err(format!("Download failed with HTTP {}", resp.status()));
+        return Err(FetchError::Source(hf_error("Download", repo, resp.status(), hf_token)));
     }
 
     let total_size = if resp.status().as_u16() == 206 {
@@ -98,9 +347,23 @@ where
             .and_then(|s| s.parse::<u64>().ok())
             .unwrap_or(0)
     } else {
+        // Source ignored the Range header and is sending the whole file
+        if existing_size > 0 {
+            log::info!("Source does not support resume, restarting download");
+            existing_size = 0;
+        }
         resp.content_length().unwrap_or(0)
     };
 
+    if total_size > 0 {
+        crate::system::ensure_disk_space(
+            partial,
+            total_size.saturating_sub(existing_size),
+            &format!("model {}", filename),
+        )
+        .map_err(FetchError::Local)?;
+    }
+
     // Open file for append (resume) or create
     use std::io::Write;
     let mut file = std::fs::OpenOptions::new()
@@ -109,47 +372,70 @@ where
         .write(true)
         .truncate(existing_size == 0)
         .open(partial)
-        .map_err(|e| format!("Failed to open partial file: {}", e))?;
+        .map_err(|e| FetchError::Local(format!("Failed to open partial file: {}", e)))?;
+    if total_size > 0 {
+        crate::system::preallocate(&file, total_size).map_err(FetchError::Local)?;
+    }
 
     let mut downloaded = existing_size;
     let mut last_pct: i32 = -1;
+    let mut low_disk_warned = false;
 
     // Stream download using chunk()
     let mut response = resp;
     while let Some(chunk) = response
         .chunk()
         .await
-        .map_err(|e| format!("Download stream error: {}", e))?
+        .map_err(|e| FetchError::Source(format!("Download stream error: {}", e)))?
     {
         file.write_all(&chunk)
-            .map_err(|e| format!("Write error: {}", e))?;
+            .map_err(|e| FetchError::Local(format!("Write error: {}", e)))?;
         downloaded += chunk.len() as u64;
 
         if total_size > 0 {
             let pct = ((downloaded as f64 / total_size as f64) * 100.0) as i32;
             if pct != last_pct {
                 last_pct = pct;
+                // Re-check free space once per percent: other programs may fill the disk meanwhile
+                let free_bytes = crate::system::available_disk_space(partial);
+                if let Some(free) = free_bytes {
+                    let remaining = total_size.saturating_sub(downloaded);
+                    if free < remaining {
+                        return Err(FetchError::Local(format!(
+                            "Disk almost full: {:.1} GB left to download but only {:.1} GB free. \
+                             Free up space and restart; the download will resume.",
+                            remaining as f64 / 1e9,
+                            free as f64 / 1e9,
+                        )));
+                    }
+                    if free < crate::system::LOW_DISK_WARN_BYTES && !low_disk_warned {
+                        low_disk_warned = true;
+                        log::warn!("Low disk space while downloading model: {:.1} GB free", free as f64 / 1e9);
+                    }
+                }
                 on_progress(DownloadProgress {
                     percent: pct as f32,
                     phase: "downloading".to_string(),
                     downloaded_bytes: downloaded,
                     total_bytes: total_size,
+                    free_bytes,
                 });
             }
         }
     }
 
-    file.flush().map_err(|e| format!("Flush error: {}", e))?;
-    drop(file);
+    file.flush()
+        .map_err(|e| FetchError::Local(format!("Flush error: {}", e)))?;
+    // On disk before the rename, so a power loss can't leave a truncated "complete" model
+    file.sync_all()
+        .map_err(|e| FetchError::Local(format!("Sync error: {}", e)))?;
 
-    // Rename partial → final
-    std::fs::rename(partial, target)
-        .map_err(|e| format!("Failed to finalize model file: {}", e))?;
-
-    log::info!(
-        "Model download complete: {:.1} GB",
-        downloaded as f64 / 1e9
-    );
+    if total_size > 0 && downloaded < total_size {
+        return Err(FetchError::Source(format!(
+            "Download ended early at {} of {} bytes",
+            downloaded, total_size
+        )));
+    }
 
-    Ok(())
+    Ok(downloaded)
 }
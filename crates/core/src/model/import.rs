@@ -0,0 +1,194 @@
+use std::io::Read;
+use std::path::Path;
+
+use super::registry::{self, ModelEntry};
+
+/// How `import_model` brings the file into the models directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    Copy,
+    /// Rename on the same filesystem, otherwise copy and delete the source.
+    Move,
+    /// Hardlink, else symlink, else copy: no duplicate data where the OS allows it.
+    Link,
+    Symlink,
+    /// Falls back to a copy across filesystems.
+    Hardlink,
+}
+
+impl ImportMode {
+    pub fn parse(mode: &str) -> Result<Self, String> {
+        match mode {
+            "copy" => Ok(ImportMode::Copy),
+            "move" => Ok(ImportMode::Move),
+            "link" => Ok(ImportMode::Link),
+            "symlink" => Ok(ImportMode::Symlink),
+            "hardlink" => Ok(ImportMode::Hardlink),
+            other => Err(format!(
+                "Unknown import mode '{}' (expected copy, move, link, symlink or hardlink)",
+                other
+            )),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn symlink_file(source: &Path, target: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(source, target)
+}
+
+/// Needs Developer Mode or an elevated process on Windows.
+#[cfg(windows)]
+fn symlink_file(source: &Path, target: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(source, target)
+}
+
+/// Reference `source` from `target` without copying; the link kind on success.
+fn link_file(source: &Path, target: &Path, mode: ImportMode) -> Option<&'static str> {
+    if matches!(mode, ImportMode::Link | ImportMode::Hardlink) {
+        match std::fs::hard_link(source, target) {
+            Ok(()) => return Some("hardlink"),
+            Err(e) => log::info!("Hardlink to {} not possible: {}", source.display(), e),
+        }
+    }
+    if matches!(mode, ImportMode::Link | ImportMode::Symlink) {
+        // A relative link would resolve against the models directory
+        let absolute = std::fs::canonicalize(source).unwrap_or_else(|_| source.to_path_buf());
+        match symlink_file(&absolute, target) {
+            Ok(()) => return Some("symlink"),
+            Err(e) => log::info!("Symlink to {} not possible: {}", source.display(), e),
+        }
+    }
+    None
+}
+
+/// Fixed part of a GGUF header.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GgufHeader {
+    pub version: u32,
+    pub tensor_count: u64,
+    pub metadata_kv_count: u64,
+}
+
+/// Read and sanity-check the header of a GGUF file: magic, a known version and
+/// at least one tensor.
+pub fn read_gguf_header(path: &Path) -> Result<GgufHeader, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut buf = [0u8; 24];
+    file.read_exact(&mut buf)
+        .map_err(|_| format!("{} is too short to be a GGUF file", path.display()))?;
+
+    if &buf[0..4] != b"GGUF" {
+        return Err(format!("{} is not a GGUF file (bad magic)", path.display()));
+    }
+    let version = u32::from_le_bytes(buf[4..8].try_into().unwrap_or_default());
+    if !(1..=3).contains(&version) {
+        return Err(format!("{} has unsupported GGUF version {}", path.display(), version));
+    }
+    let (tensor_count, metadata_kv_count) = if version == 1 {
+        // v1 used 32-bit counts
+        (
+            u32::from_le_bytes(buf[8..12].try_into().unwrap_or_default()) as u64,
+            u32::from_le_bytes(buf[12..16].try_into().unwrap_or_default()) as u64,
+        )
+    } else {
+        (
+            u64::from_le_bytes(buf[8..16].try_into().unwrap_or_default()),
+            u64::from_le_bytes(buf[16..24].try_into().unwrap_or_default()),
+        )
+    };
+    if tensor_count == 0 {
+        return Err(format!("{} has no tensors", path.display()));
+    }
+    Ok(GgufHeader {
+        version,
+        tensor_count,
+        metadata_kv_count,
+    })
+}
+
+/// Bring a GGUF from elsewhere on disk into `models_dir` and register it, without
+/// any download. `name` overrides the file name. Links that the filesystem or OS
+/// refuses fall back to a copy.
+///
+/// May copy the whole file; call from a blocking task.
+pub fn import_model(
+    models_dir: &Path,
+    source: &Path,
+    name: Option<&str>,
+    mode: ImportMode,
+) -> Result<ModelEntry, String> {
+    let file = match name.map(str::trim).filter(|n| !n.is_empty()) {
+        Some(name) => name.to_string(),
+        None => source
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or_else(|| format!("{} has no file name", source.display()))?,
+    };
+    if file.contains(['/', '\\']) || file.starts_with('.') || !file.to_lowercase().ends_with(".gguf") {
+        return Err(format!("Invalid model file name: {} (expected <name>.gguf)", file));
+    }
+
+    let size_bytes = std::fs::metadata(source)
+        .map_err(|e| format!("Failed to read {}: {}", source.display(), e))?
+        .len();
+    // ensure_model treats anything smaller as an incomplete download and deletes it
+    if size_bytes <= super::download::MIN_MODEL_BYTES {
+        return Err(format!(
+            "{} is only {} bytes; a model file is at least {} MB",
+            source.display(),
+            size_bytes,
+            super::download::MIN_MODEL_BYTES / (1024 * 1024)
+        ));
+    }
+    read_gguf_header(source)?;
+
+    std::fs::create_dir_all(models_dir).map_err(|e| format!("Failed to create models dir: {}", e))?;
+    let target = models_dir.join(&file);
+    if target.exists() {
+        return Err(format!("{} already exists in {}", file, models_dir.display()));
+    }
+
+    let link = match mode {
+        ImportMode::Link | ImportMode::Symlink | ImportMode::Hardlink => {
+            let link = link_file(source, &target, mode);
+            if link.is_none() {
+                log::warn!("Could not link {}; copying it instead", source.display());
+            }
+            link
+        }
+        ImportMode::Copy | ImportMode::Move => None,
+    };
+    let remove_source = mode == ImportMode::Move;
+
+    // A rename is instant on the same filesystem; otherwise copy (and delete the source)
+    let moved = link.is_some() || (remove_source && std::fs::rename(source, &target).is_ok());
+    if !moved {
+        crate::system::ensure_disk_space(models_dir, size_bytes, &format!("model {}", file))?;
+        let partial = models_dir.join(format!("{}.import", file));
+        log::info!("Copying {} to {}...", source.display(), target.display());
+        if let Err(e) = std::fs::copy(source, &partial) {
+            let _ = std::fs::remove_file(&partial);
+            return Err(format!("Failed to copy {}: {}", source.display(), e));
+        }
+        std::fs::rename(&partial, &target).map_err(|e| format!("Failed to finalize {}: {}", file, e))?;
+        if remove_source {
+            if let Err(e) = std::fs::remove_file(source) {
+                log::warn!("Imported {} but could not remove the original: {}", file, e);
+            }
+        }
+    }
+
+    // Any checksum recorded for an earlier file of this name no longer applies
+    super::integrity::forget(models_dir, &file)?;
+    let entry = ModelEntry::imported(source, &file, size_bytes, link);
+    registry::register(models_dir, entry.clone())?;
+    log::info!(
+        "Imported {} ({:.1} GB{})",
+        file,
+        size_bytes as f64 / 1e9,
+        link.map(|l| format!(", {} to {}", l, source.display())).unwrap_or_default()
+    );
+    Ok(entry)
+}
@@ -0,0 +1,220 @@
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Model files are hashed in chunks of this size, so a mismatch points at the damaged region.
+pub const CHUNK_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Checksums of every verified model, next to the models themselves.
+const MANIFEST_FILE: &str = "checksums.json";
+
+/// Hashes recorded the first time a model file was verified.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelChecksum {
+    pub size_bytes: u64,
+    /// SHA-256 of the whole file, hex.
+    pub sha256: String,
+    pub chunk_bytes: u64,
+    /// SHA-256 of each `chunk_bytes` chunk, hex.
+    pub chunks: Vec<String>,
+    /// Unix seconds of the last successful verification.
+    pub verified_at: u64,
+}
+
+/// Outcome of `verify_model`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "result")]
+pub enum IntegrityStatus {
+    /// No checksum was stored yet; this one is now the reference.
+    Recorded,
+    /// The file matches its stored checksum.
+    Verified,
+    /// Verified more recently than the interval; not read again.
+    Skipped,
+    /// The file differs from its stored checksum.
+    #[serde(rename_all = "camelCase")]
+    Mismatch { offset: u64, reason: String },
+}
+
+impl IntegrityStatus {
+    pub fn is_mismatch(&self) -> bool {
+        matches!(self, IntegrityStatus::Mismatch { .. })
+    }
+
+    /// One line for logs and the UI.
+    pub fn message(&self, filename: &str) -> String {
+        match self {
+            IntegrityStatus::Recorded => format!("Recorded checksum of {}", filename),
+            IntegrityStatus::Verified => format!("{} matches its recorded checksum", filename),
+            IntegrityStatus::Skipped => format!("{} was verified recently", filename),
+            IntegrityStatus::Mismatch { offset, reason } => format!(
+                "{} is corrupted ({} at byte {}); delete it and download it again",
+                filename, reason, offset
+            ),
+        }
+    }
+}
+
+fn manifest_path(models_dir: &Path) -> PathBuf {
+    models_dir.join(MANIFEST_FILE)
+}
+
+fn load_manifest(models_dir: &Path) -> BTreeMap<String, ModelChecksum> {
+    std::fs::read_to_string(manifest_path(models_dir))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn save_manifest(models_dir: &Path, manifest: &BTreeMap<String, ModelChecksum>) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(manifest)
+        .map_err(|e| format!("Failed to serialize checksums: {}", e))?;
+    let path = manifest_path(models_dir);
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json).map_err(|e| format!("Failed to write checksums: {}", e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to write checksums: {}", e))
+}
+
+fn unix_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// The stored checksum of `filename`, if it was ever verified.
+pub fn recorded(models_dir: &Path, filename: &str) -> Option<ModelChecksum> {
+    load_manifest(models_dir).remove(filename)
+}
+
+/// Drop the stored checksum of `filename`, e.g. after a fresh download replaced the file.
+pub fn forget(models_dir: &Path, filename: &str) -> Result<(), String> {
+    let mut manifest = load_manifest(models_dir);
+    if manifest.remove(filename).is_some() {
+        save_manifest(models_dir, &manifest)?;
+    }
+    Ok(())
+}
+
+/// Delete a corrupted model and its checksum so the next start downloads it again.
+pub fn discard(models_dir: &Path, filename: &str) -> Result<(), String> {
+    let path = models_dir.join(filename);
+    if path.exists() {
+        std::fs::remove_file(&path).map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+    }
+    super::registry::unregister(models_dir, filename)?;
+    forget(models_dir, filename)
+}
+
+/// Hash `path` chunk by chunk. Stops at the first chunk that differs from `expected`,
+/// returning the hashes read so far and the whole-file hash (None if stopped early).
+fn hash_chunks(path: &Path, expected: Option<&[String]>) -> Result<(Vec<String>, Option<String>), String> {
+    let mut file = std::fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut whole = Sha256::new();
+    let mut chunks = Vec::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+
+    loop {
+        let mut chunk = Sha256::new();
+        let mut read_in_chunk = 0u64;
+        while read_in_chunk < CHUNK_BYTES {
+            let want = buf.len().min((CHUNK_BYTES - read_in_chunk) as usize);
+            let n = file
+                .read(&mut buf[..want])
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            if n == 0 {
+                break;
+            }
+            chunk.update(&buf[..n]);
+            whole.update(&buf[..n]);
+            read_in_chunk += n as u64;
+        }
+        if read_in_chunk == 0 {
+            break;
+        }
+        let digest = hex::encode(chunk.finalize());
+        let differs = expected.is_some_and(|e| e.get(chunks.len()) != Some(&digest));
+        chunks.push(digest);
+        if differs {
+            return Ok((chunks, None));
+        }
+        if read_in_chunk < CHUNK_BYTES {
+            break;
+        }
+    }
+    Ok((chunks, Some(hex::encode(whole.finalize()))))
+}
+
+/// Check `filename` in `models_dir` against its stored checksum, recording one if
+/// there is none. With `interval`, a file verified within it is not read again.
+///
+/// Reads the whole file; call from a blocking task.
+pub fn verify_model(
+    models_dir: &Path,
+    filename: &str,
+    interval: Option<std::time::Duration>,
+) -> Result<IntegrityStatus, String> {
+    let path = models_dir.join(filename);
+    let size_bytes = std::fs::metadata(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .len();
+    let mut manifest = load_manifest(models_dir);
+    let now = unix_secs();
+
+    let Some(stored) = manifest.get_mut(filename) else {
+        log::info!("Hashing {} to record its checksum...", filename);
+        let (chunks, sha256) = hash_chunks(&path, None)?;
+        manifest.insert(
+            filename.to_string(),
+            ModelChecksum {
+                size_bytes,
+                sha256: sha256.unwrap_or_default(),
+                chunk_bytes: CHUNK_BYTES,
+                chunks,
+                verified_at: now,
+            },
+        );
+        save_manifest(models_dir, &manifest)?;
+        return Ok(IntegrityStatus::Recorded);
+    };
+
+    if stored.size_bytes != size_bytes {
+        return Ok(IntegrityStatus::Mismatch {
+            offset: stored.size_bytes.min(size_bytes),
+            reason: format!("size is {} bytes, expected {}", size_bytes, stored.size_bytes),
+        });
+    }
+    if let Some(interval) = interval {
+        if now.saturating_sub(stored.verified_at) < interval.as_secs() {
+            return Ok(IntegrityStatus::Skipped);
+        }
+    }
+    if stored.chunk_bytes != CHUNK_BYTES {
+        // Recorded with another chunk size; only the whole-file hash can be compared
+        let (_, sha256) = hash_chunks(&path, None)?;
+        if sha256.as_deref() != Some(stored.sha256.as_str()) {
+            return Ok(IntegrityStatus::Mismatch {
+                offset: 0,
+                reason: "SHA-256 differs".to_string(),
+            });
+        }
+    } else {
+        log::info!("Verifying {}...", filename);
+        let (chunks, sha256) = hash_chunks(&path, Some(&stored.chunks))?;
+        if sha256.as_deref() != Some(stored.sha256.as_str()) {
+            let bad = chunks.len().saturating_sub(1) as u64;
+            return Ok(IntegrityStatus::Mismatch {
+                offset: bad * CHUNK_BYTES,
+                reason: format!("chunk {} differs", bad),
+            });
+        }
+    }
+
+    stored.verified_at = now;
+    save_manifest(models_dir, &manifest)?;
+    Ok(IntegrityStatus::Verified)
+}
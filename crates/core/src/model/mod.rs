@@ -1 +1,5 @@
 pub mod download;
+pub mod import;
+pub mod integrity;
+pub mod recommend;
+pub mod registry;
@@ -0,0 +1,150 @@
+//! Pick a model + quantization that fits the detected hardware.
+
+use serde::Serialize;
+
+/// Reserved on top of the weights for KV cache, compute buffers and the driver.
+const OVERHEAD_MB: u64 = 2048;
+/// RAM left for the OS and other programs when spilling layers to system memory.
+const SYSTEM_RESERVE_MB: u64 = 4096;
+/// Share of unified memory macOS lets the GPU wire by default.
+const UNIFIED_GPU_SHARE: f64 = 0.70;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogEntry {
+    pub repo: &'static str,
+    pub file: &'static str,
+    pub quant: &'static str,
+    pub size_mb: u64,
+    pub layers: u32,
+}
+
+/// Models the Oracle serves, best quality first within each repo.
+pub const CATALOG: &[CatalogEntry] = &[
+    CatalogEntry {
+        repo: "Qwen/Qwen3-32B-GGUF",
+        file: "Qwen3-32B-Q8_0.gguf",
+        quant: "Q8_0",
+        size_mb: 34_800,
+        layers: 64,
+    },
+    CatalogEntry {
+        repo: "Qwen/Qwen3-32B-GGUF",
+        file: "Qwen3-32B-Q6_K.gguf",
+        quant: "Q6_K",
+        size_mb: 26_900,
+        layers: 64,
+    },
+    CatalogEntry {
+        repo: "Qwen/Qwen3-32B-GGUF",
+        file: "Qwen3-32B-Q5_K_M.gguf",
+        quant: "Q5_K_M",
+        size_mb: 23_200,
+        layers: 64,
+    },
+    CatalogEntry {
+        repo: "Qwen/Qwen3-32B-GGUF",
+        file: "Qwen3-32B-Q4_K_M.gguf",
+        quant: "Q4_K_M",
+        size_mb: 19_800,
+        layers: 64,
+    },
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Recommendation {
+    pub model: String,
+    pub model_file: String,
+    pub quant: String,
+    pub size_mb: u64,
+    /// Suggested `gpu_layers`: 99 when everything fits in VRAM.
+    pub gpu_layers: i32,
+    pub fully_offloaded: bool,
+    /// False when even the smallest model exceeds VRAM + RAM.
+    pub fits: bool,
+    pub reason: String,
+}
+
+/// Recommend a catalog entry for `vram_mb` of GPU memory and `ram_mb` of system RAM.
+/// With `unified_memory` (Apple Silicon), `vram_mb` is the shared pool.
+pub fn recommend(vram_mb: u64, ram_mb: u64, unified_memory: bool) -> Recommendation {
+    let gpu_budget = if unified_memory {
+        (vram_mb as f64 * UNIFIED_GPU_SHARE) as u64
+    } else {
+        vram_mb
+    };
+    let gpu_budget = gpu_budget.saturating_sub(OVERHEAD_MB);
+
+    // Best quality that fits entirely on the GPU
+    if let Some(entry) = CATALOG.iter().find(|e| e.size_mb <= gpu_budget) {
+        return build(
+            entry,
+            99,
+            true,
+            true,
+            format!(
+                "{} fits fully in {:.0} GB of GPU memory",
+                entry.quant,
+                vram_mb as f64 / 1024.0
+            ),
+        );
+    }
+
+    // Otherwise the smallest quant, split between GPU and system RAM
+    let smallest = CATALOG
+        .iter()
+        .min_by_key(|e| e.size_mb)
+        .expect("model catalog is empty");
+    let ram_budget = if unified_memory {
+        0
+    } else {
+        ram_mb.saturating_sub(SYSTEM_RESERVE_MB)
+    };
+    let gpu_share = (gpu_budget as f64 / smallest.size_mb as f64).min(1.0);
+    let gpu_layers = (smallest.layers as f64 * gpu_share).floor() as i32;
+    let fits = gpu_budget + ram_budget >= smallest.size_mb;
+
+    let reason = if !fits {
+        format!(
+            "Not enough memory: {} needs ~{:.0} GB, {:.0} GB VRAM + {:.0} GB RAM available",
+            smallest.quant,
+            (smallest.size_mb + OVERHEAD_MB) as f64 / 1024.0,
+            vram_mb as f64 / 1024.0,
+            ram_mb as f64 / 1024.0
+        )
+    } else if gpu_layers > 0 {
+        format!(
+            "{} with {} of {} layers on the GPU, the rest in system RAM (slower)",
+            smallest.quant, gpu_layers, smallest.layers
+        )
+    } else {
+        format!("{} on CPU only (no usable GPU memory)", smallest.quant)
+    };
+    build(smallest, gpu_layers, false, fits, reason)
+}
+
+/// Recommendation for this machine, using the cached `hardware::profile`.
+pub fn recommend_for_host() -> Recommendation {
+    let hardware = crate::hardware::profile();
+    recommend(hardware.vram_mb(), hardware.total_ram_mb, cfg!(target_os = "macos"))
+}
+
+fn build(
+    entry: &CatalogEntry,
+    gpu_layers: i32,
+    fully_offloaded: bool,
+    fits: bool,
+    reason: String,
+) -> Recommendation {
+    Recommendation {
+        model: entry.repo.to_string(),
+        model_file: entry.file.to_string(),
+        quant: entry.quant.to_string(),
+        size_mb: entry.size_mb,
+        gpu_layers,
+        fully_offloaded,
+        fits,
+        reason,
+    }
+}
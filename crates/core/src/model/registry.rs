@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Where each file in the models directory came from.
+const REGISTRY_FILE: &str = "registry.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelEntry {
+    pub file: String,
+    /// HuggingFace repo the file was downloaded from; empty for imports.
+    #[serde(default)]
+    pub repo: String,
+    /// Original path of an imported file.
+    #[serde(default)]
+    pub imported_from: Option<String>,
+    /// "symlink" or "hardlink" when the file references `imported_from` instead of
+    /// being a copy of it.
+    #[serde(default)]
+    pub link: Option<String>,
+    pub size_bytes: u64,
+    /// Unix seconds when the file was added.
+    pub added_at: u64,
+}
+
+impl ModelEntry {
+    pub fn downloaded(repo: &str, file: &str, size_bytes: u64) -> Self {
+        Self {
+            file: file.to_string(),
+            repo: repo.to_string(),
+            imported_from: None,
+            link: None,
+            size_bytes,
+            added_at: unix_secs(),
+        }
+    }
+
+    pub fn imported(source: &Path, file: &str, size_bytes: u64, link: Option<&str>) -> Self {
+        Self {
+            file: file.to_string(),
+            repo: String::new(),
+            imported_from: Some(source.display().to_string()),
+            link: link.map(str::to_string),
+            size_bytes,
+            added_at: unix_secs(),
+        }
+    }
+}
+
+fn registry_path(models_dir: &Path) -> PathBuf {
+    models_dir.join(REGISTRY_FILE)
+}
+
+fn load(models_dir: &Path) -> BTreeMap<String, ModelEntry> {
+    std::fs::read_to_string(registry_path(models_dir))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn save(models_dir: &Path, entries: &BTreeMap<String, ModelEntry>) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(entries)
+        .map_err(|e| format!("Failed to serialize model registry: {}", e))?;
+    let path = registry_path(models_dir);
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json).map_err(|e| format!("Failed to write model registry: {}", e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to write model registry: {}", e))
+}
+
+fn unix_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Registered models whose file is still present, by file name.
+pub fn list(models_dir: &Path) -> Vec<ModelEntry> {
+    load(models_dir)
+        .into_values()
+        .filter(|entry| models_dir.join(&entry.file).exists())
+        .collect()
+}
+
+pub fn get(models_dir: &Path, file: &str) -> Option<ModelEntry> {
+    load(models_dir).remove(file)
+}
+
+/// Add or replace the entry for `entry.file`.
+pub fn register(models_dir: &Path, entry: ModelEntry) -> Result<(), String> {
+    let mut entries = load(models_dir);
+    entries.insert(entry.file.clone(), entry);
+    save(models_dir, &entries)
+}
+
+pub fn unregister(models_dir: &Path, file: &str) -> Result<(), String> {
+    let mut entries = load(models_dir);
+    if entries.remove(file).is_some() {
+        save(models_dir, &entries)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,127 @@
+//! Signed uptime attestations, sent with every metrics report while serving.
+//!
+//! Each attestation carries a counter that only ever goes up (persisted in
+//! `<config_dir>/uptime-attestation.json`, so it survives restarts), the time, and the
+//! start of the current streak. A report missed for longer than `STREAK_GAP_SECS`
+//! starts a new streak; the counter keeps counting.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use k256::ecdsa::SigningKey;
+use serde::{Deserialize, Serialize};
+
+use crate::chain::crypto::{address_from_key, personal_sign};
+
+const ATTESTATION_FILE_NAME: &str = "uptime-attestation.json";
+/// Three missed 60s reports end a streak.
+pub const STREAK_GAP_SECS: u64 = 180;
+
+/// Local attestation history.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UptimeStreak {
+    /// Attestations signed so far, over all runs.
+    #[serde(default)]
+    pub counter: u64,
+    /// Unix epoch seconds; 0 before the first attestation.
+    #[serde(default)]
+    pub streak_started_at: u64,
+    #[serde(default)]
+    pub last_attested_at: u64,
+}
+
+impl UptimeStreak {
+    /// Saved history, or an empty one.
+    pub fn load(dir: &Path) -> Self {
+        std::fs::read_to_string(dir.join(ATTESTATION_FILE_NAME))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Length of the streak at `now`; 0 once it has lapsed.
+    pub fn streak_secs(&self, now: u64) -> u64 {
+        if self.last_attested_at == 0 || now.saturating_sub(self.last_attested_at) > STREAK_GAP_SECS
+        {
+            0
+        } else {
+            now.saturating_sub(self.streak_started_at)
+        }
+    }
+}
+
+/// Body of the `attestation` field in a metrics report.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UptimeAttestation {
+    pub counter: u64,
+    pub timestamp: u64,
+    pub streak_started_at: u64,
+    /// personal_sign over "{address}:{counter}:{timestamp}:{streakStartedAt}".
+    pub signature: String,
+}
+
+/// Signs attestations and keeps the counter on disk.
+pub struct UptimeAttester {
+    dir: PathBuf,
+    signing_key: SigningKey,
+    address: String,
+    streak: Mutex<UptimeStreak>,
+}
+
+impl UptimeAttester {
+    pub fn open(dir: &Path, signing_key: &SigningKey) -> Arc<Self> {
+        Arc::new(Self {
+            dir: dir.to_path_buf(),
+            address: address_from_key(signing_key),
+            signing_key: signing_key.clone(),
+            streak: Mutex::new(UptimeStreak::load(dir)),
+        })
+    }
+
+    /// Count one more period of uptime and sign it.
+    pub fn attest(&self) -> Result<UptimeAttestation, String> {
+        let timestamp = crate::ledger::unix_millis() / 1000;
+        let mut streak = self
+            .streak
+            .lock()
+            .map_err(|_| "Uptime attestation state poisoned".to_string())?;
+        if streak.last_attested_at == 0
+            || timestamp.saturating_sub(streak.last_attested_at) > STREAK_GAP_SECS
+        {
+            streak.streak_started_at = timestamp;
+        }
+        streak.counter += 1;
+        streak.last_attested_at = timestamp;
+
+        let message = format!(
+            "{}:{}:{}:{}",
+            self.address, streak.counter, timestamp, streak.streak_started_at
+        );
+        let signature = personal_sign(&message, &self.signing_key)?;
+        self.save(&streak)?;
+
+        Ok(UptimeAttestation {
+            counter: streak.counter,
+            timestamp,
+            streak_started_at: streak.streak_started_at,
+            signature,
+        })
+    }
+
+    pub fn streak(&self) -> UptimeStreak {
+        self.streak.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    fn save(&self, streak: &UptimeStreak) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(streak)
+            .map_err(|e| format!("Failed to serialize uptime attestations: {}", e))?;
+        let path = self.dir.join(ATTESTATION_FILE_NAME);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)
+            .map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+        std::fs::rename(&tmp, &path)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
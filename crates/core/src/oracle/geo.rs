@@ -0,0 +1,159 @@
+//! Coarse, opt-in geolocation for registration (`geolocation` in `AgentConfig`).
+//!
+//! The location comes from an IP lookup service and is cached in
+//! `<config_dir>/geolocation.json` together with the public IP it was looked up for;
+//! only a changed public IP triggers a new lookup.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+const CACHE_FILE_NAME: &str = "geolocation.json";
+const DEFAULT_LOOKUP_URL: &str = "https://ipapi.co/json/";
+const PUBLIC_IP_URL: &str = "https://api.ipify.org";
+
+/// Where the node is, to city-level precision at best.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeoLocation {
+    /// ISO 3166-1 alpha-2, e.g. "DE".
+    pub country_code: String,
+    pub region: Option<String>,
+    pub city: Option<String>,
+    /// Rounded to one decimal (~10 km).
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedLocation {
+    public_ip: String,
+    location: GeoLocation,
+}
+
+/// Looks up (and caches) the node's location.
+#[derive(Debug, Clone)]
+pub struct GeoLocator {
+    lookup_url: String,
+    cache_path: PathBuf,
+}
+
+impl GeoLocator {
+    /// None unless `geolocation` is enabled.
+    pub fn from_config(config: &crate::config::AgentConfig, config_dir: &Path) -> Option<Self> {
+        if !config.geolocation {
+            return None;
+        }
+        let lookup_url = if config.geolocation_url.is_empty() {
+            DEFAULT_LOOKUP_URL.to_string()
+        } else {
+            config.geolocation_url.clone()
+        };
+        Some(Self {
+            lookup_url,
+            cache_path: config_dir.join(CACHE_FILE_NAME),
+        })
+    }
+
+    /// Cached location while the public IP is unchanged, a fresh lookup otherwise.
+    /// None (with a warning) when either service can't be reached.
+    pub async fn locate(&self, client: &reqwest::Client) -> Option<GeoLocation> {
+        let public_ip = match fetch_public_ip(client).await {
+            Ok(ip) => ip,
+            Err(e) => {
+                log::warn!("Geolocation skipped: {}", e);
+                return self.cached().map(|c| c.location);
+            }
+        };
+        if let Some(cached) = self.cached().filter(|c| c.public_ip == public_ip) {
+            return Some(cached.location);
+        }
+
+        match lookup(client, &self.lookup_url).await {
+            Ok(location) => {
+                log::info!(
+                    "Geolocation: {}{}",
+                    location.country_code,
+                    location
+                        .region
+                        .as_deref()
+                        .map(|r| format!(" / {}", r))
+                        .unwrap_or_default()
+                );
+                let cached = CachedLocation {
+                    public_ip,
+                    location: location.clone(),
+                };
+                if let Ok(json) = serde_json::to_string_pretty(&cached) {
+                    if let Err(e) = std::fs::write(&self.cache_path, json) {
+                        log::warn!("Failed to cache geolocation: {}", e);
+                    }
+                }
+                Some(location)
+            }
+            Err(e) => {
+                log::warn!("Geolocation lookup failed: {}", e);
+                None
+            }
+        }
+    }
+
+    fn cached(&self) -> Option<CachedLocation> {
+        let json = std::fs::read_to_string(&self.cache_path).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+}
+
+async fn fetch_public_ip(client: &reqwest::Client) -> Result<String, String> {
+    let ip = client
+        .get(PUBLIC_IP_URL)
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await
+        .map_err(|e| format!("Public IP request failed: {}", e))?
+        .text()
+        .await
+        .map_err(|e| format!("Public IP read failed: {}", e))?;
+    let ip = ip.trim();
+    ip.parse::<std::net::IpAddr>()
+        .map_err(|_| format!("Unexpected public IP response: {}", ip.chars().take(64).collect::<String>()))?;
+    Ok(ip.to_string())
+}
+
+/// Query an ipapi.co-compatible service (`country_code`, `region`, `city`,
+/// `latitude`, `longitude`; ip-api.com's `countryCode`/`regionName`/`lat`/`lon` work too).
+async fn lookup(client: &reqwest::Client, url: &str) -> Result<GeoLocation, String> {
+    let data: serde_json::Value = client
+        .get(url)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Parse error: {}", e))?;
+
+    let text = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|k| data[*k].as_str())
+            .map(str::to_string)
+            .filter(|s| !s.is_empty())
+    };
+    let coord = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|k| data[*k].as_f64())
+            .map(|v| (v * 10.0).round() / 10.0)
+    };
+    let country_code = text(&["country_code", "countryCode"])
+        .ok_or_else(|| format!("No country in response: {}", data))?;
+
+    Ok(GeoLocation {
+        country_code,
+        region: text(&["region", "regionName"]),
+        city: text(&["city"]),
+        latitude: coord(&["latitude", "lat"]),
+        longitude: coord(&["longitude", "lon"]),
+    })
+}
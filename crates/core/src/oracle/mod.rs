@@ -1,2 +1,9 @@
+pub mod attestation;
+pub mod geo;
+pub mod network;
+pub mod penalty;
+pub mod peers;
+pub mod reachability;
 pub mod registry;
 pub mod reporter;
+pub mod usage;
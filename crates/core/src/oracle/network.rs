@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+
+/// Round trips used for the latency median.
+const LATENCY_SAMPLES: usize = 5;
+/// Payload size for the throughput tests.
+const TRANSFER_BYTES: usize = 4 * 1024 * 1024;
+
+/// Network quality between this node and the Oracle, reported on registration
+/// so cluster assignment can weigh links and not just tok/s.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkBenchmark {
+    /// Median HTTP round trip to the Oracle.
+    pub latency_ms: f64,
+    /// None if the Oracle doesn't expose the speed test endpoint.
+    pub download_mbps: Option<f64>,
+    pub upload_mbps: Option<f64>,
+}
+
+/// Measure latency, download and upload throughput against the Oracle.
+///
+/// Latency is required; the throughput tests are best-effort and left empty
+/// when the speed test endpoints are unavailable.
+#[tracing::instrument(name = "oracle.network_benchmark", skip(client))]
+pub async fn run_network_benchmark(
+    client: &reqwest::Client,
+    oracle_url: &str,
+) -> Result<NetworkBenchmark, String> {
+    let base = oracle_url.trim_end_matches('/');
+
+    let mut latencies = Vec::with_capacity(LATENCY_SAMPLES);
+    for _ in 0..LATENCY_SAMPLES {
+        let start = std::time::Instant::now();
+        let resp = client
+            .get(format!("{}/api/stats", base))
+            .timeout(std::time::Duration::from_secs(10))
+            .send()
+            .await;
+        if resp.is_ok() {
+            latencies.push(start.elapsed().as_secs_f64() * 1000.0);
+        }
+    }
+    if latencies.is_empty() {
+        return Err(format!("Cannot reach {}", base));
+    }
+    latencies.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let latency_ms = latencies[latencies.len() / 2];
+
+    let download_mbps = match measure_download(client, base).await {
+        Ok(mbps) => Some(mbps),
+        Err(e) => {
+            log::debug!("Download speed test skipped: {}", e);
+            None
+        }
+    };
+    let upload_mbps = match measure_upload(client, base).await {
+        Ok(mbps) => Some(mbps),
+        Err(e) => {
+            log::debug!("Upload speed test skipped: {}", e);
+            None
+        }
+    };
+
+    let result = NetworkBenchmark {
+        latency_ms,
+        download_mbps,
+        upload_mbps,
+    };
+    log::info!(
+        "Network: {:.0} ms latency, down {}, up {}",
+        result.latency_ms,
+        format_mbps(result.download_mbps),
+        format_mbps(result.upload_mbps)
+    );
+    Ok(result)
+}
+
+async fn measure_download(client: &reqwest::Client, base: &str) -> Result<f64, String> {
+    let start = std::time::Instant::now();
+    let resp = client
+        .get(format!("{}/api/v1/speedtest/download?bytes={}", base, TRANSFER_BYTES))
+        .timeout(std::time::Duration::from_secs(60))
+        .send()
+        .await
+        .map_err(|e| format!("request failed: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(format!("HTTP {}", resp.status()));
+    }
+    let body = resp
+        .bytes()
+        .await
+        .map_err(|e| format!("read failed: {}", e))?;
+    mbps(body.len(), start.elapsed())
+}
+
+async fn measure_upload(client: &reqwest::Client, base: &str) -> Result<f64, String> {
+    let payload = vec![0u8; TRANSFER_BYTES];
+    let start = std::time::Instant::now();
+    let resp = client
+        .post(format!("{}/api/v1/speedtest/upload", base))
+        .header("Content-Type", "application/octet-stream")
+        .body(payload)
+        .timeout(std::time::Duration::from_secs(60))
+        .send()
+        .await
+        .map_err(|e| format!("request failed: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(format!("HTTP {}", resp.status()));
+    }
+    mbps(TRANSFER_BYTES, start.elapsed())
+}
+
+fn mbps(bytes: usize, elapsed: std::time::Duration) -> Result<f64, String> {
+    let secs = elapsed.as_secs_f64();
+    if bytes == 0 || secs <= 0.0 {
+        return Err("no data transferred".to_string());
+    }
+    Ok(bytes as f64 * 8.0 / secs / 1_000_000.0)
+}
+
+fn format_mbps(value: Option<f64>) -> String {
+    value
+        .map(|v| format!("{:.1} Mbps", v))
+        .unwrap_or_else(|| "n/a".to_string())
+}
@@ -0,0 +1,146 @@
+use k256::ecdsa::SigningKey;
+use serde::{Deserialize, Serialize};
+
+use crate::chain::crypto::{address_from_key, personal_sign};
+use crate::inference::rpc_health;
+
+/// TCP connects used for the RTT median.
+const RTT_SAMPLES: usize = 5;
+/// Payload for the upload test; small enough not to stall a LAN peer.
+const TRANSFER_BYTES: usize = 8 * 1024 * 1024;
+
+/// Link quality from this coordinator to one rpc-server peer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerMeasurement {
+    /// "host:port" as passed to `--rpc`.
+    pub peer: String,
+    /// Median TCP connect time; None if the peer is unreachable.
+    pub rtt_ms: Option<f64>,
+    /// None if the peer is busy or the test failed.
+    pub upload_mbps: Option<f64>,
+    pub protocol_version: Option<String>,
+    /// False when the peer is unreachable or above the latency limit.
+    pub accepted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Measure RTT and throughput to one peer. `max_latency_ms` of 0 accepts any latency.
+pub async fn measure_peer(peer: &str, max_latency_ms: f64) -> PeerMeasurement {
+    let mut rtts = Vec::with_capacity(RTT_SAMPLES);
+    let mut protocol_version = None;
+    let mut last_error = None;
+    for _ in 0..RTT_SAMPLES {
+        match rpc_health::probe_addr(peer).await {
+            Ok(probe) => {
+                rtts.push(probe.connect_ms);
+                protocol_version = protocol_version.or(probe.protocol_version);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    if rtts.is_empty() {
+        return PeerMeasurement {
+            peer: peer.to_string(),
+            rtt_ms: None,
+            upload_mbps: None,
+            protocol_version: None,
+            accepted: false,
+            reason: last_error,
+        };
+    }
+    rtts.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let rtt_ms = rtts[rtts.len() / 2];
+
+    let upload_mbps = match rpc_health::measure_upload(peer, TRANSFER_BYTES).await {
+        Ok(mbps) => Some(mbps),
+        Err(e) => {
+            log::debug!("Upload test to {} skipped: {}", peer, e);
+            None
+        }
+    };
+
+    let too_slow = max_latency_ms > 0.0 && rtt_ms > max_latency_ms;
+    PeerMeasurement {
+        peer: peer.to_string(),
+        rtt_ms: Some(rtt_ms),
+        upload_mbps,
+        protocol_version,
+        accepted: !too_slow,
+        reason: too_slow.then(|| format!("{:.1} ms exceeds the {:.0} ms limit", rtt_ms, max_latency_ms)),
+    }
+}
+
+/// Measure every peer, one at a time so the throughput tests don't share the link.
+pub async fn measure_peers(peers: &[String], max_latency_ms: f64) -> Vec<PeerMeasurement> {
+    let mut results = Vec::with_capacity(peers.len());
+    for peer in peers {
+        let m = measure_peer(peer, max_latency_ms).await;
+        match (&m.rtt_ms, m.accepted) {
+            (Some(rtt), true) => log::info!(
+                "RPC peer {}: {:.1} ms, up {}",
+                peer,
+                rtt,
+                m.upload_mbps
+                    .map(|v| format!("{:.0} Mbps", v))
+                    .unwrap_or_else(|| "n/a".into())
+            ),
+            _ => log::warn!(
+                "RPC peer {} refused: {}",
+                peer,
+                m.reason.as_deref().unwrap_or("unreachable")
+            ),
+        }
+        results.push(m);
+    }
+    results
+}
+
+/// Report peer measurements to the Oracle for cluster planning.
+pub async fn report_peer_measurements(
+    client: &reqwest::Client,
+    oracle_url: &str,
+    signing_key: &SigningKey,
+    cluster_id: Option<&str>,
+    measurements: &[PeerMeasurement],
+) -> Result<(), String> {
+    let address = address_from_key(signing_key);
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| format!("Time error: {}", e))?
+        .as_secs();
+    let cluster_id = cluster_id.unwrap_or_default();
+    let signature = personal_sign(&format!("{}:{}:{}", address, cluster_id, timestamp), signing_key)?;
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct PeersPayload<'a> {
+        address: String,
+        cluster_id: &'a str,
+        peers: &'a [PeerMeasurement],
+        timestamp: u64,
+        signature: String,
+    }
+
+    let url = format!("{}/api/v1/cluster/peers", oracle_url.trim_end_matches('/'));
+    let resp = client
+        .post(&url)
+        .json(&PeersPayload {
+            address,
+            cluster_id,
+            peers: measurements,
+            timestamp,
+            signature,
+        })
+        .timeout(std::time::Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| format!("Peer report failed: {}", e))?;
+
+    if resp.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("Peer report HTTP {}", resp.status()))
+    }
+}
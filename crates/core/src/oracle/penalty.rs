@@ -0,0 +1,155 @@
+//! Penalty status from the Oracle (`GET /api/v1/nodes/{address}/penalties`).
+//!
+//! The Oracle lowers a node's reward share, or stops routing to it, after failed jobs
+//! or downtime. Nothing in the registration or metrics responses says so, so the
+//! monitor polls for it and turns the counts into warnings an operator can act on.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// How often the agents poll; penalties are decided over hours, not seconds.
+pub const POLL_INTERVAL_SECS: u64 = 300;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PenaltyStatus {
+    #[serde(default)]
+    pub penalized: bool,
+    /// Oracle's explanation, e.g. "downtime".
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Reward share while penalized (1.0 = none withheld).
+    #[serde(default)]
+    pub reward_multiplier: Option<f64>,
+    /// Unix epoch seconds the penalty lifts, if it is temporary.
+    #[serde(default)]
+    pub until: Option<u64>,
+    #[serde(default)]
+    pub failed_requests_1h: u64,
+    #[serde(default)]
+    pub timed_out_requests_1h: u64,
+    #[serde(default)]
+    pub downtime_secs_24h: u64,
+}
+
+impl PenaltyStatus {
+    /// What the operator should look at, most serious first; empty when all is well.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.penalized {
+            let mut message = format!(
+                "Penalized by the Oracle{}",
+                self.reason
+                    .as_deref()
+                    .map(|r| format!(" ({})", r))
+                    .unwrap_or_default()
+            );
+            if let Some(multiplier) = self.reward_multiplier {
+                message.push_str(&format!(", earning {:.0}% of rewards", multiplier * 100.0));
+            }
+            if let Some(until) = self.until {
+                let left = until.saturating_sub(crate::ledger::unix_millis() / 1000);
+                message.push_str(&format!(" for another {} min", left.div_ceil(60)));
+            }
+            warnings.push(message);
+        }
+        if self.failed_requests_1h > 0 {
+            warnings.push(format!(
+                "{} failed request{} in the last hour — check GPU stability and llama-server logs",
+                self.failed_requests_1h,
+                plural(self.failed_requests_1h)
+            ));
+        }
+        if self.timed_out_requests_1h > 0 {
+            warnings.push(format!(
+                "{} request{} timed out in the last hour — the model may be too large for this machine, or too many parallel slots are configured",
+                self.timed_out_requests_1h,
+                plural(self.timed_out_requests_1h)
+            ));
+        }
+        if self.downtime_secs_24h >= 300 {
+            warnings.push(format!(
+                "{} min of downtime in the last 24 h — check the network connection and power settings",
+                self.downtime_secs_24h / 60
+            ));
+        }
+        warnings
+    }
+}
+
+fn plural(n: u64) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+#[tracing::instrument(name = "oracle.penalties", level = "debug", skip(client))]
+pub async fn fetch_penalty_status(
+    client: &reqwest::Client,
+    oracle_url: &str,
+    address: &str,
+) -> Result<PenaltyStatus, String> {
+    let url = format!(
+        "{}/api/v1/nodes/{}/penalties",
+        oracle_url.trim_end_matches('/'),
+        address
+    );
+    let resp = client
+        .get(&url)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| format!("Penalty status request failed: {}", e))?;
+
+    if !resp.status().is_success() {
+        return Err(format!("Penalty status HTTP {}", resp.status()));
+    }
+    resp.json()
+        .await
+        .map_err(|e| format!("Penalty status parse error: {}", e))
+}
+
+/// Poll the penalty status every `interval_secs` and hand it to `on_change` on the
+/// first answer and whenever it differs from the last one. New warnings are logged as they appear.
+pub fn start_penalty_monitor<F>(
+    client: reqwest::Client,
+    oracle_url: String,
+    address: String,
+    interval_secs: u64,
+    on_change: F,
+) -> tokio::task::JoinHandle<()>
+where
+    F: Fn(PenaltyStatus) + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+        let mut last: Option<PenaltyStatus> = None;
+        loop {
+            interval.tick().await;
+            let status = match fetch_penalty_status(&client, &oracle_url, &address).await {
+                Ok(s) => s,
+                Err(e) => {
+                    log::debug!("Penalty status unavailable: {}", e);
+                    continue;
+                }
+            };
+            if last.as_ref() == Some(&status) {
+                continue;
+            }
+            let previous = last.as_ref().map(|l| l.warnings()).unwrap_or_default();
+            for warning in status.warnings() {
+                if !previous.contains(&warning) {
+                    log::warn!("{}", warning);
+                }
+            }
+            if last.as_ref().is_some_and(|l| l.penalized) && !status.penalized {
+                log::info!("The Oracle lifted the penalty on this node");
+            }
+            last = Some(status.clone());
+            on_change(status);
+        }
+    })
+}
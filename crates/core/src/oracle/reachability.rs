@@ -0,0 +1,148 @@
+use k256::ecdsa::SigningKey;
+use serde::{Deserialize, Serialize};
+
+use crate::chain::crypto::{address_from_key, personal_sign};
+
+/// Whether the endpoint we register under can be reached from outside.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reachability {
+    pub endpoint: String,
+    /// None when the check could not run (Oracle unreachable or without the endpoint).
+    pub reachable: Option<bool>,
+    /// Oracle-side connect time to the endpoint.
+    pub latency_ms: Option<f64>,
+    pub message: String,
+}
+
+impl Reachability {
+    /// Register relay-only: the Oracle must not route requests to the endpoint directly.
+    pub fn relay_only(&self) -> bool {
+        self.reachable == Some(false)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReachabilityResponse {
+    reachable: bool,
+    #[serde(default)]
+    latency_ms: Option<f64>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Check `http://<external_ip>:<port>` before registering it. A llama-server bound to
+/// loopback, or to IPv4 only behind an IPv6 address, is unreachable by construction;
+/// otherwise the Oracle connects back to it.
+pub async fn check_endpoint(
+    client: &reqwest::Client,
+    oracle_url: &str,
+    signing_key: &SigningKey,
+    bind_host: &str,
+    external_ip: &str,
+    port: u16,
+) -> Reachability {
+    let endpoint = crate::system::http_endpoint(external_ip, port);
+    let bind_ip = bind_host.parse::<std::net::IpAddr>().ok();
+    let loopback = bind_ip
+        .map(|ip| ip.is_loopback())
+        .unwrap_or(bind_host.eq_ignore_ascii_case("localhost"));
+    if loopback {
+        return Reachability {
+            endpoint,
+            reachable: Some(false),
+            latency_ms: None,
+            message: format!(
+                "llama-server listens on {} only; serving through the relay",
+                bind_host
+            ),
+        };
+    }
+    let ipv6_endpoint = external_ip.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_ipv6());
+    if ipv6_endpoint && bind_ip.is_some_and(|ip| ip.is_ipv4()) {
+        return Reachability {
+            endpoint,
+            reachable: Some(false),
+            latency_ms: None,
+            message: format!(
+                "llama-server listens on IPv4 ({}) but this host is IPv6-only; set bindHost to :: for direct traffic",
+                bind_host
+            ),
+        };
+    }
+
+    match ask_oracle(client, oracle_url, signing_key, &endpoint).await {
+        Ok(resp) => Reachability {
+            message: if resp.reachable {
+                format!("{} is reachable from the Oracle", endpoint)
+            } else {
+                format!(
+                    "{} is not reachable from the Oracle ({}); serving through the relay",
+                    endpoint,
+                    resp.error.as_deref().unwrap_or("connection failed")
+                )
+            },
+            endpoint,
+            reachable: Some(resp.reachable),
+            latency_ms: resp.latency_ms,
+        },
+        Err(e) => Reachability {
+            message: format!("Reachability check unavailable: {}", e),
+            endpoint,
+            reachable: None,
+            latency_ms: None,
+        },
+    }
+}
+
+/// Ask the Oracle to connect back to `endpoint`.
+async fn ask_oracle(
+    client: &reqwest::Client,
+    oracle_url: &str,
+    signing_key: &SigningKey,
+    endpoint: &str,
+) -> Result<ReachabilityResponse, String> {
+    let address = address_from_key(signing_key);
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| format!("Time error: {}", e))?
+        .as_secs();
+    let signature = personal_sign(
+        &format!("{}:{}:{}", address, endpoint, timestamp),
+        signing_key,
+    )?;
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct ReachabilityPayload<'a> {
+        address: String,
+        endpoint: &'a str,
+        timestamp: u64,
+        signature: String,
+    }
+
+    let url = format!(
+        "{}/api/v1/nodes/reachability",
+        oracle_url.trim_end_matches('/')
+    );
+    let resp = client
+        .post(&url)
+        .json(&ReachabilityPayload {
+            address,
+            endpoint,
+            timestamp,
+            signature,
+        })
+        .timeout(std::time::Duration::from_secs(15))
+        .send()
+        .await
+        .map_err(|e| format!("request failed: {}", e))?;
+
+    if !resp.status().is_success() {
+        return Err(format!("HTTP {}", resp.status()));
+    }
+    resp.json()
+        .await
+        .map_err(|e| format!("response parse error: {}", e))
+}
@@ -1,9 +1,14 @@
 use k256::ecdsa::SigningKey;
 use serde::{Deserialize, Serialize};
 use crate::chain::crypto::{address_from_key, personal_sign};
+use crate::config::{AgentConfig, ServeMode};
+use crate::error::{CoreError, CoreResult};
+use crate::inference::benchmark::BenchmarkResult;
+use crate::oracle::geo::GeoLocation;
+use crate::oracle::network::NetworkBenchmark;
 
 /// Cluster assignment returned by Oracle on registration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ClusterAssignment {
     pub mode: String,            // "standalone" | "rpc-server" | "coordinator"
@@ -18,32 +23,154 @@ pub struct RegisterResponse {
     pub success: bool,
     pub message: String,
     pub assignment: Option<ClusterAssignment>,
+    /// Oldest agent version the Oracle still routes to normally.
+    #[serde(default, rename = "minSupportedVersion")]
+    pub min_supported_version: Option<String>,
 }
 
-/// Register this node with the Oracle as a standalone inference node.
-/// Returns the cluster assignment if Oracle assigns us to a cluster.
-pub async fn register(
-    client: &reqwest::Client,
-    oracle_url: &str,
+/// Outcome of a successful registration.
+#[derive(Debug, Clone, Default)]
+pub struct Registration {
+    pub assignment: Option<ClusterAssignment>,
+    /// Set when this agent is older than the Oracle's `minSupportedVersion`.
+    pub update_required: Option<UpdateRequired>,
+}
+
+/// The Oracle wants a newer agent; until then the node gets little or no work.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateRequired {
+    pub current_version: String,
+    pub min_supported_version: String,
+}
+
+impl UpdateRequired {
+    /// Compare `min_supported_version` with this build.
+    pub fn check(min_supported_version: Option<&str>) -> Option<Self> {
+        let current = env!("CARGO_PKG_VERSION");
+        let minimum = min_supported_version?.trim().trim_start_matches('v');
+        (version_parts(current) < version_parts(minimum)).then(|| Self {
+            current_version: current.to_string(),
+            min_supported_version: minimum.to_string(),
+        })
+    }
+}
+
+/// "0.12.3-beta.1" -> [0, 12, 3]; pre-release tags are ignored.
+fn version_parts(version: &str) -> Vec<u64> {
+    version
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+/// What the node can serve and where it runs, for the Oracle's routing decisions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeInfo {
+    /// Context window per request (ctx_size split across the parallel slots).
+    pub context_length: u32,
+    /// GGUF quantization from the model file name, e.g. "Q4_K_M".
+    pub quantization: Option<String>,
+    pub vision: bool,
+    pub embeddings: bool,
+    pub rerank: bool,
+    pub agent_version: String,
+    /// llama-server build, e.g. "b4722"; see `inference::metrics::fetch_build_info`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub llama_version: Option<String>,
+    /// `std::env::consts::OS` / `ARCH`, e.g. "linux" / "x86_64".
+    pub os: String,
+    pub arch: String,
+    /// Operator-chosen region/zone label (`region` in `AgentConfig`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Only with `geolocation` enabled; filled in by `GeoLocator::locate`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<GeoLocation>,
+}
+
+impl NodeInfo {
+    pub fn from_config(config: &AgentConfig) -> Self {
+        let slots = crate::process::adjust_parallel_slots(config.ctx_size, config.parallel_slots);
+        let serve_mode = config.serve_mode();
+        Self {
+            context_length: config.ctx_size / slots.max(1),
+            quantization: quantization_from_file(&config.model_file),
+            vision: !config.mmproj_file.is_empty(),
+            embeddings: serve_mode == ServeMode::Embedding,
+            rerank: serve_mode == ServeMode::Rerank,
+            agent_version: env!("CARGO_PKG_VERSION").to_string(),
+            llama_version: None,
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            region: Some(config.region.trim().to_string()).filter(|r| !r.is_empty()),
+            location: None,
+        }
+    }
+}
+
+/// Quantization tag in a GGUF file name: "Qwen3-32B-Q4_K_M.gguf" -> "Q4_K_M".
+pub fn quantization_from_file(model_file: &str) -> Option<String> {
+    let stem = model_file.strip_suffix(".gguf").unwrap_or(model_file);
+    stem.rsplit(['-', '.'])
+        .map(|part| part.to_ascii_uppercase())
+        .find(|part| {
+            let is_q = |p: &str| {
+                p.len() > 1 && p[1..].starts_with(|c: char| c.is_ascii_digit())
+            };
+            (part.starts_with('Q') && is_q(part))
+                || (part.starts_with("IQ") && is_q(&part[1..]))
+                || matches!(part.as_str(), "F16" | "BF16" | "F32")
+        })
+}
+
+/// Signed body of `POST /api/nodes/register`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterPayload {
+    pub address: String,
+    pub endpoint: String,
+    pub capabilities: Vec<String>,
+    pub timestamp: u64,
+    pub signature: String,
+    pub benchmark_tok_per_sec: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub benchmark: Option<BenchmarkResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<NetworkBenchmark>,
+    pub lan_ip: String,
+    pub can_distribute: bool,
+    pub relay_only: bool,
+    pub node_info: NodeInfo,
+}
+
+/// Build and sign the registration payload without sending it (`register`, `start --dry-run`).
+#[allow(clippy::too_many_arguments)]
+pub fn registration_payload(
     signing_key: &SigningKey,
+    capability: &str,
     model: &str,
     http_port: u16,
-    ram_mb: u64,
-    vram_mb: u64,
-    device: &str,
     external_ip: &str,
-    benchmark_tok_per_sec: f64,
+    benchmark: Option<&BenchmarkResult>,
+    network: Option<&NetworkBenchmark>,
     can_distribute: bool,
     lan_ip: &str,
-) -> Result<Option<ClusterAssignment>, String> {
+    relay_only: bool,
+    node_info: &NodeInfo,
+) -> Result<RegisterPayload, String> {
     let address = address_from_key(signing_key);
     let timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .map_err(|e| format!("Time error: {}", e))?
         .as_secs();
 
-    let endpoint = format!("http://{}:{}", external_ip, http_port);
-    let capabilities = vec!["inference".to_string(), model.to_string()];
+    let endpoint = crate::system::http_endpoint(external_ip, http_port);
+    let capabilities = vec![capability.to_string(), model.to_string()];
 
     // Field order MUST match Oracle's NodesService.verifyRegistrationSignature
     #[derive(Serialize)]
@@ -65,30 +192,95 @@ pub async fn register(
         .map_err(|e| format!("JSON serialize error: {}", e))?;
     let signature = personal_sign(&message, signing_key)?;
 
-    // Build payload with distributed inference fields
-    #[derive(Serialize)]
-    #[serde(rename_all = "camelCase")]
-    struct RegisterPayload {
-        address: String,
-        endpoint: String,
-        capabilities: Vec<String>,
-        timestamp: u64,
-        signature: String,
-        benchmark_tok_per_sec: f64,
-        lan_ip: String,
-        can_distribute: bool,
-    }
-
-    let payload = RegisterPayload {
+    Ok(RegisterPayload {
         address,
         endpoint,
         capabilities,
         timestamp,
         signature,
-        benchmark_tok_per_sec,
+        benchmark_tok_per_sec: benchmark.map(|b| b.gen_tok_per_sec).unwrap_or(0.0),
+        benchmark: benchmark.cloned(),
+        network: network.cloned(),
         lan_ip: lan_ip.to_string(),
         can_distribute,
-    };
+        relay_only,
+        node_info: node_info.clone(),
+    })
+}
+
+/// Register this node with the Oracle as a standalone inference node.
+/// `capability` is "inference", "embedding" or "rerank" (see `ServeMode::capability`).
+/// `relay_only` tells the Oracle the endpoint can't be reached directly (see `reachability`).
+/// Returns the cluster assignment if Oracle assigns us to a cluster, and whether the
+/// Oracle considers this agent version outdated.
+#[tracing::instrument(name = "oracle.register", skip_all, fields(model = %model, port = http_port))]
+#[allow(clippy::too_many_arguments)]
+pub async fn register(
+    client: &reqwest::Client,
+    oracle_url: &str,
+    signing_key: &SigningKey,
+    capability: &str,
+    model: &str,
+    http_port: u16,
+    external_ip: &str,
+    benchmark: Option<&BenchmarkResult>,
+    network: Option<&NetworkBenchmark>,
+    can_distribute: bool,
+    lan_ip: &str,
+    relay_only: bool,
+    node_info: &NodeInfo,
+) -> CoreResult<Registration> {
+    let result = try_register(
+        client,
+        oracle_url,
+        signing_key,
+        capability,
+        model,
+        http_port,
+        external_ip,
+        benchmark,
+        network,
+        can_distribute,
+        lan_ip,
+        relay_only,
+        node_info,
+    )
+    .await;
+    if let Err(e) = &result {
+        crate::events::publish(crate::events::AgentEvent::RegistrationFailed { error: e.to_string() });
+    }
+    result
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn try_register(
+    client: &reqwest::Client,
+    oracle_url: &str,
+    signing_key: &SigningKey,
+    capability: &str,
+    model: &str,
+    http_port: u16,
+    external_ip: &str,
+    benchmark: Option<&BenchmarkResult>,
+    network: Option<&NetworkBenchmark>,
+    can_distribute: bool,
+    lan_ip: &str,
+    relay_only: bool,
+    node_info: &NodeInfo,
+) -> CoreResult<Registration> {
+    let payload = registration_payload(
+        signing_key,
+        capability,
+        model,
+        http_port,
+        external_ip,
+        benchmark,
+        network,
+        can_distribute,
+        lan_ip,
+        relay_only,
+        node_info,
+    )?;
 
     let url = format!("{}/api/nodes/register", oracle_url.trim_end_matches('/'));
 
@@ -98,23 +290,38 @@ pub async fn register(
         .timeout(std::time::Duration::from_secs(30))
         .send()
         .await
-        .map_err(|e| format!("Oracle register request failed: {}", e))?;
+        .map_err(|e| CoreError::Network(format!("Oracle register request failed: {}", e)))?;
 
     if resp.status().is_success() {
         let data: RegisterResponse = resp
             .json()
             .await
-            .map_err(|e| format!("Oracle response parse error: {}", e))?;
+            .map_err(|e| CoreError::Oracle(format!("Oracle response parse error: {}", e)))?;
 
         if data.success {
             log::info!("Registered with Oracle (assignment: {:?})", data.assignment);
-            Ok(data.assignment)
+            let update_required = UpdateRequired::check(data.min_supported_version.as_deref());
+            if let Some(update) = &update_required {
+                log::error!(
+                    "Agent update required: running {}, the Oracle requires {} or newer",
+                    update.current_version,
+                    update.min_supported_version
+                );
+            }
+            Ok(Registration {
+                assignment: data.assignment,
+                update_required,
+            })
         } else {
-            Err(format!("Oracle registration rejected: {}", data.message))
+            Err(CoreError::Oracle(format!("Oracle registration rejected: {}", data.message)))
         }
     } else {
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
-        Err(format!("Oracle register failed ({}): {}", status, &text[..text.len().min(300)]))
+        Err(CoreError::Oracle(format!(
+            "Oracle register failed ({}): {}",
+            status,
+            text.chars().take(300).collect::<String>()
+        )))
     }
 }
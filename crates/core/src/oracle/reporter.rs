@@ -1,20 +1,66 @@
+use std::sync::Arc;
 use k256::ecdsa::SigningKey;
 use serde::Serialize;
+use crate::availability::Availability;
 use crate::chain::crypto::{address_from_key, personal_sign};
+use crate::config::ServeMode;
+use crate::inference::benchmark::BenchmarkResult;
+use crate::oracle::network::NetworkBenchmark;
+use crate::oracle::attestation::{UptimeAttestation, UptimeAttester};
+use crate::oracle::geo::GeoLocator;
+use crate::oracle::registry::{ClusterAssignment, NodeInfo, UpdateRequired};
 use crate::inference::metrics::InferenceMetrics;
+use crate::relay::client::ModelRoutes;
+
+/// Called with the new assignment when a re-registration returns a different one.
+pub type AssignmentHandler = Box<dyn Fn(Option<ClusterAssignment>) + Send + Sync>;
+/// Called after each metrics report: `Some(down_for)` while the Oracle can't be
+/// reached (time since the last successful report), `None` once it answers again.
+pub type OracleStatusHandler = Box<dyn Fn(Option<std::time::Duration>) + Send + Sync>;
+/// Called when the Oracle starts (`Some`) or stops (`None`) asking for a newer agent.
+pub type UpdateRequiredHandler = Box<dyn Fn(Option<UpdateRequired>) + Send + Sync>;
 
 /// Registration parameters needed for periodic re-registration
 #[derive(Clone)]
 pub struct RegistrationParams {
+    pub serve_mode: ServeMode,
     pub model: String,
     pub http_port: u16,
-    pub ram_mb: u64,
-    pub vram_mb: u64,
-    pub device: String,
     pub external_ip: String,
-    pub benchmark_tok_per_sec: f64,
+    pub benchmark: Option<BenchmarkResult>,
+    pub network: Option<NetworkBenchmark>,
+    /// Re-benchmark this often while idle (0 = never).
+    pub rebenchmark_hours: u64,
     pub can_distribute: bool,
     pub lan_ip: String,
+    /// The endpoint failed its reachability check; register relay-only.
+    pub relay_only: bool,
+    /// Assignment the Oracle returned last; re-registrations are compared against it.
+    pub assignment: Option<ClusterAssignment>,
+    /// Update request from the last registration; re-registrations are compared against it.
+    pub update_required: Option<UpdateRequired>,
+    /// Routes shared with the relay. When the primary route changes (hot model swap),
+    /// the new model is benchmarked and re-registered right away.
+    pub routes: Option<ModelRoutes>,
+    /// Capabilities, version, platform and region sent with every registration.
+    pub node_info: NodeInfo,
+    /// Re-checked before every re-registration; the location follows a new public IP.
+    pub geo: Option<GeoLocator>,
+    /// Signs an uptime attestation for every report sent while serving.
+    pub attester: Option<Arc<UptimeAttester>>,
+}
+
+/// Everything the reporter task needs besides the HTTP client.
+pub struct ReporterConfig {
+    pub oracle_url: String,
+    pub signing_key: SigningKey,
+    pub llama_port: u16,
+    pub registration: RegistrationParams,
+    /// Pauses reporting while it requests deregistration.
+    pub availability: Option<Arc<Availability>>,
+    pub on_assignment: Option<AssignmentHandler>,
+    pub on_oracle_status: Option<OracleStatusHandler>,
+    pub on_update_required: Option<UpdateRequiredHandler>,
 }
 
 /// Start a background metrics reporter task (60s interval).
@@ -22,49 +68,119 @@ pub struct RegistrationParams {
 /// - Reports metrics every 60s as keepalive for Oracle node tracking.
 /// - Re-registers every 5 minutes at /api/nodes/register (standalone node,
 ///   NOT /api/v1/pipeline/register — agent-app is not a gRPC pipeline participant).
-pub fn start_reporter(
-    client: reqwest::Client,
-    oracle_url: String,
-    signing_key: SigningKey,
-    llama_port: u16,
-    registration: RegistrationParams,
-) -> tokio::task::JoinHandle<()> {
+/// - Goes silent while `availability` requests deregistration, so the Oracle's
+///   stale-node cleanup drops us until we resume.
+/// - If `rebenchmark_hours` is set, re-runs the benchmark (and network test) once
+///   it is due and no request is in flight, then re-registers with the new score.
+/// - Hands a changed cluster assignment to `on_assignment`, if given.
+/// - Tells `on_oracle_status`, if given, whether the Oracle is reachable.
+/// - Tells `on_update_required`, if given, when the Oracle's `minSupportedVersion`
+///   starts or stops covering this agent.
+/// - Follows `registration.routes`, if given, to the model and port now being served.
+pub fn start_reporter(client: reqwest::Client, config: ReporterConfig) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
+        let ReporterConfig {
+            oracle_url,
+            signing_key,
+            mut llama_port,
+            mut registration,
+            availability,
+            on_assignment,
+            on_oracle_status,
+            on_update_required,
+        } = config;
+        let mut routes_changed = registration.routes.as_ref().map(|r| r.subscribe());
         let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
         // Skip the first immediate tick
         interval.tick().await;
 
         let mut tick_count = 0u64;
+        let mut force_register = false;
+        let rebenchmark_every = std::time::Duration::from_secs(registration.rebenchmark_hours * 3600);
+        let mut last_benchmark = std::time::Instant::now();
+        let mut last_contact = std::time::Instant::now();
+        let mut oracle_down = false;
 
         loop {
-            interval.tick().await;
+            let swapped = tokio::select! {
+                _ = interval.tick() => false,
+                _ = routes_change(&mut routes_changed) => true,
+            };
+            if let Some(route) = registration.routes.as_ref().and_then(|r| r.primary()).filter(|_| swapped) {
+                log::info!("Now serving {} on port {}, re-registering", route.model, route.port);
+                registration.model = route.model;
+                registration.http_port = route.port;
+                llama_port = route.port;
+                // The old model's score says nothing about the new one
+                registration.benchmark = None;
+                if registration.serve_mode == ServeMode::Chat {
+                    match crate::inference::benchmark::run_benchmark(&client, llama_port).await {
+                        Ok(result) => registration.benchmark = Some(result),
+                        Err(e) => log::warn!("Benchmark after model swap failed: {}", e),
+                    }
+                }
+                last_benchmark = std::time::Instant::now();
+                force_register = true;
+            }
+
+            if availability.as_ref().map(|a| a.is_deregistered()).unwrap_or(false) {
+                log::debug!("Agent deregistered, skipping Oracle report");
+                force_register = true;
+                continue;
+            }
+
             tick_count += 1;
 
             // Every 5 minutes (every 5th tick), re-register as standalone node
             // to keep node entry alive in Oracle (no pipeline ready needed).
-            if tick_count % 5 == 0 {
+            // Also re-register right away when coming back from deregistration.
+            if tick_count.is_multiple_of(5) || force_register {
+                force_register = false;
+                if let Some(geo) = &registration.geo {
+                    if let Some(location) = geo.locate(&client).await {
+                        registration.node_info.location = Some(location);
+                    }
+                }
                 match crate::oracle::registry::register(
                     &client,
                     &oracle_url,
                     &signing_key,
+                    registration.serve_mode.capability(),
                     &registration.model,
                     registration.http_port,
-                    registration.ram_mb,
-                    registration.vram_mb,
-                    &registration.device,
                     &registration.external_ip,
-                    registration.benchmark_tok_per_sec,
+                    registration.benchmark.as_ref(),
+                    registration.network.as_ref(),
                     registration.can_distribute,
                     &registration.lan_ip,
+                    registration.relay_only,
+                    &registration.node_info,
                 )
                 .await
                 {
-                    Ok(assignment) => {
+                    Ok(result) => {
+                        let assignment = result.assignment;
+                        if result.update_required != registration.update_required {
+                            registration.update_required = result.update_required.clone();
+                            if let Some(handler) = &on_update_required {
+                                handler(result.update_required);
+                            }
+                        }
                         log::debug!(
                             "Periodic re-registration successful (mode: {})",
                             assignment.as_ref().map(|a| a.mode.as_str()).unwrap_or("standalone")
                         );
-                        // TODO: Detect mode transition and trigger switch
+                        if assignment != registration.assignment {
+                            log::info!(
+                                "Oracle changed the cluster assignment: {} -> {}",
+                                registration.assignment.as_ref().map(|a| a.mode.as_str()).unwrap_or("standalone"),
+                                assignment.as_ref().map(|a| a.mode.as_str()).unwrap_or("standalone")
+                            );
+                            registration.assignment = assignment.clone();
+                            if let Some(handler) = &on_assignment {
+                                handler(assignment);
+                            }
+                        }
                     }
                     Err(e) => {
                         log::warn!("Periodic re-registration failed: {}", e);
@@ -84,20 +200,97 @@ pub fn start_reporter(
                 }
             };
 
-            if let Err(e) =
-                report_metrics(&client, &oracle_url, &signing_key, &metrics).await
+            let paused = availability.as_ref().map(|a| !a.is_accepting()).unwrap_or(false);
+            let attestation = match registration.attester.as_ref().filter(|_| !paused) {
+                Some(attester) => attester
+                    .attest()
+                    .map_err(|e| log::warn!("Uptime attestation failed: {}", e))
+                    .ok(),
+                None => None,
+            };
+            match report_metrics(
+                &client,
+                &oracle_url,
+                &signing_key,
+                &metrics,
+                paused,
+                attestation.as_ref(),
+            )
+            .await
             {
-                log::warn!("Failed to report metrics: {}", e);
+                Ok(()) => {
+                    last_contact = std::time::Instant::now();
+                    if oracle_down {
+                        oracle_down = false;
+                        if let Some(handler) = &on_oracle_status {
+                            handler(None);
+                        }
+                    }
+                }
+                Err(e) => {
+                    log::warn!("Failed to report metrics: {}", e);
+                    oracle_down = true;
+                    if let Some(handler) = &on_oracle_status {
+                        handler(Some(last_contact.elapsed()));
+                    }
+                }
+            }
+
+            // Refresh a score taken while cold or under load, but never mid-request
+            let accepting = availability.as_ref().map(|a| a.is_accepting()).unwrap_or(true);
+            if registration.rebenchmark_hours > 0
+                && last_benchmark.elapsed() >= rebenchmark_every
+                && metrics.slots_processing == 0
+                && accepting
+            {
+                last_benchmark = std::time::Instant::now();
+                // The completion benchmark doesn't apply to embedding/rerank servers
+                if registration.serve_mode == ServeMode::Chat {
+                    match crate::inference::benchmark::run_benchmark(&client, llama_port).await {
+                        Ok(result) => {
+                            log::info!(
+                                "Re-benchmark: {:.2} tok/s (was {:.2})",
+                                result.gen_tok_per_sec,
+                                registration.benchmark.as_ref().map(|b| b.gen_tok_per_sec).unwrap_or(0.0)
+                            );
+                            registration.benchmark = Some(result);
+                            force_register = true;
+                        }
+                        Err(e) => log::warn!("Re-benchmark failed: {}", e),
+                    }
+                }
+                match crate::oracle::network::run_network_benchmark(&client, &oracle_url).await {
+                    Ok(network) => {
+                        registration.network = Some(network);
+                        force_register = true;
+                    }
+                    Err(e) => log::warn!("Network re-benchmark failed: {}", e),
+                }
             }
         }
     })
 }
 
-async fn report_metrics(
+/// Resolves when the shared routes are replaced; never without routes.
+async fn routes_change(rx: &mut Option<tokio::sync::watch::Receiver<()>>) {
+    if let Some(rx) = rx {
+        if rx.changed().await.is_ok() {
+            return;
+        }
+    }
+    std::future::pending().await
+}
+
+/// Send one signed metrics report (`paused` reports status "paused"), with an uptime
+/// attestation if given.
+#[tracing::instrument(name = "oracle.report_metrics", skip_all, fields(tokens = metrics.total_tokens))]
+pub async fn report_metrics(
     client: &reqwest::Client,
     oracle_url: &str,
     signing_key: &SigningKey,
     metrics: &InferenceMetrics,
+    paused: bool,
+    attestation: Option<&UptimeAttestation>,
 ) -> Result<(), String> {
     let address = address_from_key(signing_key);
     let timestamp = std::time::SystemTime::now()
@@ -107,7 +300,7 @@ async fn report_metrics(
 
     #[derive(Serialize)]
     #[serde(rename_all = "camelCase")]
-    struct ReportPayload {
+    struct ReportPayload<'a> {
         address: String,
         total_tokens: u64,
         total_requests: u64,
@@ -116,6 +309,14 @@ async fn report_metrics(
         uptime: u64,
         timestamp: u64,
         signature: String,
+        /// "serving" | "paused" (up and registered, but refusing new requests).
+        status: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        gpu_utilization: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        gpu_temperature: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        attestation: Option<&'a UptimeAttestation>,
     }
 
     // Sign the metrics data
@@ -125,6 +326,11 @@ async fn report_metrics(
     );
     let signature = personal_sign(&sign_msg, signing_key)?;
 
+    let gpus = tokio::task::spawn_blocking(crate::system::gpu_telemetry)
+        .await
+        .unwrap_or_default();
+    let (gpu_utilization, gpu_temperature) = crate::system::summarize_gpu_telemetry(&gpus);
+
     let payload = ReportPayload {
         address,
         total_tokens: metrics.total_tokens,
@@ -134,6 +340,10 @@ async fn report_metrics(
         uptime: metrics.uptime,
         timestamp,
         signature,
+        status: if paused { "paused" } else { "serving" },
+        gpu_utilization,
+        gpu_temperature,
+        attestation,
     };
 
     let url = format!(
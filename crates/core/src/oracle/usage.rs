@@ -0,0 +1,200 @@
+//! Per-request usage reports (`usage_reporting` in `AgentConfig`).
+//!
+//! The 60s metrics report only carries lifetime counters, so rewards can't be tied to
+//! individual requests. With usage reporting on, every completed relay request is
+//! signed on its own and queued; the queue goes to the Oracle every `FLUSH_INTERVAL`
+//! or once `MAX_BATCH` records are waiting. Failed batches stay queued for the next
+//! attempt, up to `MAX_PENDING` records.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use k256::ecdsa::SigningKey;
+use serde::{Deserialize, Serialize};
+
+use crate::chain::crypto::{address_from_key, personal_sign};
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+const MAX_BATCH: usize = 50;
+/// Oldest records are dropped past this while the Oracle is unreachable.
+const MAX_PENDING: usize = 1000;
+
+/// One completed relay request, signed by the node.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageRecord {
+    pub request_id: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub duration_ms: u64,
+    /// Unix epoch milliseconds.
+    pub finished_at: u64,
+    /// personal_sign over "{address}:{requestId}:{promptTokens}:{completionTokens}:{durationMs}:{finishedAt}".
+    pub signature: String,
+}
+
+/// Queue of signed usage records. Shared by the relay (which records) and the task
+/// started by `start_usage_reporter`.
+pub struct UsageReporter {
+    signing_key: SigningKey,
+    address: String,
+    pending: Mutex<Vec<UsageRecord>>,
+    full: tokio::sync::Notify,
+}
+
+impl UsageReporter {
+    pub fn new(signing_key: SigningKey) -> Self {
+        Self {
+            address: address_from_key(&signing_key),
+            signing_key,
+            pending: Mutex::new(Vec::new()),
+            full: tokio::sync::Notify::new(),
+        }
+    }
+
+    /// Sign and queue one completed request.
+    pub fn record(
+        &self,
+        request_id: &str,
+        prompt_tokens: u64,
+        completion_tokens: u64,
+        duration_ms: u64,
+        finished_at: u64,
+    ) {
+        let message = format!(
+            "{}:{}:{}:{}:{}:{}",
+            self.address, request_id, prompt_tokens, completion_tokens, duration_ms, finished_at
+        );
+        let signature = match personal_sign(&message, &self.signing_key) {
+            Ok(s) => s,
+            Err(e) => {
+                log::warn!("Failed to sign usage for request {}: {}", request_id, e);
+                return;
+            }
+        };
+        let Ok(mut pending) = self.pending.lock() else {
+            return;
+        };
+        pending.push(UsageRecord {
+            request_id: request_id.to_string(),
+            prompt_tokens,
+            completion_tokens,
+            duration_ms,
+            finished_at,
+            signature,
+        });
+        if pending.len() > MAX_PENDING {
+            let excess = pending.len() - MAX_PENDING;
+            pending.drain(..excess);
+        }
+        if pending.len() >= MAX_BATCH {
+            self.full.notify_one();
+        }
+    }
+
+    fn take(&self) -> Vec<UsageRecord> {
+        self.pending
+            .lock()
+            .map(|mut p| std::mem::take(&mut *p))
+            .unwrap_or_default()
+    }
+
+    /// Put records back after a failed send, ahead of anything queued since.
+    fn requeue(&self, mut records: Vec<UsageRecord>) {
+        if let Ok(mut pending) = self.pending.lock() {
+            records.append(&mut pending);
+            let excess = records.len().saturating_sub(MAX_PENDING);
+            records.drain(..excess);
+            *pending = records;
+        }
+    }
+}
+
+/// Send queued usage records in the background.
+pub fn start_usage_reporter(
+    reporter: Arc<UsageReporter>,
+    client: reqwest::Client,
+    oracle_url: String,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+        // Skip the first immediate tick
+        interval.tick().await;
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = reporter.full.notified() => {}
+            }
+
+            let records = reporter.take();
+            if records.is_empty() {
+                continue;
+            }
+            for (i, batch) in records.chunks(MAX_BATCH).enumerate() {
+                if let Err(e) = send_batch(&client, &oracle_url, &reporter, batch).await {
+                    let unsent = records[i * MAX_BATCH..].to_vec();
+                    log::warn!("Usage report failed ({} records kept): {}", unsent.len(), e);
+                    reporter.requeue(unsent);
+                    break;
+                }
+                log::debug!("Reported usage for {} request(s)", batch.len());
+            }
+        }
+    })
+}
+
+#[tracing::instrument(name = "oracle.report_usage", skip_all, fields(records = records.len()))]
+async fn send_batch(
+    client: &reqwest::Client,
+    oracle_url: &str,
+    reporter: &UsageReporter,
+    records: &[UsageRecord],
+) -> Result<(), String> {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| format!("Time error: {}", e))?
+        .as_secs();
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct UsagePayload<'a> {
+        address: &'a str,
+        timestamp: u64,
+        records: &'a [UsageRecord],
+    }
+
+    let url = format!("{}/api/v1/usage/report", oracle_url.trim_end_matches('/'));
+    let resp = client
+        .post(&url)
+        .json(&UsagePayload {
+            address: &reporter.address,
+            timestamp,
+            records,
+        })
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| format!("Usage report request failed: {}", e))?;
+
+    if resp.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("Usage report HTTP {}", resp.status()))
+    }
+}
+
+/// Start usage reporting if `usage_reporting` is on. The returned reporter is handed
+/// to the relay via `RelayContext::usage`.
+pub fn start_from_config(
+    config: &crate::config::AgentConfig,
+    signing_key: &SigningKey,
+    client: reqwest::Client,
+) -> Option<(Arc<UsageReporter>, tokio::task::JoinHandle<()>)> {
+    if !config.usage_reporting {
+        return None;
+    }
+    let reporter = Arc::new(UsageReporter::new(signing_key.clone()));
+    let handle = start_usage_reporter(Arc::clone(&reporter), client, config.oracle_url.clone());
+    Some((reporter, handle))
+}
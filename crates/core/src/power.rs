@@ -0,0 +1,233 @@
+//! Power source detection and the on-battery policy.
+
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::availability::{Availability, PauseSource};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PowerSource {
+    Ac,
+    Battery,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerStatus {
+    pub source: PowerSource,
+    pub battery_percent: Option<u8>,
+}
+
+impl PowerStatus {
+    pub fn on_battery(&self) -> bool {
+        self.source == PowerSource::Battery
+    }
+}
+
+/// What to do while running on battery (mirrors `pause_on_battery`/`battery_mode` in `AgentConfig`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatteryPolicy {
+    Off,
+    /// Stay registered but reject relay requests.
+    Pause,
+    /// Disconnect from the Oracle until AC power returns.
+    Deregister,
+    /// Keep serving, but restart llama-server CPU-only.
+    Cpu,
+}
+
+impl BatteryPolicy {
+    pub fn from_config(pause_on_battery: bool, mode: &str) -> Self {
+        if !pause_on_battery {
+            return BatteryPolicy::Off;
+        }
+        match mode {
+            "deregister" => BatteryPolicy::Deregister,
+            "cpu" => BatteryPolicy::Cpu,
+            _ => BatteryPolicy::Pause,
+        }
+    }
+}
+
+/// Current power source. Desktops without a battery report `Ac`.
+#[cfg(target_os = "linux")]
+pub fn power_status() -> PowerStatus {
+    let entries = match std::fs::read_dir("/sys/class/power_supply") {
+        Ok(e) => e,
+        Err(_) => {
+            return PowerStatus {
+                source: PowerSource::Unknown,
+                battery_percent: None,
+            }
+        }
+    };
+
+    let read = |dir: &std::path::Path, name: &str| {
+        std::fs::read_to_string(dir.join(name))
+            .map(|s| s.trim().to_string())
+            .unwrap_or_default()
+    };
+
+    let mut mains_online = false;
+    let mut has_battery = false;
+    let mut discharging = false;
+    let mut battery_percent = None;
+    for entry in entries.flatten() {
+        let dir = entry.path();
+        match read(&dir, "type").as_str() {
+            "Mains" | "USB" => mains_online |= read(&dir, "online") == "1",
+            "Battery" => {
+                // Peripheral batteries (mice, headsets) report scope "Device"
+                if read(&dir, "scope") == "Device" {
+                    continue;
+                }
+                has_battery = true;
+                discharging |= read(&dir, "status") == "Discharging";
+                battery_percent = battery_percent.or_else(|| read(&dir, "capacity").parse().ok());
+            }
+            _ => {}
+        }
+    }
+
+    let source = if has_battery && discharging && !mains_online {
+        PowerSource::Battery
+    } else {
+        PowerSource::Ac
+    };
+    PowerStatus {
+        source,
+        battery_percent,
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn power_status() -> PowerStatus {
+    let output = match std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .stderr(std::process::Stdio::null())
+        .output()
+    {
+        Ok(o) if o.status.success() => o,
+        _ => {
+            return PowerStatus {
+                source: PowerSource::Unknown,
+                battery_percent: None,
+            }
+        }
+    };
+    // "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=...)\t87%; discharging; ..."
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let source = if stdout.contains("'Battery Power'") {
+        PowerSource::Battery
+    } else if stdout.contains("'AC Power'") {
+        PowerSource::Ac
+    } else {
+        PowerSource::Unknown
+    };
+    let battery_percent = stdout
+        .split('%')
+        .next()
+        .and_then(|s| s.rsplit(|c: char| !c.is_ascii_digit()).next())
+        .and_then(|s| s.parse().ok());
+    PowerStatus {
+        source,
+        battery_percent,
+    }
+}
+
+#[cfg(windows)]
+pub fn power_status() -> PowerStatus {
+    use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    // SAFETY: SYSTEM_POWER_STATUS is plain data; zeroed is a valid initial value.
+    let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+    // SAFETY: `status` is a valid, writable SYSTEM_POWER_STATUS.
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return PowerStatus {
+            source: PowerSource::Unknown,
+            battery_percent: None,
+        };
+    }
+    let source = match status.ACLineStatus {
+        0 => PowerSource::Battery,
+        1 => PowerSource::Ac,
+        _ => PowerSource::Unknown,
+    };
+    // 128 = no system battery, 255 = unknown
+    let battery_percent = if status.BatteryFlag & 128 != 0 || status.BatteryLifePercent == 255 {
+        None
+    } else {
+        Some(status.BatteryLifePercent)
+    };
+    PowerStatus {
+        source,
+        battery_percent,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn power_status() -> PowerStatus {
+    PowerStatus {
+        source: PowerSource::Unknown,
+        battery_percent: None,
+    }
+}
+
+/// Poll the power source every `interval_secs` and pause/resume `availability`
+/// for the `Pause` and `Deregister` policies. `Cpu` needs a llama-server
+/// restart, which the caller handles via `on_change`.
+pub fn start_battery_watcher<F>(
+    policy: BatteryPolicy,
+    availability: Arc<Availability>,
+    interval_secs: u64,
+    on_change: F,
+) -> tokio::task::JoinHandle<()>
+where
+    F: Fn(&PowerStatus) + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+        let mut last: Option<PowerSource> = None;
+        loop {
+            interval.tick().await;
+            let status = match tokio::task::spawn_blocking(power_status).await {
+                Ok(s) => s,
+                Err(_) => continue,
+            };
+            if status.source == PowerSource::Unknown || last == Some(status.source) {
+                continue;
+            }
+            let first = last.is_none();
+            last = Some(status.source);
+
+            match policy {
+                BatteryPolicy::Pause | BatteryPolicy::Deregister => {
+                    if status.on_battery() {
+                        let reason = match status.battery_percent {
+                            Some(p) => format!("Running on battery ({}%)", p),
+                            None => "Running on battery".to_string(),
+                        };
+                        if availability.pause(
+                            PauseSource::Battery,
+                            reason,
+                            policy == BatteryPolicy::Deregister,
+                        ) {
+                            log::info!("Switched to battery power, pausing agent");
+                        }
+                    } else if availability.resume(PauseSource::Battery) {
+                        log::info!("AC power restored, resuming agent");
+                    }
+                }
+                BatteryPolicy::Cpu | BatteryPolicy::Off => {}
+            }
+
+            // The caller already picked the right mode at startup
+            if !first {
+                on_change(&status);
+            }
+        }
+    })
+}
@@ -0,0 +1,414 @@
+//! Checks run before starting the agent (GUI start button, `plumise-agent doctor`).
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::AgentConfig;
+use crate::system;
+
+/// Bound on each remote check; the shared HTTP client has no overall timeout.
+const CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// `fut`, or a timeout error once `CHECK_TIMEOUT` passes.
+async fn bounded<T, E: From<String>>(fut: impl std::future::Future<Output = Result<T, E>>) -> Result<T, E> {
+    tokio::time::timeout(CHECK_TIMEOUT, fut)
+        .await
+        .unwrap_or_else(|_| Err(E::from("timed out".to_string())))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightResult {
+    pub passed: bool,
+    pub checks: Vec<PreflightCheck>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightCheck {
+    pub name: String,
+    pub passed: bool,
+    pub message: String,
+    /// What to do about a failed (or degraded) check.
+    #[serde(default)]
+    pub hint: Option<String>,
+}
+
+impl PreflightCheck {
+    fn pass(name: &str, message: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            passed: true,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    fn fail(name: &str, message: impl Into<String>, hint: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            passed: false,
+            message: message.into(),
+            hint: Some(hint.to_string()),
+        }
+    }
+
+    fn with_hint(mut self, hint: &str) -> Self {
+        self.hint = Some(hint.to_string());
+        self
+    }
+}
+
+/// Where the caller keeps things, and what it may do while checking.
+pub struct PreflightEnv<'a> {
+    pub models_dir: &'a Path,
+    /// llama-server to check and GPU-probe, or why it could not be found.
+    pub llama_server: Result<PathBuf, String>,
+    /// A missing llama-server is downloaded on start (CLI) rather than an error (GUI bundle).
+    pub llama_auto_install: bool,
+    /// Kill a leftover process holding `http_port` (a previous llama-server).
+    pub free_port: bool,
+    /// The agent is running and owns `http_port`; skip the port check.
+    pub agent_running: bool,
+    /// Ask the Oracle whether the advertised endpoint is reachable.
+    pub check_reachability: bool,
+}
+
+pub async fn run_checks(config: &AgentConfig, env: PreflightEnv<'_>) -> PreflightResult {
+    let mut checks = Vec::new();
+
+    // 1. Private key
+    let signing_key = crate::chain::crypto::parse_private_key(&config.private_key).ok();
+    let pk_valid = config.private_key.starts_with("0x")
+        && config.private_key.len() == 66
+        && signing_key.is_some();
+    checks.push(if pk_valid {
+        PreflightCheck::pass("Wallet", "Private key configured")
+    } else {
+        PreflightCheck::fail(
+            "Wallet",
+            "Invalid or missing private key",
+            "Set a 0x-prefixed 64-hex-character private key (Settings, or `plumise-agent config set privateKey <key>`)",
+        )
+    });
+
+    // The shared client checks the Oracle's pins in the TLS handshake
+    let pins = crate::tls_pin::install(config);
+    let client = crate::http::client();
+
+    // 2. Oracle
+    let oracle_ok = pins.is_ok()
+        && client
+            .get(format!(
+                "{}/api/stats",
+                config.oracle_url.trim_end_matches('/')
+            ))
+            .timeout(CHECK_TIMEOUT)
+            .send()
+            .await
+            .map(|r| r.status().is_success())
+            .unwrap_or(false);
+    checks.push(match pins {
+        Err(e) => PreflightCheck::fail(
+            "Oracle",
+            e,
+            "Fix oracleSpkiPins, or remove them to disable pinning",
+        ),
+        Ok(()) if oracle_ok => {
+            PreflightCheck::pass("Oracle", format!("Connected to {}", config.oracle_url))
+        }
+        Ok(()) => PreflightCheck::fail(
+            "Oracle",
+            format!("Cannot reach {}", config.oracle_url),
+            "Check your internet connection and firewall, and that oracleUrl is correct",
+        ),
+    });
+
+    // 3. Chain RPC
+    checks.push(match config.chain.resolve() {
+        Err(e) => PreflightCheck::fail(
+            "Chain RPC",
+            e,
+            "Fix the chain profile, or remove it to use mainnet",
+        ),
+        Ok(profile) => match bounded(crate::chain::tx::fetch_chain_id(&client, &config.chain_rpc)).await {
+            Ok(id) => match crate::chain::profile::check_chain_id(&config.chain_rpc, id, &profile) {
+                None => PreflightCheck::pass(
+                    "Chain RPC",
+                    format!("Connected to {} ({}, chain {})", config.chain_rpc, profile.network, id),
+                ),
+                Some(mismatch) => PreflightCheck::fail(
+                    "Chain RPC",
+                    mismatch,
+                    "Point chainRpc at a node of the configured network, or fix chain.chainId",
+                ),
+            },
+            Err(_) => PreflightCheck::fail(
+                "Chain RPC",
+                format!("Cannot reach {}", config.chain_rpc),
+                "Check your internet connection, or point chainRpc at another Plumise RPC endpoint",
+            ),
+        },
+    });
+
+    // 4. Wallet balance
+    if pk_valid {
+        match bounded(system::check_wallet_balance(&client, &config.chain_rpc, &config.private_key)).await {
+            Ok((balance, addr)) => {
+                let is_zero = balance == "0.0000"
+                    || balance
+                        .trim_start_matches('0')
+                        .trim_start_matches('.')
+                        .is_empty();
+                checks.push(PreflightCheck::pass(
+                    "Wallet",
+                    if is_zero {
+                        format!("{}: 0 PLM (OK — gas is sponsored)", addr)
+                    } else {
+                        format!("{}: {} PLM", addr, balance)
+                    },
+                ));
+            }
+            Err(e) => {
+                checks.push(PreflightCheck::pass(
+                    "Wallet",
+                    format!("Balance check skipped: {}", e),
+                ));
+            }
+        }
+    }
+
+    // 5. HTTP port — optionally kill a leftover llama-server holding it
+    if env.agent_running {
+        checks.push(PreflightCheck::pass(
+            "HTTP Port",
+            format!("Port {} in use by the running agent", config.http_port),
+        ));
+    } else {
+        let port_hint = "Stop the program using the port or change httpPort";
+        let bind =
+            || std::net::TcpListener::bind(format!("127.0.0.1:{}", config.http_port)).is_ok();
+        if bind() {
+            checks.push(PreflightCheck::pass(
+                "HTTP Port",
+                format!("Port {} available", config.http_port),
+            ));
+        } else if !env.free_port {
+            checks.push(PreflightCheck::fail(
+                "HTTP Port",
+                format!("Port {} in use", config.http_port),
+                port_hint,
+            ));
+        } else if let Some(killed) = system::kill_process_on_port(config.http_port) {
+            // Brief wait for the OS to release the port
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            checks.push(if bind() {
+                PreflightCheck::pass(
+                    "HTTP Port",
+                    format!(
+                        "Port {} freed (killed leftover: {})",
+                        config.http_port, killed
+                    ),
+                )
+            } else {
+                PreflightCheck::fail(
+                    "HTTP Port",
+                    format!(
+                        "Port {} still in use after killing {}",
+                        config.http_port, killed
+                    ),
+                    port_hint,
+                )
+            });
+        } else {
+            let next = config.http_port.saturating_add(1);
+            checks.push(
+                match system::pick_free_port("127.0.0.1", next, config.http_port_scan.saturating_sub(1)) {
+                    Ok(port) if config.http_port_scan > 0 => PreflightCheck::pass(
+                        "HTTP Port",
+                        format!("Port {} in use by another program, will use {}", config.http_port, port),
+                    ),
+                    _ => PreflightCheck::fail(
+                        "HTTP Port",
+                        format!("Port {} in use by unknown process", config.http_port),
+                        port_hint,
+                    ),
+                },
+            );
+        }
+    }
+
+    // 6. llama-server binary
+    match &env.llama_server {
+        Ok(path) => checks.push(PreflightCheck::pass(
+            "llama-server",
+            path.display().to_string(),
+        )),
+        Err(_) if env.llama_auto_install => checks.push(PreflightCheck::pass(
+            "llama-server",
+            "Not installed (will download on start)",
+        )),
+        Err(e) => checks.push(PreflightCheck::fail(
+            "llama-server",
+            e.clone(),
+            "Reinstall the app; the bundled llama-server is missing",
+        )),
+    }
+
+    // Detected afresh so a driver installed since the last run shows up
+    let hardware = crate::hardware::refresh();
+
+    // 7. GPU detection, then an actual offload test when the binary is there
+    if config.gpu_layers > 0 {
+        match &hardware.gpu {
+            Some(gpu) => {
+                let detail = if gpu.vram_mb > 0 {
+                    format!("{} ({} MB)", gpu.name, gpu.vram_mb)
+                } else {
+                    gpu.name.clone()
+                };
+                checks.push(PreflightCheck::pass("GPU", detail));
+                if let Ok(path) = &env.llama_server {
+                    let probe = crate::gpu_probe::probe_gpu(path, env.models_dir).await;
+                    checks.push(if probe.ok {
+                        PreflightCheck::pass("GPU Offload", probe.message)
+                    } else {
+                        PreflightCheck::fail(
+                            "GPU Offload",
+                            probe.message,
+                            "Reinstall or update the GPU driver (CUDA/Vulkan runtime), or set GPU Layers to 0 for CPU mode",
+                        )
+                    });
+                }
+            }
+            None => checks.push(PreflightCheck::fail(
+                "GPU",
+                "No GPU detected",
+                "Install GPU drivers or set GPU Layers to 0 for CPU mode",
+            )),
+        }
+    }
+
+    // CPU instruction sets the llama.cpp build needs
+    let cpu = &hardware.cpu;
+    let cpu_ok = cpu.arch != "x86_64" || cpu.avx;
+    let cpu_check = PreflightCheck {
+        name: "CPU".to_string(),
+        passed: cpu_ok,
+        message: match cpu.warning() {
+            Some(warning) => format!("{} ({})", cpu.summary(), warning),
+            None => cpu.summary(),
+        },
+        hint: None,
+    };
+    checks.push(if cpu_ok {
+        cpu_check
+    } else {
+        cpu_check.with_hint(
+            "llama.cpp needs a CPU with AVX; this machine can only serve through a GPU build",
+        )
+    });
+
+    // 8. Model
+    let model_path = env.models_dir.join(&config.model_file);
+    let model_size = std::fs::metadata(&model_path)
+        .map(|m| m.len())
+        .ok()
+        .filter(|len| *len > 100 * 1024 * 1024);
+    checks.push(PreflightCheck::pass(
+        "Model",
+        match model_size {
+            Some(len) => format!("{} ({:.1} GB)", config.model_file, len as f64 / 1e9),
+            None => format!("{} (will download on start)", config.model_file),
+        },
+    ));
+
+    // 9. Disk space (only blocking when the model still has to be downloaded)
+    if let Some(free) = system::available_disk_space(env.models_dir) {
+        let free_gb = free as f64 / 1e9;
+        if model_size.is_some() {
+            checks.push(PreflightCheck::pass(
+                "Disk Space",
+                format!("{:.1} GB free", free_gb),
+            ));
+        } else {
+            match bounded(crate::model::download::remote_model_size(
+                &client,
+                &config.model,
+                &config.model_file,
+                Some(config.hf_token.as_str()),
+            ))
+            .await
+            {
+                Ok(total) => {
+                    let remaining = crate::model::download::remaining_download_bytes(
+                        env.models_dir,
+                        &config.model_file,
+                        total,
+                    );
+                    let needed = remaining + system::DISK_HEADROOM_BYTES;
+                    let message = format!(
+                        "{:.1} GB free, model download needs {:.1} GB",
+                        free_gb,
+                        needed as f64 / 1e9
+                    );
+                    checks.push(if free >= needed {
+                        PreflightCheck::pass("Disk Space", message)
+                    } else {
+                        PreflightCheck::fail(
+                            "Disk Space",
+                            message,
+                            "Free up disk space or choose a smaller model",
+                        )
+                    });
+                }
+                Err(e) => checks.push(PreflightCheck::pass(
+                    "Disk Space",
+                    format!("{:.1} GB free (model size unknown: {})", free_gb, e),
+                )),
+            }
+        }
+    }
+
+    // 10. Model fit for this hardware (informational; llama.cpp can still mmap oversized models)
+    if let Ok(rec) = tokio::task::spawn_blocking(crate::model::recommend::recommend_for_host).await
+    {
+        checks.push(if rec.model_file == config.model_file {
+            PreflightCheck::pass("Model Fit", rec.reason.clone())
+        } else {
+            PreflightCheck::pass(
+                "Model Fit",
+                format!("Recommended: {} — {}", rec.model_file, rec.reason),
+            )
+        });
+    }
+
+    // 11. Reachability of the endpoint we would register (informational; the relay covers it)
+    if env.check_reachability && oracle_ok {
+        if let Some(key) = &signing_key {
+            let local_ip = system::advertised_ip(&config.oracle_url, &config.llama_bind_host())
+                .await
+                .unwrap_or_else(|| "127.0.0.1".to_string());
+            let reach = crate::oracle::reachability::check_endpoint(
+                &client,
+                &config.oracle_url,
+                key,
+                &config.llama_bind_host(),
+                &local_ip,
+                config.http_port,
+            )
+            .await;
+            let check = PreflightCheck::pass("Reachability", reach.message.clone());
+            checks.push(match reach.reachable {
+                Some(false) => check.with_hint(
+                    "Optional: forward the port on your router and set bindHost to 0.0.0.0 for direct traffic",
+                ),
+                _ => check,
+            });
+        }
+    }
+
+    let passed = checks.iter().all(|c| c.passed);
+    PreflightResult { passed, checks }
+}
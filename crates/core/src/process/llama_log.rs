@@ -0,0 +1,86 @@
+//! What happens to llama-server's output (`llama_log_*` in `AgentConfig`).
+//!
+//! Every line goes to the optional log file; only lines at or above `llama_log_level`
+//! are passed on to the agent log and the GUI's event stream.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::config::AgentConfig;
+
+/// llama-server flags for `llama_log_verbosity` and `llama_log_disable`.
+pub fn llama_log_args(verbosity: Option<u32>, disable: bool) -> Vec<String> {
+    if disable {
+        return vec!["--log-disable".into()];
+    }
+    match verbosity {
+        Some(v) => vec!["--log-verbosity".into(), v.to_string()],
+        None => Vec::new(),
+    }
+}
+
+fn level_rank(level: &str) -> u8 {
+    match level {
+        "DEBUG" => 0,
+        "WARNING" => 2,
+        "ERROR" => 3,
+        _ => 1,
+    }
+}
+
+/// Filter and file sink for llama-server output lines. Shared by the stdout and
+/// stderr readers.
+pub struct LlamaLog {
+    min_rank: u8,
+    file: Option<Mutex<std::fs::File>>,
+}
+
+impl LlamaLog {
+    /// A relative `llama_log_file` is taken relative to `config_dir`.
+    pub fn from_config(config: &AgentConfig, config_dir: &Path) -> Self {
+        let file = Self::file_path(config, config_dir).and_then(|path| {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .map_err(|e| log::warn!("Cannot open llama log file {}: {}", path.display(), e))
+                .ok()
+        });
+        Self {
+            min_rank: level_rank(&config.llama_log_level.to_uppercase()),
+            file: file.map(Mutex::new),
+        }
+    }
+
+    fn file_path(config: &AgentConfig, config_dir: &Path) -> Option<PathBuf> {
+        let file = config.llama_log_file.trim();
+        if file.is_empty() {
+            return None;
+        }
+        let path = PathBuf::from(file);
+        Some(if path.is_absolute() {
+            path
+        } else {
+            config_dir.join(path)
+        })
+    }
+
+    /// Append `line` to the log file (masked) and return its level and masked text when
+    /// it should also be shown; None when it is below `llama_log_level`.
+    pub fn handle(&self, line: &str) -> Option<(&'static str, String)> {
+        let masked = crate::system::mask_sensitive_data(line);
+        if let Some(file) = &self.file {
+            if let Ok(mut file) = file.lock() {
+                let _ = writeln!(file, "{}", masked);
+            }
+        }
+        let level = match crate::system::parse_log_level(line) {
+            "ERROR" => "ERROR",
+            "WARNING" => "WARNING",
+            "DEBUG" => "DEBUG",
+            _ => "INFO",
+        };
+        (level_rank(level) >= self.min_rank).then_some((level, masked))
+    }
+}
@@ -1,11 +1,56 @@
+pub mod llama_log;
+pub mod sandbox;
+
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::{OnceLock, RwLock};
+
+use crate::config::ServeMode;
+use crate::error::{CoreError, CoreResult};
+
+/// Environment variable llama-server reads its `--api-key` from. Preferred over the
+/// flag because the command line is visible to every local user in `ps`.
+pub const API_KEY_ENV: &str = "LLAMA_API_KEY";
+
+static API_KEY: OnceLock<RwLock<Option<String>>> = OnceLock::new();
+
+fn api_key_slot() -> &'static RwLock<Option<String>> {
+    API_KEY.get_or_init(|| RwLock::new(None))
+}
+
+/// Fresh random key for one llama-server start (64 hex chars, from the OS RNG).
+pub fn generate_api_key() -> Result<String, String> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("OS RNG unavailable: {}", e))?;
+    Ok(hex::encode(bytes))
+}
+
+/// Key the local llama-server was started with; attached to every request we make to it.
+pub fn set_api_key(key: Option<String>) {
+    if let Ok(mut slot) = api_key_slot().write() {
+        *slot = key;
+    }
+}
+
+pub fn api_key() -> Option<String> {
+    api_key_slot().read().ok().and_then(|slot| slot.clone())
+}
+
+/// Add the local llama-server API key, if one is set, to `req`.
+pub fn with_api_key(req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match api_key() {
+        Some(key) => req.bearer_auth(key),
+        None => req,
+    }
+}
 
 /// Configuration for spawning a llama-server process.
 #[derive(Debug, Clone)]
 pub struct LlamaServerConfig {
     pub binary_path: PathBuf,
     pub model_path: PathBuf,
+    /// Multimodal projector; enables image input on `/v1/chat/completions`.
+    pub mmproj_path: Option<PathBuf>,
     pub host: String,
     pub port: u16,
     pub gpu_layers: i32,
@@ -13,6 +58,18 @@ pub struct LlamaServerConfig {
     pub parallel_slots: u32,
     pub env_vars: HashMap<String, String>,
     pub rpc_peers: Option<Vec<String>>,
+    /// Embedding/rerank modes restrict llama-server to that endpoint.
+    pub serve_mode: ServeMode,
+    /// Run inside the OS sandbox (see [`sandbox`]).
+    pub sandbox: bool,
+    /// Required as a bearer token on every endpoint except /health (see [`API_KEY_ENV`]).
+    pub api_key: Option<String>,
+    pub context_shift: bool,
+    /// `--cache-reuse` chunk size; 0 = off.
+    pub cache_reuse: u32,
+    /// `--log-verbosity`; None keeps llama-server's default.
+    pub log_verbosity: Option<u32>,
+    pub log_disable: bool,
 }
 
 /// Build llama-server command-line arguments from config.
@@ -31,8 +88,18 @@ pub fn build_llama_args(config: &LlamaServerConfig) -> Vec<String> {
         "-np".into(),
         config.parallel_slots.to_string(),
         "--jinja".into(),
+        "--metrics".into(),
     ];
 
+    if let Some(flag) = config.serve_mode.llama_flag() {
+        args.push(flag.into());
+    }
+
+    if let Some(ref mmproj) = config.mmproj_path {
+        args.push("--mmproj".into());
+        args.push(mmproj.to_string_lossy().to_string());
+    }
+
     if let Some(ref peers) = config.rpc_peers {
         if !peers.is_empty() {
             args.push("--rpc".into());
@@ -40,6 +107,23 @@ pub fn build_llama_args(config: &LlamaServerConfig) -> Vec<String> {
         }
     }
 
+    args.extend(context_args(config.context_shift, config.cache_reuse));
+    args.extend(llama_log::llama_log_args(config.log_verbosity, config.log_disable));
+
+    args
+}
+
+/// Flags for `context_shift` and `cache_reuse`.
+pub fn context_args(context_shift: bool, cache_reuse: u32) -> Vec<String> {
+    let mut args = vec![if context_shift {
+        "--context-shift".to_string()
+    } else {
+        "--no-context-shift".to_string()
+    }];
+    if cache_reuse > 0 {
+        args.push("--cache-reuse".into());
+        args.push(cache_reuse.to_string());
+    }
     args
 }
 
@@ -70,7 +154,7 @@ pub struct LlamaProcess {
 
 impl LlamaProcess {
     /// Spawn llama-server as a child process.
-    pub fn spawn(config: &LlamaServerConfig) -> Result<Self, String> {
+    pub fn spawn(config: &LlamaServerConfig) -> CoreResult<Self> {
         let args = build_llama_args(config);
 
         log::info!(
@@ -79,13 +163,24 @@ impl LlamaProcess {
             args.join(" ")
         );
 
-        let mut cmd = tokio::process::Command::new(&config.binary_path);
-        cmd.args(&args);
+        let (mut cmd, sandboxed) = if config.sandbox {
+            let policy = sandbox::SandboxPolicy::for_llama(config);
+            let (cmd, guard) =
+                sandbox::command(&config.binary_path, &args, &policy).map_err(CoreError::Process)?;
+            (cmd, Some(guard))
+        } else {
+            let mut cmd = tokio::process::Command::new(&config.binary_path);
+            cmd.args(&args);
+            (cmd, None)
+        };
 
         // Set environment variables
         for (k, v) in &config.env_vars {
             cmd.env(k, v);
         }
+        if let Some(ref key) = config.api_key {
+            cmd.env(API_KEY_ENV, key);
+        }
 
         // Set working directory to binary's parent (for DLL discovery)
         if let Some(parent) = config.binary_path.parent() {
@@ -103,27 +198,33 @@ impl LlamaProcess {
 
         let child = cmd
             .spawn()
-            .map_err(|e| format!("Failed to spawn llama-server: {}", e))?;
+            .map_err(|e| CoreError::Process(format!("Failed to spawn llama-server: {}", e)))?;
 
         let pid = child.id().unwrap_or(0);
         log::info!("llama-server spawned, PID: {}", pid);
+        if let Some(guard) = sandboxed {
+            guard
+                .attach(pid)
+                .map_err(|e| CoreError::Process(format!("Failed to sandbox llama-server: {}", e)))?;
+            log::info!("llama-server is sandboxed");
+        }
 
         Ok(Self { child, pid })
     }
 
     /// Poll /health endpoint until llama-server reports "ok" or timeout.
-    pub async fn wait_ready(&self, timeout_secs: u64, port: u16) -> Result<(), String> {
-        let client = reqwest::Client::new();
+    pub async fn wait_ready(&self, timeout_secs: u64, port: u16) -> CoreResult<()> {
+        let client = crate::http::local();
         let health_url = format!("http://127.0.0.1:{}/health", port);
         let deadline = tokio::time::Instant::now()
             + std::time::Duration::from_secs(timeout_secs);
 
         loop {
             if tokio::time::Instant::now() > deadline {
-                return Err(format!(
+                return Err(CoreError::Process(format!(
                     "llama-server did not become ready within {} seconds",
                     timeout_secs
-                ));
+                )));
             }
 
             tokio::time::sleep(std::time::Duration::from_secs(2)).await;
@@ -0,0 +1,737 @@
+//! Sandboxing for the llama.cpp binaries we download and run (`sandbox` in `AgentConfig`).
+//!
+//! - Linux: no-new-privs, rlimits, a seccomp filter denying syscalls inference never
+//!   needs, and Landlock rules limiting the filesystem to system/driver paths plus the
+//!   binary and model (and, on kernels with Landlock ABI 4, TCP to the listen port and peers).
+//! - macOS: the process runs under `sandbox-exec` with a profile denying writes outside
+//!   temp dirs, reads of the home directory and network other than its own port and peers.
+//! - Windows: a Job Object forbids child processes and desktop/clipboard access and can
+//!   cap memory. Filesystem and network are not restricted there.
+//!
+//! Only processes started through [`command`] get the full set. Already running
+//! processes (the GUI's Tauri sidecars) only get what [`limit_running`] can apply by PID.
+
+use std::path::{Path, PathBuf};
+
+/// What a sandboxed llama-server or rpc-server may touch.
+#[derive(Debug, Clone, Default)]
+pub struct SandboxPolicy {
+    /// Files and directories it may read besides system and driver paths
+    /// (its own directory, the model, the projector).
+    pub read_paths: Vec<PathBuf>,
+    /// TCP port it listens on.
+    pub listen_port: u16,
+    /// TCP ports it may connect to (rpc-server peers of a coordinator).
+    pub connect_ports: Vec<u16>,
+    /// Job memory cap on Windows; None = unlimited.
+    pub max_memory_bytes: Option<u64>,
+}
+
+impl SandboxPolicy {
+    pub fn for_llama(config: &super::LlamaServerConfig) -> Self {
+        let mut read_paths = vec![config.model_path.clone()];
+        read_paths.extend(config.mmproj_path.clone());
+        read_paths.extend(config.binary_path.parent().map(Path::to_path_buf));
+        read_paths.extend(config.env_vars.get("GGML_BACKEND_DIR").map(PathBuf::from));
+        let connect_ports = config
+            .rpc_peers
+            .iter()
+            .flatten()
+            .filter_map(|peer| {
+                peer.rsplit_once(':')
+                    .and_then(|(_, port)| port.parse().ok())
+            })
+            .collect();
+        Self {
+            read_paths,
+            listen_port: config.port,
+            connect_ports,
+            max_memory_bytes: None,
+        }
+    }
+
+    pub fn for_rpc_server(binary_path: &Path, port: u16) -> Self {
+        Self {
+            read_paths: binary_path
+                .parent()
+                .map(Path::to_path_buf)
+                .into_iter()
+                .collect(),
+            listen_port: port,
+            ..Default::default()
+        }
+    }
+}
+
+/// Keeps sandbox resources alive until the process has been spawned.
+/// Call [`Sandbox::attach`] with the child's PID right after spawning.
+pub struct Sandbox {
+    #[cfg(target_os = "linux")]
+    _ruleset: Option<std::os::fd::OwnedFd>,
+    #[cfg(windows)]
+    max_memory_bytes: Option<u64>,
+}
+
+impl Sandbox {
+    /// Finish sandboxing a freshly spawned child (Job Object on Windows; no-op elsewhere).
+    pub fn attach(self, pid: u32) -> Result<(), String> {
+        #[cfg(windows)]
+        return windows::assign_job(pid, self.max_memory_bytes);
+        #[cfg(not(windows))]
+        {
+            let _ = pid;
+            Ok(())
+        }
+    }
+}
+
+/// Build a command running `binary` with `args` inside the sandbox described by `policy`.
+pub fn command(
+    binary: &Path,
+    args: &[String],
+    policy: &SandboxPolicy,
+) -> Result<(tokio::process::Command, Sandbox), String> {
+    #[cfg(target_os = "macos")]
+    {
+        let mut cmd = tokio::process::Command::new("/usr/bin/sandbox-exec");
+        cmd.arg("-p")
+            .arg(macos::profile(binary, policy))
+            .arg(binary)
+            .args(args);
+        Ok((cmd, Sandbox {}))
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let mut cmd = tokio::process::Command::new(binary);
+        cmd.args(args);
+        let ruleset = match linux::landlock_ruleset(binary, policy) {
+            Ok(ruleset) => ruleset,
+            Err(e) => {
+                log::warn!("Landlock unavailable, filesystem is not restricted: {}", e);
+                None
+            }
+        };
+        linux::install_pre_exec(&mut cmd, ruleset.as_ref())?;
+        Ok((cmd, Sandbox { _ruleset: ruleset }))
+    }
+
+    #[cfg(windows)]
+    {
+        let mut cmd = tokio::process::Command::new(binary);
+        cmd.args(args);
+        Ok((
+            cmd,
+            Sandbox {
+                max_memory_bytes: policy.max_memory_bytes,
+            },
+        ))
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+    {
+        let _ = policy;
+        log::warn!("Process sandboxing is not supported on this platform");
+        let mut cmd = tokio::process::Command::new(binary);
+        cmd.args(args);
+        Ok((cmd, Sandbox {}))
+    }
+}
+
+/// Apply the limits that work on an already running process: a Job Object on
+/// Windows, rlimits on Linux. Used for sidecars we don't spawn ourselves.
+pub fn limit_running(pid: u32, policy: &SandboxPolicy) -> Result<(), String> {
+    #[cfg(windows)]
+    return windows::assign_job(pid, policy.max_memory_bytes);
+
+    #[cfg(target_os = "linux")]
+    {
+        let _ = policy;
+        linux::limit_pid(pid)
+    }
+
+    #[cfg(not(any(target_os = "linux", windows)))]
+    {
+        let _ = (pid, policy);
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::ffi::c_void;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::path::Path;
+
+    use super::SandboxPolicy;
+
+    /// Open file descriptors allowed in the child.
+    const MAX_OPEN_FILES: libc::rlim_t = 8192;
+
+    // Landlock syscall numbers are the same on every architecture
+    const SYS_LANDLOCK_CREATE_RULESET: libc::c_long = 444;
+    const SYS_LANDLOCK_ADD_RULE: libc::c_long = 445;
+    const SYS_LANDLOCK_RESTRICT_SELF: libc::c_long = 446;
+    const LANDLOCK_CREATE_RULESET_VERSION: u32 = 1;
+    const LANDLOCK_RULE_PATH_BENEATH: u32 = 1;
+    const LANDLOCK_RULE_NET_PORT: u32 = 2;
+
+    const ACCESS_FS_EXECUTE: u64 = 1 << 0;
+    const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+    const ACCESS_FS_READ_FILE: u64 = 1 << 2;
+    const ACCESS_FS_READ_DIR: u64 = 1 << 3;
+    /// ABI 1 rights: execute, write, read, readdir, remove and make-* (bits 0..=12).
+    const ACCESS_FS_ABI1: u64 = (1 << 13) - 1;
+    const ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+    const ACCESS_FS_FILE: u64 =
+        ACCESS_FS_EXECUTE | ACCESS_FS_WRITE_FILE | ACCESS_FS_READ_FILE | ACCESS_FS_TRUNCATE;
+    const ACCESS_FS_READ: u64 = ACCESS_FS_EXECUTE | ACCESS_FS_READ_FILE | ACCESS_FS_READ_DIR;
+    const ACCESS_NET_BIND_TCP: u64 = 1 << 0;
+    const ACCESS_NET_CONNECT_TCP: u64 = 1 << 1;
+
+    /// Readable (and executable) for every sandboxed process: libraries, driver
+    /// userspace, ICD files, /proc and /sys for hardware discovery.
+    const SYSTEM_READ_DIRS: &[&str] = &[
+        "/usr", "/lib", "/lib64", "/lib32", "/bin", "/sbin", "/etc", "/opt", "/nix", "/proc",
+        "/sys", "/run",
+    ];
+    /// Writable: GPU device nodes are opened read-write, plus scratch space.
+    const SYSTEM_WRITE_DIRS: &[&str] = &["/dev", "/tmp", "/var/tmp"];
+
+    #[repr(C)]
+    struct RulesetAttr {
+        handled_access_fs: u64,
+        handled_access_net: u64,
+    }
+
+    #[repr(C, packed)]
+    struct PathBeneathAttr {
+        allowed_access: u64,
+        parent_fd: i32,
+    }
+
+    #[repr(C)]
+    struct NetPortAttr {
+        allowed_access: u64,
+        port: u64,
+    }
+
+    /// Build the Landlock ruleset in the parent; the child only has to enforce it.
+    /// Fails on kernels without Landlock.
+    pub(super) fn landlock_ruleset(
+        binary: &Path,
+        policy: &SandboxPolicy,
+    ) -> Result<Option<OwnedFd>, String> {
+        // SAFETY: with LANDLOCK_CREATE_RULESET_VERSION the kernel ignores the (null,
+        // 0) attribute and only returns the ABI version; no memory is touched.
+        let abi = unsafe {
+            libc::syscall(
+                SYS_LANDLOCK_CREATE_RULESET,
+                std::ptr::null::<c_void>(),
+                0usize,
+                LANDLOCK_CREATE_RULESET_VERSION,
+            )
+        };
+        if abi < 1 {
+            return Err(format!("landlock: {}", std::io::Error::last_os_error()));
+        }
+        let handled_fs = if abi >= 3 {
+            ACCESS_FS_ABI1 | ACCESS_FS_TRUNCATE
+        } else {
+            ACCESS_FS_ABI1
+        };
+        let handled_net = if abi >= 4 {
+            ACCESS_NET_BIND_TCP | ACCESS_NET_CONNECT_TCP
+        } else {
+            0
+        };
+        let attr = RulesetAttr {
+            handled_access_fs: handled_fs,
+            handled_access_net: handled_net,
+        };
+        // Older kernels accept the larger struct as long as the unknown field is zero
+        // SAFETY: `attr` is a live #[repr(C)] landlock_ruleset_attr and the size passed
+        // is its own; the kernel only reads it.
+        let fd = unsafe {
+            libc::syscall(
+                SYS_LANDLOCK_CREATE_RULESET,
+                &attr as *const RulesetAttr,
+                std::mem::size_of::<RulesetAttr>(),
+                0u32,
+            )
+        };
+        if fd < 0 {
+            return Err(format!(
+                "landlock ruleset: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        // SAFETY: `fd` was just returned by the kernel and nothing else owns it.
+        let ruleset = unsafe { OwnedFd::from_raw_fd(fd as i32) };
+
+        let read = ACCESS_FS_READ & handled_fs;
+        let write = handled_fs;
+        for dir in SYSTEM_READ_DIRS {
+            allow_path(&ruleset, Path::new(dir), read)?;
+        }
+        for dir in SYSTEM_WRITE_DIRS {
+            allow_path(&ruleset, Path::new(dir), write)?;
+        }
+        allow_path(&ruleset, binary, read)?;
+        for path in &policy.read_paths {
+            allow_path(&ruleset, path, read)?;
+        }
+
+        if handled_net != 0 {
+            allow_port(&ruleset, policy.listen_port, ACCESS_NET_BIND_TCP)?;
+            for &port in &policy.connect_ports {
+                allow_port(&ruleset, port, ACCESS_NET_CONNECT_TCP)?;
+            }
+        }
+        Ok(Some(ruleset))
+    }
+
+    /// Allow `access` beneath `path`; missing paths are skipped.
+    fn allow_path(ruleset: &OwnedFd, path: &Path, access: u64) -> Result<(), String> {
+        let Ok(file) = std::fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_PATH | libc::O_CLOEXEC)
+            .open(path)
+        else {
+            return Ok(());
+        };
+        // Directory-only rights are invalid on a file rule
+        let is_dir = file.metadata().map(|m| m.is_dir()).unwrap_or(false);
+        let attr = PathBeneathAttr {
+            allowed_access: if is_dir {
+                access
+            } else {
+                access & ACCESS_FS_FILE
+            },
+            parent_fd: file.as_raw_fd(),
+        };
+        // SAFETY: `attr` is a live landlock_path_beneath_attr (packed, as the kernel
+        // declares it) whose `parent_fd` stays open until `file` drops after the call;
+        // `ruleset` is a valid Landlock fd. The kernel only reads `attr`.
+        let rc = unsafe {
+            libc::syscall(
+                SYS_LANDLOCK_ADD_RULE,
+                ruleset.as_raw_fd(),
+                LANDLOCK_RULE_PATH_BENEATH,
+                &attr as *const PathBeneathAttr,
+                0u32,
+            )
+        };
+        if rc != 0 {
+            return Err(format!(
+                "landlock rule for {}: {}",
+                path.display(),
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(())
+    }
+
+    fn allow_port(ruleset: &OwnedFd, port: u16, access: u64) -> Result<(), String> {
+        let attr = NetPortAttr {
+            allowed_access: access,
+            port: port as u64,
+        };
+        // SAFETY: `attr` is a live #[repr(C)] landlock_net_port_attr and `ruleset` a
+        // valid Landlock fd; the kernel only reads `attr`.
+        let rc = unsafe {
+            libc::syscall(
+                SYS_LANDLOCK_ADD_RULE,
+                ruleset.as_raw_fd(),
+                LANDLOCK_RULE_NET_PORT,
+                &attr as *const NetPortAttr,
+                0u32,
+            )
+        };
+        if rc != 0 {
+            return Err(format!(
+                "landlock rule for port {}: {}",
+                port,
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Syscalls that inference has no use for; they fail with EPERM.
+    fn denied_syscalls() -> Vec<libc::c_long> {
+        vec![
+            libc::SYS_ptrace,
+            libc::SYS_process_vm_readv,
+            libc::SYS_process_vm_writev,
+            libc::SYS_mount,
+            libc::SYS_umount2,
+            libc::SYS_pivot_root,
+            libc::SYS_chroot,
+            libc::SYS_unshare,
+            libc::SYS_setns,
+            libc::SYS_init_module,
+            libc::SYS_finit_module,
+            libc::SYS_delete_module,
+            libc::SYS_kexec_load,
+            libc::SYS_kexec_file_load,
+            libc::SYS_reboot,
+            libc::SYS_swapon,
+            libc::SYS_swapoff,
+            libc::SYS_bpf,
+            libc::SYS_perf_event_open,
+            libc::SYS_userfaultfd,
+            libc::SYS_keyctl,
+            libc::SYS_add_key,
+            libc::SYS_request_key,
+            libc::SYS_open_by_handle_at,
+            libc::SYS_acct,
+            libc::SYS_settimeofday,
+            libc::SYS_clock_settime,
+            libc::SYS_sethostname,
+            libc::SYS_setdomainname,
+        ]
+    }
+
+    const BPF_LD_W_ABS: u16 = 0x20;
+    const BPF_JMP_JEQ_K: u16 = 0x15;
+    const BPF_JMP_JGE_K: u16 = 0x35;
+    const BPF_RET_K: u16 = 0x06;
+    const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+    const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+    /// Offsets into `struct seccomp_data`.
+    const SECCOMP_DATA_NR: u32 = 0;
+    const SECCOMP_DATA_ARCH: u32 = 4;
+    #[cfg(target_arch = "x86_64")]
+    const AUDIT_ARCH: Option<u32> = Some(0xc000_003e);
+    #[cfg(target_arch = "aarch64")]
+    const AUDIT_ARCH: Option<u32> = Some(0xc000_00b7);
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    const AUDIT_ARCH: Option<u32> = None;
+    /// x32 syscalls on x86_64 set this bit; they would bypass the number checks.
+    const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+    fn stmt(code: u16, k: u32) -> libc::sock_filter {
+        libc::sock_filter {
+            code,
+            jt: 0,
+            jf: 0,
+            k,
+        }
+    }
+
+    fn jump(code: u16, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+        libc::sock_filter { code, jt, jf, k }
+    }
+
+    /// Deny-list filter: foreign architectures and the syscalls above get EPERM.
+    /// None on architectures the filter hasn't been written for.
+    fn seccomp_filter() -> Option<Vec<libc::sock_filter>> {
+        let arch = AUDIT_ARCH?;
+        let denied = denied_syscalls();
+        let deny = SECCOMP_RET_ERRNO | libc::EPERM as u32;
+        let mut prog = vec![
+            stmt(BPF_LD_W_ABS, SECCOMP_DATA_ARCH),
+            jump(BPF_JMP_JEQ_K, arch, 1, 0),
+            stmt(BPF_RET_K, deny),
+            stmt(BPF_LD_W_ABS, SECCOMP_DATA_NR),
+            jump(BPF_JMP_JGE_K, X32_SYSCALL_BIT, 0, 1),
+            stmt(BPF_RET_K, deny),
+        ];
+        for (i, &nr) in denied.iter().enumerate() {
+            // On a match, skip the remaining checks and the ALLOW to land on the final DENY
+            let remaining = (denied.len() - i - 1) as u8;
+            prog.push(jump(BPF_JMP_JEQ_K, nr as u32, remaining + 1, 0));
+        }
+        prog.push(stmt(BPF_RET_K, SECCOMP_RET_ALLOW));
+        prog.push(stmt(BPF_RET_K, deny));
+        Some(prog)
+    }
+
+    fn lower_rlimits() -> std::io::Result<()> {
+        let no_core = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: setrlimit/getrlimit only read or write the rlimit structs on our stack.
+        // Called after fork as well: both are async-signal-safe and nothing here allocates.
+        if unsafe { libc::setrlimit(libc::RLIMIT_CORE, &no_core) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let mut files = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: as above; `files` is writable.
+        if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut files) } == 0 {
+            files.rlim_cur = files.rlim_cur.min(MAX_OPEN_FILES);
+            files.rlim_max = files.rlim_max.min(MAX_OPEN_FILES);
+            // SAFETY: as above.
+            if unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &files) } != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    /// Enforce everything in the child between fork and exec. The closure only
+    /// makes syscalls on data prepared beforehand (no allocation after fork).
+    pub(super) fn install_pre_exec(
+        cmd: &mut tokio::process::Command,
+        ruleset: Option<&OwnedFd>,
+    ) -> Result<(), String> {
+        let filter = seccomp_filter();
+        if filter.is_none() {
+            log::warn!(
+                "No seccomp filter for {}; syscalls are not restricted",
+                std::env::consts::ARCH
+            );
+        }
+        let filter_len = match &filter {
+            Some(f) => u16::try_from(f.len()).map_err(|_| "seccomp filter too long".to_string())?,
+            None => 0,
+        };
+        let ruleset_fd = ruleset.map(|fd| fd.as_raw_fd());
+        // SAFETY: the closure runs in the forked child before exec, where only
+        // async-signal-safe operations are allowed. It calls prctl, setrlimit/getrlimit
+        // and landlock_restrict_self, all plain syscalls; reads `filter`, allocated
+        // before the fork and moved in; and builds errors with `last_os_error`, which
+        // doesn't allocate. It takes no locks and doesn't log. `ruleset_fd` stays valid
+        // because the caller keeps the ruleset in `Sandbox` until the child has spawned.
+        unsafe {
+            cmd.pre_exec(move || {
+                if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                lower_rlimits()?;
+                if let Some(fd) = ruleset_fd {
+                    if libc::syscall(SYS_LANDLOCK_RESTRICT_SELF, fd, 0u32) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                let Some(filter) = &filter else {
+                    return Ok(());
+                };
+                let prog = libc::sock_fprog {
+                    len: filter_len,
+                    filter: filter.as_ptr() as *mut libc::sock_filter,
+                };
+                if libc::prctl(
+                    libc::PR_SET_SECCOMP,
+                    libc::SECCOMP_MODE_FILTER,
+                    &prog as *const libc::sock_fprog,
+                ) != 0
+                {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        Ok(())
+    }
+
+    /// Core dumps off and the open-file cap, applied to a running process.
+    pub(super) fn limit_pid(pid: u32) -> Result<(), String> {
+        let no_core = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: prlimit only reads the new limit from `no_core`; a null old-limit
+        // pointer is allowed. A stale `pid` fails with ESRCH, it can't corrupt memory.
+        let rc = unsafe {
+            libc::prlimit(
+                pid as libc::pid_t,
+                libc::RLIMIT_CORE,
+                &no_core,
+                std::ptr::null_mut(),
+            )
+        };
+        if rc != 0 {
+            return Err(format!(
+                "prlimit({}): {}",
+                pid,
+                std::io::Error::last_os_error()
+            ));
+        }
+        let files = libc::rlimit {
+            rlim_cur: MAX_OPEN_FILES,
+            rlim_max: MAX_OPEN_FILES,
+        };
+        // SAFETY: as above, with `files`.
+        let rc = unsafe {
+            libc::prlimit(
+                pid as libc::pid_t,
+                libc::RLIMIT_NOFILE,
+                &files,
+                std::ptr::null_mut(),
+            )
+        };
+        if rc != 0 {
+            return Err(format!(
+                "prlimit({}): {}",
+                pid,
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use std::path::Path;
+
+    use super::SandboxPolicy;
+
+    fn quote(path: &Path) -> String {
+        let s = path.to_string_lossy();
+        format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+
+    /// SBPL profile for `sandbox-exec -p`. Starts from allow-default so Metal and
+    /// the dynamic loader keep working, then takes away writes, the home directory
+    /// and the network.
+    pub(super) fn profile(binary: &Path, policy: &SandboxPolicy) -> String {
+        let mut p = String::from("(version 1)\n(allow default)\n");
+
+        p.push_str("(deny file-write*)\n");
+        p.push_str(
+            "(allow file-write* (subpath \"/private/tmp\") (subpath \"/private/var/folders\") (subpath \"/dev\"))\n",
+        );
+
+        if let Some(home) = dirs_next::home_dir() {
+            p.push_str(&format!("(deny file-read* (subpath {}))\n", quote(&home)));
+            p.push_str(&format!("(allow file-read* (literal {})", quote(binary)));
+            for path in &policy.read_paths {
+                let kind = if path.is_dir() { "subpath" } else { "literal" };
+                p.push_str(&format!(" ({} {})", kind, quote(path)));
+            }
+            p.push_str(")\n");
+        }
+
+        p.push_str("(deny process-fork)\n");
+        p.push_str("(deny network-outbound)\n(allow network-outbound (remote unix-socket))\n");
+        for port in &policy.connect_ports {
+            p.push_str(&format!(
+                "(allow network-outbound (remote tcp \"*:{}\"))\n",
+                port
+            ));
+        }
+        p.push_str("(deny network-bind)\n");
+        p.push_str(&format!(
+            "(allow network-bind (local tcp \"*:{}\"))\n",
+            policy.listen_port
+        ));
+        p
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectBasicUIRestrictions,
+        JobObjectExtendedLimitInformation, SetInformationJobObject,
+        JOBOBJECT_BASIC_UI_RESTRICTIONS, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_LIMIT_ACTIVE_PROCESS, JOB_OBJECT_LIMIT_DIE_ON_UNHANDLED_EXCEPTION,
+        JOB_OBJECT_LIMIT_PROCESS_MEMORY, JOB_OBJECT_UILIMIT_DESKTOP,
+        JOB_OBJECT_UILIMIT_DISPLAYSETTINGS, JOB_OBJECT_UILIMIT_EXITWINDOWS,
+        JOB_OBJECT_UILIMIT_GLOBALATOMS, JOB_OBJECT_UILIMIT_HANDLES,
+        JOB_OBJECT_UILIMIT_READCLIPBOARD, JOB_OBJECT_UILIMIT_SYSTEMPARAMETERS,
+        JOB_OBJECT_UILIMIT_WRITECLIPBOARD,
+    };
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, PROCESS_SET_QUOTA, PROCESS_TERMINATE,
+    };
+
+    /// Put `pid` in a new Job Object: no child processes, no desktop or clipboard
+    /// access, optional memory cap. The job outlives our handle while the process runs.
+    pub(super) fn assign_job(pid: u32, max_memory_bytes: Option<u64>) -> Result<(), String> {
+        // SAFETY: null security attributes and name are allowed; `job` is checked
+        // before use and closed exactly once, after `configure_and_assign` is done with it.
+        unsafe {
+            let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+            if job.is_null() {
+                return Err(format!(
+                    "CreateJobObject: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+            let result = configure_and_assign(job, pid, max_memory_bytes);
+            CloseHandle(job);
+            result
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `job` must be a valid Job Object handle.
+    unsafe fn configure_and_assign(
+        job: windows_sys::Win32::Foundation::HANDLE,
+        pid: u32,
+        max_memory_bytes: Option<u64>,
+    ) -> Result<(), String> {
+        // SAFETY (whole body): the limit structs are plain data, zeroed is a valid start,
+        // and each is passed with its own size; `process` is checked for null before
+        // use and closed once.
+        let mut limits: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+        limits.BasicLimitInformation.LimitFlags =
+            JOB_OBJECT_LIMIT_ACTIVE_PROCESS | JOB_OBJECT_LIMIT_DIE_ON_UNHANDLED_EXCEPTION;
+        limits.BasicLimitInformation.ActiveProcessLimit = 1;
+        if let Some(bytes) = max_memory_bytes {
+            limits.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+            limits.ProcessMemoryLimit = bytes as usize;
+        }
+        if SetInformationJobObject(
+            job,
+            JobObjectExtendedLimitInformation,
+            &limits as *const _ as *const std::ffi::c_void,
+            std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+        ) == 0
+        {
+            return Err(format!("Job limits: {}", std::io::Error::last_os_error()));
+        }
+
+        let ui = JOBOBJECT_BASIC_UI_RESTRICTIONS {
+            UIRestrictionsClass: JOB_OBJECT_UILIMIT_DESKTOP
+                | JOB_OBJECT_UILIMIT_DISPLAYSETTINGS
+                | JOB_OBJECT_UILIMIT_EXITWINDOWS
+                | JOB_OBJECT_UILIMIT_GLOBALATOMS
+                | JOB_OBJECT_UILIMIT_HANDLES
+                | JOB_OBJECT_UILIMIT_READCLIPBOARD
+                | JOB_OBJECT_UILIMIT_SYSTEMPARAMETERS
+                | JOB_OBJECT_UILIMIT_WRITECLIPBOARD,
+        };
+        if SetInformationJobObject(
+            job,
+            JobObjectBasicUIRestrictions,
+            &ui as *const _ as *const std::ffi::c_void,
+            std::mem::size_of::<JOBOBJECT_BASIC_UI_RESTRICTIONS>() as u32,
+        ) == 0
+        {
+            return Err(format!(
+                "Job UI restrictions: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        let process = OpenProcess(PROCESS_SET_QUOTA | PROCESS_TERMINATE, 0, pid);
+        if process.is_null() {
+            return Err(format!(
+                "OpenProcess({}): {}",
+                pid,
+                std::io::Error::last_os_error()
+            ));
+        }
+        let assigned = AssignProcessToJobObject(job, process);
+        CloseHandle(process);
+        if assigned == 0 {
+            return Err(format!(
+                "AssignProcessToJobObject: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(())
+    }
+}
@@ -1,15 +1,28 @@
 use futures_util::{SinkExt, StreamExt};
 use k256::ecdsa::SigningKey;
-use serde::Deserialize;
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use serde::{Deserialize, Serialize};
+use tokio_tungstenite::{connect_async_tls_with_config, tungstenite::Message, Connector};
+use tracing::Instrument;
 
+use crate::audit::{AuditLog, AuditRecord};
+use crate::availability::Availability;
 use crate::chain::crypto::{address_from_key, personal_sign};
+use crate::config::ServeMode;
+use crate::inference::proof_batch::ProofBatcher;
+use crate::inference::metrics::{MetricsSampler, SharedSlots};
+use crate::inference::stats::SharedLatencyStats;
+use crate::ledger::{unix_millis, Ledger, LedgerEntry, RequestOutcome};
+use crate::oracle::usage::UsageReporter;
+use crate::process::with_api_key;
 
 #[derive(Debug, Deserialize)]
 struct ServerMessage {
     r#type: String,
     #[serde(default)]
     id: String,
+    /// Model the request is for; absent means the primary model.
+    #[serde(default)]
+    model: Option<String>,
     #[serde(default)]
     messages: Vec<ChatMessage>,
     #[serde(default, rename = "maxTokens")]
@@ -20,34 +33,251 @@ struct ServerMessage {
     top_p: f64,
     #[serde(default)]
     stream: bool,
+    /// Embedding requests: a string or an array of strings.
+    #[serde(default)]
+    input: serde_json::Value,
+    /// Rerank requests: score `documents` against `query`.
+    #[serde(default)]
+    query: String,
+    #[serde(default)]
+    documents: Vec<String>,
+    #[serde(default, rename = "topN")]
+    top_n: Option<u32>,
     #[allow(dead_code)]
     #[serde(default)]
     message: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct ChatMessage {
     role: String,
-    content: String,
+    content: MessageContent,
+}
+
+/// OpenAI-style message content: plain text, or a list of parts for vision requests.
+/// Forwarded to llama-server unchanged, which needs `mmproj_file` to accept images.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum MessageContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentPart {
+    Text {
+        text: String,
+    },
+    /// `url` is an `https://` link or a `data:image/...;base64,` URI.
+    ImageUrl {
+        image_url: ImageUrl,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct ImageUrl {
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+/// How often a non-streaming request sends `progress` while it runs.
+const PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+/// Finished requests remembered for redelivered ids...
+const SERVED_CACHE_SIZE: usize = 256;
+/// ...for at most this long.
+const SERVED_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+/// Machine-readable reason in relay `error` messages, next to the free-text `message`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum RelayErrorCode {
+    /// No free slot, or the agent is paused; retry on another node.
+    #[serde(rename = "overloaded")]
+    Overloaded,
+    /// llama-server did not answer in time.
+    #[serde(rename = "timeout")]
+    Timeout,
+    /// llama-server is down, failed or returned garbage.
+    #[serde(rename = "upstream_5xx")]
+    Upstream5xx,
+    /// The request itself was rejected; retrying elsewhere won't help.
+    #[serde(rename = "bad_request")]
+    BadRequest,
+    /// The agent is deregistering or stopping.
+    #[serde(rename = "shutting_down")]
+    ShuttingDown,
+}
+
+impl RelayErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RelayErrorCode::Overloaded => "overloaded",
+            RelayErrorCode::Timeout => "timeout",
+            RelayErrorCode::Upstream5xx => "upstream_5xx",
+            RelayErrorCode::BadRequest => "bad_request",
+            RelayErrorCode::ShuttingDown => "shutting_down",
+        }
+    }
+
+    /// llama-server answers 503 while loading or when every slot is busy.
+    fn from_status(status: reqwest::StatusCode) -> Self {
+        match status.as_u16() {
+            503 | 429 => RelayErrorCode::Overloaded,
+            408 | 504 => RelayErrorCode::Timeout,
+            400..=499 => RelayErrorCode::BadRequest,
+            _ => RelayErrorCode::Upstream5xx,
+        }
+    }
+
+    fn from_request_error(e: &reqwest::Error) -> Self {
+        if e.is_timeout() {
+            RelayErrorCode::Timeout
+        } else {
+            RelayErrorCode::Upstream5xx
+        }
+    }
+}
+
+/// A model served by this node and the local llama-server port that serves it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelRoute {
+    pub model: String,
+    pub port: u16,
+}
+
+/// Models the relay authenticates with and routes requests to. The first route is the
+/// primary one: requests that don't name a model go there. Clones share the routes, so
+/// `replace` repoints the relay (and the reporter, if given them) in one step.
+#[derive(Debug, Clone)]
+pub struct ModelRoutes {
+    routes: std::sync::Arc<std::sync::RwLock<Vec<ModelRoute>>>,
+    changed: std::sync::Arc<tokio::sync::watch::Sender<()>>,
+}
+
+impl ModelRoutes {
+    pub fn new(routes: Vec<ModelRoute>) -> Self {
+        Self {
+            routes: std::sync::Arc::new(std::sync::RwLock::new(routes)),
+            changed: std::sync::Arc::new(tokio::sync::watch::channel(()).0),
+        }
+    }
+
+    pub fn single(model: String, port: u16) -> Self {
+        Self::new(vec![ModelRoute { model, port }])
+    }
+
+    pub fn models(&self) -> Vec<String> {
+        self.routes.read().unwrap_or_else(|e| e.into_inner()).iter().map(|r| r.model.clone()).collect()
+    }
+
+    pub fn primary(&self) -> Option<ModelRoute> {
+        self.routes.read().unwrap_or_else(|e| e.into_inner()).first().cloned()
+    }
+
+    /// Serve `routes` from now on. The relay re-authenticates with the new model list;
+    /// requests already running finish against the port they started on.
+    pub fn replace(&self, routes: Vec<ModelRoute>) {
+        *self.routes.write().unwrap_or_else(|e| e.into_inner()) = routes;
+        self.changed.send_replace(());
+    }
+
+    /// Notified on every `replace`.
+    pub fn subscribe(&self) -> tokio::sync::watch::Receiver<()> {
+        self.changed.subscribe()
+    }
+
+    /// Port of the llama-server serving `model` (the primary one for `None`).
+    pub fn resolve(&self, model: Option<&str>) -> Result<u16, String> {
+        let routes = self.routes.read().unwrap_or_else(|e| e.into_inner());
+        match model.filter(|m| !m.is_empty()) {
+            None => routes
+                .first()
+                .map(|r| r.port)
+                .ok_or_else(|| "This node serves no models".to_string()),
+            Some(model) => routes
+                .iter()
+                .find(|r| r.model.eq_ignore_ascii_case(model))
+                .map(|r| r.port)
+                .ok_or_else(|| {
+                    let served: Vec<&str> = routes.iter().map(|r| r.model.as_str()).collect();
+                    format!(
+                        "Model {} is not served by this node (serving: {})",
+                        model,
+                        served.join(", ")
+                    )
+                }),
+        }
+    }
+}
+
+/// Optional collaborators shared with the relay client.
+#[derive(Clone, Default)]
+pub struct RelayContext {
+    /// Every finished request is appended here.
+    pub ledger: Option<std::sync::Arc<Ledger>>,
+    /// Incoming requests are rejected while paused; the connection is dropped while deregistered.
+    pub availability: Option<std::sync::Arc<Availability>>,
+    /// Which request type this node accepts; the other is rejected.
+    pub serve_mode: ServeMode,
+    /// Completed requests are recorded here for batched proof submission.
+    pub proofs: Option<std::sync::Arc<ProofBatcher>>,
+    /// Every finished request is also audited here, if enabled.
+    pub audit: Option<std::sync::Arc<AuditLog>>,
+    /// Latency histograms of completed requests.
+    pub stats: Option<SharedLatencyStats>,
+    /// Set while authenticated with the relay.
+    pub connected: Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
+    /// Completed requests are reported to the Oracle one by one (`usage_reporting`).
+    pub usage: Option<std::sync::Arc<UsageReporter>>,
+    /// llama-server slot snapshot; requests are refused once every slot is busy
+    /// and as many again are already queued.
+    pub slots: Option<SharedSlots>,
 }
 
 /// Start the WebSocket relay client as a background task.
 /// Connects to the inference API, authenticates, and proxies inference requests
-/// to the local llama-server.
+/// to the local llama-server. Reconnects present the session token from the last
+/// `auth_ok` so requests in flight across the drop are replayed or failed explicitly.
 pub fn start_relay(
     ws_url: String,
     signing_key: SigningKey,
-    model: String,
-    llama_port: u16,
+    routes: ModelRoutes,
+    ctx: RelayContext,
 ) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
-        let client = reqwest::Client::new();
+        let client = crate::http::local();
         let mut backoff = 1u64;
+        // Outlives individual connections so in-flight requests can be resumed
+        let link = RelayLink::default();
+        let mut session_token: Option<String> = None;
 
         loop {
+            if let Some(availability) = &ctx.availability {
+                if availability.is_deregistered() {
+                    log::info!("Relay disconnected while agent is deregistered");
+                    availability.wait_until_registered().await;
+                    backoff = 1;
+                }
+            }
+
             log::info!("Connecting to relay: {}", ws_url);
 
-            match run_relay(&ws_url, &signing_key, &model, llama_port, &client).await {
+            let result = run_relay(
+                &ws_url,
+                &signing_key,
+                &routes,
+                &client,
+                &ctx,
+                &link,
+                &mut session_token,
+            )
+            .await;
+            link.disconnect().await;
+            if let Some(flag) = &ctx.connected {
+                flag.store(false, std::sync::atomic::Ordering::Relaxed);
+            }
+            match result {
                 Ok(()) => {
                     log::info!("Relay connection closed normally");
                     backoff = 1;
@@ -65,43 +295,73 @@ pub fn start_relay(
     })
 }
 
-async fn run_relay(
-    ws_url: &str,
-    signing_key: &SigningKey,
-    model: &str,
-    llama_port: u16,
-    http_client: &reqwest::Client,
-) -> Result<(), String> {
-    // Connect
-    let (ws_stream, _) = connect_async(ws_url)
-        .await
-        .map_err(|e| format!("WebSocket connect failed: {}", e))?;
-
-    let (mut write, mut read) = ws_stream.split();
-
-    // Authenticate
+/// Signed `auth` message opening a relay session (without resume fields).
+/// `models[0]` goes in `model`; a node serving several also signs the full `models` list.
+pub fn auth_message(signing_key: &SigningKey, models: &[String]) -> Result<serde_json::Value, String> {
     let address = address_from_key(signing_key);
     let timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .map_err(|e| format!("Time error: {}", e))?
         .as_secs();
+    let model = models.first().ok_or("No model to authenticate with")?;
 
-    let sign_message = serde_json::json!({
+    let mut sign_message = serde_json::json!({
         "address": address,
         "model": model,
         "timestamp": timestamp,
-    })
-    .to_string();
+    });
+    if models.len() > 1 {
+        sign_message["models"] = models.into();
+    }
 
-    let signature = personal_sign(&sign_message, signing_key)?;
+    let signature = personal_sign(&sign_message.to_string(), signing_key)?;
 
-    let auth_msg = serde_json::json!({
+    let mut auth = serde_json::json!({
         "type": "auth",
         "address": address,
         "model": model,
         "timestamp": timestamp,
         "signature": signature,
     });
+    if models.len() > 1 {
+        auth["models"] = models.into();
+    }
+    Ok(auth)
+}
+
+#[tracing::instrument(name = "relay.session", skip_all, fields(url = %ws_url, models = %routes.models().join(",")))]
+async fn run_relay(
+    ws_url: &str,
+    signing_key: &SigningKey,
+    routes: &ModelRoutes,
+    http_client: &reqwest::Client,
+    ctx: &RelayContext,
+    link: &RelayLink,
+    session_token: &mut Option<String>,
+) -> Result<(), String> {
+    // Connect
+    // The relay shares the Oracle host's pins, checked in the handshake
+    let tls = crate::tls_pin::client_config(&[])?;
+    let (ws_stream, _) = connect_async_tls_with_config(
+        ws_url,
+        None,
+        false,
+        Some(Connector::Rustls(std::sync::Arc::new(tls))),
+    )
+    .await
+    .map_err(|e| format!("WebSocket connect failed: {}", e))?;
+
+    let (mut write, mut read) = ws_stream.split();
+
+    // Authenticate
+    let mut routes_changed = routes.subscribe();
+    let address = address_from_key(signing_key);
+    let mut auth_msg = auth_message(signing_key, &routes.models())?;
+    // Ask to resume the previous session, listing the requests still in flight here
+    if let Some(token) = session_token.as_deref() {
+        auth_msg["sessionToken"] = token.into();
+        auth_msg["inFlight"] = link.in_flight_ids().into();
+    }
 
     write
         .send(Message::Text(auth_msg.to_string()))
@@ -127,25 +387,61 @@ async fn run_relay(
         return Err(format!("Auth rejected: {}", msg));
     }
 
-    log::info!("Relay authenticated as {}", address);
+    let resumed = session_token.is_some() && auth_resp["resumed"].as_bool() == Some(true);
+    *session_token = auth_resp["sessionToken"].as_str().map(str::to_string);
+    link.connect(write).await;
+    if let Some(flag) = &ctx.connected {
+        flag.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+    if resumed {
+        let pending: Vec<String> = auth_resp["pending"]
+            .as_array()
+            .map(|ids| ids.iter().filter_map(|id| id.as_str().map(str::to_string)).collect())
+            .unwrap_or_default();
+        log::info!("Relay session resumed as {}", address);
+        link.resume(&pending).await;
+    } else {
+        let dropped = link.reset();
+        if dropped > 0 {
+            log::warn!("Relay session not resumed; {} undelivered response(s) dropped", dropped);
+        }
+        log::info!("Relay authenticated as {}", address);
+    }
 
     // Spawn ping task
-    let ping_write = std::sync::Arc::new(tokio::sync::Mutex::new(write));
-    let ping_writer = ping_write.clone();
+    let ping_link = link.clone();
     let ping_task = tokio::spawn(async move {
         let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
         loop {
             interval.tick().await;
             let msg = serde_json::json!({"type": "ping"}).to_string();
-            let mut w = ping_writer.lock().await;
-            if w.send(Message::Text(msg)).await.is_err() {
+            if !ping_link.send(msg).await {
                 break;
             }
         }
     });
 
+    let metrics = MetricsSampler::default();
+
     // Message loop
-    while let Some(msg_result) = read.next().await {
+    loop {
+        let msg_result = tokio::select! {
+            next = read.next() => match next {
+                Some(m) => m,
+                None => break,
+            },
+            _ = deregister_requested(ctx) => {
+                log::info!("Agent deregistered, closing relay connection");
+                link.send_message(Message::Close(None)).await;
+                break;
+            }
+            Ok(()) = routes_changed.changed() => {
+                // The reconnect resumes the session, so in-flight requests survive
+                log::info!("Served models changed, re-authenticating with the relay");
+                link.send_message(Message::Close(None)).await;
+                break;
+            }
+        };
         let msg = match msg_result {
             Ok(m) => m,
             Err(e) => {
@@ -166,32 +462,176 @@ async fn run_relay(
 
                 match server_msg.r#type.as_str() {
                     "request" => {
+                        if deduplicate(link, &server_msg.id).await {
+                            continue;
+                        }
+                        if let Some((code, reason)) = paused(ctx) {
+                            send_error(link, &server_msg.id, code, &format!("Agent paused: {}", reason)).await;
+                            continue;
+                        }
+                        if let Some(reason) = saturated(ctx, link) {
+                            send_error(link, &server_msg.id, RelayErrorCode::Overloaded, &reason).await;
+                            continue;
+                        }
+                        if ctx.serve_mode != ServeMode::Chat {
+                            let msg = format!("This node only serves {} requests", ctx.serve_mode.capability());
+                            send_error(link, &server_msg.id, RelayErrorCode::BadRequest, &msg).await;
+                            continue;
+                        }
+                        let port = match routes.resolve(server_msg.model.as_deref()) {
+                            Ok(port) => port,
+                            Err(msg) => {
+                                send_error(link, &server_msg.id, RelayErrorCode::BadRequest, &msg).await;
+                                continue;
+                            }
+                        };
+
                         let req_id = server_msg.id.clone();
-                        let writer = ping_write.clone();
+                        let link = link.clone();
+                        link.track(&req_id);
                         let client = http_client.clone();
-                        let port = llama_port;
+                        let metrics = metrics.clone();
                         let messages = server_msg.messages;
-                        let max_tokens = server_msg.max_tokens;
-                        let temperature = server_msg.temperature;
-                        let top_p = server_msg.top_p;
+                        let sampling = Sampling {
+                            max_tokens: server_msg.max_tokens,
+                            temperature: server_msg.temperature,
+                            top_p: server_msg.top_p,
+                        };
                         let stream = server_msg.stream;
+                        let ledger = ctx.ledger.clone();
+                        let proofs = ctx.proofs.clone();
+                        let audit = ctx.audit.clone();
+                        let stats = ctx.stats.clone();
+                        let usage = ctx.usage.clone();
+
+                        let span = tracing::info_span!(
+                            "relay.request",
+                            request_id = %req_id,
+                            stream,
+                            queue_ms = tracing::field::Empty,
+                            prompt_eval_ms = tracing::field::Empty,
+                            generation_ms = tracing::field::Empty,
+                            ws_send_ms = tracing::field::Empty,
+                            completion_tokens = tracing::field::Empty,
+                        );
 
                         // Process request in background to not block message loop
                         tokio::spawn(async move {
-                            if stream {
-                                handle_stream_request(
-                                    &client, port, &req_id, &messages, max_tokens,
-                                    temperature, top_p, &writer,
-                                )
-                                .await;
+                            let started_at = unix_millis();
+                            let result = if stream {
+                                handle_stream_request(&client, port, &req_id, &messages, sampling, &link).await
                             } else {
-                                handle_request(
-                                    &client, port, &req_id, &messages, max_tokens,
-                                    temperature, top_p, &writer,
-                                )
-                                .await;
+                                handle_request(&client, port, &req_id, &messages, sampling, &link, &metrics).await
+                            };
+
+                            if let Some(stats) = stats {
+                                record_latency(&stats, started_at, &result);
+                            }
+                            let input = serde_json::to_string(&messages).unwrap_or_default();
+                            if let Some(proofs) = proofs.filter(|_| result.outcome == RequestOutcome::Completed) {
+                                proofs.record(&input, &result.output, result.completion_tokens);
+                            }
+                            if let Some(audit) = audit {
+                                record_in_audit(&audit, &req_id, "chat", started_at, stream, &input, &result);
+                            }
+                            if let Some(usage) = usage {
+                                record_usage(&usage, &req_id, started_at, &result);
                             }
-                        });
+                            if let Some(ledger) = ledger {
+                                record_in_ledger(&ledger, &req_id, started_at, stream, result);
+                            }
+                        }.instrument(span));
+                    }
+                    kind @ ("embedding" | "rerank") => {
+                        if deduplicate(link, &server_msg.id).await {
+                            continue;
+                        }
+                        if let Some((code, reason)) = paused(ctx) {
+                            send_error(link, &server_msg.id, code, &format!("Agent paused: {}", reason)).await;
+                            continue;
+                        }
+                        if let Some(reason) = saturated(ctx, link) {
+                            send_error(link, &server_msg.id, RelayErrorCode::Overloaded, &reason).await;
+                            continue;
+                        }
+                        let (mode, audit_kind) = if kind == "rerank" {
+                            (ServeMode::Rerank, "rerank")
+                        } else {
+                            (ServeMode::Embedding, "embedding")
+                        };
+                        if ctx.serve_mode != mode {
+                            send_error(link, &server_msg.id, RelayErrorCode::BadRequest, &format!("This node does not serve {} requests", kind)).await;
+                            continue;
+                        }
+                        let port = match routes.resolve(server_msg.model.as_deref()) {
+                            Ok(port) => port,
+                            Err(msg) => {
+                                send_error(link, &server_msg.id, RelayErrorCode::BadRequest, &msg).await;
+                                continue;
+                            }
+                        };
+
+                        let (endpoint, body, result_field) = if mode == ServeMode::Rerank {
+                            if server_msg.query.is_empty() || server_msg.documents.is_empty() {
+                                send_error(link, &server_msg.id, RelayErrorCode::BadRequest, "Rerank request needs a query and documents").await;
+                                continue;
+                            }
+                            let mut body = serde_json::json!({
+                                "query": server_msg.query,
+                                "documents": server_msg.documents,
+                            });
+                            if let Some(top_n) = server_msg.top_n {
+                                body["top_n"] = top_n.into();
+                            }
+                            ("/v1/rerank", body, "results")
+                        } else {
+                            if server_msg.input.is_null() {
+                                send_error(link, &server_msg.id, RelayErrorCode::BadRequest, "Embedding request has no input").await;
+                                continue;
+                            }
+                            ("/v1/embeddings", serde_json::json!({ "input": server_msg.input }), "data")
+                        };
+
+                        let req_id = server_msg.id.clone();
+                        let link = link.clone();
+                        link.track(&req_id);
+                        let client = http_client.clone();
+                        let ledger = ctx.ledger.clone();
+                        let proofs = ctx.proofs.clone();
+                        let audit = ctx.audit.clone();
+                        let stats = ctx.stats.clone();
+                        let usage = ctx.usage.clone();
+
+                        let span = tracing::info_span!(
+                            "relay.retrieval",
+                            request_id = %req_id,
+                            kind,
+                            ws_send_ms = tracing::field::Empty,
+                        );
+
+                        tokio::spawn(async move {
+                            let started_at = unix_millis();
+                            let result = handle_retrieval_request(
+                                &client, port, &req_id, endpoint, &body, result_field, &link,
+                            )
+                            .await;
+                            if let Some(stats) = stats {
+                                record_latency(&stats, started_at, &result);
+                            }
+                            let input = body.to_string();
+                            if let Some(proofs) = proofs.filter(|_| result.outcome == RequestOutcome::Completed) {
+                                proofs.record(&input, &result.output, result.prompt_tokens);
+                            }
+                            if let Some(audit) = audit {
+                                record_in_audit(&audit, &req_id, audit_kind, started_at, false, &input, &result);
+                            }
+                            if let Some(usage) = usage {
+                                record_usage(&usage, &req_id, started_at, &result);
+                            }
+                            if let Some(ledger) = ledger {
+                                record_in_ledger(&ledger, &req_id, started_at, false, result);
+                            }
+                        }.instrument(span));
                     }
                     "pong" => {
                         // Heartbeat response, ignore
@@ -206,8 +646,7 @@ async fn run_relay(
                 break;
             }
             Message::Ping(data) => {
-                let mut w = ping_write.lock().await;
-                let _ = w.send(Message::Pong(data)).await;
+                link.send_message(Message::Pong(data)).await;
             }
             _ => {}
         }
@@ -217,27 +656,317 @@ async fn run_relay(
     Ok(())
 }
 
-type WsWriter = std::sync::Arc<
-    tokio::sync::Mutex<
-        futures_util::stream::SplitSink<
-            tokio_tungstenite::WebSocketStream<
-                tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
-            >,
-            Message,
-        >,
-    >,
+fn record_in_ledger(ledger: &Ledger, req_id: &str, started_at: u64, stream: bool, result: RequestResult) {
+    let finished_at = unix_millis();
+    let entry = LedgerEntry {
+        id: req_id.to_string(),
+        started_at,
+        finished_at,
+        prompt_tokens: result.prompt_tokens,
+        completion_tokens: result.completion_tokens,
+        duration_ms: finished_at.saturating_sub(started_at),
+        stream,
+        outcome: result.outcome,
+        error: result.error,
+    };
+    if let Err(e) = ledger.append(&entry) {
+        log::warn!("Failed to record request {} in ledger: {}", req_id, e);
+    }
+}
+
+fn record_usage(usage: &UsageReporter, req_id: &str, started_at: u64, result: &RequestResult) {
+    if result.outcome != RequestOutcome::Completed {
+        return;
+    }
+    let finished_at = unix_millis();
+    usage.record(
+        req_id,
+        result.prompt_tokens,
+        result.completion_tokens,
+        finished_at.saturating_sub(started_at),
+        finished_at,
+    );
+}
+
+fn record_latency(stats: &SharedLatencyStats, started_at: u64, result: &RequestResult) {
+    if result.outcome != RequestOutcome::Completed {
+        return;
+    }
+    if let Ok(mut stats) = stats.lock() {
+        if let Some(ttft) = result.ttft_ms {
+            stats.ttft.record(ttft);
+        }
+        stats.total.record(unix_millis().saturating_sub(started_at) as f64);
+    }
+}
+
+fn record_in_audit(
+    audit: &AuditLog,
+    req_id: &str,
+    kind: &str,
+    started_at: u64,
+    stream: bool,
+    input: &str,
+    result: &RequestResult,
+) {
+    let record = AuditRecord {
+        id: req_id,
+        kind,
+        started_at,
+        stream,
+        prompt_tokens: result.prompt_tokens,
+        completion_tokens: result.completion_tokens,
+        outcome: result.outcome,
+        error: result.error.as_deref(),
+        prompt: input,
+        response: &result.output,
+    };
+    if let Err(e) = audit.record(record) {
+        log::warn!("Failed to audit request {}: {}", req_id, e);
+    }
+}
+
+/// Resolves when the availability gate asks the relay to disconnect.
+async fn deregister_requested(ctx: &RelayContext) {
+    match &ctx.availability {
+        Some(a) => a.wait_for_deregister().await,
+        None => std::future::pending().await,
+    }
+}
+
+type WsSink = futures_util::stream::SplitSink<
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
+    Message,
 >;
 
+/// Where a request stands while the relay may reconnect under it.
+enum InFlight {
+    Running,
+    /// Finished while disconnected; the final message is replayed on resume.
+    Undelivered(String),
+    /// A stream chunk was lost; the request is failed on resume.
+    Interrupted,
+}
+
+/// Responses of recently completed requests, least recently used first.
+#[derive(Default)]
+struct ServedCache {
+    entries: std::collections::VecDeque<(String, std::time::Instant, Vec<String>)>,
+}
+
+impl ServedCache {
+    fn get(&mut self, req_id: &str) -> Option<Vec<String>> {
+        self.entries.retain(|(_, at, _)| at.elapsed() < SERVED_CACHE_TTL);
+        let index = self.entries.iter().position(|(id, _, _)| id == req_id)?;
+        let entry = self.entries.remove(index)?;
+        let messages = entry.2.clone();
+        self.entries.push_back(entry);
+        Some(messages)
+    }
+
+    fn insert(&mut self, req_id: &str, messages: Vec<String>) {
+        self.entries.retain(|(id, _, _)| id != req_id);
+        if self.entries.len() >= SERVED_CACHE_SIZE {
+            self.entries.pop_front();
+        }
+        self.entries
+            .push_back((req_id.to_string(), std::time::Instant::now(), messages));
+    }
+}
+
+/// Outgoing side of the relay, shared by request tasks across reconnects.
+#[derive(Clone, Default)]
+struct RelayLink {
+    sink: std::sync::Arc<tokio::sync::Mutex<Option<WsSink>>>,
+    in_flight: std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, InFlight>>>,
+    served: std::sync::Arc<std::sync::Mutex<ServedCache>>,
+}
+
+impl RelayLink {
+    async fn connect(&self, sink: WsSink) {
+        *self.sink.lock().await = Some(sink);
+    }
+
+    async fn disconnect(&self) {
+        *self.sink.lock().await = None;
+    }
+
+    /// False when there is no connection or the send failed.
+    async fn send_message(&self, message: Message) -> bool {
+        match self.sink.lock().await.as_mut() {
+            Some(sink) => sink.send(message).await.is_ok(),
+            None => false,
+        }
+    }
+
+    async fn send(&self, text: String) -> bool {
+        self.send_message(Message::Text(text)).await
+    }
+
+    fn track(&self, req_id: &str) {
+        if let Ok(mut map) = self.in_flight.lock() {
+            map.insert(req_id.to_string(), InFlight::Running);
+        }
+    }
+
+    fn is_in_flight(&self, req_id: &str) -> bool {
+        self.in_flight
+            .lock()
+            .map(|map| map.contains_key(req_id))
+            .unwrap_or(false)
+    }
+
+    /// Keep what a completed request sent, to answer a redelivery of it.
+    fn remember(&self, req_id: &str, messages: Vec<String>) {
+        if let Ok(mut served) = self.served.lock() {
+            served.insert(req_id, messages);
+        }
+    }
+
+    fn served(&self, req_id: &str) -> Option<Vec<String>> {
+        self.served.lock().ok()?.get(req_id)
+    }
+
+    fn interrupt(&self, req_id: &str) {
+        if let Ok(mut map) = self.in_flight.lock() {
+            if let Some(state) = map.get_mut(req_id) {
+                *state = InFlight::Interrupted;
+            }
+        }
+    }
+
+    fn in_flight_count(&self) -> usize {
+        self.in_flight.lock().map(|map| map.len()).unwrap_or(0)
+    }
+
+    fn in_flight_ids(&self) -> Vec<String> {
+        self.in_flight
+            .lock()
+            .map(|map| map.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Send a request's last message. If the connection is down it is kept for
+    /// replay, unless the request was already failed.
+    async fn send_final(&self, req_id: &str, text: String) -> bool {
+        if self.send(text.clone()).await {
+            if let Ok(mut map) = self.in_flight.lock() {
+                map.remove(req_id);
+            }
+            return true;
+        }
+        if let Ok(mut map) = self.in_flight.lock() {
+            if let Some(state) = map.get_mut(req_id).filter(|s| matches!(s, InFlight::Running)) {
+                *state = InFlight::Undelivered(text);
+            }
+        }
+        false
+    }
+
+    /// After a resumed auth: replay finished requests, fail interrupted ones and
+    /// any `pending` id the server is waiting on that this agent never saw.
+    async fn resume(&self, pending: &[String]) {
+        let (replay, failed): (Vec<_>, Vec<_>) = {
+            let Ok(mut map) = self.in_flight.lock() else {
+                return;
+            };
+            let mut replay = Vec::new();
+            let mut failed: Vec<String> = pending
+                .iter()
+                .filter(|id| !map.contains_key(*id))
+                .cloned()
+                .collect();
+            map.retain(|id, state| match state {
+                InFlight::Running => true,
+                InFlight::Undelivered(text) => {
+                    replay.push((id.clone(), std::mem::take(text)));
+                    false
+                }
+                InFlight::Interrupted => {
+                    failed.push(id.clone());
+                    false
+                }
+            });
+            (replay, failed)
+        };
+
+        if !replay.is_empty() || !failed.is_empty() {
+            log::info!(
+                "Relay resume: replaying {} response(s), failing {} request(s)",
+                replay.len(),
+                failed.len()
+            );
+        }
+        for (id, text) in replay {
+            if !self.send(text.clone()).await {
+                // Lost the connection again; keep it for the next resume
+                if let Ok(mut map) = self.in_flight.lock() {
+                    map.insert(id, InFlight::Undelivered(text));
+                }
+            }
+        }
+        for id in failed {
+            send_error(self, &id, RelayErrorCode::Upstream5xx, "Request interrupted by relay reconnect").await;
+        }
+    }
+
+    /// After a cold re-auth, forget requests that already finished: the new session
+    /// can't take their responses. Returns how many. Running requests stay tracked so
+    /// a redelivery of one is still recognised as a duplicate while its task runs.
+    fn reset(&self) -> usize {
+        self.in_flight
+            .lock()
+            .map(|mut map| {
+                let count = map.len();
+                map.retain(|_, state| matches!(state, InFlight::Running));
+                count - map.len()
+            })
+            .unwrap_or(0)
+    }
+}
+
+/// Result of a proxied request, used for accounting.
+struct RequestResult {
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    outcome: RequestOutcome,
+    error: Option<String>,
+    /// Response payload, hashed into the inference proof.
+    output: String,
+    /// Streams only: time until the first content chunk went out.
+    ttft_ms: Option<f64>,
+}
+
+impl RequestResult {
+    fn failed(error: String) -> Self {
+        Self {
+            output: String::new(),
+            prompt_tokens: 0,
+            completion_tokens: 0,
+            outcome: RequestOutcome::Failed,
+            error: Some(error),
+            ttft_ms: None,
+        }
+    }
+}
+
+/// Generation settings forwarded to llama-server with a chat request.
+#[derive(Debug, Clone, Copy)]
+struct Sampling {
+    max_tokens: u32,
+    temperature: f64,
+    top_p: f64,
+}
+
 async fn handle_request(
     client: &reqwest::Client,
     llama_port: u16,
     req_id: &str,
     messages: &[ChatMessage],
-    max_tokens: u32,
-    temperature: f64,
-    top_p: f64,
-    writer: &WsWriter,
-) {
+    sampling: Sampling,
+    link: &RelayLink,
+    metrics: &MetricsSampler,
+) -> RequestResult {
     let url = format!("http://127.0.0.1:{}/v1/chat/completions", llama_port);
 
     let openai_messages: Vec<serde_json::Value> = messages
@@ -250,52 +979,194 @@ async fn handle_request(
         })
         .collect();
 
+    // Streamed from llama-server even though the upstream gets one response, so
+    // `progress` messages can report how far along the request is
     let body = serde_json::json!({
         "messages": openai_messages,
-        "max_tokens": max_tokens,
-        "temperature": temperature,
-        "top_p": top_p,
-        "stream": false,
+        "max_tokens": sampling.max_tokens,
+        "temperature": sampling.temperature,
+        "top_p": sampling.top_p,
+        "stream": true,
+        "stream_options": { "include_usage": true },
+        "return_progress": true,
     });
 
-    match client
-        .post(&url)
+    let started = std::time::Instant::now();
+
+    let resp = match with_api_key(client.post(&url))
         .json(&body)
         .timeout(std::time::Duration::from_secs(120))
         .send()
         .await
     {
-        Ok(resp) => {
-            if resp.status().is_success() {
-                match resp.json::<serde_json::Value>().await {
-                    Ok(data) => {
-                        let response = serde_json::json!({
-                            "type": "response",
-                            "id": req_id,
-                            "choices": data["choices"],
-                            "usage": data["usage"],
-                        });
-                        let mut w = writer.lock().await;
-                        let _ = w.send(Message::Text(response.to_string())).await;
-                    }
-                    Err(e) => {
-                        send_error(writer, req_id, &format!("Response parse error: {}", e)).await;
-                    }
+        Ok(r) => r,
+        Err(e) => {
+            let msg = format!("llama-server request failed: {}", e);
+            send_error(link, req_id, RelayErrorCode::from_request_error(&e), &msg).await;
+            return RequestResult::failed(msg);
+        }
+    };
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        let msg = format!("llama-server error {}: {}", status, text.chars().take(200).collect::<String>());
+        send_error(link, req_id, RelayErrorCode::from_status(status), &msg).await;
+        return RequestResult::failed(msg);
+    }
+
+    use tokio::io::AsyncBufReadExt;
+    use futures_util::TryStreamExt;
+    let reader = tokio_util::io::StreamReader::new(
+        resp.bytes_stream()
+            .map_err(std::io::Error::other),
+    );
+    let mut lines = tokio::io::BufReader::new(reader).lines();
+    let mut progress = tokio::time::interval(PROGRESS_INTERVAL);
+    // Skip the first immediate tick
+    progress.tick().await;
+
+    let mut content = String::new();
+    let mut reasoning = String::new();
+    let mut finish_reason = serde_json::Value::Null;
+    let mut usage = serde_json::Value::Null;
+    let mut prompt_percent: Option<f64> = None;
+    let mut chunks = 0u64;
+    let mut prompt_tokens = 0u64;
+    let mut completion_tokens = 0u64;
+
+    loop {
+        let line = tokio::select! {
+            line = lines.next_line() => line,
+            _ = progress.tick() => {
+                // Nothing from llama-server yet: still waiting for a slot. llama-server
+                // only reports how many requests wait in total, not this one's place
+                let queue_depth = if prompt_percent.is_none() && chunks == 0 {
+                    metrics
+                        .sample(client, llama_port, PROGRESS_INTERVAL)
+                        .await
+                        .map(|m| m.requests_deferred)
+                } else {
+                    None
+                };
+                let mut msg = serde_json::json!({
+                    "type": "progress",
+                    "id": req_id,
+                    "elapsedMs": started.elapsed().as_millis() as u64,
+                    "tokensGenerated": chunks,
+                });
+                if let Some(depth) = queue_depth {
+                    msg["queueDepth"] = depth.into();
                 }
-            } else {
-                let status = resp.status();
-                let text = resp.text().await.unwrap_or_default();
-                send_error(
-                    writer,
-                    req_id,
-                    &format!("llama-server error {}: {}", status, &text[..text.len().min(200)]),
-                )
-                .await;
+                if let Some(percent) = prompt_percent {
+                    msg["promptEvalPercent"] = percent.into();
+                }
+                // A lost heartbeat doesn't matter; the final response is replayed on resume
+                link.send(msg.to_string()).await;
+                continue;
+            }
+        };
+        let line = match line {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => {
+                let msg = format!("llama-server response interrupted: {}", e);
+                send_error(link, req_id, RelayErrorCode::Upstream5xx, &msg).await;
+                return RequestResult::failed(msg);
+            }
+        };
+        let Some(data) = line.strip_prefix("data: ") else {
+            continue;
+        };
+        if data == "[DONE]" {
+            break;
+        }
+        let Ok(parsed) = serde_json::from_str::<serde_json::Value>(data) else {
+            continue;
+        };
+
+        let prompt_progress = &parsed["prompt_progress"];
+        if let (Some(total), Some(processed)) = (
+            prompt_progress["total"].as_u64(),
+            prompt_progress["processed"].as_u64(),
+        ) {
+            if total > 0 {
+                prompt_percent = Some((processed as f64 / total as f64 * 100.0).min(100.0));
             }
         }
-        Err(e) => {
-            send_error(writer, req_id, &format!("llama-server request failed: {}", e)).await;
+        if parsed["timings"].is_object() {
+            record_timings(&parsed["timings"], started.elapsed().as_secs_f64() * 1000.0);
+            prompt_tokens = parsed["timings"]["prompt_n"].as_u64().unwrap_or(0);
+            completion_tokens = parsed["timings"]["predicted_n"].as_u64().unwrap_or(0);
+        }
+        if parsed["usage"].is_object() {
+            usage = parsed["usage"].clone();
+        }
+        let choice = &parsed["choices"][0];
+        if let Some(text) = choice["delta"]["content"].as_str() {
+            content.push_str(text);
+            chunks += 1;
+        }
+        if let Some(text) = choice["delta"]["reasoning_content"].as_str() {
+            reasoning.push_str(text);
+            chunks += 1;
         }
+        if !choice["finish_reason"].is_null() {
+            finish_reason = choice["finish_reason"].clone();
+        }
+    }
+
+    if let Some(n) = usage["prompt_tokens"].as_u64() {
+        prompt_tokens = n;
+    }
+    if let Some(n) = usage["completion_tokens"].as_u64() {
+        completion_tokens = n;
+    }
+    // Older llama-server builds omit timings and usage; each chunk is roughly one token
+    if completion_tokens == 0 {
+        completion_tokens = chunks;
+    }
+    if usage.is_null() {
+        usage = serde_json::json!({
+            "prompt_tokens": prompt_tokens,
+            "completion_tokens": completion_tokens,
+            "total_tokens": prompt_tokens + completion_tokens,
+        });
+    }
+
+    let mut message = serde_json::json!({
+        "role": "assistant",
+        "content": content,
+    });
+    if !reasoning.is_empty() {
+        message["reasoning_content"] = reasoning.into();
+    }
+    let response = serde_json::json!({
+        "type": "response",
+        "id": req_id,
+        "choices": [{
+            "index": 0,
+            "message": message,
+            "finish_reason": finish_reason,
+        }],
+        "usage": usage,
+    });
+    link.remember(req_id, vec![response.to_string()]);
+    let send_start = std::time::Instant::now();
+    let sent = link.send_final(req_id, response.to_string()).await;
+    tracing::Span::current().record("ws_send_ms", send_start.elapsed().as_secs_f64() * 1000.0);
+
+    RequestResult {
+        output: content,
+        prompt_tokens,
+        completion_tokens,
+        outcome: if sent {
+            RequestOutcome::Completed
+        } else {
+            RequestOutcome::Cancelled
+        },
+        error: None,
+        ttft_ms: None,
     }
 }
 
@@ -304,11 +1175,9 @@ async fn handle_stream_request(
     llama_port: u16,
     req_id: &str,
     messages: &[ChatMessage],
-    max_tokens: u32,
-    temperature: f64,
-    top_p: f64,
-    writer: &WsWriter,
-) {
+    sampling: Sampling,
+    link: &RelayLink,
+) -> RequestResult {
     let url = format!("http://127.0.0.1:{}/v1/chat/completions", llama_port);
 
     let openai_messages: Vec<serde_json::Value> = messages
@@ -323,14 +1192,15 @@ async fn handle_stream_request(
 
     let body = serde_json::json!({
         "messages": openai_messages,
-        "max_tokens": max_tokens,
-        "temperature": temperature,
-        "top_p": top_p,
+        "max_tokens": sampling.max_tokens,
+        "temperature": sampling.temperature,
+        "top_p": sampling.top_p,
         "stream": true,
     });
 
-    let resp = match client
-        .post(&url)
+    let started = std::time::Instant::now();
+
+    let resp = match with_api_key(client.post(&url))
         .json(&body)
         .timeout(std::time::Duration::from_secs(120))
         .send()
@@ -338,21 +1208,18 @@ async fn handle_stream_request(
     {
         Ok(r) => r,
         Err(e) => {
-            send_error(writer, req_id, &format!("llama-server stream failed: {}", e)).await;
-            return;
+            let msg = format!("llama-server stream failed: {}", e);
+            send_error(link, req_id, RelayErrorCode::from_request_error(&e), &msg).await;
+            return RequestResult::failed(msg);
         }
     };
 
     if !resp.status().is_success() {
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
-        send_error(
-            writer,
-            req_id,
-            &format!("llama-server stream error {}: {}", status, &text[..text.len().min(200)]),
-        )
-        .await;
-        return;
+        let msg = format!("llama-server stream error {}: {}", status, text.chars().take(200).collect::<String>());
+        send_error(link, req_id, RelayErrorCode::from_status(status), &msg).await;
+        return RequestResult::failed(msg);
     }
 
     // Parse SSE stream
@@ -360,9 +1227,20 @@ async fn handle_stream_request(
     let stream = resp.bytes_stream();
     use futures_util::TryStreamExt;
     let reader = tokio_util::io::StreamReader::new(
-        stream.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)),
+        stream.map_err(std::io::Error::other),
     );
     let mut lines = tokio::io::BufReader::new(reader).lines();
+    let mut ws_send_ms = 0.0f64;
+    let mut chunks_sent = 0u64;
+    let mut ttft_ms = None;
+    let mut prompt_tokens = 0u64;
+    let mut completion_tokens = 0u64;
+    let mut output = String::new();
+    // `{"type":"chunk","id":"<req_id>","content":"` — the escaped content and `"}` follow
+    let frame_prefix = format!(
+        "{{\"type\":\"chunk\",\"id\":{},\"content\":\"",
+        serde_json::Value::from(req_id)
+    );
 
     while let Ok(Some(line)) = lines.next_line().await {
         if !line.starts_with("data: ") {
@@ -373,42 +1251,257 @@ async fn handle_stream_request(
             break;
         }
 
-        match serde_json::from_str::<serde_json::Value>(data) {
-            Ok(parsed) => {
-                if let Some(content) = parsed["choices"][0]["delta"]["content"].as_str() {
-                    if !content.is_empty() {
-                        let chunk = serde_json::json!({
-                            "type": "chunk",
-                            "id": req_id,
-                            "content": content,
-                        });
-                        let mut w = writer.lock().await;
-                        if w.send(Message::Text(chunk.to_string())).await.is_err() {
-                            return;
-                        }
+        // Token chunks forward their content still JSON-escaped; only the rest
+        // (role header, final chunk with timings, anything the scan can't decode) is parsed
+        let fast = raw_delta_content(data)
+            .and_then(|raw| unescape_json_str(raw).map(|content| (raw, content)));
+        let chunk = match fast {
+            Some((raw, content)) => (!content.is_empty())
+                .then(|| (format!("{}{}\"}}", frame_prefix, raw), content)),
+            None => match serde_json::from_str::<serde_json::Value>(data) {
+                Ok(parsed) => {
+                    // llama-server attaches timings to the final chunk
+                    if parsed["timings"].is_object() {
+                        record_timings(
+                            &parsed["timings"],
+                            started.elapsed().as_secs_f64() * 1000.0,
+                        );
+                        prompt_tokens = parsed["timings"]["prompt_n"].as_u64().unwrap_or(0);
+                        completion_tokens = parsed["timings"]["predicted_n"].as_u64().unwrap_or(0);
                     }
+                    parsed["choices"][0]["delta"]["content"]
+                        .as_str()
+                        .filter(|content| !content.is_empty())
+                        .map(|content| {
+                            let frame = serde_json::json!({
+                                "type": "chunk",
+                                "id": req_id,
+                                "content": content,
+                            });
+                            (frame.to_string(), std::borrow::Cow::Owned(content.to_string()))
+                        })
                 }
-            }
-            Err(_) => continue,
+                Err(_) => continue,
+            },
+        };
+        let Some((frame, content)) = chunk else {
+            continue;
+        };
+
+        output.push_str(&content);
+        let send_start = std::time::Instant::now();
+        if !link.send(frame).await {
+            // The upstream missed this chunk; fail the request on resume
+            link.interrupt(req_id);
+            return RequestResult {
+                output,
+                prompt_tokens,
+                completion_tokens: chunks_sent,
+                outcome: RequestOutcome::Cancelled,
+                error: Some("Relay connection lost mid-stream".to_string()),
+                ttft_ms,
+            };
+        }
+        ws_send_ms += send_start.elapsed().as_secs_f64() * 1000.0;
+        chunks_sent += 1;
+        if ttft_ms.is_none() {
+            ttft_ms = Some(started.elapsed().as_secs_f64() * 1000.0);
         }
     }
 
+    tracing::Span::current().record("ws_send_ms", ws_send_ms);
+
     // Send done
     let done = serde_json::json!({
         "type": "done",
         "id": req_id,
     });
-    let mut w = writer.lock().await;
-    let _ = w.send(Message::Text(done.to_string())).await;
+    // A redelivery gets the whole text as one chunk
+    let replay_chunk = serde_json::json!({
+        "type": "chunk",
+        "id": req_id,
+        "content": output,
+    });
+    link.remember(req_id, vec![replay_chunk.to_string(), done.to_string()]);
+    let sent = link.send_final(req_id, done.to_string()).await;
+
+    RequestResult {
+        output,
+        prompt_tokens,
+        // Older llama-server builds omit timings; each chunk is roughly one token
+        completion_tokens: if completion_tokens > 0 { completion_tokens } else { chunks_sent },
+        outcome: if sent {
+            RequestOutcome::Completed
+        } else {
+            RequestOutcome::Cancelled
+        },
+        error: None,
+        ttft_ms,
+    }
+}
+
+/// `choices[0].delta.content` of a llama-server token chunk, still JSON-escaped,
+/// found by scanning rather than parsing. None for any other shape (role header,
+/// the final chunk with timings, null content); the caller parses those fully.
+fn raw_delta_content(data: &str) -> Option<&str> {
+    const KEY: &str = "\"delta\":{\"content\":\"";
+    if data.contains("\"timings\"") {
+        return None;
+    }
+    let start = data.find(KEY)? + KEY.len();
+    let rest = &data[start..];
+    let bytes = rest.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return Some(&rest[..i]),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Decode the body of a JSON string literal. Borrowed when it has no escapes.
+fn unescape_json_str(raw: &str) -> Option<std::borrow::Cow<'_, str>> {
+    if !raw.contains('\\') {
+        return Some(std::borrow::Cow::Borrowed(raw));
+    }
+    serde_json::from_str::<String>(&format!("\"{}\"", raw))
+        .ok()
+        .map(std::borrow::Cow::Owned)
+}
+
+/// Record llama-server `timings` on the current request span. Queue time is whatever
+/// part of the wall-clock round trip llama-server did not spend on prompt eval or generation.
+fn record_timings(timings: &serde_json::Value, total_ms: f64) {
+    let span = tracing::Span::current();
+    let prompt_ms = timings["prompt_ms"].as_f64().unwrap_or(0.0);
+    let predicted_ms = timings["predicted_ms"].as_f64().unwrap_or(0.0);
+    span.record("prompt_eval_ms", prompt_ms);
+    span.record("generation_ms", predicted_ms);
+    span.record("queue_ms", (total_ms - prompt_ms - predicted_ms).max(0.0));
+    if let Some(n) = timings["predicted_n"].as_u64() {
+        span.record("completion_tokens", n);
+    }
+}
+
+/// Proxy an embedding (`/v1/embeddings`) or rerank (`/v1/rerank`) request to
+/// llama-server and relay `result_field` of its response back.
+async fn handle_retrieval_request(
+    client: &reqwest::Client,
+    llama_port: u16,
+    req_id: &str,
+    endpoint: &str,
+    body: &serde_json::Value,
+    result_field: &str,
+    link: &RelayLink,
+) -> RequestResult {
+    let url = format!("http://127.0.0.1:{}{}", llama_port, endpoint);
+
+    match with_api_key(client.post(&url))
+        .json(body)
+        .timeout(std::time::Duration::from_secs(60))
+        .send()
+        .await
+    {
+        Ok(resp) if resp.status().is_success() => match resp.json::<serde_json::Value>().await {
+            Ok(data) => {
+                let response = serde_json::json!({
+                    "type": "response",
+                    "id": req_id,
+                    result_field: data[result_field],
+                    "usage": data["usage"],
+                });
+                link.remember(req_id, vec![response.to_string()]);
+                let send_start = std::time::Instant::now();
+                let sent = link.send_final(req_id, response.to_string()).await;
+                tracing::Span::current().record(
+                    "ws_send_ms",
+                    send_start.elapsed().as_secs_f64() * 1000.0,
+                );
+                RequestResult {
+                    output: data[result_field].to_string(),
+                    prompt_tokens: data["usage"]["prompt_tokens"].as_u64().unwrap_or(0),
+                    completion_tokens: 0,
+                    outcome: if sent {
+                        RequestOutcome::Completed
+                    } else {
+                        RequestOutcome::Cancelled
+                    },
+                    error: None,
+                    ttft_ms: None,
+                }
+            }
+            Err(e) => {
+                let msg = format!("Response parse error: {}", e);
+                send_error(link, req_id, RelayErrorCode::Upstream5xx, &msg).await;
+                RequestResult::failed(msg)
+            }
+        },
+        Ok(resp) => {
+            let status = resp.status();
+            let text = resp.text().await.unwrap_or_default();
+            let msg = format!("llama-server error {}: {}", status, text.chars().take(200).collect::<String>());
+            send_error(link, req_id, RelayErrorCode::from_status(status), &msg).await;
+            RequestResult::failed(msg)
+        }
+        Err(e) => {
+            let msg = format!("llama-server request failed: {}", e);
+            send_error(link, req_id, RelayErrorCode::from_request_error(&e), &msg).await;
+            RequestResult::failed(msg)
+        }
+    }
+}
+
+/// Pause reason and its error code, if the agent is not taking requests.
+fn paused(ctx: &RelayContext) -> Option<(RelayErrorCode, String)> {
+    let availability = ctx.availability.as_ref()?;
+    let reason = availability.pause_message()?;
+    let code = if availability.is_deregistered() {
+        RelayErrorCode::ShuttingDown
+    } else {
+        RelayErrorCode::Overloaded
+    };
+    Some((code, reason))
+}
+
+/// Refusal reason when every llama-server slot is busy and the queue behind
+/// them is already as long as the slot count.
+fn saturated(ctx: &RelayContext, link: &RelayLink) -> Option<String> {
+    let (busy, total) = ctx.slots.as_ref()?.usage();
+    if total == 0 || busy < total || link.in_flight_count() < total * 2 {
+        return None;
+    }
+    Some(format!("All {} slots busy", total))
+}
+
+/// Handle a request id seen before: resend the cached response of a completed one
+/// and ignore one still running. True when the request needs no further work.
+async fn deduplicate(link: &RelayLink, req_id: &str) -> bool {
+    if link.is_in_flight(req_id) {
+        log::info!("Request {} redelivered while still running, ignoring", req_id);
+        return true;
+    }
+    let Some(messages) = link.served(req_id) else {
+        return false;
+    };
+    log::info!("Request {} redelivered, resending its response", req_id);
+    for message in messages {
+        if !link.send(message).await {
+            break;
+        }
+    }
+    true
 }
 
-async fn send_error(writer: &WsWriter, req_id: &str, message: &str) {
-    log::error!("Relay request {} error: {}", req_id, message);
+async fn send_error(link: &RelayLink, req_id: &str, code: RelayErrorCode, message: &str) {
+    log::error!("Relay request {} error ({}): {}", req_id, code.as_str(), message);
     let error = serde_json::json!({
         "type": "error",
         "id": req_id,
+        "code": code,
         "message": message,
     });
-    let mut w = writer.lock().await;
-    let _ = w.send(Message::Text(error.to_string())).await;
+    link.send_final(req_id, error.to_string()).await;
 }
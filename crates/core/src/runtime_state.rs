@@ -0,0 +1,107 @@
+//! Live state of a running CLI agent, mirrored to `<config_dir>/agent-state.json`
+//! so `plumise-agent status` (a separate process) can report it.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::availability::AvailabilitySnapshot;
+
+const STATE_FILE_NAME: &str = "agent-state.json";
+const MODE_REQUEST_FILE_NAME: &str = "node-mode-request.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AgentPhase {
+    /// llama-server is loading the model.
+    #[default]
+    Starting,
+    /// Registered (or trying to) and serving relay traffic.
+    Serving,
+    /// Outside serving hours, waiting for the next window.
+    Scheduled,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeState {
+    pub pid: u32,
+    pub phase: AgentPhase,
+    pub model: String,
+    pub http_port: u16,
+    /// "standalone" | "rpc-server" | "coordinator", as assigned by the Oracle.
+    pub node_mode: String,
+    pub cluster_id: Option<String>,
+    pub registered: bool,
+    pub registration_error: Option<String>,
+    /// Unix epoch seconds.
+    pub last_registered_at: Option<u64>,
+    pub availability: AvailabilitySnapshot,
+    /// Authenticated with the inference relay.
+    #[serde(default)]
+    pub relay_connected: bool,
+    /// Key the running llama-server requires, so `status` can read its metrics.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub llama_api_key: Option<String>,
+    /// The Oracle's `minSupportedVersion` is newer than this agent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update_required: Option<crate::oracle::registry::UpdateRequired>,
+    /// Latest penalty status from the Oracle; None until the first poll answers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub penalty: Option<crate::oracle::penalty::PenaltyStatus>,
+    pub updated_at: u64,
+}
+
+impl RuntimeState {
+    pub fn load(dir: &Path) -> Option<Self> {
+        let contents = std::fs::read_to_string(dir.join(STATE_FILE_NAME)).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    /// Stamp `updated_at` and write the state file.
+    pub fn save(&mut self, dir: &Path) -> Result<(), String> {
+        self.updated_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize agent state: {}", e))?;
+        std::fs::write(dir.join(STATE_FILE_NAME), json)
+            .map_err(|e| format!("Failed to write agent state: {}", e))
+    }
+
+    pub fn clear(dir: &Path) {
+        let _ = std::fs::remove_file(dir.join(STATE_FILE_NAME));
+        let _ = std::fs::remove_file(dir.join(MODE_REQUEST_FILE_NAME));
+    }
+}
+
+/// Node mode requested with `plumise-agent mode`, picked up by the running agent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeModeRequest {
+    /// "standalone" | "rpc-server" | "coordinator", or "auto" to follow the config again.
+    pub mode: String,
+    /// Coordinator only: rpc-server peers as "host:port".
+    #[serde(default)]
+    pub peers: Vec<String>,
+}
+
+impl NodeModeRequest {
+    pub fn save(&self, dir: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize mode request: {}", e))?;
+        std::fs::write(dir.join(MODE_REQUEST_FILE_NAME), json)
+            .map_err(|e| format!("Failed to write mode request: {}", e))
+    }
+
+    /// Read and remove a pending request.
+    pub fn take(dir: &Path) -> Option<Self> {
+        let path = dir.join(MODE_REQUEST_FILE_NAME);
+        let contents = std::fs::read_to_string(&path).ok()?;
+        let _ = std::fs::remove_file(&path);
+        serde_json::from_str(&contents)
+            .map_err(|e| log::warn!("Ignoring malformed mode request: {}", e))
+            .ok()
+    }
+}
@@ -0,0 +1,204 @@
+//! Serving hours: only run the agent inside configured local-time windows.
+//!
+//! A window is written as `[days] HH:MM-HH:MM`, e.g. `mon-fri 22:00-08:00`,
+//! `sat,sun 00:00-24:00` or just `09:00-17:00` (every day). A window whose end
+//! is before its start runs past midnight and belongs to the day it starts on.
+
+use chrono::{Datelike, Local, NaiveDateTime, TimeZone, Timelike};
+use serde::Serialize;
+
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+const MINUTES_PER_DAY: u32 = 24 * 60;
+/// How far ahead to look for the next transition (a bit over one week).
+const LOOKAHEAD_MINUTES: i64 = 8 * MINUTES_PER_DAY as i64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServingWindow {
+    /// Indexed by `Weekday::num_days_from_monday()`.
+    days: [bool; 7],
+    start_min: u32,
+    end_min: u32,
+}
+
+impl ServingWindow {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        let (days_part, time_part) = match spec.rsplit_once(char::is_whitespace) {
+            Some((d, t)) => (Some(d.trim()), t),
+            None => (None, spec),
+        };
+
+        let days = match days_part {
+            None => [true; 7],
+            Some(d) => parse_days(d)?,
+        };
+
+        let (start, end) = time_part
+            .split_once('-')
+            .ok_or_else(|| format!("Invalid serving window '{}': expected HH:MM-HH:MM", spec))?;
+        let start_min = parse_time(start)?;
+        let end_min = parse_time(end)?;
+        if start_min >= MINUTES_PER_DAY {
+            return Err(format!("Invalid serving window '{}': start must be before 24:00", spec));
+        }
+        if start_min == end_min {
+            return Err(format!("Invalid serving window '{}': start and end are equal", spec));
+        }
+
+        Ok(Self {
+            days,
+            start_min,
+            end_min,
+        })
+    }
+
+    fn contains(&self, at: NaiveDateTime) -> bool {
+        let day = at.weekday().num_days_from_monday() as usize;
+        let minute = at.hour() * 60 + at.minute();
+        if self.start_min < self.end_min {
+            self.days[day] && minute >= self.start_min && minute < self.end_min
+        } else {
+            // Crosses midnight: evening part today, morning part belongs to yesterday
+            let yesterday = (day + 6) % 7;
+            (self.days[day] && minute >= self.start_min)
+                || (self.days[yesterday] && minute < self.end_min)
+        }
+    }
+}
+
+fn parse_days(spec: &str) -> Result<[bool; 7], String> {
+    let mut days = [false; 7];
+    let spec = spec.to_ascii_lowercase();
+    if spec == "daily" || spec == "*" {
+        return Ok([true; 7]);
+    }
+    for part in spec.split(',') {
+        let part = part.trim();
+        match part.split_once('-') {
+            Some((from, to)) => {
+                let (from, to) = (day_index(from)?, day_index(to)?);
+                let mut d = from;
+                loop {
+                    days[d] = true;
+                    if d == to {
+                        break;
+                    }
+                    d = (d + 1) % 7;
+                }
+            }
+            None => days[day_index(part)?] = true,
+        }
+    }
+    Ok(days)
+}
+
+fn day_index(name: &str) -> Result<usize, String> {
+    let name = name.trim();
+    DAY_NAMES
+        .iter()
+        .position(|d| name.len() >= 3 && name.starts_with(d))
+        .ok_or_else(|| format!("Unknown weekday '{}'", name))
+}
+
+fn parse_time(s: &str) -> Result<u32, String> {
+    let (h, m) = s
+        .trim()
+        .split_once(':')
+        .ok_or_else(|| format!("Invalid time '{}': expected HH:MM", s.trim()))?;
+    let h: u32 = h.parse().map_err(|_| format!("Invalid hour in '{}'", s.trim()))?;
+    let m: u32 = m.parse().map_err(|_| format!("Invalid minute in '{}'", s.trim()))?;
+    if m >= 60 || h > 24 || (h == 24 && m != 0) {
+        return Err(format!("Invalid time '{}'", s.trim()));
+    }
+    Ok(h * 60 + m)
+}
+
+/// A set of serving windows. An empty schedule means "always serve".
+#[derive(Debug, Clone, Default)]
+pub struct Schedule {
+    windows: Vec<ServingWindow>,
+    specs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleStatus {
+    /// False when no serving windows are configured.
+    pub enabled: bool,
+    /// Whether the current local time is inside a serving window.
+    pub active: bool,
+    /// Unix epoch seconds of the next start (if inactive) or stop (if active).
+    pub next_change: Option<i64>,
+    pub windows: Vec<String>,
+}
+
+impl Schedule {
+    pub fn parse(specs: &[String]) -> Result<Self, String> {
+        let specs: Vec<String> = specs
+            .iter()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        let windows = specs
+            .iter()
+            .map(|s| ServingWindow::parse(s))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { windows, specs })
+    }
+
+    pub fn is_always(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    pub fn is_active(&self, at: NaiveDateTime) -> bool {
+        self.is_always() || self.windows.iter().any(|w| w.contains(at))
+    }
+
+    pub fn is_active_now(&self) -> bool {
+        self.is_active(Local::now().naive_local())
+    }
+
+    /// First minute after `at` where `is_active` flips, if within the next week.
+    pub fn next_change(&self, at: NaiveDateTime) -> Option<NaiveDateTime> {
+        if self.is_always() {
+            return None;
+        }
+        let current = self.is_active(at);
+        let start = at.with_second(0)?.with_nanosecond(0)?;
+        (1..=LOOKAHEAD_MINUTES)
+            .map(|m| start + chrono::Duration::minutes(m))
+            .find(|t| self.is_active(*t) != current)
+    }
+
+    pub fn status(&self) -> ScheduleStatus {
+        let now = Local::now().naive_local();
+        ScheduleStatus {
+            enabled: !self.is_always(),
+            active: self.is_active(now),
+            next_change: self.next_change(now).and_then(local_to_unix),
+            windows: self.specs.clone(),
+        }
+    }
+
+    /// Time until the next transition, capped at `max` (so callers re-check periodically).
+    pub fn time_until_change(&self, max: std::time::Duration) -> std::time::Duration {
+        let now = Local::now().naive_local();
+        self.next_change(now)
+            .and_then(|t| (t - now).to_std().ok())
+            .map(|d| d.min(max))
+            .unwrap_or(max)
+    }
+}
+
+fn local_to_unix(t: NaiveDateTime) -> Option<i64> {
+    Local.from_local_datetime(&t).earliest().map(|dt| dt.timestamp())
+}
+
+/// Format a unix timestamp as local `YYYY-MM-DD HH:MM` for logs.
+pub fn format_local(unix_secs: i64) -> String {
+    Local
+        .timestamp_opt(unix_secs, 0)
+        .single()
+        .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| unix_secs.to_string())
+}
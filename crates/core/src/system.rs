@@ -1,5 +1,9 @@
+use std::sync::Arc;
+
 use serde::{Deserialize, Serialize};
 
+use crate::availability::{Availability, PauseSource};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SystemInfo {
@@ -9,15 +13,458 @@ pub struct SystemInfo {
     pub vram_total: u64,
     pub vram_used: u64,
     pub gpu_name: String,
+    #[serde(default)]
+    pub gpus: Vec<GpuTelemetry>,
+}
+
+/// Temperature (°C) from which a GPU is treated as thermally throttling
+/// when the driver does not report throttle reasons itself.
+pub const THERMAL_WARN_CELSIUS: f32 = 85.0;
+
+/// Live readings for one GPU. Fields are None when the platform tool doesn't expose them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuTelemetry {
+    pub index: u32,
+    pub name: String,
+    pub utilization_percent: Option<f32>,
+    pub temperature_c: Option<f32>,
+    pub power_watts: Option<f64>,
+    pub sm_clock_mhz: Option<u32>,
+    pub max_sm_clock_mhz: Option<u32>,
+    pub throttling: bool,
+}
+
+/// Instruction-set extensions llama.cpp builds are compiled against.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CpuFeatures {
+    /// `std::env::consts::ARCH`, e.g. "x86_64" or "aarch64".
+    pub arch: String,
+    pub sse42: bool,
+    pub avx: bool,
+    pub avx2: bool,
+    pub fma: bool,
+    pub f16c: bool,
+    pub avx512: bool,
+    pub neon: bool,
+    pub dotprod: bool,
+}
+
+impl CpuFeatures {
+    /// Detected extensions, e.g. "AVX, AVX2, FMA, F16C".
+    pub fn summary(&self) -> String {
+        let names: Vec<&str> = [
+            (self.sse42, "SSE4.2"),
+            (self.avx, "AVX"),
+            (self.avx2, "AVX2"),
+            (self.fma, "FMA"),
+            (self.f16c, "F16C"),
+            (self.avx512, "AVX-512"),
+            (self.neon, "NEON"),
+            (self.dotprod, "DotProd"),
+        ]
+        .into_iter()
+        .filter_map(|(present, name)| present.then_some(name))
+        .collect();
+        if names.is_empty() {
+            format!("{} (no SIMD extensions detected)", self.arch)
+        } else {
+            format!("{}: {}", self.arch, names.join(", "))
+        }
+    }
+
+    /// Why prebuilt llama.cpp binaries may die with an illegal instruction here.
+    /// Only Windows has non-AVX2 release builds.
+    pub fn warning(&self) -> Option<String> {
+        if self.arch != "x86_64" || self.avx2 {
+            return None;
+        }
+        Some(if self.avx {
+            "CPU lacks AVX2: builds other than win-avx/win-noavx may crash with an illegal instruction".into()
+        } else {
+            "CPU lacks AVX: only the win-noavx build of llama.cpp can run here".into()
+        })
+    }
 }
 
-/// Cross-platform GPU detection.
+/// Detect SIMD support of the running CPU (cpuid on x86, feature registers on ARM).
+pub fn cpu_features() -> CpuFeatures {
+    #[allow(unused_mut)]
+    let mut features = CpuFeatures {
+        arch: std::env::consts::ARCH.to_string(),
+        ..Default::default()
+    };
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        features.sse42 = std::is_x86_feature_detected!("sse4.2");
+        features.avx = std::is_x86_feature_detected!("avx");
+        features.avx2 = std::is_x86_feature_detected!("avx2");
+        features.fma = std::is_x86_feature_detected!("fma");
+        features.f16c = std::is_x86_feature_detected!("f16c");
+        features.avx512 = std::is_x86_feature_detected!("avx512f");
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        features.neon = std::arch::is_aarch64_feature_detected!("neon");
+        features.dotprod = std::arch::is_aarch64_feature_detected!("dotprod");
+    }
+
+    features
+}
+
+/// The GPU llama.cpp would use.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuInfo {
+    /// "nvidia" | "amd" | "intel" | "apple" | "unknown"
+    pub vendor: String,
+    pub name: String,
+    /// Dedicated VRAM (unified memory on Apple); 0 when unknown.
+    pub vram_mb: u64,
+}
+
+impl GpuInfo {
+    fn from_name(name: String, vram_mb: u64) -> Self {
+        Self {
+            vendor: gpu_vendor(&name).to_string(),
+            name,
+            vram_mb,
+        }
+    }
+}
+
+/// Vendor from an adapter name.
+pub fn gpu_vendor(name: &str) -> &'static str {
+    let lower = name.to_lowercase();
+    if ["nvidia", "geforce", "rtx", "quadro", "tesla"].iter().any(|k| lower.contains(k)) {
+        "nvidia"
+    } else if ["amd", "radeon", "instinct", "advanced micro devices"].iter().any(|k| lower.contains(k)) {
+        "amd"
+    } else if lower.contains("intel") || lower.contains("arc ") {
+        "intel"
+    } else if lower.contains("apple") || lower.contains("metal") {
+        "apple"
+    } else {
+        "unknown"
+    }
+}
+
+/// Cross-platform GPU detection: (name, VRAM MB).
 pub fn detect_gpu() -> Option<(String, u64)> {
+    detect_gpu_info().map(|gpu| (gpu.name, gpu.vram_mb))
+}
+
+/// Cross-platform GPU detection with the vendor.
+pub fn detect_gpu_info() -> Option<GpuInfo> {
+    // Names end in "(Metal)", so Intel Macs with a Radeon still report "amd"
     #[cfg(target_os = "macos")]
-    return detect_metal_gpu();
+    return detect_metal_gpu().map(|(name, vram)| GpuInfo::from_name(name, vram));
 
-    #[cfg(not(target_os = "macos"))]
-    return detect_nvidia_gpu();
+    #[cfg(windows)]
+    return detect_nvidia_gpu()
+        .or_else(detect_windows_gpu)
+        .map(|(name, vram)| GpuInfo::from_name(name, vram));
+
+    // Drivers may still be installing on a fresh server: fall through to whatever is there
+    #[cfg(target_os = "linux")]
+    return detect_jetson_gpu()
+        .or_else(|| detect_nvidia_gpu().map(|(name, vram)| GpuInfo::from_name(name, vram)))
+        .or_else(detect_nvml_gpu)
+        .or_else(detect_rocm_gpu)
+        .or_else(detect_sysfs_gpu)
+        .or_else(detect_lspci_gpu);
+
+    #[cfg(not(any(target_os = "macos", windows, target_os = "linux")))]
+    return detect_nvidia_gpu().map(|(name, vram)| GpuInfo::from_name(name, vram));
+}
+
+/// Board name of an NVIDIA Jetson (e.g. "NVIDIA Jetson AGX Orin Developer Kit"), if this is one.
+pub fn jetson_model() -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        let model = std::fs::read_to_string("/proc/device-tree/model")
+            .map(|m| m.trim_end_matches('\0').trim().to_string())
+            .unwrap_or_default();
+        if model.to_lowercase().contains("jetson") {
+            return Some(model);
+        }
+        if std::path::Path::new("/etc/nv_tegra_release").exists() {
+            return Some(if model.is_empty() { "NVIDIA Jetson".to_string() } else { model });
+        }
+    }
+    None
+}
+
+/// Jetson GPUs share system RAM; nvidia-smi there reports no memory total.
+#[cfg(target_os = "linux")]
+fn detect_jetson_gpu() -> Option<GpuInfo> {
+    let model = jetson_model()?;
+    Some(GpuInfo {
+        vendor: "nvidia".to_string(),
+        name: format!("{} (unified memory)", model),
+        vram_mb: total_ram_mb(),
+    })
+}
+
+/// NVML straight from the driver library, for when nvidia-smi isn't installed.
+#[cfg(target_os = "linux")]
+fn detect_nvml_gpu() -> Option<GpuInfo> {
+    use std::ffi::{c_char, c_void, CStr};
+
+    #[repr(C)]
+    struct NvmlMemory {
+        total: u64,
+        free: u64,
+        used: u64,
+    }
+    type Init = unsafe extern "C" fn() -> i32;
+    type Shutdown = unsafe extern "C" fn() -> i32;
+    type GetHandle = unsafe extern "C" fn(u32, *mut *mut c_void) -> i32;
+    type GetName = unsafe extern "C" fn(*mut c_void, *mut c_char, u32) -> i32;
+    type GetMemory = unsafe extern "C" fn(*mut c_void, *mut NvmlMemory) -> i32;
+
+    // SAFETY: the library handle is checked for null and closed once, after the last
+    // call into it. Each symbol is non-null and transmuted to the signature NVML's
+    // nvml.h declares for it (handles are opaque pointers, return codes are
+    // nvmlReturn_t, a C int). `name` and `memory` are live, writable buffers whose
+    // sizes are passed along, and NVML NUL-terminates `name` on success before
+    // `CStr::from_ptr` reads it.
+    unsafe {
+        let lib = libc::dlopen(c"libnvidia-ml.so.1".as_ptr(), libc::RTLD_LAZY);
+        if lib.is_null() {
+            return None;
+        }
+        let sym = |name: &CStr| libc::dlsym(lib, name.as_ptr());
+        let (init, shutdown, get_handle, get_name, get_memory) = (
+            sym(c"nvmlInit_v2"),
+            sym(c"nvmlShutdown"),
+            sym(c"nvmlDeviceGetHandleByIndex_v2"),
+            sym(c"nvmlDeviceGetName"),
+            sym(c"nvmlDeviceGetMemoryInfo"),
+        );
+        let mut result = None;
+        if ![init, shutdown, get_handle, get_name, get_memory].iter().any(|s| s.is_null()) {
+            let init: Init = std::mem::transmute(init);
+            let shutdown: Shutdown = std::mem::transmute(shutdown);
+            let get_handle: GetHandle = std::mem::transmute(get_handle);
+            let get_name: GetName = std::mem::transmute(get_name);
+            let get_memory: GetMemory = std::mem::transmute(get_memory);
+
+            if init() == 0 {
+                let mut device = std::ptr::null_mut();
+                if get_handle(0, &mut device) == 0 {
+                    let mut name = [0 as c_char; 96];
+                    let mut memory = NvmlMemory { total: 0, free: 0, used: 0 };
+                    if get_name(device, name.as_mut_ptr(), name.len() as u32) == 0 {
+                        let name = CStr::from_ptr(name.as_ptr()).to_string_lossy().to_string();
+                        let vram_mb = if get_memory(device, &mut memory) == 0 {
+                            memory.total / (1024 * 1024)
+                        } else {
+                            0
+                        };
+                        result = Some(GpuInfo {
+                            vendor: "nvidia".to_string(),
+                            name,
+                            vram_mb,
+                        });
+                    }
+                }
+                shutdown();
+            }
+        }
+        libc::dlclose(lib);
+        result
+    }
+}
+
+/// AMD GPUs through rocm-smi's JSON output.
+#[cfg(target_os = "linux")]
+fn detect_rocm_gpu() -> Option<GpuInfo> {
+    let output = std::process::Command::new("rocm-smi")
+        .args(["--showproductname", "--showmeminfo", "vram", "--json"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_rocm_smi(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// `{"card0": {"Card series": "...", "VRAM Total Memory (B)": "..."}}`; key names vary
+/// between ROCm releases, so match them loosely. Largest card wins.
+#[cfg(target_os = "linux")]
+fn parse_rocm_smi(stdout: &str) -> Option<GpuInfo> {
+    let json: serde_json::Value = serde_json::from_str(stdout).ok()?;
+    json.as_object()?
+        .iter()
+        .filter(|(card, _)| card.starts_with("card"))
+        .filter_map(|(_, fields)| {
+            let fields = fields.as_object()?;
+            let field = |pred: &dyn Fn(&str) -> bool| {
+                fields
+                    .iter()
+                    .find(|(k, _)| pred(&k.to_lowercase()))
+                    .and_then(|(_, v)| v.as_str())
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+            };
+            let name = field(&|k| k.contains("series"))
+                .or_else(|| field(&|k| k.contains("model")))
+                .unwrap_or("AMD GPU");
+            let vram_mb = field(&|k| k.contains("vram total memory"))
+                .and_then(|v| v.parse::<u64>().ok())
+                .map(|bytes| bytes / (1024 * 1024))
+                .unwrap_or(0);
+            Some(GpuInfo {
+                vendor: "amd".to_string(),
+                name: name.to_string(),
+                vram_mb,
+            })
+        })
+        .max_by_key(|gpu| gpu.vram_mb)
+}
+
+/// Display devices under /sys/class/drm: vendor from the PCI id, VRAM from amdgpu's
+/// `mem_info_vram_total`, name from lspci when available. Discrete cards win.
+#[cfg(target_os = "linux")]
+fn detect_sysfs_gpu() -> Option<GpuInfo> {
+    let entries = std::fs::read_dir("/sys/class/drm").ok()?;
+    entries
+        .flatten()
+        .filter(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            name.starts_with("card") && !name.contains('-')
+        })
+        .filter_map(|e| {
+            let device = e.path().join("device");
+            let vendor = match std::fs::read_to_string(device.join("vendor")).ok()?.trim() {
+                "0x10de" => "nvidia",
+                "0x1002" => "amd",
+                "0x8086" => "intel",
+                _ => "unknown",
+            };
+            let vram_mb = std::fs::read_to_string(device.join("mem_info_vram_total"))
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .map(|bytes| bytes / (1024 * 1024))
+                .unwrap_or(0);
+            let slot = std::fs::canonicalize(&device)
+                .ok()
+                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()));
+            let name = slot
+                .as_deref()
+                .and_then(lspci_device_name)
+                .unwrap_or_else(|| format!("{} GPU", vendor.to_uppercase()));
+            Some(GpuInfo {
+                vendor: vendor.to_string(),
+                name,
+                vram_mb,
+            })
+        })
+        .max_by_key(|gpu| (gpu.vendor != "intel", gpu.vram_mb))
+}
+
+/// Device name of the PCI device at `slot` (e.g. "0000:03:00.0").
+#[cfg(target_os = "linux")]
+fn lspci_device_name(slot: &str) -> Option<String> {
+    let output = std::process::Command::new("lspci")
+        .args(["-mm", "-s", slot])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    parse_lspci_mm(stdout.lines().next()?).map(|(_, name)| name)
+}
+
+/// `03:00.0 "VGA compatible controller" "NVIDIA Corporation" "AD102 [GeForce RTX 4090]" ...`
+/// to (class, "vendor device").
+#[cfg(target_os = "linux")]
+fn parse_lspci_mm(line: &str) -> Option<(String, String)> {
+    let fields: Vec<&str> = line.split('"').skip(1).step_by(2).collect();
+    let (class, vendor, device) = (fields.first()?, fields.get(1)?, fields.get(2)?);
+    let vendor = vendor
+        .trim_end_matches(" Corporation")
+        .replace("Advanced Micro Devices, Inc. [AMD/ATI]", "AMD");
+    Some((class.to_string(), format!("{} {}", vendor, device)))
+}
+
+/// Last resort: the PCI display controllers lspci lists. No VRAM.
+#[cfg(target_os = "linux")]
+fn detect_lspci_gpu() -> Option<GpuInfo> {
+    let output = std::process::Command::new("lspci")
+        .arg("-mm")
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(parse_lspci_mm)
+        .filter(|(class, _)| {
+            class.contains("VGA") || class.contains("3D controller") || class.contains("Display controller")
+        })
+        .map(|(_, name)| GpuInfo::from_name(name, 0))
+        .max_by_key(|gpu| gpu.vendor != "intel")
+}
+
+/// Windows fallback for machines without nvidia-smi (AMD, Intel, or NVIDIA without
+/// the tool): every display adapter from WMI `Win32_VideoController`, with dedicated
+/// VRAM from the display class registry key (`AdapterRAM` is capped at 4 GB).
+/// Picks the adapter with the most VRAM.
+#[cfg(windows)]
+fn detect_windows_gpu() -> Option<(String, u64)> {
+    use std::os::windows::process::CommandExt;
+
+    const SCRIPT: &str = r#"$ErrorActionPreference = 'SilentlyContinue'
+$mem = @{}
+Get-ItemProperty 'HKLM:\SYSTEM\CurrentControlSet\Control\Class\{4d36e968-e325-11ce-bfc1-08002be10318}\0*' | ForEach-Object {
+  $q = $_.'HardwareInformation.qwMemorySize'
+  if ($q) { $mem[$_.DriverDesc] = [uint64]$q }
+}
+Get-CimInstance Win32_VideoController | ForEach-Object {
+  $b = $mem[$_.Name]
+  if (-not $b) { $b = [uint64]$_.AdapterRAM }
+  "$($_.Name)|$b"
+}"#;
+
+    let mut cmd = std::process::Command::new("powershell");
+    cmd.args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .creation_flags(0x08000000); // CREATE_NO_WINDOW
+    let output = cmd.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_windows_adapters(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// "name|bytes" lines to (name, VRAM MB) of the largest real adapter.
+#[cfg(windows)]
+fn parse_windows_adapters(stdout: &str) -> Option<(String, u64)> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let (name, bytes) = line.trim().rsplit_once('|')?;
+            let name = name.trim();
+            let lower = name.to_lowercase();
+            // Software and remote-session adapters can't run llama.cpp
+            if name.is_empty() || lower.contains("basic display") || lower.contains("basic render") || lower.contains("remote") {
+                return None;
+            }
+            let vram_mb = bytes.trim().parse::<u64>().unwrap_or(0) / (1024 * 1024);
+            Some((name.to_string(), vram_mb))
+        })
+        .max_by_key(|(_, vram_mb)| *vram_mb)
 }
 
 /// Detect Metal GPU on macOS (Apple Silicon or discrete).
@@ -44,20 +491,36 @@ fn detect_metal_gpu() -> Option<(String, u64)> {
         .unwrap_or_else(|| "Apple GPU".to_string());
 
     // Apple Silicon uses unified memory — report total system RAM
-    let sys = sysinfo::System::new_all();
-    let unified_ram_mb = sys.total_memory() / (1024 * 1024);
+    Some((format!("{} (Metal)", name), total_ram_mb()))
+}
 
-    Some((format!("{} (Metal)", name), unified_ram_mb))
+/// nvidia-smi to run: PATH first, then where older Windows drivers install it.
+#[cfg(not(target_os = "macos"))]
+fn nvidia_smi_candidates() -> Vec<std::path::PathBuf> {
+    #[allow(unused_mut)]
+    let mut candidates = vec![std::path::PathBuf::from("nvidia-smi")];
+    #[cfg(windows)]
+    if let Some(program_files) = std::env::var_os("ProgramFiles") {
+        candidates.push(
+            std::path::Path::new(&program_files)
+                .join("NVIDIA Corporation")
+                .join("NVSMI")
+                .join("nvidia-smi.exe"),
+        );
+    }
+    candidates
 }
 
 #[cfg(not(target_os = "macos"))]
 fn detect_nvidia_gpu() -> Option<(String, u64)> {
-    let output = std::process::Command::new("nvidia-smi")
-        .args(["--query-gpu=name,memory.total", "--format=csv,noheader,nounits"])
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::null())
-        .output()
-        .ok()?;
+    let output = nvidia_smi_candidates().into_iter().find_map(|program| {
+        std::process::Command::new(program)
+            .args(["--query-gpu=name,memory.total", "--format=csv,noheader,nounits"])
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .output()
+            .ok()
+    })?;
 
     if !output.status.success() {
         return None;
@@ -75,27 +538,643 @@ fn detect_nvidia_gpu() -> Option<(String, u64)> {
     Some((name, vram))
 }
 
+/// Current total GPU power draw in watts (summed across NVIDIA GPUs).
+/// Returns None when no GPU reports power readings.
+pub fn gpu_power_draw_watts() -> Option<f64> {
+    let output = std::process::Command::new("nvidia-smi")
+        .args(["--query-gpu=power.draw", "--format=csv,noheader,nounits"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let readings: Vec<f64> = stdout
+        .lines()
+        .filter_map(|l| l.trim().parse::<f64>().ok())
+        .collect();
+
+    if readings.is_empty() {
+        None
+    } else {
+        Some(readings.iter().sum())
+    }
+}
+
+/// Sample utilization, temperature, power and clocks for every GPU
+/// (nvidia-smi, then rocm-smi; ioreg on macOS). Blocking: spawns tools.
+pub fn gpu_telemetry() -> Vec<GpuTelemetry> {
+    #[cfg(target_os = "macos")]
+    return metal_gpu_telemetry().into_iter().collect();
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let nvidia = nvidia_gpu_telemetry();
+        if !nvidia.is_empty() {
+            return nvidia;
+        }
+        rocm_gpu_telemetry()
+    }
+}
+
+/// Average utilization and hottest temperature across GPUs, for reporting.
+pub fn summarize_gpu_telemetry(gpus: &[GpuTelemetry]) -> (Option<f32>, Option<f32>) {
+    let utils: Vec<f32> = gpus.iter().filter_map(|g| g.utilization_percent).collect();
+    let avg_util = if utils.is_empty() {
+        None
+    } else {
+        Some(utils.iter().sum::<f32>() / utils.len() as f32)
+    };
+    let max_temp = gpus
+        .iter()
+        .filter_map(|g| g.temperature_c)
+        .fold(None, |acc: Option<f32>, t| Some(acc.map_or(t, |a| a.max(t))));
+    (avg_util, max_temp)
+}
+
+#[cfg(not(target_os = "macos"))]
+fn nvidia_gpu_telemetry() -> Vec<GpuTelemetry> {
+    let output = match std::process::Command::new("nvidia-smi")
+        .args([
+            "--query-gpu=index,name,utilization.gpu,temperature.gpu,power.draw,clocks.sm,clocks.max.sm,clocks_throttle_reasons.hw_thermal_slowdown,clocks_throttle_reasons.sw_thermal_slowdown",
+            "--format=csv,noheader,nounits",
+        ])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .output()
+    {
+        Ok(o) if o.status.success() => o,
+        _ => return Vec::new(),
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split(',').map(|s| s.trim()).collect();
+            if parts.len() < 9 {
+                return None;
+            }
+            let temperature_c = parts[3].parse::<f32>().ok();
+            let reason_active = |s: &str| s.eq_ignore_ascii_case("active");
+            Some(GpuTelemetry {
+                index: parts[0].parse().unwrap_or(0),
+                name: parts[1].to_string(),
+                utilization_percent: parts[2].parse().ok(),
+                temperature_c,
+                power_watts: parts[4].parse().ok(),
+                sm_clock_mhz: parts[5].parse().ok(),
+                max_sm_clock_mhz: parts[6].parse().ok(),
+                throttling: reason_active(parts[7])
+                    || reason_active(parts[8])
+                    || temperature_c.map(|t| t >= THERMAL_WARN_CELSIUS).unwrap_or(false),
+            })
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "macos"))]
+fn rocm_gpu_telemetry() -> Vec<GpuTelemetry> {
+    let output = match std::process::Command::new("rocm-smi")
+        .args(["--showuse", "--showtemp", "--showpower", "--showclocks", "--showproductname", "--json"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .output()
+    {
+        Ok(o) if o.status.success() => o,
+        _ => return Vec::new(),
+    };
+
+    let json: serde_json::Value = match serde_json::from_slice(&output.stdout) {
+        Ok(v) => v,
+        Err(_) => return Vec::new(),
+    };
+    let Some(cards) = json.as_object() else {
+        return Vec::new();
+    };
+
+    // Keys vary between ROCm releases, so match on fragments of the field names
+    let find = |card: &serde_json::Map<String, serde_json::Value>, needles: &[&str]| -> Option<f64> {
+        card.iter()
+            .find(|(k, _)| needles.iter().all(|n| k.contains(n)))
+            .and_then(|(_, v)| v.as_str())
+            .and_then(|s| s.trim_start_matches('(').trim_end_matches("Mhz)").trim().parse().ok())
+    };
+
+    cards
+        .iter()
+        .filter(|(k, _)| k.starts_with("card"))
+        .filter_map(|(key, card)| {
+            let card = card.as_object()?;
+            let temperature_c = find(card, &["Temperature", "edge"])
+                .or_else(|| find(card, &["Temperature", "junction"]))
+                .map(|t| t as f32);
+            Some(GpuTelemetry {
+                index: key.trim_start_matches("card").parse().unwrap_or(0),
+                name: card
+                    .get("Card series")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("AMD GPU")
+                    .to_string(),
+                utilization_percent: find(card, &["GPU use"]).map(|u| u as f32),
+                temperature_c,
+                power_watts: find(card, &["Graphics Package Power"]),
+                sm_clock_mhz: find(card, &["sclk clock"]).map(|c| c as u32),
+                max_sm_clock_mhz: None,
+                throttling: temperature_c.map(|t| t >= THERMAL_WARN_CELSIUS).unwrap_or(false),
+            })
+        })
+        .collect()
+}
+
+/// Sample GPU telemetry every `interval_secs`, logging when a GPU starts or stops
+/// thermal throttling, and hand each sample to `on_sample`.
+pub fn start_gpu_monitor<F>(interval_secs: u64, on_sample: F) -> tokio::task::JoinHandle<()>
+where
+    F: Fn(Vec<GpuTelemetry>) + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+        let mut throttled: std::collections::HashSet<u32> = std::collections::HashSet::new();
+        loop {
+            interval.tick().await;
+            let gpus = match tokio::task::spawn_blocking(gpu_telemetry).await {
+                Ok(g) => g,
+                Err(_) => continue,
+            };
+            for gpu in &gpus {
+                if gpu.throttling && throttled.insert(gpu.index) {
+                    log::warn!(
+                        "GPU {} ({}) is thermal throttling at {}",
+                        gpu.index,
+                        gpu.name,
+                        gpu.temperature_c
+                            .map(|t| format!("{:.0}°C", t))
+                            .unwrap_or_else(|| "unknown temperature".into())
+                    );
+                } else if !gpu.throttling && throttled.remove(&gpu.index) {
+                    log::info!("GPU {} ({}) no longer throttling", gpu.index, gpu.name);
+                }
+            }
+            on_sample(gpus);
+        }
+    })
+}
+
+/// System-wide memory pressure as the OS reports it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryPressure {
+    Normal,
+    Warning,
+    Critical,
+}
+
+/// What to do under critical memory pressure (`memory_pressure_action` in `AgentConfig`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryPressurePolicy {
+    Off,
+    /// Log and emit warnings only.
+    Warn,
+    /// Reject new relay requests until pressure drops below critical.
+    Pause,
+    /// Restart llama-server with a smaller context; the caller handles it via `on_change`.
+    ReduceCtx,
+}
+
+impl MemoryPressurePolicy {
+    pub fn from_config(action: &str) -> Self {
+        match action {
+            "off" => MemoryPressurePolicy::Off,
+            "pause" => MemoryPressurePolicy::Pause,
+            "reduce-ctx" => MemoryPressurePolicy::ReduceCtx,
+            _ => MemoryPressurePolicy::Warn,
+        }
+    }
+}
+
+/// Smallest context `ReduceCtx` shrinks to.
+pub const MIN_REDUCED_CTX: u32 = 4096;
+
+/// Current memory pressure. macOS only (`kern.memorystatus_vm_pressure_level`,
+/// the level behind `memory_pressure`); None elsewhere. Blocking: spawns sysctl.
+#[cfg(target_os = "macos")]
+pub fn memory_pressure() -> Option<MemoryPressure> {
+    let output = std::process::Command::new("sysctl")
+        .args(["-n", "kern.memorystatus_vm_pressure_level"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // DISPATCH_MEMORYPRESSURE_NORMAL = 1, WARN = 2, CRITICAL = 4
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "1" => Some(MemoryPressure::Normal),
+        "2" => Some(MemoryPressure::Warning),
+        "4" => Some(MemoryPressure::Critical),
+        _ => None,
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn memory_pressure() -> Option<MemoryPressure> {
+    None
+}
+
+/// Poll memory pressure every `interval_secs`, logging level changes and pausing
+/// `availability` at critical for the `Pause` policy. `on_change` gets every new level.
+pub fn start_memory_pressure_watcher<F>(
+    policy: MemoryPressurePolicy,
+    availability: Arc<Availability>,
+    interval_secs: u64,
+    on_change: F,
+) -> tokio::task::JoinHandle<()>
+where
+    F: Fn(MemoryPressure) + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+        let mut last = MemoryPressure::Normal;
+        loop {
+            interval.tick().await;
+            let level = match tokio::task::spawn_blocking(memory_pressure).await {
+                Ok(Some(level)) => level,
+                _ => continue,
+            };
+            if level == last {
+                continue;
+            }
+            match level {
+                MemoryPressure::Critical => log::warn!("Memory pressure is critical"),
+                MemoryPressure::Warning => log::warn!("Memory pressure is elevated"),
+                MemoryPressure::Normal => log::info!("Memory pressure back to normal"),
+            }
+            if policy == MemoryPressurePolicy::Pause {
+                if level == MemoryPressure::Critical {
+                    availability.pause(PauseSource::MemoryPressure, "Critical memory pressure", false);
+                } else {
+                    availability.resume(PauseSource::MemoryPressure);
+                }
+            }
+            last = level;
+            on_change(level);
+        }
+    })
+}
+
+/// Apple GPUs expose utilization via IOAccelerator; temperature needs root (powermetrics).
+#[cfg(target_os = "macos")]
+fn metal_gpu_telemetry() -> Option<GpuTelemetry> {
+    let output = std::process::Command::new("ioreg")
+        .args(["-r", "-d", "1", "-c", "IOAccelerator"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let utilization = stdout
+        .split("\"Device Utilization %\"=")
+        .nth(1)
+        .and_then(|rest| {
+            let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+            digits.parse::<f32>().ok()
+        });
+    let name = detect_metal_gpu().map(|(n, _)| n).unwrap_or_else(|| "Apple GPU".to_string());
+    Some(GpuTelemetry {
+        index: 0,
+        name,
+        utilization_percent: utilization,
+        ..Default::default()
+    })
+}
+
+/// Resource usage of one process (the managed llama-server), as opposed to the whole machine.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessUsage {
+    pub pid: u32,
+    /// Percent of one core, like `top`; above 100 when several cores are busy.
+    pub cpu_percent: f32,
+    pub rss_bytes: u64,
+    /// None when no GPU tool reports per-process memory.
+    pub gpu_memory_mb: Option<u64>,
+}
+
+/// Sample CPU, RSS and GPU memory of `pid`. None if the process is gone.
+/// Blocking: waits one CPU sampling interval and spawns nvidia-smi.
+pub fn process_usage(pid: u32) -> Option<ProcessUsage> {
+    use sysinfo::{Pid, ProcessesToUpdate};
+
+    let target = Pid::from_u32(pid);
+    let mut sys = sysinfo::System::new();
+    // CPU usage is the delta between two refreshes
+    sys.refresh_processes(ProcessesToUpdate::Some(&[target]), true);
+    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    sys.refresh_processes(ProcessesToUpdate::Some(&[target]), true);
+    let process = sys.process(target)?;
+
+    Some(ProcessUsage {
+        pid,
+        cpu_percent: process.cpu_usage(),
+        rss_bytes: process.memory(),
+        gpu_memory_mb: process_gpu_memory_mb(pid),
+    })
+}
+
+/// GPU memory held by `pid`, summed over NVIDIA GPUs (`--query-compute-apps`).
+fn process_gpu_memory_mb(pid: u32) -> Option<u64> {
+    let output = std::process::Command::new("nvidia-smi")
+        .args(["--query-compute-apps=pid,used_memory", "--format=csv,noheader,nounits"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let total = stdout
+        .lines()
+        .filter_map(|line| {
+            let (app_pid, used) = line.split_once(',')?;
+            if app_pid.trim().parse::<u32>().ok()? != pid {
+                return None;
+            }
+            used.trim().parse::<u64>().ok()
+        })
+        .sum();
+    // The process simply has no GPU allocations when nvidia-smi works but doesn't list it
+    Some(total)
+}
+
 /// Discover the machine's LAN IP by connecting a UDP socket to a remote address.
+/// IPv4 first, then a global IPv6 address on IPv6-only networks.
 pub fn get_local_ip() -> Option<String> {
+    get_local_ipv4().or_else(get_local_ipv6)
+}
+
+/// Outgoing IPv4 address; None without an IPv4 route.
+pub fn get_local_ipv4() -> Option<String> {
     let socket = std::net::UdpSocket::bind("0.0.0.0:0").ok()?;
     socket.connect("8.8.8.8:80").ok()?;
-    let addr = socket.local_addr().ok()?;
-    let ip = addr.ip().to_string();
-    if ip == "127.0.0.1" || ip == "::1" {
+    let ip = socket.local_addr().ok()?.ip();
+    if ip.is_loopback() || ip.is_unspecified() {
         return None;
     }
-    Some(ip)
+    Some(ip.to_string())
 }
 
-/// Get total system RAM in megabytes.
+/// Outgoing global IPv6 address; None without an IPv6 route or with only
+/// link-local / unique-local addresses, which nobody outside the LAN can reach.
+pub fn get_local_ipv6() -> Option<String> {
+    let socket = std::net::UdpSocket::bind("[::]:0").ok()?;
+    socket.connect("[2001:4860:4860::8888]:80").ok()?;
+    match socket.local_addr().ok()?.ip() {
+        std::net::IpAddr::V6(ip) if is_global_ipv6(&ip) => Some(ip.to_string()),
+        _ => None,
+    }
+}
+
+/// Global unicast (2000::/3), minus the documentation range.
+fn is_global_ipv6(ip: &std::net::Ipv6Addr) -> bool {
+    let segments = ip.segments();
+    segments[0] & 0xe000 == 0x2000 && !(segments[0] == 0x2001 && segments[1] == 0x0db8)
+}
+
+/// `host:port`, with IPv6 literals bracketed (`[2001:db8::1]:8080`).
+pub fn host_port(host: &str, port: u16) -> String {
+    if host.contains(':') && !host.starts_with('[') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+/// `http://host:port` for an endpoint we advertise.
+pub fn http_endpoint(host: &str, port: u16) -> String {
+    format!("http://{}", host_port(host, port))
+}
+
+/// The local IP to advertise to the Oracle: of a family the Oracle resolves to,
+/// and that llama-server listens on (`0.0.0.0` is IPv4 only). IPv4 wins when both
+/// work. Falls back to `get_local_ip` when the Oracle can't be resolved.
+pub async fn advertised_ip(oracle_url: &str, bind_host: &str) -> Option<String> {
+    let listens_v6 = bind_host
+        .parse::<std::net::IpAddr>()
+        .map(|ip| ip.is_ipv6())
+        .unwrap_or(true);
+
+    let oracle = url::Url::parse(oracle_url).ok();
+    let resolved: Vec<std::net::SocketAddr> = match oracle.as_ref().and_then(|u| {
+        Some((u.host_str()?.trim_matches(['[', ']']).to_string(), u.port_or_known_default()?))
+    }) {
+        Some(target) => tokio::net::lookup_host(target)
+            .await
+            .map(|addrs| addrs.collect())
+            .unwrap_or_default(),
+        None => Vec::new(),
+    };
+    if resolved.is_empty() {
+        return get_local_ip();
+    }
+
+    let oracle_v4 = resolved.iter().any(|a| a.is_ipv4());
+    let oracle_v6 = resolved.iter().any(|a| a.is_ipv6());
+    if oracle_v4 {
+        if let Some(ip) = get_local_ipv4() {
+            return Some(ip);
+        }
+    }
+    if oracle_v6 && listens_v6 {
+        if let Some(ip) = get_local_ipv6() {
+            return Some(ip);
+        }
+    }
+    get_local_ip()
+}
+
+/// Get total system RAM in megabytes. Uncached; prefer `hardware::profile()`.
 pub fn total_ram_mb() -> u64 {
-    let sys = sysinfo::System::new_all();
+    let mut sys = sysinfo::System::new();
+    sys.refresh_memory();
     sys.total_memory() / (1024 * 1024)
 }
 
+/// Extra space kept free on top of what a download needs.
+pub const DISK_HEADROOM_BYTES: u64 = 512 * 1024 * 1024;
+/// Free space below which a low-disk warning is raised during downloads.
+pub const LOW_DISK_WARN_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Free bytes on the disk holding `path` (the path itself need not exist yet).
+pub fn available_disk_space(path: &std::path::Path) -> Option<u64> {
+    // Resolve the deepest existing ancestor so symlinks and relative paths map to a real mount
+    let existing = path.ancestors().find(|p| p.exists())?;
+    let resolved = existing.canonicalize().unwrap_or_else(|_| existing.to_path_buf());
+
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|d| resolved.starts_with(d.mount_point()))
+        .max_by_key(|d| d.mount_point().as_os_str().len())
+        .map(|d| d.available_space())
+}
+
+/// Fail with a readable message when `path`'s disk has less than `needed` bytes (plus headroom) free.
+/// Unknown free space is not treated as an error.
+pub fn ensure_disk_space(path: &std::path::Path, needed: u64, what: &str) -> Result<(), String> {
+    let Some(free) = available_disk_space(path) else {
+        return Ok(());
+    };
+    let required = needed.saturating_add(DISK_HEADROOM_BYTES);
+    if free < required {
+        return Err(format!(
+            "Not enough disk space for {}: need {:.1} GB, {:.1} GB free on {}",
+            what,
+            required as f64 / 1e9,
+            free as f64 / 1e9,
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+/// Reserve disk blocks for `file` up to `len` bytes without changing its size, so a
+/// download fails up front when the disk is too small and lands less fragmented.
+/// Only running out of space is an error; filesystems without preallocation are skipped.
+pub fn preallocate(file: &std::fs::File, len: u64) -> Result<(), String> {
+    match preallocate_impl(file, len) {
+        Ok(()) => Ok(()),
+        Err(e) if is_disk_full(&e) => Err(format!(
+            "Not enough disk space to reserve {:.1} GB: {}",
+            len as f64 / 1e9,
+            e
+        )),
+        Err(e) => {
+            log::debug!("Preallocation not available: {}", e);
+            Ok(())
+        }
+    }
+}
+
+fn is_disk_full(e: &std::io::Error) -> bool {
+    #[cfg(unix)]
+    {
+        e.raw_os_error() == Some(libc::ENOSPC)
+    }
+    #[cfg(windows)]
+    {
+        // ERROR_HANDLE_DISK_FULL, ERROR_DISK_FULL
+        matches!(e.raw_os_error(), Some(39) | Some(112))
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = e;
+        false
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn preallocate_impl(file: &std::fs::File, len: u64) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+    // KEEP_SIZE: the file length (and with it the resume offset) stays as is
+    // SAFETY: the fd belongs to `file`, which outlives the call; no memory is passed.
+    let rc = unsafe { libc::fallocate(file.as_raw_fd(), libc::FALLOC_FL_KEEP_SIZE, 0, len as libc::off_t) };
+    if rc == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn preallocate_impl(file: &std::fs::File, len: u64) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+    let current = file.metadata()?.len();
+    if len <= current {
+        return Ok(());
+    }
+    let mut store = libc::fstore_t {
+        fst_flags: libc::F_ALLOCATECONTIG | libc::F_ALLOCATEALL,
+        fst_posmode: libc::F_PEOFPOSMODE,
+        fst_offset: 0,
+        fst_length: (len - current) as libc::off_t,
+        fst_bytesalloc: 0,
+    };
+    // SAFETY: the fd belongs to `file`, which outlives the call; F_PREALLOCATE takes
+    // an fstore_t, and `store` is a live one.
+    let mut rc = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_PREALLOCATE, &store) };
+    if rc == -1 {
+        // No contiguous run that large; any blocks will do
+        store.fst_flags = libc::F_ALLOCATEALL;
+        // SAFETY: as above.
+        rc = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_PREALLOCATE, &store) };
+    }
+    if rc == -1 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+fn preallocate_impl(file: &std::fs::File, len: u64) -> std::io::Result<()> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Storage::FileSystem::{
+        FileAllocationInfo, SetFileInformationByHandle, FILE_ALLOCATION_INFO,
+    };
+    if len <= file.metadata()?.len() {
+        // A smaller allocation size would truncate the file
+        return Ok(());
+    }
+    let info = FILE_ALLOCATION_INFO {
+        AllocationSize: len as i64,
+    };
+    // SAFETY: the handle belongs to `file`, which outlives the call, and `info` is a
+    // live FILE_ALLOCATION_INFO passed with its own size.
+    let ok = unsafe {
+        SetFileInformationByHandle(
+            file.as_raw_handle() as _,
+            FileAllocationInfo,
+            &info as *const FILE_ALLOCATION_INFO as *const std::ffi::c_void,
+            std::mem::size_of::<FILE_ALLOCATION_INFO>() as u32,
+        )
+    };
+    if ok != 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos", windows)))]
+fn preallocate_impl(_file: &std::fs::File, _len: u64) -> std::io::Result<()> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "not supported on this platform"))
+}
+
+/// Flush a directory entry (e.g. after a rename into it) to disk. No-op where
+/// directories can't be opened (Windows).
+pub fn sync_dir(dir: &std::path::Path) {
+    #[cfg(unix)]
+    if let Err(e) = std::fs::File::open(dir).and_then(|d| d.sync_all()) {
+        log::debug!("Failed to sync {}: {}", dir.display(), e);
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+}
+
 /// Kill a process by PID (cross-platform).
 pub fn kill_pid(pid: u32) {
     log::info!("Killing process PID: {}", pid);
+    // SAFETY: kill takes no pointers; an unknown pid only fails with ESRCH.
     #[cfg(unix)]
     unsafe {
         libc::kill(pid as i32, libc::SIGKILL);
@@ -110,6 +1189,61 @@ pub fn kill_pid(pid: u32) {
     }
 }
 
+/// Ask a process to exit gracefully (SIGTERM on Unix, taskkill without /F on Windows).
+pub fn terminate_pid(pid: u32) {
+    log::info!("Stopping process PID: {}", pid);
+    // SAFETY: as in `kill_pid`.
+    #[cfg(unix)]
+    unsafe {
+        libc::kill(pid as i32, libc::SIGTERM);
+    }
+    #[cfg(windows)]
+    {
+        let mut cmd = std::process::Command::new("taskkill");
+        cmd.args(["/PID", &pid.to_string()]);
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000);
+        let _ = cmd.output();
+    }
+}
+
+/// Whether a process with this PID is still running.
+pub fn is_pid_alive(pid: u32) -> bool {
+    #[cfg(unix)]
+    {
+        // Signal 0 only checks for existence/permission
+        // SAFETY: as in `kill_pid`.
+        unsafe { libc::kill(pid as i32, 0) == 0 }
+    }
+    #[cfg(windows)]
+    {
+        let mut cmd = std::process::Command::new("tasklist");
+        cmd.args(["/FI", &format!("PID eq {}", pid), "/NH", "/FO", "CSV"]);
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000);
+        cmd.output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).contains(&format!("\"{}\"", pid)))
+            .unwrap_or(false)
+    }
+}
+
+/// First port from `preferred` up to `preferred + scan` that can be bound on `host`.
+pub fn pick_free_port(host: &str, preferred: u16, scan: u16) -> Result<u16, String> {
+    let last = preferred.saturating_add(scan);
+    (preferred..=last)
+        .find(|port| std::net::TcpListener::bind((host, *port)).is_ok())
+        .ok_or_else(|| {
+            if scan == 0 {
+                format!("Port {} is in use; free it or change httpPort", preferred)
+            } else {
+                format!(
+                    "Ports {}-{} are all in use; free one or change httpPort",
+                    preferred, last
+                )
+            }
+        })
+}
+
 /// Kill any llama-server process listening on the given port.
 pub fn kill_process_on_port(port: u16) -> Option<String> {
     #[cfg(unix)]
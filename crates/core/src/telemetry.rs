@@ -0,0 +1,76 @@
+//! Optional OpenTelemetry export for `tracing` spans.
+//!
+//! Relay, oracle and inference code always emit spans; they only leave the
+//! process when an OTLP endpoint is configured and the `otel` feature is on.
+
+use std::sync::OnceLock;
+
+static INSTALLED: OnceLock<()> = OnceLock::new();
+
+/// Install the OTLP span exporter for `endpoint` (e.g. `http://localhost:4318/v1/traces`).
+/// Does nothing when the endpoint is empty or an exporter is already installed.
+pub fn init_tracing(endpoint: Option<&str>) -> Result<(), String> {
+    let endpoint = match endpoint.map(str::trim) {
+        Some(e) if !e.is_empty() => e,
+        _ => return Ok(()),
+    };
+
+    if INSTALLED.get().is_some() {
+        return Ok(());
+    }
+
+    #[cfg(feature = "otel")]
+    {
+        install_otlp(endpoint)?;
+        let _ = INSTALLED.set(());
+        log::info!("OpenTelemetry tracing enabled (OTLP endpoint: {})", endpoint);
+        Ok(())
+    }
+
+    #[cfg(not(feature = "otel"))]
+    {
+        log::warn!(
+            "OTLP endpoint {} configured but this build has no `otel` feature; tracing disabled",
+            endpoint
+        );
+        Ok(())
+    }
+}
+
+/// Flush and shut down the exporter (no-op when tracing was never installed).
+pub fn shutdown_tracing() {
+    #[cfg(feature = "otel")]
+    if INSTALLED.get().is_some() {
+        opentelemetry::global::shutdown_tracer_provider();
+    }
+}
+
+#[cfg(feature = "otel")]
+fn install_otlp(endpoint: &str) -> Result<(), String> {
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_otlp::WithExportConfig;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()
+        .map_err(|e| format!("OTLP exporter init failed: {}", e))?;
+
+    let provider = opentelemetry_sdk::trace::TracerProvider::builder()
+        .with_batch_exporter(exporter, opentelemetry_sdk::runtime::Tokio)
+        .with_resource(opentelemetry_sdk::Resource::new(vec![
+            opentelemetry::KeyValue::new("service.name", "plumise-agent"),
+            opentelemetry::KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+        ]))
+        .build();
+
+    let tracer = provider.tracer("plumise-agent");
+    opentelemetry::global::set_tracer_provider(provider);
+
+    tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+        .try_init()
+        .map_err(|e| format!("Tracing subscriber init failed: {}", e))
+}
@@ -0,0 +1,321 @@
+//! In-process mock Oracle for integration tests (`testing` feature).
+//!
+//! Serves `/api/nodes/register`, `/api/v1/metrics/report` and `/api/v1/pipeline/*` on a
+//! random local port. Registration and metrics signatures are checked the same way the
+//! Oracle checks them; requests with a bad signature get a 401 and land in `rejected()`.
+//! Pipeline calls are only recorded: the agent is not a pipeline participant, so tests
+//! can assert it never makes them.
+
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::chain::crypto::recover_address;
+use crate::oracle::registry::ClusterAssignment;
+
+/// Signed timestamps older (or newer) than this are refused, like the Oracle does.
+const MAX_CLOCK_SKEW_SECS: u64 = 300;
+const MAX_REQUEST_BYTES: usize = 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub body: Value,
+}
+
+#[derive(Default)]
+struct MockState {
+    registrations: Vec<Value>,
+    metrics_reports: Vec<Value>,
+    pipeline_requests: Vec<RecordedRequest>,
+    rejected: Vec<RecordedRequest>,
+    assignment: Option<ClusterAssignment>,
+    reject_registrations: Option<String>,
+}
+
+pub struct MockOracle {
+    url: String,
+    state: Arc<Mutex<MockState>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl MockOracle {
+    /// Bind to 127.0.0.1 on a free port and start serving.
+    pub async fn start() -> Result<Self, String> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| format!("Mock Oracle bind failed: {}", e))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|e| format!("Mock Oracle address error: {}", e))?;
+        let state = Arc::new(Mutex::new(MockState::default()));
+
+        let server_state = Arc::clone(&state);
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = Arc::clone(&server_state);
+                tokio::spawn(async move {
+                    if let Err(e) = serve_connection(stream, &state).await {
+                        log::debug!("Mock Oracle connection error: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            url: format!("http://{}", addr),
+            state,
+            task,
+        })
+    }
+
+    /// Base URL to use as `oracle_url`.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Assignment returned by subsequent registrations (`None` = standalone).
+    pub fn set_assignment(&self, assignment: Option<ClusterAssignment>) {
+        self.state.lock().unwrap().assignment = assignment;
+    }
+
+    /// Answer subsequent registrations with `success: false` and this message.
+    pub fn reject_registrations(&self, message: Option<String>) {
+        self.state.lock().unwrap().reject_registrations = message;
+    }
+
+    /// Registration bodies that passed signature verification.
+    pub fn registrations(&self) -> Vec<Value> {
+        self.state.lock().unwrap().registrations.clone()
+    }
+
+    /// Metrics report bodies that passed signature verification.
+    pub fn metrics_reports(&self) -> Vec<Value> {
+        self.state.lock().unwrap().metrics_reports.clone()
+    }
+
+    pub fn pipeline_requests(&self) -> Vec<RecordedRequest> {
+        self.state.lock().unwrap().pipeline_requests.clone()
+    }
+
+    /// Requests refused for a missing or invalid signature.
+    pub fn rejected(&self) -> Vec<RecordedRequest> {
+        self.state.lock().unwrap().rejected.clone()
+    }
+}
+
+impl Drop for MockOracle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Handle one request and close the connection.
+async fn serve_connection(mut stream: TcpStream, state: &Mutex<MockState>) -> Result<(), String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 8192];
+    let header_end = loop {
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if buf.len() > MAX_REQUEST_BYTES {
+            return Err("Request headers too large".into());
+        }
+        let n = stream.read(&mut chunk).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("Connection closed before headers".into());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let target = request_line.next().unwrap_or_default();
+    let path = target.split('?').next().unwrap_or_default().to_string();
+    let content_length = lines
+        .filter_map(|l| l.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    if content_length > MAX_REQUEST_BYTES {
+        return Err("Request body too large".into());
+    }
+
+    while buf.len() < header_end + content_length {
+        let n = stream.read(&mut chunk).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("Connection closed before body".into());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    let body = &buf[header_end..header_end + content_length];
+    let body: Value = if body.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(body).unwrap_or(Value::Null)
+    };
+
+    let (status, response) = route(state, &method, &path, body);
+    let response = response.to_string();
+    let reason = match status {
+        200 => "OK",
+        401 => "Unauthorized",
+        _ => "Not Found",
+    };
+    let reply = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        response.len(),
+        response
+    );
+    stream
+        .write_all(reply.as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    let _ = stream.shutdown().await;
+    Ok(())
+}
+
+fn route(state: &Mutex<MockState>, method: &str, path: &str, body: Value) -> (u16, Value) {
+    let mut state = state.lock().unwrap();
+    let record = |body: Value| RecordedRequest {
+        method: method.to_string(),
+        path: path.to_string(),
+        body,
+    };
+
+    match (method, path) {
+        ("POST", "/api/nodes/register") => {
+            if let Err(e) = verify_registration(&body) {
+                state.rejected.push(record(body));
+                return (401, json!({ "success": false, "message": e }));
+            }
+            if let Some(message) = state.reject_registrations.clone() {
+                return (
+                    200,
+                    json!({ "success": false, "message": message, "assignment": null }),
+                );
+            }
+            state.registrations.push(body);
+            (
+                200,
+                json!({
+                    "success": true,
+                    "message": "Node registered",
+                    "assignment": state.assignment,
+                }),
+            )
+        }
+        ("POST", "/api/v1/metrics/report") => {
+            if let Err(e) = verify_metrics_report(&body) {
+                state.rejected.push(record(body));
+                return (401, json!({ "success": false, "message": e }));
+            }
+            state.metrics_reports.push(body);
+            (200, json!({ "success": true }))
+        }
+        (_, p) if p.starts_with("/api/v1/pipeline/") => {
+            state.pipeline_requests.push(record(body));
+            (200, json!({ "success": true }))
+        }
+        _ => (404, json!({ "success": false, "message": "Not found" })),
+    }
+}
+
+fn verify_registration(body: &Value) -> Result<(), String> {
+    // Field order MUST match registry::registration_payload
+    #[derive(Serialize)]
+    struct SignData<'a> {
+        address: &'a str,
+        endpoint: &'a str,
+        capabilities: Vec<&'a str>,
+        timestamp: u64,
+    }
+
+    let address = str_field(body, "address")?;
+    let capabilities = body
+        .get("capabilities")
+        .and_then(Value::as_array)
+        .ok_or("Missing capabilities")?
+        .iter()
+        .map(|c| c.as_str().ok_or("Invalid capability"))
+        .collect::<Result<Vec<_>, _>>()?;
+    let sign_data = SignData {
+        address,
+        endpoint: str_field(body, "endpoint")?,
+        capabilities,
+        timestamp: fresh_timestamp(body)?,
+    };
+    let message = serde_json::to_string(&sign_data).map_err(|e| e.to_string())?;
+    verify_signer(&message, body, address)
+}
+
+fn verify_metrics_report(body: &Value) -> Result<(), String> {
+    let address = str_field(body, "address")?;
+    let message = format!(
+        "{}:{}:{}:{}",
+        address,
+        u64_field(body, "totalTokens")?,
+        u64_field(body, "totalRequests")?,
+        fresh_timestamp(body)?
+    );
+    verify_signer(&message, body, address)?;
+
+    // Optional uptime attestation, signed on its own
+    match body.get("attestation").filter(|a| !a.is_null()) {
+        Some(attestation) => {
+            let message = format!(
+                "{}:{}:{}:{}",
+                address,
+                u64_field(attestation, "counter")?,
+                fresh_timestamp(attestation)?,
+                u64_field(attestation, "streakStartedAt")?
+            );
+            verify_signer(&message, attestation, address)
+        }
+        None => Ok(()),
+    }
+}
+
+fn verify_signer(message: &str, body: &Value, address: &str) -> Result<(), String> {
+    let signer = recover_address(message, str_field(body, "signature")?)?;
+    if signer.eq_ignore_ascii_case(address) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Signature from {} does not match {}",
+            signer, address
+        ))
+    }
+}
+
+fn fresh_timestamp(body: &Value) -> Result<u64, String> {
+    let timestamp = u64_field(body, "timestamp")?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| format!("Time error: {}", e))?
+        .as_secs();
+    if now.abs_diff(timestamp) > MAX_CLOCK_SKEW_SECS {
+        return Err(format!("Stale timestamp: {}", timestamp));
+    }
+    Ok(timestamp)
+}
+
+fn str_field<'a>(body: &'a Value, name: &str) -> Result<&'a str, String> {
+    body.get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("Missing {}", name))
+}
+
+fn u64_field(body: &Value, name: &str) -> Result<u64, String> {
+    body.get(name)
+        .and_then(Value::as_u64)
+        .ok_or_else(|| format!("Missing {}", name))
+}
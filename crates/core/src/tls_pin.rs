@@ -0,0 +1,235 @@
+//! Optional SPKI pinning for the Oracle and relay hosts.
+//!
+//! Pins are `sha256/<base64>` hashes of the server's SubjectPublicKeyInfo (the
+//! format curl's `--pinnedpubkey` and HPKP use). The platform trust store still
+//! validates the chain; the pin check runs in the TLS handshake itself (see
+//! [`client_config`]), so a pinned host whose key doesn't match is dropped before any
+//! request is written. [`crate::http::client`] and the relay connection use it.
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use base64::Engine;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+type PinMap = HashMap<String, Vec<[u8; 32]>>;
+
+static PINS: OnceLock<RwLock<PinMap>> = OnceLock::new();
+static FAILURES: OnceLock<tokio::sync::broadcast::Sender<PinFailure>> = OnceLock::new();
+
+/// A connection rejected because its key matched none of the host's pins.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PinFailure {
+    pub host: String,
+    /// `sha256/<base64>` of the key the server presented; empty if it sent no certificate.
+    pub presented: String,
+    pub message: String,
+}
+
+fn pins() -> &'static RwLock<PinMap> {
+    PINS.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+fn failures() -> &'static tokio::sync::broadcast::Sender<PinFailure> {
+    FAILURES.get_or_init(|| tokio::sync::broadcast::channel(16).0)
+}
+
+/// Parse one `sha256/<base64>` pin.
+pub fn parse_pin(pin: &str) -> Result<[u8; 32], String> {
+    let encoded = pin
+        .trim()
+        .strip_prefix("sha256/")
+        .ok_or_else(|| format!("Invalid TLS pin '{}': expected sha256/<base64>", pin))?;
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim_start_matches('/'))
+        .map_err(|e| format!("Invalid TLS pin '{}': {}", pin, e))?;
+    bytes
+        .try_into()
+        .map_err(|_| format!("Invalid TLS pin '{}': not a SHA-256 hash", pin))
+}
+
+/// Pin `host` to `pin_list`, replacing earlier pins. An empty list removes pinning.
+pub fn set_pins(host: &str, pin_list: &[String]) -> Result<(), String> {
+    let parsed = pin_list.iter().map(|p| parse_pin(p)).collect::<Result<Vec<_>, _>>()?;
+    let mut map = pins().write().map_err(|_| "TLS pin table poisoned".to_string())?;
+    if parsed.is_empty() {
+        map.remove(&host.to_lowercase());
+    } else {
+        log::info!("TLS pinning enabled for {} ({} pin(s))", host, parsed.len());
+        map.insert(host.to_lowercase(), parsed);
+    }
+    Ok(())
+}
+
+/// Install the configured pins for the Oracle host (which also serves the relay).
+pub fn install(config: &crate::config::AgentConfig) -> Result<(), String> {
+    let url = url::Url::parse(&config.oracle_url)
+        .map_err(|e| format!("Invalid oracle_url '{}': {}", config.oracle_url, e))?;
+    // Plain HTTP has no handshake to check the pins in
+    if url.scheme() != "https" && !config.oracle_spki_pins.is_empty() {
+        return Err(format!("oracle_url '{}' must use https to be pinned", config.oracle_url));
+    }
+    match url.host_str() {
+        Some(host) => set_pins(host, &config.oracle_spki_pins),
+        None if config.oracle_spki_pins.is_empty() => Ok(()),
+        None => Err(format!("oracle_url '{}' has no host to pin", config.oracle_url)),
+    }
+}
+
+/// Whether any host is pinned.
+pub fn enabled() -> bool {
+    pins().read().map(|map| !map.is_empty()).unwrap_or(true)
+}
+
+/// Receive every pin failure, e.g. to surface it in the UI.
+pub fn subscribe() -> tokio::sync::broadcast::Receiver<PinFailure> {
+    failures().subscribe()
+}
+
+/// Platform certificate validation followed by the pin check for the server name.
+#[derive(Debug)]
+struct PinningVerifier {
+    inner: rustls_platform_verifier::Verifier,
+}
+
+impl ServerCertVerifier for PinningVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified =
+            self.inner
+                .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)?;
+        check_certificate(&server_name.to_str(), Some(end_entity.as_ref())).map_err(rustls::Error::General)?;
+        Ok(verified)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+/// TLS client config that validates against the platform trust store and rejects a
+/// pinned host's certificate during the handshake. Pins are read per handshake, so
+/// later `set_pins` calls apply to new connections. `alpn` is offered as given.
+pub fn client_config(alpn: &[&[u8]]) -> Result<rustls::ClientConfig, String> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let inner = rustls_platform_verifier::Verifier::new(provider.clone())
+        .map_err(|e| format!("TLS verifier unavailable: {}", e))?;
+    let mut config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("TLS setup failed: {}", e))?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(PinningVerifier { inner }))
+        .with_no_client_auth();
+    config.alpn_protocols = alpn.iter().map(|p| p.to_vec()).collect();
+    Ok(config)
+}
+
+/// Check a peer certificate (DER) against the pins for `host`. Unpinned hosts always pass.
+fn check_certificate(host: &str, cert_der: Option<&[u8]>) -> Result<(), String> {
+    let expected = match pins().read() {
+        Ok(map) => match map.get(&host.to_lowercase()) {
+            Some(expected) => expected.clone(),
+            None => return Ok(()),
+        },
+        Err(_) => return Err("TLS pin table poisoned".to_string()),
+    };
+
+    let presented = cert_der.and_then(spki_sha256);
+    if presented.is_some_and(|hash| expected.contains(&hash)) {
+        return Ok(());
+    }
+    let presented = presented
+        .map(|hash| format!("sha256/{}", base64::engine::general_purpose::STANDARD.encode(hash)))
+        .unwrap_or_default();
+    let message = if presented.is_empty() {
+        format!("TLS pin check failed for {}: no server certificate to verify", host)
+    } else {
+        format!(
+            "TLS pin check failed for {}: server key {} matches no configured pin (possible interception)",
+            host, presented
+        )
+    };
+    log::error!("{}", message);
+    let _ = failures().send(PinFailure {
+        host: host.to_string(),
+        presented,
+        message: message.clone(),
+    });
+    Err(message)
+}
+
+/// SHA-256 of the certificate's DER-encoded SubjectPublicKeyInfo.
+pub fn spki_sha256(cert_der: &[u8]) -> Option<[u8; 32]> {
+    let spki = subject_public_key_info(cert_der)?;
+    Some(Sha256::digest(spki).into())
+}
+
+/// Locate SubjectPublicKeyInfo (tag and length included) in an X.509 certificate.
+fn subject_public_key_info(cert_der: &[u8]) -> Option<&[u8]> {
+    // Certificate ::= SEQUENCE { tbsCertificate, signatureAlgorithm, signature }
+    let (_, cert, _) = der_element(cert_der, 0x30)?;
+    let (_, mut tbs, _) = der_element(cert, 0x30)?;
+    // [0] version is optional
+    if tbs.first() == Some(&0xa0) {
+        tbs = der_element(tbs, 0xa0)?.2;
+    }
+    // serialNumber, signature, issuer, validity, subject
+    for tag in [0x02, 0x30, 0x30, 0x30, 0x30] {
+        tbs = der_element(tbs, tag)?.2;
+    }
+    let (whole, _, _) = der_element(tbs, 0x30)?;
+    Some(whole)
+}
+
+/// Split the DER element at the start of `input` (which must have `tag`) into
+/// (whole element, contents, remaining input).
+fn der_element(input: &[u8], tag: u8) -> Option<(&[u8], &[u8], &[u8])> {
+    if *input.first()? != tag {
+        return None;
+    }
+    let first = *input.get(1)?;
+    let (len, header) = if first < 0x80 {
+        (first as usize, 2)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 {
+            return None;
+        }
+        let len = input
+            .get(2..2 + count)?
+            .iter()
+            .fold(0usize, |acc, &b| (acc << 8) | b as usize);
+        (len, 2 + count)
+    };
+    let end = header.checked_add(len)?;
+    Some((input.get(..end)?, input.get(header..end)?, input.get(end..)?))
+}
@@ -0,0 +1,128 @@
+//! Wallet balance history and low-balance alerts.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+/// Samples kept in memory: 24 hours at the default 5-minute interval.
+const MAX_SAMPLES: usize = 288;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceSample {
+    /// Unix millis.
+    pub timestamp: u64,
+    pub balance_plm: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceAlert {
+    pub threshold_plm: f64,
+    pub balance_plm: f64,
+    /// True when the balance dropped below the threshold, false when it recovered.
+    pub below: bool,
+    pub address: String,
+}
+
+/// Recent balance samples, oldest first.
+#[derive(Debug, Default)]
+pub struct BalanceHistory {
+    samples: VecDeque<BalanceSample>,
+}
+
+pub type SharedBalanceHistory = Arc<Mutex<BalanceHistory>>;
+
+impl BalanceHistory {
+    pub fn record(&mut self, balance_plm: f64) {
+        if self.samples.len() == MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(BalanceSample {
+            timestamp: crate::ledger::unix_millis(),
+            balance_plm,
+        });
+    }
+
+    pub fn samples(&self) -> Vec<BalanceSample> {
+        self.samples.iter().cloned().collect()
+    }
+
+    pub fn latest(&self) -> Option<&BalanceSample> {
+        self.samples.back()
+    }
+}
+
+/// Thresholds crossed going from `previous` to `current`, as (threshold, below).
+fn crossed(thresholds: &[f64], previous: f64, current: f64) -> Vec<(f64, bool)> {
+    thresholds
+        .iter()
+        .filter_map(|&t| {
+            if previous >= t && current < t {
+                Some((t, true))
+            } else if previous < t && current >= t {
+                Some((t, false))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Poll the wallet balance every `interval_secs` into `history`, calling `on_alert`
+/// whenever it crosses one of `thresholds_plm`. A balance already below a threshold
+/// on the first sample alerts too.
+pub fn start_balance_watcher<F>(
+    rpc_url: String,
+    private_key: String,
+    thresholds_plm: Vec<f64>,
+    history: SharedBalanceHistory,
+    interval_secs: u64,
+    on_alert: F,
+) -> tokio::task::JoinHandle<()>
+where
+    F: Fn(BalanceAlert) + Send + 'static,
+{
+    tokio::spawn(async move {
+        let client = crate::http::client();
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+        let mut previous = f64::INFINITY;
+        loop {
+            interval.tick().await;
+            let (balance, address) =
+                match crate::system::check_wallet_balance(&client, &rpc_url, &private_key).await {
+                    Ok(b) => b,
+                    Err(e) => {
+                        log::debug!("Balance check failed: {}", e);
+                        continue;
+                    }
+                };
+            let Ok(balance_plm) = balance.parse::<f64>() else {
+                continue;
+            };
+
+            if let Ok(mut h) = history.lock() {
+                h.record(balance_plm);
+            }
+            for (threshold_plm, below) in crossed(&thresholds_plm, previous, balance_plm) {
+                if below {
+                    log::warn!(
+                        "Wallet balance {} PLM dropped below {} PLM",
+                        balance,
+                        threshold_plm
+                    );
+                } else {
+                    log::info!("Wallet balance back above {} PLM", threshold_plm);
+                }
+                on_alert(BalanceAlert {
+                    threshold_plm,
+                    balance_plm,
+                    below,
+                    address: address.clone(),
+                });
+            }
+            previous = balance_plm;
+        }
+    })
+}
@@ -0,0 +1,119 @@
+//! POST agent events from the `events` bus to the configured `webhooks`.
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::config::WebhookConfig;
+use crate::events::{AgentEvent, Event};
+
+const ATTEMPTS: u32 = 3;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Body of every webhook request.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Payload<'a> {
+    /// Agent wallet address.
+    node: &'a str,
+    title: &'a str,
+    message: String,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+/// Whether `hook` wants `event`.
+pub fn wants(hook: &WebhookConfig, event: &AgentEvent) -> bool {
+    hook.events.is_empty() || hook.events.iter().any(|e| e == event.kind())
+}
+
+/// Check URLs and event filters; unknown event kinds are errors so typos don't
+/// silently mute a hook.
+pub fn validate(hooks: &[WebhookConfig]) -> Result<(), String> {
+    for hook in hooks {
+        let url = url::Url::parse(&hook.url).map_err(|e| format!("Invalid webhook URL '{}': {}", hook.url, e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("Webhook URL '{}' must be http or https", hook.url));
+        }
+        if let Some(kind) = hook.events.iter().find(|k| !AgentEvent::KINDS.contains(&k.as_str())) {
+            return Err(format!(
+                "Unknown webhook event '{}' (expected one of {})",
+                kind,
+                AgentEvent::KINDS.join(", ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+async fn deliver(client: &reqwest::Client, url: &str, body: &str) -> Result<(), String> {
+    let mut last_error = String::new();
+    for attempt in 1..=ATTEMPTS {
+        let result = client
+            .post(url)
+            .header("Content-Type", "application/json")
+            .body(body.to_string())
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await;
+        match result {
+            Ok(resp) if resp.status().is_success() => return Ok(()),
+            // A 4xx won't change on retry
+            Ok(resp) if resp.status().is_client_error() => return Err(format!("HTTP {}", resp.status())),
+            Ok(resp) => last_error = format!("HTTP {}", resp.status()),
+            Err(e) => last_error = e.to_string(),
+        }
+        if attempt < ATTEMPTS {
+            tokio::time::sleep(Duration::from_secs(2u64.pow(attempt))).await;
+        }
+    }
+    Err(last_error)
+}
+
+/// Forward every published event to the hooks that want it. Deliveries run
+/// concurrently and retry transient failures; a slow endpoint delays nothing
+/// else. None when no webhooks are configured.
+pub fn start_webhooks(hooks: Vec<WebhookConfig>, node: String) -> Option<tokio::task::JoinHandle<()>> {
+    if hooks.is_empty() {
+        return None;
+    }
+    log::info!("Webhooks enabled ({} endpoint(s))", hooks.len());
+    let mut events = crate::events::subscribe();
+    Some(tokio::spawn(async move {
+        let client = crate::http::client();
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                    log::warn!("Webhooks fell behind; {} event(s) not sent", n);
+                    continue;
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            };
+            let payload = Payload {
+                node: &node,
+                title: event.event.title(),
+                message: event.event.message(),
+                event: &event,
+            };
+            let body = match serde_json::to_string(&payload) {
+                Ok(body) => body,
+                Err(e) => {
+                    log::warn!("Failed to serialize webhook payload: {}", e);
+                    continue;
+                }
+            };
+            for hook in hooks.iter().filter(|h| wants(h, &event.event)) {
+                let client = client.clone();
+                let url = hook.url.clone();
+                let body = body.clone();
+                let kind = event.event.kind();
+                crate::events::spawn_delivery(async move {
+                    if let Err(e) = deliver(&client, &url, &body).await {
+                        log::warn!("Webhook {} for {} failed: {}", url, kind, e);
+                    }
+                });
+            }
+        }
+    }))
+}
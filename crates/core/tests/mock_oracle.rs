@@ -0,0 +1,97 @@
+//! Registration and metrics reporting against the in-process mock Oracle.
+//! Run with `cargo test -p plumise-agent-core --features testing`.
+
+use plumise_agent_core::chain::crypto::{address_from_key, parse_private_key};
+use plumise_agent_core::inference::metrics::InferenceMetrics;
+use plumise_agent_core::oracle::registry::{self, ClusterAssignment, NodeInfo};
+use plumise_agent_core::oracle::reporter;
+use plumise_agent_core::testing::MockOracle;
+
+const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+async fn register(oracle: &MockOracle) -> Result<registry::Registration, String> {
+    let signing_key = parse_private_key(TEST_KEY)?;
+    registry::register(
+        &reqwest::Client::new(),
+        oracle.url(),
+        &signing_key,
+        "inference",
+        "Qwen/Qwen3-32B-GGUF",
+        18920,
+        "203.0.113.5",
+        None,
+        None,
+        false,
+        "192.168.1.20",
+        false,
+        &NodeInfo::default(),
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tokio::test]
+async fn register_round_trip() {
+    let oracle = MockOracle::start().await.unwrap();
+    let assignment = ClusterAssignment {
+        mode: "coordinator".to_string(),
+        cluster_id: Some("cluster-1".to_string()),
+        rpc_port: 50052,
+        rpc_peers: Some(vec!["192.168.1.21:50052".to_string()]),
+    };
+    oracle.set_assignment(Some(assignment.clone()));
+
+    let registration = register(&oracle).await.unwrap();
+    assert_eq!(registration.assignment, Some(assignment));
+
+    let registrations = oracle.registrations();
+    assert_eq!(registrations.len(), 1);
+    let body = &registrations[0];
+    let address = address_from_key(&parse_private_key(TEST_KEY).unwrap());
+    assert_eq!(body["address"], address.as_str());
+    assert_eq!(body["endpoint"], "http://203.0.113.5:18920");
+    assert_eq!(body["capabilities"][0], "inference");
+    assert_eq!(body["capabilities"][1], "Qwen/Qwen3-32B-GGUF");
+    assert_eq!(body["lanIp"], "192.168.1.20");
+    assert!(oracle.rejected().is_empty());
+    assert!(oracle.pipeline_requests().is_empty());
+}
+
+#[tokio::test]
+async fn register_rejected_by_oracle() {
+    let oracle = MockOracle::start().await.unwrap();
+    oracle.reject_registrations(Some("Model not served".to_string()));
+
+    let err = register(&oracle).await.unwrap_err();
+    assert!(err.contains("Model not served"), "unexpected error: {}", err);
+    assert!(oracle.registrations().is_empty());
+    assert!(oracle.rejected().is_empty());
+}
+
+#[tokio::test]
+async fn report_metrics_round_trip() {
+    let oracle = MockOracle::start().await.unwrap();
+    let signing_key = parse_private_key(TEST_KEY).unwrap();
+    let metrics = InferenceMetrics {
+        total_tokens: 1234,
+        total_requests: 7,
+        uptime: 3600,
+        ..Default::default()
+    };
+
+    reporter::report_metrics(&reqwest::Client::new(), oracle.url(), &signing_key, &metrics, false, None)
+        .await
+        .unwrap();
+    reporter::report_metrics(&reqwest::Client::new(), oracle.url(), &signing_key, &metrics, true, None)
+        .await
+        .unwrap();
+
+    let reports = oracle.metrics_reports();
+    assert_eq!(reports.len(), 2);
+    assert_eq!(reports[0]["address"], address_from_key(&signing_key).as_str());
+    assert_eq!(reports[0]["totalTokens"], 1234);
+    assert_eq!(reports[0]["totalRequests"], 7);
+    assert_eq!(reports[0]["status"], "serving");
+    assert_eq!(reports[1]["status"], "paused");
+    assert!(oracle.rejected().is_empty());
+}
@@ -1,15 +1,51 @@
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tauri::{AppHandle, Emitter, Manager};
 use tauri_plugin_shell::process::CommandEvent;
 use tauri_plugin_shell::ShellExt;
 use tokio::sync::Mutex;
 
+use plumise_agent_core::availability::{Availability, PauseSource};
 use plumise_agent_core::chain;
-use plumise_agent_core::config::AgentConfig;
-use plumise_agent_core::oracle;
+use plumise_agent_core::config::{AgentConfig, ClusterConfig, ServeMode};
+use plumise_agent_core::crash;
+use plumise_agent_core::gpu_probe::{self, GpuProbeResult};
+use plumise_agent_core::inference::energy::{self, SharedEnergyMeter};
+use plumise_agent_core::inference::metrics::{MetricsHistory, SharedSlots, SlotStatus, Throughput};
+use plumise_agent_core::inference::playground::{self, TestInferenceResult};
+use plumise_agent_core::inference::proof_batch::ProofBatcher;
+use plumise_agent_core::inference::rpc_health;
+use plumise_agent_core::inference::stats::{LatencyReport, SharedLatencyStats};
+use plumise_agent_core::lifetime::{self, LifetimeStats, LifetimeTracker, SharedLifetime};
+use plumise_agent_core::oracle::{
+    self,
+    attestation::UptimeAttester,
+    penalty::PenaltyStatus,
+    registry::{ClusterAssignment, UpdateRequired},
+    usage::UsageReporter,
+};
+use plumise_agent_core::power::{self, BatteryPolicy};
+use plumise_agent_core::preflight::{self, PreflightResult};
+use plumise_agent_core::process;
+use plumise_agent_core::process::llama_log::LlamaLog;
+use plumise_agent_core::relay::client::{ModelRoute, ModelRoutes};
+use plumise_agent_core::schedule::{Schedule, ScheduleStatus};
 use plumise_agent_core::system;
+use plumise_agent_core::wallet::{self, SharedBalanceHistory};
+
+use crate::commands::logs::LogBuffer;
+use crate::notify::{self, Notice};
+
+/// How long a freshly spawned rpc-server gets to open its port.
+const RPC_READY_TIMEOUT_SECS: u64 = 30;
+/// How long `switch_model`'s new llama-server gets to load its model.
+const SWAP_READY_TIMEOUT_SECS: u64 = 600;
+/// How long the retiring llama-server gets to finish its in-flight requests.
+const SWAP_DRAIN_TIMEOUT_SECS: u64 = 120;
+/// Grace period for the retiring llama-server when its /metrics can't say it is idle.
+const SWAP_DRAIN_BLIND_SECS: u64 = 30;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "kebab-case")]
@@ -36,6 +72,8 @@ pub enum AgentStatus {
     Running,
     Stopping,
     Error,
+    /// Waiting for the next serving window.
+    Scheduled,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,6 +81,27 @@ pub enum AgentStatus {
 pub struct AgentStatusResponse {
     pub status: AgentStatus,
     pub http_port: u16,
+    /// The Oracle asked for a newer agent at the last registration.
+    pub update_required: Option<UpdateRequired>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UptimeStreakReport {
+    /// Attestations signed over all runs.
+    pub attestations: u64,
+    /// Current unbroken streak; 0 once reports have lapsed.
+    pub streak_secs: u64,
+}
+
+impl UptimeStreakReport {
+    fn from_attester(attester: &UptimeAttester) -> Self {
+        let streak = attester.streak();
+        Self {
+            attestations: streak.counter,
+            streak_secs: streak.streak_secs(plumise_agent_core::ledger::unix_millis() / 1000),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +116,26 @@ pub struct AgentMetricsResponse {
     pub tps: f64,
     pub node_mode: String,
     pub cluster_id: Option<String>,
+    pub energy_kwh: f64,
+    pub energy_cost: f64,
+    pub power_watts: f64,
+    pub latency: LatencyReport,
+    pub throughput: Throughput,
+    /// llama-server's own CPU, RSS and GPU memory; None while stopped or if unreadable.
+    pub process: Option<system::ProcessUsage>,
+    /// All-time totals including this session, once the agent has run.
+    pub lifetime: Option<LifetimeStats>,
+    /// Signed uptime attestations; see `oracle::attestation`.
+    pub uptime_streak: Option<UptimeStreakReport>,
+    /// Latest penalty status from the Oracle, once polled.
+    pub penalty: Option<PenaltyStatus>,
+    /// Operator-facing advice derived from `penalty`.
+    pub warnings: Vec<String>,
+    /// KV cache fill across llama-server's slots (0.0–1.0).
+    pub kv_cache_usage_ratio: f64,
+    pub kv_cache_tokens: u64,
+    /// Per-slot llama-server state from `/slots`; empty while stopped.
+    pub slots: Vec<SlotStatus>,
 }
 
 // ---- State ----
@@ -68,10 +147,60 @@ pub struct AgentState {
     pub start_time: Option<std::time::Instant>,
     pub background_tasks: Vec<tokio::task::JoinHandle<()>>,
     pub model_path: Option<PathBuf>,
+    pub mmproj_path: Option<PathBuf>,
     pub agent_address: Option<String>,
     pub node_mode: NodeMode,
     pub cluster_id: Option<String>,
     pub rpc_server_pid: Option<u32>,
+    pub energy: SharedEnergyMeter,
+    /// Relay request latency histograms; reset on every launch.
+    pub latency: SharedLatencyStats,
+    /// Counter samples behind the rolling tok/s and request rates; reset on every launch.
+    pub throughput: MetricsHistory,
+    pub electricity_price_kwh: f64,
+    pub benchmark_tok_per_sec: f64,
+    pub availability: Arc<Availability>,
+    /// Serving-hours supervisor; lives across scheduled stops, aborted by `stop_agent`.
+    pub schedule_task: Option<tokio::task::JoinHandle<()>>,
+    /// Inference proofs awaiting the next batch (`proof_submission` != "off").
+    pub proofs: Option<Arc<ProofBatcher>>,
+    /// Per-request usage awaiting the next report (`usage_reporting`).
+    pub usage: Option<Arc<UsageReporter>>,
+    /// Wallet balance samples; kept across agent restarts for the wallet card.
+    pub balance_history: SharedBalanceHistory,
+    /// All-time totals, opened on the first launch and kept across restarts.
+    pub lifetime: Option<SharedLifetime>,
+    /// Config the running agent was launched with, for restarts outside `launch_agent`.
+    pub launch_config: Option<AgentConfig>,
+    /// RPC peers the coordinator llama-server was started with.
+    pub rpc_peers: Vec<String>,
+    /// Inference relay; only runs while llama-server serves requests.
+    pub relay_task: Option<tokio::task::JoinHandle<()>>,
+    /// Latest llama-server `/slots` snapshot, used by the relay for admission control.
+    pub slots: SharedSlots,
+    /// Keeps `slots` fresh; runs alongside `relay_task`.
+    pub slots_task: Option<tokio::task::JoinHandle<()>>,
+    /// Keeps the pooled llama-server connection warm; runs alongside `relay_task`.
+    pub keepalive_task: Option<tokio::task::JoinHandle<()>>,
+    /// Webhook delivery; outlives a stop so the `Stopped` event still goes out,
+    /// replaced on the next launch.
+    pub webhook_task: Option<tokio::task::JoinHandle<()>>,
+    /// Telegram/Discord alerts; same lifetime as `webhook_task`.
+    pub alerts_task: Option<tokio::task::JoinHandle<()>>,
+    /// rpc-server health monitor; only runs in rpc-server mode.
+    pub rpc_health_task: Option<tokio::task::JoinHandle<()>>,
+    /// Latest assignment from the Oracle, applied again when `set_node_mode` returns to "auto".
+    pub oracle_assignment: Option<ClusterAssignment>,
+    /// Set by `set_node_mode`; Oracle reassignments are ignored until "auto".
+    pub mode_override: bool,
+    /// Model -> llama-server port, shared by the relay and the reporter; repointed by `switch_model`.
+    pub model_routes: Option<ModelRoutes>,
+    /// From the latest registration; kept across restarts until the Oracle clears it.
+    pub update_required: Option<UpdateRequired>,
+    /// Uptime attestation counter, opened on the first launch and kept across restarts.
+    pub attester: Option<Arc<UptimeAttester>>,
+    /// Polled by the penalty monitor; kept across restarts.
+    pub penalty: Option<PenaltyStatus>,
 }
 
 impl Default for AgentState {
@@ -83,10 +212,37 @@ impl Default for AgentState {
             start_time: None,
             background_tasks: Vec::new(),
             model_path: None,
+            mmproj_path: None,
             agent_address: None,
             node_mode: NodeMode::Standalone,
             cluster_id: None,
             rpc_server_pid: None,
+            energy: SharedEnergyMeter::default(),
+            latency: SharedLatencyStats::default(),
+            throughput: MetricsHistory::default(),
+            electricity_price_kwh: 0.0,
+            benchmark_tok_per_sec: 0.0,
+            availability: Arc::new(Availability::new()),
+            schedule_task: None,
+            proofs: None,
+            usage: None,
+            balance_history: SharedBalanceHistory::default(),
+            lifetime: None,
+            launch_config: None,
+            rpc_peers: Vec::new(),
+            relay_task: None,
+            slots: SharedSlots::default(),
+            slots_task: None,
+            keepalive_task: None,
+            webhook_task: None,
+            alerts_task: None,
+            rpc_health_task: None,
+            oracle_assignment: None,
+            mode_override: false,
+            model_routes: None,
+            update_required: None,
+            attester: None,
+            penalty: None,
         }
     }
 }
@@ -97,6 +253,88 @@ pub type SharedAgentState = Arc<Mutex<AgentState>>;
 
 #[tauri::command]
 pub async fn start_agent(config: AgentConfig, app: AppHandle) -> Result<(), String> {
+    let schedule = config.schedule()?;
+    if schedule.is_always() {
+        return launch_agent(config, app).await;
+    }
+
+    let state = app.state::<SharedAgentState>();
+    {
+        let mut guard = state.lock().await;
+        if guard.schedule_task.is_some()
+            || guard.status == AgentStatus::Running
+            || guard.status == AgentStatus::Starting
+        {
+            return Err("Agent is already running or starting".into());
+        }
+        let app_sched = app.clone();
+        guard.schedule_task = Some(tokio::spawn(async move {
+            run_schedule(schedule, config, app_sched).await;
+        }));
+    }
+    Ok(())
+}
+
+/// Start and stop the agent at the edges of the configured serving windows.
+async fn run_schedule(schedule: Schedule, config: AgentConfig, app: AppHandle) {
+    let state = Arc::clone(&app.state::<SharedAgentState>().inner());
+    loop {
+        let status = schedule.status();
+        let _ = app.emit("agent-schedule", status.clone());
+
+        let current = state.lock().await.status.clone();
+        if status.active {
+            if current == AgentStatus::Stopped || current == AgentStatus::Scheduled {
+                let _ = app.emit("agent-log", LogEvent {
+                    level: "INFO".to_string(),
+                    message: "Serving window opened, starting agent".to_string(),
+                });
+                if let Err(e) = launch_agent(config.clone(), app.clone()).await {
+                    log::error!("Scheduled start failed: {}", e);
+                    let _ = app.emit("agent-log", LogEvent {
+                        level: "ERROR".to_string(),
+                        message: format!("Scheduled start failed: {}", e),
+                    });
+                }
+            }
+        } else if matches!(
+            current,
+            AgentStatus::Running | AgentStatus::Starting | AgentStatus::Error
+        ) {
+            let _ = app.emit("agent-log", LogEvent {
+                level: "INFO".to_string(),
+                message: "Serving window closed, deregistering and stopping agent".to_string(),
+            });
+            let availability = state.lock().await.availability.clone();
+            availability.pause(PauseSource::Schedule, "Outside serving hours", true);
+            // Give the relay a moment to close the connection cleanly
+            tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+            shutdown_agent(&state, &app, AgentStatus::Scheduled).await;
+        } else if current == AgentStatus::Stopped {
+            state.lock().await.status = AgentStatus::Scheduled;
+            let _ = app.emit("agent-status", AgentStatusEvent {
+                status: AgentStatus::Scheduled,
+            });
+        }
+
+        if !status.active {
+            if let Some(next) = status.next_change {
+                log::info!(
+                    "Outside serving hours; next activation at {}",
+                    plumise_agent_core::schedule::format_local(next)
+                );
+            }
+        }
+        tokio::time::sleep(schedule.time_until_change(std::time::Duration::from_secs(60))).await;
+    }
+}
+
+#[tauri::command]
+pub async fn get_schedule_status(config: AgentConfig) -> Result<ScheduleStatus, String> {
+    Ok(config.schedule()?.status())
+}
+
+async fn launch_agent(config: AgentConfig, app: AppHandle) -> Result<(), String> {
     let state = app.state::<SharedAgentState>();
 
     {
@@ -106,6 +344,13 @@ pub async fn start_agent(config: AgentConfig, app: AppHandle) -> Result<(), Stri
         }
         guard.status = AgentStatus::Starting;
         guard.http_port = config.http_port;
+        notify::set_enabled(config.notifications);
+        guard.energy = SharedEnergyMeter::default();
+        guard.latency = SharedLatencyStats::default();
+        guard.throughput = MetricsHistory::default();
+        guard.electricity_price_kwh = config.electricity_price_kwh;
+        guard.availability = Arc::new(Availability::new());
+        guard.launch_config = Some(config.clone());
     }
 
     // Validate private key
@@ -117,6 +362,34 @@ pub async fn start_agent(config: AgentConfig, app: AppHandle) -> Result<(), Stri
         state.lock().await.status = AgentStatus::Stopped;
         return Err("Invalid private key format. Must be 0x-prefixed hex (66 chars).".into());
     }
+    if let Err(e) = config.static_cluster() {
+        state.lock().await.status = AgentStatus::Stopped;
+        return Err(e);
+    }
+    if let Err(e) = plumise_agent_core::tls_pin::install(&config) {
+        state.lock().await.status = AgentStatus::Stopped;
+        return Err(e);
+    }
+    plumise_agent_core::http::configure(&config);
+    if let Err(e) = plumise_agent_core::webhook::validate(&config.webhooks)
+        .and_then(|()| config.alerts.validate())
+    {
+        state.lock().await.status = AgentStatus::Stopped;
+        return Err(e);
+    }
+    if let Ok(signing_key) = chain::crypto::parse_private_key(&config.private_key) {
+        let address = chain::crypto::address_from_key(&signing_key);
+        let mut guard = state.lock().await;
+        for task in [guard.webhook_task.take(), guard.alerts_task.take()].into_iter().flatten() {
+            task.abort();
+        }
+        guard.webhook_task = plumise_agent_core::webhook::start_webhooks(config.webhooks.clone(), address.clone());
+        guard.alerts_task = plumise_agent_core::alerts::start_alerts(config.alerts.clone(), address);
+    }
+    if let Err(e) = chain::profile::install(&plumise_agent_core::http::client(), &config).await {
+        state.lock().await.status = AgentStatus::Stopped;
+        return Err(e);
+    }
 
     // Derive agent address
     let signing_key = match chain::crypto::parse_private_key(&config.private_key) {
@@ -128,6 +401,10 @@ pub async fn start_agent(config: AgentConfig, app: AppHandle) -> Result<(), Stri
     };
     let agent_address = chain::crypto::address_from_key(&signing_key);
 
+    if let Err(e) = plumise_agent_core::telemetry::init_tracing(config.otlp_endpoint.as_deref()) {
+        log::warn!("Tracing export disabled: {}", e);
+    }
+
     // Resolve model path
     let app_data_dir = app
         .path()
@@ -145,6 +422,8 @@ pub async fn start_agent(config: AgentConfig, app: AppHandle) -> Result<(), Stri
         &models_dir,
         &config.model,
         &config.model_file,
+        Some(config.hf_token.as_str()),
+        Some(config.model_url.as_str()),
         &app,
     )
     .await
@@ -155,6 +434,40 @@ pub async fn start_agent(config: AgentConfig, app: AppHandle) -> Result<(), Stri
             return Err(format!("Model download failed: {}", e));
         }
     };
+    if let Err(e) = crate::model::download::verify_on_start(
+        &models_dir,
+        &config.model_file,
+        config.model_verify_interval(),
+        &app,
+    )
+    .await
+    {
+        state.lock().await.status = AgentStatus::Stopped;
+        return Err(e);
+    }
+
+    // Vision projector from the same repo; a direct model_url can't point at both files
+    let mmproj_path = if config.mmproj_file.is_empty() {
+        None
+    } else {
+        let mirror = Some(config.model_url.as_str()).filter(|u| !u.to_lowercase().ends_with(".gguf"));
+        match crate::model::download::ensure_model(
+            &models_dir,
+            &config.model,
+            &config.mmproj_file,
+            Some(config.hf_token.as_str()),
+            mirror,
+            &app,
+        )
+        .await
+        {
+            Ok(p) => Some(p),
+            Err(e) => {
+                state.lock().await.status = AgentStatus::Stopped;
+                return Err(format!("mmproj download failed: {}", e));
+            }
+        }
+    };
 
     // Kill any leftover llama-server on our port (e.g. from force-quit)
     if let Some(killed) = system::kill_process_on_port(config.http_port) {
@@ -166,45 +479,53 @@ pub async fn start_agent(config: AgentConfig, app: AppHandle) -> Result<(), Stri
         // Brief pause so the OS releases the port
         tokio::time::sleep(std::time::Duration::from_millis(500)).await;
     }
-
-    // Auto-adjust parallel_slots to ensure each slot gets at least 4096 tokens
-    let mut effective_slots = config.parallel_slots;
-    if effective_slots > 1 {
-        let per_slot = config.ctx_size / effective_slots;
-        if per_slot < 4096 {
-            effective_slots = (config.ctx_size / 4096).max(1);
-            log::warn!(
-                "Reduced parallel_slots from {} to {} (per-slot context {} < 4096)",
-                config.parallel_slots, effective_slots, per_slot,
+    let mut config = config;
+    match system::pick_free_port(&config.llama_bind_host(), config.http_port, config.http_port_scan) {
+        Ok(port) if port != config.http_port => {
+            let message = format!(
+                "Port {} is in use by another program, using {}",
+                config.http_port, port
             );
+            log::warn!("{}", message);
             let _ = app.emit("agent-log", LogEvent {
                 level: "WARNING".to_string(),
-                message: format!(
-                    "Auto-adjusted parallel slots: {} → {} (need ≥4096 tokens per slot, ctx={})",
-                    config.parallel_slots, effective_slots, config.ctx_size,
-                ),
+                message,
             });
+            config.http_port = port;
+            let mut guard = state.lock().await;
+            guard.http_port = port;
+            guard.launch_config = Some(config.clone());
+        }
+        Ok(_) => {}
+        Err(e) => {
+            state.lock().await.status = AgentStatus::Stopped;
+            return Err(e);
         }
     }
 
-    // Build llama-server arguments
-    let mut args: Vec<String> = vec![
-        "-m".into(),
-        model_path.to_string_lossy().to_string(),
-        "--host".into(),
-        "0.0.0.0".into(),
-        "--port".into(),
-        config.http_port.to_string(),
-        "-ngl".into(),
-        config.gpu_layers.to_string(),
-        "--ctx-size".into(),
-        config.ctx_size.to_string(),
-        "-np".into(),
-        effective_slots.to_string(),
-        "--jinja".into(),
-    ];
+    // Auto-adjust parallel_slots to ensure each slot gets at least 4096 tokens
+    // (the args below apply it; this only tells the UI)
+    if config.parallel_slots > 1 && config.ctx_size / config.parallel_slots < 4096 {
+        let _ = app.emit("agent-log", LogEvent {
+            level: "WARNING".to_string(),
+            message: format!(
+                "Auto-adjusted parallel slots: {} → {} (need ≥4096 tokens per slot, ctx={})",
+                config.parallel_slots,
+                (config.ctx_size / 4096).max(1),
+                config.ctx_size,
+            ),
+        });
+    }
 
+    // Battery "cpu" mode: keep serving without the GPU while unplugged
+    if effective_gpu_layers(&config) != config.gpu_layers {
+        let _ = app.emit("agent-log", LogEvent {
+            level: "INFO".to_string(),
+            message: "Running on battery: starting llama-server CPU-only".to_string(),
+        });
+    }
 
+    let args = llama_server_args(&config, &model_path, mmproj_path.as_deref(), config.http_port, None);
     let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
 
     // Resolve DLL/backend directories for llama.cpp
@@ -268,6 +589,10 @@ pub async fn start_agent(config: AgentConfig, app: AppHandle) -> Result<(), Stri
         message: format!("Backend search dirs: {:?}", backend_search_dirs),
     });
 
+    // Per-start key so nobody else on the LAN can use this llama-server
+    let api_key = process::generate_api_key()?;
+    process::set_api_key(Some(api_key.clone()));
+
     // Try sidecar first (Tauri handles import DLL resolution)
     let spawn_result = app
         .shell()
@@ -276,6 +601,7 @@ pub async fn start_agent(config: AgentConfig, app: AppHandle) -> Result<(), Stri
             Ok(cmd.args(&args_ref).envs([
                 ("PATH".to_string(), dll_path_env.clone()),
                 ("GGML_BACKEND_DIR".to_string(), backend_path.clone()),
+                (process::API_KEY_ENV.to_string(), api_key.clone()),
             ]))
         })
         .and_then(|cmd| cmd.spawn());
@@ -284,19 +610,22 @@ pub async fn start_agent(config: AgentConfig, app: AppHandle) -> Result<(), Stri
         Ok((rx, child)) => {
             let pid = child.pid();
             log::info!("llama-server spawned via sidecar, PID: {}", pid);
+            limit_sidecar(&config, &app, "llama-server", pid, config.http_port);
 
             let mut guard = state.lock().await;
             guard.pid = Some(pid);
             guard.start_time = Some(std::time::Instant::now());
             guard.model_path = Some(model_path.clone());
+            guard.mmproj_path = mmproj_path.clone();
             guard.agent_address = Some(agent_address.clone());
             drop(guard);
 
             // Handle sidecar events (stdout/stderr + termination)
             let state_ev = Arc::clone(&state.inner());
             let app_ev = app.clone();
+            let log_ev = open_llama_log(&app, &config);
             tokio::spawn(async move {
-                handle_sidecar_events(rx, state_ev, app_ev).await;
+                handle_sidecar_events(rx, pid, state_ev, app_ev, log_ev).await;
             });
         }
         Err(e) => {
@@ -312,6 +641,7 @@ pub async fn start_agent(config: AgentConfig, app: AppHandle) -> Result<(), Stri
             cmd.current_dir(&exe_dir);
             cmd.env("PATH", &dll_path_env);
             cmd.env("GGML_BACKEND_DIR", &exe_dir);
+            cmd.env(process::API_KEY_ENV, &api_key);
             #[cfg(target_os = "windows")]
             {
                 use std::os::windows::process::CommandExt;
@@ -328,12 +658,14 @@ pub async fn start_agent(config: AgentConfig, app: AppHandle) -> Result<(), Stri
             log::info!("llama-server spawned via fallback, PID: {:?}", pid);
 
             // Stream stdout/stderr
+            let llama_log = open_llama_log(&app, &config);
             for stream in [
                 tokio_child.stdout.take().map(StreamKind::Out),
                 tokio_child.stderr.take().map(StreamKind::Err),
             ] {
                 if let Some(kind) = stream {
                     let app_h = app.clone();
+                    let log_h = Arc::clone(&llama_log);
                     tokio::spawn(async move {
                         use tokio::io::{AsyncBufReadExt, BufReader};
                         let mut last_pct: i32 = -1;
@@ -341,13 +673,13 @@ pub async fn start_agent(config: AgentConfig, app: AppHandle) -> Result<(), Stri
                             StreamKind::Out(s) => {
                                 let mut lines = BufReader::new(s).lines();
                                 while let Ok(Some(line)) = lines.next_line().await {
-                                    handle_log_line(&line, &app_h, &mut last_pct);
+                                    handle_log_line(&line, &app_h, &log_h, &mut last_pct);
                                 }
                             }
                             StreamKind::Err(s) => {
                                 let mut lines = BufReader::new(s).lines();
                                 while let Ok(Some(line)) = lines.next_line().await {
-                                    handle_log_line(&line, &app_h, &mut last_pct);
+                                    handle_log_line(&line, &app_h, &log_h, &mut last_pct);
                                 }
                             }
                         }
@@ -360,6 +692,7 @@ pub async fn start_agent(config: AgentConfig, app: AppHandle) -> Result<(), Stri
                 guard.pid = pid;
                 guard.start_time = Some(std::time::Instant::now());
                 guard.model_path = Some(model_path.clone());
+                guard.mmproj_path = mmproj_path.clone();
                 guard.agent_address = Some(agent_address.clone());
             }
 
@@ -369,29 +702,7 @@ pub async fn start_agent(config: AgentConfig, app: AppHandle) -> Result<(), Stri
             tokio::spawn(async move {
                 if let Ok(exit_status) = tokio_child.wait().await {
                     log::warn!("llama-server exited: {:?}", exit_status);
-                    let mut guard = state_exit.lock().await;
-                    if guard.status != AgentStatus::Stopped
-                        && guard.status != AgentStatus::Stopping
-                    {
-                        if guard.node_mode == NodeMode::Coordinator {
-                            log::warn!("Coordinator llama-server crashed — likely RPC peer disconnected");
-                            let _ = app_exit.emit("agent-log", LogEvent {
-                                level: "WARNING".to_string(),
-                                message: "Distributed inference pipeline failed. Cluster will be reassigned on next registration.".to_string(),
-                            });
-                        }
-                        guard.status = AgentStatus::Error;
-                        guard.pid = None;
-                        guard.node_mode = NodeMode::Standalone;
-                        guard.cluster_id = None;
-                        let _ = app_exit.emit("agent-status", AgentStatusEvent {
-                            status: AgentStatus::Error,
-                        });
-                        let _ = app_exit.emit("agent-log", LogEvent {
-                            level: "ERROR".to_string(),
-                            message: system::describe_exit_code(exit_status.code()),
-                        });
-                    }
+                    on_llama_exit(&state_exit, &app_exit, pid, exit_status.code()).await;
                 }
             });
         }
@@ -409,41 +720,22 @@ pub async fn start_agent(config: AgentConfig, app: AppHandle) -> Result<(), Stri
 
 async fn handle_sidecar_events(
     mut rx: tauri::async_runtime::Receiver<CommandEvent>,
+    pid: u32,
     state: SharedAgentState,
     app: AppHandle,
+    llama_log: Arc<LlamaLog>,
 ) {
     let mut last_pct: i32 = -1;
     while let Some(event) = rx.recv().await {
         match event {
             CommandEvent::Stdout(bytes) | CommandEvent::Stderr(bytes) => {
                 if let Ok(line) = String::from_utf8(bytes) {
-                    handle_log_line(&line, &app, &mut last_pct);
+                    handle_log_line(&line, &app, &llama_log, &mut last_pct);
                 }
             }
             CommandEvent::Terminated(payload) => {
                 log::warn!("llama-server terminated: code={:?}", payload.code);
-                let mut guard = state.lock().await;
-                if guard.status != AgentStatus::Stopped && guard.status != AgentStatus::Stopping {
-                    // If coordinator mode crashed (likely RPC peer loss), log specific message
-                    if guard.node_mode == NodeMode::Coordinator {
-                        log::warn!("Coordinator llama-server crashed — likely RPC peer disconnected");
-                        let _ = app.emit("agent-log", LogEvent {
-                            level: "WARNING".to_string(),
-                            message: "Distributed inference pipeline failed. Cluster will be reassigned on next registration.".to_string(),
-                        });
-                    }
-                    guard.status = AgentStatus::Error;
-                    guard.pid = None;
-                    guard.node_mode = NodeMode::Standalone;
-                    guard.cluster_id = None;
-                    let _ = app.emit("agent-status", AgentStatusEvent {
-                        status: AgentStatus::Error,
-                    });
-                    let _ = app.emit("agent-log", LogEvent {
-                        level: "ERROR".to_string(),
-                        message: system::describe_exit_code(payload.code),
-                    });
-                }
+                on_llama_exit(&state, &app, Some(pid), payload.code).await;
                 break;
             }
             _ => {}
@@ -451,6 +743,154 @@ async fn handle_sidecar_events(
     }
 }
 
+/// React to llama-server `pid` exiting. Ignored when it was killed on purpose (its
+/// PID is no longer the current one); a crashed coordinator is handed to
+/// `recover_coordinator`, anything else marks the agent failed.
+async fn on_llama_exit(state: &SharedAgentState, app: &AppHandle, pid: Option<u32>, code: Option<i32>) {
+    let mut guard = state.lock().await;
+    if guard.status == AgentStatus::Stopped
+        || guard.status == AgentStatus::Stopping
+        || guard.pid != pid
+    {
+        return;
+    }
+    guard.pid = None;
+    if let Some(config) = &guard.launch_config {
+        save_crash_record(app, config, code);
+    }
+
+    if guard.node_mode == NodeMode::Coordinator && !guard.rpc_peers.is_empty() {
+        if let Some(config) = guard.launch_config.clone() {
+            log::warn!("Coordinator llama-server crashed — checking RPC peers");
+            let peers = std::mem::take(&mut guard.rpc_peers);
+            guard.status = AgentStatus::Starting;
+            drop(guard);
+            tokio::spawn(recover_coordinator(Arc::clone(state), app.clone(), config, peers, code));
+            return;
+        }
+    }
+    fail_after_exit(&mut guard, app, code);
+}
+
+/// Persist a crash record with the recent log lines for `get_crash_history`.
+fn save_crash_record(app: &AppHandle, config: &AgentConfig, code: Option<i32>) {
+    let Ok(app_data_dir) = app.path().app_data_dir() else {
+        return;
+    };
+    let log_tail = app.state::<LogBuffer>().tail(crash::CRASH_LOG_LINES);
+    let record = crash::CrashRecord::new(code, log_tail, config);
+    match crash::record_crash(&app_data_dir, &record) {
+        Ok(path) => log::info!("Crash record saved to {}", path.display()),
+        Err(e) => log::warn!("Failed to save crash record: {}", e),
+    }
+}
+
+#[tauri::command]
+pub async fn get_crash_history(
+    limit: Option<usize>,
+    app: AppHandle,
+) -> Result<Vec<crash::CrashRecord>, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(crash::crash_history(&app_data_dir, limit.unwrap_or(50)))
+}
+
+/// Mark the agent failed after llama-server exited unexpectedly.
+fn fail_after_exit(guard: &mut AgentState, app: &AppHandle, code: Option<i32>) {
+    if guard.node_mode == NodeMode::Coordinator {
+        let _ = app.emit("agent-log", LogEvent {
+            level: "WARNING".to_string(),
+            message: "Distributed inference pipeline failed. Cluster will be reassigned on next registration.".to_string(),
+        });
+    }
+    guard.status = AgentStatus::Error;
+    guard.pid = None;
+    guard.node_mode = NodeMode::Standalone;
+    guard.cluster_id = None;
+    guard.rpc_peers.clear();
+    let _ = app.emit("agent-status", AgentStatusEvent {
+        status: AgentStatus::Error,
+    });
+    let reason = system::describe_exit_code(code);
+    let _ = app.emit("agent-log", LogEvent {
+        level: "ERROR".to_string(),
+        message: reason.clone(),
+    });
+    plumise_agent_core::events::publish(plumise_agent_core::events::AgentEvent::Crashed {
+        reason: reason.clone(),
+    });
+    notify::notify(app, Notice::AgentCrashed { reason });
+}
+
+/// Drop the RPC peers that stopped answering and restart the coordinator with the
+/// rest, or standalone when none are left. If every peer still answers, the crash
+/// wasn't a lost peer and the agent is marked failed as before.
+/// Boxed because the restarted llama-server's exit handler can call back into this.
+fn recover_coordinator(
+    state: SharedAgentState,
+    app: AppHandle,
+    config: AgentConfig,
+    peers: Vec<String>,
+    code: Option<i32>,
+) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send>> {
+    Box::pin(async move {
+        let measurements = oracle::peers::measure_peers(&peers, config.max_peer_latency_ms).await;
+        let (survivors, dead): (Vec<_>, Vec<_>) =
+            measurements.iter().partition(|m| m.accepted);
+        if dead.is_empty() {
+            let mut guard = state.lock().await;
+            if guard.status == AgentStatus::Starting {
+                fail_after_exit(&mut guard, &app, code);
+            }
+            return;
+        }
+
+        let survivors: Vec<String> = survivors.iter().map(|m| m.peer.clone()).collect();
+        let dead: Vec<&str> = dead.iter().map(|m| m.peer.as_str()).collect();
+        let _ = app.emit("agent-log", LogEvent {
+            level: "WARNING".to_string(),
+            message: if survivors.is_empty() {
+                format!("RPC peer(s) {} lost, restarting standalone", dead.join(", "))
+            } else {
+                format!(
+                    "RPC peer(s) {} lost, restarting coordinator with {} remaining",
+                    dead.join(", "),
+                    survivors.len()
+                )
+            },
+        });
+        let _ = app.emit("peer-measurements", &measurements);
+
+        let cluster_id = state.lock().await.cluster_id.clone();
+        if let Ok(signing_key) = chain::crypto::parse_private_key(&config.private_key) {
+            if let Err(e) = oracle::peers::report_peer_measurements(
+                &plumise_agent_core::http::client(),
+                &config.oracle_url,
+                &signing_key,
+                cluster_id.as_deref(),
+                &measurements,
+            )
+            .await
+            {
+                log::warn!("{}", e);
+            }
+        }
+
+        // Stopped while we were measuring
+        if state.lock().await.status != AgentStatus::Starting {
+            return;
+        }
+        restart_as_coordinator(&state, &app, &config, &survivors).await;
+        let mut guard = state.lock().await;
+        if survivors.is_empty() {
+            guard.node_mode = NodeMode::Standalone;
+            guard.cluster_id = None;
+        }
+    })
+}
+
 enum StreamKind {
     Out(tokio::process::ChildStdout),
     Err(tokio::process::ChildStderr),
@@ -462,90 +902,434 @@ pub async fn stop_agent(app: AppHandle) -> Result<(), String> {
 
     {
         let mut guard = state.lock().await;
+        if let Some(task) = guard.schedule_task.take() {
+            task.abort();
+        }
         if guard.status == AgentStatus::Stopped {
             return Err("Agent is not running".into());
         }
-        guard.status = AgentStatus::Stopping;
+    }
 
-        // Abort background tasks
-        for task in guard.background_tasks.drain(..) {
-            task.abort();
+    shutdown_agent(&state, &app, AgentStatus::Stopped).await;
+    log::info!("Agent stopped");
+    plumise_agent_core::events::publish(plumise_agent_core::events::AgentEvent::Stopped {
+        reason: "Stopped by the operator".to_string(),
+    });
+    Ok(())
+}
+
+/// Switch the running agent to `mode` ("standalone", "rpc-server" or "coordinator" with
+/// `peers`) without a full restart. "auto" goes back to the Oracle's assignment (or the
+/// static cluster config). Returns the resulting node mode.
+#[tauri::command]
+pub async fn set_node_mode(
+    mode: String,
+    peers: Option<Vec<String>>,
+    app: AppHandle,
+) -> Result<String, String> {
+    let state = Arc::clone(app.state::<SharedAgentState>().inner());
+    let (config, oracle_assignment) = {
+        let guard = state.lock().await;
+        match (&guard.status, &guard.launch_config) {
+            (AgentStatus::Running, Some(config)) => (config.clone(), guard.oracle_assignment.clone()),
+            _ => return Err("Agent is not running".into()),
         }
-    }
+    };
 
-    // Force kill process(es)
-    {
-        let mut guard = state.lock().await;
-        if let Some(pid) = guard.pid.take() {
-            system::kill_pid(pid);
+    let (assignment, forced) = match mode.as_str() {
+        "auto" => match config.static_cluster()? {
+            Some(assignment) => (Some(assignment), true),
+            None => (oracle_assignment, false),
+        },
+        "standalone" => (None, true),
+        "coordinator" | "rpc-server" => {
+            let mut forced_config = config.clone();
+            forced_config.cluster = ClusterConfig {
+                role: mode.clone(),
+                peers: peers.unwrap_or_default(),
+            };
+            (forced_config.static_cluster()?, true)
         }
-        // Also kill rpc-server if running
-        if let Some(rpc_pid) = guard.rpc_server_pid.take() {
-            crate::inference::rpc_server::stop_rpc_server(rpc_pid);
+        other => {
+            return Err(format!(
+                "Invalid node mode '{}': expected \"standalone\", \"rpc-server\", \"coordinator\" or \"auto\"",
+                other
+            ))
         }
-        guard.status = AgentStatus::Stopped;
-        guard.start_time = None;
-        guard.node_mode = NodeMode::Standalone;
-        guard.cluster_id = None;
-    }
+    };
+    let signing_key = chain::crypto::parse_private_key(&config.private_key)?;
 
-    let _ = app.emit("agent-status", AgentStatusEvent {
-        status: AgentStatus::Stopped,
+    state.lock().await.mode_override = mode != "auto";
+    let _ = app.emit("agent-log", LogEvent {
+        level: "INFO".to_string(),
+        message: format!("Node mode set to {}", mode),
     });
+    apply_assignment(
+        &state,
+        &app,
+        &config,
+        &plumise_agent_core::http::client(),
+        &signing_key,
+        assignment.as_ref(),
+        forced,
+    )
+    .await;
 
-    log::info!("Agent stopped");
-    Ok(())
+    let node_mode = state.lock().await.node_mode.to_string();
+    Ok(node_mode)
 }
 
-#[tauri::command]
-pub async fn get_agent_status(
-    state: tauri::State<'_, SharedAgentState>,
-) -> Result<AgentStatusResponse, String> {
-    let guard = state.lock().await;
-    Ok(AgentStatusResponse {
-        status: guard.status.clone(),
-        http_port: guard.http_port,
-    })
-}
+/// Serializes `switch_model`.
+static MODEL_SWITCH: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
 
+/// Switch the running agent to another model without downtime: download it, start a
+/// second llama-server on a free port, repoint the relay and Oracle registration once it
+/// is ready, then retire the old server after its in-flight requests finish. The config
+/// on disk is left to the caller; later restarts use the new model.
 #[tauri::command]
-pub async fn get_agent_metrics(
-    state: tauri::State<'_, SharedAgentState>,
-) -> Result<AgentMetricsResponse, String> {
-    let (http_port, model_path, agent_address, uptime, status, node_mode, cluster_id) = {
+pub async fn switch_model(model: String, model_file: String, app: AppHandle) -> Result<(), String> {
+    // Held until the swap finishes or fails; a second call would spawn another server
+    let _switching = MODEL_SWITCH
+        .try_lock()
+        .map_err(|_| "A model switch is already in progress".to_string())?;
+    let state = Arc::clone(app.state::<SharedAgentState>().inner());
+    let (mut config, routes) = {
         let guard = state.lock().await;
-        (
-            guard.http_port,
-            guard.model_path.clone(),
-            guard.agent_address.clone().unwrap_or_default(),
-            guard.start_time.map(|t| t.elapsed().as_secs_f64()).unwrap_or(0.0),
-            guard.status.clone(),
-            guard.node_mode.to_string(),
-            guard.cluster_id.clone(),
-        )
+        if guard.status != AgentStatus::Running {
+            return Err("The agent must be running to switch models".into());
+        }
+        if guard.node_mode != NodeMode::Standalone {
+            return Err(format!("Cannot switch models in {} mode", guard.node_mode));
+        }
+        match (guard.launch_config.clone(), guard.model_routes.clone()) {
+            (Some(config), Some(routes)) => (config, routes),
+            _ => return Err("The agent is still starting".into()),
+        }
     };
-
-    if status != AgentStatus::Running {
-        return Ok(AgentMetricsResponse {
-            status: "stopped".to_string(),
-            model: String::new(),
-            address: agent_address,
-            uptime: 0.0,
-            total_tokens: 0,
-            total_requests: 0,
-            tps: 0.0,
-            node_mode: "standalone".to_string(),
-            cluster_id: None,
-        });
+    if config.model == model && config.model_file == model_file {
+        return Err(format!("{} is already being served", model_file));
     }
+    config.model = model;
+    config.model_file = model_file;
+    // Both belong to the old model
+    config.model_url.clear();
+    config.mmproj_file.clear();
 
-    let client = reqwest::Client::new();
-    let metrics = plumise_agent_core::inference::metrics::fetch_metrics(&client, http_port)
-        .await
-        .unwrap_or_default();
-
-    let model_name = model_path
-        .as_ref()
+    let _ = app.emit("agent-log", LogEvent {
+        level: "INFO".to_string(),
+        message: format!("Switching model to {} ({})", config.model, config.model_file),
+    });
+    let models_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?
+        .join("models");
+    let model_path = crate::model::download::ensure_model(
+        &models_dir,
+        &config.model,
+        &config.model_file,
+        Some(config.hf_token.as_str()),
+        None,
+        &app,
+    )
+    .await
+    .map_err(|e| format!("Model download failed: {}", e))?;
+
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|l| l.local_addr())
+        .map(|a| a.port())
+        .map_err(|e| format!("No free port for the new llama-server: {}", e))?;
+
+    let args = llama_server_args(&config, &model_path, None, port, None);
+    let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+
+    let backend_path = {
+        let mut dirs = Vec::new();
+        if let Ok(resource_dir) = app.path().resource_dir() {
+            dirs.push(resource_dir.to_string_lossy().to_string());
+            let bin_dir = resource_dir.join("binaries");
+            if bin_dir.is_dir() {
+                dirs.push(bin_dir.to_string_lossy().to_string());
+            }
+        }
+        dirs.join(if cfg!(windows) { ";" } else { ":" })
+    };
+    // Same key as the running server: relay requests carry it to both while they overlap
+    let mut envs = vec![("GGML_BACKEND_DIR".to_string(), backend_path)];
+    if let Some(key) = process::api_key() {
+        envs.push((process::API_KEY_ENV.to_string(), key));
+    }
+
+    let (rx, child) = app
+        .shell()
+        .sidecar("llama-server")
+        .map(|cmd| cmd.args(&args_ref).envs(envs))
+        .and_then(|cmd| cmd.spawn())
+        .map_err(|e| format!("Failed to spawn llama-server for {}: {}", config.model_file, e))?;
+    let new_pid = child.pid();
+    log::info!("llama-server for {} spawned on port {}, PID: {}", config.model_file, port, new_pid);
+    limit_sidecar(&config, &app, "llama-server", new_pid, port);
+    // Exits are ignored until the swap makes this the current PID
+    let state_ev = Arc::clone(&state);
+    let app_ev = app.clone();
+    let log_ev = open_llama_log(&app, &config);
+    tokio::spawn(async move {
+        handle_sidecar_events(rx, new_pid, state_ev, app_ev, log_ev).await;
+    });
+
+    let client = plumise_agent_core::http::local();
+    let health_url = format!("http://127.0.0.1:{}/health", port);
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(SWAP_READY_TIMEOUT_SECS);
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+        let ready = match client.get(&health_url).send().await {
+            Ok(resp) => resp
+                .json::<serde_json::Value>()
+                .await
+                .map(|body| body["status"].as_str() == Some("ok"))
+                .unwrap_or(false),
+            Err(_) => false,
+        };
+        if ready {
+            break;
+        }
+        let failure = if state.lock().await.status != AgentStatus::Running {
+            Some("the agent stopped".to_string())
+        } else if !system::is_pid_alive(new_pid) {
+            Some("the new llama-server exited".to_string())
+        } else if std::time::Instant::now() >= deadline {
+            Some(format!("the new llama-server was not ready after {}s", SWAP_READY_TIMEOUT_SECS))
+        } else {
+            None
+        };
+        if let Some(reason) = failure {
+            system::kill_pid(new_pid);
+            return Err(format!("Model switch aborted: {}", reason));
+        }
+    }
+
+    // Repoint everything at once; the relay re-authenticates, the reporter re-registers
+    let oracle_model = config.oracle_model();
+    let (old_pid, old_port) = {
+        let mut guard = state.lock().await;
+        if guard.status != AgentStatus::Running {
+            system::kill_pid(new_pid);
+            return Err("Model switch aborted: the agent stopped".into());
+        }
+        let old_port = routes.primary().map(|r| r.port).unwrap_or(guard.http_port);
+        routes.replace(vec![ModelRoute {
+            model: oracle_model.clone(),
+            port,
+        }]);
+        let old_pid = guard.pid.replace(new_pid);
+        guard.http_port = port;
+        guard.model_path = Some(model_path);
+        guard.mmproj_path = None;
+        if let Some(launch) = guard.launch_config.as_mut() {
+            launch.model = config.model.clone();
+            launch.model_file = config.model_file.clone();
+            launch.model_url.clear();
+            launch.mmproj_file.clear();
+            launch.http_port = port;
+        }
+        (old_pid, old_port)
+    };
+    let _ = app.emit("agent-log", LogEvent {
+        level: "INFO".to_string(),
+        message: format!("Now serving {} on port {}", oracle_model, port),
+    });
+
+    // Let the old server finish what it started before retiring it
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(SWAP_DRAIN_TIMEOUT_SECS);
+    while std::time::Instant::now() < deadline {
+        match plumise_agent_core::inference::metrics::fetch_metrics(&client, old_port).await {
+            Ok(m) if m.slots_processing == 0 => break,
+            Ok(_) => tokio::time::sleep(std::time::Duration::from_secs(2)).await,
+            Err(e) => {
+                // Can't tell whether it is idle; give in-flight requests a fixed grace period
+                let grace = deadline
+                    .saturating_duration_since(std::time::Instant::now())
+                    .min(std::time::Duration::from_secs(SWAP_DRAIN_BLIND_SECS));
+                log::warn!(
+                    "Cannot read /metrics on port {} ({}); retiring it in {}s",
+                    old_port,
+                    e,
+                    grace.as_secs()
+                );
+                tokio::time::sleep(grace).await;
+                break;
+            }
+        }
+    }
+    if let Some(pid) = old_pid {
+        system::kill_pid(pid);
+        log::info!("Retired llama-server PID {} (port {})", pid, old_port);
+    }
+    Ok(())
+}
+
+/// Abort background tasks and kill llama-server/rpc-server, leaving the agent in `final_status`.
+async fn shutdown_agent(state: &SharedAgentState, app: &AppHandle, final_status: AgentStatus) {
+    {
+        let mut guard = state.lock().await;
+        guard.status = AgentStatus::Stopping;
+
+        // Abort background tasks
+        for task in guard.background_tasks.drain(..) {
+            task.abort();
+        }
+        for task in [
+            guard.relay_task.take(),
+            guard.slots_task.take(),
+            guard.keepalive_task.take(),
+            guard.rpc_health_task.take(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            task.abort();
+        }
+        // Persist the partial proof batch; it is submitted on the next start
+        if let Some(batch) = guard.proofs.take().and_then(|p| p.take_batch(true)) {
+            log::info!("Saved {} pending proof(s) for submission on next start", batch.leaf_count);
+        }
+        guard.usage = None;
+    }
+
+    // Force kill process(es)
+    {
+        let mut guard = state.lock().await;
+        if let Some(pid) = guard.pid.take() {
+            system::kill_pid(pid);
+        }
+        // Also kill rpc-server if running
+        if let Some(rpc_pid) = guard.rpc_server_pid.take() {
+            crate::inference::rpc_server::stop_rpc_server(rpc_pid);
+        }
+        guard.status = final_status.clone();
+        guard.start_time = None;
+        guard.node_mode = NodeMode::Standalone;
+        guard.cluster_id = None;
+        guard.rpc_peers.clear();
+        guard.oracle_assignment = None;
+        guard.mode_override = false;
+        guard.model_routes = None;
+    }
+
+    let _ = app.emit("agent-status", AgentStatusEvent {
+        status: final_status,
+    });
+}
+
+/// Stop taking relay requests but keep llama-server and the model loaded, so short
+/// maintenance doesn't cost a reload. The node stays registered and reports "paused".
+#[tauri::command]
+pub async fn pause_agent(state: tauri::State<'_, SharedAgentState>) -> Result<(), String> {
+    let guard = state.lock().await;
+    if guard.status != AgentStatus::Running {
+        return Err("Agent is not running".to_string());
+    }
+    guard.availability.pause(PauseSource::Manual, "Paused for maintenance", false);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn resume_agent(state: tauri::State<'_, SharedAgentState>) -> Result<(), String> {
+    state.lock().await.availability.resume(PauseSource::Manual);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_agent_status(
+    state: tauri::State<'_, SharedAgentState>,
+) -> Result<AgentStatusResponse, String> {
+    let guard = state.lock().await;
+    Ok(AgentStatusResponse {
+        status: guard.status.clone(),
+        http_port: guard.http_port,
+        update_required: guard.update_required.clone(),
+    })
+}
+
+#[tauri::command]
+pub async fn get_agent_metrics(
+    state: tauri::State<'_, SharedAgentState>,
+) -> Result<AgentMetricsResponse, String> {
+    let (http_port, model_path, agent_address, uptime, status, node_mode, cluster_id, energy_report, latency, pid, lifetime, uptime_streak, penalty, slots) = {
+        let guard = state.lock().await;
+        let energy_report = guard
+            .energy
+            .lock()
+            .map(|m| m.report(guard.electricity_price_kwh))
+            .unwrap_or_default();
+        let latency = guard.latency.lock().map(|l| l.report()).unwrap_or_default();
+        (
+            guard.http_port,
+            guard.model_path.clone(),
+            guard.agent_address.clone().unwrap_or_default(),
+            guard.start_time.map(|t| t.elapsed().as_secs_f64()).unwrap_or(0.0),
+            guard.status.clone(),
+            guard.node_mode.to_string(),
+            guard.cluster_id.clone(),
+            energy_report,
+            latency,
+            guard.pid,
+            guard.lifetime.clone(),
+            guard.attester.as_deref().map(UptimeStreakReport::from_attester),
+            guard.penalty.clone(),
+            if guard.slots_task.is_some() { guard.slots.snapshot() } else { Vec::new() },
+        )
+    };
+    let warnings = penalty.as_ref().map(|p| p.warnings()).unwrap_or_default();
+
+    if status != AgentStatus::Running {
+        return Ok(AgentMetricsResponse {
+            status: "stopped".to_string(),
+            model: String::new(),
+            address: agent_address,
+            uptime: 0.0,
+            total_tokens: 0,
+            total_requests: 0,
+            tps: 0.0,
+            node_mode: "standalone".to_string(),
+            cluster_id: None,
+            energy_kwh: energy_report.energy_kwh,
+            energy_cost: energy_report.energy_cost,
+            power_watts: 0.0,
+            latency,
+            throughput: Throughput::default(),
+            process: None,
+            lifetime: lifetime.and_then(|t| t.lock().ok().map(|t| t.stats())),
+            uptime_streak,
+            penalty,
+            warnings,
+            kv_cache_usage_ratio: 0.0,
+            kv_cache_tokens: 0,
+            slots: Vec::new(),
+        });
+    }
+
+    let client = plumise_agent_core::http::local();
+    let metrics = plumise_agent_core::inference::metrics::fetch_metrics(&client, http_port)
+        .await
+        .unwrap_or_default();
+    // llama-server has no request counter; completed relay requests stand in
+    let throughput = state.lock().await.throughput.record(&metrics, latency.total.count);
+    let lifetime = lifetime.and_then(|t| {
+        let mut t = t.lock().ok()?;
+        t.observe(metrics.total_tokens, latency.total.count);
+        Some(t.stats())
+    });
+    let process = match pid {
+        Some(pid) => tokio::task::spawn_blocking(move || system::process_usage(pid))
+            .await
+            .ok()
+            .flatten(),
+        None => None,
+    };
+
+    let model_name = model_path
+        .as_ref()
         .and_then(|p| p.file_name())
         .map(|n| n.to_string_lossy().to_string())
         .unwrap_or_default();
@@ -560,9 +1344,41 @@ pub async fn get_agent_metrics(
         tps: metrics.tps,
         node_mode,
         cluster_id,
+        energy_kwh: energy_report.energy_kwh,
+        energy_cost: energy_report.energy_cost,
+        power_watts: energy_report.current_power_watts,
+        latency,
+        throughput,
+        process,
+        lifetime,
+        uptime_streak,
+        penalty,
+        warnings,
+        kv_cache_usage_ratio: metrics.kv_cache_usage_ratio,
+        kv_cache_tokens: metrics.kv_cache_tokens,
+        slots,
     })
 }
 
+/// All-time totals, from the running tracker or the saved file.
+#[tauri::command]
+pub async fn get_lifetime_stats(
+    state: tauri::State<'_, SharedAgentState>,
+    app: AppHandle,
+) -> Result<LifetimeStats, String> {
+    if let Some(tracker) = state.lock().await.lifetime.clone() {
+        return tracker
+            .lock()
+            .map(|t| t.stats())
+            .map_err(|e| format!("Lifetime stats poisoned: {}", e));
+    }
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(LifetimeStats::load(&app_data_dir))
+}
+
 #[tauri::command]
 pub async fn check_model(config: AgentConfig, app: AppHandle) -> Result<bool, String> {
     let app_data_dir = app
@@ -576,10 +1392,55 @@ pub async fn check_model(config: AgentConfig, app: AppHandle) -> Result<bool, St
             .unwrap_or(false))
 }
 
+/// Run llama-server briefly with `-ngl 1` on a tiny model to confirm GPU offload
+/// works, which backend loaded, and how fast it generates.
+#[tauri::command]
+pub async fn probe_gpu(app: AppHandle) -> Result<GpuProbeResult, String> {
+    let (exe, _) = find_llama_server(&app)?;
+    let models_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?
+        .join("models");
+    let result = gpu_probe::probe_gpu(&exe, &models_dir).await;
+    log::info!("GPU probe: {}", result.message);
+    Ok(result)
+}
+
+/// Playground prompt against the running llama-server. Tokens stream as
+/// "test-inference-token" events; the returned result carries latency and tok/s.
+#[tauri::command]
+pub async fn test_inference(
+    prompt: String,
+    max_tokens: Option<u32>,
+    state: tauri::State<'_, SharedAgentState>,
+    app: AppHandle,
+) -> Result<TestInferenceResult, String> {
+    let http_port = {
+        let guard = state.lock().await;
+        if guard.status != AgentStatus::Running {
+            return Err("Agent is not running".to_string());
+        }
+        guard.http_port
+    };
+    if prompt.trim().is_empty() {
+        return Err("Prompt is empty".to_string());
+    }
+
+    let client = plumise_agent_core::http::local();
+    playground::test_inference(&client, http_port, &prompt, max_tokens.unwrap_or(256), |token| {
+        let _ = app.emit("test-inference-token", TestInferenceTokenEvent {
+            content: token.to_string(),
+        });
+    })
+    .await
+}
+
 // ---- Health Polling ----
 
 async fn poll_agent_health(state: SharedAgentState, app: AppHandle, config: AgentConfig) {
-    let client = reqwest::Client::new();
+    // Also used for the Oracle, so it must expose certificates for pin checks
+    let client = plumise_agent_core::http::client();
     let health_url = format!("http://127.0.0.1:{}/health", config.http_port);
     let mut interval = tokio::time::interval(std::time::Duration::from_secs(3));
     let mut ready_detected = false;
@@ -645,232 +1506,1014 @@ async fn poll_agent_health(state: SharedAgentState, app: AppHandle, config: Agen
             });
             log::info!("llama-server is ready");
 
-            on_agent_ready(&state, &app, &config, &client).await;
+            on_agent_ready(&state, &app, &config, &client).await;
+        }
+    }
+}
+
+/// Store the Oracle's update request, tell the UI ("agent-update-required") and notify
+/// once when it first appears.
+async fn set_update_required(state: &SharedAgentState, app: &AppHandle, update: Option<UpdateRequired>) {
+    {
+        let mut guard = state.lock().await;
+        if guard.update_required == update {
+            return;
+        }
+        guard.update_required = update.clone();
+    }
+    let _ = app.emit("agent-update-required", update.clone());
+    if let Some(update) = update {
+        let _ = app.emit("agent-log", LogEvent {
+            level: "ERROR".to_string(),
+            message: format!(
+                "Agent update required: running {}, the Oracle requires {} or newer",
+                update.current_version, update.min_supported_version
+            ),
+        });
+        notify::notify(app, Notice::UpdateRequired {
+            current: update.current_version,
+            minimum: update.min_supported_version,
+        });
+    }
+}
+
+/// Called when llama-server health reports "ok" — registers with Oracle (which handles on-chain registration)
+async fn on_agent_ready(
+    state: &SharedAgentState,
+    app: &AppHandle,
+    config: &AgentConfig,
+    client: &reqwest::Client,
+) {
+    let signing_key = match chain::crypto::parse_private_key(&config.private_key) {
+        Ok(k) => k,
+        Err(e) => {
+            log::error!("Private key parse error: {}", e);
+            return;
+        }
+    };
+
+    // On-chain registration and heartbeats are handled by Oracle via sponsor flow.
+    // Agent wallet does not need PLM balance.
+
+    // Detect LAN IP for external access (Oracle/inference API need to reach us)
+    let local_ip = system::advertised_ip(&config.oracle_url, &config.llama_bind_host())
+        .await
+        .unwrap_or_else(|| "127.0.0.1".to_string());
+    log::info!("Detected local IP: {}", local_ip);
+    let _ = app.emit("agent-log", LogEvent {
+        level: "INFO".to_string(),
+        message: format!("Local endpoint: {}", system::http_endpoint(&local_ip, config.http_port)),
+    });
+
+    if let Err(e) = plumise_agent_core::inference::metrics::probe_metrics(client, config.http_port).await {
+        log::warn!("{}", e);
+        let _ = app.emit("agent-log", LogEvent {
+            level: "WARNING".to_string(),
+            message: e,
+        });
+    }
+
+    // Map HuggingFace GGUF repo name to oracle model ID
+    let serve_mode = config.serve_mode();
+    let oracle_model = config.oracle_model();
+
+    // 1. Oracle registration (Oracle will sponsor on-chain registration if needed)
+    // Run benchmark (completion throughput; not applicable to embedding/rerank servers)
+    let benchmark = if serve_mode != ServeMode::Chat {
+        None
+    } else {
+        match plumise_agent_core::inference::benchmark::run_benchmark(client, config.http_port).await {
+            Ok(result) => {
+                let _ = app.emit(
+                    "agent-log",
+                    format!(
+                        "Benchmark: prompt {:.1} tok/s, generation {:.2} tok/s",
+                        result.prompt_tok_per_sec, result.gen_tok_per_sec
+                    ),
+                );
+                Some(result)
+            }
+            Err(e) => {
+                log::warn!("Benchmark failed (using default): {}", e);
+                let _ = app.emit("agent-log", format!("Benchmark skipped: {}", e));
+                None
+            }
+        }
+    };
+    state.lock().await.benchmark_tok_per_sec =
+        benchmark.as_ref().map(|b| b.gen_tok_per_sec).unwrap_or(0.0);
+
+    let network = match oracle::network::run_network_benchmark(client, &config.oracle_url).await {
+        Ok(result) => {
+            let _ = app.emit(
+                "agent-log",
+                format!("Network: {:.0} ms latency to Oracle", result.latency_ms),
+            );
+            Some(result)
+        }
+        Err(e) => {
+            log::warn!("Network benchmark failed: {}", e);
+            None
+        }
+    };
+
+    // Determine if distributed inference is enabled (never for small embedding/rerank models).
+    // A static cluster is never offered to the Oracle for assignment.
+    let static_cluster = config.static_cluster().unwrap_or_default();
+    let can_distribute = static_cluster.is_none()
+        && config.distributed_mode != "disabled"
+        && serve_mode == ServeMode::Chat;
+
+    // Make sure the endpoint we advertise can actually be reached before registering it
+    let reachability = oracle::reachability::check_endpoint(
+        client,
+        &config.oracle_url,
+        &signing_key,
+        &config.llama_bind_host(),
+        &local_ip,
+        config.http_port,
+    )
+    .await;
+    let _ = app.emit("agent-log", LogEvent {
+        level: if reachability.reachable == Some(true) { "INFO" } else { "WARN" }.to_string(),
+        message: reachability.message.clone(),
+    });
+    let _ = app.emit("agent-reachability", reachability.clone());
+    let relay_only = reachability.relay_only();
+    let geo = app
+        .path()
+        .app_data_dir()
+        .ok()
+        .and_then(|dir| oracle::geo::GeoLocator::from_config(config, &dir));
+    let mut node_info = oracle::registry::NodeInfo::from_config(config);
+    node_info.llama_version = plumise_agent_core::inference::metrics::fetch_build_info(client, config.http_port)
+        .await
+        .ok();
+    if let Some(geo) = &geo {
+        node_info.location = geo.locate(client).await;
+    }
+
+    let registration = oracle::registry::register(
+        client,
+        &config.oracle_url,
+        &signing_key,
+        serve_mode.capability(),
+        &oracle_model,
+        config.http_port,
+        &local_ip,
+        benchmark.as_ref(),
+        network.as_ref(),
+        can_distribute,
+        &local_ip,
+        relay_only,
+        &node_info,
+    )
+    .await;
+    let (assignment, update_required) = match registration {
+        Ok(registration) => {
+            let mode_str = registration.assignment.as_ref().map(|a| a.mode.as_str()).unwrap_or("standalone");
+            let _ = app.emit("agent-log", LogEvent {
+                level: "INFO".to_string(),
+                message: format!("Registered with Oracle (mode: {})", mode_str),
+            });
+            set_update_required(state, app, registration.update_required.clone()).await;
+            (registration.assignment, registration.update_required)
+        }
+        Err(e) => {
+            log::warn!("Oracle registration failed (non-fatal): {}", e);
+            let _ = app.emit("agent-log", LogEvent {
+                level: "WARNING".to_string(),
+                message: format!("Oracle registration failed: {}", e),
+            });
+            (None, None)
+        }
+    };
+
+    plumise_agent_core::events::publish(plumise_agent_core::events::AgentEvent::Started {
+        model: oracle_model.clone(),
+        endpoint: system::http_endpoint(&local_ip, config.http_port),
+    });
+
+    // A static cluster replaces the Oracle's assignment
+    let forced = static_cluster.is_some();
+    let applied = match static_cluster {
+        Some(assignment) => {
+            let _ = app.emit("agent-log", LogEvent {
+                level: "INFO".to_string(),
+                message: format!("Static cluster config: running as {}", assignment.mode),
+            });
+            Some(assignment)
+        }
+        None => assignment.clone(),
+    };
+    state.lock().await.oracle_assignment = assignment.clone();
+    apply_assignment(state, app, config, client, &signing_key, applied.as_ref(), forced).await;
+
+    // Shared by the relay and the reporter so `switch_model` can repoint both
+    let routes = ModelRoutes::single(oracle_model.clone(), config.http_port);
+    state.lock().await.model_routes = Some(routes.clone());
+
+    // 2. Spawn background tasks (metrics reporter + periodic re-registration)
+    let (availability, attester) = {
+        let mut guard = state.lock().await;
+        if guard.attester.is_none() {
+            match app.path().app_data_dir() {
+                Ok(app_data_dir) => guard.attester = Some(UptimeAttester::open(&app_data_dir, &signing_key)),
+                Err(e) => log::warn!("Uptime attestations disabled: {}", e),
+            }
+        }
+        (guard.availability.clone(), guard.attester.clone())
+    };
+    let reporter_handle = plumise_agent_core::oracle::reporter::start_reporter(
+        client.clone(),
+        plumise_agent_core::oracle::reporter::ReporterConfig {
+            oracle_url: config.oracle_url.clone(),
+            signing_key: signing_key.clone(),
+            llama_port: config.http_port,
+            registration: plumise_agent_core::oracle::reporter::RegistrationParams {
+                serve_mode,
+                model: oracle_model.clone(),
+                http_port: config.http_port,
+                external_ip: local_ip.clone(),
+                benchmark,
+                network,
+                rebenchmark_hours: config.rebenchmark_hours,
+                can_distribute,
+                lan_ip: local_ip.clone(),
+                relay_only,
+                assignment,
+                update_required,
+                routes: Some(routes),
+                node_info,
+                geo,
+                attester,
+            },
+            availability: Some(availability.clone()),
+            // A static cluster ignores the Oracle's assignment
+            on_assignment: (!forced).then(|| {
+                let app = app.clone();
+                let config = config.clone();
+                let client = client.clone();
+                let signing_key = signing_key.clone();
+                Box::new(move |assignment: Option<ClusterAssignment>| {
+                    let app = app.clone();
+                    let config = config.clone();
+                    let client = client.clone();
+                    let signing_key = signing_key.clone();
+                    tokio::spawn(async move {
+                        let state = Arc::clone(app.state::<SharedAgentState>().inner());
+                        {
+                            let mut guard = state.lock().await;
+                            guard.oracle_assignment = assignment.clone();
+                            if guard.mode_override {
+                                log::info!("Node mode set manually, not applying the Oracle's new assignment");
+                                return;
+                            }
+                        }
+                        apply_assignment(&state, &app, &config, &client, &signing_key, assignment.as_ref(), false)
+                            .await;
+                    });
+                }) as plumise_agent_core::oracle::reporter::AssignmentHandler
+            }),
+            on_oracle_status: {
+                let app = app.clone();
+                let notified = std::sync::atomic::AtomicBool::new(false);
+                Some(Box::new(move |down_for: Option<std::time::Duration>| match down_for {
+                    Some(down_for) if down_for >= notify::ORACLE_UNREACHABLE_AFTER => {
+                        if !notified.swap(true, std::sync::atomic::Ordering::Relaxed) {
+                            notify::notify(&app, Notice::OracleUnreachable { down_for });
+                        }
+                    }
+                    Some(_) => {}
+                    None => notified.store(false, std::sync::atomic::Ordering::Relaxed),
+                }) as plumise_agent_core::oracle::reporter::OracleStatusHandler)
+            },
+            on_update_required: {
+                let app = app.clone();
+                Some(Box::new(move |update: Option<UpdateRequired>| {
+                    let app = app.clone();
+                    tokio::spawn(async move {
+                        let state = Arc::clone(app.state::<SharedAgentState>().inner());
+                        set_update_required(&state, &app, update).await;
+                    });
+                }) as plumise_agent_core::oracle::reporter::UpdateRequiredHandler)
+            },
+        },
+    );
+
+    // Failed jobs and downtime the Oracle holds against this node
+    let penalty_handle = oracle::penalty::start_penalty_monitor(
+        client.clone(),
+        config.oracle_url.clone(),
+        chain::crypto::address_from_key(&signing_key),
+        oracle::penalty::POLL_INTERVAL_SECS,
+        {
+            let app = app.clone();
+            move |status: PenaltyStatus| {
+                let app = app.clone();
+                tokio::spawn(async move {
+                    let state = Arc::clone(app.state::<SharedAgentState>().inner());
+                    let previous = state.lock().await.penalty.replace(status.clone());
+                    let known = previous.as_ref().map(|p| p.warnings()).unwrap_or_default();
+                    for warning in status.warnings().into_iter().filter(|w| !known.contains(w)) {
+                        let _ = app.emit("agent-log", LogEvent {
+                            level: "WARNING".to_string(),
+                            message: warning,
+                        });
+                    }
+                    if status.penalized && !previous.is_some_and(|p| p.penalized) {
+                        notify::notify(&app, Notice::Penalized {
+                            reason: status.warnings().into_iter().next().unwrap_or_default(),
+                        });
+                    }
+                });
+            }
+        },
+    );
+
+    let mut guard = state.lock().await;
+    guard.background_tasks.push(reporter_handle);
+    guard.background_tasks.push(penalty_handle);
+    let energy_handle = energy::start_energy_sampler(guard.energy.clone(), 15);
+    guard.background_tasks.push(energy_handle);
+    if guard.lifetime.is_none() {
+        match app.path().app_data_dir() {
+            Ok(app_data_dir) => {
+                guard.lifetime = Some(Arc::new(std::sync::Mutex::new(LifetimeTracker::open(&app_data_dir))));
+            }
+            Err(e) => log::warn!("Lifetime stats disabled: {}", e),
+        }
+    }
+    let lifetime_tracker = guard.lifetime.clone();
+    if let Some(tracker) = &lifetime_tracker {
+        if let Ok(mut t) = tracker.lock() {
+            t.record_launch();
+        }
+        let lifetime_routes = guard.model_routes.clone();
+        guard.background_tasks.push(lifetime::start_lifetime_recorder(
+            tracker.clone(),
+            move || lifetime_routes.as_ref()?.primary().map(|route| route.port),
+            Some(guard.latency.clone()),
+            60,
+        ));
+    }
+    let app_gpu = app.clone();
+    guard.background_tasks.push(system::start_gpu_monitor(10, move |gpus| {
+        let _ = app_gpu.emit("gpu-telemetry", gpus);
+    }));
+    guard
+        .background_tasks
+        .push(forward_availability_events(availability.clone(), app.clone()));
+    guard.background_tasks.push(forward_pin_failures(app.clone()));
+    let app_rewards = app.clone();
+    let claim_history = app
+        .path()
+        .app_data_dir()
+        .ok()
+        .and_then(|dir| plumise_agent_core::claims::ClaimHistory::open(&dir).ok())
+        .map(Arc::new);
+    let payout_address = config.payout_address().ok().flatten();
+    let forward_rpc = config.chain_rpc.clone();
+    let forward_key = signing_key.clone();
+    guard.background_tasks.push(chain::events::start_reward_watcher(
+        config.chain_rpc.clone(),
+        Some(config.chain_ws_url()),
+        chain::crypto::address_from_key(&signing_key),
+        move |event| {
+            if event.kind == chain::events::RewardEventKind::Claimed {
+                if let Some(mut t) = lifetime_tracker.as_ref().and_then(|t| t.lock().ok()) {
+                    t.record_claim(event.amount_plm);
+                    let _ = t.save();
+                }
+                match claim_history.as_ref().map(|h| h.record_event(&event)) {
+                    Some(Ok(true)) => {
+                        if let (Some(to), Some(history)) = (payout_address, claim_history.clone()) {
+                            let (rpc, key, event) =
+                                (forward_rpc.clone(), forward_key.clone(), event.clone());
+                            tokio::spawn(async move {
+                                if let Err(e) = plumise_agent_core::claims::forward_claimed(
+                                    &rpc, &key, to, &event, &history,
+                                )
+                                .await
+                                {
+                                    log::warn!("Failed to forward claimed rewards: {}", e);
+                                }
+                            });
+                        }
+                    }
+                    Some(Err(e)) => log::warn!("Failed to record claim: {}", e),
+                    _ => {}
+                }
+                notify::notify(&app_rewards, Notice::RewardClaimed {
+                    amount_plm: event.amount_plm,
+                });
+            }
+            let _ = app_rewards.emit("reward-event", event);
+        },
+    ));
+    let app_balance = app.clone();
+    guard.background_tasks.push(wallet::start_balance_watcher(
+        config.chain_rpc.clone(),
+        config.private_key.clone(),
+        config.balance_alert_plm.clone(),
+        guard.balance_history.clone(),
+        config.balance_check_secs.max(30),
+        move |alert| {
+            let _ = app_balance.emit("agent-log", LogEvent {
+                level: if alert.below { "WARNING" } else { "INFO" }.to_string(),
+                message: if alert.below {
+                    format!(
+                        "Wallet balance {:.4} PLM is below {} PLM",
+                        alert.balance_plm, alert.threshold_plm
+                    )
+                } else {
+                    format!("Wallet balance back above {} PLM", alert.threshold_plm)
+                },
+            });
+            let _ = app_balance.emit("balance-alert", alert);
+        },
+    ));
+
+    let idle_policy = config.idle_policy();
+    if idle_policy.enabled() {
+        let _ = app.emit("agent-log", LogEvent {
+            level: "INFO".to_string(),
+            message: format!(
+                "Idle policy '{}': serving only after {}s without input",
+                idle_policy.mode, idle_policy.idle_threshold_secs
+            ),
+        });
+        guard
+            .background_tasks
+            .push(plumise_agent_core::idle::start_idle_watcher(idle_policy, availability.clone()));
+    }
+
+    let battery_policy = config.battery_policy();
+    if battery_policy != BatteryPolicy::Off {
+        let app_power = app.clone();
+        let config_power = config.clone();
+        guard.background_tasks.push(power::start_battery_watcher(
+            battery_policy,
+            availability.clone(),
+            15,
+            move |status| {
+                let _ = app_power.emit("agent-power", status.clone());
+                if battery_policy == BatteryPolicy::Cpu {
+                    // Detached: the restart aborts this watcher along with the other background tasks
+                    let app = app_power.clone();
+                    let fallback = config_power.clone();
+                    let message = if status.on_battery() {
+                        "Switched to battery power: restarting llama-server CPU-only"
+                    } else {
+                        "AC power restored: restarting llama-server with GPU offload"
+                    };
+                    tokio::spawn(async move {
+                        let config = current_launch_config(&app, fallback).await;
+                        restart_llama(app, config, message.to_string()).await;
+                    });
+                }
+            },
+        ));
+    }
+
+    let memory_policy = config.memory_pressure_policy();
+    if memory_policy != system::MemoryPressurePolicy::Off {
+        let app_memory = app.clone();
+        let config_memory = config.clone();
+        guard.background_tasks.push(system::start_memory_pressure_watcher(
+            memory_policy,
+            availability.clone(),
+            10,
+            move |level| {
+                let _ = app_memory.emit("memory-pressure", level);
+                if level == system::MemoryPressure::Normal {
+                    return;
+                }
+                let _ = app_memory.emit("agent-log", LogEvent {
+                    level: "WARNING".to_string(),
+                    message: if level == system::MemoryPressure::Critical {
+                        "Memory pressure is critical: the model may be killed by the OS".to_string()
+                    } else {
+                        "Memory pressure is elevated".to_string()
+                    },
+                });
+                if memory_policy == system::MemoryPressurePolicy::ReduceCtx
+                    && level == system::MemoryPressure::Critical
+                {
+                    let app = app_memory.clone();
+                    let fallback = config_memory.clone();
+                    tokio::spawn(async move {
+                        let mut config = current_launch_config(&app, fallback).await;
+                        if config.ctx_size <= system::MIN_REDUCED_CTX {
+                            return;
+                        }
+                        config.ctx_size = (config.ctx_size / 2).max(system::MIN_REDUCED_CTX);
+                        let message = format!(
+                            "Critical memory pressure: restarting llama-server with ctx_size {}",
+                            config.ctx_size
+                        );
+                        restart_llama(app, config, message).await;
+                    });
+                }
+            },
+        ));
+    }
+
+    // Only start WS relay if NOT in rpc-server mode (rpc-servers don't serve requests)
+    if guard.node_mode != NodeMode::RpcServer && guard.relay_task.is_none() {
+        start_relay_task(&mut guard, app, config, &signing_key, client);
+    }
+}
+
+/// Bring the node into the mode `assignment` asks for: rpc-server, coordinator with
+/// its peers, or standalone. Used at startup and whenever the Oracle reassigns us;
+/// a mode the node is already in is left running. `forced` skips the
+/// distributed_mode / serve-mode checks (static cluster config).
+async fn apply_assignment(
+    state: &SharedAgentState,
+    app: &AppHandle,
+    config: &AgentConfig,
+    client: &reqwest::Client,
+    signing_key: &chain::crypto::SigningKey,
+    assignment: Option<&ClusterAssignment>,
+    forced: bool,
+) {
+    let distributed = forced
+        || (config.distributed_mode != "standalone"
+            && config.distributed_mode != "disabled"
+            && config.serve_mode() == ServeMode::Chat);
+    let mode = match assignment {
+        Some(a) if distributed => a.mode.as_str(),
+        _ => "standalone", // User forced standalone, or embedding/rerank mode
+    };
+    let cluster_id = assignment.and_then(|a| a.cluster_id.clone());
+    let current = state.lock().await.node_mode.clone();
+
+    match mode {
+        "rpc-server" => {
+            if current == NodeMode::RpcServer {
+                state.lock().await.cluster_id = cluster_id;
+                return;
+            }
+            // RPC Server mode: stop llama-server, start rpc-server
+            let _ = app.emit("agent-log", LogEvent {
+                level: "INFO".to_string(),
+                message: "Switching to RPC Server mode for distributed inference".to_string(),
+            });
+
+            // Kill llama-server and the relay; rpc-servers don't serve requests
+            {
+                let mut guard = state.lock().await;
+                if let Some(pid) = guard.pid.take() {
+                    system::kill_pid(pid);
+                }
+                guard.rpc_peers.clear();
+                for task in [guard.relay_task.take(), guard.slots_task.take(), guard.keepalive_task.take()]
+                    .into_iter()
+                    .flatten()
+                {
+                    task.abort();
+                }
+            }
+
+            let rpc_port = assignment.map(|a| a.rpc_port).unwrap_or(config.rpc_port);
+
+            // Keep the node out of the Oracle until the RPC port accepts connections
+            let availability = state.lock().await.availability.clone();
+            availability.pause(PauseSource::RpcServer, "rpc-server starting", true);
+
+            // Start rpc-server
+            let started = match crate::inference::rpc_server::start_rpc_server(
+                app, state, rpc_port, config.gpu_layers,
+            ).await {
+                Ok(rpc_pid) => {
+                    limit_sidecar(config, app, "rpc-server", rpc_pid, rpc_port);
+                    match rpc_health::wait_until_ready(
+                        rpc_port,
+                        std::time::Duration::from_secs(RPC_READY_TIMEOUT_SECS),
+                    ).await {
+                        Ok(probe) => Ok((rpc_pid, probe)),
+                        Err(e) => {
+                            crate::inference::rpc_server::stop_rpc_server(rpc_pid);
+                            Err(e)
+                        }
+                    }
+                }
+                Err(e) => Err(e),
+            };
+
+            match started {
+                Ok((rpc_pid, probe)) => {
+                    availability.resume(PauseSource::RpcServer);
+                    let mut guard = state.lock().await;
+                    guard.rpc_server_pid = Some(rpc_pid);
+                    guard.node_mode = NodeMode::RpcServer;
+                    guard.cluster_id = cluster_id;
+
+                    let _ = app.emit("agent-log", LogEvent {
+                        level: "INFO".to_string(),
+                        message: format!(
+                            "RPC server ready on port {} (PID: {}, protocol {})",
+                            rpc_port,
+                            rpc_pid,
+                            probe.protocol_version.as_deref().unwrap_or("unknown")
+                        ),
+                    });
+
+                    let app_rpc = app.clone();
+                    let availability_rpc = availability.clone();
+                    guard.rpc_health_task = Some(rpc_health::start_rpc_health_monitor(
+                        rpc_port,
+                        15,
+                        move |health| {
+                            if health.healthy {
+                                availability_rpc.resume(PauseSource::RpcServer);
+                            } else {
+                                availability_rpc.pause(PauseSource::RpcServer, health.message.clone(), true);
+                            }
+                            let _ = app_rpc.emit("rpc-health", health);
+                        },
+                    ));
+                }
+                Err(e) => {
+                    availability.resume(PauseSource::RpcServer);
+                    log::error!("Failed to start rpc-server: {}", e);
+                    let _ = app.emit("agent-log", LogEvent {
+                        level: "ERROR".to_string(),
+                        message: format!("Failed to start rpc-server: {}. Falling back to standalone.", e),
+                    });
+                    switch_to_llama(state, app, config, client, signing_key, &[]).await;
+                    let mut guard = state.lock().await;
+                    guard.node_mode = NodeMode::Standalone;
+                    guard.cluster_id = None;
+                }
+            }
+        }
+        "coordinator" => {
+            // Coordinator mode: restart llama-server with --rpc peers
+            let peers = assignment
+                .and_then(|a| a.rpc_peers.as_ref())
+                .cloned()
+                .unwrap_or_default();
+
+            // Measure each peer before committing; one slow link stalls the whole pipeline
+            let measurements =
+                oracle::peers::measure_peers(&peers, config.max_peer_latency_ms).await;
+            for m in measurements.iter().filter(|m| !m.accepted) {
+                let _ = app.emit("agent-log", LogEvent {
+                    level: "WARNING".to_string(),
+                    message: format!(
+                        "Skipping RPC peer {}: {}",
+                        m.peer,
+                        m.reason.as_deref().unwrap_or("unreachable")
+                    ),
+                });
+            }
+            let _ = app.emit("peer-measurements", &measurements);
+            if !measurements.is_empty() {
+                if let Err(e) = oracle::peers::report_peer_measurements(
+                    client,
+                    &config.oracle_url,
+                    signing_key,
+                    cluster_id.as_deref(),
+                    &measurements,
+                )
+                .await
+                {
+                    log::warn!("{}", e);
+                }
+            }
+            let peers: Vec<String> = measurements
+                .into_iter()
+                .filter(|m| m.accepted)
+                .map(|m| m.peer)
+                .collect();
+
+            if peers.is_empty() {
+                log::warn!("Coordinator mode but no usable rpc peers, staying standalone");
+                let _ = app.emit("agent-log", LogEvent {
+                    level: "WARNING".to_string(),
+                    message: "Coordinator mode but no usable peers, running as standalone".to_string(),
+                });
+                switch_to_llama(state, app, config, client, signing_key, &[]).await;
+                let mut guard = state.lock().await;
+                guard.node_mode = NodeMode::Standalone;
+                guard.cluster_id = None;
+            } else {
+                let _ = app.emit("agent-log", LogEvent {
+                    level: "INFO".to_string(),
+                    message: format!("Restarting as coordinator with {} RPC peers", peers.len()),
+                });
+
+                switch_to_llama(state, app, config, client, signing_key, &peers).await;
+
+                let mut guard = state.lock().await;
+                guard.node_mode = NodeMode::Coordinator;
+                guard.cluster_id = cluster_id;
+            }
+        }
+        _ => {
+            // Standalone mode: keep llama-server running as-is, or bring it back
+            if current != NodeMode::Standalone {
+                let _ = app.emit("agent-log", LogEvent {
+                    level: "INFO".to_string(),
+                    message: format!("Leaving {} mode, restarting standalone", current),
+                });
+                switch_to_llama(state, app, config, client, signing_key, &[]).await;
+            }
+            let mut guard = state.lock().await;
+            guard.node_mode = NodeMode::Standalone;
+            guard.cluster_id = None;
         }
     }
 }
 
-/// Called when llama-server health reports "ok" — registers with Oracle (which handles on-chain registration)
-async fn on_agent_ready(
+/// Make sure llama-server runs with exactly `rpc_peers`, stopping the rpc-server
+/// and restarting the relay if the node was serving RPC before.
+async fn switch_to_llama(
     state: &SharedAgentState,
     app: &AppHandle,
     config: &AgentConfig,
     client: &reqwest::Client,
+    signing_key: &chain::crypto::SigningKey,
+    rpc_peers: &[String],
 ) {
-    let signing_key = match chain::crypto::parse_private_key(&config.private_key) {
-        Ok(k) => k,
-        Err(e) => {
-            log::error!("Private key parse error: {}", e);
+    {
+        let mut guard = state.lock().await;
+        let mut running = guard.rpc_peers.clone();
+        let mut wanted = rpc_peers.to_vec();
+        running.sort();
+        wanted.sort();
+        if guard.pid.is_some() && running == wanted {
             return;
         }
-    };
+        if let Some(rpc_pid) = guard.rpc_server_pid.take() {
+            // Not an error: the Terminated handler only flags a running rpc-server
+            guard.node_mode = NodeMode::Standalone;
+            crate::inference::rpc_server::stop_rpc_server(rpc_pid);
+            if let Some(task) = guard.rpc_health_task.take() {
+                task.abort();
+            }
+            guard.availability.resume(PauseSource::RpcServer);
+        }
+    }
 
-    // On-chain registration and heartbeats are handled by Oracle via sponsor flow.
-    // Agent wallet does not need PLM balance.
+    restart_as_coordinator(state, app, config, rpc_peers).await;
+
+    let mut guard = state.lock().await;
+    if guard.relay_task.is_none() {
+        start_relay_task(&mut guard, app, config, signing_key, client);
+    }
+}
+
+/// Connect the inference relay (and the proof batcher, if enabled) and keep the
+/// handle in `guard.relay_task`.
+fn start_relay_task(
+    guard: &mut AgentState,
+    app: &AppHandle,
+    config: &AgentConfig,
+    signing_key: &chain::crypto::SigningKey,
+    client: &reqwest::Client,
+) {
+    let relay_base = config.oracle_url.trim_end_matches('/');
+    let relay_base = if relay_base.ends_with("/oracle") {
+        relay_base.trim_end_matches("/oracle")
+    } else {
+        relay_base
+    };
+    let ws_base = relay_base
+        .replace("https://", "wss://")
+        .replace("http://", "ws://");
+    let ws_url = format!("{}/ws/agent-relay", ws_base);
 
-    // Detect LAN IP for external access (Oracle/inference API need to reach us)
-    let local_ip = system::get_local_ip().unwrap_or_else(|| "127.0.0.1".to_string());
-    log::info!("Detected local IP: {}", local_ip);
     let _ = app.emit("agent-log", LogEvent {
         level: "INFO".to_string(),
-        message: format!("Local endpoint: http://{}:{}", local_ip, config.http_port),
+        message: format!("Connecting to inference relay: {}", ws_url),
     });
 
-    // Map HuggingFace GGUF repo name to oracle model ID
-    let oracle_model = plumise_agent_core::config::oracle_model_name(&config.model);
+    let ledger = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())
+        .and_then(|dir| plumise_agent_core::ledger::Ledger::open(&dir))
+        .map(Arc::new)
+        .map_err(|e| log::warn!("Request ledger disabled: {}", e))
+        .ok();
+
+    if guard.proofs.is_none() {
+        let proofs = app.path().app_data_dir().ok().and_then(|dir| {
+            plumise_agent_core::inference::proof_batch::start_from_config(
+                config,
+                &dir,
+                signing_key,
+                client.clone(),
+            )
+        });
+        guard.proofs = proofs.map(|(batcher, handle)| {
+            guard.background_tasks.push(handle);
+            batcher
+        });
+    }
+    if guard.usage.is_none() {
+        let usage = plumise_agent_core::oracle::usage::start_from_config(
+            config,
+            signing_key,
+            client.clone(),
+        );
+        guard.usage = usage.map(|(reporter, handle)| {
+            guard.background_tasks.push(handle);
+            reporter
+        });
+    }
 
-    // 1. Oracle registration (Oracle will sponsor on-chain registration if needed)
-    let ram_mb = if config.ram_limit_gb > 0 {
-        (config.ram_limit_gb as u64) * 1024
+    let routes = guard
+        .model_routes
+        .clone()
+        .unwrap_or_else(|| ModelRoutes::single(config.oracle_model(), config.http_port));
+    for task in [guard.slots_task.take(), guard.keepalive_task.take()].into_iter().flatten() {
+        task.abort();
+    }
+    let slot_routes = routes.clone();
+    guard.slots_task = Some(plumise_agent_core::inference::metrics::start_slot_poller(
+        client.clone(),
+        guard.slots.clone(),
+        std::time::Duration::from_secs(2),
+        move || slot_routes.primary().map(|route| route.port),
+    ));
+    if config.llama_keepalive_secs > 0 {
+        let keepalive_routes = routes.clone();
+        guard.keepalive_task = Some(plumise_agent_core::http::start_keepalive(
+            std::time::Duration::from_secs(config.llama_keepalive_secs),
+            move || keepalive_routes.primary().map(|route| route.port),
+        ));
+    }
+    guard.relay_task = Some(plumise_agent_core::relay::client::start_relay(
+        ws_url,
+        signing_key.clone(),
+        routes,
+        plumise_agent_core::relay::client::RelayContext {
+            ledger,
+            availability: Some(guard.availability.clone()),
+            serve_mode: config.serve_mode(),
+            proofs: guard.proofs.clone(),
+            stats: Some(guard.latency.clone()),
+            connected: None,
+            usage: guard.usage.clone(),
+            slots: Some(guard.slots.clone()),
+            audit: app
+                .path()
+                .app_data_dir()
+                .ok()
+                .and_then(|dir| plumise_agent_core::audit::AuditLog::from_config(config, &dir))
+                .map(Arc::new),
+        },
+    ));
+}
+
+/// `-ngl` for a llama-server start: 0 in battery "cpu" mode while unplugged.
+fn effective_gpu_layers(config: &AgentConfig) -> i32 {
+    if config.battery_policy() == BatteryPolicy::Cpu && power::power_status().on_battery() {
+        0
     } else {
-        let sys = sysinfo::System::new_all();
-        sys.total_memory() / (1024 * 1024)
-    };
+        config.gpu_layers
+    }
+}
 
-    // Run benchmark
-    let benchmark_tok_per_sec = match plumise_agent_core::inference::benchmark::run_benchmark(client, config.http_port).await {
-        Ok(tps) => {
-            log::info!("Benchmark result: {:.2} tok/s", tps);
-            let _ = app.emit("agent-log", format!("Benchmark: {:.2} tok/s", tps));
-            tps
-        }
-        Err(e) => {
-            log::warn!("Benchmark failed (using default): {}", e);
-            let _ = app.emit("agent-log", format!("Benchmark skipped: {}", e));
-            0.0
-        }
-    };
+/// llama-server arguments for `config` serving `model_path` on `port`, shared by
+/// launch, coordinator restarts and model switches.
+fn llama_server_args(
+    config: &AgentConfig,
+    model_path: &Path,
+    mmproj_path: Option<&Path>,
+    port: u16,
+    rpc_peers: Option<Vec<String>>,
+) -> Vec<String> {
+    process::build_llama_args(&process::LlamaServerConfig {
+        // Spawning (binary, env, sandbox, key) is the sidecar's business; only args are built
+        binary_path: PathBuf::new(),
+        model_path: model_path.to_path_buf(),
+        mmproj_path: mmproj_path.map(Path::to_path_buf),
+        host: config.llama_bind_host(),
+        port,
+        gpu_layers: effective_gpu_layers(config),
+        ctx_size: config.ctx_size,
+        parallel_slots: process::adjust_parallel_slots(config.ctx_size, config.parallel_slots),
+        env_vars: HashMap::new(),
+        rpc_peers,
+        serve_mode: config.serve_mode(),
+        sandbox: false,
+        api_key: None,
+        context_shift: config.context_shift,
+        cache_reuse: config.cache_reuse,
+        log_verbosity: config.llama_log_verbosity,
+        log_disable: config.llama_log_disable,
+    })
+}
 
-    // Determine if distributed inference is enabled
-    let can_distribute = config.distributed_mode != "disabled";
+/// The config the agent is running with, including a `switch_model` since launch;
+/// `fallback` when none is recorded.
+async fn current_launch_config(app: &AppHandle, fallback: AgentConfig) -> AgentConfig {
+    let state = Arc::clone(app.state::<SharedAgentState>().inner());
+    let launch = state.lock().await.launch_config.clone();
+    launch.unwrap_or(fallback)
+}
 
-    match oracle::registry::register(
-        client,
-        &config.oracle_url,
-        &signing_key,
-        oracle_model,
-        config.http_port,
-        ram_mb,
-        0,
-        &config.device,
-        &local_ip,
-        benchmark_tok_per_sec,
-        can_distribute,
-        &local_ip,
-    )
-    .await
-    {
-        Ok(assignment) => {
-            let mode_str = assignment.as_ref().map(|a| a.mode.as_str()).unwrap_or("standalone");
+/// Restart llama-server with `config` (battery "cpu" mode power changes, "reduce-ctx"
+/// memory pressure), logging `message` first.
+/// Boxed so the launch_agent -> on_agent_ready -> restart cycle has a nameable future type.
+fn restart_llama(
+    app: AppHandle,
+    config: AgentConfig,
+    message: String,
+) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send>> {
+    Box::pin(async move {
+        let _ = app.emit("agent-log", LogEvent {
+            level: "INFO".to_string(),
+            message,
+        });
+        let state = Arc::clone(&app.state::<SharedAgentState>().inner());
+        shutdown_agent(&state, &app, AgentStatus::Stopped).await;
+        // Brief pause for port release
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let _ = app.emit("agent-status", AgentStatusEvent {
+            status: AgentStatus::Starting,
+        });
+        if let Err(e) = launch_agent(config, app.clone()).await {
+            log::error!("llama-server restart failed: {}", e);
             let _ = app.emit("agent-log", LogEvent {
-                level: "INFO".to_string(),
-                message: format!("Registered with Oracle (mode: {})", mode_str),
+                level: "ERROR".to_string(),
+                message: format!("llama-server restart failed: {}", e),
             });
+        }
+    })
+}
 
-            // Apply mode-aware logic based on Oracle assignment
-            let effective_mode = if config.distributed_mode == "standalone" {
-                "standalone" // User forced standalone
-            } else {
-                mode_str
+/// Emit `agent-availability` (and a log line) whenever the agent is paused or resumed.
+fn forward_availability_events(
+    availability: Arc<Availability>,
+    app: AppHandle,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut rx = availability.subscribe();
+        while rx.changed().await.is_ok() {
+            let snapshot = availability.snapshot();
+            let message = match availability.pause_message() {
+                Some(reason) if snapshot.deregistered => format!("Agent deregistered: {}", reason),
+                Some(reason) => format!("Agent paused: {}", reason),
+                None => "Agent resumed".to_string(),
             };
+            let _ = app.emit("agent-log", LogEvent {
+                level: "INFO".to_string(),
+                message,
+            });
+            let _ = app.emit("agent-availability", snapshot);
+        }
+    })
+}
+
+/// Apply the sandbox limits that work on a running sidecar (`sandbox` in the config).
+/// Seccomp, Landlock and sandbox-exec need to wrap the spawn, which only the CLI does.
+fn limit_sidecar(config: &AgentConfig, app: &AppHandle, name: &str, pid: u32, port: u16) {
+    if !config.sandbox {
+        return;
+    }
+    let policy = plumise_agent_core::process::sandbox::SandboxPolicy {
+        listen_port: port,
+        ..Default::default()
+    };
+    let (level, message) = match plumise_agent_core::process::sandbox::limit_running(pid, &policy) {
+        Ok(()) => ("INFO", format!("{} (PID {}) placed under process limits", name, pid)),
+        Err(e) => ("WARNING", format!("Could not sandbox {}: {}", name, e)),
+    };
+    let _ = app.emit("agent-log", LogEvent {
+        level: level.to_string(),
+        message,
+    });
+}
 
-            match effective_mode {
-                "rpc-server" => {
-                    // RPC Server mode: stop llama-server, start rpc-server
+/// Emit `tls-pin-failure` (and an error log line) when an Oracle/relay connection fails its pin check.
+fn forward_pin_failures(app: AppHandle) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut rx = plumise_agent_core::tls_pin::subscribe();
+        loop {
+            match rx.recv().await {
+                Ok(failure) => {
                     let _ = app.emit("agent-log", LogEvent {
-                        level: "INFO".to_string(),
-                        message: "Switching to RPC Server mode for distributed inference".to_string(),
+                        level: "ERROR".to_string(),
+                        message: failure.message.clone(),
                     });
-
-                    // Kill llama-server
-                    {
-                        let mut guard = state.lock().await;
-                        if let Some(pid) = guard.pid.take() {
-                            system::kill_pid(pid);
-                        }
-                    }
-
-                    let rpc_port = assignment.as_ref()
-                        .map(|a| a.rpc_port)
-                        .unwrap_or(config.rpc_port);
-
-                    // Start rpc-server
-                    match crate::inference::rpc_server::start_rpc_server(
-                        app, state, rpc_port, config.gpu_layers,
-                    ).await {
-                        Ok(rpc_pid) => {
-                            let mut guard = state.lock().await;
-                            guard.rpc_server_pid = Some(rpc_pid);
-                            guard.node_mode = NodeMode::RpcServer;
-                            guard.cluster_id = assignment.as_ref().and_then(|a| a.cluster_id.clone());
-
-                            let _ = app.emit("agent-log", LogEvent {
-                                level: "INFO".to_string(),
-                                message: format!("RPC server started on port {} (PID: {})", rpc_port, rpc_pid),
-                            });
-                        }
-                        Err(e) => {
-                            log::error!("Failed to start rpc-server: {}", e);
-                            let _ = app.emit("agent-log", LogEvent {
-                                level: "ERROR".to_string(),
-                                message: format!("Failed to start rpc-server: {}. Falling back to standalone.", e),
-                            });
-                        }
-                    }
-                }
-                "coordinator" => {
-                    // Coordinator mode: restart llama-server with --rpc peers
-                    let peers = assignment.as_ref()
-                        .and_then(|a| a.rpc_peers.as_ref())
-                        .cloned()
-                        .unwrap_or_default();
-
-                    if peers.is_empty() {
-                        log::warn!("Coordinator mode but no rpc peers, staying standalone");
-                        let _ = app.emit("agent-log", LogEvent {
-                            level: "WARNING".to_string(),
-                            message: "Coordinator mode but no peers yet, running as standalone".to_string(),
-                        });
-                    } else {
-                        let _ = app.emit("agent-log", LogEvent {
-                            level: "INFO".to_string(),
-                            message: format!("Restarting as coordinator with {} RPC peers", peers.len()),
-                        });
-
-                        restart_as_coordinator(state, app, config, &peers).await;
-
-                        let mut guard = state.lock().await;
-                        guard.node_mode = NodeMode::Coordinator;
-                        guard.cluster_id = assignment.as_ref().and_then(|a| a.cluster_id.clone());
-                    }
-                }
-                _ => {
-                    // Standalone mode: keep llama-server running as-is
-                    let mut guard = state.lock().await;
-                    guard.node_mode = NodeMode::Standalone;
+                    let _ = app.emit("tls-pin-failure", failure);
                 }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
             }
         }
-        Err(e) => {
-            log::warn!("Oracle registration failed (non-fatal): {}", e);
-            let _ = app.emit("agent-log", LogEvent {
-                level: "WARNING".to_string(),
-                message: format!("Oracle registration failed: {}", e),
-            });
-        }
-    }
-
-    // 2. Spawn background tasks (metrics reporter + periodic re-registration)
-    let reporter_handle = plumise_agent_core::oracle::reporter::start_reporter(
-        client.clone(),
-        config.oracle_url.clone(),
-        signing_key.clone(),
-        config.http_port,
-        plumise_agent_core::oracle::reporter::RegistrationParams {
-            model: oracle_model.to_string(),
-            http_port: config.http_port,
-            ram_mb,
-            vram_mb: 0,
-            device: config.device.clone(),
-            external_ip: local_ip.clone(),
-            benchmark_tok_per_sec,
-            can_distribute,
-            lan_ip: local_ip.clone(),
-        },
-    );
-
-    let mut guard = state.lock().await;
-    guard.background_tasks.push(reporter_handle);
-
-    // Only start WS relay if NOT in rpc-server mode (rpc-servers don't serve requests)
-    if guard.node_mode != NodeMode::RpcServer {
-        let relay_base = config.oracle_url.trim_end_matches('/');
-        let relay_base = if relay_base.ends_with("/oracle") {
-            relay_base.trim_end_matches("/oracle")
-        } else {
-            relay_base
-        };
-        let ws_base = relay_base
-            .replace("https://", "wss://")
-            .replace("http://", "ws://");
-        let ws_url = format!("{}/ws/agent-relay", ws_base);
-
-        let _ = app.emit("agent-log", LogEvent {
-            level: "INFO".to_string(),
-            message: format!("Connecting to inference relay: {}", ws_url),
-        });
-
-        let relay_handle = plumise_agent_core::relay::client::start_relay(
-            ws_url,
-            signing_key.clone(),
-            oracle_model.to_string(),
-            config.http_port,
-        );
-        guard.background_tasks.push(relay_handle);
-    }
+    })
 }
 
-/// Restart llama-server with --rpc flag connecting to distributed RPC peers.
+/// Restart llama-server with --rpc flag connecting to distributed RPC peers
+/// (plain standalone when `rpc_peers` is empty). Model and port come from the launch
+/// config as `switch_model` left it, and the relay routes follow the new server.
 async fn restart_as_coordinator(
     state: &SharedAgentState,
     app: &AppHandle,
@@ -878,25 +2521,31 @@ async fn restart_as_coordinator(
     rpc_peers: &[String],
 ) {
     // 1. Kill current llama-server
-    {
+    let config = {
         let mut guard = state.lock().await;
         if let Some(pid) = guard.pid.take() {
             system::kill_pid(pid);
         }
         guard.status = AgentStatus::Starting;
-    }
+        guard.launch_config.clone().unwrap_or_else(|| config.clone())
+    };
+    let config = &config;
 
     // Brief pause for port release
     tokio::time::sleep(std::time::Duration::from_millis(500)).await;
 
     // 2. Build rpc argument: "host1:port1,host2:port2,..."
     let rpc_arg = rpc_peers.join(",");
-    log::info!("Restarting llama-server as coordinator with --rpc {}", rpc_arg);
+    if rpc_peers.is_empty() {
+        log::info!("Restarting llama-server standalone");
+    } else {
+        log::info!("Restarting llama-server as coordinator with --rpc {}", rpc_arg);
+    }
 
     // 3. Get model path from state
-    let model_path = {
+    let (model_path, mmproj_path) = {
         let guard = state.lock().await;
-        guard.model_path.clone()
+        (guard.model_path.clone(), guard.mmproj_path.clone())
     };
 
     let model_path = match model_path {
@@ -908,24 +2557,13 @@ async fn restart_as_coordinator(
     };
 
     // 4. Build args with --rpc
-    let args: Vec<String> = vec![
-        "-m".into(),
-        model_path.to_string_lossy().to_string(),
-        "--host".into(),
-        "0.0.0.0".into(),
-        "--port".into(),
-        config.http_port.to_string(),
-        "-ngl".into(),
-        config.gpu_layers.to_string(),
-        "--ctx-size".into(),
-        config.ctx_size.to_string(),
-        "-np".into(),
-        config.parallel_slots.to_string(),
-        "--jinja".into(),
-        "--rpc".into(),
-        rpc_arg,
-    ];
-
+    let args = llama_server_args(
+        config,
+        &model_path,
+        mmproj_path.as_deref(),
+        config.http_port,
+        Some(rpc_peers.to_vec()),
+    );
     let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
 
     // 5. Resolve backend directory
@@ -941,13 +2579,22 @@ async fn restart_as_coordinator(
         dirs.join(if cfg!(windows) { ";" } else { ":" })
     };
 
-    // 6. Spawn sidecar
+    // 6. Spawn sidecar with a fresh API key
+    let api_key = match process::generate_api_key() {
+        Ok(key) => key,
+        Err(e) => {
+            log::error!("Coordinator restart failed: {}", e);
+            return;
+        }
+    };
+    process::set_api_key(Some(api_key.clone()));
     let spawn_result = app
         .shell()
         .sidecar("llama-server")
         .and_then(|cmd| {
             Ok(cmd.args(&args_ref).envs([
                 ("GGML_BACKEND_DIR".to_string(), backend_path.clone()),
+                (process::API_KEY_ENV.to_string(), api_key),
             ]))
         })
         .and_then(|cmd| cmd.spawn());
@@ -956,21 +2603,31 @@ async fn restart_as_coordinator(
         Ok((rx, child)) => {
             let pid = child.pid();
             log::info!("llama-server (coordinator) spawned, PID: {}", pid);
+            limit_sidecar(config, app, "llama-server", pid, config.http_port);
 
             let mut guard = state.lock().await;
             guard.pid = Some(pid);
+            guard.rpc_peers = rpc_peers.to_vec();
+            guard.http_port = config.http_port;
+            if let Some(routes) = &guard.model_routes {
+                routes.replace(vec![ModelRoute {
+                    model: config.oracle_model(),
+                    port: config.http_port,
+                }]);
+            }
             drop(guard);
 
             // Handle events
             let state_ev = Arc::clone(state);
             let app_ev = app.clone();
+            let log_ev = open_llama_log(app, config);
             tokio::spawn(async move {
-                handle_sidecar_events(rx, state_ev, app_ev).await;
+                handle_sidecar_events(rx, pid, state_ev, app_ev, log_ev).await;
             });
 
             // Poll health to confirm readiness
             let health_url = format!("http://127.0.0.1:{}/health", config.http_port);
-            let client = reqwest::Client::new();
+            let client = plumise_agent_core::http::local();
             let mut attempts = 0;
             loop {
                 tokio::time::sleep(std::time::Duration::from_secs(3)).await;
@@ -989,7 +2646,11 @@ async fn restart_as_coordinator(
                             });
                             let _ = app.emit("agent-log", LogEvent {
                                 level: "INFO".to_string(),
-                                message: format!("Coordinator ready with {} RPC peers", rpc_peers.len()),
+                                message: if rpc_peers.is_empty() {
+                                    "llama-server ready (standalone)".to_string()
+                                } else {
+                                    format!("Coordinator ready with {} RPC peers", rpc_peers.len())
+                                },
                             });
                             break;
                         }
@@ -1009,191 +2670,25 @@ async fn restart_as_coordinator(
 
 // ---- Pre-flight Check ----
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct PreflightResult {
-    pub passed: bool,
-    pub checks: Vec<PreflightCheck>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct PreflightCheck {
-    pub name: String,
-    pub passed: bool,
-    pub message: String,
-}
-
 #[tauri::command]
 pub async fn preflight_check(
     config: AgentConfig,
     app: AppHandle,
 ) -> Result<PreflightResult, String> {
-    let mut checks = Vec::new();
-
-    // 1. Private key
-    let pk_valid = config.private_key.starts_with("0x") && config.private_key.len() == 66;
-    checks.push(PreflightCheck {
-        name: "Wallet".to_string(),
-        passed: pk_valid,
-        message: if pk_valid {
-            "Private key configured".into()
-        } else {
-            "Invalid or missing private key".into()
-        },
-    });
-
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(5))
-        .build()
-        .unwrap();
-
-    // 2. Oracle
-    let oracle_ok = client
-        .get(format!(
-            "{}/api/stats",
-            config.oracle_url.trim_end_matches('/')
-        ))
-        .send()
-        .await
-        .map(|r| r.status().is_success())
-        .unwrap_or(false);
-    checks.push(PreflightCheck {
-        name: "Oracle".to_string(),
-        passed: oracle_ok,
-        message: if oracle_ok {
-            format!("Connected to {}", config.oracle_url)
-        } else {
-            format!("Cannot reach {}", config.oracle_url)
-        },
-    });
-
-    // 3. Chain RPC
-    let rpc_ok = client
-        .post(&config.chain_rpc)
-        .json(&serde_json::json!({"jsonrpc":"2.0","method":"eth_chainId","params":[],"id":1}))
-        .send()
-        .await
-        .ok()
-        .and_then(|r| if r.status().is_success() { Some(()) } else { None })
-        .is_some();
-    checks.push(PreflightCheck {
-        name: "Chain RPC".to_string(),
-        passed: rpc_ok,
-        message: if rpc_ok {
-            format!("Connected to {}", config.chain_rpc)
-        } else {
-            format!("Cannot reach {}", config.chain_rpc)
-        },
-    });
-
-    // 4. Wallet balance
-    if pk_valid {
-        match system::check_wallet_balance(&client, &config.chain_rpc, &config.private_key).await {
-            Ok((balance, addr)) => {
-                let is_zero = balance == "0.0000"
-                    || balance
-                        .trim_start_matches('0')
-                        .trim_start_matches('.')
-                        .is_empty();
-                checks.push(PreflightCheck {
-                    name: "Wallet".to_string(),
-                    passed: true,
-                    message: if is_zero {
-                        format!("{}: 0 PLM (OK — gas is sponsored)", addr)
-                    } else {
-                        format!("{}: {} PLM", addr, balance)
-                    },
-                });
-            }
-            Err(e) => {
-                checks.push(PreflightCheck {
-                    name: "Wallet".to_string(),
-                    passed: true,
-                    message: format!("Balance check skipped: {}", e),
-                });
-            }
-        }
-    }
-
-    // 5. HTTP port — auto-kill leftover llama-server if port is occupied
-    let mut port_free =
-        std::net::TcpListener::bind(format!("127.0.0.1:{}", config.http_port)).is_ok();
-    let port_message = if port_free {
-        format!("Port {} available", config.http_port)
-    } else {
-        // Try to kill leftover process (likely a previous llama-server)
-        if let Some(killed) = system::kill_process_on_port(config.http_port) {
-            // Brief wait for OS to release the port
-            std::thread::sleep(std::time::Duration::from_millis(500));
-            port_free =
-                std::net::TcpListener::bind(format!("127.0.0.1:{}", config.http_port)).is_ok();
-            if port_free {
-                format!("Port {} freed (killed leftover: {})", config.http_port, killed)
-            } else {
-                format!("Port {} still in use after killing {}", config.http_port, killed)
-            }
-        } else {
-            format!("Port {} in use by unknown process", config.http_port)
-        }
-    };
-    checks.push(PreflightCheck {
-        name: "HTTP Port".to_string(),
-        passed: port_free,
-        message: port_message,
-    });
-
-    // 6. GPU detection (cross-platform)
-    if config.gpu_layers > 0 {
-        let gpu_info = system::detect_gpu();
-        match gpu_info {
-            Some((name, vram_mb)) => {
-                let detail = if vram_mb > 0 {
-                    format!("{} ({} MB)", name, vram_mb)
-                } else {
-                    name
-                };
-                checks.push(PreflightCheck {
-                    name: "GPU".to_string(),
-                    passed: true,
-                    message: detail,
-                });
-            }
-            None => {
-                checks.push(PreflightCheck {
-                    name: "GPU".to_string(),
-                    passed: false,
-                    message: "No GPU detected. Install GPU drivers or set GPU Layers to 0 for CPU mode.".into(),
-                });
-            }
-        }
-    }
-
-    // 7. Model
     let app_data_dir = app
         .path()
         .app_data_dir()
         .map_err(|e| format!("App data dir: {}", e))?;
-    let model_path = app_data_dir.join("models").join(&config.model_file);
-    let model_exists = model_path.exists()
-        && std::fs::metadata(&model_path)
-            .map(|m| m.len() > 100 * 1024 * 1024)
-            .unwrap_or(false);
-    checks.push(PreflightCheck {
-        name: "Model".to_string(),
-        passed: true,
-        message: if model_exists {
-            let gb = std::fs::metadata(&model_path)
-                .map(|m| m.len() as f64 / 1e9)
-                .unwrap_or(0.0);
-            format!("{} ({:.1} GB)", config.model_file, gb)
-        } else {
-            format!("{} (will download on start)", config.model_file)
-        },
-    });
-
-    let passed = checks.iter().all(|c| c.passed);
-    Ok(PreflightResult { passed, checks })
+    let models_dir = app_data_dir.join("models");
+    let env = preflight::PreflightEnv {
+        models_dir: &models_dir,
+        llama_server: find_llama_server(&app).map(|(exe, _)| exe),
+        llama_auto_install: false,
+        free_port: true,
+        agent_running: false,
+        check_reachability: false,
+    };
+    Ok(preflight::run_checks(&config, env).await)
 }
 
 // ---- Helpers ----
@@ -1204,6 +2699,11 @@ struct LogEvent {
     message: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TestInferenceTokenEvent {
+    content: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct AgentStatusEvent {
     status: AgentStatus,
@@ -1216,7 +2716,7 @@ struct LoadingProgressEvent {
     phase: String,
 }
 
-fn handle_log_line(line: &str, app: &AppHandle, last_progress_pct: &mut i32) {
+fn handle_log_line(line: &str, app: &AppHandle, llama_log: &LlamaLog, last_progress_pct: &mut i32) {
     if let Some(progress) = parse_loading_progress(line) {
         let pct = progress.percent as i32;
         if pct != *last_progress_pct {
@@ -1225,12 +2725,19 @@ fn handle_log_line(line: &str, app: &AppHandle, last_progress_pct: &mut i32) {
         }
         return;
     }
-    let level = system::parse_log_level(line);
-    let masked = system::mask_sensitive_data(line);
-    let _ = app.emit("agent-log", LogEvent {
-        level: level.to_string(),
-        message: masked,
-    });
+    // Below `llama_log_level`: only the log file gets it
+    if let Some((level, masked)) = llama_log.handle(line) {
+        let _ = app.emit("agent-log", LogEvent {
+            level: level.to_string(),
+            message: masked,
+        });
+    }
+}
+
+/// llama-server output filter; `llama_log_file` is relative to the app data dir.
+fn open_llama_log(app: &AppHandle, config: &AgentConfig) -> Arc<LlamaLog> {
+    let dir = app.path().app_data_dir().unwrap_or_default();
+    Arc::new(LlamaLog::from_config(config, &dir))
 }
 
 fn parse_loading_progress(line: &str) -> Option<LoadingProgressEvent> {
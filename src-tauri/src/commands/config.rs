@@ -1,8 +1,14 @@
 use tauri::Manager;
+use plumise_agent_core::cleanup::{CleanupReport, CleanupTargets};
 use plumise_agent_core::config::AgentConfig;
+use plumise_agent_core::error::CoreError;
+use plumise_agent_core::key_rotation::KeyRotationReport;
+
+use super::agent::{AgentStatus, SharedAgentState};
+use super::logs::LogBuffer;
 
 #[tauri::command]
-pub async fn save_config(config: AgentConfig, app: tauri::AppHandle) -> Result<(), String> {
+pub async fn save_config(config: AgentConfig, app: tauri::AppHandle) -> Result<(), CoreError> {
     let app_data_dir = app
         .path()
         .app_data_dir()
@@ -12,7 +18,7 @@ pub async fn save_config(config: AgentConfig, app: tauri::AppHandle) -> Result<(
 }
 
 #[tauri::command]
-pub async fn load_config(app: tauri::AppHandle) -> Result<AgentConfig, String> {
+pub async fn load_config(app: tauri::AppHandle) -> Result<AgentConfig, CoreError> {
     let app_data_dir = app
         .path()
         .app_data_dir()
@@ -20,3 +26,51 @@ pub async fn load_config(app: tauri::AppHandle) -> Result<AgentConfig, String> {
 
     plumise_agent_core::config::load_config(&app_data_dir)
 }
+
+/// Delete the selected app data. The agent must be stopped, and keys are only
+/// deleted with `confirm_keys`. llama.cpp binaries ship with the app and are kept.
+#[tauri::command]
+pub async fn reset_agent_data(
+    targets: CleanupTargets,
+    confirm_keys: bool,
+    state: tauri::State<'_, SharedAgentState>,
+    logs: tauri::State<'_, LogBuffer>,
+    app: tauri::AppHandle,
+) -> Result<CleanupReport, String> {
+    if state.lock().await.status != AgentStatus::Stopped {
+        return Err("Stop the agent before deleting its data".to_string());
+    }
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    let report = plumise_agent_core::cleanup::clean(&app_data_dir, None, targets, confirm_keys)?;
+    if targets.logs {
+        logs.clear();
+    }
+    Ok(report)
+}
+
+/// Replace the node key with `new_key` (generated when None), backing up the old one.
+/// With `link` the Oracle links the old address to the new one first. The agent must
+/// be stopped; the new address is registered with the Oracle right away, and a failed
+/// registration comes back in the report's `registration_error`.
+#[tauri::command]
+pub async fn rotate_key(
+    new_key: Option<String>,
+    link: bool,
+    state: tauri::State<'_, SharedAgentState>,
+    app: tauri::AppHandle,
+) -> Result<KeyRotationReport, String> {
+    if state.lock().await.status != AgentStatus::Stopped {
+        return Err("Stop the agent before changing its key".to_string());
+    }
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    let client = plumise_agent_core::http::client();
+    plumise_agent_core::key_rotation::rotate_key(&client, &app_data_dir, new_key, link).await
+}
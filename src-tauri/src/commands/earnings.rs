@@ -0,0 +1,155 @@
+use tauri::Manager;
+
+use plumise_agent_core::claims::{self, ClaimHistory, ClaimRecord, ClaimSummary};
+use plumise_agent_core::config::AgentConfig;
+use plumise_agent_core::economics::estimate::{self, EarningsEstimate};
+use plumise_agent_core::economics::projection::{self, RewardProjection};
+use plumise_agent_core::ledger::{self, Ledger, LedgerEntry, LedgerSummary};
+use plumise_agent_core::wallet::BalanceSample;
+
+use crate::commands::agent::SharedAgentState;
+
+/// Aggregate served-request totals from the local ledger.
+/// `since_hours` limits the window; omit for all-time totals.
+#[tauri::command]
+pub async fn get_ledger_summary(
+    since_hours: Option<u64>,
+    app: tauri::AppHandle,
+) -> Result<LedgerSummary, String> {
+    let ledger = open_ledger(&app)?;
+    ledger.summary(since_millis(since_hours))
+}
+
+/// Most recent ledger entries (newest first), capped at `limit`.
+#[tauri::command]
+pub async fn get_ledger_entries(
+    since_hours: Option<u64>,
+    limit: Option<usize>,
+    app: tauri::AppHandle,
+) -> Result<Vec<LedgerEntry>, String> {
+    let ledger = open_ledger(&app)?;
+    let mut entries = ledger.entries(since_millis(since_hours))?;
+    entries.reverse();
+    entries.truncate(limit.unwrap_or(200));
+    Ok(entries)
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaimHistoryResponse {
+    pub summary: ClaimSummary,
+    /// Newest first.
+    pub records: Vec<ClaimRecord>,
+}
+
+/// Claim attempts and on-chain claims, newest first, capped at `limit`.
+/// The summary covers the whole history.
+#[tauri::command]
+pub async fn get_claim_history(
+    limit: Option<usize>,
+    app: tauri::AppHandle,
+) -> Result<ClaimHistoryResponse, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    let mut records = ClaimHistory::open(&app_data_dir)?.records()?;
+    let summary = claims::summarize(&records);
+    records.reverse();
+    records.truncate(limit.unwrap_or(200));
+    Ok(ClaimHistoryResponse { summary, records })
+}
+
+/// Estimate PLM rewards per day from the benchmark, ledger utilization and the
+/// per-token rate observed since the last claim.
+/// Falls back to live llama-server throughput when no benchmark ran this session.
+#[tauri::command]
+pub async fn estimate_earnings(
+    config: AgentConfig,
+    uptime_hours_per_day: Option<f64>,
+    app: tauri::AppHandle,
+) -> Result<EarningsEstimate, String> {
+    let (benchmark, http_port) = {
+        let state = app.state::<SharedAgentState>();
+        let guard = state.lock().await;
+        (guard.benchmark_tok_per_sec, guard.http_port)
+    };
+
+    let client = plumise_agent_core::http::client();
+    let tok_per_sec = if benchmark > 0.0 {
+        benchmark
+    } else {
+        plumise_agent_core::inference::metrics::fetch_metrics(&plumise_agent_core::http::local(), http_port)
+            .await
+            .map(|m| m.tps)
+            .unwrap_or(0.0)
+    };
+
+    let ledger = open_ledger(&app).ok();
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    let address = plumise_agent_core::chain::crypto::parse_private_key(&config.private_key)
+        .ok()
+        .map(|key| plumise_agent_core::chain::crypto::address_from_key(&key));
+    estimate::estimate_from_chain(
+        &client,
+        &config.chain_rpc,
+        address.as_deref(),
+        &app_data_dir,
+        tok_per_sec,
+        ledger.as_ref(),
+        uptime_hours_per_day.unwrap_or(24.0),
+    )
+    .await
+}
+
+/// Project PLM rewards from what accrued since the last claim. Needs a running agent
+/// for the address.
+#[tauri::command]
+pub async fn get_reward_projection(app: tauri::AppHandle) -> Result<RewardProjection, String> {
+    let (address, chain_rpc) = {
+        let state = app.state::<SharedAgentState>();
+        let guard = state.lock().await;
+        let address = guard
+            .agent_address
+            .clone()
+            .ok_or_else(|| "Agent is not running".to_string())?;
+        let chain_rpc = guard
+            .launch_config
+            .as_ref()
+            .map(|c| c.chain_rpc.clone())
+            .ok_or_else(|| "Agent is not running".to_string())?;
+        (address, chain_rpc)
+    };
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    projection::projection(&plumise_agent_core::http::client(), &chain_rpc, &address, &app_data_dir).await
+}
+
+/// Wallet balance samples recorded by the balance watcher, oldest first.
+#[tauri::command]
+pub async fn get_balance_history(app: tauri::AppHandle) -> Result<Vec<BalanceSample>, String> {
+    let state = app.state::<SharedAgentState>();
+    let guard = state.lock().await;
+    let history = guard
+        .balance_history
+        .lock()
+        .map_err(|_| "Balance history unavailable".to_string())?;
+    Ok(history.samples())
+}
+
+fn open_ledger(app: &tauri::AppHandle) -> Result<Ledger, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ledger::open(&app_data_dir)
+}
+
+fn since_millis(hours: Option<u64>) -> Option<u64> {
+    hours.map(|h| ledger::unix_millis().saturating_sub(h.saturating_mul(3600 * 1000)))
+}
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+use plumise_agent_core::chain;
+use plumise_agent_core::config::AgentConfig;
+use plumise_agent_core::inference::energy::{self, EnergyReport};
+
+use crate::commands::agent::SharedAgentState;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnergyEarningsResponse {
+    pub energy: EnergyReport,
+    pub pending_reward_plm: f64,
+    pub reward_value: f64,
+    pub net_earnings: f64,
+}
+
+/// Energy consumed this session and net earnings after electricity cost.
+#[tauri::command]
+pub async fn get_energy_report(
+    config: AgentConfig,
+    state: tauri::State<'_, SharedAgentState>,
+) -> Result<EnergyEarningsResponse, String> {
+    let report = {
+        let guard = state.lock().await;
+        let meter = guard
+            .energy
+            .lock()
+            .map_err(|_| "Energy meter unavailable".to_string())?;
+        meter.report(config.electricity_price_kwh)
+    };
+
+    let pending_reward_plm = if config.private_key.is_empty() {
+        0.0
+    } else {
+        let signing_key = chain::crypto::parse_private_key(&config.private_key)?;
+        let address = chain::crypto::address_from_key(&signing_key);
+        let client = plumise_agent_core::http::client();
+        match chain::rewards::get_pending_reward(&client, &config.chain_rpc, &address).await {
+            Ok(wei) => wei as f64 / 1e18,
+            Err(e) => {
+                log::warn!("Pending reward lookup failed: {}", e);
+                0.0
+            }
+        }
+    };
+
+    Ok(EnergyEarningsResponse {
+        reward_value: pending_reward_plm * config.plm_price,
+        net_earnings: energy::net_earnings(pending_reward_plm, config.plm_price, report.energy_cost),
+        energy: report,
+        pending_reward_plm,
+    })
+}
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tauri::{AppHandle, Listener, Manager};
+
+/// Lines kept for windows opened after the agent started.
+const LOG_CAPACITY: usize = 5000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogRecord {
+    pub id: u64,
+    /// Unix milliseconds.
+    pub timestamp: u64,
+    pub level: String,
+    pub message: String,
+}
+
+#[derive(Default)]
+pub struct LogBuffer {
+    inner: Mutex<LogRing>,
+}
+
+#[derive(Default)]
+struct LogRing {
+    next_id: u64,
+    records: VecDeque<LogRecord>,
+}
+
+impl LogBuffer {
+    fn push(&self, level: String, message: String) {
+        let Ok(mut ring) = self.inner.lock() else {
+            return;
+        };
+        ring.next_id += 1;
+        let id = ring.next_id;
+        if ring.records.len() >= LOG_CAPACITY {
+            ring.records.pop_front();
+        }
+        ring.records.push_back(LogRecord {
+            id,
+            timestamp: plumise_agent_core::ledger::unix_millis(),
+            level,
+            message,
+        });
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut ring) = self.inner.lock() {
+            ring.records.clear();
+        }
+    }
+
+    /// The last `n` lines as "[LEVEL] message", oldest first.
+    pub fn tail(&self, n: usize) -> Vec<String> {
+        let Ok(ring) = self.inner.lock() else {
+            return Vec::new();
+        };
+        let skip = ring.records.len().saturating_sub(n);
+        ring.records
+            .iter()
+            .skip(skip)
+            .map(|r| format!("[{}] {}", r.level, r.message))
+            .collect()
+    }
+}
+
+/// "WARN" and "WARNING" are both emitted; compare them as one level.
+fn normalize_level(level: &str) -> String {
+    match level.to_ascii_uppercase().as_str() {
+        "WARN" => "WARNING".to_string(),
+        other => other.to_string(),
+    }
+}
+
+#[derive(Deserialize)]
+struct LogPayload {
+    level: String,
+    message: String,
+}
+
+/// Record every "agent-log" event, whichever module emitted it.
+pub fn capture(app: &AppHandle) {
+    let handle = app.clone();
+    app.listen_any("agent-log", move |event| {
+        if let Ok(payload) = serde_json::from_str::<LogPayload>(event.payload()) {
+            handle
+                .state::<LogBuffer>()
+                .push(normalize_level(&payload.level), payload.message);
+        }
+    });
+}
+
+/// The most recent `limit` log lines (all buffered lines by default), oldest first,
+/// optionally restricted to one level.
+#[tauri::command]
+pub fn get_recent_logs(
+    level: Option<String>,
+    limit: Option<usize>,
+    buffer: tauri::State<'_, LogBuffer>,
+) -> Result<Vec<LogRecord>, String> {
+    let ring = buffer
+        .inner
+        .lock()
+        .map_err(|e| format!("Log buffer poisoned: {}", e))?;
+    let level = level.map(|l| normalize_level(&l));
+    let mut records: Vec<LogRecord> = ring
+        .records
+        .iter()
+        .rev()
+        .filter(|r| level.as_ref().map_or(true, |l| &r.level == l))
+        .take(limit.unwrap_or(LOG_CAPACITY))
+        .cloned()
+        .collect();
+    records.reverse();
+    Ok(records)
+}
+
+#[tauri::command]
+pub fn clear_logs(buffer: tauri::State<'_, LogBuffer>) -> Result<(), String> {
+    buffer.clear();
+    Ok(())
+}
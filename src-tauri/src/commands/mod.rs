@@ -1,3 +1,8 @@
 pub mod agent;
 pub mod config;
+pub mod earnings;
+pub mod energy;
+pub mod logs;
+pub mod model;
 pub mod system;
+pub mod update;
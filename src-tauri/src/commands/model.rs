@@ -0,0 +1,153 @@
+use plumise_agent_core::model::integrity::IntegrityStatus;
+use plumise_agent_core::model::registry::ModelEntry;
+use serde::Serialize;
+use tauri::Manager;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelVariant {
+    pub file: String,
+    pub quant: Option<String>,
+    pub size_bytes: u64,
+    /// Already present in the models directory (no download needed to switch).
+    pub downloaded: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelIntegrity {
+    pub file: String,
+    pub status: IntegrityStatus,
+    pub message: String,
+}
+
+/// Re-hash the configured model and mmproj (or just `file`) against their recorded checksums.
+#[tauri::command]
+pub async fn verify_model(
+    file: Option<String>,
+    app: tauri::AppHandle,
+) -> Result<Vec<ModelIntegrity>, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    let models_dir = app_data_dir.join("models");
+    let files = match file {
+        Some(file) => vec![file],
+        None => {
+            let config = plumise_agent_core::config::load_config(&app_data_dir)?;
+            [config.model_file, config.mmproj_file]
+                .into_iter()
+                .filter(|f| !f.is_empty() && models_dir.join(f).exists())
+                .collect()
+        }
+    };
+
+    let mut results = Vec::new();
+    for file in files {
+        let (dir, name) = (models_dir.clone(), file.clone());
+        let status = tokio::task::spawn_blocking(move || {
+            plumise_agent_core::model::integrity::verify_model(&dir, &name, None)
+        })
+        .await
+        .map_err(|e| format!("Verification task failed: {}", e))??;
+        results.push(ModelIntegrity {
+            message: status.message(&file),
+            file,
+            status,
+        });
+    }
+    Ok(results)
+}
+
+/// Delete a corrupted model file and its checksum; it downloads again on the next start.
+#[tauri::command]
+pub async fn redownload_model(file: String, app: tauri::AppHandle) -> Result<(), String> {
+    if file.contains(['/', '\\']) || file.starts_with('.') {
+        return Err(format!("Invalid model file name: {}", file));
+    }
+    let models_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join("models");
+    plumise_agent_core::model::integrity::discard(&models_dir, &file)
+}
+
+/// Copy, move or link a local GGUF into the models directory and register it; no download.
+/// `mode` is "copy", "move", "link", "symlink" or "hardlink".
+#[tauri::command]
+pub async fn import_model(
+    path: String,
+    name: Option<String>,
+    mode: String,
+    app: tauri::AppHandle,
+) -> Result<ModelEntry, String> {
+    let mode = plumise_agent_core::model::import::ImportMode::parse(&mode)?;
+    let models_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join("models");
+    tokio::task::spawn_blocking(move || {
+        plumise_agent_core::model::import::import_model(
+            &models_dir,
+            std::path::Path::new(path.trim()),
+            name.as_deref(),
+            mode,
+        )
+    })
+    .await
+    .map_err(|e| format!("Import task failed: {}", e))?
+}
+
+/// Models in the models directory and where they came from.
+#[tauri::command]
+pub async fn list_models(app: tauri::AppHandle) -> Result<Vec<ModelEntry>, String> {
+    let models_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join("models");
+    Ok(plumise_agent_core::model::registry::list(&models_dir))
+}
+
+/// List the quantization variants available in a HuggingFace GGUF repo.
+/// Switching is just a `model_file` change; the new file downloads on next start.
+#[tauri::command]
+pub async fn list_model_variants(
+    repo: String,
+    app: tauri::AppHandle,
+) -> Result<Vec<ModelVariant>, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    let models_dir = app_data_dir.join("models");
+    let hf_token = plumise_agent_core::config::load_config(&app_data_dir)
+        .map(|c| c.hf_token)
+        .unwrap_or_default();
+
+    let client = plumise_agent_core::http::client();
+    let files = plumise_agent_core::model::download::list_gguf_files(
+        &client,
+        &repo,
+        Some(hf_token.as_str()),
+    )
+    .await?;
+
+    Ok(files
+        .into_iter()
+        .map(|f| {
+            let downloaded = std::fs::metadata(models_dir.join(&f.file))
+                .map(|m| m.len() == f.size_bytes)
+                .unwrap_or(false);
+            ModelVariant {
+                file: f.file,
+                quant: f.quant,
+                size_bytes: f.size_bytes,
+                downloaded,
+            }
+        })
+        .collect())
+}
@@ -1,4 +1,7 @@
 use serde::{Deserialize, Serialize};
+use plumise_agent_core::hardware::HardwareProfile;
+use plumise_agent_core::model::recommend::Recommendation;
+use plumise_agent_core::system::GpuTelemetry;
 use sysinfo::System;
 use tokio::process::Command;
 
@@ -11,6 +14,7 @@ pub struct SystemInfo {
     pub vram_total: u64,
     pub vram_used: u64,
     pub gpu_name: String,
+    pub gpus: Vec<GpuTelemetry>,
 }
 
 #[tauri::command]
@@ -30,7 +34,20 @@ pub async fn get_system_info() -> Result<SystemInfo, String> {
     let ram_used = sys.used_memory(); // in bytes
 
     // Try to get GPU info via nvidia-smi
-    let (vram_total, vram_used, gpu_name) = get_gpu_info().await;
+    let (mut vram_total, vram_used, mut gpu_name) = get_gpu_info().await;
+    if gpu_name.is_empty() {
+        // No nvidia-smi: name and size from the detected hardware, usage unknown
+        let hardware = tokio::task::spawn_blocking(plumise_agent_core::hardware::profile)
+            .await
+            .map_err(|e| format!("Hardware detection failed: {}", e))?;
+        if let Some(gpu) = &hardware.gpu {
+            gpu_name = gpu.name.clone();
+            vram_total = gpu.vram_mb * 1024 * 1024;
+        }
+    }
+    let gpus = tokio::task::spawn_blocking(plumise_agent_core::system::gpu_telemetry)
+        .await
+        .unwrap_or_default();
 
     Ok(SystemInfo {
         cpu_usage,
@@ -39,9 +56,32 @@ pub async fn get_system_info() -> Result<SystemInfo, String> {
         vram_total,
         vram_used,
         gpu_name,
+        gpus,
     })
 }
 
+/// Model + quantization suggested for this machine's VRAM/RAM.
+#[tauri::command]
+pub async fn recommend_model() -> Result<Recommendation, String> {
+    tokio::task::spawn_blocking(plumise_agent_core::model::recommend::recommend_for_host)
+        .await
+        .map_err(|e| format!("Recommendation failed: {}", e))
+}
+
+/// Detected GPU, RAM and CPU. Cached after the first call; `refresh` detects again,
+/// e.g. after a driver install.
+#[tauri::command]
+pub async fn get_hardware_profile(refresh: Option<bool>) -> Result<HardwareProfile, String> {
+    let detect = if refresh.unwrap_or(false) {
+        plumise_agent_core::hardware::refresh
+    } else {
+        plumise_agent_core::hardware::profile
+    };
+    tokio::task::spawn_blocking(move || (*detect()).clone())
+        .await
+        .map_err(|e| format!("Hardware detection failed: {}", e))
+}
+
 async fn get_gpu_info() -> (u64, u64, String) {
     // Try nvidia-smi for NVIDIA GPUs (with hidden console window on Windows)
     let mut cmd = Command::new("nvidia-smi");
@@ -0,0 +1,163 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+use plumise_agent_core::availability::PauseSource;
+use plumise_agent_core::config::AgentConfig;
+
+use super::agent::{AgentStatus, SharedAgentState};
+
+const STABLE_ENDPOINT: &str =
+    "https://github.com/mikusnuz/plumise-agent-app/releases/latest/download/latest.json";
+/// Pre-releases publish their manifest to the rolling `beta` release.
+const BETA_ENDPOINT: &str =
+    "https://github.com/mikusnuz/plumise-agent-app/releases/download/beta/latest.json";
+/// Longest an install waits for in-flight requests before stopping the agent anyway.
+const DRAIN_TIMEOUT_SECS: u64 = 600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateChannel {
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    pub fn from_config(channel: &str) -> Self {
+        match channel {
+            "beta" => UpdateChannel::Beta,
+            _ => UpdateChannel::Stable,
+        }
+    }
+
+    fn endpoint(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => STABLE_ENDPOINT,
+            UpdateChannel::Beta => BETA_ENDPOINT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub date: Option<String>,
+    pub body: Option<String>,
+    pub channel: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateProgressEvent {
+    /// "downloading" | "waiting" (for in-flight requests) | "installing".
+    phase: &'static str,
+    downloaded_bytes: u64,
+    total_bytes: Option<u64>,
+}
+
+/// The channel comes from the saved config so a check can run before Settings is opened.
+fn saved_config(app: &AppHandle) -> Result<AgentConfig, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(plumise_agent_core::config::load_config(&app_data_dir)?)
+}
+
+async fn find_update(app: &AppHandle, config: &AgentConfig) -> Result<Option<Update>, String> {
+    let channel = UpdateChannel::from_config(&config.update_channel);
+    let endpoint = channel
+        .endpoint()
+        .parse()
+        .map_err(|e| format!("Invalid update endpoint: {}", e))?;
+    app.updater_builder()
+        .endpoints(vec![endpoint])
+        .map_err(|e| format!("Updater configuration error: {}", e))?
+        .build()
+        .map_err(|e| format!("Updater configuration error: {}", e))?
+        .check()
+        .await
+        .map_err(|e| format!("Update check failed: {}", e))
+}
+
+#[tauri::command]
+pub async fn check_for_update(app: AppHandle) -> Result<Option<UpdateInfo>, String> {
+    let config = saved_config(&app)?;
+    Ok(find_update(&app, &config).await?.map(|update| UpdateInfo {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        date: update.date.map(|d| d.to_string()),
+        body: update.body.clone(),
+        channel: config.update_channel.clone(),
+    }))
+}
+
+/// Download the update, wait for the agent to finish in-flight requests, stop it,
+/// install and relaunch. New requests are refused while draining so an update never
+/// cuts a job short.
+#[tauri::command]
+pub async fn install_update(app: AppHandle) -> Result<(), String> {
+    let config = saved_config(&app)?;
+    let update = find_update(&app, &config)
+        .await?
+        .ok_or_else(|| "No update available".to_string())?;
+
+    let app_progress = app.clone();
+    let mut downloaded = 0u64;
+    let bytes = update
+        .download(
+            move |chunk, total| {
+                downloaded += chunk as u64;
+                let _ = app_progress.emit("update-progress", UpdateProgressEvent {
+                    phase: "downloading",
+                    downloaded_bytes: downloaded,
+                    total_bytes: total,
+                });
+            },
+            || {},
+        )
+        .await
+        .map_err(|e| format!("Update download failed: {}", e))?;
+
+    let state = app.state::<SharedAgentState>().inner().clone();
+    let (running, http_port, availability) = {
+        let guard = state.lock().await;
+        (
+            guard.status == AgentStatus::Running,
+            guard.http_port,
+            guard.availability.clone(),
+        )
+    };
+    if running {
+        let _ = app.emit("update-progress", UpdateProgressEvent {
+            phase: "waiting",
+            downloaded_bytes: bytes.len() as u64,
+            total_bytes: Some(bytes.len() as u64),
+        });
+        availability.pause(PauseSource::Update, "Installing an update", false);
+        let client = plumise_agent_core::http::local();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(DRAIN_TIMEOUT_SECS);
+        while std::time::Instant::now() < deadline {
+            let busy = plumise_agent_core::inference::metrics::fetch_metrics(&client, http_port)
+                .await
+                .map(|m| m.slots_processing > 0)
+                .unwrap_or(false);
+            if !busy {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        }
+        super::agent::stop_agent(app.clone()).await?;
+    }
+
+    let _ = app.emit("update-progress", UpdateProgressEvent {
+        phase: "installing",
+        downloaded_bytes: bytes.len() as u64,
+        total_bytes: Some(bytes.len() as u64),
+    });
+    update
+        .install(bytes)
+        .map_err(|e| format!("Update install failed: {}", e))?;
+    app.restart();
+}
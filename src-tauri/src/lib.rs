@@ -4,6 +4,7 @@ use tokio::sync::Mutex;
 mod commands;
 mod inference;
 mod model;
+mod notify;
 
 use commands::agent::{AgentState, SharedAgentState};
 
@@ -13,7 +14,9 @@ pub fn run() {
 
     tauri::Builder::default()
         .manage(agent_state)
+        .manage(commands::logs::LogBuffer::default())
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .setup(|app| {
             if cfg!(debug_assertions) {
@@ -23,18 +26,48 @@ pub fn run() {
                         .build(),
                 )?;
             }
+            commands::logs::capture(app.handle());
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             commands::agent::start_agent,
             commands::agent::stop_agent,
+            commands::agent::pause_agent,
+            commands::agent::resume_agent,
+            commands::agent::set_node_mode,
+            commands::agent::switch_model,
+            commands::agent::get_schedule_status,
             commands::agent::get_agent_status,
             commands::agent::get_agent_metrics,
+            commands::agent::get_lifetime_stats,
             commands::agent::check_model,
             commands::agent::preflight_check,
+            commands::agent::probe_gpu,
+            commands::agent::test_inference,
+            commands::agent::get_crash_history,
+            commands::energy::get_energy_report,
+            commands::logs::get_recent_logs,
+            commands::logs::clear_logs,
+            commands::model::list_model_variants,
+            commands::model::verify_model,
+            commands::model::redownload_model,
+            commands::model::import_model,
+            commands::model::list_models,
+            commands::earnings::get_ledger_summary,
+            commands::earnings::get_ledger_entries,
+            commands::earnings::get_claim_history,
+            commands::earnings::estimate_earnings,
+            commands::earnings::get_reward_projection,
+            commands::earnings::get_balance_history,
             commands::system::get_system_info,
+            commands::system::recommend_model,
+            commands::system::get_hardware_profile,
             commands::config::save_config,
             commands::config::load_config,
+            commands::config::reset_agent_data,
+            commands::config::rotate_key,
+            commands::update::check_for_update,
+            commands::update::install_update,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -6,15 +6,25 @@ pub async fn ensure_model(
     models_dir: &Path,
     repo: &str,
     filename: &str,
+    hf_token: Option<&str>,
+    model_url: Option<&str>,
     app: &AppHandle,
 ) -> Result<PathBuf, String> {
     let app_clone = app.clone();
+    let low_disk_warned = std::sync::atomic::AtomicBool::new(false);
+    let downloaded = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let downloaded_cb = downloaded.clone();
 
-    plumise_agent_core::model::download::ensure_model(
+    let path = plumise_agent_core::model::download::ensure_model(
         models_dir,
         repo,
         filename,
+        hf_token,
+        model_url,
         move |progress| {
+            if progress.phase == "downloading" {
+                downloaded_cb.store(true, std::sync::atomic::Ordering::Relaxed);
+            }
             let _ = app_clone.emit(
                 "agent-loading-progress",
                 serde_json::json!({
@@ -24,7 +34,65 @@ pub async fn ensure_model(
                     "totalBytes": progress.total_bytes,
                 }),
             );
+            if let Some(free) = progress.free_bytes {
+                if free < plumise_agent_core::system::LOW_DISK_WARN_BYTES
+                    && !low_disk_warned.swap(true, std::sync::atomic::Ordering::Relaxed)
+                {
+                    let _ = app_clone.emit(
+                        "agent-disk-low",
+                        serde_json::json!({
+                            "freeBytes": free,
+                            "remainingBytes": progress.total_bytes.saturating_sub(progress.downloaded_bytes),
+                        }),
+                    );
+                    let _ = app_clone.emit(
+                        "agent-log",
+                        serde_json::json!({
+                            "level": "WARNING",
+                            "message": format!("Low disk space: {:.1} GB free", free as f64 / 1e9),
+                        }),
+                    );
+                }
+            }
         },
     )
+    .await?;
+
+    // Only for an actual download, not a file that was already there
+    if downloaded.load(std::sync::atomic::Ordering::Relaxed) {
+        crate::notify::notify(app, crate::notify::Notice::ModelDownloaded {
+            file: filename.to_string(),
+        });
+    }
+    Ok(path)
+}
+
+/// Re-verify `filename` against its recorded checksum, at most every
+/// `modelVerifyIntervalHours`. A mismatch emits `agent-model-corrupt` so the UI can
+/// offer a re-download, and fails the start.
+pub async fn verify_on_start(
+    models_dir: &Path,
+    filename: &str,
+    interval: Option<std::time::Duration>,
+    app: &AppHandle,
+) -> Result<(), String> {
+    let Some(interval) = interval else {
+        return Ok(());
+    };
+    let (dir, file) = (models_dir.to_path_buf(), filename.to_string());
+    let status = tokio::task::spawn_blocking(move || {
+        plumise_agent_core::model::integrity::verify_model(&dir, &file, Some(interval))
+    })
     .await
+    .map_err(|e| format!("Verification task failed: {}", e))??;
+    if !status.is_mismatch() {
+        return Ok(());
+    }
+
+    let message = status.message(filename);
+    let _ = app.emit(
+        "agent-model-corrupt",
+        serde_json::json!({ "file": filename, "message": message }),
+    );
+    Err(message)
 }
@@ -0,0 +1,97 @@
+//! Desktop notifications for the events an operator wants to hear about even with
+//! the window closed. Everything goes through `notify`, which also emits an
+//! "agent-notification" event so the UI can show the same message in-app.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+/// How long the Oracle must stay unreachable before it's worth a notification.
+pub const ORACLE_UNREACHABLE_AFTER: Duration = Duration::from_secs(10 * 60);
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Follow the `notifications` config setting; the in-app event is always emitted.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub enum Notice {
+    ModelDownloaded { file: String },
+    AgentCrashed { reason: String },
+    RewardClaimed { amount_plm: f64 },
+    OracleUnreachable { down_for: Duration },
+    UpdateRequired { current: String, minimum: String },
+    Penalized { reason: String },
+}
+
+impl Notice {
+    fn kind(&self) -> &'static str {
+        match self {
+            Notice::ModelDownloaded { .. } => "model-downloaded",
+            Notice::AgentCrashed { .. } => "agent-crashed",
+            Notice::RewardClaimed { .. } => "reward-claimed",
+            Notice::OracleUnreachable { .. } => "oracle-unreachable",
+            Notice::UpdateRequired { .. } => "update-required",
+            Notice::Penalized { .. } => "penalized",
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Notice::ModelDownloaded { .. } => "Model downloaded",
+            Notice::AgentCrashed { .. } => "Agent stopped unexpectedly",
+            Notice::RewardClaimed { .. } => "Rewards claimed",
+            Notice::OracleUnreachable { .. } => "Oracle unreachable",
+            Notice::UpdateRequired { .. } => "Agent update required",
+            Notice::Penalized { .. } => "Node penalized",
+        }
+    }
+
+    fn body(&self) -> String {
+        match self {
+            Notice::ModelDownloaded { file } => format!("{} is ready to serve.", file),
+            Notice::AgentCrashed { reason } => reason.clone(),
+            Notice::RewardClaimed { amount_plm } => format!("{:.4} PLM claimed.", amount_plm),
+            Notice::OracleUnreachable { down_for } => format!(
+                "No contact with the Oracle for {} minutes. The node isn't receiving work.",
+                down_for.as_secs() / 60
+            ),
+            Notice::UpdateRequired { current, minimum } => format!(
+                "Version {} is no longer supported (minimum {}). Update to keep receiving work.",
+                current, minimum
+            ),
+            Notice::Penalized { reason } => reason.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct NotificationEvent {
+    kind: &'static str,
+    title: &'static str,
+    body: String,
+}
+
+pub fn notify(app: &AppHandle, notice: Notice) {
+    let event = NotificationEvent {
+        kind: notice.kind(),
+        title: notice.title(),
+        body: notice.body(),
+    };
+    let _ = app.emit("agent-notification", event.clone());
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(event.title)
+        .body(&event.body)
+        .show()
+    {
+        log::warn!("Desktop notification failed: {}", e);
+    }
+}
@@ -5,6 +5,7 @@ import Sidebar from './components/layout/Sidebar';
 import UpdateChecker from './components/UpdateChecker';
 import Dashboard from './pages/Dashboard';
 import Logs from './pages/Logs';
+import Playground from './pages/Playground';
 import Settings from './pages/Settings';
 import { useAgentProcess } from './hooks/useAgentProcess';
 import type { AgentConfig } from './types';
@@ -21,7 +22,7 @@ if (typeof window !== 'undefined' && '__TAURI_INTERNALS__' in window) {
 }
 
 export default function App() {
-  const { status, metrics, health, logs, loadingProgress, nodeMode, clusterId, start, stop, clearLogs } = useAgentProcess();
+  const { status, metrics, health, logs, loadingProgress, nodeMode, clusterId, schedule, paused, updateRequired, start, stop, pause, resume, clearLogs } = useAgentProcess();
   const configRef = useRef<AgentConfig>(DEFAULT_CONFIG);
   const [hasPrivateKey, setHasPrivateKey] = useState(false);
 
@@ -57,7 +58,7 @@ export default function App() {
 
   // Stop agent before closing the app window
   const handleBeforeClose = useCallback(async () => {
-    if (status === 'running' || status === 'starting') {
+    if (status === 'running' || status === 'starting' || status === 'scheduled') {
       await stop();
     }
   }, [status, stop]);
@@ -81,8 +82,13 @@ export default function App() {
                 loadingProgress={loadingProgress}
                 nodeMode={nodeMode}
                 clusterId={clusterId}
+                schedule={schedule}
                 onStart={handleStart}
                 onStop={stop}
+                paused={paused}
+                updateRequired={updateRequired}
+                onPause={pause}
+                onResume={resume}
               />
             }
           />
@@ -90,6 +96,10 @@ export default function App() {
             path="/logs"
             element={<Logs logs={logs} onClear={handleClearLogs} />}
           />
+          <Route
+            path="/playground"
+            element={<Playground status={status} />}
+          />
           <Route
             path="/settings"
             element={
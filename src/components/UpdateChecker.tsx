@@ -1,38 +1,38 @@
 import { useEffect, useState } from 'react';
-import { check as checkUpdate } from '@tauri-apps/plugin-updater';
 import { Download, AlertCircle, X } from 'lucide-react';
+import type { UpdateInfo } from '../types';
 
-interface UpdateInfo {
-  version: string;
-  date?: string;
-  body?: string;
+// --- Tauri API loading (awaitable) ---
+let invokePromise: Promise<typeof import('@tauri-apps/api/core')['invoke']> | null = null;
+let listenPromise: Promise<typeof import('@tauri-apps/api/event')['listen']> | null = null;
+
+if (typeof window !== 'undefined' && '__TAURI_INTERNALS__' in window) {
+  invokePromise = import('@tauri-apps/api/core').then((mod) => mod.invoke);
+  listenPromise = import('@tauri-apps/api/event').then((mod) => mod.listen);
 }
 
+type UpdatePhase = 'downloading' | 'waiting' | 'installing';
+
 export default function UpdateChecker() {
   const [updateAvailable, setUpdateAvailable] = useState<UpdateInfo | null>(null);
   const [isDownloading, setIsDownloading] = useState(false);
   const [downloadProgress, setDownloadProgress] = useState(0);
+  const [phase, setPhase] = useState<UpdatePhase>('downloading');
   const [error, setError] = useState<string | null>(null);
   const [dismissed, setDismissed] = useState(false);
 
   useEffect(() => {
-    if (typeof window === 'undefined' || !('__TAURI_INTERNALS__' in window)) {
-      return;
-    }
+    if (!invokePromise) return;
 
     const performUpdateCheck = async () => {
       try {
-        const update = await checkUpdate();
-
-        if (update?.available) {
-          setUpdateAvailable({
-            version: update.version,
-            date: update.date,
-            body: update.body,
-          });
+        const invoke = await invokePromise!;
+        const update = await invoke<UpdateInfo | null>('check_for_update');
+        if (update) {
+          setUpdateAvailable(update);
         }
       } catch (err) {
-        const message = err instanceof Error ? err.message : 'Unknown error';
+        const message = err instanceof Error ? err.message : String(err);
         console.error('[UpdateChecker] Failed to check for updates:', message);
         setError(message);
       }
@@ -41,52 +41,46 @@ export default function UpdateChecker() {
     performUpdateCheck();
   }, []);
 
+  useEffect(() => {
+    let cancelled = false;
+    let unlisten: (() => void) | null = null;
+    listenPromise?.then((listen) =>
+      listen('update-progress', (event: any) => {
+        const { phase: next, downloadedBytes, totalBytes } = event.payload;
+        setPhase(next);
+        if (totalBytes) {
+          setDownloadProgress((downloadedBytes / totalBytes) * 100);
+        }
+      }).then((fn: () => void) => {
+        if (cancelled) fn();
+        else unlisten = fn;
+      }),
+    );
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, []);
+
   const handleInstall = async () => {
-    if (!updateAvailable || isDownloading) return;
+    if (!updateAvailable || isDownloading || !invokePromise) return;
 
     try {
       setIsDownloading(true);
       setDownloadProgress(0);
-
-      const update = await checkUpdate();
-      if (!update?.available) {
-        setError('No update available');
-        return;
-      }
-
-      let totalBytes = 0;
-      let downloadedBytes = 0;
-      await update.downloadAndInstall((event) => {
-        switch (event.event) {
-          case 'Started':
-            totalBytes = (event.data as any).contentLength ?? 0;
-            downloadedBytes = 0;
-            setDownloadProgress(0);
-            break;
-          case 'Progress':
-            downloadedBytes += event.data.chunkLength;
-            if (totalBytes > 0) {
-              setDownloadProgress((downloadedBytes / totalBytes) * 100);
-            }
-            break;
-          case 'Finished':
-            setDownloadProgress(100);
-            break;
-        }
-      });
-
-      // Relaunch after install
-      const { relaunch: doRelaunch } = await import('@tauri-apps/plugin-process');
-      await doRelaunch();
+      setPhase('downloading');
+      // Waits for in-flight requests, then installs and relaunches the app
+      const invoke = await invokePromise;
+      await invoke('install_update');
     } catch (err) {
-      const message = err instanceof Error ? err.message : 'Installation failed';
+      const message = err instanceof Error ? err.message : String(err);
       console.error('[UpdateChecker] Installation error:', message);
       setError(message);
       setIsDownloading(false);
     }
   };
 
-  if (typeof window === 'undefined' || !('__TAURI_INTERNALS__' in window)) {
+  if (!invokePromise) {
     return null;
   }
 
@@ -103,7 +97,7 @@ export default function UpdateChecker() {
               <AlertCircle size={18} className="text-[var(--warning)] flex-shrink-0" />
               <div className="flex-1 min-w-0">
                 <p className="text-sm font-medium text-[var(--text-primary)]">
-                  Update check failed
+                  Update failed
                 </p>
                 <p className="text-xs text-[var(--text-muted)] truncate">{error}</p>
               </div>
@@ -114,8 +108,18 @@ export default function UpdateChecker() {
               <div className="flex-1 min-w-0">
                 <p className="text-sm font-medium text-[var(--text-primary)]">
                   Update available: v{updateAvailable.version}
+                  {updateAvailable.channel === 'beta' && (
+                    <span className="ml-2 text-xs text-[var(--text-muted)]">(beta)</span>
+                  )}
                 </p>
-                {isDownloading && (
+                {isDownloading && phase !== 'downloading' && (
+                  <p className="text-xs text-[var(--text-muted)]">
+                    {phase === 'waiting'
+                      ? 'Waiting for in-flight requests to finish...'
+                      : 'Installing...'}
+                  </p>
+                )}
+                {isDownloading && phase === 'downloading' && (
                   <div className="mt-1.5">
                     <div className="h-1.5 bg-[var(--bg-input)] rounded-full overflow-hidden">
                       <div
@@ -1,13 +1,17 @@
 import { useState, useEffect, useRef } from 'react';
-import { Play, Square, Loader2, AlertTriangle } from 'lucide-react';
-import type { AgentStatus } from '../../types';
+import { Play, Pause, Square, Loader2, AlertTriangle } from 'lucide-react';
+import type { AgentStatus, ScheduleStatus } from '../../types';
 
 interface ProcessControlProps {
   status: AgentStatus;
   hasPrivateKey: boolean;
   loadingProgress?: { percent: number; phase: string; downloadedBytes?: number; totalBytes?: number } | null;
+  schedule?: ScheduleStatus | null;
   onStart: () => void;
   onStop: () => void;
+  paused?: boolean;
+  onPause?: () => void;
+  onResume?: () => void;
 }
 
 function formatElapsed(seconds: number): string {
@@ -16,6 +20,10 @@ function formatElapsed(seconds: number): string {
   return m > 0 ? `${m}m ${s}s` : `${s}s`;
 }
 
+function formatNextChange(unixSecs: number): string {
+  return new Date(unixSecs * 1000).toLocaleString([], { weekday: 'short', hour: '2-digit', minute: '2-digit' });
+}
+
 function formatBytes(bytes: number): string {
   if (bytes >= 1e9) return `${(bytes / 1e9).toFixed(1)} GB`;
   if (bytes >= 1e6) return `${(bytes / 1e6).toFixed(0)} MB`;
@@ -32,7 +40,7 @@ function getProgressInfo(elapsed: number): { percent: number; step: string } {
   return { percent: Math.min(90 + (elapsed - 120) * 0.05, 98), step: 'Finalizing model setup...' };
 }
 
-export default function ProcessControl({ status, hasPrivateKey, loadingProgress, onStart, onStop }: ProcessControlProps) {
+export default function ProcessControl({ status, hasPrivateKey, loadingProgress, schedule, onStart, onStop, paused, onPause, onResume }: ProcessControlProps) {
   const canStart = (status === 'stopped' || status === 'error') && hasPrivateKey;
   const [elapsed, setElapsed] = useState(0);
   const intervalRef = useRef<number | null>(null);
@@ -77,7 +85,16 @@ export default function ProcessControl({ status, hasPrivateKey, loadingProgress,
         <div className="flex-1 min-w-0">
           <h3 className="text-sm font-semibold text-[var(--text-primary)]">Agent Process</h3>
           <p className="text-xs text-[var(--text-muted)] mt-0.5">
-            {status === 'running' && 'Agent is running and processing requests'}
+            {status === 'running' && paused && 'Paused for maintenance — model stays loaded, no new requests'}
+            {status === 'running' && !paused && 'Agent is running and processing requests'}
+            {status === 'running' && !paused && schedule?.enabled && schedule.nextChange && (
+              <span> until {formatNextChange(schedule.nextChange)}</span>
+            )}
+            {status === 'scheduled' && (
+              schedule?.nextChange
+                ? `Outside serving hours — next start ${formatNextChange(schedule.nextChange)}`
+                : 'Outside serving hours'
+            )}
             {status === 'starting' && progress && (
               <span className="text-cyan-400">
                 {progress.step} ({formatElapsed(elapsed)})
@@ -106,11 +123,22 @@ export default function ProcessControl({ status, hasPrivateKey, loadingProgress,
               <Play size={14} />
               <span>Start</span>
             </button>
-          ) : status === 'running' ? (
-            <button className="btn-danger flex items-center gap-2" onClick={onStop}>
-              <Square size={14} />
-              <span>Stop</span>
-            </button>
+          ) : status === 'running' || status === 'scheduled' ? (
+            <>
+              {status === 'running' && onPause && onResume && (
+                <button
+                  className="btn-secondary flex items-center gap-2"
+                  onClick={paused ? onResume : onPause}
+                >
+                  {paused ? <Play size={14} /> : <Pause size={14} />}
+                  <span>{paused ? 'Resume' : 'Pause'}</span>
+                </button>
+              )}
+              <button className="btn-danger flex items-center gap-2" onClick={onStop}>
+                <Square size={14} />
+                <span>Stop</span>
+              </button>
+            </>
           ) : (
             <button className="btn-secondary flex items-center gap-2" disabled>
               <Loader2 size={14} style={{ animation: 'spin-slow 1s linear infinite' }} />
@@ -1,5 +1,5 @@
 import { NavLink } from 'react-router-dom';
-import { LayoutDashboard, ScrollText, Settings, type LucideIcon } from 'lucide-react';
+import { LayoutDashboard, MessageSquare, ScrollText, Settings, type LucideIcon } from 'lucide-react';
 import type { AgentStatus } from '../../types';
 
 interface NavItem {
@@ -11,6 +11,7 @@ interface NavItem {
 const NAV_ITEMS: NavItem[] = [
   { to: '/', label: 'Dashboard', icon: LayoutDashboard },
   { to: '/logs', label: 'Logs', icon: ScrollText },
+  { to: '/playground', label: 'Playground', icon: MessageSquare },
   { to: '/settings', label: 'Settings', icon: Settings },
 ];
 
@@ -20,6 +21,7 @@ const STATUS_CONFIG: Record<AgentStatus, { label: string; color: string; badgeCl
   running: { label: 'Running', color: '#4ade80', badgeClass: 'badge-success' },
   stopping: { label: 'Stopping...', color: '#fb923c', badgeClass: 'badge-warning' },
   error: { label: 'Error', color: '#ef4444', badgeClass: 'badge-danger' },
+  scheduled: { label: 'Scheduled', color: '#06b6d4', badgeClass: '' },
 };
 
 interface SidebarProps {
@@ -1,5 +1,5 @@
 import { useState, useCallback, useRef, useEffect } from 'react';
-import type { AgentStatus, AgentConfig, LogEntry, AgentMetrics, AgentHealth, NodeMode } from '../types';
+import type { AgentStatus, AgentConfig, AvailabilitySnapshot, LogEntry, AgentMetrics, AgentHealth, LatencyReport, LifetimeStats, NodeMode, ProcessUsage, RecentLog, Throughput, ScheduleStatus, UpdateRequired, UptimeStreak, PenaltyStatus, RewardProjection, SlotStatus } from '../types';
 
 // --- Tauri API loading (awaitable) ---
 let invokePromise: Promise<typeof import('@tauri-apps/api/core')['invoke']> | null = null;
@@ -20,12 +20,18 @@ async function getListen() {
   try { return await listenPromise; } catch { return null; }
 }
 
+// Pool parameters change per epoch, not per poll
+const PROJECTION_REFRESH_MS = 5 * 60 * 1000;
+
 const EMPTY_METRICS: AgentMetrics = {
   totalRequests: 0,
   totalTokensProcessed: 0,
   avgLatencyMs: 0,
   tokensPerSecond: 0,
   uptimeSeconds: 0,
+  energyKwh: 0,
+  energyCost: 0,
+  powerWatts: 0,
 };
 
 /**
@@ -39,10 +45,14 @@ export function useAgentProcess() {
   const [loadingProgress, setLoadingProgress] = useState<{ percent: number; phase: string; downloadedBytes?: number; totalBytes?: number } | null>(null);
   const [nodeMode, setNodeMode] = useState<NodeMode>('standalone');
   const [clusterId, setClusterId] = useState<string | null>(null);
+  const [schedule, setSchedule] = useState<ScheduleStatus | null>(null);
+  const [paused, setPaused] = useState(false);
+  const [updateRequired, setUpdateRequired] = useState<UpdateRequired | null>(null);
   const logIdRef = useRef(0);
   const pollRef = useRef<number | null>(null);
   const startTimeRef = useRef<number | null>(null);
   const elapsedRef = useRef<number | null>(null);
+  const projectionRef = useRef<{ value: RewardProjection | null; fetchedAt: number }>({ value: null, fetchedAt: 0 });
 
   const addLog = useCallback((level: LogEntry['level'], message: string) => {
     const entry: LogEntry = {
@@ -89,9 +99,28 @@ export function useAgentProcess() {
             tps: number;
             nodeMode: string;
             clusterId: string | null;
+            energyKwh: number;
+            energyCost: number;
+            powerWatts: number;
+            latency: LatencyReport;
+            throughput: Throughput;
+            process: ProcessUsage | null;
+            lifetime: LifetimeStats | null;
+            uptimeStreak: UptimeStreak | null;
+            penalty: PenaltyStatus | null;
+            warnings: string[];
+            kvCacheUsageRatio: number;
+            kvCacheTokens: number;
+            slots: SlotStatus[];
           };
 
           if (m.status === 'ok') {
+            if (Date.now() - projectionRef.current.fetchedAt > PROJECTION_REFRESH_MS) {
+              projectionRef.current.fetchedAt = Date.now();
+              inv<RewardProjection>('get_reward_projection')
+                .then((p) => { projectionRef.current.value = p; })
+                .catch(() => { /* RPC unreachable; keep the last projection */ });
+            }
             const mode = (m.nodeMode || 'standalone') as NodeMode;
             setHealth({
               status: 'ok',
@@ -103,9 +132,23 @@ export function useAgentProcess() {
             setMetrics({
               totalRequests: m.totalRequests,
               totalTokensProcessed: m.totalTokens,
-              avgLatencyMs: 0,
-              tokensPerSecond: m.tps,
+              avgLatencyMs: m.latency.total.avgMs,
+              tokensPerSecond: m.throughput.tps1m,
               uptimeSeconds: Math.floor(m.uptime),
+              energyKwh: m.energyKwh,
+              energyCost: m.energyCost,
+              powerWatts: m.powerWatts,
+              latency: m.latency,
+              throughput: m.throughput,
+              process: m.process,
+              lifetime: m.lifetime,
+              uptimeStreak: m.uptimeStreak,
+              penalty: m.penalty,
+              warnings: m.warnings,
+              projection: projectionRef.current.value,
+              kvCacheUsageRatio: m.kvCacheUsageRatio,
+              kvCacheTokens: m.kvCacheTokens,
+              slots: m.slots,
             });
             setNodeMode(mode);
             setClusterId(m.clusterId || null);
@@ -140,7 +183,7 @@ export function useAgentProcess() {
   }, []);
 
   const start = useCallback(async (config: AgentConfig) => {
-    if (status === 'running' || status === 'starting') return;
+    if (status === 'running' || status === 'starting' || status === 'scheduled') return;
 
     // Frontend-level validation
     if (!config.privateKey || config.privateKey.trim() === '') {
@@ -170,11 +213,14 @@ export function useAgentProcess() {
         addLog('INFO', 'Running pre-flight checks...');
         const result = await invoke('preflight_check', { config }) as {
           passed: boolean;
-          checks: Array<{ name: string; passed: boolean; message: string }>;
+          checks: Array<{ name: string; passed: boolean; message: string; hint: string | null }>;
         };
 
         for (const check of result.checks) {
           addLog(check.passed ? 'INFO' : 'ERROR', `[${check.name}] ${check.message}`);
+          if (!check.passed && check.hint) {
+            addLog('INFO', `[${check.name}] ${check.hint}`);
+          }
         }
 
         if (!result.passed) {
@@ -208,7 +254,7 @@ export function useAgentProcess() {
   }, [status, addLog, startPolling]);
 
   const stop = useCallback(async () => {
-    if (status !== 'running' && status !== 'starting') return;
+    if (status !== 'running' && status !== 'starting' && status !== 'scheduled') return;
 
     setStatus('stopping');
     addLog('INFO', 'Stopping agent...');
@@ -225,16 +271,78 @@ export function useAgentProcess() {
       }
       setStatus('stopped');
       setHealth(null);
+      setSchedule(null);
     } catch (err) {
       setStatus('error');
       addLog('ERROR', `Failed to stop agent: ${err}`);
     }
   }, [status, addLog, stopPolling]);
 
+  const setPausedManually = useCallback(async (pause: boolean) => {
+    if (status !== 'running') return;
+    const invoke = await getInvoke();
+    if (!invoke) return;
+
+    try {
+      await invoke(pause ? 'pause_agent' : 'resume_agent');
+    } catch (err) {
+      addLog('ERROR', `Failed to ${pause ? 'pause' : 'resume'} agent: ${err}`);
+    }
+  }, [status, addLog]);
+
+  const pause = useCallback(() => setPausedManually(true), [setPausedManually]);
+  const resume = useCallback(() => setPausedManually(false), [setPausedManually]);
+
+  const setMode = useCallback(async (mode: NodeMode | 'auto', peers?: string[]) => {
+    if (status !== 'running') return;
+    const invoke = await getInvoke();
+    if (!invoke) return;
+
+    try {
+      const applied = await invoke<string>('set_node_mode', { mode, peers });
+      setNodeMode(applied as NodeMode);
+    } catch (err) {
+      addLog('ERROR', `Failed to switch node mode: ${err}`);
+    }
+  }, [status, addLog]);
+
+  // Hot swap: the agent keeps serving the old model until the new one is ready
+  const switchModel = useCallback(async (model: string, modelFile: string) => {
+    if (status !== 'running') return false;
+    const invoke = await getInvoke();
+    if (!invoke) return false;
+
+    try {
+      await invoke('switch_model', { model, modelFile });
+      return true;
+    } catch (err) {
+      addLog('ERROR', `Failed to switch model: ${err}`);
+      return false;
+    }
+  }, [status, addLog]);
+
   useEffect(() => {
     let cancelled = false;
     const unlisteners: (() => void)[] = [];
 
+    // Backfill lines logged before this window opened
+    getInvoke().then(async (invoke) => {
+      if (!invoke) return;
+      try {
+        const recent = await invoke<RecentLog[]>('get_recent_logs', { limit: 500 });
+        if (cancelled || recent.length === 0) return;
+        const backfill: LogEntry[] = recent.map((r) => ({
+          id: ++logIdRef.current,
+          timestamp: new Date(r.timestamp).toISOString(),
+          level: r.level as LogEntry['level'],
+          message: r.message,
+        }));
+        setLogs((prev) => [...backfill, ...prev].slice(-500));
+      } catch {
+        // Older backend without the log buffer
+      }
+    });
+
     // Set up Tauri event listeners
     getListen().then((listen) => {
       if (!listen || cancelled) return;
@@ -258,6 +366,44 @@ export function useAgentProcess() {
         unlisteners.push(unlisten);
       });
 
+      listen('agent-availability', (event: any) => {
+        const snapshot = event.payload as AvailabilitySnapshot;
+        setPaused(snapshot.reasons.some((r) => r.source === 'manual'));
+      }).then((unlisten: () => void) => {
+        if (cancelled) { unlisten(); return; }
+        unlisteners.push(unlisten);
+      });
+
+      listen('agent-schedule', (event: any) => {
+        setSchedule(event.payload as ScheduleStatus);
+      }).then((unlisten: () => void) => {
+        if (cancelled) { unlisten(); return; }
+        unlisteners.push(unlisten);
+      });
+
+      listen('agent-update-required', (event: any) => {
+        setUpdateRequired((event.payload as UpdateRequired | null) ?? null);
+      }).then((unlisten: () => void) => {
+        if (cancelled) { unlisten(); return; }
+        unlisteners.push(unlisten);
+      });
+
+      listen('agent-model-corrupt', async (event: any) => {
+        const { file, message } = event.payload as { file: string; message: string };
+        addLog('ERROR', message);
+        if (!window.confirm(`${message}.\n\nDelete it and download it again on the next start?`)) return;
+        const invoke = await getInvoke();
+        try {
+          await invoke?.('redownload_model', { file });
+          addLog('INFO', `${file} deleted; it will be downloaded again on the next start`);
+        } catch (err) {
+          addLog('ERROR', `Failed to delete ${file}: ${err}`);
+        }
+      }).then((unlisten: () => void) => {
+        if (cancelled) { unlisten(); return; }
+        unlisteners.push(unlisten);
+      });
+
       listen('agent-loading-progress', (event: any) => {
         const { percent, phase, downloadedBytes, totalBytes } = event.payload;
         setLoadingProgress({ percent, phase, downloadedBytes, totalBytes });
@@ -280,12 +426,16 @@ export function useAgentProcess() {
       startTimeRef.current = null;
       setLoadingProgress(null);
     }
+    if (status !== 'running') {
+      setPaused(false);
+    }
   }, [status]);
 
   const clearLogs = useCallback(() => {
     setLogs([]);
     logIdRef.current = 0;
+    getInvoke().then((invoke) => invoke?.('clear_logs')).catch(() => {});
   }, []);
 
-  return { status, metrics, health, logs, loadingProgress, nodeMode, clusterId, start, stop, addLog, clearLogs };
+  return { status, metrics, health, logs, loadingProgress, nodeMode, clusterId, schedule, paused, updateRequired, start, stop, pause, resume, setMode, switchModel, addLog, clearLogs };
 }
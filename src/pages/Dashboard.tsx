@@ -1,6 +1,6 @@
 import { useEffect, useMemo, useRef, useState } from 'react';
 import {
-  Zap, Hash, Clock, Gauge, Cpu, HardDrive, Network,
+  Zap, Hash, Clock, Gauge, Cpu, HardDrive, Network, AlertTriangle,
 } from 'lucide-react';
 import {
   AreaChart, Area, XAxis, YAxis, Tooltip, ResponsiveContainer,
@@ -8,7 +8,7 @@ import {
 import StatCard from '../components/dashboard/StatCard';
 import GaugeRing from '../components/dashboard/GaugeRing';
 import ProcessControl from '../components/dashboard/ProcessControl';
-import type { AgentStatus, AgentMetrics, AgentHealth, LogEntry, NodeMode } from '../types';
+import type { AgentStatus, AgentMetrics, AgentHealth, LogEntry, NodeMode, ScheduleStatus, UpdateRequired } from '../types';
 import { useSystemInfo } from '../hooks/useSystemInfo';
 
 interface DashboardProps {
@@ -20,8 +20,13 @@ interface DashboardProps {
   loadingProgress?: { percent: number; phase: string } | null;
   nodeMode: NodeMode;
   clusterId: string | null;
+  schedule?: ScheduleStatus | null;
   onStart: () => void;
   onStop: () => void;
+  paused: boolean;
+  updateRequired?: UpdateRequired | null;
+  onPause: () => void;
+  onResume: () => void;
 }
 
 const NODE_MODE_LABELS: Record<NodeMode, { label: string; description: string; color: string }> = {
@@ -53,7 +58,7 @@ function generateMockChartData() {
   }));
 }
 
-export default function Dashboard({ status, metrics, health, logs, hasPrivateKey, loadingProgress, nodeMode, clusterId, onStart, onStop }: DashboardProps) {
+export default function Dashboard({ status, metrics, health, logs, hasPrivateKey, loadingProgress, nodeMode, clusterId, schedule, onStart, onStop, paused, updateRequired, onPause, onResume }: DashboardProps) {
   const chartData = useMemo(generateMockChartData, []);
 
   // Smooth uptime counter: interpolate between 3-second server polls
@@ -110,7 +115,30 @@ export default function Dashboard({ status, metrics, health, logs, hasPrivateKey
   return (
     <div className="flex-1 overflow-y-auto p-6 space-y-5">
       {/* Process Control */}
-      <ProcessControl status={status} hasPrivateKey={hasPrivateKey} loadingProgress={loadingProgress} onStart={onStart} onStop={onStop} />
+      <ProcessControl status={status} hasPrivateKey={hasPrivateKey} loadingProgress={loadingProgress} schedule={schedule} onStart={onStart} onStop={onStop} paused={paused} onPause={onPause} onResume={onResume} />
+
+      {/* Oracle asked for a newer agent */}
+      {updateRequired && (
+        <div className="glass-card px-4 py-2.5 flex items-center gap-3 text-amber-400">
+          <AlertTriangle size={14} />
+          <span className="text-xs font-semibold">Update required</span>
+          <span className="text-[10px] text-[var(--text-dim)]">
+            Version {updateRequired.currentVersion} is below the Oracle's minimum ({updateRequired.minSupportedVersion}). Update to keep receiving work.
+          </span>
+        </div>
+      )}
+
+      {/* Penalties and failure counts reported by the Oracle */}
+      {metrics.warnings && metrics.warnings.length > 0 && (
+        <div className="glass-card px-4 py-2.5 space-y-1 text-amber-400">
+          {metrics.warnings.map((warning) => (
+            <div key={warning} className="flex items-center gap-2 text-xs">
+              <AlertTriangle size={14} className="shrink-0" />
+              <span>{warning}</span>
+            </div>
+          ))}
+        </div>
+      )}
 
       {/* Node Mode Badge */}
       {status === 'running' && (
@@ -141,12 +169,18 @@ export default function Dashboard({ status, metrics, health, logs, hasPrivateKey
           icon={Hash}
           label="Total Requests"
           value={formatNumber(metrics.totalRequests)}
+          sub={metrics.slots && metrics.slots.length > 0
+            ? `${metrics.slots.filter((s) => s.processing).length}/${metrics.slots.length} slots busy`
+            : undefined}
           color="#06b6d4"
         />
         <StatCard
           icon={Zap}
           label="Tokens Processed"
           value={formatNumber(metrics.totalTokensProcessed)}
+          sub={metrics.projection
+            ? `Projected ${metrics.projection.plmPerDay.toFixed(2)} PLM/day (last ${(metrics.projection.windowSecs / 3600).toFixed(1)}h)`
+            : undefined}
           color="#8b5cf6"
         />
         <StatCard
@@ -159,6 +193,9 @@ export default function Dashboard({ status, metrics, health, logs, hasPrivateKey
           icon={Clock}
           label="Uptime"
           value={formatUptime(displayUptime)}
+          sub={metrics.uptimeStreak && metrics.uptimeStreak.streakSecs > 0
+            ? `Verified streak ${formatUptime(metrics.uptimeStreak.streakSecs)}`
+            : undefined}
           color="#4ade80"
         />
       </div>
@@ -243,7 +280,38 @@ export default function Dashboard({ status, metrics, health, logs, hasPrivateKey
               <HardDrive size={10} />
               {health?.model?.split('/').pop() || '\u2014'}
             </span>
+            {metrics.kvCacheUsageRatio !== undefined && metrics.kvCacheTokens ? (
+              <span title={`${formatNumber(metrics.kvCacheTokens)} tokens cached across slots`}>
+                KV cache {(metrics.kvCacheUsageRatio * 100).toFixed(0)}%
+              </span>
+            ) : null}
           </div>
+          {systemInfo?.gpus && systemInfo.gpus.length > 0 && (
+            <div className="flex items-center gap-3 text-[10px] text-[var(--text-dim)]">
+              {systemInfo.gpus.map((gpu) => (
+                <span
+                  key={gpu.index}
+                  className={gpu.throttling ? 'text-amber-400' : undefined}
+                  title={gpu.throttling ? 'GPU is thermal throttling — throughput will drop' : gpu.name}
+                >
+                  {[
+                    systemInfo.gpus!.length > 1 ? `GPU${gpu.index}` : 'GPU',
+                    gpu.utilizationPercent != null ? `${gpu.utilizationPercent.toFixed(0)}%` : null,
+                    gpu.temperatureC != null ? `${gpu.temperatureC.toFixed(0)}\u00b0C` : null,
+                    gpu.smClockMhz != null ? `${gpu.smClockMhz} MHz` : null,
+                  ].filter(Boolean).join(' \u00b7 ')}
+                  {gpu.throttling && ' (throttling)'}
+                </span>
+              ))}
+            </div>
+          )}
+          {metrics.powerWatts > 0 && (
+            <div className="flex items-center gap-1 text-[10px] text-[var(--text-dim)]">
+              <Zap size={10} />
+              {`${metrics.powerWatts.toFixed(0)} W \u00b7 ${metrics.energyKwh.toFixed(3)} kWh`}
+              {metrics.energyCost > 0 && ` \u00b7 cost ${metrics.energyCost.toFixed(2)}`}
+            </div>
+          )}
         </div>
       </div>
 
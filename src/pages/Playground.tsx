@@ -0,0 +1,125 @@
+import { useState, useRef, useEffect } from 'react';
+import { Send, Loader2 } from 'lucide-react';
+import type { AgentStatus, TestInferenceResult } from '../types';
+
+// --- Tauri API loading (awaitable) ---
+let invokePromise: Promise<typeof import('@tauri-apps/api/core')['invoke']> | null = null;
+let listenPromise: Promise<typeof import('@tauri-apps/api/event')['listen']> | null = null;
+
+if (typeof window !== 'undefined' && '__TAURI_INTERNALS__' in window) {
+  invokePromise = import('@tauri-apps/api/core').then((mod) => mod.invoke);
+  listenPromise = import('@tauri-apps/api/event').then((mod) => mod.listen);
+}
+
+interface PlaygroundProps {
+  status: AgentStatus;
+}
+
+export default function Playground({ status }: PlaygroundProps) {
+  const [prompt, setPrompt] = useState('Say hello in one sentence.');
+  const [maxTokens, setMaxTokens] = useState(256);
+  const [output, setOutput] = useState('');
+  const [result, setResult] = useState<TestInferenceResult | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const [busy, setBusy] = useState(false);
+  const outputRef = useRef<HTMLDivElement>(null);
+
+  useEffect(() => {
+    let cancelled = false;
+    let unlisten: (() => void) | null = null;
+    listenPromise?.then((listen) =>
+      listen('test-inference-token', (event: any) => {
+        setOutput((prev) => prev + event.payload.content);
+      }).then((fn: () => void) => {
+        if (cancelled) fn();
+        else unlisten = fn;
+      }),
+    );
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, []);
+
+  useEffect(() => {
+    if (outputRef.current) {
+      outputRef.current.scrollTop = outputRef.current.scrollHeight;
+    }
+  }, [output]);
+
+  const running = status === 'running';
+
+  const handleSend = async () => {
+    const invoke = invokePromise ? await invokePromise : null;
+    if (!invoke || busy) return;
+    setBusy(true);
+    setOutput('');
+    setResult(null);
+    setError(null);
+    try {
+      const res = await invoke<TestInferenceResult>('test_inference', { prompt, maxTokens });
+      setResult(res);
+      setOutput(res.output);
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  return (
+    <div className="flex-1 flex flex-col overflow-hidden p-6 gap-4">
+      <section className="glass-card p-5 space-y-3">
+        <textarea
+          className="input-field w-full h-24 resize-none"
+          value={prompt}
+          onChange={(e) => setPrompt(e.target.value)}
+          placeholder="Ask your node something..."
+        />
+        <div className="flex items-center gap-3">
+          <label className="text-xs text-[var(--text-muted)]">Max tokens</label>
+          <input
+            type="number"
+            className="input-field w-24"
+            min={1}
+            max={4096}
+            value={maxTokens}
+            onChange={(e) => setMaxTokens(Math.max(1, Number(e.target.value) || 1))}
+          />
+          {!running && (
+            <span className="text-xs text-[var(--text-dim)]">Start the agent to test inference.</span>
+          )}
+          <button
+            className="btn-primary flex items-center gap-1.5 ml-auto"
+            onClick={handleSend}
+            disabled={!running || busy || !prompt.trim()}
+          >
+            {busy ? <Loader2 size={14} className="animate-spin" /> : <Send size={14} />}
+            <span>Send</span>
+          </button>
+        </div>
+      </section>
+
+      <div ref={outputRef} className="flex-1 glass-card p-4 overflow-y-auto">
+        {error ? (
+          <p className="text-sm text-[#ef4444]">{error}</p>
+        ) : output ? (
+          <p className="text-sm text-[var(--text-secondary)] whitespace-pre-wrap">{output}</p>
+        ) : (
+          <div className="flex items-center justify-center h-full text-[var(--text-dim)] text-sm">
+            The response streams here.
+          </div>
+        )}
+      </div>
+
+      {result && (
+        <div className="glass-card px-4 py-2.5 flex items-center gap-6 text-xs text-[var(--text-muted)]">
+          <span>TTFT {result.ttftMs.toFixed(0)} ms</span>
+          <span>Total {(result.totalMs / 1000).toFixed(2)} s</span>
+          <span>{result.promptTokens} prompt / {result.completionTokens} completion tokens</span>
+          <span>{result.tokensPerSec.toFixed(1)} tok/s</span>
+        </div>
+      )}
+    </div>
+  );
+}
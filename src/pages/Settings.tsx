@@ -1,7 +1,7 @@
 import { useState, useEffect } from 'react';
 import { Save, Eye, EyeOff, RotateCcw, ChevronDown, ChevronRight, Network } from 'lucide-react';
-import type { AgentConfig, AgentStatus } from '../types';
-import { DEFAULT_CONFIG } from '../types';
+import type { AgentConfig, ClusterConfig, AgentStatus, ModelEntry, ModelVariant } from '../types';
+import { DEFAULT_CONFIG, errorMessage } from '../types';
 
 const STORAGE_KEY = 'plumise-agent-config';
 
@@ -34,6 +34,7 @@ async function loadConfig(): Promise<AgentConfig> {
     if (raw) {
       const parsed = JSON.parse(raw);
       delete parsed.privateKey; // Ensure privateKey is never loaded from localStorage
+      delete parsed.hfToken;
       return { ...DEFAULT_CONFIG, ...parsed };
     }
   } catch { /* ignore */ }
@@ -46,13 +47,13 @@ async function saveConfig(config: AgentConfig) {
     try {
       await invoke('save_config', { config });
     } catch (err) {
-      console.error('Failed to save config via Tauri:', err);
+      console.error('Failed to save config via Tauri:', errorMessage(err));
     }
   }
 
   // Save to localStorage as fallback, but NEVER store privateKey
   try {
-    const { privateKey: _, ...safeConfig } = config;
+    const { privateKey: _, hfToken: _token, ...safeConfig } = config;
     localStorage.setItem(STORAGE_KEY, JSON.stringify(safeConfig));
   } catch (err) {
     console.error('Failed to save config to localStorage:', err);
@@ -71,6 +72,10 @@ export default function Settings({ status, onConfigChange }: SettingsProps) {
   const [isLoading, setIsLoading] = useState(true);
   const [showAdvanced, setShowAdvanced] = useState(false);
   const [systemRamGb, setSystemRamGb] = useState(16);
+  const [variants, setVariants] = useState<ModelVariant[]>([]);
+  const [importPath, setImportPath] = useState('');
+  const [importMode, setImportMode] = useState<'copy' | 'link'>('copy');
+  const [importStatus, setImportStatus] = useState<string | null>(null);
 
   const isRunning = status === 'running' || status === 'starting';
 
@@ -93,6 +98,22 @@ export default function Settings({ status, onConfigChange }: SettingsProps) {
     });
   }, []); // eslint-disable-line react-hooks/exhaustive-deps
 
+  // List quantization variants of the selected repo
+  useEffect(() => {
+    let cancelled = false;
+    getInvoke().then((invoke) => {
+      if (!invoke) return;
+      invoke('list_model_variants', { repo: config.model })
+        .then((list) => {
+          if (!cancelled) setVariants(list as ModelVariant[]);
+        })
+        .catch(() => {
+          if (!cancelled) setVariants([]);
+        });
+    });
+    return () => { cancelled = true; };
+  }, [config.model]);
+
   // Auto-save on config changes (debounced) and propagate to parent
   useEffect(() => {
     if (isLoading) return;
@@ -105,6 +126,20 @@ export default function Settings({ status, onConfigChange }: SettingsProps) {
     return () => clearTimeout(timer);
   }, [config, isLoading]); // eslint-disable-line react-hooks/exhaustive-deps
 
+  const handleImport = async () => {
+    const invoke = await getInvoke();
+    if (!invoke || !importPath.trim()) return;
+    setImportStatus('Importing...');
+    try {
+      const entry = await invoke<ModelEntry>('import_model', { path: importPath, name: null, mode: importMode });
+      update('modelFile', entry.file);
+      setImportPath('');
+      setImportStatus(`Imported ${entry.file} (${(entry.sizeBytes / 1e9).toFixed(1)} GB${entry.link ? `, ${entry.link}` : ''})`);
+    } catch (err) {
+      setImportStatus(errorMessage(err));
+    }
+  };
+
   const handleSave = () => {
     saveConfig(config);
     onConfigChange(config);
@@ -204,6 +239,12 @@ export default function Settings({ status, onConfigChange }: SettingsProps) {
                 disabled={isRunning}
               >
                 <option value="Qwen/Qwen3-32B-GGUF">Qwen3 32B GGUF (~20GB)</option>
+                <option value="nomic-ai/nomic-embed-text-v1.5-GGUF">
+                  Nomic Embed Text v1.5 (embeddings, ~150MB)
+                </option>
+                <option value="gpustack/bge-reranker-v2-m3-GGUF">
+                  BGE Reranker v2 M3 (rerank, ~600MB)
+                </option>
               </select>
             </div>
 
@@ -225,6 +266,136 @@ export default function Settings({ status, onConfigChange }: SettingsProps) {
             </div>
           </div>
 
+          <div>
+            <label className="block text-xs text-[var(--text-muted)] mb-1.5">
+              Serve Mode
+            </label>
+            <select
+              className="input-field"
+              value={config.serveMode ?? 'chat'}
+              onChange={(e) => update('serveMode', e.target.value as AgentConfig['serveMode'])}
+              disabled={isRunning}
+            >
+              <option value="chat">Chat (text generation)</option>
+              <option value="embedding">Embeddings only</option>
+              <option value="rerank">Reranking only</option>
+            </select>
+            <p className="text-[10px] text-[var(--text-dim)] mt-1">
+              Embedding and rerank modes serve a small model, for machines too small for the chat model.
+            </p>
+          </div>
+
+          <div>
+            <label className="block text-xs text-[var(--text-muted)] mb-1.5">
+              Quantization
+            </label>
+            <select
+              className="input-field"
+              value={config.modelFile}
+              onChange={(e) => update('modelFile', e.target.value)}
+              disabled={isRunning}
+            >
+              {!variants.some((v) => v.file === config.modelFile) && (
+                <option value={config.modelFile}>{config.modelFile}</option>
+              )}
+              {variants.map((v) => (
+                <option key={v.file} value={v.file}>
+                  {v.quant ?? v.file} ({(v.sizeBytes / 1e9).toFixed(1)} GB)
+                  {v.downloaded ? ' — downloaded' : ''}
+                </option>
+              ))}
+            </select>
+            <p className="text-[10px] text-[var(--text-dim)] mt-1">
+              Higher quants are more accurate but need more memory. A new file downloads on next start.
+            </p>
+          </div>
+
+          <div>
+            <label className="block text-xs text-[var(--text-muted)] mb-1.5">
+              Import Local GGUF
+            </label>
+            <div className="flex gap-2">
+              <input
+                type="text"
+                className="input-field font-mono text-xs"
+                placeholder="/path/to/model.gguf"
+                value={importPath}
+                onChange={(e) => setImportPath(e.target.value)}
+                disabled={isRunning}
+              />
+              <select
+                className="input-field text-xs w-28"
+                value={importMode}
+                onChange={(e) => setImportMode(e.target.value as 'copy' | 'link')}
+                disabled={isRunning}
+              >
+                <option value="copy">Copy</option>
+                <option value="link">Link</option>
+              </select>
+              <button
+                className="btn-secondary text-xs"
+                onClick={handleImport}
+                disabled={isRunning || !importPath.trim()}
+              >
+                Import
+              </button>
+            </div>
+            <p className="text-[10px] text-[var(--text-dim)] mt-1">
+              {importStatus ?? 'Uses a GGUF you already have (e.g. from Ollama or LM Studio) without downloading. Link avoids a second copy on the same disk.'}
+            </p>
+          </div>
+
+          <div>
+            <label className="block text-xs text-[var(--text-muted)] mb-1.5">
+              HuggingFace Token
+            </label>
+            <input
+              type="password"
+              className="input-field font-mono text-xs"
+              placeholder="hf_..."
+              value={config.hfToken ?? ''}
+              onChange={(e) => update('hfToken', e.target.value.trim())}
+              disabled={isRunning}
+            />
+            <p className="text-[10px] text-[var(--text-dim)] mt-1">
+              Only needed for gated or private repos. Stored in the OS keychain.
+            </p>
+          </div>
+
+          <div>
+            <label className="block text-xs text-[var(--text-muted)] mb-1.5">
+              Model URL
+            </label>
+            <input
+              type="text"
+              className="input-field font-mono text-xs"
+              placeholder="https://mirror.example.com or direct .gguf link"
+              value={config.modelUrl ?? ''}
+              onChange={(e) => update('modelUrl', e.target.value.trim())}
+              disabled={isRunning}
+            />
+            <p className="text-[10px] text-[var(--text-dim)] mt-1">
+              Optional. Tried first; falls back to HuggingFace and hf-mirror.com.
+            </p>
+          </div>
+
+          <div>
+            <label className="block text-xs text-[var(--text-muted)] mb-1.5">
+              Vision Projector (mmproj)
+            </label>
+            <input
+              type="text"
+              className="input-field font-mono text-xs"
+              placeholder="mmproj-F16.gguf"
+              value={config.mmprojFile ?? ''}
+              onChange={(e) => update('mmprojFile', e.target.value.trim())}
+              disabled={isRunning}
+            />
+            <p className="text-[10px] text-[var(--text-dim)] mt-1">
+              Only for vision models. Downloaded from the same repo and enables image input.
+            </p>
+          </div>
+
           <div className="grid grid-cols-2 gap-4">
             <div>
               <label className="block text-xs text-[var(--text-muted)] mb-1.5">
@@ -338,6 +509,50 @@ export default function Settings({ status, onConfigChange }: SettingsProps) {
                 Port for ggml-rpc server (default: 50052)
               </p>
             </div>
+
+            <div>
+              <label className="block text-xs text-[var(--text-muted)] mb-1.5">
+                Static Cluster Role
+              </label>
+              <select
+                className="input-field"
+                value={config.cluster?.role ?? ''}
+                onChange={(e) => update('cluster', {
+                  role: e.target.value as ClusterConfig['role'],
+                  peers: config.cluster?.peers ?? [],
+                })}
+                disabled={isRunning}
+              >
+                <option value="">None (Oracle decides)</option>
+                <option value="coordinator">Coordinator</option>
+                <option value="rpc-server">RPC Server</option>
+              </select>
+              <p className="text-[10px] text-[var(--text-dim)] mt-1">
+                Form a LAN cluster yourself instead of waiting for an assignment
+              </p>
+            </div>
+
+            {config.cluster?.role === 'coordinator' && (
+              <div>
+                <label className="block text-xs text-[var(--text-muted)] mb-1.5">
+                  RPC Peers
+                </label>
+                <input
+                  type="text"
+                  className="input-field"
+                  defaultValue={config.cluster.peers.join(', ')}
+                  onBlur={(e) => update('cluster', {
+                    role: 'coordinator',
+                    peers: e.target.value.split(',').map((p) => p.trim()).filter(Boolean),
+                  })}
+                  placeholder="192.168.0.101:50052, 192.168.0.102"
+                  disabled={isRunning}
+                />
+                <p className="text-[10px] text-[var(--text-dim)] mt-1">
+                  Comma-separated host:port; port defaults to the RPC Port
+                </p>
+              </div>
+            )}
           </div>
         </section>
 
@@ -353,6 +568,23 @@ export default function Settings({ status, onConfigChange }: SettingsProps) {
 
           {showAdvanced && (
             <div className="space-y-4 pt-2">
+              <div>
+                <label className="block text-xs text-[var(--text-muted)] mb-1.5">
+                  Update Channel
+                </label>
+                <select
+                  className="input-field w-48"
+                  value={config.updateChannel ?? 'stable'}
+                  onChange={(e) => update('updateChannel', e.target.value as AgentConfig['updateChannel'])}
+                >
+                  <option value="stable">Stable</option>
+                  <option value="beta">Beta</option>
+                </select>
+                <p className="text-[10px] text-[var(--text-dim)] mt-1">
+                  Updates wait for in-flight requests before installing
+                </p>
+              </div>
+
               <div>
                 <label className="block text-xs text-[var(--text-muted)] mb-1.5">
                   Oracle URL
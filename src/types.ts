@@ -1,4 +1,4 @@
-export type AgentStatus = 'stopped' | 'starting' | 'running' | 'stopping' | 'error';
+export type AgentStatus = 'stopped' | 'starting' | 'running' | 'stopping' | 'error' | 'scheduled';
 
 export type NodeMode = 'standalone' | 'rpc-server' | 'coordinator';
 
@@ -9,12 +9,128 @@ export interface ClusterAssignment {
   rpcPeers: string[] | null; // coordinator only
 }
 
+export interface LatencySummary {
+  count: number;
+  avgMs: number;
+  p50Ms: number;
+  p95Ms: number;
+  p99Ms: number;
+  maxMs: number;
+}
+
+export interface LatencyReport {
+  ttft: LatencySummary;
+  total: LatencySummary;
+}
+
+export interface Throughput {
+  lifetimeTps: number;
+  tps1m: number;
+  tps5m: number;
+  requestsPerMin1m: number;
+  requestsPerMin5m: number;
+}
+
+export interface TestInferenceResult {
+  output: string;
+  ttftMs: number;
+  totalMs: number;
+  promptTokens: number;
+  completionTokens: number;
+  tokensPerSec: number;
+}
+
+export interface GpuProbeResult {
+  ok: boolean;
+  backend: string | null;
+  offloadedLayers: number | null;
+  tokPerSec: number | null;
+  durationMs: number;
+  message: string;
+  logTail: string[];
+}
+
+export interface UpdateRequired {
+  currentVersion: string;
+  minSupportedVersion: string;
+}
+
+export interface Reachability {
+  endpoint: string;
+  reachable: boolean | null;
+  latencyMs: number | null;
+  message: string;
+}
+
+export interface PenaltyStatus {
+  penalized: boolean;
+  reason: string | null;
+  rewardMultiplier: number | null;
+  until: number | null;
+  failedRequests1h: number;
+  timedOutRequests1h: number;
+  downtimeSecs24h: number;
+}
+
+export interface RewardProjection {
+  since: number;
+  windowSecs: number;
+  accruedWei: string;
+  tokens: number;
+  weiPerToken: string | null;
+  plmPerDay: number;
+  plmPerWeek: number;
+}
+
+export interface UptimeStreak {
+  attestations: number;
+  streakSecs: number;
+}
+
+export interface LifetimeStats {
+  tokens: number;
+  requests: number;
+  uptimeSecs: number;
+  restarts: number;
+  rewardsClaimedPlm: number;
+  firstStartedAt: number | null;
+}
+
+export interface ProcessUsage {
+  pid: number;
+  cpuPercent: number;
+  rssBytes: number;
+  gpuMemoryMb: number | null;
+}
+
+export interface SlotStatus {
+  id: number;
+  processing: boolean;
+  nCtx: number;
+  tokensDecoded: number;
+  taskId: number | null;
+}
+
 export interface AgentMetrics {
   totalRequests: number;
   totalTokensProcessed: number;
   avgLatencyMs: number;
   tokensPerSecond: number;
   uptimeSeconds: number;
+  energyKwh: number;
+  energyCost: number;
+  powerWatts: number;
+  latency?: LatencyReport;
+  throughput?: Throughput;
+  process?: ProcessUsage | null;
+  lifetime?: LifetimeStats | null;
+  uptimeStreak?: UptimeStreak | null;
+  penalty?: PenaltyStatus | null;
+  warnings?: string[];
+  projection?: RewardProjection | null;
+  kvCacheUsageRatio?: number;
+  kvCacheTokens?: number;
+  slots?: SlotStatus[];
 }
 
 export interface AgentHealth {
@@ -33,6 +149,100 @@ export interface SystemInfo {
   vramTotal: number;
   vramUsed: number;
   gpuName: string;
+  gpus?: GpuTelemetry[];
+}
+
+export interface GpuInfo {
+  vendor: string;
+  name: string;
+  vramMb: number;
+}
+
+export interface CpuFeatures {
+  arch: string;
+  sse42: boolean;
+  avx: boolean;
+  avx2: boolean;
+  fma: boolean;
+  f16c: boolean;
+  avx512: boolean;
+  neon: boolean;
+  dotprod: boolean;
+}
+
+export interface HardwareProfile {
+  gpu: GpuInfo | null;
+  totalRamMb: number;
+  cpu: CpuFeatures;
+  cpuCores: number;
+  detectedAt: number;
+}
+
+export interface GpuTelemetry {
+  index: number;
+  name: string;
+  utilizationPercent: number | null;
+  temperatureC: number | null;
+  powerWatts: number | null;
+  smClockMhz: number | null;
+  maxSmClockMhz: number | null;
+  throttling: boolean;
+}
+
+export interface RewardEvent {
+  kind: 'accrued' | 'claimed';
+  amountWei: string;
+  amountPlm: number;
+  txHash: string;
+  blockNumber: number;
+}
+
+export interface ClaimRecord {
+  at: number;
+  source: 'agent' | 'chain';
+  status: 'confirmed' | 'reverted' | 'failed';
+  txHash?: string;
+  amountWei?: string;
+  amountPlm?: number;
+  gasUsed?: number;
+  blockNumber?: number;
+  error?: string;
+  payout?: { address: string; txHash?: string; error?: string };
+}
+
+export interface ClaimHistory {
+  summary: {
+    attempts: number;
+    confirmed: number;
+    failed: number;
+    claimedPlm: number;
+    gasUsed: number;
+  };
+  records: ClaimRecord[];
+}
+
+export interface BalanceSample {
+  timestamp: number;
+  balancePlm: number;
+}
+
+export interface BalanceAlert {
+  thresholdPlm: number;
+  balancePlm: number;
+  below: boolean;
+  address: string;
+}
+
+export interface RpcHealth {
+  port: number;
+  healthy: boolean;
+  message: string;
+}
+
+export interface PinFailure {
+  host: string;
+  presented: string;
+  message: string;
 }
 
 export interface LoadingProgress {
@@ -42,6 +252,56 @@ export interface LoadingProgress {
   totalBytes?: number;
 }
 
+export interface PeerMeasurement {
+  peer: string;
+  rttMs: number | null;
+  uploadMbps: number | null;
+  protocolVersion: string | null;
+  accepted: boolean;
+  reason?: string;
+}
+
+export interface ClusterConfig {
+  role: '' | 'coordinator' | 'rpc-server';
+  peers: string[];
+}
+
+export type AgentEventKind =
+  | 'agent-started'
+  | 'agent-stopped'
+  | 'agent-crashed'
+  | 'registration-failed'
+  | 'reward-claimed'
+  | 'download-completed';
+
+export interface WebhookConfig {
+  url: string;
+  events: AgentEventKind[];
+}
+
+export interface AlertsConfig {
+  telegramBotToken: string;
+  telegramChatId: string;
+  discordWebhookUrl: string;
+  agentStarted: boolean;
+  agentStopped: boolean;
+  agentCrashed: boolean;
+  registrationFailed: boolean;
+  rewardClaimed: boolean;
+  downloadCompleted: boolean;
+  minIntervalSecs: number;
+}
+
+export interface ChainProfile {
+  network: 'mainnet' | string;
+  /** Chain id the RPC is expected to serve; 0 = whatever it reports (mainnet only). */
+  chainId?: number;
+  rewardPool?: string;
+  registerPrecompile?: string;
+  heartbeatPrecompile?: string;
+  proofRootPrecompile?: string;
+}
+
 export interface AgentConfig {
   privateKey: string;
   model: string;
@@ -49,13 +309,152 @@ export interface AgentConfig {
   device: string;
   oracleUrl: string;
   chainRpc: string;
+  chainWsUrl?: string;
+  chain?: ChainProfile;
+  payoutAddress?: string;
+  llamaLogVerbosity?: number | null;
+  llamaLogDisable?: boolean;
+  llamaLogFile?: string;
+  llamaLogLevel?: 'debug' | 'info' | 'warning' | 'error';
+  balanceAlertPlm?: number[];
+  balanceCheckSecs?: number;
+  maxPeerLatencyMs?: number;
+  cluster?: ClusterConfig;
+  webhooks?: WebhookConfig[];
+  alerts?: AlertsConfig;
+  oracleSpkiPins?: string[];
+  auditLog?: boolean;
+  auditLogContent?: 'omit' | 'hash' | 'store';
+  auditLogMaxMb?: number;
+  auditLogMaxDays?: number;
+  modelVerifyIntervalHours?: number;
   httpPort: number;
+  httpPortScan?: number;
   gpuLayers: number;
   ctxSize: number;
   parallelSlots: number;
+  contextShift?: boolean;
+  cacheReuse?: number;
   ramLimitGb: number;
   distributedMode: 'auto' | 'standalone' | 'disabled';
   rpcPort: number;
+  bindHost?: string;
+  httpPoolIdleSecs?: number;
+  llamaKeepaliveSecs?: number;
+  otlpEndpoint?: string | null;
+  electricityPriceKwh?: number;
+  plmPrice?: number;
+  idlePolicy?: 'off' | 'pause' | 'deregister';
+  idleThresholdSecs?: number;
+  idleGpuBusyPercent?: number;
+  servingWindows?: string[];
+  pauseOnBattery?: boolean;
+  batteryMode?: 'pause' | 'deregister' | 'cpu';
+  memoryPressureAction?: 'off' | 'warn' | 'pause' | 'reduce-ctx';
+  sandbox?: boolean;
+  notifications?: boolean;
+  updateChannel?: 'stable' | 'beta';
+  rebenchmarkHours?: number;
+  llamaVersion?: string;
+  llamaPath?: string;
+  hfToken?: string;
+  modelUrl?: string;
+  mmprojFile?: string;
+  serveMode?: 'chat' | 'embedding' | 'rerank';
+  proofSubmission?: 'off' | 'oracle' | 'chain';
+  proofBatchSize?: number;
+  proofBatchMinutes?: number;
+  usageReporting?: boolean;
+  region?: string;
+  geolocation?: boolean;
+  geolocationUrl?: string;
+}
+
+export interface ScheduleStatus {
+  enabled: boolean;
+  active: boolean;
+  /** Unix seconds of the next start (if inactive) or stop (if active). */
+  nextChange: number | null;
+  windows: string[];
+}
+
+export type PauseSource = 'idle' | 'schedule' | 'battery' | 'rpc-server' | 'memory-pressure' | 'update' | 'manual';
+
+export interface PauseReason {
+  source: PauseSource;
+  reason: string;
+  deregister: boolean;
+  since: number;
+}
+
+export interface AvailabilitySnapshot {
+  accepting: boolean;
+  deregistered: boolean;
+  reasons: PauseReason[];
+}
+
+export interface CleanupTargets {
+  models?: boolean;
+  binaries?: boolean;
+  logs?: boolean;
+  config?: boolean;
+  keys?: boolean;
+}
+
+export interface KeyRotationReport {
+  oldAddress: string | null;
+  newAddress: string;
+  migrationLinked: boolean;
+  registered: boolean;
+  /** Why the Oracle registration failed; it registers again on the next start. */
+  registrationError: string | null;
+  backupPath: string | null;
+}
+
+export type CoreErrorCategory =
+  | 'config' | 'network' | 'oracle' | 'chain' | 'process' | 'model' | 'other';
+
+/** Structured error returned by commands backed by plumise-agent-core. */
+export interface CoreError {
+  category: CoreErrorCategory;
+  code: number;
+  message: string;
+}
+
+export function errorMessage(err: unknown): string {
+  if (err && typeof err === 'object' && 'message' in err) {
+    return String((err as { message: unknown }).message);
+  }
+  return String(err);
+}
+
+export interface CleanupReport {
+  removed: string[];
+  freedBytes: number;
+  errors: string[];
+}
+
+export interface UpdateInfo {
+  version: string;
+  currentVersion: string;
+  date: string | null;
+  body: string | null;
+  channel: string;
+}
+
+export interface CrashRecord {
+  timestamp: number;
+  exitCode: number | null;
+  description: string;
+  logTail: string[];
+  config: Partial<AgentConfig>;
+}
+
+export interface RecentLog {
+  id: number;
+  timestamp: number;
+  level: string;
+  message: string;
 }
 
 export interface LogEntry {
@@ -80,3 +479,30 @@ export const DEFAULT_CONFIG: AgentConfig = {
   distributedMode: 'auto',
   rpcPort: 50052,
 };
+
+export interface ModelRecommendation {
+  model: string;
+  modelFile: string;
+  quant: string;
+  sizeMb: number;
+  gpuLayers: number;
+  fullyOffloaded: boolean;
+  fits: boolean;
+  reason: string;
+}
+
+export interface ModelEntry {
+  file: string;
+  repo: string;
+  importedFrom: string | null;
+  link: 'symlink' | 'hardlink' | null;
+  sizeBytes: number;
+  addedAt: number;
+}
+
+export interface ModelVariant {
+  file: string;
+  quant: string | null;
+  sizeBytes: number;
+  downloaded: boolean;
+}